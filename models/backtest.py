@@ -3,9 +3,7 @@ import os
 # THIS MUTES TENSORFLOW 
 os.environ['TF_CPP_MIN_LOG_LEVEL'] = '3'
 import tensorflow as tf
-import sqlite3
 import pandas as pd
-from ta import add_all_ta_features
 import warnings
 import pickle
 from sklearn.preprocessing import RobustScaler
@@ -15,64 +13,18 @@ def suppress_output():
     sys.stdout = open(os.devnull, 'w')
     sys.stderr = open(os.devnull, 'w')
 
-def backtest(candle_time=None, pair="BTCUSDT", model_name="neural_net_model"):
+def backtest(candle_time=None, pair="BTCUSDT", model_name="neural_net_model", open_times=[], columns=[], rows=[]):
     warnings.simplefilter(action='ignore', category=FutureWarning)
     warnings.simplefilter("ignore", category=RuntimeWarning)
     suppress_output()
     #%%
 
     # Load the saved model
-    model_path = "./models/generated/" + model_name; 
+    model_path = os.path.join(os.environ.get("MESHETAR_MODELS_DIR", "./models/generated"), model_name)
     loaded_model = tf.keras.models.load_model(model_path) 
     #%%
-    conn = sqlite3.connect('./database.sqlite')
-    time_query = f"AND open_time >= \"{candle_time}\"" if candle_time else ""
-    print(time_query)
-    # cursor = sqliteConnection.cursor()
-    query = f"""
-    SELECT open_time,
-    open,
-    high, 
-    low, 
-    close, 
-    volume
-    FROM candles
-    WHERE asset = '{pair}'
-    {time_query}
-    ORDER BY open_time ASC 
-    LIMIT 1440;"""
-
-    klines = pd.read_sql_query(query, conn)
-    # Make predictions using the loaded model
-    klines = add_all_ta_features(klines,
-                                    open = "open", 
-                                    close = "close",
-                                    volume = "volume",
-                                    low = "low",
-                                    high = "high",
-                                    fillna=True).dropna()
-    #%%
-
-    # Store the 'open_time' column
-    open_time_data = klines['open_time'].copy()
-    close_data = klines['close'].copy()
-
-
-    columns_not_to_predict = [
-        'open_time', 
-        'open',
-        'close', 
-        'low', 
-        'high', 
-        'volume']
-    klines_to_predict = klines.drop(columns=columns_not_to_predict)
-
-    # lags = range(1, 15)
-    # klines_to_predict.assign(**{
-    #     f'{col} (t-{lag})': klines_to_predict[col].shift(lag)
-    #     for lag in lags
-    #     for col in klines_to_predict
-    # })
+    # Features are computed by the features pipeline in Rust, one row per open_time
+    klines_to_predict = pd.DataFrame(rows, columns=columns)
 
     #%%
     scaler = RobustScaler()
@@ -93,15 +45,25 @@ def backtest(candle_time=None, pair="BTCUSDT", model_name="neural_net_model"):
             return "hold"
     cut_predictions['model_prediction'] = cut_predictions.apply(set_model_prediction, axis=1).astype(str)
 
+    # Probability of the output that triggered the decision
+    def model_confidence(prediction, decision):
+        if decision == "buy":
+            return float(prediction[0])
+        elif decision == "sell":
+            return float(prediction[2])
+        else:
+            return float(1 - max(prediction[0], prediction[2]))
+    cut_predictions['model_confidence'] = [
+        model_confidence(prediction, decision)
+        for prediction, decision in zip(predictions, cut_predictions['model_prediction'])
+    ]
+
     # Merge 'open_time' with the predictions
-    cut_predictions['open_time'] = open_time_data.reset_index(drop=True)
-    cut_predictions['close'] = close_data.reset_index(drop=True)
-    
+    cut_predictions['open_time'] = open_times
+
     # Return combined data
-    combined_predictions = list(zip(cut_predictions['open_time'], cut_predictions['model_prediction']))
-    #combined_predictions = list(zip(cut_predictions['open_time'], cut_predictions['model_prediction'])) 
+    combined_predictions = list(zip(cut_predictions['open_time'], cut_predictions['model_prediction'], cut_predictions['model_confidence']))
 
-    # Revert order
     return combined_predictions
 
 # predictions = backtest("2023-12-01T12:50:00+00:00")
@@ -4,10 +4,7 @@ import os
 os.environ['TF_CPP_MIN_LOG_LEVEL'] = '3'
 import pandas as pd
 import numpy as np
-from ta import add_all_ta_features
-from ta.utils import dropna
 import matplotlib.pyplot as plt
-import sqlite3
 from scipy.signal import find_peaks
 import seaborn as sns
 import tensorflow as tf
@@ -17,6 +14,7 @@ from sklearn.preprocessing import StandardScaler, LabelEncoder, RobustScaler
 from sklearn.metrics import roc_curve, roc_auc_score, RocCurveDisplay, confusion_matrix
 from sklearn.model_selection import train_test_split
 import pickle
+import json
 import warnings
 import sys
 
@@ -24,44 +22,22 @@ def suppress_output():
     sys.stdout = open(os.devnull, 'w')
     sys.stderr = open(os.devnull, 'w')
 
-def new_model(pair="BTCUSDT", model_name="neural_net_model"):
+def new_model(pair="BTCUSDT", model_name="neural_net_model", open_times=[], closes=[], columns=[], rows=[]):
     
     suppress_output()
 
     pd.set_option('display.max_rows', 500)
     pd.set_option('display.max_columns', 50)
-    #while not os.path.basename(os.getcwd()) == 'meshetar/server':
-    #    os.chdir('..')  # Move up one directory
 
     warnings.filterwarnings("ignore", category=RuntimeWarning)
-    model_path = "./models/generated/" + model_name 
+    model_path = os.path.join(os.environ.get("MESHETAR_MODELS_DIR", "./models/generated"), model_name)
 
     # %%
-    #import os
-    #print(os.path.abspath('./database.sqlite'))
-    conn = sqlite3.connect('./database.sqlite')
-    # cursor = sqliteConnection.cursor()
-    query = f"""SELECT open_time,
-                    open,
-                     high, 
-                     low, 
-                     close, 
-                     volume
-              FROM candles 
-              WHERE asset = '{pair}'"""
-
-    # %%
-    klines = pd.read_sql_query(query, conn)
-    klines['open_time'] = pd.to_datetime(klines['open_time'])
-    klines.loc[:, klines.columns.difference(['open_time'])] = klines.loc[:, klines.columns.difference(['open_time'])].apply(pd.to_numeric, errors='coerce')
-    #%%
-    klines = add_all_ta_features(klines,
-                                 open = "open", 
-                                 close = "close",
-                                 volume = "volume",
-                                 low = "low",
-                                 high = "high",
-                                 fillna=True).dropna()
+    # Features are computed by the features pipeline in Rust, one row per open_time
+    features = pd.DataFrame(rows, columns=columns)
+    klines = pd.concat([
+        pd.DataFrame({'open_time': pd.to_datetime(open_times), 'close': closes}),
+        features], axis=1)
     # %%
     one_percent_of_klines = klines.shape[0]*0.01
     peaks, _ = find_peaks(klines["close"], 
@@ -80,15 +56,7 @@ def new_model(pair="BTCUSDT", model_name="neural_net_model"):
     klines.loc[peaks, 'signal'] = "sell"
 
     # %%
-    columns_not_to_predict = [
-        'open_time', 
-        'open',
-        'close',
-        'low', 
-        'high', 
-        'volume',
-        'signal']
-    klines_to_predict = klines.drop(columns=columns_not_to_predict)
+    klines_to_predict = klines[columns]
     # %%
     # lags = range(1, 3)
 
@@ -224,6 +192,15 @@ def new_model(pair="BTCUSDT", model_name="neural_net_model"):
 
     # %%
     model.save(model_path)
+
+    # Export for the native ONNX backend, cutoffs are stored as JSON so Rust can read them
+    with open(model_path + '/cutoffs.json', 'w') as handle:
+        json.dump([float(cutoff) for cutoff in cutoffs], handle)
+    try:
+        import tf2onnx
+        tf2onnx.convert.from_keras(model, output_path=model_path + '/model.onnx')
+    except ImportError:
+        print("tf2onnx is not installed, skipping ONNX export")
     #
     # %%
     test_set_close = klines.loc[y_test.index, 'close']
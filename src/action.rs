@@ -5,22 +5,21 @@ use serde::{
   de::{self, Deserializer, Visitor},
   Deserialize, Serialize,
 };
-use uuid::Uuid;
 
 use crate::{
   assets::Pair,
-  components::list::LabelValueItem,
-  core::{Command, CoreMessage},
+  core::{alerts::Alert, Command, CoreMessage},
+  exchange::{
+    account::AccountName,
+    convert::ConvertQuote,
+    transfer::{Transfer, TransferRecord},
+  },
+  lifecycle::RunState,
+  mode::Mode,
   screens::ScreenId,
-  statistic::TradingSummary,
+  tui::RenderSettings,
 };
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub enum ScreenUpdate {
-  Report(TradingSummary),
-  Running(Vec<LabelValueItem<String>>),
-}
-
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Action {
   Tick,
@@ -33,15 +32,38 @@ pub enum Action {
   Error(String),
   Help,
   Navigate(ScreenId),
+  /// Returns to the previous screen on the navigation stack.
+  NavigateBack,
   Key(KeyCode),
   Move(MoveDirection),
   Accept,
   CoreCommand(Command),
   CoreMessage(CoreMessage),
   GenerateModel(Pair),
-  GenerateReport(Uuid),
-  GenerateRunOverview(Uuid, Pair),
-  ScreenUpdate(ScreenUpdate),
+  Alert(Alert),
+  /// Generated models or strategy modules changed on disk.
+  ModelsChanged,
+  /// The session of the app moved on in its lifecycle, also sent to newly opened screens.
+  RunStateChanged(RunState),
+  /// Moves an asset between two of the configured accounts.
+  Transfer(Transfer),
+  /// Fetches the history of transfers between the configured accounts.
+  QueryTransfers,
+  /// History of transfers between the configured accounts, latest first.
+  Transfers(Vec<TransferRecord>),
+  /// Quotes converting the small balances of the account to USDT.
+  QuoteDust(AccountName),
+  DustQuotes(AccountName, Vec<ConvertQuote>),
+  /// Accepts the quotes, converting the balances.
+  ConvertDust(AccountName, Vec<ConvertQuote>),
+  /// Changes the tick and frame rates and adaptive rendering without a restart.
+  SetRenderSettings(RenderSettings),
+  /// Copies the selected line of the screen.
+  Copy,
+  /// Copies the id of the session of the screen, or of the selected one.
+  CopyId,
+  /// Puts the text on the system clipboard.
+  Clipboard(String),
 }
 
 impl<'de> Deserialize<'de> for Action {
@@ -70,6 +92,16 @@ impl<'de> Deserialize<'de> for Action {
           "Quit" => Ok(Action::Quit),
           "Refresh" => Ok(Action::Refresh),
           "Help" => Ok(Action::Help),
+          "NavigateBack" => Ok(Action::NavigateBack),
+          "Copy" => Ok(Action::Copy),
+          "CopyId" => Ok(Action::CopyId),
+          data if data.starts_with("Navigate(") => {
+            let mode = data.trim_start_matches("Navigate(").trim_end_matches(')');
+            let mode: Mode = mode.trim().parse().map_err(E::custom)?;
+            ScreenId::from_mode(mode)
+              .map(Action::Navigate)
+              .ok_or_else(|| E::custom(format!("No screen to navigate to: {}", value)))
+          },
           data if data.starts_with("Error(") => {
             let error_msg = data.trim_start_matches("Error(").trim_end_matches(")");
             Ok(Action::Error(error_msg.to_string()))
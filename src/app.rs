@@ -1,48 +1,97 @@
 use crate::{
-  action::{Action, MoveDirection, ScreenUpdate},
-  assets::{asset_ticker, error::AssetError, MarketEvent, MarketFeed, Pair},
+  action::{Action, MoveDirection},
+  clock::{real_clock, SharedClock, SimulatedClock},
+  assets::{
+    asset_ticker, backtest_ticker::BacktestProgress, candle_window::CandleWindow,
+    error::AssetError, Asset, MarketEvent, MarketEventDetail, MarketFeed, Pair,
+  },
   components::{
+    glyphs::{glyphs, set_charset, to_ascii},
     header::MeshetarHeader,
-    style::{outer_container_block, stylized_block},
+    style::{
+      fits_terminal, outer_container_block, set_theme, stylized_block,
+      terminal_too_small, theme,
+    },
   },
   config::Config,
-  core::{error::CoreError, Command, Core, CoreMessage},
-  database::{error::DatabaseError, Database},
-  events::{Event, EventTx},
+  core::{
+    alerts::{send_webhook, Alert},
+    error::CoreError,
+    Command, Core, CoreMessage,
+  },
+  database::{
+    error::DatabaseError, retention, snapshot::DatabaseSnapshot, Database,
+  },
+  events::{
+    recording::{recording_path, EventRecorder},
+    wal::{self, WriteAheadLog},
+    Event, EventTx,
+  },
   exchange::{
-    account::{get_account_from_exchange, new_account_stream, ExchangeAccount},
+    account::{
+      get_account_from_exchange, get_api_restrictions, new_account_stream, AccountName,
+      ExchangeAccount,
+    },
     binance_client::{self, BinanceClient, BinanceClientError},
+    convert::{self, ConvertQuote, DUST_TARGET},
+    derivatives,
     error::ExchangeError,
+    futures,
+    payload,
+    sim::SimExchange,
+    transfer::{self, Transfer},
     ExchangeEvent,
   },
+  lifecycle::{RunEvent, RunState},
   mode::Mode,
+  paths,
   portfolio::{
-    allocator::Allocator, error::PortfolioError, risk::RiskEvaluator, Portfolio,
+    allocator::Allocator, balance::EquitySource, error::PortfolioError,
+    risk::{self, RiskEvaluator},
+    Portfolio,
   },
   screens::{
+    analytics::Analytics,
+    compare::Compare,
+    correlations::Correlations,
+    diagnostics::Diagnostics,
     exchange::Exchange,
     home::Home,
+    market::MarketOverview,
     model_config::ModelConfig,
     models::Models,
     report::Report,
     run_config::{CoreConfiguration, RunConfig},
     running::{Running, RunningMode},
     sessions::Sessions,
+    settings::Settings,
+    trades::Trades,
     Screen, ScreenId,
   },
   statistic::{StatisticConfig, TradingSummary},
-  strategy::{generate_new_model, Strategy},
-  trading::{error::TraderError, execution::Execution, Trader},
-  tui::{self, Frame, Tui},
-  utils::load_config::{self, read_config, ExchangeConfig},
+  strategy::{generate_new_model, watcher::watch_models, Strategy},
+  trading::{
+    backtest, error::TraderError, event_queue::EventQueue, execution::Execution,
+    params::Market, schedule, Trader,
+  },
+  tui::{self, Frame, RenderSettings, Tui},
+  utils::{
+    clipboard::Clipboard,
+    crash,
+    formatting::{format_number, set_number_locale},
+    load_config::{self, read_config, ExchangeConfig},
+  },
+  watchlist::Watchlist,
 };
 use chrono::{DateTime, Utc};
 use crossterm::event::{KeyCode, KeyEvent};
 use eyre::Result;
+use notify::RecommendedWatcher;
 use ratatui::{
-  layout::{Constraint, Layout, Margin},
+  layout::{Alignment, Constraint, Layout, Margin},
   prelude::Rect,
-  widgets::Clear,
+  style::{Modifier, Style},
+  widgets::{Clear, Paragraph},
 };
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc, time::Duration};
@@ -50,8 +99,9 @@ use thiserror::Error;
 use tokio::sync::{
   broadcast,
   mpsc::{self, error::TryRecvError, UnboundedReceiver, UnboundedSender},
-  Mutex,
+  watch, Mutex,
 };
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 #[derive(Error, Debug)]
@@ -70,13 +120,31 @@ enum MainError {
   Asset(#[from] AssetError),
   #[error("Exchange: {0}")]
   Exchange(#[from] ExchangeError),
+  #[error(
+    "The API key of {0} can withdraw, disable withdrawals on it or set \
+     allow_withdrawal_keys to run live."
+  )]
+  WithdrawalKey(AccountName),
+  #[error("Could not check that the API key of {0} cannot withdraw, not running live.")]
+  UncheckedKey(AccountName),
+  #[error("{0} is a synthetic pair, it is only backtested.")]
+  SyntheticPair(Pair),
 }
 
 pub struct App {
   pub config: Config,
   pub tick_rate: f64,
   pub frame_rate: f64,
+  /// Draw frames only when something changed since the last one.
+  pub adaptive_rendering: bool,
+  /// Input or an action changed what is shown since the last frame.
+  dirty: bool,
+  /// The last frame showed the alert toast.
+  toast_shown: bool,
+  clipboard: Clipboard,
   pub screen: Box<dyn Screen>,
+  screen_id: ScreenId,
+  nav_stack: Vec<ScreenId>,
   pub should_quit: bool,
   pub should_suspend: bool,
   pub mode: Mode,
@@ -84,27 +152,121 @@ pub struct App {
   action_rx: UnboundedReceiver<Action>,
   event_broadcast: broadcast::Sender<Event>,
   database: Arc<Mutex<Database>>,
+  snapshot: watch::Receiver<DatabaseSnapshot>,
   portfolio: Arc<Mutex<Portfolio>>,
-  core: Option<Core>,
+  run_state: RunState,
   core_command_tx: Option<mpsc::Sender<Command>>,
-  binance_client: BinanceClient,
+  /// Session that is running, for the control server.
+  session_tx: watch::Sender<Option<(Uuid, Pair)>>,
+  /// Of every account in the exchange config.
+  binance_clients: HashMap<AccountName, BinanceClient>,
   tui: Tui,
   use_testnet: bool,
   header: MeshetarHeader,
+  alert: Option<Alert>,
+  /// Sends `Action::ModelsChanged` while it is kept.
+  _models_watcher: Option<RecommendedWatcher>,
 }
 
-static STATISTIC_CONFIG: StatisticConfig = StatisticConfig {
+/// How long an alert toast stays under the header.
+const ALERT_TOAST_SECS: i64 = 30;
+
+pub static STATISTIC_CONFIG: StatisticConfig = StatisticConfig {
   starting_equity: 0f64,
   trading_days_per_year: 365,
   risk_free_return: 0.0,
   created_at: DateTime::UNIX_EPOCH,
+  reporting_currency: Asset::USDT,
 };
 
+/// [STATISTIC_CONFIG] with the statistic defaults of the config profile.
+pub fn statistic_config() -> StatisticConfig {
+  match read_config() {
+    Ok(config) => StatisticConfig {
+      trading_days_per_year: config.statistics.trading_days_per_year,
+      risk_free_return: config.statistics.risk_free_return,
+      reporting_currency: config.statistics.reporting_currency,
+      ..STATISTIC_CONFIG
+    },
+    Err(_) => STATISTIC_CONFIG,
+  }
+}
+
+/// Sends the balances of the account and then their updates from its user data stream,
+/// as exchange events of the account. Restrictions of its API key are sent before them
+/// when `check_key` is set.
+async fn track_account(
+  account: AccountName,
+  binance_client: BinanceClient,
+  stream_url: String,
+  event_tx: broadcast::Sender<Event>,
+  check_key: bool,
+) {
+  if check_key {
+    match get_api_restrictions(&binance_client).await {
+      Ok(restrictions) => {
+        if restrictions.enable_withdrawals {
+          log::warn!("The API key of {} can withdraw.", account);
+        }
+        let event = ExchangeEvent::ApiRestrictions(account.clone(), restrictions);
+        if event_tx.send(Event::Exchange(event)).is_err() {
+          log::warn!("Error sending API key restrictions.");
+        }
+      },
+      Err(e) => log::error!("Failed to check the API key of {}: {:?}", account, e),
+    }
+  }
+  log::info!("Fetching initial balances of {}.", account);
+  match get_account_from_exchange(binance_client.clone()).await {
+    Ok(exchange_account) => {
+      let event = ExchangeEvent::ExchangeAccount(account.clone(), exchange_account);
+      if event_tx.send(Event::Exchange(event)).is_err() {
+        log::warn!("Error sending account update.");
+      }
+    },
+    Err(e) => {
+      log::error!("Failed to fetch the {} account: {:?}", account, e);
+      return;
+    },
+  }
+  let mut account_listener = match new_account_stream(&stream_url, binance_client).await {
+    Ok(account_listener) => account_listener,
+    Err(e) => {
+      log::error!("Failed to listen to the {} account: {:?}", account, e);
+      return;
+    },
+  };
+  while let Some(balances) = account_listener.recv().await {
+    let event = ExchangeEvent::ExchangeBalanceUpdate(account.clone(), balances);
+    if event_tx.send(Event::Exchange(event)).is_err() {
+      log::warn!("Error sending account balance update");
+    }
+  }
+  log::info!("Account listener of {} disconnected.", account);
+}
+
+/// Stays under the header while an API key can withdraw.
+fn withdrawal_warning(accounts: &[AccountName]) -> Paragraph<'static> {
+  let accounts: Vec<String> = accounts.iter().map(AccountName::to_string).collect();
+  let text = format!(" Withdrawals enabled on the API key of {} ", accounts.join(", "));
+  Paragraph::new(text).alignment(Alignment::Right).style(
+    Style::default()
+      .fg(theme().text_critical)
+      .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+  )
+}
+
 impl App {
   async fn new_run(
     &mut self,
     core_configuration: CoreConfiguration,
   ) -> Result<(Uuid, Pair)> {
+    if core_configuration.run_live {
+      if core_configuration.pair.is_synthetic() {
+        return Err(MainError::SyntheticPair(core_configuration.pair).into());
+      }
+      self.check_withdrawal_key(&core_configuration.account)?;
+    }
     let mut traders = Vec::new();
     let core_id = Uuid::new_v4();
     let pair = core_configuration.pair.clone();
@@ -116,32 +278,116 @@ impl App {
       mpsc::channel::<Command>(20);
     let command_transmitters =
       HashMap::from([(core_configuration.pair, trader_command_transmitter)]);
-    let event_rx = self.event_broadcast.subscribe();
+    // Backtests get their own event channel, fed by the backtest ticker
+    let (backtest_ticker, backtest_progress) = if core_configuration.run_live {
+      (None, None)
+    } else {
+      let (progress_tx, progress_rx) = watch::channel(BacktestProgress::default());
+      let cancel = CancellationToken::new();
+      let database = self.database.clone();
+      let ticker =
+        backtest::ticker(&core_configuration, database, Some(progress_tx), cancel.clone())
+          .await?;
+      (Some((ticker, cancel.clone())), Some((progress_rx, cancel)))
+    };
+    let events_tx = match backtest_ticker {
+      Some(_) => broadcast::channel(backtest::BACKTEST_EVENTS).0,
+      None => self.event_broadcast.clone(),
+    };
+    let event_rx = events_tx.subscribe();
+    let clock: SharedClock = if core_configuration.run_live {
+      real_clock()
+    } else {
+      Arc::new(SimulatedClock::default())
+    };
+    {
+      let mut portfolio = self.portfolio.lock().await;
+      portfolio.set_clock(clock.clone());
+      portfolio.set_order_options(core_configuration.order_options);
+      portfolio.set_leverage(core_id, core_configuration.leverage());
+    }
+    let configuration = core_configuration.clone();
+    let recorder = if self.config.record_events {
+      let path = recording_path(core_id);
+      match EventRecorder::create(&path, core_id, core_configuration.clone()) {
+        Ok(recorder) => Some(recorder),
+        Err(e) => {
+          log::warn!("Failed to start recording to {}: {}", path.display(), e);
+          None
+        },
+      }
+    } else {
+      None
+    };
+
+    // Live orders are not sent without a log to recover them from
+    let wal = if core_configuration.run_live {
+      Some(WriteAheadLog::create(core_id, pair)?)
+    } else {
+      None
+    };
 
-    let trader_client = self.binance_client.clone();
+    let binance_client = self.binance_client(&core_configuration.account).await?;
+    let exchange_fee = core_configuration.exchange_fee;
+    let mut margin_client = None;
+    let execution = match core_configuration.market {
+      Market::Spot => Execution::new(exchange_fee, binance_client.clone(), clock.clone())
+        .with_slicing(self.config.order_slicing.clone()),
+      // Only live runs touch the futures account, backtests trade at the leverage of the
+      // portfolio
+      Market::Futures if core_configuration.run_live => {
+        let futures_client = BinanceClient::futures(&core_configuration.account).await?;
+        futures::configure(&futures_client, pair, &core_configuration.futures)?;
+        margin_client = Some(futures_client.clone());
+        Execution::new(exchange_fee, futures_client, clock.clone())
+          .with_futures(core_configuration.futures)
+      },
+      Market::Futures => Execution::stub(exchange_fee, clock.clone())
+        .with_futures(core_configuration.futures),
+    };
+    let execution = if self.config.sim_exchange.enabled && !core_configuration.run_live {
+      let sim = SimExchange::new(self.config.sim_exchange.clone());
+      execution.with_sim(Arc::new(std::sync::Mutex::new(sim)))
+    } else {
+      execution
+    };
     traders.push(
       Trader::builder()
         .core_id(core_id)
         .pair(core_configuration.pair)
         .trading_is_live(core_configuration.run_live)
         .command_reciever(trader_command_receiver)
+        .message_tx(core_message_tx.clone())
         .event_transmitter(event_transmitter)
         .portfolio(Arc::clone(&self.portfolio))
-        .strategy(Strategy::new(core_configuration.pair, core_configuration.model_name))
-        .execution(Execution::new(core_configuration.exchange_fee, trader_client))
+        .database(Arc::clone(&self.database))
+        .strategy(Strategy::new(
+          core_configuration.pair,
+          core_configuration.trading_ensemble(),
+          core_configuration.signal_evaluation,
+        ))
+        .execution(execution)
         .event_rx(event_rx)
+        .candle_window(CandleWindow::new(self.config.candle_window))
+        .event_queue(EventQueue::new(self.config.trader_queue.clone()))
+        .recorder(recorder)
+        .wal(wal)
+        .clock(clock.clone())
+        .trailing_stops(core_configuration.trailing_stops)
+        .volatility_regime(self.config.volatility_regime.clone())
         .build()?,
     );
 
+    let exchange_equity = self.exchange_equity(&configuration).await;
     let statistic_config = StatisticConfig {
-      starting_equity: core_configuration.starting_equity,
-      created_at: Utc::now(),
-      ..STATISTIC_CONFIG
+      starting_equity: exchange_equity.unwrap_or(core_configuration.starting_equity),
+      created_at: clock.now(),
+      ..statistic_config()
     };
 
-    let mut core = Core::builder()
+    let mut core_builder = Core::builder()
       .id(core_id)
-      .binance_client(self.binance_client.clone())
+      .binance_client(binance_client)
       .portfolio(self.portfolio.clone())
       .command_rx(core_command_rx)
       .message_tx(core_message_tx)
@@ -151,9 +397,34 @@ impl App {
       .statistics_config(statistic_config)
       .n_days_history_fetch(core_configuration.n_days_to_fetch as i64)
       .is_backtest(!core_configuration.run_live)
-      .build()?;
+      .event_rx(events_tx.subscribe())
+      .alert_rules(self.config.alerts.clone())
+      .staleness(self.config.staleness.clone())
+      .clock(clock)
+      .label(core_configuration.label)
+      .retraining(self.config.retraining.clone(), configuration);
+    if let Some((progress_rx, cancel)) = backtest_progress {
+      core_builder = core_builder.backtest(progress_rx, cancel);
+    }
+    if exchange_equity.is_some() {
+      core_builder =
+        core_builder.reconciled_asset(core_configuration.account.clone(), pair.quote());
+    }
+    if let Some(client) = margin_client {
+      core_builder =
+        core_builder.margin_health(self.config.margin_health.clone(), client);
+    }
+    let mut core = core_builder.build()?;
+    if let Some((ticker, cancel)) = backtest_ticker {
+      backtest::forward(ticker, events_tx, cancel);
+    }
 
+    if core_configuration.run_live {
+      let account = core_configuration.account.clone();
+      self.database.lock().await.set_session_account(core_id, account);
+    }
     self.core_command_tx = Some(core_command_tx);
+    self.session_tx.send_replace(Some((core_id, pair)));
 
     // This forwards messages from Core to App
     let action_tx_clone = self.action_tx.clone();
@@ -176,137 +447,347 @@ impl App {
     // This starts the Core and sends message when it ends
     let action_tx = self.action_tx.clone();
     tokio::spawn(async move {
-      match core.run().await {
-        Ok(_) => log::info!("Core {} finished.", core_id),
-        Err(e) => log::error!("{}", e.to_string()),
+      let message = match core.run().await {
+        Ok(_) => {
+          log::info!("Core {} finished.", core_id);
+          CoreMessage::Finished(core_id)
+        },
+        Err(e) => {
+          log::error!("{}", e.to_string());
+          CoreMessage::Failed { core_id, error: e.to_string() }
+        },
       };
-      let _ = action_tx.send(Action::CoreMessage(CoreMessage::Finished(core_id)));
+      let _ = action_tx.send(Action::CoreMessage(message));
     });
 
     Ok((core_id, pair))
   }
 
+  /// Client of the account, failing for one that is not in the exchange config.
+  async fn binance_client(&self, account: &AccountName) -> Result<BinanceClient> {
+    match self.binance_clients.get(account) {
+      Some(binance_client) => Ok(binance_client.clone()),
+      None => Ok(BinanceClient::for_account(account).await?),
+    }
+  }
+
+  /// Refuses live sessions on an API key that can withdraw or was not checked, unless
+  /// the exchange config allows it. Testnet keys cannot withdraw real funds.
+  fn check_withdrawal_key(&self, account: &AccountName) -> Result<(), MainError> {
+    if self.use_testnet || read_config().is_ok_and(|config| config.allow_withdrawal_keys)
+    {
+      return Ok(());
+    }
+    match self.snapshot.borrow().withdrawals_enabled.get(account) {
+      Some(false) => Ok(()),
+      Some(true) => Err(MainError::WithdrawalKey(account.clone())),
+      None => Err(MainError::UncheckedKey(account.clone())),
+    }
+  }
+
+  /// Sends the transfer with the client of `main` and alerts with the outcome, the
+  /// history of transfers is fetched again after it.
+  fn transfer(&self, transfer: Transfer) {
+    let binance_client = self.binance_clients[&AccountName::default()].clone();
+    let action_tx = self.action_tx.clone();
+    tokio::task::spawn_blocking(move || {
+      let result = read_config()
+        .map_err(ExchangeError::from)
+        .and_then(|config| transfer::transfer(&binance_client, &config, &transfer));
+      let message = match result {
+        Ok(_) => format!("Transferred {}.", transfer),
+        Err(e) => format!("Failed to transfer {}: {}", transfer, e),
+      };
+      let alert = Alert { core_id: None, time: Utc::now(), message };
+      let _ = action_tx.send(Action::Alert(alert));
+      let _ = action_tx.send(Action::QueryTransfers);
+    });
+  }
+
+  fn query_transfers(&self) {
+    let binance_client = self.binance_clients[&AccountName::default()].clone();
+    let action_tx = self.action_tx.clone();
+    tokio::task::spawn_blocking(move || {
+      let result = read_config()
+        .map_err(ExchangeError::from)
+        .and_then(|config| transfer::fetch_transfers(&binance_client, &config));
+      match result {
+        Ok(transfers) => {
+          let _ = action_tx.send(Action::Transfers(transfers));
+        },
+        Err(e) => log::warn!("Failed to fetch the transfers: {}", e),
+      }
+    });
+  }
+
+  /// Quotes converting the small balances of the account, between sessions only as
+  /// they may still be trading them.
+  async fn quote_dust(&self, account: &AccountName) {
+    if self.run_state.session().is_some() {
+      let message = "Small balances are converted once the session ends.".to_string();
+      let alert = Alert { core_id: None, time: Utc::now(), message };
+      let _ = self.action_tx.send(Action::Alert(alert));
+      return;
+    }
+    let Ok(binance_client) = self.binance_client(account).await else {
+      return;
+    };
+    let balances: Vec<(String, f64)> = self
+      .database
+      .lock()
+      .await
+      .get_exchange_balances(account)
+      .into_iter()
+      .map(|(asset, balance)| (asset, balance.available))
+      .collect();
+    let threshold = self.config.dust_threshold;
+    let account = account.clone();
+    let action_tx = self.action_tx.clone();
+    tokio::task::spawn_blocking(move || {
+      let quotes = convert::quote_dust(&binance_client, &balances, threshold);
+      let _ = action_tx.send(Action::DustQuotes(account, quotes));
+    });
+  }
+
+  /// Accepts the quotes and alerts with what was converted.
+  fn convert_dust(&self, account: &AccountName, quotes: Vec<ConvertQuote>) {
+    let Some(binance_client) = self.binance_clients.get(account).cloned() else {
+      return;
+    };
+    let action_tx = self.action_tx.clone();
+    tokio::task::spawn_blocking(move || {
+      let mut converted = Vec::new();
+      let mut failed = Vec::new();
+      for quote in &quotes {
+        match convert::accept_quote(&binance_client, quote) {
+          Ok(_) => converted.push(quote),
+          Err(e) => failed.push(format!("{} ({})", quote.from_asset, e)),
+        }
+      }
+      let to_amount: f64 = converted.iter().map(|quote| quote.to_amount).sum();
+      let mut message = format!(
+        "Converted {} small balances to {} {}.",
+        converted.len(),
+        format_number(to_amount),
+        DUST_TARGET
+      );
+      if !failed.is_empty() {
+        message.push_str(&format!(" Failed to convert {}.", failed.join(", ")));
+      }
+      let alert = Alert { core_id: None, time: Utc::now(), message };
+      let _ = action_tx.send(Action::Alert(alert));
+    });
+  }
+
+  /// Free balance of the pair's quote asset on the exchange, for live sessions that take
+  /// their equity from it. None falls back to the entered starting equity.
+  async fn exchange_equity(&self, configuration: &CoreConfiguration) -> Option<f64> {
+    if !configuration.run_live || configuration.equity_source != EquitySource::Exchange {
+      return None;
+    }
+    let quote = configuration.pair.quote();
+    let reporting_currency = statistic_config().reporting_currency;
+    if quote != reporting_currency {
+      log::warn!(
+        "Equity of {} is not in the reporting currency {}, using the entered equity.",
+        quote,
+        reporting_currency
+      );
+      return None;
+    }
+    let balances =
+      self.database.lock().await.get_exchange_balances(&configuration.account);
+    match balances.get(&quote.to_string()) {
+      Some(balance) => Some(balance.available),
+      None => {
+        log::warn!("No {} balance from the exchange, using the entered equity.", quote);
+        None
+      },
+    }
+  }
+
   pub async fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
     let config = Config::new()?;
+    set_number_locale(config.number_locale);
+    set_theme(config.theme);
+    set_charset(config.charset);
+    payload::set_strict(config.strict_parsing);
     let mode = Mode::Home;
     let mut screen = Home::default();
     let tui = tui::Tui::new()?.tick_rate(tick_rate).frame_rate(frame_rate);
-    let use_testnet = read_config()?.use_testnet;
+    let exchange_config = read_config()?;
+    let use_testnet = exchange_config.use_testnet;
+    let stream_url = exchange_config.stream_url.clone();
     let (action_tx, action_rx) = mpsc::unbounded_channel();
     let (event_broadcast, mut event_rx) = broadcast::channel(20);
-    let binance_client = BinanceClient::new().await.map_err(MainError::from)?;
-    let binance_client_clone = binance_client.clone();
+    let mut binance_clients = HashMap::new();
+    for account in exchange_config.account_names() {
+      let binance_client =
+        BinanceClient::for_account(&account).await.map_err(MainError::from)?;
+      binance_clients.insert(account, binance_client);
+    }
     let pairs = vec![Pair::BTCUSDT, Pair::ETHBTC];
     let database: Arc<Mutex<Database>> =
       Arc::new(Mutex::new(Database::new().await.map_err(MainError::from)?));
+    let snapshot = database.lock().await.subscribe();
+    crash::configure(config.panic_recovery.clone(), database.clone());
+    let recovered = wal::recover(&mut *database.lock().await).await;
+    for note in &recovered {
+      log::warn!("Write-ahead log: {}", note);
+    }
+    let alert = (!recovered.is_empty()).then(|| Alert {
+      core_id: None,
+      time: Utc::now(),
+      message: format!("Recovered from a crash: {}", recovered.join(" ")),
+    });
+    retention::spawn_pruning(config.retention.clone());
+    derivatives::spawn_ingestion(config.derivatives.clone());
+    schedule::spawn_schedule(
+      config.scheduled_backtests.clone(),
+      database.clone(),
+      action_tx.clone(),
+    );
+    let (session_tx, session_rx) = watch::channel(None);
+    #[cfg(unix)]
+    if config.control_socket {
+      use crate::control::{socket_path, ControlServer};
+      let server = ControlServer::new(action_tx.clone(), database.clone(), session_rx);
+      if let Err(e) = server.spawn(socket_path()) {
+        log::error!("Failed to start control server: {}", e);
+      }
+    }
+    let models_dir = paths::models_dir();
+    let _ = std::fs::create_dir_all(&models_dir);
+    let models_dirs = [models_dir.as_path(), config.strategies_dir.as_path()];
+    let models_watcher = match watch_models(&models_dirs, action_tx.clone()) {
+      Ok(watcher) => Some(watcher),
+      Err(e) => {
+        log::error!("Failed to watch the models: {}", e);
+        None
+      },
+    };
     let portfolio: Arc<Mutex<Portfolio>> = Arc::new(Mutex::new(
       Portfolio::builder()
         .database(database.clone())
-        .allocation_manager(Allocator { default_order_value: 100.0 })
+        .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 1.0 })
         .risk_manager(RiskEvaluator {})
-        .statistic_config(STATISTIC_CONFIG)
+        .statistic_config(statistic_config())
         .build()
         .await?,
     ));
+    risk::spawn_correlations(config.correlation_limit.clone(), portfolio.clone());
 
     screen.register_action_handler(action_tx.clone())?;
     screen.register_config_handler(config.clone())?;
     screen.init(tui.size()?)?;
 
-    let binance_client_clone = binance_client.clone();
+    for (account, binance_client) in &binance_clients {
+      tokio::spawn(track_account(
+        account.clone(),
+        binance_client.clone(),
+        stream_url.clone(),
+        event_broadcast.clone(),
+        !use_testnet,
+      ));
+    }
+    let ticker_client = binance_clients[&AccountName::default()].clone();
     let event_tx = event_broadcast.clone();
+    let record_trades = config.record_trades;
+    let trade_writer = database.lock().await.candle_writer();
     tokio::spawn(async move {
-      let stream_url = ExchangeConfig::get_exchange_stream_url(use_testnet);
-      let binance_client_for_account = binance_client_clone.clone();
-      log::info!("Fething initial balances.");
-      match get_account_from_exchange(binance_client_for_account).await {
-        Ok(account) => {
-          if let Err(e) =
-            event_tx.send(Event::Exchange(ExchangeEvent::ExchangeAccount(account)))
-          {
-            log::warn!("Error sending account update.");
-          }
-        },
-        Err(e) => {
-          log::error!("{:?}", e);
-          return;
-        },
-      }
       // GET CRYPTO TICKER
-      match asset_ticker::new_ticker(pairs, &stream_url).await {
+      match asset_ticker::new_ticker(pairs, &stream_url, ticker_client, record_trades)
+        .await
+      {
         Ok(mut ticker) => {
-          // GET ACCOUNT LISTENER
-          match new_account_stream(&stream_url, binance_client_clone).await {
-            Ok(mut account_listener) => {
-              log::info!("Database loop started.");
-              loop {
-                match ticker.try_recv() {
-                  Ok(market_event) => {
-                    if let Err(e) = event_tx.send(Event::Market(market_event)) {
-                      log::warn!("Error sending market event.");
-                    }
-                  },
-                  Err(e) => match e {
-                    mpsc::error::TryRecvError::Empty => {},
-                    mpsc::error::TryRecvError::Disconnected => {
-                      log::info!("Asset ticker disconnected.");
-                      return;
-                    },
-                  },
-                }
-                match account_listener.try_recv() {
-                  Ok(balances) => {
-                    if let Err(e) = event_tx.send(Event::Exchange(
-                      ExchangeEvent::ExchangeBalanceUpdate(balances),
-                    )) {
-                      log::warn!("Error sending account balance update");
-                    }
-                  },
-                  Err(e) => match e {
-                    mpsc::error::TryRecvError::Empty => continue,
-                    mpsc::error::TryRecvError::Disconnected => {
-                      log::info!("Account listener disconnected.");
-                      return;
-                    },
-                  },
-                }
-              }
-            },
-            Err(e) => {
-              log::error!("{:?}", e);
-              return;
-            },
+          log::info!("Database loop started.");
+          while let Some(event) = ticker.recv().await {
+            let event = match event {
+              ExchangeEvent::Market(market_event) => match market_event {
+                // Recorded for backtests only, traders of live sessions trade candles
+                MarketEvent {
+                  time, pair, detail: MarketEventDetail::Trade(trade), ..
+                } => {
+                  if let Err(e) = trade_writer.write_trade(pair, time, trade) {
+                    log::warn!("Error storing trade: {}", e);
+                  }
+                  continue;
+                },
+                market_event => Event::Market(market_event),
+              },
+              event => Event::Exchange(event),
+            };
+            if let Err(e) = event_tx.send(event) {
+              log::warn!("Error sending market event.");
+            }
           }
+          log::info!("Asset ticker disconnected.");
         },
-        Err(e) => {
-          log::error!("{:?}", e);
-          return;
-        },
+        Err(e) => log::error!("{:?}", e),
       };
     });
 
     let db_clone = database.clone();
+    let candle_writer = database.lock().await.candle_writer();
     let event_tx = event_broadcast.clone();
+    let mut watchlist = Watchlist::new(config.watchlist.clone());
+    let alert_tx = action_tx.clone();
     tokio::spawn(async move {
       loop {
         match event_rx.try_recv() {
-          Ok(event) => match event {
-            Event::Exchange(exchange_event) => match exchange_event {
-              ExchangeEvent::ExchangeAccount(account) => {
-                let lock = db_clone.lock();
-                lock.await.set_exchange_account(account);
-              },
-              ExchangeEvent::ExchangeBalanceUpdate(balances) => {
-                let lock = db_clone.lock();
-                lock.await.set_exchange_balances(balances);
+          Ok(event) => {
+            crash::record_event(&event);
+            match event {
+              Event::Exchange(exchange_event) => match exchange_event {
+                ExchangeEvent::ExchangeAccount(account, exchange_account) => {
+                  let lock = db_clone.lock();
+                  lock.await.set_exchange_account(&account, exchange_account);
+                },
+                ExchangeEvent::ExchangeBalanceUpdate(account, balances) => {
+                  let lock = db_clone.lock();
+                  lock.await.set_exchange_balances(&account, balances);
+                },
+                ExchangeEvent::ApiRestrictions(account, restrictions) => {
+                  let lock = db_clone.lock();
+                  let enabled = restrictions.enable_withdrawals;
+                  lock.await.set_withdrawals_enabled(&account, enabled);
+                },
+                ExchangeEvent::Market(market_event) => {
+                  if let Err(e) = event_tx.send(Event::Market(market_event)) {
+                    log::warn!("Error passing on event market update");
+                  }
+                },
+                ExchangeEvent::InvalidPayload(error) => {
+                  let alert = Alert { core_id: None, time: Utc::now(), message: error };
+                  let _ = alert_tx.send(Action::Alert(alert));
+                },
               },
-              ExchangeEvent::Market(market_event) => {
-                if let Err(e) = event_tx.send(Event::Market(market_event)) {
-                  log::warn!("Error passing on event market update");
+              Event::Market(market_event) => {
+                for price_alert in watchlist.update(&market_event) {
+                  let alert = Alert {
+                    core_id: None,
+                    time: Utc::now(),
+                    message: price_alert.to_string(),
+                  };
+                  log::info!("Watchlist: {}", alert.message);
+                  if let Some(url) = &price_alert.webhook {
+                    send_webhook(url.clone(), alert.clone());
+                  }
+                  let _ = alert_tx.send(Action::Alert(alert));
+                }
+                let (candle, is_closed) = match &market_event.detail {
+                  MarketEventDetail::Candle(candle) => (candle, true),
+                  MarketEventDetail::CandleUpdate(candle) => (candle, false),
+                  _ => continue,
+                };
+                if let Err(e) =
+                  candle_writer.write(market_event.pair, candle.clone(), is_closed)
+                {
+                  log::warn!("Error storing live candle: {}", e);
                 }
               },
-            },
-            _ => {},
+              _ => {},
+            }
           },
           Err(e) => match e {
             broadcast::error::TryRecvError::Lagged(n) => {
@@ -325,7 +806,13 @@ impl App {
       use_testnet,
       tick_rate,
       frame_rate,
+      adaptive_rendering: config.adaptive_rendering,
+      dirty: true,
+      toast_shown: false,
+      clipboard: Clipboard::default(),
       screen: Box::new(screen),
+      screen_id: ScreenId::HOME,
+      nav_stack: Vec::new(),
       should_quit: false,
       should_suspend: false,
       config,
@@ -335,25 +822,80 @@ impl App {
       event_broadcast,
       tui,
       database,
+      snapshot,
       portfolio,
-      core: None,
-      binance_client,
+      run_state: RunState::default(),
+      binance_clients,
       core_command_tx: None,
+      session_tx,
       header: MeshetarHeader::new(use_testnet),
+      alert,
+      _models_watcher: models_watcher,
     })
   }
 
-  pub fn navigate(&mut self, screen: ScreenId) -> Result<()> {
-    let mut screen: Box<dyn Screen> = match screen {
+  /// Opens the screen, keeping the way back on the navigation stack. Home resets the
+  /// stack and a screen that is already on it is returned to instead of pushed again.
+  pub fn navigate(&mut self, screen_id: ScreenId) -> Result<()> {
+    if screen_id == ScreenId::HOME {
+      self.nav_stack.clear();
+    } else if let Some(index) = self.nav_stack.iter().position(|id| *id == screen_id) {
+      self.nav_stack.truncate(index);
+    } else if self.screen_id != screen_id && !self.screen_id.is_transient() {
+      self.nav_stack.push(self.screen_id);
+    }
+    self.open(screen_id)
+  }
+
+  pub fn navigate_back(&mut self) -> Result<()> {
+    let previous = self.nav_stack.pop().unwrap_or(ScreenId::HOME);
+    self.open(previous)
+  }
+
+  /// Moves the session on in its lifecycle, opening the screen of the new state when it
+  /// has another one. Events that do not apply to the current state are ignored.
+  fn transition(&mut self, event: RunEvent) -> Result<()> {
+    let Some(state) = self.run_state.next(event) else {
+      return Ok(());
+    };
+    log::info!("Session {:?} -> {:?}", self.run_state, state);
+    if state.session().is_none() {
+      self.core_command_tx = None;
+      self.session_tx.send_replace(None);
+    }
+    if let RunState::Failed { error } = &state {
+      let core_id = self.run_state.session().map(|(core_id, _)| core_id);
+      let message = format!("{} (logs in {})", error, paths::logs_dir().display());
+      self.alert = Some(Alert { core_id, time: Utc::now(), message });
+    }
+    let previous = std::mem::replace(&mut self.run_state, state.clone());
+    match state.screen() {
+      screen if screen == previous.screen() => {},
+      Some(screen_id) => self.navigate(screen_id)?,
+      None if self.screen_id.is_transient() => self.navigate_back()?,
+      None => {},
+    }
+    self.action_tx.send(Action::RunStateChanged(state))?;
+    Ok(())
+  }
+
+  fn breadcrumbs(&self) -> String {
+    self
+      .nav_stack
+      .iter()
+      .chain([&self.screen_id])
+      .map(ScreenId::title)
+      .collect::<Vec<_>>()
+      .join(glyphs().breadcrumb)
+  }
+
+  fn open(&mut self, screen_id: ScreenId) -> Result<()> {
+    let mut screen: Box<dyn Screen> = match screen_id {
       ScreenId::HOME => Box::new(Home::default()),
       ScreenId::SESSIONS => Box::new(Sessions::default()),
       ScreenId::MODELS => Box::new(Models::default()),
       ScreenId::MODELCONFIG => Box::new(ModelConfig::default()),
-      ScreenId::REPORT(core_id) => {
-        let screen = Box::new(Report::new(core_id));
-        self.action_tx.send(Action::GenerateReport(core_id))?;
-        screen
-      },
+      ScreenId::REPORT(core_id) => Box::new(Report::new(core_id)),
       ScreenId::RUNNING((core_id, pair)) => {
         let mut running = Running::new(core_id, pair);
         running.set_mode(RunningMode::RUNNING);
@@ -361,17 +903,69 @@ impl App {
       },
       ScreenId::RUNCONFIG => Box::new(RunConfig::new()),
       ScreenId::EXCHANGE => Box::new(Exchange::new()),
+      ScreenId::TRADES(core_id) => Box::new(Trades::new(core_id)),
+      ScreenId::ANALYTICS => Box::new(Analytics::new()),
+      ScreenId::MARKETS => Box::new(MarketOverview::new()),
+      ScreenId::DIAGNOSTICS => Box::new(Diagnostics::new()),
+      ScreenId::SETTINGS => Box::new(Settings::new(RenderSettings {
+        tick_rate: self.tick_rate,
+        frame_rate: self.frame_rate,
+        adaptive: self.adaptive_rendering,
+      })),
+      ScreenId::COMPARE(first, second) => Box::new(Compare::new(first, second)),
+      ScreenId::CORRELATIONS => Box::new(Correlations::new()),
     };
     screen.register_action_handler(self.action_tx.clone())?;
     screen.register_config_handler(self.config.clone())?;
+    screen.register_snapshot_handler(self.snapshot.clone())?;
     screen.init(self.tui.size()?)?;
+    screen.update(Action::RunStateChanged(self.run_state.clone()))?;
     self.screen = screen;
+    self.screen_id = screen_id;
+    self.mode = screen_id.mode();
     Ok(())
   }
 
+  fn toast(&self) -> Option<&Alert> {
+    self
+      .alert
+      .as_ref()
+      .filter(|alert| (Utc::now() - alert.time).num_seconds() < ALERT_TOAST_SECS)
+  }
+
+  /// Whether the next frame differs from the last one, always with adaptive rendering
+  /// off.
+  fn needs_redraw(&self) -> bool {
+    !self.adaptive_rendering
+      || self.dirty
+      || self.header.is_dirty()
+      || self.screen.is_dirty()
+      || self.toast().is_some() != self.toast_shown
+  }
+
+  fn set_render_settings(&mut self, settings: RenderSettings) {
+    log::info!("Rendering at {:?}.", settings);
+    self.tick_rate = settings.tick_rate;
+    self.frame_rate = settings.frame_rate;
+    self.adaptive_rendering = settings.adaptive;
+    self.tui.set_rates(settings.tick_rate, settings.frame_rate);
+  }
+
   fn draw(&mut self) -> Result<()> {
+    let breadcrumbs = self.breadcrumbs();
+    let withdrawal_accounts = self.snapshot.borrow().withdrawal_accounts();
+    let toast = self.toast().cloned();
+    self.dirty = false;
+    self.toast_shown = toast.is_some();
     self.tui.draw(|f| {
       let area = f.size();
+      if !fits_terminal(area) {
+        terminal_too_small(f, area);
+        if glyphs().ascii {
+          to_ascii(f.buffer_mut());
+        }
+        return;
+      }
       f.render_widget(outer_container_block(), area);
       let layout = Layout::vertical(vec![
         Constraint::Length(3),
@@ -383,10 +977,27 @@ impl App {
         let action_tx = self.action_tx.clone();
         action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
       }
+      f.render_widget(
+        Paragraph::new(breadcrumbs).style(Style::default().fg(theme().text_dimmed)),
+        layout[1],
+      );
+      if let Some(alert) = &toast {
+        f.render_widget(
+          Paragraph::new(alert.message.as_str())
+            .alignment(Alignment::Right)
+            .style(Style::default().fg(theme().text_critical)),
+          layout[1],
+        );
+      } else if !withdrawal_accounts.is_empty() {
+        f.render_widget(withdrawal_warning(&withdrawal_accounts), layout[1]);
+      }
       if let Err(e) = self.screen.draw(f, layout[2]) {
         let action_tx = self.action_tx.clone();
         action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
       }
+      if glyphs().ascii {
+        to_ascii(f.buffer_mut());
+      }
     })?;
     Ok(())
   }
@@ -396,17 +1007,25 @@ impl App {
     let action_tx = self.action_tx.clone();
     loop {
       if let Some(e) = self.tui.next().await {
+        if !matches!(e, tui::Event::Tick | tui::Event::Render) {
+          self.dirty = true;
+        }
         match e {
           tui::Event::Quit => action_tx.send(Action::Quit)?,
           tui::Event::Tick => action_tx.send(Action::Tick)?,
           tui::Event::Render => action_tx.send(Action::Render)?,
           tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
           tui::Event::Key(key) => {
-            if let Some(keymap) = self.config.keybindings.get(&self.mode) {
-              if let Some(action) = keymap.get(&vec![key]) {
-                action_tx.send(action.clone())?;
-              }
-            };
+            let captures_text = self.screen.captures_text();
+            // Bindings of the screen before the global ones
+            let action = [self.mode, Mode::Global]
+              .iter()
+              .filter_map(|mode| self.config.keybindings.get(mode))
+              .find_map(|keymap| keymap.get(&vec![key]))
+              .filter(|_| !captures_text);
+            if let Some(action) = action {
+              action_tx.send(action.clone())?;
+            }
             match key.code {
               KeyCode::Up => {
                 let _ = action_tx.send(Action::Move(MoveDirection::Up));
@@ -424,9 +1043,9 @@ impl App {
                 let _ = action_tx.send(Action::Accept);
               },
               KeyCode::Esc => {
-                let _ = action_tx.send(Action::Navigate(ScreenId::HOME));
+                let _ = action_tx.send(Action::NavigateBack);
               },
-              KeyCode::Char('q') => {
+              KeyCode::Char('q') if !captures_text => {
                 let _ = action_tx.send(Action::Quit);
               },
               _ => {},
@@ -444,17 +1063,30 @@ impl App {
 
         if action_clone_log != Action::Tick && action_clone_log != Action::Render {
           log::debug!("{action:?}");
+          self.dirty = true;
         }
 
         match action {
           Action::Tick => {
+            let session = *self.session_tx.borrow();
+            self.dirty |= self.snapshot.has_changed().unwrap_or(false);
+            let snapshot = self.snapshot.borrow_and_update();
+            // The header follows the account of the live session, `main` otherwise
+            let account = session
+              .and_then(|(id, _)| snapshot.session_accounts.get(&id).cloned())
+              .unwrap_or_default();
             let header_last_updated =
               self.header.last_updated().unwrap_or(DateTime::default());
-            if Utc::now() - Duration::from_secs(10) > header_last_updated {
-              let db = self.database.lock().await;
-              let valuation = db.get_valuation();
-              drop(db);
-              self.header.update(valuation.0, valuation.1);
+            if Utc::now() - Duration::from_secs(10) > header_last_updated
+              || *self.header.account() != account
+            {
+              let valuation = snapshot.valuation(&account);
+              self.header.update(account, valuation.0, valuation.1);
+            }
+            let session_pnl = session.and_then(|(id, _)| snapshot.session_pnl(&id));
+            self.header.set_session_pnl(session_pnl);
+            if session.is_none() {
+              self.header.set_margin_health(None);
             }
           },
           Action::Quit => self.should_quit = true,
@@ -464,33 +1096,99 @@ impl App {
             self.tui.resize(Rect::new(0, 0, w, h))?;
             self.draw()?;
           },
-          Action::Render => {
-            self.draw()?;
+          Action::Render if self.needs_redraw() => self.draw()?,
+          Action::SetRenderSettings(settings) => self.set_render_settings(settings),
+          Action::Clipboard(ref text) => {
+            let message = match self.clipboard.copy(text) {
+              Ok(()) => format!("Copied {}", text),
+              Err(e) => format!("Could not copy to the clipboard: {}", e),
+            };
+            self.alert = Some(Alert { core_id: None, time: Utc::now(), message });
           },
           Action::Navigate(screen) => {
             self.navigate(screen)?;
           },
+          Action::NavigateBack => {
+            self.navigate_back()?;
+          },
           Action::CoreCommand(command) => match command {
-            Command::Start(core_configuration) => {
-              let (core_id, pair) = self.new_run(core_configuration).await?;
-              let _ = self.navigate(ScreenId::RUNNING((core_id, pair)))?;
+            Command::Start(core_configuration) if self.run_state.can_start() => {
+              let event = match self.new_run(core_configuration).await {
+                Ok((core_id, pair)) => RunEvent::Started { core_id, pair },
+                Err(e) => RunEvent::Failed { core_id: None, error: e.to_string() },
+              };
+              self.transition(event)?;
+            },
+            Command::Start(_) => {
+              log::warn!("A session is running, not starting another.")
             },
-            _ => {
+            _ if self.run_state.accepts_commands() => {
+              if matches!(command, Command::Terminate(_)) {
+                self.transition(RunEvent::Terminate)?;
+              }
               if let Some(tx) = &self.core_command_tx {
                 tx.send(command).await?;
               }
             },
+            _ => log::warn!("No running session for {:?}.", command),
           },
           Action::CoreMessage(msg) => match msg {
+            CoreMessage::TradersStarted { core_id } => {
+              self.transition(RunEvent::TradersStarted(core_id))?
+            },
             CoreMessage::Finished(core_id) => {
-              self.navigate(ScreenId::REPORT(core_id))?;
+              self.transition(RunEvent::Finished(core_id))?
+            },
+            CoreMessage::Failed { core_id, error } => {
+              self.transition(RunEvent::Failed { core_id: Some(core_id), error })?
             },
+            CoreMessage::Alert(alert) => self.alert = Some(alert),
+            CoreMessage::SystemError { core_id, error } => {
+              let time = Utc::now();
+              self.alert = Some(Alert { core_id: Some(core_id), time, message: error });
+            },
+            CoreMessage::TraderFailed { core_id, pair, error } => {
+              let time = Utc::now();
+              let message = format!(
+                "Trader of {} stopped: {} (logs in {})",
+                pair,
+                error,
+                paths::logs_dir().display()
+              );
+              self.alert = Some(Alert { core_id: Some(core_id), time, message });
+            },
+            CoreMessage::SummaryReady { core_id, path } => {
+              let time = Utc::now();
+              let message = format!("Report written to {}", path.display());
+              self.alert = Some(Alert { core_id: Some(core_id), time, message });
+            },
+            CoreMessage::MarginHealth { health, level, .. } => {
+              self.header.set_margin_health(Some((health, level)))
+            },
+            CoreMessage::HistoryFetchDone { core_id, candles } => {
+              log::info!("Fetched {} candles of history for {}.", candles, core_id)
+            },
+            // Shown by the running screen
+            CoreMessage::HistoryFetchStarted { .. }
+            | CoreMessage::HistoryFetchProgress { .. }
+            | CoreMessage::MarketDataStale { .. }
+            | CoreMessage::MarketDataFresh { .. }
+            | CoreMessage::WarmingUp { .. }
+            | CoreMessage::BacktestProgress { .. } => {},
+          },
+          Action::Alert(alert) => self.alert = Some(alert),
+          Action::Transfer(transfer) => self.transfer(transfer),
+          Action::QueryTransfers => self.query_transfers(),
+          Action::QuoteDust(ref account) => self.quote_dust(account).await,
+          Action::ConvertDust(ref account, ref quotes) => {
+            self.convert_dust(account, quotes.clone())
           },
 
           Action::GenerateModel(pair) => {
             log::warn!("Starting new model generation");
+            let database = Arc::clone(&self.database);
             tokio::spawn(async move {
-              match generate_new_model(pair).await {
+              match generate_new_model(pair, None, database).await {
                 Ok(_) => {
                   log::warn!("New model created.");
                 },
@@ -500,18 +1198,6 @@ impl App {
               }
             });
           },
-          Action::GenerateRunOverview(core_id, pair) => {
-            let mut db = self.database.try_lock()?;
-            if let Ok(report) = db.generate_run_overview(&core_id, &pair) {
-              action_tx.send(Action::ScreenUpdate(ScreenUpdate::Running(report)))?;
-            }
-          },
-          Action::GenerateReport(core_id) => {
-            let mut db = self.database.try_lock()?;
-            if let Ok(report) = db.get_statistics(&core_id) {
-              action_tx.send(Action::ScreenUpdate(ScreenUpdate::Report(report)))?;
-            }
-          },
           _ => {},
         }
         if let Some(action) = self.screen.update(action_clone.clone())? {
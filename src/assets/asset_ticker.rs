@@ -1,15 +1,35 @@
-use super::{error::AssetError, Candle, MarketEvent, MarketEventDetail, Pair};
-use crate::{exchange::error::ExchangeError, utils::serde_utils::f64_from_string};
+use super::{
+  error::AssetError, Candle, MarketEvent, MarketEventDetail, Pair, PublicTrade, Side,
+  CANDLE_INTERVAL,
+};
+use crate::{
+  diagnostics::{self, Task},
+  exchange::{
+    binance_client::BinanceClient, error::ExchangeError, payload::parse, BinanceKline,
+    ExchangeEvent,
+  },
+  utils::serde_utils::f64_from_string,
+};
 use binance_spot_connector_rust::{
-  market::klines::KlineInterval, market_stream::kline::KlineStream,
-  tokio_tungstenite::BinanceWebSocketClient,
+  market::klines::KlineInterval,
+  market_stream::{agg_trade::AggTradeStream, kline::KlineStream},
+  tokio_tungstenite::{BinanceWebSocketClient, WebSocketState},
 };
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use futures::{StreamExt, TryFutureExt};
 use serde::Deserialize;
-use std::str::FromStr;
-use tokio::sync::mpsc::{self, error::SendError, UnboundedReceiver};
-use tracing::{info, warn};
+use std::{collections::HashMap, str::FromStr, time::Duration};
+use tokio::{
+  net::TcpStream,
+  sync::mpsc::{self, error::SendError, UnboundedReceiver, UnboundedSender},
+};
+use tokio_tungstenite::MaybeTlsStream;
+use tracing::{debug, info, warn};
+
+/// How often klines are polled over REST while the stream is down, one CANDLE_INTERVAL.
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+type KlineConnection = WebSocketState<MaybeTlsStream<TcpStream>>;
 
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize)]
@@ -28,6 +48,8 @@ pub struct KlineDetail {
   pub open_time: i64, // Kline start time
   #[serde(rename = "T")]
   pub close_time: i64, // Kline close time
+  #[serde(rename = "s", default)]
+  pub symbol: String, // Symbol
   #[serde(rename = "i")]
   pub interval: String, // Interval
   #[serde(rename = "f")]
@@ -58,11 +80,106 @@ pub struct KlineDetail {
   pub ignore: String, // Ignore
 }
 
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+pub struct AggTradeEvent {
+  pub e: String, // Event type
+  pub E: i64,    // Event time
+  #[serde(rename = "s")]
+  pub symbol: String, // Symbol
+  #[serde(rename = "a")]
+  pub id: i64, // Aggregate trade ID
+  #[serde(rename = "p", deserialize_with = "f64_from_string")]
+  pub price: f64, // Price
+  #[serde(rename = "q", deserialize_with = "f64_from_string")]
+  pub quantity: f64, // Quantity
+  #[serde(rename = "f")]
+  pub first_trade_id: i64, // First trade ID
+  #[serde(rename = "l")]
+  pub last_trade_id: i64, // Last trade ID
+  #[serde(rename = "T")]
+  pub trade_time: i64, // Trade time
+  #[serde(rename = "m")]
+  pub is_buyer_maker: bool, // Is the buyer the market maker?
+  #[serde(rename = "M", default)]
+  pub ignore: bool, // Ignore
+}
+
+impl From<&AggTradeEvent> for PublicTrade {
+  fn from(event: &AggTradeEvent) -> Self {
+    PublicTrade {
+      id: event.id.to_string(),
+      price: event.price,
+      amount: event.quantity,
+      // The taker sold into the bid when the buyer made the market
+      side: if event.is_buyer_maker { Side::Sell } else { Side::Buy },
+    }
+  }
+}
+
+/// Type of a stream message, to pick the payload it is parsed as.
+#[derive(Deserialize)]
+struct StreamMessage {
+  #[serde(default)]
+  e: String,
+}
+
+/// Streams klines of the pairs, and their aggregate trades when `trades` is set, as market
+/// events. Payloads that fail to parse are sent as invalid payload events. When the
+/// stream goes down, closed candles are polled over REST every [FALLBACK_POLL_INTERVAL]
+/// and sent flagged as degraded until it reconnects; trades are missed meanwhile.
 pub async fn new_ticker(
   pairs: Vec<Pair>,
   stream_url: &str,
-) -> Result<UnboundedReceiver<MarketEvent>, ExchangeError> {
+  binance_client: BinanceClient,
+  trades: bool,
+) -> Result<UnboundedReceiver<ExchangeEvent>, ExchangeError> {
   let (tx, rx) = mpsc::unbounded_channel();
+  let conn = connect(&pairs, stream_url, trades).await?;
+  let stream_url = stream_url.to_string();
+
+  tokio::spawn(async move {
+    let mut conn = Some(conn);
+    let mut last_polled: HashMap<Pair, DateTime<Utc>> = HashMap::new();
+    loop {
+      if let Some(conn) = conn.take() {
+        if let Err(e) = stream(conn, &tx).await {
+          log::error!("Mystery market feed error: {}", e);
+          return;
+        }
+        warn!("Kline stream disconnected, polling klines over REST until it recovers.");
+      }
+      for pair in pairs.iter() {
+        match poll(&binance_client, *pair, last_polled.get(pair).copied()) {
+          Ok(Some(event)) => {
+            last_polled.insert(*pair, event.time);
+            if tx.send(ExchangeEvent::Market(event)).is_err() {
+              return;
+            }
+          },
+          Ok(None) => {},
+          Err(e) => warn!("Failed to poll klines of {}: {}", pair, e),
+        }
+      }
+      tokio::time::sleep(FALLBACK_POLL_INTERVAL).await;
+      match connect(&pairs, &stream_url, trades).await {
+        Ok(new_conn) => {
+          info!("Kline stream reconnected.");
+          conn = Some(new_conn);
+        },
+        Err(e) => warn!("Failed to reconnect kline stream: {}", e),
+      }
+    }
+  });
+
+  Ok(rx)
+}
+
+async fn connect(
+  pairs: &[Pair],
+  stream_url: &str,
+  trades: bool,
+) -> Result<KlineConnection, ExchangeError> {
   let (mut conn, _) = BinanceWebSocketClient::connect_async(stream_url)
     .map_err(|e| ExchangeError::BinanceStreamError(e.to_string()))
     .await?;
@@ -70,49 +187,120 @@ pub async fn new_ticker(
   for pair in pairs {
     conn
       .subscribe(vec![
-        &KlineStream::new(&pair.to_string(), KlineInterval::Minutes1).into()
+        &KlineStream::new(&pair.to_string(), CANDLE_INTERVAL).into()
       ])
       .await;
+    if trades {
+      conn.subscribe(vec![&AggTradeStream::new(&pair.to_string()).into()]).await;
+    }
   }
+  Ok(conn)
+}
 
-  tokio::spawn(async move {
-    while let Some(message) = conn.as_mut().next().await {
-      match message {
-        Ok(message) => {
-          let data = message.into_data();
-          if let Ok(string_data) = String::from_utf8(data) {
-            let raw_asset_parse: Result<KlineEvent, serde_json::Error> =
-              serde_json::from_str(&string_data);
-            match raw_asset_parse {
-              Ok(new_kline) => {
-                if let Ok(pair) = Pair::from_str(&new_kline.symbol) {
-                  if let Err(e) = tx.send(MarketEvent {
-                    time: Utc.timestamp_opt(new_kline.E, 0).unwrap(),
-                    pair,
-                    detail: MarketEventDetail::Candle(Candle::from(&new_kline)),
-                  }) {
-                    let e_msg = e.to_string();
-                    match e {
-                      SendError(market_event) => {
-                        log::error!("Mystery market feed error: {}", e_msg);
-                        break;
-                      },
-                    }
-                  };
-                } else {
-                  log::warn!("Couldn't parse Pair from websocket kline.")
-                };
-              },
+/// Forwards klines and trades until the stream ends, errors only when the receiver is
+/// dropped. Messages other than klines and trades, like replies to the subscriptions,
+/// are skipped.
+async fn stream(
+  mut conn: KlineConnection,
+  tx: &UnboundedSender<ExchangeEvent>,
+) -> Result<(), SendError<ExchangeEvent>> {
+  while let Some(message) = conn.as_mut().next().await {
+    match message {
+      Ok(message) => {
+        let data = message.into_data();
+        if let Ok(string_data) = String::from_utf8(data) {
+          let kind = serde_json::from_str::<StreamMessage>(&string_data)
+            .map(|message| message.e)
+            .unwrap_or_default();
+          if kind == "aggTrade" {
+            match trade_event(&string_data) {
+              Ok(Some(event)) => tx.send(ExchangeEvent::Market(event))?,
+              Ok(None) => {},
               Err(e) => {
-                warn!("Error parsing asset feed event: {}", e);
+                warn!("Error parsing trade feed event: {}", e);
+                tx.send(ExchangeEvent::InvalidPayload(e.to_string()))?;
               },
             }
+            continue;
           }
-        },
-        Err(e) => warn!("Error recieving on PRICE SOCKET: {:?}", e),
-      }
+          if kind != "kline" {
+            debug!("Skipping market stream message: {}", string_data);
+            continue;
+          }
+          let raw_asset_parse: Result<KlineEvent, ExchangeError> =
+            parse("kline", &string_data);
+          match raw_asset_parse {
+            Ok(new_kline) => {
+              let Some(time) = Utc.timestamp_millis_opt(new_kline.E).single() else {
+                warn!(event_time = new_kline.E, "Kline with invalid event time.");
+                continue;
+              };
+              if let Ok(pair) = Pair::from_str(&new_kline.symbol) {
+                let candle = Candle::from(&new_kline);
+                let detail = if new_kline.detail.is_closed {
+                  MarketEventDetail::Candle(candle)
+                } else {
+                  MarketEventDetail::CandleUpdate(candle)
+                };
+                let lag = (Utc::now() - time).to_std().unwrap_or_default();
+                diagnostics::record_latency(Task::MarketStream, lag);
+                let event = MarketEvent { time, pair, detail, degraded: false };
+                tx.send(ExchangeEvent::Market(event))?;
+              } else {
+                log::warn!("Couldn't parse Pair from websocket kline.")
+              };
+            },
+            Err(e) => {
+              warn!("Error parsing asset feed event: {}", e);
+              tx.send(ExchangeEvent::InvalidPayload(e.to_string()))?;
+            },
+          }
+        }
+      },
+      Err(e) => {
+        warn!("Error recieving on PRICE SOCKET: {:?}", e);
+        break;
+      },
     }
-  });
+  }
+  Ok(())
+}
 
-  Ok(rx)
+/// Market event of a trade payload, none for a trade of an unknown pair or time.
+fn trade_event(data: &str) -> Result<Option<MarketEvent>, ExchangeError> {
+  let event: AggTradeEvent = parse("aggTrade", data)?;
+  let Some(time) = Utc.timestamp_millis_opt(event.trade_time).single() else {
+    return Ok(None);
+  };
+  let Ok(pair) = Pair::from_str(&event.symbol) else {
+    return Ok(None);
+  };
+  let detail = MarketEventDetail::Trade(PublicTrade::from(&event));
+  Ok(Some(MarketEvent { time, pair, detail, degraded: false }))
+}
+
+/// Latest closed candle of the pair, if it closed after the last polled one.
+fn poll(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  last_polled: Option<DateTime<Utc>>,
+) -> Result<Option<MarketEvent>, ExchangeError> {
+  let request =
+    binance_spot_connector_rust::market::klines(&pair.to_string(), CANDLE_INTERVAL)
+      .limit(2);
+  let res = binance_client.send(request)?;
+  let klines: Vec<BinanceKline> = serde_json::from_str(&res)?;
+  let now = Utc::now();
+  let candle = klines
+    .iter()
+    .map(Candle::from)
+    .rev()
+    .find(|candle| candle.close_time < now)
+    .filter(|candle| last_polled.is_none_or(|last| candle.close_time > last));
+  Ok(candle.map(|candle| MarketEvent {
+    time: candle.close_time,
+    pair,
+    detail: MarketEventDetail::Candle(candle),
+    degraded: true,
+  }))
 }
@@ -1,65 +1,259 @@
-use super::{error::AssetError, MarketEvent, MarketEventDetail, Pair};
+use super::{
+  error::AssetError,
+  gaps::{GapDetector, GapPolicy, GapStats},
+  candle_interval_duration, Candle, MarketEvent, MarketEventDetail, Pair,
+};
 use crate::{
-  database::Database,
-  strategy::{Signal, Strategy},
-  utils::remove_vec_items_from_start,
+  database::{cursor::CandleCursor, error::DatabaseError, fetch_trades, Database},
+  strategy::{ensemble::Ensemble, Strategy},
 };
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::sync::Arc;
 use tokio::sync::{
-  mpsc::{self, UnboundedReceiver},
-  Mutex,
+  mpsc::{self, Receiver},
+  watch, Mutex,
 };
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
+/// Candles read from the database and run through the models at once.
+const PAGE_SIZE: usize = 10_000;
+/// Candles sent ahead of the consumer, so the progress follows the candles handled.
+const CHANNEL_SIZE: usize = 1_000;
+
+/// How far a backtest got, updated as candles are sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct BacktestProgress {
+  pub candles: usize,
+  /// Candles of the backtest, without the buffer of model input at the start.
+  pub total: usize,
+  /// Close time of the latest candle sent.
+  pub time: Option<DateTime<Utc>>,
+  /// Gaps in the candles read so far.
+  pub gaps: GapStats,
+}
+
+impl BacktestProgress {
+  /// Share of the candles sent, from 0 to 1.
+  pub fn ratio(&self) -> f64 {
+    if self.total == 0 {
+      return 0.0;
+    }
+    (self.candles as f64 / self.total as f64).min(1.0)
+  }
+}
+
+/// Where the backtested candles come from.
+enum CandleSource {
+  Database(CandleCursor),
+  /// Candles of a model's training dataset.
+  Dataset(std::vec::IntoIter<Candle>),
+}
+
+impl CandleSource {
+  async fn next_page(&mut self) -> Result<Vec<Candle>, DatabaseError> {
+    match self {
+      CandleSource::Database(cursor) => cursor.next_page().await,
+      CandleSource::Dataset(candles) => Ok(candles.by_ref().take(PAGE_SIZE).collect()),
+    }
+  }
+}
+
+/// How a backtest ticker runs, besides the candles it runs on.
+pub struct TickerOptions {
+  /// Candles at the start that only serve as model input.
+  pub buffer_n_of_candles: usize,
+  pub gaps: GapPolicy,
+  /// Sends the recorded aggregate trades of each candle before it.
+  pub trades: bool,
+  pub progress_tx: Option<watch::Sender<BacktestProgress>>,
+  /// Stops the models and ends the candles early.
+  pub cancel: CancellationToken,
+}
+
+impl TickerOptions {
+  pub fn new(buffer_n_of_candles: usize) -> Self {
+    Self {
+      buffer_n_of_candles,
+      gaps: GapPolicy::default(),
+      trades: false,
+      progress_tx: None,
+      cancel: CancellationToken::new(),
+    }
+  }
+}
+
 pub async fn new_ticker(
   database: Arc<Mutex<Database>>,
   last_n_candles: usize,
-  buffer_n_of_candles: usize,
   pair: Pair,
-  model_name: String,
-) -> Result<UnboundedReceiver<MarketEvent>, AssetError> {
-  let (tx, rx) = mpsc::unbounded_channel();
-  let candles = database.lock().await.fetch_all_candles(pair.clone()).await?;
-  let skip_n_candles = candles.len() - last_n_candles;
+  ensemble: Ensemble,
+  options: TickerOptions,
+) -> Result<Receiver<MarketEvent>, AssetError> {
+  let (cursor, n_candles) = {
+    let database = database.lock().await;
+    let cursor = database.candle_cursor(pair, last_n_candles, PAGE_SIZE).await?;
+    (cursor, database.count_closed_candles(pair).await?.min(last_n_candles))
+  };
+  let source = CandleSource::Database(cursor);
+  Ok(spawn_ticker(source, n_candles, pair, ensemble, options))
+}
 
-  // take only specified number of candles
-  let candles = remove_vec_items_from_start(candles, skip_n_candles);
+/// Like [new_ticker], over the given candles instead of the stored ones.
+pub fn new_dataset_ticker(
+  candles: Vec<Candle>,
+  pair: Pair,
+  ensemble: Ensemble,
+  options: TickerOptions,
+) -> Receiver<MarketEvent> {
+  let n_candles = candles.len();
+  let source = CandleSource::Dataset(candles.into_iter());
+  spawn_ticker(source, n_candles, pair, ensemble, options)
+}
+
+fn spawn_ticker(
+  mut source: CandleSource,
+  n_candles: usize,
+  pair: Pair,
+  ensemble: Ensemble,
+  options: TickerOptions,
+) -> Receiver<MarketEvent> {
+  let TickerOptions { buffer_n_of_candles, gaps, trades, progress_tx, cancel } = options;
+  let progress_tx = progress_tx.unwrap_or_else(|| watch::channel(Default::default()).0);
+  let total = n_candles.saturating_sub(buffer_n_of_candles);
+  progress_tx.send_replace(BacktestProgress { total, ..Default::default() });
+  let mut gaps = GapDetector::new(gaps, candle_interval_duration());
+  let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+  // Candles carried over from the previous page so features of the next one are complete
+  let context = ensemble
+    .members
+    .iter()
+    .map(|member| member.features.warmup())
+    .max()
+    .unwrap_or_default()
+    .max(buffer_n_of_candles);
 
   tokio::spawn(async move {
-    let candles_copy = candles.clone();
-    let open_time =
-      candles_copy.first().expect("No candles for backtesting :<").open_time;
-    match Strategy::generate_backtest_signals(
-      open_time,
-      candles_copy,
-      buffer_n_of_candles,
-      pair,
-      model_name,
-    )
-    .await
-    {
-      Ok(Some(signals)) => {
-        let mut stream_candles = candles.iter().skip(buffer_n_of_candles).enumerate();
-        info!(
-          "Backtesting {} candles, with {} signals",
-          stream_candles.len(),
-          signals.len()
-        );
-        while let Some((index, candle)) = stream_candles.next() {
-          let signal = signals.get(index);
-          let signal: Option<Signal> =
-            if signal.is_some() { signal.unwrap().to_owned() } else { None };
-          let _ = tx.send(MarketEvent {
-            time: candle.close_time,
-            pair,
-            detail: MarketEventDetail::BacktestCandle((candle.to_owned(), signal)),
-          });
+    let mut candles: Vec<Candle> = Vec::with_capacity(context + PAGE_SIZE);
+    let mut n_backtested = 0;
+    let mut n_signals = 0;
+    let mut skip_n_candles = buffer_n_of_candles;
+    // Candles before the models warmed up on the current segment have no signal
+    let mut segment_starts = Vec::new();
+    let mut warming_up = 0;
+    'pages: loop {
+      let page = match source.next_page().await {
+        Ok(page) if page.is_empty() => break,
+        Ok(page) => page,
+        Err(e) => {
+          error!("Err on reading candles for backtest: {:?}", e);
+          break;
+        },
+      };
+      let mut page = page;
+      let n_read = page.len();
+      segment_starts.extend(gaps.apply(&mut page));
+      progress_tx.send_modify(|progress| {
+        progress.total += page.len() - n_read;
+        progress.gaps = gaps.stats();
+      });
+      let n_carried = candles.len();
+      candles.extend(page);
+      let open_time = candles[0].open_time;
+      let signals = match Strategy::generate_backtest_signals(
+        open_time,
+        candles.clone(),
+        buffer_n_of_candles,
+        pair,
+        &ensemble,
+        &cancel,
+      )
+      .await
+      {
+        Ok(Some(signals)) => signals,
+        Ok(None) => break,
+        Err(e) => {
+          error!("Err on backtest: {:?}", e);
+          break;
+        },
+      };
+      for (candle, signal) in
+        candles.iter().zip(signals).skip(n_carried.max(skip_n_candles))
+      {
+        if segment_starts.first() == Some(&candle.open_time) {
+          segment_starts.remove(0);
+          warming_up = context;
         }
-      },
-      Ok(None) => (),
-      Err(e) => error!("Err on backtest: {:?}", e),
-    };
+        let signal = if warming_up > 0 {
+          warming_up -= 1;
+          None
+        } else {
+          signal
+        };
+        n_backtested += 1;
+        n_signals += signal.is_some() as usize;
+        if trades {
+          for event in candle_trades(pair, candle).await {
+            if !send(&tx, event, &cancel).await {
+              break 'pages;
+            }
+          }
+        }
+        let event = MarketEvent {
+          time: candle.close_time,
+          pair,
+          detail: MarketEventDetail::BacktestCandle((candle.to_owned(), signal)),
+          degraded: false,
+        };
+        if !send(&tx, event, &cancel).await {
+          break 'pages;
+        }
+        progress_tx.send_modify(|progress| {
+          progress.candles = n_backtested;
+          progress.time = Some(candle.close_time);
+        });
+      }
+      skip_n_candles = 0;
+      candles.drain(..candles.len().saturating_sub(context));
+    }
+    if cancel.is_cancelled() {
+      info!("Backtest cancelled after {} candles, {} signals", n_backtested, n_signals);
+    } else {
+      info!("Backtested {} candles, with {} signals", n_backtested, n_signals);
+    }
   });
 
-  Ok(rx)
+  rx
+}
+
+/// Sends the event unless the receiver is gone or the backtest was cancelled.
+async fn send(
+  tx: &mpsc::Sender<MarketEvent>,
+  event: MarketEvent,
+  cancel: &CancellationToken,
+) -> bool {
+  tokio::select! {
+    sent = tx.send(event) => sent.is_ok(),
+    _ = cancel.cancelled() => false,
+  }
+}
+
+/// Recorded trades within the candle, as market events at their trade time.
+async fn candle_trades(pair: Pair, candle: &Candle) -> Vec<MarketEvent> {
+  match fetch_trades(pair, candle.open_time, candle.close_time).await {
+    Ok(trades) => trades
+      .into_iter()
+      .map(|(time, trade)| MarketEvent {
+        time,
+        pair,
+        detail: MarketEventDetail::Trade(trade),
+        degraded: false,
+      })
+      .collect(),
+    Err(e) => {
+      error!("Err on reading trades for backtest: {:?}", e);
+      Vec::new()
+    },
+  }
 }
@@ -0,0 +1,69 @@
+use super::{Candle, MarketEventDetail};
+use std::collections::VecDeque;
+
+/// Candles kept per pair when `candle_window` is not set in the config.
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// Latest closed candles of a pair, oldest first, kept in memory so strategies do not
+/// read them back from the database on every market event.
+#[derive(Clone, Debug)]
+pub struct CandleWindow {
+  capacity: usize,
+  candles: VecDeque<Candle>,
+}
+
+impl CandleWindow {
+  pub fn new(capacity: usize) -> Self {
+    Self { capacity, candles: VecDeque::with_capacity(capacity) }
+  }
+
+  /// Keeps the closed candles of the event, updates of open candles are not stored.
+  pub fn update(&mut self, detail: &MarketEventDetail) {
+    if let MarketEventDetail::Candle(candle) = detail {
+      self.push(candle.clone());
+    }
+  }
+
+  /// Adds a closed candle, replacing a candle with the same open time. Candles older
+  /// than the latest one are ignored.
+  pub fn push(&mut self, candle: Candle) {
+    match self.candles.back() {
+      Some(last) if last.open_time == candle.open_time => {
+        self.candles.pop_back();
+      },
+      Some(last) if last.open_time > candle.open_time => return,
+      _ => {},
+    }
+    self.candles.push_back(candle);
+    while self.candles.len() > self.capacity {
+      self.candles.pop_front();
+    }
+  }
+
+  /// Up to `n` closed candles that opened before the candle, followed by the candle.
+  pub fn history(&self, candle: &Candle, n: usize) -> Vec<Candle> {
+    let before = self.candles.partition_point(|c| c.open_time < candle.open_time);
+    let mut history: Vec<Candle> =
+      self.candles.range(before.saturating_sub(n)..before).cloned().collect();
+    history.push(candle.clone());
+    history
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  pub fn len(&self) -> usize {
+    self.candles.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.candles.is_empty()
+  }
+}
+
+impl Default for CandleWindow {
+  fn default() -> Self {
+    Self::new(DEFAULT_CAPACITY)
+  }
+}
@@ -0,0 +1,121 @@
+use super::Candle;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use strum::{Display, EnumIter, EnumString};
+
+/// How a backtest handles missing candles in the stored history.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum GapPolicy {
+  /// Runs over the gap as if the candles were adjacent, only counting it.
+  #[default]
+  Ignore,
+  /// Fills the gap with flat candles at the last close and without volume.
+  Fill,
+  /// Starts a new segment after the gap, with no signals until the models warmed up on
+  /// it again.
+  Split,
+}
+
+/// Gaps found in the candles of a backtest.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct GapStats {
+  pub gaps: usize,
+  pub missing_candles: usize,
+  /// Missing candles of the longest gap.
+  pub longest: usize,
+}
+
+impl fmt::Display for GapStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.gaps {
+      0 => write!(f, "none"),
+      gaps => write!(
+        f,
+        "{gaps}, {} missing candles, longest {}",
+        self.missing_candles, self.longest
+      ),
+    }
+  }
+}
+
+/// Finds gaps between consecutive candles, across the pages of a backtest.
+#[derive(Debug)]
+pub struct GapDetector {
+  policy: GapPolicy,
+  interval: Duration,
+  last: Option<Candle>,
+  stats: GapStats,
+}
+
+impl GapDetector {
+  pub fn new(policy: GapPolicy, interval: Duration) -> Self {
+    Self { policy, interval, last: None, stats: GapStats::default() }
+  }
+
+  pub fn stats(&self) -> GapStats {
+    self.stats
+  }
+
+  /// Counts the gaps before the candles of the page and fills them when the policy says
+  /// so. Returns the open times of candles that start a new segment.
+  pub fn apply(&mut self, page: &mut Vec<Candle>) -> Vec<DateTime<Utc>> {
+    let mut candles = Vec::with_capacity(page.len());
+    let mut segment_starts = Vec::new();
+    for candle in page.drain(..) {
+      if let Some(last) = &self.last {
+        let missing = self.missing_between(last.open_time, candle.open_time);
+        if missing > 0 {
+          self.stats.gaps += 1;
+          self.stats.missing_candles += missing;
+          self.stats.longest = self.stats.longest.max(missing);
+          match self.policy {
+            GapPolicy::Ignore => {},
+            GapPolicy::Fill => {
+              candles.extend((1..=missing as i32).map(|n| self.flat_candle(last, n)))
+            },
+            GapPolicy::Split => segment_starts.push(candle.open_time),
+          }
+        }
+      }
+      self.last = Some(candle.clone());
+      candles.push(candle);
+    }
+    *page = candles;
+    segment_starts
+  }
+
+  fn missing_between(&self, last: DateTime<Utc>, next: DateTime<Utc>) -> usize {
+    let steps = (next - last).num_seconds() / self.interval.num_seconds().max(1);
+    steps.saturating_sub(1).max(0) as usize
+  }
+
+  /// Candle `n` intervals after `last`, at its close.
+  fn flat_candle(&self, last: &Candle, n: i32) -> Candle {
+    let shift = self.interval * n;
+    Candle {
+      open_time: last.open_time + shift,
+      close_time: last.close_time + shift,
+      open: last.close,
+      high: last.close,
+      low: last.close,
+      close: last.close,
+      volume: 0.0,
+      trade_count: 0,
+    }
+  }
+}
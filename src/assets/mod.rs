@@ -1,18 +1,23 @@
 pub mod asset_ticker;
 pub mod backtest_ticker;
+pub mod candle_window;
 // pub mod book;
 pub mod error;
+pub mod gaps;
+pub mod synthetic;
 // pub mod routes;
 
-use self::{asset_ticker::KlineEvent, error::AssetError};
+use self::{
+  asset_ticker::KlineEvent, backtest_ticker::TickerOptions, error::AssetError,
+};
 use crate::{
   database::Database,
   exchange::{
     binance_client::{self, BinanceClient},
     error::ExchangeError,
-    BinanceKline,
+    BinanceKline, ExchangeEvent,
   },
-  strategy::Signal,
+  strategy::{ensemble::Ensemble, Signal},
   utils::formatting::{dt_to_readable, timestamp_to_dt},
 };
 use binance_spot_connector_rust::market::klines::KlineInterval;
@@ -21,10 +26,18 @@ use futures::TryFutureExt;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::{sync::Arc, thread::sleep};
-use strum::{Display, EnumString};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 use tokio::sync::{mpsc, Mutex};
 use tracing::info;
 
+/// Interval of candles that are streamed, fetched and stored.
+pub const CANDLE_INTERVAL: KlineInterval = KlineInterval::Minutes1;
+
+/// Time between the open times of consecutive candles of [CANDLE_INTERVAL].
+pub fn candle_interval_duration() -> Duration {
+  Duration::try_minutes(1).unwrap_or_default()
+}
+
 #[derive(
   PartialEq,
   Default,
@@ -38,11 +51,75 @@ use tracing::info;
   Deserialize,
   PartialOrd,
   EnumString,
+  EnumIter,
 )]
 pub enum Pair {
   #[default]
   BTCUSDT,
   ETHBTC,
+  /// Synthetic pairs, with candles only from the synthetic data generator.
+  SYNTH1USDT,
+  SYNTH2USDT,
+  SYNTH3USDT,
+}
+
+impl Pair {
+  pub fn base(&self) -> Asset {
+    match self {
+      Pair::BTCUSDT => Asset::BTC,
+      Pair::ETHBTC => Asset::ETH,
+      Pair::SYNTH1USDT | Pair::SYNTH2USDT | Pair::SYNTH3USDT => Asset::SYNTH,
+    }
+  }
+  /// Asset the pair is priced in, and so the currency of its PnL.
+  pub fn quote(&self) -> Asset {
+    match self {
+      Pair::ETHBTC => Asset::BTC,
+      _ => Asset::USDT,
+    }
+  }
+  /// Decimals of the price filter of the pair on Binance, limit prices are rounded to it.
+  pub fn price_decimals(&self) -> u32 {
+    match self {
+      Pair::ETHBTC => 5,
+      _ => 2,
+    }
+  }
+  /// Not listed on Binance, only backtested.
+  pub fn is_synthetic(&self) -> bool {
+    self.base() == Asset::SYNTH
+  }
+  /// Pair that trades the two assets against each other, either way round.
+  pub fn between(a: Asset, b: Asset) -> Option<Pair> {
+    Pair::iter().find(|pair| {
+      let assets = (pair.base(), pair.quote());
+      assets == (a, b) || assets == (b, a)
+    })
+  }
+}
+
+#[derive(
+  PartialEq,
+  Default,
+  Display,
+  Debug,
+  Hash,
+  Eq,
+  Clone,
+  Copy,
+  Serialize,
+  Deserialize,
+  PartialOrd,
+  Ord,
+  EnumString,
+)]
+pub enum Asset {
+  #[default]
+  USDT,
+  BTC,
+  ETH,
+  /// Base asset of the synthetic pairs, never held.
+  SYNTH,
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
@@ -58,6 +135,23 @@ pub struct MarketEvent {
   pub time: DateTime<Utc>,
   pub pair: Pair,
   pub detail: MarketEventDetail,
+  /// Synthesized from REST polling while the kline stream is down, so candles arrive
+  /// late and without the updates in between.
+  #[serde(default)]
+  pub degraded: bool,
+}
+
+impl MarketEvent {
+  /// Latest price of the pair the event carries.
+  pub fn price(&self) -> f64 {
+    match &self.detail {
+      MarketEventDetail::Trade(trade) => trade.price,
+      MarketEventDetail::Candle(candle) => candle.close,
+      MarketEventDetail::CandleUpdate(candle) => candle.close,
+      MarketEventDetail::BacktestCandle((candle, _)) => candle.close,
+      MarketEventDetail::OrderBookL1(book_l1) => book_l1.volume_weighted_mid_price(),
+    }
+  }
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
@@ -65,6 +159,8 @@ pub enum MarketEventDetail {
   Trade(PublicTrade),
   OrderBookL1(OrderBookL1),
   Candle(Candle),
+  /// Kline update of a candle that has not closed yet.
+  CandleUpdate(Candle),
   BacktestCandle((Candle, Option<Signal>)),
 }
 
@@ -148,7 +244,9 @@ pub struct PublicTrade {
   pub side: Side,
 }
 
-#[derive(Clone, Eq, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+#[derive(
+  Clone, Eq, PartialEq, PartialOrd, Debug, Display, EnumString, Deserialize, Serialize,
+)]
 pub enum Side {
   Buy,
   Sell,
@@ -168,7 +266,7 @@ pub struct MarketFeed {
   database: Arc<Mutex<Database>>,
   last_n_candles: usize,
   pair: Pair,
-  model_name: String,
+  ensemble: Ensemble,
   stream_url: String,
 }
 impl MarketFeed {
@@ -193,7 +291,7 @@ impl MarketFeed {
   //           self.last_n_candles,
   //           50,
   //           self.pair.clone(),
-  //           self.model_name.clone(),
+  //           self.ensemble.clone(),
   //         )
   //         .await?,
   //     )
@@ -207,8 +305,10 @@ impl MarketFeed {
   async fn new_live_feed(
     &self,
     pairs: Vec<Pair>,
-  ) -> Result<mpsc::UnboundedReceiver<MarketEvent>, ExchangeError> {
-    let ticker = asset_ticker::new_ticker(pairs, &self.stream_url).await?;
+  ) -> Result<mpsc::UnboundedReceiver<ExchangeEvent>, ExchangeError> {
+    let binance_client = BinanceClient::new().await?;
+    let ticker =
+      asset_ticker::new_ticker(pairs, &self.stream_url, binance_client, false).await?;
     Ok(ticker)
   }
   async fn new_backtest(
@@ -217,14 +317,14 @@ impl MarketFeed {
     last_n_candles: usize,
     buffer_n_of_candles: usize,
     pair: Pair,
-    model_name: String,
-  ) -> Result<mpsc::UnboundedReceiver<MarketEvent>, AssetError> {
+    ensemble: Ensemble,
+  ) -> Result<mpsc::Receiver<MarketEvent>, AssetError> {
     let ticker = backtest_ticker::new_ticker(
       database,
       last_n_candles,
-      buffer_n_of_candles,
       pair,
-      model_name,
+      ensemble,
+      TickerOptions::new(buffer_n_of_candles),
     )
     .await?;
     Ok(ticker)
@@ -234,7 +334,7 @@ impl MarketFeed {
     database: Arc<Mutex<Database>>,
     last_n_candles: usize,
     pair: Pair,
-    model_name: String,
+    ensemble: Ensemble,
     stream_url: String,
   ) -> Self {
     MarketFeed {
@@ -243,7 +343,7 @@ impl MarketFeed {
       database,
       last_n_candles,
       pair,
-      model_name,
+      ensemble,
       stream_url,
     }
   }
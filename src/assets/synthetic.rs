@@ -0,0 +1,157 @@
+use super::{candle_interval_duration, Candle};
+use chrono::{DateTime, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// Steps simulated within each candle for its high and low.
+const STEPS_PER_CANDLE: usize = 4;
+const MINUTES_PER_YEAR: f64 = 365.0 * 24.0 * 60.0;
+/// Expected jumps of the jump-diffusion per year, about one a week.
+const JUMPS_PER_YEAR: f64 = 52.0;
+/// Mean and standard deviation of the log size of a jump.
+const JUMP_SIZE: (f64, f64) = (-0.02, 0.05);
+/// Expected candles a regime of the regime-switching model lasts, a day.
+const REGIME_CANDLES: f64 = 1440.0;
+/// Volatility of the calm and the turbulent regime, as a multiple of the volatility.
+const REGIME_VOLATILITY: (f64, f64) = (0.5, 2.0);
+
+/// Model of a synthetic price path.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum PathModel {
+  /// Geometric Brownian motion, log returns normal with the drift and volatility.
+  #[default]
+  Gbm,
+  /// GBM with lognormal jumps, mostly down, at random times.
+  JumpDiffusion,
+  /// GBM that switches at random between a calm and a turbulent regime, the drift
+  /// turning negative in the turbulent one.
+  RegimeSwitching,
+}
+
+/// Candles of a synthetic price path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntheticSeries {
+  pub model: PathModel,
+  /// Open time of the first candle.
+  pub start: DateTime<Utc>,
+  pub candles: usize,
+  pub start_price: f64,
+  /// Annual drift and volatility of the log returns.
+  pub drift: f64,
+  pub volatility: f64,
+  /// The same seed gives the same candles.
+  pub seed: u64,
+}
+
+impl SyntheticSeries {
+  pub fn generate(&self) -> Vec<Candle> {
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let dt = candle_interval_duration().num_minutes() as f64
+      / MINUTES_PER_YEAR
+      / STEPS_PER_CANDLE as f64;
+    let mut turbulent = false;
+    let mut price = self.start_price;
+    let mut candles = Vec::with_capacity(self.candles);
+    for i in 0..self.candles {
+      if self.model == PathModel::RegimeSwitching
+        && rng.gen::<f64>() < 1.0 / REGIME_CANDLES
+      {
+        turbulent = !turbulent;
+      }
+      let (drift, volatility) = match (self.model, turbulent) {
+        (PathModel::RegimeSwitching, false) => {
+          (self.drift, self.volatility * REGIME_VOLATILITY.0)
+        },
+        (PathModel::RegimeSwitching, true) => {
+          (-self.drift.abs(), self.volatility * REGIME_VOLATILITY.1)
+        },
+        _ => (self.drift, self.volatility),
+      };
+      let open = price;
+      let (mut high, mut low) = (open, open);
+      let mut moved = 0.0;
+      for _ in 0..STEPS_PER_CANDLE {
+        let z: f64 = rng.sample(StandardNormal);
+        let mut log_return =
+          (drift - volatility.powi(2) / 2.0) * dt + volatility * dt.sqrt() * z;
+        let jumps = self.model == PathModel::JumpDiffusion;
+        if jumps && rng.gen::<f64>() < JUMPS_PER_YEAR * dt {
+          let jump: f64 = rng.sample(StandardNormal);
+          log_return += JUMP_SIZE.0 + JUMP_SIZE.1 * jump;
+        }
+        price *= log_return.exp();
+        high = high.max(price);
+        low = low.min(price);
+        moved += z.abs();
+      }
+      // Busier candles move more
+      let volume = 10.0 * (1.0 + moved / STEPS_PER_CANDLE as f64);
+      let open_time = self.start + candle_interval_duration() * i as i32;
+      candles.push(Candle {
+        open_time,
+        close_time: open_time + candle_interval_duration()
+          - chrono::Duration::try_milliseconds(1).unwrap_or_default(),
+        open,
+        high,
+        low,
+        close: price,
+        volume,
+        trade_count: (volume * 10.0) as i64,
+      });
+    }
+    candles
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+  use strum::IntoEnumIterator;
+
+  #[test]
+  fn generates_continuous_candles_by_seed() {
+    let series = SyntheticSeries {
+      model: PathModel::Gbm,
+      start: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+      candles: 2000,
+      start_price: 100.0,
+      drift: 0.0,
+      volatility: 0.8,
+      seed: 7,
+    };
+    for model in PathModel::iter() {
+      let series = SyntheticSeries { model, ..series.clone() };
+      let candles = series.generate();
+      assert_eq!(candles, series.generate());
+      assert_eq!(candles.len(), 2000);
+      assert_eq!(candles[0].open, 100.0);
+      for pair in candles.windows(2) {
+        assert_eq!(pair[1].open, pair[0].close);
+        assert_eq!(pair[1].open_time - pair[0].open_time, candle_interval_duration());
+      }
+      for candle in &candles {
+        assert!(candle.low > 0.0 && candle.low <= candle.open.min(candle.close));
+        assert!(candle.high >= candle.open.max(candle.close));
+      }
+    }
+    let other_seed = SyntheticSeries { seed: 8, ..series.clone() };
+    assert_ne!(series.generate(), other_seed.generate());
+  }
+}
@@ -1,8 +1,14 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use uuid::Uuid;
 
-use crate::utils::version;
+use crate::{
+  assets::{gaps::GapPolicy, synthetic::PathModel, Pair},
+  trading::stress::Scenario,
+  utils::version,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
@@ -18,4 +24,189 @@ pub struct Cli {
     default_value_t = 60.0
   )]
   pub frame_rate: f64,
+
+  #[arg(
+    long,
+    value_name = "FILE",
+    help = "Replay a recorded session through a trader with stub execution and exit"
+  )]
+  pub replay: Option<PathBuf>,
+
+  #[arg(
+    long,
+    value_name = "NAME",
+    help = "Use the config profile in env.<NAME>.toml instead of env.toml"
+  )]
+  pub profile: Option<String>,
+
+  #[arg(
+    long,
+    value_name = "DIR",
+    help = "Keep the database, models and sessions here and the config in DIR/.config"
+  )]
+  pub data_dir: Option<PathBuf>,
+
+  #[arg(
+    long,
+    value_name = "FILE",
+    help = "Use this SQLite file instead of the database of the config profile"
+  )]
+  pub db: Option<PathBuf>,
+
+  #[command(subcommand)]
+  pub command: Option<Commands>,
+}
+
+/// Run without the TUI and exit.
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+  /// Fetch candles of a pair from Binance into the database
+  Fetch {
+    #[arg(long)]
+    pair: Pair,
+    /// Days of history to fetch, up to now
+    #[arg(long, default_value_t = 30)]
+    days: i64,
+  },
+  /// List or generate models
+  Models {
+    #[command(subcommand)]
+    command: ModelsCommands,
+  },
+  /// Backtest generated models on the candles in the database
+  Backtest {
+    #[arg(long)]
+    pair: Pair,
+    #[arg(
+      long = "model",
+      value_name = "NAME",
+      required = true,
+      help = "Generated model to run, repeat for an ensemble"
+    )]
+    models: Vec<String>,
+    /// Run the models with a module in the strategies directory
+    #[arg(long, value_name = "NAME")]
+    strategy: Option<String>,
+    /// Backtest on this many of the latest candles
+    #[arg(long, default_value_t = 1440)]
+    last_n_candles: usize,
+    #[arg(long, default_value_t = 1000.0)]
+    starting_equity: f64,
+    #[arg(long, default_value_t = 0.0)]
+    exchange_fee: f64,
+    /// Backtest on the candles this model was trained on instead of the latest ones
+    #[arg(long, value_name = "MODEL")]
+    dataset: Option<String>,
+    /// Missing candles: ignore them, fill them with flat candles or split the backtest
+    #[arg(long, default_value_t = GapPolicy::Ignore)]
+    gaps: GapPolicy,
+    /// Replay the aggregate trades recorded with `record_trades` within each candle
+    #[arg(long)]
+    trades: bool,
+  },
+  /// Backtest generated models on the latest candles of a pair with shocks applied
+  StressTest {
+    #[arg(long)]
+    pair: Pair,
+    #[arg(
+      long = "model",
+      value_name = "NAME",
+      required = true,
+      help = "Generated model to run, repeat for an ensemble"
+    )]
+    models: Vec<String>,
+    /// Run the models with a module in the strategies directory
+    #[arg(long, value_name = "NAME")]
+    strategy: Option<String>,
+    #[arg(
+      long = "scenario",
+      value_name = "NAME",
+      help = "flash-crash, gap-down or chop, repeat for more, all by default"
+    )]
+    scenarios: Vec<Scenario>,
+    /// Shock this many of the latest candles
+    #[arg(long, default_value_t = 1440)]
+    last_n_candles: usize,
+    #[arg(long, default_value_t = 1000.0)]
+    starting_equity: f64,
+    #[arg(long, default_value_t = 0.0)]
+    exchange_fee: f64,
+    /// Trade USD-M futures with this leverage instead of spot
+    #[arg(long)]
+    leverage: Option<u32>,
+  },
+  /// Export the closed candles of a pair to CSV
+  Export {
+    #[arg(long)]
+    pair: Pair,
+    /// Directory of the `<pair>_<interval>.csv` file, which must not exist yet
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+  },
+  /// Export the exited trades of a session to CSV, with the equity before and after each
+  ExportTrades {
+    /// Id of the session, as listed on the Sessions screen
+    #[arg(long)]
+    session: Uuid,
+    /// Directory of the `<session>_trades.csv` file, which must not exist yet
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+  },
+  /// Store the account's trades of a pair on Binance as a session with its statistics
+  ImportTrades {
+    #[arg(long)]
+    pair: Pair,
+    /// First day of the trades, e.g. 2024-01-31
+    #[arg(long)]
+    from: NaiveDate,
+    /// Day after the last trade, today by default
+    #[arg(long)]
+    to: Option<NaiveDate>,
+    /// Equity of the pair's quote asset at the start, for returns and drawdown
+    #[arg(long, default_value_t = 1000.0)]
+    starting_equity: f64,
+  },
+  /// Generate the candles of a synthetic pair, replacing those stored for its times
+  Synthesize {
+    /// One of the SYNTH pairs
+    #[arg(long)]
+    pair: Pair,
+    #[arg(long, default_value_t = PathModel::Gbm)]
+    model: PathModel,
+    /// Days of candles, up to now
+    #[arg(long, default_value_t = 30)]
+    days: i64,
+    #[arg(long, default_value_t = 30000.0)]
+    start_price: f64,
+    /// Annual drift of the log returns
+    #[arg(long, default_value_t = 0.0)]
+    drift: f64,
+    /// Annual volatility of the log returns
+    #[arg(long, default_value_t = 0.6)]
+    volatility: f64,
+    /// Random by default, the same seed generates the same candles
+    #[arg(long)]
+    seed: Option<u64>,
+  },
+  /// Delete candles the retention policy of the config no longer keeps
+  Prune,
+  /// Send a request to the control socket of a running instance
+  Control {
+    /// JSON request, e.g. '{"command": "status"}'
+    request: String,
+  },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModelsCommands {
+  /// List generated models, newest first
+  List,
+  /// Generate a new model from the candles of a pair
+  New {
+    #[arg(long)]
+    pair: Pair,
+    /// Train with the `train` function of a module in the strategies directory
+    #[arg(long, value_name = "NAME")]
+    strategy: Option<String>,
+  },
 }
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use std::{
+  fmt::Debug,
+  sync::{Arc, RwLock},
+};
+
+/// Source of the current time for trading. Live sessions use the wall clock, backtests
+/// and replays a clock driven by candle time, so every timestamp of a session (orders,
+/// fills, balances, statistics) is on the same timeline.
+pub trait Clock: Debug + Send + Sync {
+  fn now(&self) -> DateTime<Utc>;
+
+  /// Called with the time of every market event the session handles.
+  fn advance(&self, _time: DateTime<Utc>) {}
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+  fn now(&self) -> DateTime<Utc> {
+    Utc::now()
+  }
+}
+
+/// Time of the latest market event, never moving backwards. Until the first one it
+/// reads the wall clock, unless started at a known time.
+#[derive(Debug, Default)]
+pub struct SimulatedClock {
+  time: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl SimulatedClock {
+  pub fn starting_at(start: DateTime<Utc>) -> Self {
+    Self { time: RwLock::new(Some(start)) }
+  }
+}
+
+impl Clock for SimulatedClock {
+  fn now(&self) -> DateTime<Utc> {
+    self.time.read().unwrap_or_else(|e| e.into_inner()).unwrap_or_else(Utc::now)
+  }
+
+  fn advance(&self, time: DateTime<Utc>) {
+    let mut current = self.time.write().unwrap_or_else(|e| e.into_inner());
+    if current.is_none_or(|current| time > current) {
+      *current = Some(time);
+    }
+  }
+}
+
+pub fn real_clock() -> SharedClock {
+  Arc::new(RealClock)
+}
@@ -0,0 +1,218 @@
+use crate::{
+  app::statistic_config,
+  assets::{
+    candle_interval_duration, synthetic::SyntheticSeries, Pair, CANDLE_INTERVAL,
+  },
+  cli::{Commands, ModelsCommands},
+  config::Config,
+  database::{fetch_exited_positions, retention, Database},
+  exchange::{binance_client::BinanceClient, fetch_candles, futures::FuturesSettings},
+  screens::run_config::CoreConfiguration,
+  statistic::{attribution::trades_csv, StatisticConfig},
+  strategy::{
+    ensemble::{Ensemble, EnsembleMember, VotingMethod},
+    generate_new_model, get_generated_models, plugin,
+  },
+  trading::{
+    backtest::backtest,
+    import::import_trades,
+    params::Market,
+    stress::{stress_test, Scenario},
+  },
+};
+use chrono::{Duration, DurationRound, NaiveTime, Utc};
+use eyre::{eyre, Result};
+use std::{path::PathBuf, sync::Arc};
+use strum::IntoEnumIterator;
+use tokio::sync::Mutex;
+
+/// Runs a CLI subcommand, printing its outcome to stdout.
+pub async fn run(command: Commands) -> Result<()> {
+  match command {
+    Commands::Fetch { pair, days } => {
+      let duration = Duration::try_days(days)
+        .ok_or_else(|| eyre!("Invalid number of days: {}", days))?;
+      let binance_client = Arc::new(BinanceClient::new().await?);
+      let candles = fetch_candles(duration, pair, binance_client).await?;
+      let n_candles = candles.len();
+      Database::new().await?.add_candles(pair, candles).await?;
+      println!("Fetched {} candles of {}.", n_candles, pair);
+    },
+    Commands::Models { command: ModelsCommands::List } => {
+      for model in get_generated_models()? {
+        println!("{}", model);
+      }
+    },
+    Commands::Models { command: ModelsCommands::New { pair, strategy } } => {
+      let strategy = find_strategy(strategy)?;
+      let database = Arc::new(Mutex::new(Database::new().await?));
+      generate_new_model(pair, strategy, database).await?;
+      println!("Generated a new model for {}.", pair);
+    },
+    Commands::Backtest {
+      pair,
+      models,
+      strategy,
+      last_n_candles,
+      starting_equity,
+      exchange_fee,
+      dataset,
+      gaps,
+      trades,
+    } => {
+      let members = ensemble_members(&models, strategy)?;
+      let configuration = CoreConfiguration {
+        starting_equity,
+        backtest_last_n_candles: last_n_candles,
+        exchange_fee,
+        pair,
+        ensemble: Ensemble::new(members, VotingMethod::default(), 0.0),
+        dataset,
+        gaps,
+        replay_trades: trades,
+        ..CoreConfiguration::default()
+      };
+      println!("{}", backtest(configuration).await?);
+    },
+    Commands::StressTest {
+      pair,
+      models,
+      strategy,
+      scenarios,
+      last_n_candles,
+      starting_equity,
+      exchange_fee,
+      leverage,
+    } => {
+      let members = ensemble_members(&models, strategy)?;
+      let (market, futures) = match leverage {
+        Some(leverage) => {
+          let futures = FuturesSettings { leverage, ..FuturesSettings::default() };
+          (Market::Futures, futures)
+        },
+        None => (Market::Spot, FuturesSettings::default()),
+      };
+      let configuration = CoreConfiguration {
+        starting_equity,
+        backtest_last_n_candles: last_n_candles,
+        exchange_fee,
+        pair,
+        ensemble: Ensemble::new(members, VotingMethod::default(), 0.0),
+        market,
+        futures,
+        ..CoreConfiguration::default()
+      };
+      let scenarios =
+        if scenarios.is_empty() { Scenario::iter().collect() } else { scenarios };
+      let database = Arc::new(Mutex::new(Database::new().await?));
+      println!("{}", stress_test(configuration, &scenarios, database).await?);
+    },
+    Commands::Export { pair, dir } => {
+      Database::new().await?;
+      let path =
+        retention::export(&dir, &pair.to_string(), &CANDLE_INTERVAL.to_string()).await?;
+      println!("Exported candles of {} to {}.", pair, path.display());
+    },
+    Commands::ExportTrades { session, dir } => {
+      let path = dir.join(format!("{session}_trades.csv"));
+      if path.exists() {
+        return Err(eyre!("{} already exists", path.display()));
+      }
+      Database::new().await?;
+      let positions = fetch_exited_positions(Some(session)).await?;
+      std::fs::write(&path, trades_csv(&positions))?;
+      let n_trades = positions.len();
+      println!("Exported {} trades of {} to {}.", n_trades, session, path.display());
+    },
+    Commands::ImportTrades { pair, from, to, starting_equity } => {
+      let from = from.and_time(NaiveTime::MIN).and_utc();
+      let to = to.map_or_else(Utc::now, |to| to.and_time(NaiveTime::MIN).and_utc());
+      if from >= to {
+        return Err(eyre!("--from has to be before --to"));
+      }
+      let statistic_config =
+        StatisticConfig { starting_equity, created_at: from, ..statistic_config() };
+      println!("{}", import_trades(pair, from, to, statistic_config).await?);
+    },
+    Commands::Synthesize { pair, model, days, start_price, drift, volatility, seed } => {
+      if !pair.is_synthetic() {
+        return Err(eyre!("{} is not a synthetic pair", pair));
+      }
+      let duration = Duration::try_days(days)
+        .ok_or_else(|| eyre!("Invalid number of days: {}", days))?;
+      let end = Utc::now().duration_trunc(candle_interval_duration())?;
+      let series = SyntheticSeries {
+        model,
+        start: end - duration,
+        candles: (duration.num_minutes() / candle_interval_duration().num_minutes())
+          as usize,
+        start_price,
+        drift,
+        volatility,
+        seed: seed.unwrap_or_else(rand::random),
+      };
+      let candles = series.generate();
+      let n_candles = candles.len();
+      Database::new().await?.add_candles(pair, candles).await?;
+      let seed = series.seed;
+      println!("Generated {n_candles} {model} candles of {pair} with seed {seed}.");
+    },
+    Commands::Prune => {
+      let policy = Config::new()?.retention;
+      if policy.is_empty() {
+        println!("No retention policy is configured, nothing to prune.");
+        return Ok(());
+      }
+      Database::new().await?;
+      let n_deleted = retention::prune(&policy, Utc::now()).await?;
+      println!("Pruned {} candles.", n_deleted);
+    },
+    #[cfg(unix)]
+    Commands::Control { request } => {
+      let response = crate::control::send(crate::control::socket_path(), &request).await?;
+      print!("{}", response);
+    },
+    #[cfg(not(unix))]
+    Commands::Control { .. } => {
+      return Err(eyre!("The control socket needs a Unix system"));
+    },
+  }
+  Ok(())
+}
+
+/// Members of an ensemble of the named generated models, run with the strategy module.
+fn ensemble_members(
+  models: &[String],
+  strategy: Option<String>,
+) -> Result<Vec<EnsembleMember>> {
+  let strategy = find_strategy(strategy)?;
+  let generated = get_generated_models()?;
+  models
+    .iter()
+    .map(|name| {
+      let model = generated
+        .iter()
+        .find(|model| model.name() == name)
+        .ok_or_else(|| eyre!("No generated model named {}", name))?
+        .to_model_id();
+      Ok(
+        EnsembleMember::new(
+          model.name,
+          model.ensemble_weight,
+          model.features,
+          model.backend,
+        )
+        .with_strategy(strategy.clone()),
+      )
+    })
+    .collect()
+}
+
+/// Path of the named module in the configured strategies directory.
+fn find_strategy(name: Option<String>) -> Result<Option<PathBuf>> {
+  let Some(name) = name else {
+    return Ok(None);
+  };
+  let config = Config::new()?;
+  Ok(plugin::find(&config.strategies_dir, &name)?.path)
+}
@@ -1,8 +1,12 @@
 pub mod form;
+pub mod glyphs;
 pub mod header;
+pub mod heatmap;
 pub mod list;
+pub mod sparkline;
 pub mod report;
 pub mod style;
+pub mod table;
 
 use eyre::Result;
 use ratatui::prelude::*;
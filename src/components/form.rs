@@ -1,2 +1,4 @@
 pub mod input;
+pub mod multi_select;
 pub mod select;
+pub mod text_input;
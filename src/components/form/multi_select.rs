@@ -0,0 +1,168 @@
+use eyre::Result;
+use ratatui::{
+  prelude::{Constraint, Layout, Margin, Rect},
+  widgets::{Block, Borders, Clear, Paragraph},
+  Frame,
+};
+use std::fmt::Display;
+
+use crate::components::{
+  list::List,
+  style::{default_action_block_style, input_block},
+  ListDisplay,
+};
+
+/// List item wrapper that remembers whether the item was picked.
+#[derive(Clone, Default)]
+pub struct Checkable<T: Clone + ListDisplay + Default> {
+  item: T,
+  checked: bool,
+}
+
+impl<T: Clone + ListDisplay + Default> ListDisplay for Checkable<T> {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    let layout =
+      Layout::horizontal(vec![Constraint::Length(4), Constraint::Min(0)]).split(area);
+    let mark = if self.checked { "[x]" } else { "[ ]" };
+    f.render_widget(Paragraph::new(mark).block(input_block(active, false)), layout[0]);
+    self.item.draw(f, layout[1], active)
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let layout =
+      Layout::horizontal(vec![Constraint::Length(4), Constraint::Min(0)]).split(area);
+    T::default().draw_header(f, layout[1])
+  }
+}
+
+/// Like `Select`, but any number of options can be checked with `toggle_current`.
+#[derive(Default)]
+pub struct MultiSelect<T: Display + Clone + ListDisplay + Default + PartialEq> {
+  label: String,
+  is_active: bool,
+  is_editing: bool,
+  has_error: bool,
+  edit_list: List<Checkable<T>>,
+}
+impl<T: Display + Clone + ListDisplay + Default + PartialEq> MultiSelect<T> {
+  pub fn new(options: Vec<T>, label: Option<String>) -> Self {
+    let mut select = Self {
+      label: label.unwrap_or("".to_string()),
+      is_active: false,
+      is_editing: false,
+      has_error: false,
+      edit_list: List::default(),
+    };
+    select.set_options(options);
+    select
+  }
+
+  pub fn draw_edit(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    if self.is_editing {
+      let layout = Layout::vertical(vec![
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+      ])
+      .split(area);
+      let inner_layout = Layout::horizontal(vec![
+        Constraint::Percentage(10),
+        Constraint::Min(0),
+        Constraint::Percentage(10),
+      ])
+      .split(layout[1]);
+      f.render_widget(Clear, inner_layout[1]);
+      f.render_widget(input_block(false, false), inner_layout[1]);
+      self
+        .edit_list
+        .draw(f, inner_layout[1].inner(&Margin { horizontal: 1, vertical: 0 }))?;
+    }
+
+    Ok(())
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let input_area = Layout::vertical(vec![
+      Constraint::Length(1),
+      Constraint::Length(1),
+      Constraint::Min(0),
+    ])
+    .split(area);
+
+    let inner_input =
+      Layout::horizontal(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(input_area[0]);
+
+    // Render input area bottom line
+    f.render_widget(
+      Block::new()
+        .borders(Borders::BOTTOM)
+        .style(default_action_block_style(false, self.has_error)),
+      input_area[1],
+    );
+
+    // Label
+    f.render_widget(
+      Paragraph::new(self.label.to_string())
+        .block(input_block(self.is_active, self.has_error)),
+      inner_input[0],
+    );
+
+    // Value
+    let values = self.values();
+    let value = if values.is_empty() {
+      "None".to_string()
+    } else {
+      values.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", ")
+    };
+    f.render_widget(
+      Paragraph::new(value).block(input_block(self.is_active, self.has_error)),
+      inner_input[1],
+    );
+
+    Ok(())
+  }
+  pub fn set_active(&mut self, val: bool) {
+    self.is_active = val;
+  }
+  pub fn toggle_edit(&mut self) -> bool {
+    self.is_editing = !self.is_editing;
+    self.is_editing
+  }
+  pub fn is_editing(&self) -> bool {
+    self.is_editing
+  }
+  /// Checks or unchecks the highlighted option.
+  pub fn toggle_current(&mut self) {
+    if let Some(current) = self.edit_list.selected_mut() {
+      current.checked = !current.checked;
+    }
+  }
+  pub fn set_error(&mut self) {
+    self.has_error = true;
+  }
+  pub fn values(&self) -> Vec<T> {
+    self
+      .edit_list
+      .items()
+      .iter()
+      .filter(|option| option.checked)
+      .map(|option| option.item.clone())
+      .collect()
+  }
+  pub fn edit_next(&mut self) {
+    self.edit_list.next();
+  }
+  pub fn edit_previous(&mut self) {
+    self.edit_list.previous();
+  }
+  /// Replaces the options, keeping checked whatever was checked before.
+  pub fn set_options(&mut self, items: Vec<T>) {
+    let checked = self.values();
+    self.edit_list.update_items(
+      items
+        .into_iter()
+        .map(|item| Checkable { checked: checked.contains(&item), item })
+        .collect(),
+    );
+  }
+}
@@ -9,13 +9,21 @@ use std::fmt::Display;
 use uuid::Uuid;
 
 use crate::{
-  assets::Pair,
+  assets::{gaps::GapPolicy, Pair},
   components::{
     list::List,
     style::{default_action_block_style, input_block, stylized_block},
     ListDisplay,
   },
-  strategy::ModelId,
+  exchange::{
+    account::AccountName,
+    futures::{MarginType, PositionMode},
+  },
+  portfolio::balance::EquitySource,
+  strategy::{
+    ensemble::VotingMethod, plugin::StrategyPlugin, ModelId, SignalEvaluation,
+  },
+  trading::params::{Market, OrderType, TimeInForce, TrailingStops},
 };
 
 impl ListDisplay for ModelId {
@@ -45,6 +53,19 @@ impl ListDisplay for ModelId {
   }
 }
 
+impl ListDisplay for StrategyPlugin {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
 impl ListDisplay for Pair {
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
     f.render_widget(
@@ -58,6 +79,175 @@ impl ListDisplay for Pair {
   }
 }
 
+impl ListDisplay for VotingMethod {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for GapPolicy {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for EquitySource {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for AccountName {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for TrailingStops {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for OrderType {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for TimeInForce {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for Market {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for MarginType {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for PositionMode {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for bool {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for f64 {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
+impl ListDisplay for SignalEvaluation {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    f.render_widget(
+      Paragraph::new(self.to_string()).block(input_block(active, false)),
+      area,
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    Ok(())
+  }
+}
+
 #[derive(Default)]
 pub struct Select<T: Display + Clone + ListDisplay + Default> {
   label: String,
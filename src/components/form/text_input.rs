@@ -0,0 +1,89 @@
+use eyre::Result;
+use ratatui::{
+  prelude::{Constraint, Layout, Rect},
+  widgets::{Block, Borders, Paragraph},
+  Frame,
+};
+
+use crate::components::style::{default_action_block_style, input_block};
+
+/// Like `Input`, but for free text typed while the field is being edited.
+#[derive(Default)]
+pub struct TextInput {
+  label: String,
+  value: String,
+  max_length: usize,
+  is_active: bool,
+  is_editing: bool,
+}
+impl TextInput {
+  pub fn new(label: Option<String>, max_length: usize) -> Self {
+    Self {
+      label: label.unwrap_or("".to_string()),
+      value: String::new(),
+      max_length,
+      is_active: false,
+      is_editing: false,
+    }
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let input_area = Layout::vertical(vec![
+      Constraint::Length(1),
+      Constraint::Length(1),
+      Constraint::Min(0),
+    ])
+    .split(area);
+
+    let inner_input =
+      Layout::horizontal(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(input_area[0]);
+
+    // Render input area bottom line
+    f.render_widget(
+      Block::new()
+        .borders(Borders::BOTTOM)
+        .style(default_action_block_style(false, false)),
+      input_area[1],
+    );
+
+    // Label
+    f.render_widget(
+      Paragraph::new(self.label.to_string()).block(input_block(self.is_active, false)),
+      inner_input[0],
+    );
+
+    // Value, with a cursor while typing
+    let value =
+      if self.is_editing { format!("{}_", self.value) } else { self.value.clone() };
+    f.render_widget(
+      Paragraph::new(value).block(input_block(self.is_active, false)),
+      inner_input[1],
+    );
+
+    Ok(())
+  }
+  pub fn set_active(&mut self, val: bool) {
+    self.is_active = val;
+  }
+  pub fn toggle_edit(&mut self) -> bool {
+    self.is_editing = !self.is_editing;
+    self.is_editing
+  }
+  pub fn is_editing(&self) -> bool {
+    self.is_editing
+  }
+  /// Appends a typed character, up to the maximum length.
+  pub fn push(&mut self, c: char) {
+    if self.value.chars().count() < self.max_length && !c.is_control() {
+      self.value.push(c);
+    }
+  }
+  pub fn pop(&mut self) {
+    self.value.pop();
+  }
+  /// Typed text without surrounding whitespace.
+  pub fn value(&self) -> String {
+    self.value.trim().to_string()
+  }
+}
@@ -0,0 +1,142 @@
+use ratatui::buffer::Buffer;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Characters of the screens, picked with `charset` in the config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Charset {
+  /// Unicode, unless the locale or the terminal cannot show it.
+  #[default]
+  Auto,
+  Unicode,
+  /// Plain ASCII, for terminals that garble box drawing, blocks and symbols.
+  Ascii,
+}
+
+pub struct Glyphs {
+  pub logo: &'static str,
+  /// Levels of `MiniChart` from the lowest, the last one fills the cell.
+  pub sparkline: [&'static str; 8],
+  pub ellipsis: &'static str,
+  /// Between the screens of the breadcrumbs.
+  pub breadcrumb: &'static str,
+  pub bitcoin: &'static str,
+  /// Keys that move between columns and values.
+  pub left_right: &'static str,
+  pub micro: &'static str,
+  /// Borders and anything else left outside ASCII are replaced after drawing.
+  pub ascii: bool,
+}
+
+pub static UNICODE_GLYPHS: Glyphs = Glyphs {
+  logo: "╔╦╗╔═╗╔═╗╦ ╦╔═╗╔╦╗╔═╗╦═╗
+║║║║╣ ╚═╗╠═╣║╣  ║ ╠═╣╠╦╝
+╩ ╩╚═╝╚═╝╩ ╩╚═╝ ╩ ╩ ╩╩╚═",
+  sparkline: ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"],
+  ellipsis: "…",
+  breadcrumb: " › ",
+  bitcoin: "₿",
+  left_right: "←/→",
+  micro: "µ",
+  ascii: false,
+};
+
+pub static ASCII_GLYPHS: Glyphs = Glyphs {
+  logo: "+-+-+-+-+-+-+-+-+
+|M|E|S|H|E|T|A|R|
++-+-+-+-+-+-+-+-+",
+  sparkline: ["_", ".", ",", "-", "~", "=", "*", "#"],
+  ellipsis: "...",
+  breadcrumb: " > ",
+  bitcoin: "BTC",
+  left_right: "left/right",
+  micro: "u",
+  ascii: true,
+};
+
+static GLYPHS: OnceLock<&'static Glyphs> = OnceLock::new();
+
+/// Sets the characters of every screen, has no effect once set.
+pub fn set_charset(charset: Charset) {
+  let glyphs = match charset {
+    Charset::Auto if supports_unicode(|name| std::env::var(name).ok()) => &UNICODE_GLYPHS,
+    Charset::Auto | Charset::Ascii => &ASCII_GLYPHS,
+    Charset::Unicode => &UNICODE_GLYPHS,
+  };
+  let _ = GLYPHS.set(glyphs);
+}
+
+pub fn glyphs() -> &'static Glyphs {
+  GLYPHS.get().copied().unwrap_or(&UNICODE_GLYPHS)
+}
+
+/// Whether the terminal shows Unicode, read from the environment: the first of `LC_ALL`,
+/// `LC_CTYPE` and `LANG` that is set names a UTF-8 locale and `TERM` is not the Linux
+/// console or a dumb terminal. With no locale set, as on Windows, it is assumed to.
+fn supports_unicode(var: impl Fn(&str) -> Option<String>) -> bool {
+  if var("TERM").is_some_and(|term| term == "linux" || term == "dumb") {
+    return false;
+  }
+  let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+    .into_iter()
+    .filter_map(&var)
+    .find(|value| !value.is_empty());
+  locale.is_none_or(|locale| {
+    let locale = locale.to_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+  })
+}
+
+/// Replaces what is left outside ASCII in the buffer, lines of borders with `-`, `|` and
+/// `+` and anything else with `?`.
+pub fn to_ascii(buf: &mut Buffer) {
+  for cell in buf.content.iter_mut().filter(|cell| !cell.symbol().is_ascii()) {
+    let symbol = match cell.symbol().chars().next().unwrap_or(' ') {
+      '─' | '━' | '═' | '╌' | '┄' => "-",
+      '│' | '┃' | '║' | '╎' | '┆' => "|",
+      '\u{2500}'..='\u{257F}' => "+",
+      '\u{2580}'..='\u{259F}' => "#",
+      '…' => ".",
+      _ => "?",
+    };
+    cell.set_symbol(symbol);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders},
+  };
+
+  fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    move |name| {
+      vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+    }
+  }
+
+  #[test]
+  fn detects_unicode_from_the_locale() {
+    assert!(supports_unicode(env(&[])));
+    assert!(supports_unicode(env(&[("LANG", "en_US.UTF-8")])));
+    assert!(supports_unicode(env(&[("LC_ALL", "sl_SI.utf8"), ("LANG", "C")])));
+    assert!(!supports_unicode(env(&[("LANG", "C")])));
+    assert!(!supports_unicode(env(&[("LC_CTYPE", "POSIX"), ("LANG", "en_US.UTF-8")])));
+    assert!(!supports_unicode(env(&[("LANG", "en_US.UTF-8"), ("TERM", "linux")])));
+  }
+
+  #[test]
+  fn replaces_borders_and_symbols() {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 5, 3));
+    Block::default()
+      .borders(Borders::ALL)
+      .border_type(BorderType::Rounded)
+      .render(buf.area, &mut buf);
+    buf.set_string(1, 1, "₿…", Style::default());
+    to_ascii(&mut buf);
+    assert_eq!(buf, Buffer::with_lines(vec!["+---+", "|?. |", "+---+"]));
+  }
+}
@@ -1,19 +1,41 @@
-use crate::utils::formatting::time_ago;
+use super::{glyphs::glyphs, sparkline::MiniChart, style::theme};
+use crate::{
+  assets::Asset,
+  core::margin::MarginLevel,
+  exchange::{account::AccountName, futures::MarginHealth},
+  database::snapshot::SessionPnl,
+  utils::formatting::{format_amount, format_signed_number, time_ago},
+};
 use chrono::{DateTime, Utc};
 use eyre::Result;
 use ratatui::{
   layout::{Alignment, Constraint, Layout, Margin, Rect},
-  widgets::Paragraph,
+  style::Style,
+  widgets::{Gauge, Paragraph},
   Frame,
 };
-use std::default;
+use std::{collections::VecDeque, default};
+
+/// Number of past USDT valuations charted in the header.
+const VALUATION_HISTORY_LEN: usize = 120;
 
 #[derive(Default)]
 pub struct MeshetarHeader {
   btc_valuation: f64,
   usdt_valuation: f64,
+  /// Whose balances are valued.
+  account: AccountName,
   last_update: Option<DateTime<Utc>>,
   is_testnet: bool,
+  usdt_history: VecDeque<f64>,
+  /// Of the running session.
+  session_pnl: Option<SessionPnl>,
+  /// Of the running futures session.
+  margin: Option<(MarginHealth, MarginLevel)>,
+  /// Changed since the last draw.
+  dirty: bool,
+  /// Age of the last update as it was drawn.
+  drawn_ago: String,
 }
 
 impl MeshetarHeader {
@@ -23,16 +45,52 @@ impl MeshetarHeader {
   pub fn last_updated(&self) -> Option<DateTime<Utc>> {
     self.last_update.clone()
   }
-  pub fn update(&mut self, btc_valuation: f64, usdt_valuation: f64) {
+  pub fn account(&self) -> &AccountName {
+    &self.account
+  }
+  pub fn update(
+    &mut self,
+    account: AccountName,
+    btc_valuation: f64,
+    usdt_valuation: f64,
+  ) {
+    if account != self.account {
+      self.usdt_history.clear();
+    }
+    self.account = account;
     self.btc_valuation = btc_valuation;
     self.usdt_valuation = usdt_valuation;
     self.last_update = Some(Utc::now());
+    if self.usdt_history.len() == VALUATION_HISTORY_LEN {
+      self.usdt_history.pop_front();
+    }
+    self.usdt_history.push_back(usdt_valuation);
+    self.dirty = true;
+  }
+  pub fn set_session_pnl(&mut self, session_pnl: Option<SessionPnl>) {
+    self.dirty |= self.session_pnl != session_pnl;
+    self.session_pnl = session_pnl;
+  }
+  pub fn set_margin_health(&mut self, margin: Option<(MarginHealth, MarginLevel)>) {
+    self.dirty |= self.margin != margin;
+    self.margin = margin;
+  }
+  /// Whether drawing the header again would change it.
+  pub fn is_dirty(&self) -> bool {
+    self.dirty || self.drawn_ago != time_ago(self.last_update.unwrap_or_default())
   }
   pub fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let pnl_width = if self.session_pnl.is_some() { 30 } else { 0 };
+    let margin_width = if self.margin.is_some() { 18 } else { 0 };
     let layout = Layout::horizontal(vec![
       Constraint::Length(26),
       Constraint::Length(1),
       Constraint::Min(0),
+      Constraint::Length(1),
+      Constraint::Length(pnl_width),
+      Constraint::Length(24),
+      Constraint::Length(1),
+      Constraint::Length(margin_width),
     ])
     .split(area);
     let info_layout = Layout::vertical(vec![
@@ -40,14 +98,60 @@ impl MeshetarHeader {
       Constraint::Length(1),
       Constraint::Length(1),
     ])
-    .split(layout[2]);
+    .split(layout[5]);
+    if let Some(pnl) = &self.session_pnl {
+      let pnl_layout =
+        Layout::vertical(vec![Constraint::Length(1); 2]).split(layout[4]);
+      let rows = [("Realised", pnl.realised), ("Unrealised", pnl.unrealised)];
+      for ((label, value), area) in rows.into_iter().zip(pnl_layout.iter()) {
+        let style = if value < 0.0 {
+          Style::default().fg(theme().negative)
+        } else {
+          Style::default()
+        };
+        let text = format!(
+          "{label} {} {:+.2}%",
+          format_signed_number(value),
+          pnl.percentage(value)
+        );
+        let paragraph = Paragraph::new(text).style(style).alignment(Alignment::Right);
+        f.render_widget(paragraph, *area);
+      }
+    }
+    if let Some((health, level)) = &self.margin {
+      let margin_layout =
+        Layout::vertical(vec![Constraint::Length(1); 2]).split(layout[7]);
+      let color = match level {
+        MarginLevel::Healthy => theme().positive,
+        MarginLevel::Warning => theme().warning,
+        MarginLevel::Critical => theme().negative,
+      };
+      let ratio = health.ratio().clamp(0.0, 1.0);
+      let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(color))
+        .ratio(ratio)
+        .label(format!("Margin {:.0}%", ratio * 100.0));
+      f.render_widget(gauge, margin_layout[0]);
+      f.render_widget(
+        Paragraph::new(level.to_string()).alignment(Alignment::Right),
+        margin_layout[1],
+      );
+    }
     f.render_widget(logo(), layout[0].inner(&Margin { horizontal: 1, vertical: 0 }));
+    let history: Vec<f64> = self.usdt_history.iter().copied().collect();
+    f.render_widget(
+      MiniChart::new(&history).style(Style::default().fg(theme().text_dimmed)),
+      layout[2],
+    );
+    let valuation = format_amount(self.btc_valuation, Asset::BTC);
     f.render_widget(
-      Paragraph::new(self.btc_valuation.to_string() + " ₿").alignment(Alignment::Right),
+      Paragraph::new(format!("{} {}", valuation, glyphs().bitcoin))
+        .alignment(Alignment::Right),
       info_layout[0],
     );
     f.render_widget(
-      Paragraph::new(self.usdt_valuation.to_string() + " $").alignment(Alignment::Right),
+      Paragraph::new(format_amount(self.usdt_valuation, Asset::USDT) + " $")
+        .alignment(Alignment::Right),
       info_layout[1],
     );
 
@@ -57,8 +161,11 @@ impl MeshetarHeader {
       DateTime::default()
     };
 
+    self.drawn_ago = time_ago(time);
+    self.dirty = false;
+    f.render_widget(Paragraph::new(self.account.to_string()), info_layout[2]);
     f.render_widget(
-      Paragraph::new(time_ago(time)).alignment(Alignment::Right),
+      Paragraph::new(self.drawn_ago.as_str()).alignment(Alignment::Right),
       info_layout[2],
     );
     Ok(())
@@ -66,8 +173,5 @@ impl MeshetarHeader {
 }
 
 pub fn logo<'a>() -> Paragraph<'a> {
-  let title = r#"╔╦╗╔═╗╔═╗╦ ╦╔═╗╔╦╗╔═╗╦═╗
-║║║║╣ ╚═╗╠═╣║╣  ║ ╠═╣╠╦╝
-╩ ╩╚═╝╚═╝╩ ╩╚═╝ ╩ ╩ ╩╩╚═"#;
-  Paragraph::new(title).alignment(Alignment::Center)
+  Paragraph::new(glyphs().logo).alignment(Alignment::Center)
 }
@@ -0,0 +1,129 @@
+use super::style::theme;
+use crate::statistic::{
+  correlation::CorrelationMatrix,
+  heatmap::{PnLHeatmap, HOURS, WEEKDAYS},
+};
+use ratatui::{prelude::*, widgets::Widget};
+
+const LABEL_WIDTH: u16 = 4;
+
+/// Weekday by hour grid of a [`PnLHeatmap`]. Profitable buckets are in the theme's
+/// positive color, losing ones in its negative color, brighter the further they are from
+/// zero. Without colors they are marked `+` and `-`. Buckets without trades stay blank.
+pub struct PnLHeatmapWidget<'a> {
+  heatmap: &'a PnLHeatmap,
+}
+
+impl<'a> PnLHeatmapWidget<'a> {
+  pub fn new(heatmap: &'a PnLHeatmap) -> Self {
+    Self { heatmap }
+  }
+}
+
+fn bucket_color(profit_loss: f64, max_abs: f64) -> Color {
+  let intensity =
+    if max_abs > 0.0 { (profit_loss.abs() / max_abs).min(1.0) } else { 0.0 };
+  let (r, g, b) =
+    if profit_loss >= 0.0 { theme().heat_positive } else { theme().heat_negative };
+  let scale = |channel: u8| (channel as f64 * (60.0 + 195.0 * intensity) / 255.0) as u8;
+  Color::Rgb(scale(r), scale(g), scale(b))
+}
+
+impl Widget for PnLHeatmapWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let cell_width = (area.width.saturating_sub(LABEL_WIDTH) / HOURS as u16).max(1);
+    let label_style = Style::default().fg(theme().text_dimmed);
+    let max_abs = self.heatmap.max_abs();
+    for hour in (0..HOURS).step_by(3) {
+      let x = area.x + LABEL_WIDTH + hour as u16 * cell_width;
+      if x < area.right() {
+        buf.set_stringn(x, area.y, format!("{hour:02}"), 2, label_style);
+      }
+    }
+    for (row, weekday) in WEEKDAYS.iter().enumerate() {
+      let y = area.y + 1 + row as u16;
+      if y >= area.bottom() {
+        break;
+      }
+      buf.set_stringn(area.x, y, weekday.to_string(), LABEL_WIDTH as usize, label_style);
+      for hour in 0..HOURS {
+        let x = area.x + LABEL_WIDTH + hour as u16 * cell_width;
+        if x >= area.right() {
+          break;
+        }
+        let bucket = self.heatmap.bucket(*weekday, hour);
+        let width = cell_width.min(area.right() - x);
+        if !theme().colored {
+          let mark = match bucket.trades {
+            0 => " ",
+            _ if bucket.profit_loss >= 0.0 => "+",
+            _ => "-",
+          };
+          buf.set_stringn(x, y, mark.repeat(width as usize), width as usize, label_style);
+          continue;
+        }
+        let background = if bucket.trades == 0 {
+          theme().bg_action_field
+        } else {
+          bucket_color(bucket.profit_loss, max_abs)
+        };
+        buf.set_style(Rect::new(x, y, width, 1), Style::default().bg(background));
+      }
+    }
+  }
+}
+
+/// Width of the pair labels and of the cells of a [`CorrelationHeatmapWidget`].
+const PAIR_WIDTH: u16 = 9;
+
+/// Pair by pair grid of a [`CorrelationMatrix`], each cell with its correlation on a
+/// background like the buckets of [`PnLHeatmapWidget`]: positive correlations in the
+/// positive color, negative ones in the negative color. Pairs without enough common
+/// candles show `n/a`.
+pub struct CorrelationHeatmapWidget<'a> {
+  matrix: &'a CorrelationMatrix,
+}
+
+impl<'a> CorrelationHeatmapWidget<'a> {
+  pub fn new(matrix: &'a CorrelationMatrix) -> Self {
+    Self { matrix }
+  }
+}
+
+impl Widget for CorrelationHeatmapWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let label_style = Style::default().fg(theme().text_dimmed);
+    let pairs = &self.matrix.pairs;
+    for (column, pair) in pairs.iter().enumerate() {
+      let x = area.x + PAIR_WIDTH * (column as u16 + 1);
+      if x >= area.right() {
+        break;
+      }
+      buf.set_stringn(x, area.y, pair.to_string(), PAIR_WIDTH as usize - 1, label_style);
+    }
+    for (row, a) in pairs.iter().enumerate() {
+      let y = area.y + 1 + row as u16;
+      if y >= area.bottom() {
+        break;
+      }
+      buf.set_stringn(area.x, y, a.to_string(), PAIR_WIDTH as usize - 1, label_style);
+      for (column, b) in pairs.iter().enumerate() {
+        let x = area.x + PAIR_WIDTH * (column as u16 + 1);
+        if x >= area.right() {
+          break;
+        }
+        let width = (PAIR_WIDTH - 1).min(area.right() - x);
+        let correlation = self.matrix.get(*a, *b);
+        let text = correlation.map_or("n/a".to_string(), |c| format!("{c:+.2}"));
+        let style = match correlation {
+          Some(c) if theme().colored => {
+            Style::default().fg(theme().text).bg(bucket_color(c, 1.0))
+          },
+          _ => label_style,
+        };
+        let width = width as usize;
+        buf.set_stringn(x, y, format!("{text:^width$}"), width, style);
+      }
+    }
+  }
+}
@@ -57,6 +57,18 @@ impl<T: ListDisplay + Clone + Default> List<T> {
     }
   }
 
+  pub fn selected_index(&self) -> Option<usize> {
+    self.selected.filter(|selected| *selected < self.items.len())
+  }
+
+  pub fn selected_mut(&mut self) -> Option<&mut T> {
+    self.selected.and_then(|selected| self.items.get_mut(selected))
+  }
+
+  pub fn items(&self) -> &[T] {
+    &self.items
+  }
+
   pub fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
     let layout = Layout::default()
       .constraints(vec![Constraint::Length(2), Constraint::Min(0)])
@@ -119,6 +131,13 @@ impl<T: Display + Clone + Default> LabelValueItem<T> {
   }
 }
 
+/// "Sharpe ratio: 1.2", as copied to the clipboard.
+impl<T: Display + Clone + Default> Display for LabelValueItem<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.label, self.value)
+  }
+}
+
 impl<T: Display + Clone + Default> ListDisplay for LabelValueItem<T> {
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
     let area =
@@ -0,0 +1,49 @@
+use super::glyphs::glyphs;
+use ratatui::{prelude::*, widgets::Widget};
+
+/// Compact chart of a price or equity series drawn with Unicode blocks, one column per
+/// point. Only the most recent points that fit the width are drawn, scaled between the
+/// lowest and highest of them; taller areas stack blocks for finer resolution.
+pub struct MiniChart<'a> {
+  data: &'a [f64],
+  style: Style,
+}
+
+impl<'a> MiniChart<'a> {
+  pub fn new(data: &'a [f64]) -> Self {
+    Self { data, style: Style::default() }
+  }
+
+  pub fn style(self, style: Style) -> Self {
+    Self { style, ..self }
+  }
+}
+
+impl Widget for MiniChart<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    buf.set_style(area, self.style);
+    if area.is_empty() || self.data.is_empty() {
+      return;
+    }
+    let data = &self.data[self.data.len().saturating_sub(area.width as usize)..];
+    let low = data.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = data.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let blocks = &glyphs().sparkline;
+    let levels = area.height as usize * blocks.len();
+    for (column, value) in data.iter().enumerate() {
+      // A flat series is drawn at half height
+      let ratio = if high > low { (value - low) / (high - low) } else { 0.5 };
+      // Always at least the lowest block, so every point stays visible
+      let filled = ((ratio * (levels - 1) as f64).round() as usize + 1).min(levels);
+      let x = area.x + column as u16;
+      for row in 0..area.height as usize {
+        let row_filled = filled.saturating_sub(row * blocks.len()).min(blocks.len());
+        if row_filled == 0 {
+          break;
+        }
+        let y = area.bottom() - 1 - row as u16;
+        buf.get_mut(x, y).set_symbol(blocks[row_filled - 1]);
+      }
+    }
+  }
+}
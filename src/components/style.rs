@@ -1,10 +1,11 @@
-use std::u8;
+use std::{sync::OnceLock, u8};
 
 use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use ratatui::{
   prelude::{Alignment, Constraint, Direction, Layout, Rect},
   style::{Color, Modifier, Style},
-  widgets::{Block, BorderType, Borders, Padding, Paragraph},
+  widgets::{Block, BorderType, Borders, Padding, Paragraph, Wrap},
   Frame,
 };
 
@@ -26,8 +27,57 @@ pub struct Theme {
   pub text_button: Color,
   pub text_button_selected: Color,
   pub brand: Color,
+  /// Gains and finished work.
+  pub positive: Color,
+  /// Losses and failures.
+  pub negative: Color,
+  /// Stale data, bottlenecks and work in progress.
+  pub warning: Color,
+  /// Text on `warning` backgrounds.
+  pub text_warning: Color,
+  /// Heatmap buckets of the largest gain and loss, fainter ones are scaled down.
+  pub heat_positive: (u8, u8, u8),
+  pub heat_negative: (u8, u8, u8),
+  /// False for `NO_COLOR`, selection is then shown with modifiers only.
+  pub colored: bool,
 }
 
+/// Palette picked with `theme` in the config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+  #[default]
+  Default,
+  /// White on black with bright accents.
+  HighContrast,
+  /// Blue and orange instead of green and red.
+  ColorBlind,
+}
+
+static THEME: OnceLock<&'static Theme> = OnceLock::new();
+
+/// Sets the palette of every screen, has no effect once set. `NO_COLOR` overrides it.
+pub fn set_theme(name: ThemeName) {
+  let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+  let theme = match name {
+    _ if no_color => &NO_COLOR_THEME,
+    ThemeName::Default => &DEFAULT_THEME,
+    ThemeName::HighContrast => &HIGH_CONTRAST_THEME,
+    ThemeName::ColorBlind => &COLOR_BLIND_THEME,
+  };
+  let _ = THEME.set(theme);
+}
+
+pub fn theme() -> &'static Theme {
+  THEME.get().copied().unwrap_or(&DEFAULT_THEME)
+}
+
+/// Smallest terminal the screens are laid out for, smaller ones show a notice instead.
+pub const MIN_TERMINAL_WIDTH: u16 = 60;
+pub const MIN_TERMINAL_HEIGHT: u16 = 20;
+/// Screens narrower than this stack their panels and drop side margins.
+pub const NARROW_WIDTH: u16 = 80;
+
 pub static DEFAULT_THEME: Theme = Theme {
   bg: Color::Indexed(233),
   bg_button: Color::Indexed(10),
@@ -44,8 +94,101 @@ pub static DEFAULT_THEME: Theme = Theme {
   text_button: Color::Indexed(0),
   text_button_selected: Color::Indexed(16),
   brand: Color::Indexed(220),
+  positive: Color::Green,
+  negative: Color::Red,
+  warning: Color::Yellow,
+  text_warning: Color::Black,
+  heat_positive: (0, 255, 0),
+  heat_negative: (255, 0, 0),
+  colored: true,
+};
+
+pub static HIGH_CONTRAST_THEME: Theme = Theme {
+  bg: Color::Black,
+  bg_button: Color::Indexed(250),
+  bg_button_selected: Color::Indexed(226),
+  bg_action_field: Color::Black,
+  bg_action_field_active: Color::Indexed(238),
+  bg_action_field_error: Color::Indexed(196),
+  border: Color::White,
+  border_active: Color::Indexed(226),
+  text_dimmed: Color::Indexed(252),
+  text: Color::White,
+  text_critical: Color::Indexed(203),
+  text_selected: Color::Indexed(226),
+  text_button: Color::Black,
+  text_button_selected: Color::Black,
+  brand: Color::Indexed(226),
+  positive: Color::Indexed(46),
+  negative: Color::Indexed(203),
+  warning: Color::Indexed(226),
+  text_warning: Color::Black,
+  heat_positive: (0, 255, 0),
+  heat_negative: (255, 40, 40),
+  colored: true,
 };
 
+/// Okabe-Ito blue, orange and yellow, told apart with any color vision deficiency.
+pub static COLOR_BLIND_THEME: Theme = Theme {
+  bg: Color::Indexed(233),
+  bg_button: Color::Indexed(32),
+  bg_button_selected: Color::Indexed(38),
+  bg_action_field: Color::Indexed(234),
+  bg_action_field_active: Color::Indexed(236),
+  bg_action_field_error: Color::Indexed(166),
+  border: Color::Indexed(245),
+  border_active: Color::Indexed(245),
+  text_dimmed: Color::Indexed(7),
+  text: Color::Indexed(15),
+  text_critical: Color::Indexed(208),
+  text_selected: Color::Indexed(220),
+  text_button: Color::Indexed(15),
+  text_button_selected: Color::Indexed(16),
+  brand: Color::Indexed(220),
+  positive: Color::Indexed(32),
+  negative: Color::Indexed(208),
+  warning: Color::Indexed(220),
+  text_warning: Color::Black,
+  heat_positive: (0, 114, 178),
+  heat_negative: (230, 159, 0),
+  colored: true,
+};
+
+/// Terminal defaults only, see <https://no-color.org>.
+pub static NO_COLOR_THEME: Theme = Theme {
+  bg: Color::Reset,
+  bg_button: Color::Reset,
+  bg_button_selected: Color::Reset,
+  bg_action_field: Color::Reset,
+  bg_action_field_active: Color::Reset,
+  bg_action_field_error: Color::Reset,
+  border: Color::Reset,
+  border_active: Color::Reset,
+  text_dimmed: Color::Reset,
+  text: Color::Reset,
+  text_critical: Color::Reset,
+  text_selected: Color::Reset,
+  text_button: Color::Reset,
+  text_button_selected: Color::Reset,
+  brand: Color::Reset,
+  positive: Color::Reset,
+  negative: Color::Reset,
+  warning: Color::Reset,
+  text_warning: Color::Reset,
+  heat_positive: (0, 0, 0),
+  heat_negative: (0, 0, 0),
+  colored: false,
+};
+
+/// Reverses selected styles when the theme has no colors to highlight them with.
+fn highlighted(style: Style, selected: bool) -> Style {
+  if selected && !theme().colored {
+    style.add_modifier(Modifier::REVERSED)
+  } else {
+    style
+  }
+}
+
 pub fn stylized_block<'a>(selected: bool) -> Block<'a> {
   let border_style = default_border_style(selected);
   let content_style = default_style(selected);
@@ -57,34 +200,37 @@ pub fn stylized_block<'a>(selected: bool) -> Block<'a> {
 }
 
 pub fn default_style(active: bool) -> Style {
+  let theme = theme();
   if active {
-    Style::default().bg(DEFAULT_THEME.bg).fg(DEFAULT_THEME.text_selected)
+    highlighted(Style::default().bg(theme.bg).fg(theme.text_selected), true)
   } else {
-    Style::default().bg(DEFAULT_THEME.bg).fg(DEFAULT_THEME.text)
+    Style::default().bg(theme.bg).fg(theme.text)
   }
 }
 
 pub fn default_border_style(active: bool) -> Style {
   if active {
-    Style::default().bg(DEFAULT_THEME.bg).fg(DEFAULT_THEME.border_active)
+    Style::default().bg(theme().bg).fg(theme().border_active)
   } else {
-    Style::default().bg(DEFAULT_THEME.bg).fg(DEFAULT_THEME.border)
+    Style::default().bg(theme().bg).fg(theme().border)
   }
 }
 
 pub fn default_action_block_style(active: bool, error: bool) -> Style {
-  let text_style = if active { DEFAULT_THEME.text_selected } else { DEFAULT_THEME.text };
-  if error {
-    Style::default().bg(DEFAULT_THEME.bg_action_field_error).fg(text_style)
+  let theme = theme();
+  let text_style = if active { theme.text_selected } else { theme.text };
+  let style = if error {
+    Style::default().bg(theme.bg_action_field_error).fg(text_style)
   } else if active {
-    Style::default().bg(DEFAULT_THEME.bg_action_field_active).fg(text_style)
+    Style::default().bg(theme.bg_action_field_active).fg(text_style)
   } else {
-    Style::default().bg(DEFAULT_THEME.bg_action_field).fg(text_style)
-  }
+    Style::default().bg(theme.bg_action_field).fg(text_style)
+  };
+  highlighted(style, active)
 }
 
 pub fn header_style() -> Style {
-  Style::default().bg(DEFAULT_THEME.bg).fg(DEFAULT_THEME.brand)
+  Style::default().bg(theme().bg).fg(theme().brand)
 }
 
 pub fn stylized_button<'a>(selected: bool) -> Block<'a> {
@@ -98,15 +244,19 @@ pub fn stylized_button<'a>(selected: bool) -> Block<'a> {
 }
 
 pub fn button_style(selected: bool) -> Style {
+  let theme = theme();
   if selected {
-    Style::default()
-      .bg(DEFAULT_THEME.bg_button_selected)
-      .fg(DEFAULT_THEME.text_button_selected)
-      .add_modifier(Modifier::BOLD)
+    highlighted(
+      Style::default()
+        .bg(theme.bg_button_selected)
+        .fg(theme.text_button_selected)
+        .add_modifier(Modifier::BOLD),
+      true,
+    )
   } else {
     Style::default()
-      .bg(DEFAULT_THEME.bg_button)
-      .fg(DEFAULT_THEME.text_button)
+      .bg(theme.bg_button)
+      .fg(theme.text_button)
       .add_modifier(Modifier::BOLD)
   }
 }
@@ -114,10 +264,10 @@ pub fn button_style(selected: bool) -> Style {
 pub fn button_border_style(selected: bool) -> Style {
   if selected {
     Style::default()
-      .bg(DEFAULT_THEME.bg_button_selected)
-      .fg(DEFAULT_THEME.bg_button_selected)
+      .bg(theme().bg_button_selected)
+      .fg(theme().bg_button_selected)
   } else {
-    Style::default().bg(DEFAULT_THEME.bg_button).fg(DEFAULT_THEME.bg_button)
+    Style::default().bg(theme().bg_button).fg(theme().bg_button)
   }
 }
 
@@ -158,6 +308,15 @@ pub fn button<'a>(text: &'a str, is_selected: bool) -> Paragraph<'a> {
     .block(Block::new().padding(Padding::vertical(1)).style(button_style(is_selected)))
 }
 
+/// Button of an action that is not available, dimmed and never selected.
+pub fn disabled_button(text: &str) -> Paragraph<'_> {
+  Paragraph::new(text).alignment(Alignment::Center).block(
+    Block::new()
+      .padding(Padding::vertical(1))
+      .style(button_style(false).fg(theme().text_dimmed)),
+  )
+}
+
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
 pub fn centered_rect_procentage(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
   let popup_layout = Layout::default()
@@ -180,7 +339,10 @@ pub fn centered_rect_procentage(percent_x: u16, percent_y: u16, r: Rect) -> Rect
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
+/// The rect is cut to `r` when it does not fit.
 pub fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
+  let width = width.min(r.width);
+  let height = height.min(r.height);
   let popup_layout = Layout::default()
     .direction(Direction::Horizontal)
     .constraints([
@@ -199,3 +361,25 @@ pub fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
     ])
     .split(popup_layout[1])[1]
 }
+
+/// Whether the screens fit the terminal.
+pub fn fits_terminal(area: Rect) -> bool {
+  area.width >= MIN_TERMINAL_WIDTH && area.height >= MIN_TERMINAL_HEIGHT
+}
+
+/// Notice drawn over the whole terminal while it is too small for the screens.
+pub fn terminal_too_small(f: &mut Frame<'_>, area: Rect) {
+  let text = format!(
+    "Terminal too small\n{}x{}, needs {}x{}",
+    area.width, area.height, MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT
+  );
+  let notice = centered_rect(area.width, 2, area);
+  f.render_widget(Block::default().style(default_style(false)), area);
+  f.render_widget(
+    Paragraph::new(text)
+      .alignment(Alignment::Center)
+      .wrap(Wrap { trim: true })
+      .style(default_style(true)),
+    notice,
+  );
+}
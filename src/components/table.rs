@@ -0,0 +1,187 @@
+use super::{
+  glyphs::glyphs,
+  sparkline::MiniChart,
+  style::{default_style, theme},
+};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// A line for the cells and an empty one below them, as rows of `List` are drawn.
+const ROW_HEIGHT: u16 = 2;
+
+/// Column of a `Table`, its title and cells are aligned the same way.
+#[derive(Clone, Debug)]
+pub struct Column {
+  title: String,
+  width: Constraint,
+  alignment: Alignment,
+}
+
+impl Column {
+  pub fn new(title: impl Into<String>, width: Constraint) -> Self {
+    Self { title: title.into(), width, alignment: Alignment::Left }
+  }
+
+  /// Aligns to the right, for amounts and durations.
+  pub fn right(self) -> Self {
+    Self { alignment: Alignment::Right, ..self }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+  Text(String, Style),
+  /// Chart of the series, see `MiniChart`.
+  Chart(Vec<f64>, Style),
+}
+
+impl Cell {
+  pub fn styled(text: impl Into<String>, style: Style) -> Self {
+    Self::Text(text.into(), style)
+  }
+}
+
+impl From<String> for Cell {
+  fn from(text: String) -> Self {
+    Self::Text(text, Style::default())
+  }
+}
+
+impl From<&str> for Cell {
+  fn from(text: &str) -> Self {
+    Self::from(text.to_string())
+  }
+}
+
+/// Row of a `Table`, with a cell for each of its columns.
+pub trait TableRow {
+  fn columns() -> Vec<Column>;
+  fn cells(&self) -> Vec<Cell>;
+}
+
+/// Rows laid out in the columns of `T`, under a header that stays in place while they
+/// scroll to the selected one. Text too long for its column ends with an ellipsis.
+pub struct Table<T: TableRow> {
+  items: Vec<T>,
+  selected: Option<usize>,
+  /// First row drawn.
+  offset: usize,
+}
+
+impl<T: TableRow> Default for Table<T> {
+  fn default() -> Self {
+    Self { items: Vec::new(), selected: Some(0), offset: 0 }
+  }
+}
+
+impl<T: TableRow> Table<T> {
+  pub fn update_items(&mut self, items: Vec<T>) {
+    self.items = items;
+  }
+
+  pub fn items(&self) -> &[T] {
+    &self.items
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  pub fn selected(&self) -> Option<&T> {
+    self.selected.and_then(|selected| self.items.get(selected))
+  }
+
+  pub fn next(&mut self) {
+    let last = self.items.len().saturating_sub(1);
+    self.selected = Some(self.selected.map_or(0, |selected| (selected + 1).min(last)));
+  }
+
+  pub fn previous(&mut self) {
+    self.selected = Some(self.selected.unwrap_or(0).saturating_sub(1));
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let columns = T::columns();
+    let layout =
+      Layout::vertical(vec![Constraint::Length(ROW_HEIGHT), Constraint::Min(0)])
+        .split(area);
+    let header_style = Style::default().fg(theme().text_dimmed);
+    let header = columns
+      .iter()
+      .map(|column| Cell::styled(column.title.clone(), header_style))
+      .collect();
+    draw_row(f, layout[0], &columns, header, default_style(false));
+
+    let visible = (layout[1].height / ROW_HEIGHT) as usize;
+    if let Some(selected) = self.selected {
+      if selected < self.offset {
+        self.offset = selected;
+      } else if selected >= self.offset + visible {
+        self.offset = selected + 1 - visible.max(1);
+      }
+    }
+    self.offset = self.offset.min(self.items.len().saturating_sub(visible));
+    for (row, (index, item)) in
+      self.items.iter().enumerate().skip(self.offset).take(visible).enumerate()
+    {
+      let area = Rect {
+        y: layout[1].y + row as u16 * ROW_HEIGHT,
+        height: ROW_HEIGHT,
+        ..layout[1]
+      };
+      let style = default_style(self.selected == Some(index));
+      draw_row(f, area, &columns, item.cells(), style);
+    }
+    Ok(())
+  }
+}
+
+fn draw_row(
+  f: &mut Frame<'_>,
+  area: Rect,
+  columns: &[Column],
+  cells: Vec<Cell>,
+  style: Style,
+) {
+  f.render_widget(Block::default().style(style), area);
+  let line = Rect { height: 1, ..area };
+  let areas =
+    Layout::horizontal(columns.iter().map(|column| column.width)).spacing(1).split(line);
+  for ((column, cell), area) in columns.iter().zip(cells).zip(areas.iter()) {
+    match cell {
+      Cell::Text(text, cell_style) => f.render_widget(
+        Paragraph::new(truncate(&text, area.width))
+          .alignment(column.alignment)
+          .style(style.patch(cell_style)),
+        *area,
+      ),
+      Cell::Chart(values, cell_style) => {
+        f.render_widget(MiniChart::new(&values).style(style.patch(cell_style)), *area)
+      },
+    }
+  }
+}
+
+/// The text cut to the width, ending with an ellipsis if it did not fit.
+fn truncate(text: &str, width: u16) -> String {
+  let width = width as usize;
+  if text.width() <= width {
+    return text.to_string();
+  }
+  let ellipsis = glyphs().ellipsis;
+  let mut truncated = String::new();
+  let mut truncated_width = 0;
+  for c in text.chars() {
+    let c_width = c.width().unwrap_or(0);
+    if truncated_width + c_width + ellipsis.width() > width {
+      break;
+    }
+    truncated_width += c_width;
+    truncated.push(c);
+  }
+  if width >= ellipsis.width() {
+    truncated.push_str(ellipsis);
+  }
+  truncated
+}
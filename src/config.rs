@@ -11,7 +11,21 @@ use serde::{
 };
 use serde_json::Value as JsonValue;
 
-use crate::{action::Action, mode::Mode};
+use crate::{
+  action::Action, assets::candle_window,
+  components::{glyphs::Charset, style::ThemeName},
+  core::alerts::AlertRule,
+  database::retention::RetentionPolicy, exchange::derivatives::DerivativesConfig, paths,
+  exchange::sim::SimConfig,
+  portfolio::{regime::RegimeConfig, risk::CorrelationLimit},
+  core::retraining::RetrainingConfig,
+  core::watchdog::StalenessConfig, core::margin::MarginHealthConfig, mode::Mode,
+  trading::{
+    event_queue::EventQueueConfig, schedule::ScheduleConfig, slicing::SlicingConfig,
+  },
+  utils::{crash::PanicRecovery, formatting::NumberLocale, log_file::LogConfig},
+  watchlist::PriceAlert,
+};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
@@ -31,13 +45,99 @@ pub struct Config {
   pub keybindings: KeyBindings,
   #[serde(default)]
   pub styles: Styles,
+  #[serde(default)]
+  pub retention: RetentionPolicy,
+  #[serde(default)]
+  pub alerts: Vec<AlertRule>,
+  #[serde(default)]
+  pub watchlist: Vec<PriceAlert>,
+  #[serde(default)]
+  pub panic_recovery: PanicRecovery,
+  #[serde(default)]
+  pub staleness: StalenessConfig,
+  #[serde(default)]
+  pub retraining: RetrainingConfig,
+  #[serde(default)]
+  pub trader_queue: EventQueueConfig,
+  #[serde(default)]
+  pub order_slicing: SlicingConfig,
+  #[serde(default)]
+  pub scheduled_backtests: ScheduleConfig,
+  #[serde(default)]
+  pub volatility_regime: RegimeConfig,
+  #[serde(default)]
+  pub correlation_limit: CorrelationLimit,
+  #[serde(default)]
+  pub derivatives: DerivativesConfig,
+  #[serde(default)]
+  pub margin_health: MarginHealthConfig,
+  #[serde(default)]
+  pub sim_exchange: SimConfig,
+  /// Closed candles of the pair kept in memory for the strategy.
+  #[serde(default = "default_candle_window")]
+  pub candle_window: usize,
+  /// Record the events of every session for replay.
+  #[serde(default)]
+  pub record_events: bool,
+  /// Subscribe to the aggregate trades of the pairs and store them for backtests.
+  #[serde(default)]
+  pub record_trades: bool,
+  /// Serve control requests on `control.sock` in the data dir.
+  #[serde(default)]
+  pub control_socket: bool,
+  /// Reject Binance payloads with fields the payload structs do not know.
+  #[serde(default)]
+  pub strict_parsing: bool,
+  /// Directory scanned for strategy modules, see `strategy::plugin`.
+  #[serde(default = "default_strategies_dir")]
+  pub strategies_dir: PathBuf,
+  /// Symbols shown on the market overview screen.
+  #[serde(default = "default_market_overview")]
+  pub market_overview: Vec<String>,
+  /// Thousands and decimal separators of prices, balances and reports.
+  #[serde(default)]
+  pub number_locale: NumberLocale,
+  /// Palette of the screens, `NO_COLOR` in the environment turns colors off instead.
+  #[serde(default)]
+  pub theme: ThemeName,
+  /// Characters of the screens, `auto` falls back to ASCII when the locale is not UTF-8.
+  #[serde(default)]
+  pub charset: Charset,
+  /// USDT value under which the Exchange screen converts a balance as dust.
+  #[serde(default = "default_dust_threshold")]
+  pub dust_threshold: f64,
+  /// Draw frames only when the screen or the header changed, see the Settings screen.
+  #[serde(default = "default_adaptive_rendering")]
+  pub adaptive_rendering: bool,
+  #[serde(default)]
+  pub logging: LogConfig,
+}
+
+fn default_candle_window() -> usize {
+  candle_window::DEFAULT_CAPACITY
+}
+
+fn default_dust_threshold() -> f64 {
+  10.0
+}
+
+fn default_adaptive_rendering() -> bool {
+  true
+}
+
+fn default_strategies_dir() -> PathBuf {
+  PathBuf::from("strategies")
+}
+
+fn default_market_overview() -> Vec<String> {
+  ["BTCUSDT", "ETHUSDT", "ETHBTC", "BNBUSDT"].map(String::from).to_vec()
 }
 
 impl Config {
   pub fn new() -> Result<Self, config::ConfigError> {
     let default_config: Config = json5::from_str(CONFIG).unwrap();
-    let data_dir = crate::utils::get_data_dir();
-    let config_dir = crate::utils::get_config_dir();
+    let data_dir = paths::data_dir();
+    let config_dir = paths::config_dir();
     let mut builder = config::Config::builder()
       .set_default("_data_dir", data_dir.to_str().unwrap())?
       .set_default("_config_dir", config_dir.to_str().unwrap())?;
@@ -63,6 +163,7 @@ impl Config {
     }
 
     let mut cfg: Self = builder.build()?.try_deserialize()?;
+    cfg.strategies_dir = paths::resolve(&cfg.strategies_dir);
 
     for (mode, default_bindings) in default_config.keybindings.iter() {
       let user_bindings = cfg.keybindings.entry(*mode).or_default();
@@ -402,6 +503,7 @@ mod tests {
   use pretty_assertions::assert_eq;
 
   use super::*;
+  use crate::screens::ScreenId;
 
   #[test]
   fn test_parse_style_default() {
@@ -455,12 +557,20 @@ mod tests {
     let c = Config::new()?;
     assert_eq!(
       c.keybindings
-        .get(&Mode::Home)
+        .get(&Mode::Global)
         .unwrap()
         .get(&parse_key_sequence("<q>").unwrap_or_default())
         .unwrap(),
       &Action::Quit
     );
+    assert_eq!(
+      c.keybindings
+        .get(&Mode::Models)
+        .unwrap()
+        .get(&parse_key_sequence("<n>").unwrap_or_default())
+        .unwrap(),
+      &Action::Navigate(ScreenId::MODELCONFIG)
+    );
     Ok(())
   }
 
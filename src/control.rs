@@ -0,0 +1,216 @@
+use crate::{
+  action::Action, assets::Pair, core::Command, database::Database,
+  paths, portfolio::position::Position, statistic::TradingSummary, utils::set_log_filter,
+};
+use serde::{Deserialize, Serialize};
+use std::{io, path::PathBuf, sync::Arc};
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+  net::{UnixListener, UnixStream},
+  sync::{mpsc::UnboundedSender, watch, Mutex},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Socket the control server listens on, `control.sock` in the data dir.
+pub fn socket_path() -> PathBuf {
+  paths::data_dir().join("control.sock")
+}
+
+/// One request per line, e.g. `{"command": "exit_position", "pair": "BTCUSDT"}`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+  Status,
+  ExitPosition { pair: Pair },
+  ExitAllPositions,
+  Terminate,
+  Pause,
+  Resume,
+  /// Replaces the filter of the log file, e.g. `{"command": "log_filter", "filter":
+  /// "meshetar_tui[core]=debug"}`.
+  LogFilter { filter: String },
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ControlStatus {
+  pub core_id: Option<Uuid>,
+  pub pair: Option<Pair>,
+  pub open_positions: Vec<Position>,
+  pub statistics: Option<TradingSummary>,
+}
+
+/// Answers each request with one line.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ControlResponse {
+  pub ok: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub status: Option<ControlStatus>,
+}
+
+impl ControlResponse {
+  fn ok() -> Self {
+    Self { ok: true, ..Self::default() }
+  }
+
+  fn error(message: impl Into<String>) -> Self {
+    Self { ok: false, error: Some(message.into()), ..Self::default() }
+  }
+}
+
+/// Serves control requests for the session in `session`, sending its commands through
+/// the app like the TUI does.
+pub struct ControlServer {
+  action_tx: UnboundedSender<Action>,
+  database: Arc<Mutex<Database>>,
+  session: watch::Receiver<Option<(Uuid, Pair)>>,
+}
+
+impl ControlServer {
+  pub fn new(
+    action_tx: UnboundedSender<Action>,
+    database: Arc<Mutex<Database>>,
+    session: watch::Receiver<Option<(Uuid, Pair)>>,
+  ) -> Self {
+    Self { action_tx, database, session }
+  }
+
+  /// Listens on the socket for as long as the app runs. A socket left behind by an
+  /// earlier instance is replaced, one another instance still listens on is not.
+  pub fn spawn(self, path: PathBuf) -> io::Result<()> {
+    if path.exists() {
+      if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+        let error = format!("another instance is listening on {}", path.display());
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, error));
+      }
+      std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    info!("Control server listening on {}", path.display());
+    let server = Arc::new(self);
+    tokio::spawn(async move {
+      loop {
+        match listener.accept().await {
+          Ok((stream, _)) => {
+            let server = server.clone();
+            tokio::spawn(async move {
+              if let Err(e) = server.serve(stream).await {
+                warn!("Control connection failed: {}", e);
+              }
+            });
+          },
+          Err(e) => warn!("Failed to accept control connection: {}", e),
+        }
+      }
+    });
+    Ok(())
+  }
+
+  async fn serve(&self, stream: UnixStream) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+      if line.trim().is_empty() {
+        continue;
+      }
+      let response = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(request) => self.handle(request).await,
+        Err(e) => ControlResponse::error(format!("Invalid request: {}", e)),
+      };
+      let mut response = serde_json::to_string(&response)?;
+      response.push('\n');
+      writer.write_all(response.as_bytes()).await?;
+    }
+    Ok(())
+  }
+
+  async fn handle(&self, request: ControlRequest) -> ControlResponse {
+    let session = *self.session.borrow();
+    let Some((core_id, pair)) = session else {
+      return match request {
+        ControlRequest::Status => ControlResponse {
+          status: Some(ControlStatus::default()),
+          ..ControlResponse::ok()
+        },
+        ControlRequest::LogFilter { filter } => log_filter(&filter),
+        _ => ControlResponse::error("No session is running"),
+      };
+    };
+    let command = match request {
+      ControlRequest::Status => {
+        let mut database = self.database.lock().await;
+        let status = ControlStatus {
+          core_id: Some(core_id),
+          pair: Some(pair),
+          open_positions: database.get_all_open_positions(core_id).unwrap_or_default(),
+          statistics: database.get_aggregate_statistics(&core_id).ok(),
+        };
+        return ControlResponse { status: Some(status), ..ControlResponse::ok() };
+      },
+      ControlRequest::ExitPosition { pair } => Command::ExitPosition(pair),
+      ControlRequest::ExitAllPositions => Command::ExitAllPositions,
+      ControlRequest::Terminate => {
+        Command::Terminate("Terminated over control socket".into())
+      },
+      ControlRequest::Pause => Command::Pause,
+      ControlRequest::Resume => Command::Resume,
+      ControlRequest::LogFilter { filter } => return log_filter(&filter),
+    };
+    match self.action_tx.send(Action::CoreCommand(command)) {
+      Ok(_) => ControlResponse::ok(),
+      Err(e) => ControlResponse::error(e.to_string()),
+    }
+  }
+}
+
+/// Sends one request line to a running instance and returns its response line.
+pub async fn send(path: PathBuf, request: &str) -> io::Result<String> {
+  let stream = UnixStream::connect(&path).await?;
+  let (reader, mut writer) = stream.into_split();
+  writer.write_all(format!("{}\n", request.trim()).as_bytes()).await?;
+  let mut response = String::new();
+  BufReader::new(reader).read_line(&mut response).await?;
+  Ok(response)
+}
+
+fn log_filter(filter: &str) -> ControlResponse {
+  match set_log_filter(filter) {
+    Ok(()) => ControlResponse::ok(),
+    Err(e) => ControlResponse::error(e.to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use tokio::sync::mpsc;
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn answers_requests_over_the_socket() {
+    let path = std::env::temp_dir().join(format!("meshetar-{}.sock", Uuid::new_v4()));
+    let (action_tx, _action_rx) = mpsc::unbounded_channel();
+    let database = Arc::new(Mutex::new(Database::test().await));
+    let (_session_tx, session_rx) = watch::channel(None);
+    ControlServer::new(action_tx.clone(), database.clone(), session_rx.clone())
+      .spawn(path.clone())
+      .unwrap();
+
+    let status = send(path.clone(), r#"{"command": "status"}"#).await.unwrap();
+    let status: serde_json::Value = serde_json::from_str(&status).unwrap();
+    let idle =
+      json!({ "core_id": null, "pair": null, "open_positions": [], "statistics": null });
+    assert_eq!(status, json!({ "ok": true, "status": idle }));
+    let invalid = send(path.clone(), "status").await.unwrap();
+    assert!(invalid.starts_with(r#"{"ok":false,"error":"Invalid request: "#));
+    let pause = send(path.clone(), r#"{"command": "pause"}"#).await.unwrap();
+    assert_eq!(pause, "{\"ok\":false,\"error\":\"No session is running\"}\n");
+
+    // The socket of a running instance is not taken over
+    let second = ControlServer::new(action_tx, database, session_rx).spawn(path.clone());
+    assert_eq!(second.unwrap_err().kind(), io::ErrorKind::AddrInUse);
+    std::fs::remove_file(&path).unwrap();
+  }
+}
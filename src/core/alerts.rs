@@ -0,0 +1,125 @@
+use crate::statistic::TradingSummary;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Condition of an alert rule, checked against the session statistics every second.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+  /// Current drawdown is deeper than the fraction, e.g. `0.1` for 10%.
+  DrawdownAbove(f64),
+  /// No market event arrived for this many seconds.
+  NoMarketEventFor(u64),
+  /// The current losing streak reached this many trades.
+  ConsecutiveLosses(u64),
+}
+
+impl fmt::Display for AlertCondition {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::DrawdownAbove(fraction) => write!(f, "drawdown > {:.1}%", fraction * 100.0),
+      Self::NoMarketEventFor(secs) => write!(f, "no market event for {}s", secs),
+      Self::ConsecutiveLosses(n) => write!(f, "{} consecutive losses", n),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+  /// Shows the alert in the TUI.
+  Toast,
+  /// POSTs the alert as JSON to the url.
+  Webhook(String),
+  /// Traders stop acting on strategy signals until resumed.
+  Pause,
+  /// Ends the session, exiting all positions.
+  Terminate,
+}
+
+/// A rule from the `alerts` list of the config, e.g. in `config.toml`:
+///
+/// ```toml
+/// [[alerts]]
+/// condition = { drawdown_above = 0.1 }
+/// actions = ["toast", "pause"]
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct AlertRule {
+  pub condition: AlertCondition,
+  pub actions: Vec<AlertAction>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct Alert {
+  /// Session the alert is about, none for watchlist alerts.
+  pub core_id: Option<Uuid>,
+  pub time: DateTime<Utc>,
+  pub message: String,
+}
+
+/// Evaluates alert rules for one session. A rule fires when its condition starts to
+/// hold and is re-armed once it stops holding, so a breached threshold alerts only once.
+pub struct AlertEngine {
+  rules: Vec<AlertRule>,
+  triggered: Vec<bool>,
+  last_market_event: DateTime<Utc>,
+}
+
+impl AlertEngine {
+  pub fn new(rules: Vec<AlertRule>, now: DateTime<Utc>) -> Self {
+    let triggered = vec![false; rules.len()];
+    Self { rules, triggered, last_market_event: now }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  pub fn market_event(&mut self, time: DateTime<Utc>) {
+    self.last_market_event = time;
+  }
+
+  /// Returns the rules that fired since the last evaluation.
+  pub fn evaluate(
+    &mut self,
+    statistics: Option<&TradingSummary>,
+    now: DateTime<Utc>,
+  ) -> Vec<&AlertRule> {
+    let mut fired = Vec::new();
+    for (rule, triggered) in self.rules.iter().zip(self.triggered.iter_mut()) {
+      let holds = match rule.condition {
+        AlertCondition::DrawdownAbove(fraction) => statistics.is_some_and(|statistics| {
+          statistics.drawdown.current_drawdown.drawdown > fraction
+        }),
+        AlertCondition::NoMarketEventFor(secs) => {
+          (now - self.last_market_event).num_seconds() >= secs as i64
+        },
+        AlertCondition::ConsecutiveLosses(n) => statistics
+          .is_some_and(|statistics| statistics.trades.current_losing_streak >= n),
+      };
+      if holds && !*triggered {
+        fired.push(rule);
+      }
+      *triggered = holds;
+    }
+    fired
+  }
+}
+
+/// Posts the alert without blocking the caller; failures are only logged.
+pub fn send_webhook(url: String, alert: Alert) {
+  tokio::task::spawn_blocking(move || {
+    let body = match serde_json::to_string(&alert) {
+      Ok(body) => body,
+      Err(e) => return warn!("Failed to serialize alert: {}", e),
+    };
+    match ureq::post(&url).set("Content-Type", "application/json").send_string(&body) {
+      Ok(_) => info!("Alert sent to {}", url),
+      Err(e) => warn!("Failed to send alert to {}: {}", url, e),
+    }
+  });
+}
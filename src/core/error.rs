@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::database::error::DatabaseError;
+use crate::{
+  database::error::DatabaseError, strategy::error::StrategyError,
+  trading::error::TraderError,
+};
 
 #[derive(Error, Debug)]
 pub enum CoreError {
@@ -10,4 +13,8 @@ pub enum CoreError {
   RepositoryInteraction(#[from] DatabaseError),
   #[error("Failed to build report: {0}")]
   ReportError(String),
+  #[error("Failed to train a model: {0}")]
+  StrategyError(#[from] StrategyError),
+  #[error("Failed to backtest: {0}")]
+  TraderError(#[from] TraderError),
 }
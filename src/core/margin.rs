@@ -0,0 +1,101 @@
+use crate::exchange::futures::MarginHealth;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// Thresholds of the margin health monitor of live futures sessions, from
+/// `margin_health` in the config. Thresholds are margin ratios, the maintenance margin
+/// over the margin balance.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MarginHealthConfig {
+  pub poll_secs: u64,
+  /// Alerts once the ratio reaches it.
+  pub warn_ratio: f64,
+  /// Reduces the positions of the session on every poll while the ratio is at least
+  /// this.
+  pub reduce_ratio: f64,
+  /// Share of the open quantity each reduction exits.
+  pub reduce_share: f64,
+  /// Url the margin alerts are posted to, optional.
+  pub webhook: Option<String>,
+}
+
+impl Default for MarginHealthConfig {
+  fn default() -> Self {
+    Self {
+      poll_secs: 30,
+      warn_ratio: 0.5,
+      reduce_ratio: 0.8,
+      reduce_share: 0.5,
+      webhook: None,
+    }
+  }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Display, Serialize)]
+#[strum(serialize_all = "lowercase")]
+pub enum MarginLevel {
+  #[default]
+  Healthy,
+  Warning,
+  Critical,
+}
+
+impl MarginLevel {
+  pub fn of(health: &MarginHealth, config: &MarginHealthConfig) -> Self {
+    let ratio = health.ratio();
+    if ratio >= config.reduce_ratio {
+      MarginLevel::Critical
+    } else if ratio >= config.warn_ratio {
+      MarginLevel::Warning
+    } else {
+      MarginLevel::Healthy
+    }
+  }
+}
+
+/// Follows the margin level of a session between polls.
+pub struct MarginMonitor {
+  config: MarginHealthConfig,
+  level: MarginLevel,
+}
+
+impl MarginMonitor {
+  pub fn new(config: MarginHealthConfig) -> Self {
+    Self { config, level: MarginLevel::Healthy }
+  }
+
+  pub fn config(&self) -> &MarginHealthConfig {
+    &self.config
+  }
+
+  /// Level of the polled health and whether it changed since the last poll.
+  pub fn update(&mut self, health: &MarginHealth) -> (MarginLevel, bool) {
+    let level = MarginLevel::of(health, &self.config);
+    let changed = level != self.level;
+    self.level = level;
+    (level, changed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn health(maintenance_margin: f64) -> MarginHealth {
+    MarginHealth { margin_balance: 100.0, maintenance_margin }
+  }
+
+  #[test]
+  fn reports_changes_of_the_margin_level() {
+    let mut monitor = MarginMonitor::new(MarginHealthConfig::default());
+    assert_eq!(monitor.update(&health(10.0)), (MarginLevel::Healthy, false));
+    assert_eq!(monitor.update(&health(55.0)), (MarginLevel::Warning, true));
+    assert_eq!(monitor.update(&health(85.0)), (MarginLevel::Critical, true));
+    // Stays critical, reduced again without another alert
+    assert_eq!(monitor.update(&health(82.0)), (MarginLevel::Critical, false));
+    assert_eq!(monitor.update(&health(20.0)), (MarginLevel::Healthy, true));
+    let wiped = MarginHealth { margin_balance: 0.0, maintenance_margin: 1.0 };
+    assert_eq!(wiped.ratio(), 1.0);
+  }
+}
@@ -1,38 +1,161 @@
+pub mod alerts;
 pub mod error;
+pub mod margin;
+pub mod retraining;
+pub mod watchdog;
 
 use crate::{
-  assets::Pair,
-  database::Database,
+  assets::{backtest_ticker::BacktestProgress, Asset, Pair},
+  clock::{real_clock, SharedClock},
+  database::{Database, SessionLabel},
+  diagnostics::{self, Task},
+  events::Event,
+  exchange::account::AccountName,
   exchange::binance_client::BinanceClient,
-  exchange::fetch_candles,
+  exchange::{
+    fetch_candles_with_progress,
+    futures::{self, MarginHealth},
+    market::fetch_tickers_24h,
+    ExchangeEvent,
+  },
+  paths,
   portfolio::{balance::Balance, error::PortfolioError, Portfolio},
   screens::run_config::CoreConfiguration,
-  statistic::{StatisticConfig, TradingSummary},
-  trading::Trader,
+  statistic::{
+    attribution::{trades_csv, TRADES_FILE},
+    session_time::SessionTime,
+    StatisticConfig, TradingSummary,
+  },
+  strategy::ModelId,
+  trading::{params::RuntimeParams, Trader},
 };
+use alerts::{send_webhook, Alert, AlertAction, AlertEngine, AlertRule};
 use chrono::{DateTime, Duration, Utc};
 use error::CoreError;
+use margin::{MarginHealthConfig, MarginLevel, MarginMonitor};
 use prettytable::Table;
+use retraining::{retrain, RetrainingConfig, RetrainingOutcome};
 use serde::Serialize;
-use std::{collections::HashMap, fs::File, io::Write, sync::Arc};
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::Write,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
 use tokio::sync::{
+  broadcast,
   mpsc::{self, Receiver, Sender},
-  Mutex,
+  watch, Mutex,
 };
-use tracing::{error, info, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, instrument, warn, Instrument};
 use uuid::Uuid;
+use watchdog::{Staleness, StalenessConfig, StalenessWatchdog};
+
+const SUMMARY_CSS: &str = include_str!("../../summary.css");
+
+/// Directory with the output of a single session.
+pub fn session_dir(core_id: Uuid) -> PathBuf {
+  paths::sessions_dir().join(core_id.to_string())
+}
+
+/// Records the prices that convert the quote assets of the pairs into the reporting
+/// currency of the session, for pairs that do not trade the conversion themselves. Live
+/// sessions take the last price of the exchange, others and live ones without it the
+/// last stored close before the start. Entries wait for a rate that is missing.
+pub async fn record_conversion_rates(
+  database: &Arc<Mutex<Database>>,
+  core_id: Uuid,
+  pairs: &[Pair],
+  reporting: Asset,
+  started_at: DateTime<Utc>,
+  live_client: Option<BinanceClient>,
+) {
+  let conversions: Vec<Pair> = pairs
+    .iter()
+    .filter(|pair| pair.quote() != reporting)
+    .filter_map(|pair| Pair::between(pair.quote(), reporting))
+    .filter(|conversion| !pairs.contains(conversion))
+    .collect();
+  for conversion in conversions {
+    let mut price = None;
+    if let Some(client) = live_client.clone() {
+      let symbols = [conversion.to_string()];
+      let tickers =
+        tokio::task::spawn_blocking(move || fetch_tickers_24h(&client, &symbols)).await;
+      match tickers {
+        Ok(Ok(tickers)) => price = tickers.first().map(|ticker| ticker.last_price),
+        Ok(Err(e)) => warn!("Failed to fetch the price of {}: {}", conversion, e),
+        Err(e) => warn!("Failed to fetch the price of {}: {}", conversion, e),
+      }
+    }
+    let mut database = database.lock().await;
+    if price.is_none() {
+      price = database
+        .fetch_candles_before(conversion, started_at, 1)
+        .await
+        .ok()
+        .and_then(|candles| candles.last().map(|candle| candle.close));
+    }
+    match price {
+      Some(price) => database.record_price(core_id, conversion, price),
+      None => warn!("No price of {} to convert into {}.", conversion, reporting),
+    }
+  }
+}
 
 #[derive(Serialize, Clone, PartialEq, Debug)]
 pub enum Command {
   ExitPosition(Pair),
+  /// Exits the given share of the open quantity of the pair's position.
+  ReducePosition(Pair, f64),
   ExitAllPositions,
   Terminate(String),
   Start(CoreConfiguration),
+  /// Replaces the model of the trader for the model's pair, effective from next candle.
+  SwapModel(ModelId),
+  /// Traders stop acting on strategy signals; open positions are still tracked.
+  Pause,
+  Resume,
+  /// Traders stop opening positions; exits are still acted on.
+  PauseEntries,
+  ResumeEntries,
+  /// Replaces the runtime parameters of the traders, effective from their next candle.
+  UpdateParams(RuntimeParams),
 }
 
 #[derive(Serialize, Clone, PartialEq, Debug)]
 pub enum CoreMessage {
+  /// Days of history of the session's pairs the core fetches before the traders start.
+  HistoryFetchStarted { core_id: Uuid, days: i64 },
+  /// Share of the pair's history fetched so far.
+  HistoryFetchProgress { core_id: Uuid, pair: Pair, progress: f64 },
+  /// Candles of all pairs fetched and stored.
+  HistoryFetchDone { core_id: Uuid, candles: usize },
+  /// History is fetched and the traders run.
+  TradersStarted { core_id: Uuid },
+  /// The trader of the pair stopped on an error or panicked, the others keep running.
+  TraderFailed { core_id: Uuid, pair: Pair, error: String },
+  /// The session's report was written to the file.
+  SummaryReady { core_id: Uuid, path: PathBuf },
   Finished(Uuid),
+  /// The session stopped on an error, without a report.
+  Failed { core_id: Uuid, error: String },
+  Alert(Alert),
+  /// No market event for the pair of the session since the time.
+  MarketDataStale { core_id: Uuid, pair: Pair, since: DateTime<Utc> },
+  MarketDataFresh { core_id: Uuid, pair: Pair },
+  /// Closed candles the trader of the pair still needs before its first signal, sent
+  /// until it reaches 0.
+  WarmingUp { core_id: Uuid, pair: Pair, remaining: usize },
+  /// Candles of a backtest sent to its traders so far.
+  BacktestProgress { core_id: Uuid, progress: BacktestProgress },
+  /// Failure a session keeps running through, like a signal deferred for a missing
+  /// balance.
+  SystemError { core_id: Uuid, error: String },
+  /// Margin health of the futures wallet of a live futures session, at each poll.
+  MarginHealth { core_id: Uuid, health: MarginHealth, level: MarginLevel },
 }
 
 pub struct Core {
@@ -47,6 +170,21 @@ pub struct Core {
   traders: Vec<Trader>,
   n_days_history_fetch: i64,
   is_backtest: bool,
+  event_rx: broadcast::Receiver<Event>,
+  alert_rules: Vec<AlertRule>,
+  staleness: StalenessConfig,
+  clock: SharedClock,
+  label: SessionLabel,
+  retraining: RetrainingConfig,
+  /// Configuration the session started with, retrained models are validated with it.
+  configuration: Option<CoreConfiguration>,
+  backtest_progress: Option<watch::Receiver<BacktestProgress>>,
+  /// Stops the backtest ticker, the traders then finish as when the candles run out.
+  backtest_cancel: Option<CancellationToken>,
+  /// Account and asset whose free balance on the exchange the session's balance follows.
+  reconciled_asset: Option<(AccountName, Asset)>,
+  /// Futures client whose margin health the session polls and de-risks on.
+  margin: Option<(MarginHealthConfig, Arc<BinanceClient>)>,
 }
 
 impl Core {
@@ -56,8 +194,31 @@ impl Core {
 }
 
 impl Core {
+  #[instrument(
+    name = "core",
+    skip_all,
+    fields(core_id = %self.id, session = %self.label.label)
+  )]
   pub async fn run(&mut self) -> Result<(), CoreError> {
     info!("Core {} is starting up.", &self.id);
+    // Traders look the balance up from their first signal, with or without a history
+    // fetch before them
+    let starting_time = self.clock.now();
+    self
+      .init_core_in_db(self.id, self.statistics_config.starting_equity, starting_time)
+      .await?;
+    let pairs: Vec<Pair> = self.command_transmitters.keys().copied().collect();
+    let live_client = (!self.is_backtest).then(|| (*self.binance_client).clone());
+    let reporting = self.statistics_config.reporting_currency;
+    record_conversion_rates(
+      &self.database,
+      self.id,
+      &pairs,
+      reporting,
+      starting_time,
+      live_client,
+    )
+    .await;
     if self.n_days_history_fetch > 0 {
       let mut fetching_stopped = self.fetch_history(self.n_days_history_fetch).await;
       loop {
@@ -68,37 +229,127 @@ impl Core {
         }
       }
     }
-    let starting_time = if self.is_backtest {
-      // TODO: implement proper lookup of when BACKTEST started
-      Utc::now()
-    } else {
-      Utc::now()
+    let session = {
+      let mut database = self.database.lock().await;
+      let session_time = SessionTime::new(Utc::now(), self.is_backtest);
+      database.start_session_time(self.id, session_time);
+      database.add_session(self.id, starting_time, self.is_backtest, &self.label).await
     };
+    if let Err(e) = session {
+      error!("Failed to record session {}: {}", self.id, e);
+    }
 
-    let _ = self
-      .init_core_in_db(self.id, self.statistics_config.starting_equity, starting_time)
-      .await;
-
+    let mut alerts = AlertEngine::new(self.alert_rules.clone(), Utc::now());
+    let mut alert_interval = tokio::time::interval(std::time::Duration::from_secs(1));
+    let mut watchdog = StalenessWatchdog::new(
+      self.staleness.clone(),
+      self.command_transmitters.keys().copied(),
+      Utc::now(),
+    );
+    let mut watchdog_interval = tokio::time::interval(std::time::Duration::from_secs(1));
+    let mut margin_monitor =
+      self.margin.as_ref().map(|(config, _)| MarginMonitor::new(config.clone()));
+    let margin_poll = self.margin.as_ref().map_or(30, |(config, _)| config.poll_secs);
+    let mut margin_interval =
+      tokio::time::interval(std::time::Duration::from_secs(margin_poll.max(1)));
+    let retraining = self.retraining.is_enabled() && !self.is_backtest;
+    let period = self.retraining.period();
+    let mut progress_interval =
+      tokio::time::interval(std::time::Duration::from_millis(250));
+    let mut retraining_interval =
+      tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let (retrained_tx, mut retrained_rx) = mpsc::channel(1);
+    let mut is_retraining = false;
+    let mut events_open = true;
     let mut trading_stopped = self.run_traders().await;
+    let _ = self.message_tx.send(CoreMessage::TradersStarted { core_id: self.id }).await;
     loop {
       tokio::select! {
           _ = trading_stopped.recv() => {
               log::info!("Trading loop finished.");
               break;
           },
+          event = self.event_rx.recv(), if events_open => {
+              diagnostics::record_queue_depth(Task::Core, self.event_rx.len());
+              let _timer = diagnostics::time_iteration(Task::Core);
+              match event {
+                  Ok(Event::Market(market_event))
+                      if self.command_transmitters.contains_key(&market_event.pair) =>
+                  {
+                      alerts.market_event(Utc::now());
+                      if let Some(staleness) =
+                          watchdog.market_event(market_event.pair, Utc::now())
+                      {
+                          self.handle_staleness(market_event.pair, staleness).await;
+                      }
+                  }
+                  Ok(Event::Exchange(ExchangeEvent::ExchangeBalanceUpdate(
+                      account,
+                      balances,
+                  ))) => {
+                      self.reconcile_balance(&account, &balances).await;
+                  }
+                  Err(broadcast::error::RecvError::Closed) => events_open = false,
+                  _ => {}
+              }
+          },
+          _ = alert_interval.tick(), if !alerts.is_empty() => {
+              if self.handle_alerts(&mut alerts).await {
+                  break;
+              }
+          },
+          _ = retraining_interval.tick(), if retraining && !is_retraining => {
+              is_retraining = self.start_retraining(retrained_tx.clone());
+          },
+          Some(outcome) = retrained_rx.recv() => {
+              is_retraining = false;
+              self.handle_retraining(outcome).await;
+          },
+          _ = progress_interval.tick(), if self.backtest_progress.is_some() => {
+              self.send_backtest_progress().await;
+          },
+          _ = watchdog_interval.tick() => {
+              for (pair, staleness) in watchdog.check(Utc::now()) {
+                  self.handle_staleness(pair, staleness).await;
+              }
+          },
+          _ = margin_interval.tick(), if margin_monitor.is_some() => {
+              if let Some(monitor) = margin_monitor.as_mut() {
+                  self.check_margin_health(monitor).await;
+              }
+          },
           command = self.command_rx.recv() => {
+              let _timer = diagnostics::time_iteration(Task::Core);
               if let Some(command) = command {
                   match command {
                       Command::ExitPosition(asset) => {
                           self.exit_position(asset).await;
                       }
+                      Command::ReducePosition(asset, share) => {
+                          self.reduce_position(asset, share).await;
+                      }
                       Command::ExitAllPositions => {
                           self.exit_all_positions().await;
                       }
                       Command::Terminate(message) => {
+                          if let Some(cancel) = &self.backtest_cancel {
+                              info!("Cancelling backtest: {}", message);
+                              cancel.cancel();
+                              continue;
+                          }
                           self.terminate_traders(message).await;
                           break;
                       },
+                      Command::SwapModel(model_id) => {
+                          self.swap_model(model_id).await;
+                      },
+                      Command::Pause
+                      | Command::Resume
+                      | Command::PauseEntries
+                      | Command::ResumeEntries
+                      | Command::UpdateParams(_) => {
+                          self.send_to_traders(command).await;
+                      },
                       _  => {}
                   }
               } else {
@@ -108,81 +359,294 @@ impl Core {
       }
     }
 
-    // File to print out the statistics
-    match File::create("summary.html") {
-      Ok(mut out) => {
-        let css_content = std::fs::read_to_string("summary.css")
-          .map_err(|e| CoreError::ReportError(e.to_string()))?;
-        writeln!(out, "<style>{}</style>", css_content)
-          .map_err(|e| CoreError::ReportError(e.to_string()))?;
-        let (overall_stats_tables, exited_trades_table) =
-          self.generate_session_summary().await?;
-        overall_stats_tables.iter().for_each(|table| {
-          match table.print_html(&mut out) {
-            Err(e) => log::error!("{}", e.to_string()),
-            _ => (),
-          };
-          // let _ = table.printstd();
-        });
-        let _ = exited_trades_table.print_html(&mut out);
-        warn!("\n\n\nCheck summary.html for backtesting stats\n\n");
-      },
-      Err(e) => log::error!("{}", e.to_string()),
-    }
+    self.database.lock().await.finish_session_time(self.id, Utc::now());
+    let summary_path = self.write_summary().await?;
+    self.database.lock().await.set_session_summary_path(self.id, &summary_path).await?;
+    let message =
+      CoreMessage::SummaryReady { core_id: self.id, path: summary_path.clone() };
+    let _ = self.message_tx.send(message).await;
+    warn!("\n\n\nCheck {} for backtesting stats\n\n", summary_path.display());
     Ok(())
   }
 
+  /// Writes the statistics to `summary.html` of the session directory, with the styles
+  /// inlined so the file can be opened on its own, and the trades to `trades.csv` next to
+  /// it.
+  async fn write_summary(&self) -> Result<PathBuf, CoreError> {
+    let report_error = |e: std::io::Error| CoreError::ReportError(e.to_string());
+    let session_dir = session_dir(self.id);
+    std::fs::create_dir_all(&session_dir).map_err(report_error)?;
+    let summary_path = session_dir.join("summary.html");
+    let mut out = File::create(&summary_path).map_err(report_error)?;
+    writeln!(out, "<style>{}</style>", SUMMARY_CSS).map_err(report_error)?;
+    let (overall_stats_tables, exited_trades_table) =
+      self.generate_session_summary().await?;
+    for table in overall_stats_tables.iter() {
+      if let Err(e) = table.print_html(&mut out) {
+        log::error!("{}", e.to_string());
+      }
+    }
+    if let Some(progress_rx) = &self.backtest_progress {
+      let gaps = progress_rx.borrow().gaps;
+      writeln!(out, "<p>Candle gaps: {}</p>", gaps).map_err(report_error)?;
+    }
+    let statistics = self.database.lock().await.get_aggregate_statistics(&self.id)?;
+    writeln!(out, "{}", crate::statistic::drawdown_svg(&statistics.drawdown.curve))
+      .map_err(report_error)?;
+    exited_trades_table.print_html(&mut out).map_err(report_error)?;
+    let positions = self.database.lock().await.get_exited_positions(self.id)?;
+    std::fs::write(session_dir.join(TRADES_FILE), trades_csv(&positions))
+      .map_err(report_error)?;
+    Ok(summary_path)
+  }
+
   async fn fetch_history(&mut self, n_days: i64) -> mpsc::Receiver<bool> {
-    let assets: Vec<Pair> =
-      self.traders.iter().map(|trader| trader.pair.clone()).collect();
+    // Synthetic pairs have no history on Binance
+    let assets: Vec<Pair> = self
+      .traders
+      .iter()
+      .map(|trader| trader.pair)
+      .filter(|pair| !pair.is_synthetic())
+      .collect();
     let binance_client = self.binance_client.clone();
-    let handles = assets.into_iter().map(move |asset| {
-      (
-        asset.clone(),
-        fetch_candles(Duration::days(n_days), asset.clone(), binance_client.clone()),
-      )
-    });
+    let core_id = self.id;
+    let message_tx = self.message_tx.clone();
+    let _ =
+      message_tx.send(CoreMessage::HistoryFetchStarted { core_id, days: n_days }).await;
     let (notify_transmitter, notify_receiver) = mpsc::channel(1);
     let database = self.database.clone();
-    tokio::spawn(async move {
-      for handle in handles {
-        match handle.1.await {
+    let fetch = async move {
+      let mut fetched = 0;
+      for pair in assets {
+        // Progress is dropped while the app is behind on messages
+        let progress = |progress| {
+          let message = CoreMessage::HistoryFetchProgress { core_id, pair, progress };
+          let _ = message_tx.try_send(message);
+        };
+        let client = binance_client.clone();
+        match fetch_candles_with_progress(Duration::days(n_days), pair, client, progress)
+          .await
+        {
           Ok(candles) => {
-            let _ = database.lock().await.add_candles(handle.0, candles).await;
+            fetched += candles.len();
+            let _ = database.lock().await.add_candles(pair, candles).await;
           },
           Err(err) => {
-            error!(
-              error = &*format!("{:?}", err),
-              "Trader thread has panicked during execution",
-            )
+            error!(error = &*format!("{:?}", err), "Failed to fetch history of {}", pair);
+            let error = format!("Failed to fetch history of {}: {}", pair, err);
+            let _ = message_tx.send(CoreMessage::SystemError { core_id, error }).await;
           },
         }
       }
+      let _ = message_tx
+        .send(CoreMessage::HistoryFetchDone { core_id, candles: fetched })
+        .await;
       let _ = notify_transmitter.send(true).await;
-    });
+    };
+    tokio::spawn(fetch.in_current_span());
     notify_receiver
   }
   async fn run_traders(&mut self) -> mpsc::Receiver<bool> {
     let traders = std::mem::take(&mut self.traders);
     let mut thread_handles = Vec::with_capacity(traders.len());
     for mut trader in traders.into_iter() {
+      let pair = trader.pair;
       let handle = tokio::spawn(async move { trader.run().await });
-      thread_handles.push(handle);
+      thread_handles.push((pair, handle));
     }
     let (notify_transmitter, notify_receiver) = mpsc::channel(1);
+    let core_id = self.id;
+    let message_tx = self.message_tx.clone();
     tokio::spawn(async move {
-      for handle in thread_handles {
-        if let Err(err) = handle.await {
-          error!(
-            error = &*format!("{:?}", err),
-            "Trader thread has panicked during execution",
-          )
-        }
+      for (pair, handle) in thread_handles {
+        let error = match handle.await {
+          Ok(Ok(())) => continue,
+          Ok(Err(err)) => err.to_string(),
+          Err(err) => {
+            error!(
+              error = &*format!("{:?}", err),
+              "Trader thread has panicked during execution",
+            );
+            err.to_string()
+          },
+        };
+        let _ = message_tx.send(CoreMessage::TraderFailed { core_id, pair, error }).await;
       }
       let _ = notify_transmitter.send(true).await;
     });
     notify_receiver
   }
+  /// Runs the actions of alert rules that fired. Returns true when the session should
+  /// terminate.
+  async fn handle_alerts(&mut self, alerts: &mut AlertEngine) -> bool {
+    let statistics =
+      self.database.lock().await.get_aggregate_statistics(&self.id).ok();
+    let now = Utc::now();
+    let fired: Vec<AlertRule> =
+      alerts.evaluate(statistics.as_ref(), now).into_iter().cloned().collect();
+    let mut terminate = false;
+    for rule in fired {
+      let alert = Alert {
+        core_id: Some(self.id),
+        time: now,
+        message: format!("Alert: {}", rule.condition),
+      };
+      warn!("{}", alert.message);
+      let journal =
+        self.database.lock().await.add_journal_entry(self.id, now, &alert.message).await;
+      if let Err(e) = journal {
+        warn!("Failed to record alert: {}", e);
+      }
+      for action in rule.actions {
+        match action {
+          AlertAction::Toast => {
+            let _ = self.message_tx.send(CoreMessage::Alert(alert.clone())).await;
+          },
+          AlertAction::Webhook(url) => send_webhook(url, alert.clone()),
+          AlertAction::Pause => self.send_to_traders(Command::Pause).await,
+          AlertAction::Terminate => {
+            self.terminate_traders(alert.message.clone()).await;
+            terminate = true;
+          },
+        }
+      }
+    }
+    terminate
+  }
+  /// Polls the margin health of the futures wallet, alerts when its level changes and
+  /// reduces the positions of the session while it is critical.
+  async fn check_margin_health(&mut self, monitor: &mut MarginMonitor) {
+    let Some((_, client)) = self.margin.clone() else {
+      return;
+    };
+    let fetched =
+      tokio::task::spawn_blocking(move || futures::fetch_margin_health(&client)).await;
+    let health = match fetched {
+      Ok(Ok(health)) => health,
+      Ok(Err(e)) => return warn!("Failed to fetch the margin health: {}", e),
+      Err(e) => return warn!("Failed to fetch the margin health: {}", e),
+    };
+    let (level, changed) = monitor.update(&health);
+    let message = CoreMessage::MarginHealth { core_id: self.id, health, level };
+    let _ = self.message_tx.send(message).await;
+    if changed {
+      let now = Utc::now();
+      let alert = Alert {
+        core_id: Some(self.id),
+        time: now,
+        message: format!("Margin {}: {:.0}% used", level, health.ratio() * 100.0),
+      };
+      warn!("{}", alert.message);
+      let journal =
+        self.database.lock().await.add_journal_entry(self.id, now, &alert.message).await;
+      if let Err(e) = journal {
+        warn!("Failed to record margin alert: {}", e);
+      }
+      let _ = self.message_tx.send(CoreMessage::Alert(alert.clone())).await;
+      if let Some(url) = monitor.config().webhook.clone() {
+        send_webhook(url, alert);
+      }
+    }
+    if level == MarginLevel::Critical {
+      let share = monitor.config().reduce_share;
+      let pairs: Vec<Pair> = self.command_transmitters.keys().copied().collect();
+      for pair in pairs {
+        self.reduce_position(pair, share).await;
+      }
+    }
+  }
+  /// Moves the session's balance of the reconciled asset to its free balance on the
+  /// exchange, so orders are sized with what the account actually holds.
+  async fn reconcile_balance(
+    &mut self,
+    account: &AccountName,
+    balances: &[(String, Balance)],
+  ) {
+    let Some((_, asset)) =
+      self.reconciled_asset.clone().filter(|(reconciled, _)| reconciled == account)
+    else {
+      return;
+    };
+    let Some((_, exchange_balance)) =
+      balances.iter().find(|(name, _)| *name == asset.to_string())
+    else {
+      return;
+    };
+    let mut database = self.database.lock().await;
+    let mut asset_balances = match database.get_asset_balances(self.id) {
+      Ok(asset_balances) => asset_balances,
+      Err(e) => {
+        warn!("Failed to reconcile the balance of {}: {}", asset, e);
+        return;
+      },
+    };
+    let time = self.clock.now();
+    let difference = asset_balances.reconcile(asset, exchange_balance.available, time);
+    if difference.abs() > f64::EPSILON {
+      info!("Reconciled the {} balance with the exchange by {:.8}.", asset, difference);
+    }
+    let valuation = asset_balances.valuation(&database.get_exchange_rates(self.id), time);
+    database.set_asset_balances(self.id, asset_balances);
+    let result = valuation
+      .and_then(|valuation| database.set_balance(self.id, valuation).map_err(Into::into));
+    if let Err(e) = result {
+      warn!("Failed to reconcile the balance of {}: {}", asset, e);
+    }
+  }
+  async fn send_backtest_progress(&mut self) {
+    let Some(progress_rx) = self.backtest_progress.as_mut() else {
+      return;
+    };
+    if !progress_rx.has_changed().unwrap_or(false) {
+      return;
+    }
+    let progress = *progress_rx.borrow_and_update();
+    let message = CoreMessage::BacktestProgress { core_id: self.id, progress };
+    let _ = self.message_tx.send(message).await;
+  }
+  async fn handle_staleness(&mut self, pair: Pair, staleness: Staleness) {
+    let Some(command_tx) = self.command_transmitters.get(&pair) else {
+      return;
+    };
+    let (command, message) = match staleness {
+      Staleness::Stale(since) => {
+        warn!("No market data for {} since {}, pausing entries.", pair, since);
+        let message = CoreMessage::MarketDataStale { core_id: self.id, pair, since };
+        (Command::PauseEntries, Some(message))
+      },
+      Staleness::Exit => {
+        warn!("Market data for {} still stale, exiting position.", pair);
+        (Command::ExitPosition(pair), None)
+      },
+      Staleness::Fresh => {
+        info!("Market data for {} recovered, resuming entries.", pair);
+        let message = CoreMessage::MarketDataFresh { core_id: self.id, pair };
+        (Command::ResumeEntries, Some(message))
+      },
+    };
+    if command_tx.send(command).await.is_err() {
+      error!(
+        market = &*format!("{:?}", pair),
+        why = "dropped receiver",
+        "failed to send staleness command to Trader command_rx"
+      );
+    }
+    if let Some(message) = message {
+      let _ = self.message_tx.send(message).await;
+    }
+  }
+  async fn send_to_traders(&self, command: Command) {
+    for (market, command_transmitter) in self.command_transmitters.iter() {
+      if command_transmitter.send(command.clone()).await.is_err() {
+        error!(
+          asset = &*format!("{:?}", market),
+          why = "dropped receiver",
+          "failed to send {:?} to Trader command_rx",
+          command
+        );
+      }
+    }
+  }
   async fn terminate_traders(&self, message: String) {
     self.exit_all_positions().await;
     for (market, command_transmitter) in self.command_transmitters.iter() {
@@ -202,6 +666,18 @@ impl Core {
       }
     }
   }
+  async fn reduce_position(&self, pair: Pair, share: f64) {
+    let Some(command_tx) = self.command_transmitters.get(&pair) else {
+      return warn!("No trader of {} to reduce the position of.", pair);
+    };
+    if command_tx.send(Command::ReducePosition(pair, share)).await.is_err() {
+      error!(
+        market = &*format!("{:?}", pair),
+        why = "dropped receiver",
+        "failed to send Command::ReducePosition to Trader command_rx"
+      );
+    }
+  }
   async fn exit_position(&self, pair: Pair) {
     if let Some((market_ref, command_tx)) = self.command_transmitters.get_key_value(&pair)
     {
@@ -220,63 +696,73 @@ impl Core {
       );
     }
   }
-  async fn generate_session_summary(&self) -> Result<(Vec<Table>, Table), CoreError> {
-    // Fetch statistics for each Market
-    let assets: Vec<_> = self.command_transmitters.clone().into_keys().collect();
-    let mut stats_per_market = Vec::new();
-    let core_id: Uuid = self.id.clone();
-    let stats: Vec<_> = assets
-      .into_iter()
-      .map(|asset| {
-        let portfolio_clone = self.portfolio.clone();
-        tokio::spawn(async move {
-          let mut portfolio = portfolio_clone.lock().await;
-          match portfolio.get_statistics(&core_id).await {
-            Ok(statistics) => Some((asset, statistics)),
-            Err(error) => {
-              error!(
-                ?error,
-                ?asset,
-                "failed to get Market statistics when generating trading session summary"
-              );
-              None
-            },
-          }
-        })
-      })
-      .collect();
-
-    for stat in stats {
-      let stat = stat.await.map_err(|e| CoreError::ReportError(e.to_string()))?;
-      if let Some(result) = stat {
-        stats_per_market.push(result);
+  /// Retrains on a background task, the outcome is sent on `retrained_tx`. Returns
+  /// whether retraining started.
+  fn start_retraining(
+    &self,
+    retrained_tx: mpsc::Sender<Result<RetrainingOutcome, CoreError>>,
+  ) -> bool {
+    let Some(configuration) = self.configuration.clone() else {
+      return false;
+    };
+    info!("Retraining the model of {}.", configuration.pair);
+    let config = self.retraining.clone();
+    let database = self.database.clone();
+    tokio::spawn(async move {
+      let outcome = retrain(&configuration, &config, database).await;
+      let _ = retrained_tx.send(outcome).await;
+    });
+    true
+  }
+  async fn handle_retraining(&mut self, outcome: Result<RetrainingOutcome, CoreError>) {
+    let entry = match &outcome {
+      Ok(outcome) => outcome.to_string(),
+      Err(e) => format!("Retraining failed: {}", e),
+    };
+    info!("{}", entry);
+    if let Err(e) =
+      self.database.lock().await.add_journal_entry(self.id, Utc::now(), &entry).await
+    {
+      warn!("Failed to record retraining: {}", e);
+    }
+    let Ok(outcome) = outcome else {
+      return;
+    };
+    if outcome.accepted {
+      if let Some(configuration) = self.configuration.as_mut() {
+        configuration.ensemble.swap_model(&outcome.candidate);
       }
+      self.swap_model(outcome.candidate).await;
     }
-
-    let mut database = self.database.lock().await;
-
-    let final_balance = database.get_balance(self.id).ok();
-    let min_start_time = if self.is_backtest {
-      stats_per_market
-        .iter()
-        .map(|(_, stats)| stats)
-        .min_by(|stats1, stats2| stats1.starting_time.cmp(&stats2.starting_time))
-        .map(|stats| stats.starting_time)
-        .to_owned()
-        .unwrap_or(Utc::now())
+  }
+  async fn swap_model(&self, model_id: ModelId) {
+    let pair = model_id.pair;
+    if let Some(command_tx) = self.command_transmitters.get(&pair) {
+      if command_tx.send(Command::SwapModel(model_id)).await.is_err() {
+        error!(
+          market = &*format!("{:?}", pair),
+          why = "dropped receiver",
+          "failed to send Command::SwapModel to Trader command_rx"
+        );
+      }
     } else {
-      Utc::now()
-    };
-    let mut statistics_summary = database.get_statistics(&core_id)?;
-    // Generate average statistics across all markets using session's exited Positions
-    let exited_positions = database.get_exited_positions(self.id)?;
-    statistics_summary.generate_summary(&exited_positions);
-    let exited_positions_table =
-      crate::statistic::exited_positions_table(exited_positions);
-    let stats_per_market: Vec<_> = stats_per_market
+      warn!(
+        market = &*format!("{:?}", pair),
+        why = "Engine has no trader_command_tx associated with provided Market",
+        "failed to swap model"
+      );
+    }
+  }
+  async fn generate_session_summary(&self) -> Result<(Vec<Table>, Table), CoreError> {
+    let mut database = self.database.lock().await;
+    let stats_per_market: Vec<_> = database
+      .get_pair_statistics(self.id)
       .into_iter()
-      .map(|(core_id, summary)| (core_id.to_string(), summary))
+      .map(|(pair, summary)| (pair.to_string(), summary))
       .collect();
+    let statistics_summary = database.get_aggregate_statistics(&self.id)?;
+    let exited_positions_table =
+      crate::statistic::exited_positions_table(database.get_exited_positions(self.id)?);
 
     let overall_stats_tables = crate::statistic::combine(
       stats_per_market
@@ -295,15 +781,14 @@ impl Core {
     starting_time: DateTime<Utc>,
   ) -> Result<(), CoreError> {
     let mut db = self.database.lock().await;
-    db.set_balance(
-      core_id,
-      Balance { time: Utc::now(), total: starting_cash, available: starting_cash },
-    )?;
-    db.set_statistics(
+    let balance =
+      Balance { time: starting_time, total: starting_cash, available: starting_cash };
+    db.init_balance(core_id, balance, self.statistics_config.reporting_currency)?;
+    db.init_statistics(
       core_id,
+      self.command_transmitters.keys().copied(),
       TradingSummary::init(self.statistics_config, Some(starting_time)),
-    )
-    .map_err(CoreError::RepositoryInteraction)?;
+    );
     log::info!("New core initiated in DB {}", core_id);
     Ok(())
   }
@@ -321,6 +806,15 @@ pub struct CoreBuilder {
   statistics_config: Option<StatisticConfig>,
   n_days_history_fetch: Option<i64>,
   is_backtest: Option<bool>,
+  event_rx: Option<broadcast::Receiver<Event>>,
+  alert_rules: Option<Vec<AlertRule>>,
+  staleness: Option<StalenessConfig>,
+  clock: Option<SharedClock>,
+  label: Option<SessionLabel>,
+  retraining: Option<(RetrainingConfig, CoreConfiguration)>,
+  backtest: Option<(watch::Receiver<BacktestProgress>, CancellationToken)>,
+  reconciled_asset: Option<(AccountName, Asset)>,
+  margin: Option<(MarginHealthConfig, BinanceClient)>,
 }
 
 impl CoreBuilder {
@@ -337,6 +831,15 @@ impl CoreBuilder {
       statistics_config: None,
       n_days_history_fetch: None,
       is_backtest: None,
+      event_rx: None,
+      alert_rules: None,
+      staleness: None,
+      clock: None,
+      label: None,
+      retraining: None,
+      backtest: None,
+      reconciled_asset: None,
+      margin: None,
     }
   }
   pub fn id(self, id: Uuid) -> Self {
@@ -372,7 +875,51 @@ impl CoreBuilder {
   pub fn is_backtest(self, value: bool) -> Self {
     CoreBuilder { is_backtest: Some(value), ..self }
   }
+  pub fn event_rx(self, value: broadcast::Receiver<Event>) -> Self {
+    CoreBuilder { event_rx: Some(value), ..self }
+  }
+  pub fn alert_rules(self, value: Vec<AlertRule>) -> Self {
+    CoreBuilder { alert_rules: Some(value), ..self }
+  }
+  pub fn staleness(self, value: StalenessConfig) -> Self {
+    CoreBuilder { staleness: Some(value), ..self }
+  }
+
+  /// Defaults to the wall clock.
+  pub fn clock(self, value: SharedClock) -> Self {
+    CoreBuilder { clock: Some(value), ..self }
+  }
+  /// Defaults to no label.
+  pub fn label(self, value: SessionLabel) -> Self {
+    CoreBuilder { label: Some(value), ..self }
+  }
+  /// Retrains the model of live runs on a schedule, optional.
+  pub fn retraining(self, config: RetrainingConfig, value: CoreConfiguration) -> Self {
+    CoreBuilder { retraining: Some((config, value)), ..self }
+  }
+  /// Progress of the backtest ticker, passed on to the UI, and its cancellation token,
+  /// optional.
+  pub fn backtest(
+    self,
+    progress: watch::Receiver<BacktestProgress>,
+    cancel: CancellationToken,
+  ) -> Self {
+    CoreBuilder { backtest: Some((progress, cancel)), ..self }
+  }
+  /// Follows the free balance of the asset in the updates of the account, optional.
+  pub fn reconciled_asset(self, account: AccountName, asset: Asset) -> Self {
+    CoreBuilder { reconciled_asset: Some((account, asset)), ..self }
+  }
+  /// Polls the margin health of the futures client and de-risks on it, optional.
+  pub fn margin_health(self, config: MarginHealthConfig, client: BinanceClient) -> Self {
+    CoreBuilder { margin: Some((config, client)), ..self }
+  }
   pub fn build(self) -> Result<Core, CoreError> {
+    let (backtest_progress, backtest_cancel) = self.backtest.unzip();
+    let (retraining, configuration) = match self.retraining {
+      Some((config, configuration)) => (config, Some(configuration)),
+      None => (RetrainingConfig::default(), None),
+    };
     let binance_client =
       self.binance_client.ok_or(CoreError::BuilderIncomplete("binance client"))?;
     let binance_client = Arc::new(binance_client);
@@ -398,7 +945,308 @@ impl CoreBuilder {
         .n_days_history_fetch
         .ok_or(CoreError::BuilderIncomplete("n_days_history_fetch"))?,
       is_backtest: self.is_backtest.ok_or(CoreError::BuilderIncomplete("is_backtest"))?,
+      event_rx: self.event_rx.ok_or(CoreError::BuilderIncomplete("event_rx"))?,
+      alert_rules: self.alert_rules.unwrap_or_default(),
+      staleness: self.staleness.unwrap_or_default(),
+      clock: self.clock.unwrap_or_else(real_clock),
+      label: self.label.unwrap_or_default(),
+      retraining,
+      configuration,
+      backtest_progress,
+      backtest_cancel,
+      reconciled_asset: self.reconciled_asset,
+      margin: self.margin.map(|(config, client)| (config, Arc::new(client))),
     };
     Ok(core)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    app::STATISTIC_CONFIG,
+    assets::{
+      asset_ticker,
+      candle_window::{self, CandleWindow},
+      Candle,
+    },
+    exchange::mock::{self, MockExchange},
+    features::FeaturePipeline,
+    portfolio::{allocator::Allocator, risk::RiskEvaluator},
+    strategy::{
+      ensemble::{Ensemble, EnsembleMember, VotingMethod},
+      onnx::ModelBackend,
+      SignalEvaluation, Strategy,
+    },
+    trading::{event_queue::EventQueue, execution::Execution},
+  };
+  use chrono::DurationRound;
+  use std::time::Duration;
+  use tokio::time::{sleep, timeout};
+
+  const PRICE: f64 = 100.0;
+  const TIMEOUT: Duration = Duration::from_secs(30);
+
+  /// Strategy module that buys on every candle.
+  const BUY_STRATEGY: &str = "def run(candle_time, pair, model_name, columns, rows):
+    return (\"buy\", 1.0)
+";
+
+  fn strategy_path(core_id: Uuid) -> PathBuf {
+    std::env::temp_dir().join(format!("meshetar-buy-{}.py", core_id))
+  }
+
+  /// Live session of BTCUSDT against the mock exchange with a strategy that always buys,
+  /// fetching a day of history first. Returns the core, its command sender and the
+  /// receiver of its messages.
+  async fn mock_session(
+    mock: &MockExchange,
+    core_id: Uuid,
+    database: Arc<Mutex<Database>>,
+  ) -> (Core, Sender<Command>, Receiver<CoreMessage>) {
+    let statistics_config =
+      StatisticConfig { starting_equity: 1000.0, ..STATISTIC_CONFIG };
+    let portfolio = Portfolio::builder()
+      .database(database.clone())
+      .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 1.0 })
+      .risk_manager(RiskEvaluator {})
+      .statistic_config(statistics_config)
+      .build()
+      .await
+      .unwrap();
+    let portfolio = Arc::new(Mutex::new(portfolio));
+
+    std::fs::write(strategy_path(core_id), BUY_STRATEGY).unwrap();
+    let member = EnsembleMember::new(
+      "mock".to_string(),
+      1.0,
+      FeaturePipeline::default(),
+      ModelBackend::Python,
+    )
+    .with_strategy(Some(strategy_path(core_id)));
+    let ensemble = Ensemble::new(vec![member], VotingMethod::default(), 0.0);
+
+    // Klines of the market stream reach the trader and core like they do in the app
+    let (events_tx, _) = broadcast::channel(100);
+    let pairs = vec![Pair::BTCUSDT];
+    let mut market_rx =
+      asset_ticker::new_ticker(pairs, mock.stream_url(), mock.client(), false)
+        .await
+        .unwrap();
+    let market_tx = events_tx.clone();
+    tokio::spawn(async move {
+      while let Some(event) = market_rx.recv().await {
+        if let ExchangeEvent::Market(market_event) = event {
+          let _ = market_tx.send(Event::Market(market_event));
+        }
+      }
+    });
+
+    let (core_command_tx, core_command_rx) = mpsc::channel(10);
+    let (message_tx, message_rx) = mpsc::channel(100);
+    let (trader_command_tx, trader_command_rx) = mpsc::channel(10);
+    let (event_tx, _event_rx) = mpsc::unbounded_channel();
+    let trader = Trader::builder()
+      .core_id(core_id)
+      .pair(Pair::BTCUSDT)
+      .command_reciever(trader_command_rx)
+      .message_tx(message_tx.clone())
+      .event_transmitter(crate::events::EventTx::new(event_tx))
+      .event_rx(events_tx.subscribe())
+      .portfolio(portfolio.clone())
+      .database(database.clone())
+      .strategy(Strategy::new(Pair::BTCUSDT, ensemble, SignalEvaluation::default()))
+      .candle_window(CandleWindow::new(candle_window::DEFAULT_CAPACITY))
+      .event_queue(EventQueue::default())
+      .execution(Execution::new(0.0, mock.client(), real_clock()))
+      .trading_is_live(true)
+      .build()
+      .unwrap();
+    let core = Core::builder()
+      .id(core_id)
+      .database(database)
+      .portfolio(portfolio)
+      .binance_client(mock.client())
+      .command_rx(core_command_rx)
+      .message_tx(message_tx)
+      .command_transmitters(HashMap::from([(Pair::BTCUSDT, trader_command_tx)]))
+      .traders(vec![trader])
+      .statistics_config(statistics_config)
+      .n_days_history_fetch(1)
+      .is_backtest(false)
+      .event_rx(events_tx.subscribe())
+      .build()
+      .unwrap();
+    (core, core_command_tx, message_rx)
+  }
+
+  /// Waits for the first message the filter picks out.
+  async fn next_message<T>(
+    messages: &mut Receiver<CoreMessage>,
+    mut filter: impl FnMut(CoreMessage) -> Option<T>,
+  ) -> T {
+    timeout(TIMEOUT, async {
+      loop {
+        let message = messages.recv().await.expect("Core stopped sending messages");
+        if let Some(found) = filter(message) {
+          return found;
+        }
+      }
+    })
+    .await
+    .unwrap()
+  }
+
+  /// Waits until the mock exchange received the number of orders.
+  async fn wait_for_orders(mock: &MockExchange, count: usize) {
+    timeout(TIMEOUT, async {
+      while mock.orders().await.len() < count {
+        sleep(Duration::from_millis(50)).await;
+      }
+    })
+    .await
+    .unwrap()
+  }
+
+  fn current_candle() -> Candle {
+    let minute = chrono::Duration::try_minutes(1).unwrap();
+    mock::candle(Utc::now().duration_trunc(minute).unwrap(), PRICE)
+  }
+
+  /// Without traders the session ends right after starting up, with the balance of its
+  /// starting equity.
+  #[tokio::test]
+  async fn balance_is_initialized_without_history_fetch() {
+    let core_id = Uuid::new_v4();
+    let statistics_config =
+      StatisticConfig { starting_equity: 1000.0, ..STATISTIC_CONFIG };
+    let database = Arc::new(Mutex::new(Database::test().await));
+    let portfolio = Portfolio::builder()
+      .database(database.clone())
+      .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 0.1 })
+      .risk_manager(RiskEvaluator {})
+      .statistic_config(statistics_config)
+      .build()
+      .await
+      .unwrap();
+    let (_command_tx, command_rx) = mpsc::channel(1);
+    let (message_tx, _message_rx) = mpsc::channel(10);
+    let (_event_tx, event_rx) = broadcast::channel(10);
+    let mut core = Core::builder()
+      .id(core_id)
+      .database(database.clone())
+      .portfolio(Arc::new(Mutex::new(portfolio)))
+      .binance_client(BinanceClient::with_url("http://127.0.0.1:1"))
+      .command_rx(command_rx)
+      .message_tx(message_tx)
+      .command_transmitters(HashMap::new())
+      .traders(vec![])
+      .statistics_config(statistics_config)
+      .n_days_history_fetch(0)
+      .is_backtest(false)
+      .event_rx(event_rx)
+      .build()
+      .unwrap();
+
+    timeout(Duration::from_secs(5), core.run()).await.unwrap().unwrap();
+    let balance = database.lock().await.get_balance(core_id).unwrap();
+    assert_eq!(balance.total, 1000.0);
+    assert_eq!(balance.available, 1000.0);
+    let _ = std::fs::remove_dir_all(session_dir(core_id));
+  }
+
+  /// A live session fetches its history, enters on a kline of the market stream, exits
+  /// on command and writes the trade to its summary, all against the mock exchange.
+  // The trader loop does not yield while its queue is empty, the other tasks need their
+  // own workers
+  #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+  async fn live_session_against_mock_exchange() {
+    let mock = MockExchange::start(PRICE).await;
+    let core_id = Uuid::new_v4();
+    let database = Arc::new(Mutex::new(Database::test().await));
+    let (mut core, command_tx, mut messages) =
+      mock_session(&mock, core_id, database.clone()).await;
+    let core = tokio::spawn(async move { core.run().await });
+
+    let candles = next_message(&mut messages, |message| match message {
+      CoreMessage::HistoryFetchDone { candles, .. } => Some(candles),
+      _ => None,
+    })
+    .await;
+    // A day of one minute candles, without the one still open
+    assert!((1439..=1440).contains(&candles), "fetched {} candles", candles);
+    next_message(&mut messages, |message| {
+      matches!(message, CoreMessage::TradersStarted { .. }).then_some(())
+    })
+    .await;
+
+    mock.send_kline(Pair::BTCUSDT, &current_candle(), true);
+    wait_for_orders(&mock, 1).await;
+    command_tx.send(Command::ExitPosition(Pair::BTCUSDT)).await.unwrap();
+    wait_for_orders(&mock, 2).await;
+    timeout(TIMEOUT, async {
+      while database.lock().await.get_exited_positions(core_id).unwrap().is_empty() {
+        sleep(Duration::from_millis(50)).await;
+      }
+    })
+    .await
+    .unwrap();
+
+    command_tx.send(Command::Terminate("Test finished".to_string())).await.unwrap();
+    let summary = next_message(&mut messages, |message| match message {
+      CoreMessage::SummaryReady { path, .. } => Some(path),
+      _ => None,
+    })
+    .await;
+    assert!(timeout(TIMEOUT, core).await.unwrap().unwrap().is_ok());
+
+    let sides: Vec<String> = mock
+      .orders()
+      .await
+      .iter()
+      .filter_map(|order| {
+        let mut params = order.url.query_pairs();
+        params.find(|(key, _)| key == "side").map(|(_, side)| side.into())
+      })
+      .collect();
+    assert_eq!(sides, ["BUY", "SELL"]);
+    let trades = std::fs::read_to_string(summary.with_file_name(TRADES_FILE)).unwrap();
+    assert_eq!(trades.lines().count(), 2, "{}", trades);
+    let _ = std::fs::remove_dir_all(session_dir(core_id));
+    let _ = std::fs::remove_file(strategy_path(core_id));
+  }
+
+  /// An order the exchange rejects for the balance opens no position and pauses the
+  /// trader, so the next signal sends no order.
+  #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+  async fn rejected_order_pauses_trader() {
+    let mock = MockExchange::start(PRICE).await;
+    let message = "Account has insufficient balance for requested action.";
+    mock.reject_orders(-2010, message).await;
+    let core_id = Uuid::new_v4();
+    let database = Arc::new(Mutex::new(Database::test().await));
+    let (mut core, command_tx, mut messages) =
+      mock_session(&mock, core_id, database.clone()).await;
+    let core = tokio::spawn(async move { core.run().await });
+    next_message(&mut messages, |message| {
+      matches!(message, CoreMessage::TradersStarted { .. }).then_some(())
+    })
+    .await;
+
+    let candle = current_candle();
+    mock.send_kline(Pair::BTCUSDT, &candle, true);
+    wait_for_orders(&mock, 1).await;
+    let minute = chrono::Duration::try_minutes(1).unwrap();
+    let next = mock::candle(candle.open_time + minute, PRICE);
+    mock.send_kline(Pair::BTCUSDT, &next, true);
+    sleep(Duration::from_millis(500)).await;
+    assert_eq!(mock.orders().await.len(), 1);
+    assert!(database.lock().await.get_all_open_positions(core_id).unwrap().is_empty());
+
+    command_tx.send(Command::Terminate("Test finished".to_string())).await.unwrap();
+    assert!(timeout(TIMEOUT, core).await.unwrap().unwrap().is_ok());
+    let _ = std::fs::remove_dir_all(session_dir(core_id));
+    let _ = std::fs::remove_file(strategy_path(core_id));
+  }
+}
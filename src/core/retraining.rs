@@ -0,0 +1,93 @@
+use super::error::CoreError;
+use crate::{
+  database::Database,
+  screens::run_config::CoreConfiguration,
+  strategy::{train_model, ModelId},
+  trading::backtest::backtest_on,
+};
+use serde::Deserialize;
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+/// Retraining of the model of live sessions, from `retraining` in the config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetrainingConfig {
+  /// Hours between retrainings, 0 turns them off.
+  pub every_hours: u64,
+  /// Latest candles left out of training, both models are backtested on them.
+  pub holdout_candles: usize,
+  /// Share of the starting equity the new model's PnL on the holdout candles has to
+  /// beat the current model's by to replace it.
+  pub margin: f64,
+}
+
+impl Default for RetrainingConfig {
+  fn default() -> Self {
+    Self { every_hours: 0, holdout_candles: 1440, margin: 0.01 }
+  }
+}
+
+impl RetrainingConfig {
+  pub fn is_enabled(&self) -> bool {
+    self.every_hours > 0
+  }
+
+  pub fn period(&self) -> Duration {
+    Duration::from_secs(self.every_hours.max(1) * 3600)
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct RetrainingOutcome {
+  pub candidate: ModelId,
+  /// PnL of the current ensemble on the holdout candles.
+  pub current_pnl: f64,
+  pub candidate_pnl: f64,
+  pub accepted: bool,
+}
+
+impl fmt::Display for RetrainingOutcome {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let verdict =
+      if self.accepted { "swapping it in" } else { "keeping the current one" };
+    write!(
+      f,
+      "Retrained model {}, holdout PnL {:.8} against {:.8} of the current model, {}",
+      self.candidate.name, self.candidate_pnl, self.current_pnl, verdict
+    )
+  }
+}
+
+/// Trains a new model of the session's pair without the holdout candles, then backtests
+/// it and the session's current ensemble on them. The new model is accepted when its PnL
+/// beats the current one by the margin.
+pub async fn retrain(
+  configuration: &CoreConfiguration,
+  config: &RetrainingConfig,
+  database: Arc<Mutex<Database>>,
+) -> Result<RetrainingOutcome, CoreError> {
+  let strategy =
+    configuration.ensemble.members.first().and_then(|member| member.strategy.clone());
+  let candidate =
+    train_model(configuration.pair, strategy, database.clone(), config.holdout_candles)
+      .await?
+      .to_model_id();
+  let holdout = CoreConfiguration {
+    run_live: false,
+    backtest_last_n_candles: config.holdout_candles,
+    ..configuration.clone()
+  };
+  let current_pnl =
+    backtest_on(holdout.clone(), database.clone()).await?.statistics.pnl.total_pnl;
+  let mut ensemble = holdout.ensemble.clone();
+  ensemble.swap_model(&candidate);
+  let candidate_pnl = backtest_on(CoreConfiguration { ensemble, ..holdout }, database)
+    .await?
+    .statistics
+    .pnl
+    .total_pnl;
+  let accepted =
+    candidate_pnl > current_pnl + config.margin * configuration.starting_equity;
+  Ok(RetrainingOutcome { candidate, current_pnl, candidate_pnl, accepted })
+}
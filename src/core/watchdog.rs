@@ -0,0 +1,84 @@
+use crate::assets::Pair;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Thresholds of the market data watchdog, from `staleness` in the config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StalenessConfig {
+  /// Seconds without a market event for a pair before its entries are paused.
+  pub pause_after: u64,
+  /// Seconds without a market event before the position of the pair is exited, none
+  /// keeps it open.
+  pub exit_after: Option<u64>,
+}
+
+impl Default for StalenessConfig {
+  fn default() -> Self {
+    Self { pause_after: 180, exit_after: None }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Staleness {
+  /// No market event since the time, past the pause threshold.
+  Stale(DateTime<Utc>),
+  /// Past the exit threshold.
+  Exit,
+  /// Market events arrive again after the pair was stale.
+  Fresh,
+}
+
+struct PairState {
+  last_event: DateTime<Utc>,
+  stale: bool,
+  exited: bool,
+}
+
+/// Tracks the age of the last market event per pair. Each threshold is reported once
+/// per stale period.
+pub struct StalenessWatchdog {
+  config: StalenessConfig,
+  pairs: HashMap<Pair, PairState>,
+}
+
+impl StalenessWatchdog {
+  pub fn new(
+    config: StalenessConfig,
+    pairs: impl IntoIterator<Item = Pair>,
+    now: DateTime<Utc>,
+  ) -> Self {
+    let pairs = pairs
+      .into_iter()
+      .map(|pair| (pair, PairState { last_event: now, stale: false, exited: false }))
+      .collect();
+    Self { config, pairs }
+  }
+
+  /// Returns [Staleness::Fresh] when the pair was stale.
+  pub fn market_event(&mut self, pair: Pair, now: DateTime<Utc>) -> Option<Staleness> {
+    let state = self.pairs.get_mut(&pair)?;
+    state.last_event = now;
+    state.exited = false;
+    std::mem::take(&mut state.stale).then_some(Staleness::Fresh)
+  }
+
+  /// Returns the thresholds crossed since the last check.
+  pub fn check(&mut self, now: DateTime<Utc>) -> Vec<(Pair, Staleness)> {
+    let mut crossed = Vec::new();
+    for (pair, state) in self.pairs.iter_mut() {
+      let age = (now - state.last_event).num_seconds();
+      if !state.stale && age >= self.config.pause_after as i64 {
+        state.stale = true;
+        crossed.push((*pair, Staleness::Stale(state.last_event)));
+      }
+      let exit_due = self.config.exit_after.is_some_and(|secs| age >= secs as i64);
+      if exit_due && !state.exited {
+        state.exited = true;
+        crossed.push((*pair, Staleness::Exit));
+      }
+    }
+    crossed
+  }
+}
@@ -0,0 +1,51 @@
+use super::{error::DatabaseError, sqlite::DB_POOL};
+use crate::assets::{Candle, Pair, CANDLE_INTERVAL};
+use chrono::{DateTime, Utc};
+
+enum Position {
+  From(Option<DateTime<Utc>>),
+  After(DateTime<Utc>),
+}
+
+/// Reads closed candles of a pair oldest first, one page per query. Pages are keyed by
+/// open time rather than offset, so each query is an index seek and memory stays
+/// bounded by the page size however long the history is.
+pub struct CandleCursor {
+  pair: Pair,
+  page_size: usize,
+  position: Position,
+}
+
+impl CandleCursor {
+  /// Starts at `from`, or at the oldest candle when it is `None`.
+  pub fn new(pair: Pair, from: Option<DateTime<Utc>>, page_size: usize) -> Self {
+    Self { pair, page_size, position: Position::From(from) }
+  }
+
+  /// Returns the next page, empty once the history is exhausted.
+  pub async fn next_page(&mut self) -> Result<Vec<Candle>, DatabaseError> {
+    let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+    let (condition, bound) = match self.position {
+      Position::From(None) => ("", None),
+      Position::From(Some(from)) => ("AND open_time >= ?3", Some(from)),
+      Position::After(after) => ("AND open_time > ?3", Some(after)),
+    };
+    let sql = format!(
+      "SELECT * FROM candles
+      WHERE asset = ?1 AND interval = ?2 AND is_closed = 1 {condition}
+      ORDER BY open_time ASC
+      LIMIT {}",
+      self.page_size
+    );
+    let mut query =
+      sqlx::query_as(&sql).bind(self.pair.to_string()).bind(CANDLE_INTERVAL.to_string());
+    if let Some(bound) = bound {
+      query = query.bind(bound);
+    }
+    let candles: Vec<Candle> = query.fetch_all(connection).await?;
+    if let Some(last) = candles.last() {
+      self.position = Position::After(last.open_time);
+    }
+    Ok(candles)
+  }
+}
@@ -16,4 +16,10 @@ pub enum DatabaseError {
   Initialization(String),
   #[error("DB errored out on exchange: {0}")]
   ExchangeError(#[from] ExchangeError),
+  #[error("Candle writer has stopped")]
+  WriterClosed,
+  #[error("Failed to write candle batch: {0}")]
+  BatchWrite(String),
+  #[error("Failed to archive candles: {0}")]
+  Archive(String),
 }
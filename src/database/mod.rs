@@ -1,28 +1,47 @@
+pub mod cursor;
 pub mod error;
+pub mod retention;
+pub mod snapshot;
 pub mod sqlite;
+pub mod writer;
 
-use self::{error::DatabaseError, sqlite::DB_POOL};
+use self::{
+  cursor::CandleCursor,
+  error::DatabaseError,
+  snapshot::DatabaseSnapshot,
+  sqlite::DB_POOL,
+  writer::{CandleWriter, WriterMetrics},
+};
 use crate::{
   assets::{
     asset_ticker::{self, KlineDetail},
     error::AssetError,
-    Candle, MarketEvent, MarketEventDetail, Pair,
+    Asset, Candle, MarketEvent, MarketEventDetail, Pair, PublicTrade, Side,
+    CANDLE_INTERVAL,
   },
   components::list::LabelValueItem,
   events::Event,
+  features::Derivatives,
   exchange::{
-    account::{self, get_account_from_exchange, new_account_stream, ExchangeAccount},
+    account::{
+      self, get_account_from_exchange, new_account_stream, AccountName, ExchangeAccount,
+    },
     binance_client::{self, BinanceClient},
   },
   portfolio::{
-    balance::{Balance, BalanceId},
+    balance::{AssetBalances, Balance, BalanceId, ExchangeRates},
     position::{determine_position_id, Position, PositionId},
+    regime::VolatilityRegime,
   },
-  statistic::TradingSummary,
+  statistic::{session_time::SessionTime, TradingSummary},
+  strategy::Signal,
+  trading::params::RuntimeParams,
   utils::formatting::duration_to_readable,
 };
 use chrono::{DateTime, Duration, Utc};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use sqlx::{query::Query, sqlite::SqliteArguments, FromRow, QueryBuilder, Sqlite};
+use std::{collections::HashMap, path::Path, str::FromStr};
 use tokio::sync::{
   broadcast,
   mpsc::{
@@ -30,7 +49,7 @@ use tokio::sync::{
     error::{SendError, TryRecvError},
     Receiver, Sender,
   },
-  Mutex,
+  watch, Mutex,
 };
 use uuid::Uuid;
 
@@ -38,24 +57,75 @@ pub struct Database {
   open_positions: HashMap<PositionId, Position>,
   closed_positions: HashMap<String, Vec<Position>>,
   current_balances: HashMap<BalanceId, Balance>,
-  exchange_balances: HashMap<String, Balance>,
-  statistics: HashMap<Uuid, TradingSummary>,
-  exchange_account: ExchangeAccount,
+  asset_balances: HashMap<Uuid, AssetBalances>,
+  exchange_rates: HashMap<Uuid, ExchangeRates>,
+  /// Balances of the assets of each account.
+  exchange_balances: HashMap<AccountName, HashMap<String, Balance>>,
+  statistics: HashMap<StatisticsId, TradingSummary>,
+  /// Statistics of every pair of a session together.
+  aggregate_statistics: HashMap<Uuid, TradingSummary>,
+  session_times: HashMap<Uuid, SessionTime>,
+  exchange_accounts: HashMap<AccountName, ExchangeAccount>,
   asset_prices: HashMap<String, KlineDetail>,
+  candle_writer: CandleWriter,
+  snapshot: watch::Sender<DatabaseSnapshot>,
 }
 impl Database {
   pub async fn new() -> Result<Database, DatabaseError> {
     sqlite::initialize().await?;
-    let database = Database {
+    Ok(Self::empty())
+  }
+
+  /// Database of a test, connected once per test run to a file in the temp directory.
+  #[cfg(test)]
+  pub async fn test() -> Database {
+    static CONNECTED: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+    CONNECTED
+      .get_or_init(|| async {
+        let file = format!("meshetar-test-{}.sqlite", std::process::id());
+        sqlite::set_path(std::env::temp_dir().join(file));
+        sqlite::initialize().await.unwrap();
+      })
+      .await;
+    Self::empty()
+  }
+
+  fn empty() -> Database {
+    Database {
       open_positions: HashMap::new(),
       closed_positions: HashMap::new(),
       current_balances: HashMap::new(),
+      asset_balances: HashMap::new(),
+      exchange_rates: HashMap::new(),
       exchange_balances: HashMap::new(),
       statistics: HashMap::new(),
-      exchange_account: ExchangeAccount::default(),
+      aggregate_statistics: HashMap::new(),
+      session_times: HashMap::new(),
+      exchange_accounts: HashMap::new(),
       asset_prices: HashMap::new(),
-    };
-    Ok(database)
+      candle_writer: CandleWriter::spawn(),
+      snapshot: watch::Sender::new(DatabaseSnapshot::default()),
+    }
+  }
+
+  /// Receiver of the state screens display, updated on every change to it.
+  pub fn subscribe(&self) -> watch::Receiver<DatabaseSnapshot> {
+    self.snapshot.subscribe()
+  }
+
+  /// Starting balance of a session, held in its reporting currency.
+  pub fn init_balance(
+    &mut self,
+    core_id: Uuid,
+    balance: Balance,
+    reporting_currency: Asset,
+  ) -> Result<(), DatabaseError> {
+    self.set_balance(core_id, balance)?;
+    self.set_asset_balances(core_id, AssetBalances::funded(reporting_currency, balance));
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.starting_balances.insert(core_id, balance);
+    });
+    Ok(())
   }
 
   pub fn set_balance(
@@ -64,6 +134,9 @@ impl Database {
     balance: Balance,
   ) -> Result<(), DatabaseError> {
     self.current_balances.insert(Balance::balance_id(core_id), balance);
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.balances.insert(core_id, balance);
+    });
     Ok(())
   }
 
@@ -76,36 +149,99 @@ impl Database {
     )
   }
 
-  pub fn set_exchange_balances(&mut self, exchange_balances: Vec<(String, Balance)>) {
+  pub fn set_asset_balances(&mut self, core_id: Uuid, balances: AssetBalances) {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.asset_balances.insert(core_id, balances.clone());
+    });
+    self.asset_balances.insert(core_id, balances);
+  }
+
+  pub fn get_asset_balances(
+    &self,
+    core_id: Uuid,
+  ) -> Result<AssetBalances, DatabaseError> {
+    self.asset_balances.get(&core_id).cloned().ok_or(DatabaseError::DataMissing(format!(
+      "Asset balances for {} missing on database lookup.",
+      core_id
+    )))
+  }
+
+  /// Prices of the session's pairs, for converting between assets.
+  pub fn record_price(&mut self, core_id: Uuid, pair: Pair, price: f64) {
+    self.exchange_rates.entry(core_id).or_default().update(pair, price);
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.exchange_rates.entry(core_id).or_default().update(pair, price);
+    });
+  }
+
+  pub fn get_exchange_rates(&self, core_id: Uuid) -> ExchangeRates {
+    self.exchange_rates.get(&core_id).cloned().unwrap_or_default()
+  }
+
+  /// Runtime parameters the traders of the session currently apply.
+  pub fn set_runtime_params(&mut self, core_id: Uuid, params: RuntimeParams) {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.runtime_params.insert(core_id, params);
+    });
+  }
+
+  pub fn set_volatility_regime(
+    &mut self,
+    core_id: Uuid,
+    pair: Pair,
+    regime: VolatilityRegime,
+  ) {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.volatility_regimes.entry(core_id).or_default().insert(pair, regime);
+    });
+  }
+
+  pub fn set_exchange_balances(
+    &mut self,
+    account: &AccountName,
+    exchange_balances: Vec<(String, Balance)>,
+  ) {
+    let account_balances = self.exchange_balances.entry(account.clone()).or_default();
     for (asset_name, balance) in exchange_balances {
-      self.exchange_balances.insert(asset_name, balance);
+      account_balances.insert(asset_name, balance);
     }
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.exchange_balances = self.exchange_balances.clone();
+    });
   }
 
-  pub fn get_exchange_balances(&self) -> HashMap<String, Balance> {
-    self.exchange_balances.clone()
+  pub fn get_exchange_balances(&self, account: &AccountName) -> HashMap<String, Balance> {
+    self.exchange_balances.get(account).cloned().unwrap_or_default()
   }
 
-  pub fn get_valuation(&self) -> (f64, f64) {
-    // TODO: add all other cryptos
-    let btc_valuation =
-      self.exchange_balances.get("BTC").unwrap_or(&Balance::default()).available;
-    let usdt_valuation =
-      self.exchange_balances.get("USDT").unwrap_or(&Balance::default()).available;
-    (btc_valuation, usdt_valuation)
+  pub fn get_exchange_account(&self, account: &AccountName) -> ExchangeAccount {
+    self.exchange_accounts.get(account).cloned().unwrap_or_default()
   }
 
-  pub fn get_exchange_account(&self) -> ExchangeAccount {
-    self.exchange_account.clone()
+  pub fn set_exchange_account(&mut self, account: &AccountName, value: ExchangeAccount) {
+    let balances = value.get_balances();
+    self.exchange_accounts.insert(account.clone(), value);
+    self.set_exchange_balances(account, balances);
   }
 
-  pub fn set_exchange_account(&mut self, value: ExchangeAccount) {
-    let balances = value.get_balances();
-    self.exchange_account = value;
-    self.set_exchange_balances(balances);
+  /// Whether the API key of the account can withdraw, as checked at startup.
+  pub fn set_withdrawals_enabled(&mut self, account: &AccountName, enabled: bool) {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.withdrawals_enabled.insert(account.clone(), enabled);
+    });
+  }
+
+  /// Account a live session trades on.
+  pub fn set_session_account(&mut self, core_id: Uuid, account: AccountName) {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.session_accounts.insert(core_id, account);
+    });
   }
 
   pub fn set_open_position(&mut self, position: Position) -> Result<(), DatabaseError> {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.open_positions.insert(position.position_id.clone(), position.clone());
+    });
     self.open_positions.insert(position.position_id.clone(), position);
     Ok(())
   }
@@ -153,15 +289,43 @@ impl Database {
     &mut self,
     position_id: &String,
   ) -> Result<Option<Position>, DatabaseError> {
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.open_positions.remove(position_id);
+    });
     Ok(self.open_positions.remove(position_id))
   }
 
-  pub fn set_exited_position(
+  /// Keeps the exited position in memory for the running session and persists it, so
+  /// analytics can be computed across sessions.
+  pub async fn set_exited_position(
     &mut self,
     core_id: Uuid,
     position: Position,
   ) -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    let exit_time = position
+      .meta
+      .exit_balance
+      .map(|balance| balance.time)
+      .unwrap_or(position.meta.update_time);
+    sqlx::query(
+      "INSERT OR REPLACE INTO exited_positions(core_id, position_id, asset, enter_time,
+        exit_time, realised_profit_loss, position)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
+    .bind(core_id.to_string())
+    .bind(&position.position_id)
+    .bind(position.asset.to_string())
+    .bind(position.meta.enter_time)
+    .bind(exit_time)
+    .bind(position.realised_profit_loss)
+    .bind(serde_json::to_string(&position)?)
+    .execute(connection)
+    .await?;
     let exited_positions_key = determine_exited_positions_id(core_id);
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.exited_positions.entry(core_id).or_default().push(position.clone());
+    });
     match self.closed_positions.get_mut(&exited_positions_key) {
       None => {
         self.closed_positions.insert(exited_positions_key, vec![position]);
@@ -171,6 +335,41 @@ impl Database {
     Ok(())
   }
 
+  /// Persists every open position, so they can be recovered after a crash. Returns how
+  /// many were written.
+  pub async fn flush_open_positions(&self) -> Result<usize, DatabaseError> {
+    let positions: Vec<Position> = self.open_positions.values().cloned().collect();
+    self.save_crash_positions(&positions).await?;
+    Ok(positions.len())
+  }
+
+  /// Writes the positions to `crash_open_positions`.
+  pub async fn save_crash_positions(
+    &self,
+    positions: &[Position],
+  ) -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+    let saved_at = Utc::now();
+    let mut tx = connection.begin().await?;
+    for position in positions {
+      sqlx::query(
+        "INSERT OR REPLACE INTO crash_open_positions(position_id, saved_at, position)
+        VALUES (?1, ?2, ?3)",
+      )
+      .bind(&position.position_id)
+      .bind(saved_at)
+      .bind(serde_json::to_string(position)?)
+      .execute(tx.as_mut())
+      .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+  }
+
+  pub fn get_all_statistics(&self) -> &HashMap<Uuid, TradingSummary> {
+    &self.aggregate_statistics
+  }
+
   pub fn get_exited_positions(
     &mut self,
     core_id: Uuid,
@@ -184,107 +383,594 @@ impl Database {
     )
   }
 
+  /// Stores historic candles. The last kline from the API may still be open, it is
+  /// finalized by a later upsert. Returns once all of them are written.
   pub async fn add_candles(
     &mut self,
     pair: Pair,
     candles: Vec<Candle>,
+  ) -> Result<(), DatabaseError> {
+    let now = Utc::now();
+    for candle in candles {
+      let is_closed = candle.close_time < now;
+      self.candle_writer.write(pair, candle, is_closed)?;
+    }
+    self.candle_writer.flush().await
+  }
+
+  /// Handle for queueing kline updates from the websocket without holding the
+  /// Database lock. Updates of the same kline overwrite each other until the closed
+  /// one arrives; closed rows are never overwritten.
+  pub fn candle_writer(&self) -> CandleWriter {
+    self.candle_writer.clone()
+  }
+
+  pub fn candle_writer_metrics(&self) -> WriterMetrics {
+    self.candle_writer.metrics()
+  }
+
+  pub async fn add_model_votes(
+    &mut self,
+    core_id: Uuid,
+    signal: &Signal,
   ) -> Result<(), DatabaseError> {
     let connection = DB_POOL.get().unwrap();
     let mut tx = connection.begin().await?;
-    for candle in candles {
+    for vote in signal.votes.iter() {
       sqlx::query(
-                r#"
-                INSERT OR REPLACE INTO candles(asset, open_time, open, high, low, close, close_time, volume, trade_count)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-                "#,
-            )
-            .bind(pair.to_string())
-            .bind(candle.open_time)
-            .bind(candle.open)
-            .bind(candle.high)
-            .bind(candle.low)
-            .bind(candle.close)
-            .bind(candle.close_time)
-            .bind(candle.volume)
-            .bind(candle.trade_count)
-            .execute(tx.as_mut())
-            .await?;
+        r#"
+        INSERT OR REPLACE INTO model_votes
+          (core_id, asset, time, model_name, weight, vote, confidence)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+      )
+      .bind(core_id.to_string())
+      .bind(signal.pair.to_string())
+      .bind(signal.time)
+      .bind(&vote.model_name)
+      .bind(vote.weight)
+      .bind(&vote.output)
+      .bind(vote.confidence)
+      .execute(tx.as_mut())
+      .await?;
     }
     tx.commit().await?;
     Ok(())
   }
 
+  pub async fn add_session(
+    &mut self,
+    core_id: Uuid,
+    started_at: DateTime<Utc>,
+    is_backtest: bool,
+    label: &SessionLabel,
+  ) -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    sqlx::query(
+      "INSERT OR IGNORE INTO sessions(core_id, started_at, is_backtest, label, notes)
+      VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(core_id.to_string())
+    .bind(started_at)
+    .bind(is_backtest)
+    .bind(&label.label)
+    .bind(&label.notes)
+    .execute(connection)
+    .await?;
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.session_labels.insert(core_id, label.clone());
+    });
+    Ok(())
+  }
+
+  pub async fn set_session_summary_path(
+    &mut self,
+    core_id: Uuid,
+    path: &Path,
+  ) -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    sqlx::query("UPDATE sessions SET summary_path = ?2 WHERE core_id = ?1")
+      .bind(core_id.to_string())
+      .bind(path.to_string_lossy())
+      .execute(connection)
+      .await?;
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.summary_paths.insert(core_id, path.to_path_buf());
+    });
+    Ok(())
+  }
+
+  /// Records a notable event of a session, like a change of its configuration.
+  pub async fn add_journal_entry(
+    &mut self,
+    core_id: Uuid,
+    time: DateTime<Utc>,
+    entry: &str,
+  ) -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    sqlx::query("INSERT INTO journal(core_id, time, entry) VALUES (?1, ?2, ?3)")
+      .bind(core_id.to_string())
+      .bind(time)
+      .bind(entry)
+      .execute(connection)
+      .await?;
+    Ok(())
+  }
+
   pub async fn fetch_all_candles(
     &mut self,
     pair: Pair,
   ) -> Result<Vec<Candle>, DatabaseError> {
     let connection = DB_POOL.get().unwrap();
-    let candles: Vec<Candle> = sqlx::query_as("SELECT * FROM candles WHERE asset = ?1")
-      .bind(pair.to_string())
-      .fetch_all(connection)
-      .await?;
+    let candles: Vec<Candle> = sqlx::query_as(
+      "SELECT * FROM candles
+      WHERE asset = ?1 AND interval = ?2 AND is_closed = 1
+      ORDER BY open_time ASC",
+    )
+    .bind(pair.to_string())
+    .bind(CANDLE_INTERVAL.to_string())
+    .fetch_all(connection)
+    .await?;
     Ok(candles)
   }
 
-  pub fn set_statistics(
+  /// Cursor over the last `last_n_candles` closed candles of a pair, or all of them
+  /// when there are fewer.
+  pub async fn candle_cursor(
+    &self,
+    pair: Pair,
+    last_n_candles: usize,
+    page_size: usize,
+  ) -> Result<CandleCursor, DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    let from: Option<DateTime<Utc>> = sqlx::query_scalar(
+      "SELECT open_time FROM candles
+      WHERE asset = ?1 AND interval = ?2 AND is_closed = 1
+      ORDER BY open_time DESC
+      LIMIT 1 OFFSET ?3",
+    )
+    .bind(pair.to_string())
+    .bind(CANDLE_INTERVAL.to_string())
+    .bind(last_n_candles.saturating_sub(1) as i64)
+    .fetch_optional(connection)
+    .await?;
+    Ok(CandleCursor::new(pair, from, page_size))
+  }
+
+  /// Closed candles of the pair in the database.
+  pub async fn count_closed_candles(&self, pair: Pair) -> Result<usize, DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    let count: i64 = sqlx::query_scalar(
+      "SELECT COUNT(*) FROM candles WHERE asset = ?1 AND interval = ?2 AND is_closed = 1",
+    )
+    .bind(pair.to_string())
+    .bind(CANDLE_INTERVAL.to_string())
+    .fetch_one(connection)
+    .await?;
+    Ok(count as usize)
+  }
+
+  /// Returns up to `limit` candles that opened before `until`, oldest first.
+  pub async fn fetch_candles_before(
+    &mut self,
+    pair: Pair,
+    until: DateTime<Utc>,
+    limit: usize,
+  ) -> Result<Vec<Candle>, DatabaseError> {
+    let connection = DB_POOL.get().unwrap();
+    let mut candles: Vec<Candle> = sqlx::query_as(
+      "SELECT * FROM candles
+      WHERE asset = ?1 AND interval = ?2 AND is_closed = 1 AND open_time < ?3
+      ORDER BY open_time DESC
+      LIMIT ?4",
+    )
+    .bind(pair.to_string())
+    .bind(CANDLE_INTERVAL.to_string())
+    .bind(until)
+    .bind(limit as i64)
+    .fetch_all(connection)
+    .await?;
+    candles.reverse();
+    Ok(candles)
+  }
+
+  /// Starts the statistics of every pair of the session and their aggregate.
+  pub fn init_statistics(
     &mut self,
     core_id: Uuid,
+    pairs: impl IntoIterator<Item = Pair>,
     statistic: TradingSummary,
+  ) {
+    for pair in pairs {
+      self.set_statistics((core_id, pair), statistic.clone());
+    }
+    self.set_aggregate_statistics(core_id, statistic);
+  }
+
+  /// Adds the exited position to the statistics of its pair and to the aggregate.
+  pub fn update_statistics(
+    &mut self,
+    core_id: Uuid,
+    position: &Position,
   ) -> Result<(), DatabaseError> {
-    self.statistics.insert(core_id, statistic);
+    let mut statistic = self.get_statistics(core_id, position.asset)?;
+    statistic.update(position);
+    self.set_statistics((core_id, position.asset), statistic);
+    let mut aggregate = self.get_aggregate_statistics(&core_id)?;
+    aggregate.update(position);
+    self.set_aggregate_statistics(core_id, aggregate);
     Ok(())
   }
 
-  pub fn generate_run_overview(
-    &mut self,
-    core_id: &Uuid,
-    pair: &Pair,
-  ) -> Result<Vec<LabelValueItem<String>>, DatabaseError> {
-    let duration = if let Some(stats) = self.statistics.get(core_id) {
-      Utc::now() - stats.starting_time
-    } else {
-      Duration::nanoseconds(0)
-    };
-    let open_trades = self.get_open_positions(core_id, vec![pair.clone().to_owned()]);
-    let closed_positions = self.get_exited_positions(core_id.clone().to_owned());
-    let n_closed_positions = {
-      if let Ok(trades) = closed_positions {
-        trades.len()
-      } else {
-        0
-      }
-    };
-
-    let balance = if let Ok(balance) = self.get_balance(core_id.clone().to_owned()) {
-      balance.total.to_string()
-    } else {
-      "No balance available.".to_string()
-    };
-    let rows: Vec<LabelValueItem<String>> = vec![
-      LabelValueItem::new("Pair".to_string(), pair.to_string()),
-      LabelValueItem::new(
-        "Duration".to_string(),
-        format!("{}", duration_to_readable(&duration)),
-      ),
-      LabelValueItem::new("Balance".to_string(), balance),
-      LabelValueItem::new("Trades".to_string(), (n_closed_positions).to_string()),
-    ];
-    Ok(rows)
+  fn set_statistics(&mut self, id: StatisticsId, statistic: TradingSummary) {
+    self.statistics.insert(id, statistic.clone());
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.pair_statistics.insert(id, statistic);
+    });
+  }
+
+  fn set_aggregate_statistics(&mut self, core_id: Uuid, statistic: TradingSummary) {
+    self.aggregate_statistics.insert(core_id, statistic.clone());
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.statistics.insert(core_id, statistic);
+    });
   }
 
   pub fn get_statistics(
-    &mut self,
+    &self,
+    core_id: Uuid,
+    pair: Pair,
+  ) -> Result<TradingSummary, DatabaseError> {
+    self.statistics.get(&(core_id, pair)).cloned().ok_or_else(|| {
+      DatabaseError::DataMissing(format!(
+        "Statistics for {} of {} missing on database lookup.",
+        pair, core_id
+      ))
+    })
+  }
+
+  /// Statistics of the pairs of the session, sorted by pair.
+  pub fn get_pair_statistics(&self, core_id: Uuid) -> Vec<(Pair, TradingSummary)> {
+    let mut statistics: Vec<(Pair, TradingSummary)> = self
+      .statistics
+      .iter()
+      .filter(|((id, _), _)| *id == core_id)
+      .map(|((_, pair), statistic)| (*pair, statistic.clone()))
+      .collect();
+    statistics.sort_by_key(|(pair, _)| pair.to_string());
+    statistics
+  }
+
+  pub fn get_aggregate_statistics(
+    &self,
     core_id: &Uuid,
   ) -> Result<TradingSummary, DatabaseError> {
-    let keys = self.statistics.keys();
-    self.statistics.get(core_id).copied().ok_or(DatabaseError::DataMissing(format!(
-      "Statistics for {} missing on database lookup. Available keys: {:?}",
-      core_id, keys
-    )))
+    let keys = self.aggregate_statistics.keys();
+    self.aggregate_statistics.get(core_id).cloned().ok_or(DatabaseError::DataMissing(
+      format!(
+        "Statistics for {} missing on database lookup. Available keys: {:?}",
+        core_id, keys
+      ),
+    ))
+  }
+
+  pub fn start_session_time(&mut self, core_id: Uuid, session_time: SessionTime) {
+    self.set_session_time(core_id, session_time);
+  }
+
+  /// Extends the market time the session covers.
+  pub fn record_market_time(&mut self, core_id: Uuid, time: DateTime<Utc>) {
+    if let Some(mut session_time) = self.session_times.get(&core_id).copied() {
+      session_time.market_event(time);
+      self.set_session_time(core_id, session_time);
+    }
+  }
+
+  pub fn finish_session_time(&mut self, core_id: Uuid, finished_at: DateTime<Utc>) {
+    if let Some(mut session_time) = self.session_times.get(&core_id).copied() {
+      session_time.finish(finished_at);
+      self.set_session_time(core_id, session_time);
+    }
+  }
+
+  pub fn get_session_time(&self, core_id: &Uuid) -> Option<SessionTime> {
+    self.session_times.get(core_id).copied()
   }
+
+  fn set_session_time(&mut self, core_id: Uuid, session_time: SessionTime) {
+    self.session_times.insert(core_id, session_time);
+    self.snapshot.send_modify(|snapshot| {
+      snapshot.session_times.insert(core_id, session_time);
+    });
+  }
+}
+
+/// Statistics are kept per pair of a session.
+pub type StatisticsId = (Uuid, Pair);
+
+/// Name and notes given to a session when it was started, to tell experiments apart.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionLabel {
+  pub label: String,
+  pub notes: String,
+}
+
+impl SessionLabel {
+  pub fn is_empty(&self) -> bool {
+    self.label.is_empty() && self.notes.is_empty()
+  }
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct SessionRecord {
+  pub core_id: String,
+  pub started_at: DateTime<Utc>,
+  pub is_backtest: bool,
+  pub label: String,
+  pub notes: String,
 }
 
 pub type ExitedPositionsId = String;
+
+/// Equity curve (cumulative realised PnL) of the latest backtest each model voted in,
+/// keyed by model name.
+pub async fn fetch_backtest_equity_by_model(
+) -> Result<HashMap<String, Vec<f64>>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let model_sessions: Vec<(String, String)> = sqlx::query_as(
+    "SELECT votes.model_name, sessions.core_id FROM sessions
+    JOIN (SELECT DISTINCT core_id, model_name FROM model_votes) votes
+      ON votes.core_id = sessions.core_id
+    WHERE sessions.is_backtest = 1
+    ORDER BY sessions.started_at ASC",
+  )
+  .fetch_all(connection)
+  .await?;
+  // Later sessions overwrite earlier ones, leaving the latest per model
+  let latest: HashMap<String, String> = model_sessions.into_iter().collect();
+  let mut equity_by_model = HashMap::with_capacity(latest.len());
+  for (model_name, core_id) in latest {
+    let profit_losses: Vec<f64> = sqlx::query_scalar(
+      "SELECT realised_profit_loss FROM exited_positions
+      WHERE core_id = ?1
+      ORDER BY exit_time ASC",
+    )
+    .bind(core_id)
+    .fetch_all(connection)
+    .await?;
+    let equity = profit_losses
+      .iter()
+      .scan(0.0, |equity, profit_loss| {
+        *equity += profit_loss;
+        Some(*equity)
+      })
+      .collect();
+    equity_by_model.insert(model_name, equity);
+  }
+  Ok(equity_by_model)
+}
+
+/// Every stored session, latest first.
+pub async fn fetch_sessions() -> Result<Vec<SessionRecord>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let sessions = sqlx::query_as(
+    "SELECT core_id, started_at, is_backtest, label, notes FROM sessions
+    ORDER BY started_at DESC",
+  )
+  .fetch_all(connection)
+  .await?;
+  Ok(sessions)
+}
+
+/// Closes of the latest `limit` closed candles of the pair by open time, oldest first.
+pub async fn fetch_latest_closes(
+  pair: Pair,
+  limit: usize,
+) -> Result<Vec<(DateTime<Utc>, f64)>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let mut closes: Vec<(DateTime<Utc>, f64)> = sqlx::query_as(
+    "SELECT open_time, close FROM candles
+    WHERE asset = ?1 AND interval = ?2 AND is_closed = 1
+    ORDER BY open_time DESC
+    LIMIT ?3",
+  )
+  .bind(pair.to_string())
+  .bind(CANDLE_INTERVAL.to_string())
+  .bind(limit as i64)
+  .fetch_all(connection)
+  .await?;
+  closes.reverse();
+  Ok(closes)
+}
+
+/// Daily Sharpe ratio of the latest scheduled backtest of the model on the pair.
+pub async fn fetch_last_scheduled_sharpe(
+  model_name: &str,
+  pair: Pair,
+) -> Result<Option<f64>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let sharpe_ratio = sqlx::query_scalar(
+    "SELECT sharpe_ratio FROM scheduled_backtests
+    WHERE model_name = ?1 AND asset = ?2
+    ORDER BY ran_at DESC LIMIT 1",
+  )
+  .bind(model_name)
+  .bind(pair.to_string())
+  .fetch_optional(connection)
+  .await?;
+  Ok(sharpe_ratio)
+}
+
+/// Stores the outcome of a scheduled backtest, for comparing the next run to it.
+pub async fn add_scheduled_backtest(
+  core_id: Uuid,
+  model_name: &str,
+  pair: Pair,
+  ran_at: DateTime<Utc>,
+  sharpe_ratio: f64,
+) -> Result<(), DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  sqlx::query(
+    "INSERT INTO scheduled_backtests(core_id, model_name, asset, ran_at, sharpe_ratio)
+    VALUES (?1, ?2, ?3, ?4, ?5)",
+  )
+  .bind(core_id.to_string())
+  .bind(model_name)
+  .bind(pair.to_string())
+  .bind(ran_at)
+  .bind(sharpe_ratio)
+  .execute(connection)
+  .await?;
+  Ok(())
+}
+
+/// Series of the perpetual future of a pair, ingested by `exchange::derivatives`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DerivativeSeries {
+  FundingRate,
+  OpenInterest,
+}
+
+impl DerivativeSeries {
+  fn table(&self) -> &'static str {
+    match self {
+      Self::FundingRate => "funding_rates",
+      Self::OpenInterest => "open_interest",
+    }
+  }
+
+  fn column(&self) -> &'static str {
+    match self {
+      Self::FundingRate => "rate",
+      Self::OpenInterest => "open_interest",
+    }
+  }
+}
+
+/// Stores values of the series, the ones already stored are kept.
+pub async fn add_derivative_series(
+  series: DerivativeSeries,
+  pair: Pair,
+  values: &[(DateTime<Utc>, f64)],
+) -> Result<(), DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let mut tx = connection.begin().await?;
+  for chunk in values.chunks(500) {
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
+      "INSERT OR IGNORE INTO {}(asset, time, {}) ",
+      series.table(),
+      series.column()
+    ));
+    query.push_values(chunk, |mut row, (time, value)| {
+      row.push_bind(pair.to_string()).push_bind(*time).push_bind(*value);
+    });
+    query.build().execute(tx.as_mut()).await?;
+  }
+  tx.commit().await?;
+  Ok(())
+}
+
+/// Time of the latest stored value of the series, ingestion continues from it.
+pub async fn fetch_latest_derivative_time(
+  series: DerivativeSeries,
+  pair: Pair,
+) -> Result<Option<DateTime<Utc>>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let time = sqlx::query_scalar(&format!(
+    "SELECT time FROM {} WHERE asset = ?1 ORDER BY time DESC LIMIT 1",
+    series.table()
+  ))
+  .bind(pair.to_string())
+  .fetch_optional(connection)
+  .await?;
+  Ok(time)
+}
+
+/// Values of the series from `from` up to `until`, oldest first, along with the last
+/// one before `from` that is still in effect at it.
+pub async fn fetch_derivative_series(
+  series: DerivativeSeries,
+  pair: Pair,
+  from: DateTime<Utc>,
+  until: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, f64)>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let (table, column) = (series.table(), series.column());
+  let values = sqlx::query_as(&format!(
+    "SELECT time, {column} FROM {table}
+    WHERE asset = ?1 AND time <= ?3 AND time >= COALESCE(
+      (SELECT MAX(time) FROM {table} WHERE asset = ?1 AND time <= ?2), ?2
+    )
+    ORDER BY time ASC"
+  ))
+  .bind(pair.to_string())
+  .bind(from)
+  .bind(until)
+  .fetch_all(connection)
+  .await?;
+  Ok(values)
+}
+
+/// Funding rates and open interest of the pair between the open times of the candles.
+pub async fn fetch_derivatives(
+  pair: Pair,
+  candles: &[Candle],
+) -> Result<Derivatives, DatabaseError> {
+  let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+    return Ok(Derivatives::default());
+  };
+  let (from, until) = (first.open_time, last.open_time);
+  let fetch = |series| fetch_derivative_series(series, pair, from, until);
+  Ok(Derivatives {
+    funding_rates: fetch(DerivativeSeries::FundingRate).await?,
+    open_interest: fetch(DerivativeSeries::OpenInterest).await?,
+  })
+}
+
+/// Recorded aggregate trades of the pair from `from` up to `until`, oldest first.
+pub async fn fetch_trades(
+  pair: Pair,
+  from: DateTime<Utc>,
+  until: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, PublicTrade)>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let rows: Vec<(DateTime<Utc>, String, f64, f64, String)> = sqlx::query_as(
+    "SELECT time, id, price, quantity, side FROM agg_trades
+    WHERE asset = ?1 AND time >= ?2 AND time <= ?3
+    ORDER BY time ASC, id ASC",
+  )
+  .bind(pair.to_string())
+  .bind(from)
+  .bind(until)
+  .fetch_all(connection)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .filter_map(|(time, id, price, amount, side)| {
+        let side = Side::from_str(&side).ok()?;
+        Some((time, PublicTrade { id, price, amount, side }))
+      })
+      .collect(),
+  )
+}
+
+/// Exited positions persisted by any session, or only by the given one, oldest first.
+pub async fn fetch_exited_positions(
+  core_id: Option<Uuid>,
+) -> Result<Vec<Position>, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let rows: Vec<String> = sqlx::query_scalar(
+    "SELECT position FROM exited_positions
+    WHERE ?1 IS NULL OR core_id = ?1
+    ORDER BY exit_time ASC",
+  )
+  .bind(core_id.map(|core_id| core_id.to_string()))
+  .fetch_all(connection)
+  .await?;
+  rows.iter().map(|row| serde_json::from_str(row).map_err(DatabaseError::from)).collect()
+}
+
 pub fn determine_exited_positions_id(core_id: Uuid) -> ExitedPositionsId {
   format!("positions_exited_{}", core_id)
 }
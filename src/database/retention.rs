@@ -0,0 +1,182 @@
+use super::{error::DatabaseError, sqlite::DB_POOL};
+use crate::{assets::Candle, paths};
+use chrono::{DateTime, Duration, Utc};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use std::{
+  collections::HashMap,
+  fs::OpenOptions,
+  io::{BufWriter, Write},
+  path::{Path, PathBuf},
+};
+use tracing::{error, info};
+
+/// How long candles are kept, per interval (`"1m"`, `"1h"`, ...). Intervals without an
+/// entry are kept forever, so the default policy never deletes anything.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RetentionPolicy {
+  #[serde(default)]
+  pub keep_days: HashMap<String, u32>,
+  /// Export candles to CSV under the data directory before deleting them.
+  #[serde(default = "default_archive")]
+  pub archive: bool,
+  #[serde(default = "default_prune_every_hours")]
+  pub prune_every_hours: u64,
+}
+
+fn default_archive() -> bool {
+  true
+}
+
+fn default_prune_every_hours() -> u64 {
+  24
+}
+
+impl Default for RetentionPolicy {
+  fn default() -> Self {
+    Self {
+      keep_days: HashMap::new(),
+      archive: default_archive(),
+      prune_every_hours: default_prune_every_hours(),
+    }
+  }
+}
+
+impl RetentionPolicy {
+  pub fn is_empty(&self) -> bool {
+    self.keep_days.is_empty()
+  }
+}
+
+pub fn archive_dir() -> PathBuf {
+  paths::data_dir().join("archive")
+}
+
+/// Prunes once at start and then periodically, for as long as the app runs.
+pub fn spawn_pruning(policy: RetentionPolicy) {
+  if policy.is_empty() {
+    return;
+  }
+  tokio::spawn(async move {
+    let period = std::time::Duration::from_secs(policy.prune_every_hours.max(1) * 3600);
+    let mut interval = tokio::time::interval(period);
+    loop {
+      interval.tick().await;
+      if let Err(e) = prune(&policy, Utc::now()).await {
+        error!("Error pruning candles: {}", e);
+      }
+    }
+  });
+}
+
+/// Deletes closed candles older than the policy allows, archiving them first if enabled.
+/// Returns the number of deleted candles.
+pub async fn prune(
+  policy: &RetentionPolicy,
+  now: DateTime<Utc>,
+) -> Result<u64, DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let mut n_deleted = 0;
+  for (interval, days) in policy.keep_days.iter() {
+    let Some(cutoff) =
+      Duration::try_days(i64::from(*days)).and_then(|keep| now.checked_sub_signed(keep))
+    else {
+      continue;
+    };
+    let assets: Vec<String> = sqlx::query_scalar(
+      "SELECT DISTINCT asset FROM candles
+      WHERE interval = ?1 AND is_closed = 1 AND open_time < ?2",
+    )
+    .bind(interval)
+    .bind(cutoff)
+    .fetch_all(connection)
+    .await?;
+    for asset in assets {
+      if policy.archive {
+        archive(&archive_dir(), &asset, interval, cutoff).await?;
+      }
+      let result = sqlx::query(
+        "DELETE FROM candles
+        WHERE asset = ?1 AND interval = ?2 AND is_closed = 1 AND open_time < ?3",
+      )
+      .bind(&asset)
+      .bind(interval)
+      .bind(cutoff)
+      .execute(connection)
+      .await?;
+      info!(
+        "Pruned {} {} {} candles before {}",
+        result.rows_affected(),
+        asset,
+        interval,
+        cutoff
+      );
+      n_deleted += result.rows_affected();
+    }
+  }
+  if n_deleted > 0 {
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(connection).await?;
+  }
+  Ok(n_deleted)
+}
+
+/// Writes all closed candles of the asset to a new `<dir>/<asset>_<interval>.csv`.
+pub async fn export(
+  dir: &Path,
+  asset: &str,
+  interval: &str,
+) -> Result<PathBuf, DatabaseError> {
+  let path = dir.join(format!("{asset}_{interval}.csv"));
+  if path.exists() {
+    return Err(DatabaseError::Archive(format!("{} already exists", path.display())));
+  }
+  archive(dir, asset, interval, Utc::now()).await?;
+  Ok(path)
+}
+
+/// Appends candles older than `cutoff` to `<dir>/<asset>_<interval>.csv`, streaming them
+/// from the database.
+async fn archive(
+  dir: &Path,
+  asset: &str,
+  interval: &str,
+  cutoff: DateTime<Utc>,
+) -> Result<(), DatabaseError> {
+  let connection = DB_POOL.get().ok_or(DatabaseError::ReadError)?;
+  let io_error = |e: std::io::Error| DatabaseError::Archive(e.to_string());
+  std::fs::create_dir_all(dir).map_err(io_error)?;
+  let path = dir.join(format!("{asset}_{interval}.csv"));
+  let is_new = !path.exists();
+  let file =
+    OpenOptions::new().create(true).append(true).open(&path).map_err(io_error)?;
+  let mut file = BufWriter::new(file);
+  if is_new {
+    writeln!(file, "open_time,close_time,open,high,low,close,volume,trade_count")
+      .map_err(io_error)?;
+  }
+  let mut candles = sqlx::query_as::<_, Candle>(
+    "SELECT * FROM candles
+    WHERE asset = ?1 AND interval = ?2 AND is_closed = 1 AND open_time < ?3
+    ORDER BY open_time ASC",
+  )
+  .bind(asset)
+  .bind(interval)
+  .bind(cutoff)
+  .fetch(connection);
+  while let Some(candle) = candles.try_next().await? {
+    writeln!(
+      file,
+      "{},{},{},{},{},{},{},{}",
+      candle.open_time.to_rfc3339(),
+      candle.close_time.to_rfc3339(),
+      candle.open,
+      candle.high,
+      candle.low,
+      candle.close,
+      candle.volume,
+      candle.trade_count
+    )
+    .map_err(io_error)?;
+  }
+  file.flush().map_err(io_error)
+}
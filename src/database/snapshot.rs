@@ -0,0 +1,245 @@
+use super::{SessionLabel, StatisticsId};
+use crate::{
+  assets::{Asset, Pair},
+  components::list::LabelValueItem,
+  exchange::account::AccountName,
+  portfolio::{
+    balance::{AssetBalances, Balance, ExchangeRates},
+    position::{Position, PositionId},
+    regime::VolatilityRegime,
+  },
+  statistic::{session_time::SessionTime, TradingSummary},
+  trading::params::RuntimeParams,
+  utils::formatting::{duration_to_readable, format_amount, format_number},
+};
+use chrono::{Duration, Utc};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use uuid::Uuid;
+
+/// Read-only copy of the state screens display. The Database publishes it on a watch
+/// channel whenever that state changes, so drawing never waits on the trading path.
+#[derive(Clone, Debug, Default)]
+pub struct DatabaseSnapshot {
+  /// Balance of a session in its reporting currency.
+  pub balances: HashMap<Uuid, Balance>,
+  pub asset_balances: HashMap<Uuid, AssetBalances>,
+  pub starting_balances: HashMap<Uuid, Balance>,
+  pub open_positions: HashMap<PositionId, Position>,
+  pub exchange_rates: HashMap<Uuid, ExchangeRates>,
+  pub exited_positions: HashMap<Uuid, Vec<Position>>,
+  pub exchange_balances: HashMap<AccountName, HashMap<String, Balance>>,
+  /// Aggregate statistics of every pair of a session.
+  pub statistics: HashMap<Uuid, TradingSummary>,
+  pub pair_statistics: HashMap<StatisticsId, TradingSummary>,
+  pub summary_paths: HashMap<Uuid, PathBuf>,
+  pub session_times: HashMap<Uuid, SessionTime>,
+  pub runtime_params: HashMap<Uuid, RuntimeParams>,
+  /// Volatility regime of each pair of a session, once classified.
+  pub volatility_regimes: HashMap<Uuid, HashMap<Pair, VolatilityRegime>>,
+  pub session_labels: HashMap<Uuid, SessionLabel>,
+  pub session_accounts: HashMap<Uuid, AccountName>,
+  /// Whether the API key of the account can withdraw, of the accounts checked so far.
+  pub withdrawals_enabled: HashMap<AccountName, bool>,
+}
+
+/// PnL of a session in its reporting currency.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionPnl {
+  /// Of the exited positions.
+  pub realised: f64,
+  /// Of the open positions, marked to the latest price.
+  pub unrealised: f64,
+  pub starting_equity: f64,
+}
+
+impl SessionPnl {
+  /// Share of the starting equity.
+  pub fn percentage(&self, pnl: f64) -> f64 {
+    if self.starting_equity == 0.0 {
+      0.0
+    } else {
+      pnl / self.starting_equity * 100.0
+    }
+  }
+
+  /// "+12.50000000 (+1.25%)"
+  pub fn describe(&self, pnl: f64) -> String {
+    format!("{:+.8} ({:+.2}%)", pnl, self.percentage(pnl))
+  }
+}
+
+impl DatabaseSnapshot {
+  pub fn valuation(&self, account: &AccountName) -> (f64, f64) {
+    // TODO: add all other cryptos
+    let balances = self.exchange_balances.get(account);
+    let available = |asset: &str| {
+      let balance = balances.and_then(|balances| balances.get(asset));
+      balance.copied().unwrap_or_default().available
+    };
+    (available("BTC"), available("USDT"))
+  }
+
+  pub fn run_overview(&self, core_id: &Uuid, pair: &Pair) -> Vec<LabelValueItem<String>> {
+    let duration = match self.session_times.get(core_id) {
+      Some(session_time) => session_time.describe(Utc::now()),
+      None => duration_to_readable(&Duration::zero()),
+    };
+    let balance = match self.balances.get(core_id) {
+      Some(balance) => balance.total.to_string(),
+      None => "No balance available.".to_string(),
+    };
+    let n_closed_positions = self.exited_positions.get(core_id).map_or(0, Vec::len);
+    let mut overview = vec![
+      LabelValueItem::new("Pair".to_string(), pair.to_string()),
+      LabelValueItem::new("Duration".to_string(), duration),
+      LabelValueItem::new("Balance".to_string(), balance),
+      LabelValueItem::new("Trades".to_string(), n_closed_positions.to_string()),
+    ];
+    if let Some(account) = self.session_accounts.get(core_id) {
+      overview.insert(1, LabelValueItem::new("Account".to_string(), account.to_string()));
+    }
+    if let Some(pnl) = self.session_pnl(core_id) {
+      overview.extend([
+        LabelValueItem::new("Realised PnL".to_string(), pnl.describe(pnl.realised)),
+        LabelValueItem::new("Unrealised PnL".to_string(), pnl.describe(pnl.unrealised)),
+      ]);
+    }
+    // Sessions trading pairs of different quote assets hold more than one
+    let assets = self.asset_balances.get(core_id).map(|assets| &assets.balances);
+    if let Some(balances) = assets.filter(|balances| balances.len() > 1) {
+      overview.extend(balances.iter().map(|(asset, balance)| {
+        LabelValueItem::new(format!("{asset} balance"), format!("{:.8}", balance.total))
+      }));
+    }
+    if let Some(stats) = self.statistics.get(core_id) {
+      // Windows only advance on exits, so expire a copy against the wall clock.
+      let mut rolling = stats.rolling.clone();
+      rolling.expire(Utc::now());
+      for (label, window) in [("24h", &rolling.last_day), ("7d", &rolling.last_week)] {
+        overview.extend([
+          LabelValueItem::new(format!("{label} PnL"), format!("{:.8}", window.pnl)),
+          LabelValueItem::new(
+            format!("{label} win rate"),
+            format!("{:.2}%", window.win_rate() * 100.0),
+          ),
+          LabelValueItem::new(format!("{label} trades"), window.trades.to_string()),
+        ]);
+      }
+    }
+    overview
+  }
+
+  pub fn session_pnl(&self, core_id: &Uuid) -> Option<SessionPnl> {
+    let starting_equity = self.starting_balances.get(core_id)?.total;
+    let total = self.balances.get(core_id)?.total;
+    let reporting = self.asset_balances.get(core_id).map(|a| a.reporting_currency);
+    let rates = self.exchange_rates.get(core_id).cloned().unwrap_or_default();
+    let core_id = core_id.to_string();
+    let unrealised = self
+      .open_positions
+      .values()
+      .filter(|position| position.position_id.starts_with(&core_id))
+      .map(|position| {
+        let quote = position.asset.quote();
+        let rate = rates.rate(quote, reporting.unwrap_or(quote)).unwrap_or(1.0);
+        position.unrealised_profit_loss * rate
+      })
+      .sum();
+    Some(SessionPnl { realised: total - starting_equity, unrealised, starting_equity })
+  }
+
+  /// Open positions of the session, sorted by pair.
+  pub fn open_positions(&self, core_id: &Uuid) -> Vec<Position> {
+    let core_id = core_id.to_string();
+    let mut positions: Vec<Position> = self
+      .open_positions
+      .values()
+      .filter(|position| position.position_id.starts_with(&core_id))
+      .cloned()
+      .collect();
+    positions.sort_by_key(|position| position.asset.to_string());
+    positions
+  }
+
+  pub fn report_overview(&self, core_id: &Uuid) -> Option<Vec<LabelValueItem<String>>> {
+    let mut overview = self.statistics.get(core_id)?.generate_short_report();
+    if let Some(session_time) = self.session_times.get(core_id) {
+      let duration = session_time.describe(Utc::now());
+      overview.insert(0, LabelValueItem::new("Duration".to_string(), duration));
+    }
+    if let Some(label) = self.session_labels.get(core_id) {
+      if !label.notes.is_empty() {
+        overview.insert(0, LabelValueItem::new("Notes".to_string(), label.notes.clone()));
+      }
+      if !label.label.is_empty() {
+        overview.insert(0, LabelValueItem::new("Label".to_string(), label.label.clone()));
+      }
+    }
+    Some(overview)
+  }
+
+  /// Pairs of the session, sorted by name.
+  pub fn report_pairs(&self, core_id: &Uuid) -> Vec<Pair> {
+    let mut pairs: Vec<Pair> = self
+      .pair_statistics
+      .keys()
+      .filter(|(id, _)| id == core_id)
+      .map(|(_, pair)| *pair)
+      .collect();
+    pairs.sort_by_key(Pair::to_string);
+    pairs
+  }
+
+  pub fn pair_report_overview(
+    &self,
+    core_id: &Uuid,
+    pair: &Pair,
+  ) -> Option<Vec<LabelValueItem<String>>> {
+    let statistics = self.pair_statistics.get(&(*core_id, *pair));
+    statistics.map(TradingSummary::generate_short_report)
+  }
+
+  /// Drawdown after every exit of the session, or of one of its pairs.
+  pub fn drawdown_curve(&self, core_id: &Uuid, pair: Option<&Pair>) -> Vec<f64> {
+    let statistics = match pair {
+      Some(pair) => self.pair_statistics.get(&(*core_id, *pair)),
+      None => self.statistics.get(core_id),
+    };
+    statistics.map(|statistics| statistics.drawdown.curve.values()).unwrap_or_default()
+  }
+
+  /// Accounts with balances from the exchange, sorted by name.
+  pub fn exchange_accounts(&self) -> Vec<AccountName> {
+    let mut accounts: Vec<AccountName> = self.exchange_balances.keys().cloned().collect();
+    accounts.sort();
+    accounts
+  }
+
+  /// Accounts whose API keys can withdraw, sorted by name.
+  pub fn withdrawal_accounts(&self) -> Vec<AccountName> {
+    let mut accounts: Vec<AccountName> = self
+      .withdrawals_enabled
+      .iter()
+      .filter(|(_, enabled)| **enabled)
+      .map(|(account, _)| account.clone())
+      .collect();
+    accounts.sort();
+    accounts
+  }
+
+  /// Exchange balances of the account sorted by asset name.
+  pub fn sorted_exchange_balances(
+    &self,
+    account: &AccountName,
+  ) -> Vec<(String, Balance)> {
+    let mut balances: Vec<(String, Balance)> = self
+      .exchange_balances
+      .get(account)
+      .into_iter()
+      .flatten()
+      .map(|(asset, balance)| (asset.clone(), *balance))
+      .collect();
+    balances.sort_by(|a, b| a.0.cmp(&b.0));
+    balances
+  }
+}
@@ -1,16 +1,62 @@
-use sqlx::{Pool, Sqlite, SqlitePool};
-use std::{fs::File, path::Path};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous},
+    Pool, Sqlite, SqlitePool,
+};
+use std::{path::PathBuf, str::FromStr, sync::OnceLock, time::Duration};
 use tokio::sync::OnceCell;
 
 use super::error::DatabaseError;
+use crate::{paths, utils::load_config::read_config};
 
 pub static DB_POOL: OnceCell<Pool<Sqlite>> = OnceCell::const_new();
 
+/// Set by `--db`, takes precedence over the database of the config profile.
+static DB_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Opens this file instead of the database of the config profile. Has no effect once the
+/// connection is set.
+pub fn set_path(path: PathBuf) {
+    let _ = DB_PATH_OVERRIDE.set(path);
+}
+
+/// The `--db` override, else the database of the config profile, else `database.sqlite`.
+/// Databases of the config are relative to the data directory.
+pub fn database_path() -> PathBuf {
+    match DB_PATH_OVERRIDE.get() {
+        Some(path) => path.clone(),
+        None => paths::resolve(
+            &read_config()
+                .map(|config| config.database)
+                .unwrap_or_else(|_| PathBuf::from("database.sqlite")),
+        ),
+    }
+}
+
+/// Candles are keyed by interval too. Rows of a kline that has not closed yet have
+/// `is_closed = 0` and get overwritten by later updates until the kline closes.
+const CREATE_CANDLES_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS candles (
+        asset TEXT NOT NULL,
+        interval TEXT NOT NULL,
+        open_time INTEGER NOT NULL,
+        open REAL NOT NULL,
+        high REAL NOT NULL,
+        low REAL NOT NULL,
+        close REAL NOT NULL,
+        close_time INTEGER NOT NULL,
+        volume REAL NOT NULL,
+        trade_count INTEGER NOT NULL,
+        is_closed INTEGER NOT NULL,
+        PRIMARY KEY (asset, interval, open_time)
+    );";
+
 pub async fn initialize() -> Result<(), DatabaseError> {
     println!("Initializing database.");
     match set_connection().await {
         Ok(_) => {
+            migrate_candles().await?;
             setup_tables().await?;
+            migrate_sessions().await?;
             Ok(())
         }
         Err(e) => Err(e),
@@ -18,13 +64,20 @@ pub async fn initialize() -> Result<(), DatabaseError> {
 }
 
 pub async fn set_connection() -> Result<(), DatabaseError> {
-    // Creates the database file if it doesnt exist
-    let database_path = "database.sqlite";
-    if Path::new(database_path).exists() == false {
-        File::create(database_path).map_err(|e| DatabaseError::Initialization(e.to_string()))?;
-    }
+    // Creates the database file if it doesnt exist. WAL lets readers run while candles
+    // are written, and with WAL a NORMAL sync is still safe against corruption.
+    let path = database_path();
+    log::info!("Opening database {}.", path.display());
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(Duration::from_secs(5))
+        .pragma("temp_store", "memory")
+        .pragma("cache_size", "-16000");
     // Creates a new pool
-    let pool = SqlitePool::connect("database.sqlite")
+    let pool = SqlitePool::connect_with(options)
         .await
         .map_err(|e| DatabaseError::Initialization(e.to_string()))?;
     DB_POOL
@@ -61,18 +114,6 @@ pub async fn setup_tables() -> Result<(), DatabaseError> {
             btc_valuation REAL NOT NULL,
             busd_valuation REAL NOT NULL
         );
-        CREATE TABLE IF NOT EXISTS candles (
-            asset TEXT NOT NULL,
-            open_time INTEGER NOT NULL, 
-            open REAL NOT NULL, 
-            high REAL NOT NULL,
-            low REAL NOT NULL, 
-            close REAL NOT NULL, 
-            close_time INTEGER NOT NULL, 
-            volume REAL NOT NULL, 
-            trade_count INTEGER NOT NULL,
-            PRIMARY KEY (open_time, asset)
-        );
         CREATE TABLE IF NOT EXISTS indicators (
             symbol TEXT NOT NULL,
             interval TEXT NOT NULL,
@@ -105,6 +146,74 @@ pub async fn setup_tables() -> Result<(), DatabaseError> {
             signal TEXT NOT NULL,
             PRIMARY KEY (symbol, interval, time)
         );
+        CREATE TABLE IF NOT EXISTS model_votes (
+            core_id TEXT NOT NULL,
+            asset TEXT NOT NULL,
+            time DATETIME NOT NULL,
+            model_name TEXT NOT NULL,
+            weight REAL NOT NULL,
+            vote TEXT NOT NULL,
+            confidence REAL NOT NULL,
+            PRIMARY KEY (core_id, asset, time, model_name)
+        );
+        CREATE TABLE IF NOT EXISTS sessions (
+            core_id TEXT NOT NULL PRIMARY KEY,
+            started_at DATETIME NOT NULL,
+            is_backtest INTEGER NOT NULL,
+            summary_path TEXT,
+            label TEXT NOT NULL DEFAULT '',
+            notes TEXT NOT NULL DEFAULT ''
+        );
+        CREATE TABLE IF NOT EXISTS scheduled_backtests (
+            core_id TEXT NOT NULL PRIMARY KEY,
+            model_name TEXT NOT NULL,
+            asset TEXT NOT NULL,
+            ran_at DATETIME NOT NULL,
+            sharpe_ratio REAL NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS agg_trades (
+            asset TEXT NOT NULL,
+            id TEXT NOT NULL,
+            time DATETIME NOT NULL,
+            price REAL NOT NULL,
+            quantity REAL NOT NULL,
+            side TEXT NOT NULL,
+            PRIMARY KEY (asset, id)
+        );
+        CREATE INDEX IF NOT EXISTS agg_trades_time ON agg_trades (asset, time);
+        CREATE TABLE IF NOT EXISTS funding_rates (
+            asset TEXT NOT NULL,
+            time DATETIME NOT NULL,
+            rate REAL NOT NULL,
+            PRIMARY KEY (asset, time)
+        );
+        CREATE TABLE IF NOT EXISTS open_interest (
+            asset TEXT NOT NULL,
+            time DATETIME NOT NULL,
+            open_interest REAL NOT NULL,
+            PRIMARY KEY (asset, time)
+        );
+        CREATE TABLE IF NOT EXISTS exited_positions (
+            core_id TEXT NOT NULL,
+            position_id TEXT NOT NULL,
+            asset TEXT NOT NULL,
+            enter_time DATETIME NOT NULL,
+            exit_time DATETIME NOT NULL,
+            realised_profit_loss REAL NOT NULL,
+            position TEXT NOT NULL,
+            PRIMARY KEY (core_id, position_id, enter_time)
+        );
+        CREATE TABLE IF NOT EXISTS crash_open_positions (
+            position_id TEXT NOT NULL,
+            saved_at DATETIME NOT NULL,
+            position TEXT NOT NULL,
+            PRIMARY KEY (position_id, saved_at)
+        );
+        CREATE TABLE IF NOT EXISTS journal (
+            core_id TEXT NOT NULL,
+            time DATETIME NOT NULL,
+            entry TEXT NOT NULL
+        );
         CREATE TABLE IF NOT EXISTS account(
             maker_commission INTEGER NOT NULL,
             taker_commission INTEGER NOT NULL,
@@ -146,6 +255,63 @@ pub async fn setup_tables() -> Result<(), DatabaseError> {
         .execute(connection)
         .await
         .map_err(|e| DatabaseError::Initialization(e.to_string()))?;
+        sqlx::query(CREATE_CANDLES_TABLE)
+            .execute(connection)
+            .await
+            .map_err(|e| DatabaseError::Initialization(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Moves candles stored before they were keyed by interval into the current table. All
+/// of them were 1m klines from the REST API, so they are closed.
+async fn migrate_candles() -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get();
+    if let Some(connection) = connection {
+        let columns: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM pragma_table_info('candles')")
+                .fetch_all(connection)
+                .await?;
+        let is_legacy = !columns.is_empty() && !columns.iter().any(|c| c.0 == "interval");
+        if is_legacy {
+            let mut tx = connection.begin().await?;
+            sqlx::query("ALTER TABLE candles RENAME TO candles_legacy")
+                .execute(tx.as_mut())
+                .await?;
+            sqlx::query(CREATE_CANDLES_TABLE).execute(tx.as_mut()).await?;
+            sqlx::query(
+                "INSERT OR IGNORE INTO candles
+                SELECT asset, '1m', open_time, open, high, low, close, close_time, volume,
+                    trade_count, 1
+                FROM candles_legacy",
+            )
+            .execute(tx.as_mut())
+            .await?;
+            sqlx::query("DROP TABLE candles_legacy").execute(tx.as_mut()).await?;
+            tx.commit().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the label and notes to sessions stored before they could be given.
+async fn migrate_sessions() -> Result<(), DatabaseError> {
+    let connection = DB_POOL.get();
+    if let Some(connection) = connection {
+        let columns: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM pragma_table_info('sessions')")
+                .fetch_all(connection)
+                .await?;
+        if !columns.iter().any(|c| c.0 == "label") {
+            let mut tx = connection.begin().await?;
+            sqlx::query("ALTER TABLE sessions ADD COLUMN label TEXT NOT NULL DEFAULT ''")
+                .execute(tx.as_mut())
+                .await?;
+            sqlx::query("ALTER TABLE sessions ADD COLUMN notes TEXT NOT NULL DEFAULT ''")
+                .execute(tx.as_mut())
+                .await?;
+            tx.commit().await?;
+        }
     }
     Ok(())
 }
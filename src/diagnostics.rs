@@ -0,0 +1,206 @@
+use lazy_static::lazy_static;
+use std::{
+  collections::{HashMap, VecDeque},
+  sync::Mutex as StdMutex,
+  time::{Duration, Instant},
+};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Parts of the pipeline that are instrumented. Event loops record their latency and
+/// queue depth, shared state records how long its lock was waited for. The latency of
+/// the market stream is the lag behind the exchange's event time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Display, EnumIter)]
+pub enum Task {
+  #[default]
+  #[strum(serialize = "Market stream")]
+  MarketStream,
+  Core,
+  Trader,
+  Portfolio,
+  Database,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TaskMetrics {
+  pub iterations: u64,
+  pub total_latency: Duration,
+  pub max_latency: Duration,
+  /// Events waiting at the last iteration.
+  pub queue_depth: usize,
+  pub max_queue_depth: usize,
+  pub lock_waits: u64,
+  pub total_lock_wait: Duration,
+  pub max_lock_wait: Duration,
+}
+
+impl TaskMetrics {
+  pub fn mean_latency(&self) -> Duration {
+    mean(self.total_latency, self.iterations)
+  }
+
+  pub fn mean_lock_wait(&self) -> Duration {
+    mean(self.total_lock_wait, self.lock_waits)
+  }
+}
+
+fn mean(total: Duration, n: u64) -> Duration {
+  match u32::try_from(n) {
+    Ok(0) => Duration::ZERO,
+    Ok(n) => total / n,
+    Err(_) => Duration::from_secs_f64(total.as_secs_f64() / n as f64),
+  }
+}
+
+/// Steps of acting on a closed candle in live runs, each timed from the close.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Display, EnumIter)]
+pub enum Stage {
+  #[default]
+  #[strum(serialize = "Signal generated")]
+  Signal,
+  #[strum(serialize = "Order created")]
+  Order,
+  #[strum(serialize = "Exchange ack")]
+  Ack,
+}
+
+/// Latencies of a stage the percentiles are taken over, the latest ones.
+const STAGE_SAMPLES: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Percentiles {
+  pub samples: usize,
+  pub p50: Duration,
+  pub p95: Duration,
+}
+
+lazy_static! {
+  static ref METRICS: StdMutex<HashMap<Task, TaskMetrics>> = StdMutex::new(HashMap::new());
+  static ref STAGES: StdMutex<HashMap<Stage, VecDeque<Duration>>> =
+    StdMutex::new(HashMap::new());
+}
+
+fn update(task: Task, f: impl FnOnce(&mut TaskMetrics)) {
+  if let Ok(mut metrics) = METRICS.lock() {
+    f(metrics.entry(task).or_default());
+  }
+}
+
+/// Time one iteration of the task's event loop took.
+pub fn record_latency(task: Task, latency: Duration) {
+  update(task, |metrics| {
+    metrics.iterations += 1;
+    metrics.total_latency += latency;
+    metrics.max_latency = metrics.max_latency.max(latency);
+  });
+}
+
+pub fn record_queue_depth(task: Task, depth: usize) {
+  update(task, |metrics| {
+    metrics.queue_depth = depth;
+    metrics.max_queue_depth = metrics.max_queue_depth.max(depth);
+  });
+}
+
+/// Locks the mutex, recording the wait under the task that owns the state.
+pub async fn lock<T>(task: Task, mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  let started = Instant::now();
+  let guard = mutex.lock().await;
+  let wait = started.elapsed();
+  update(task, |metrics| {
+    metrics.lock_waits += 1;
+    metrics.total_lock_wait += wait;
+    metrics.max_lock_wait = metrics.max_lock_wait.max(wait);
+  });
+  guard
+}
+
+/// Records the latency of a loop iteration when dropped, so early `continue`s count.
+pub struct IterationTimer {
+  task: Task,
+  started: Instant,
+}
+
+pub fn time_iteration(task: Task) -> IterationTimer {
+  IterationTimer { task, started: Instant::now() }
+}
+
+impl Drop for IterationTimer {
+  fn drop(&mut self) {
+    record_latency(self.task, self.started.elapsed());
+  }
+}
+
+/// Metrics of every task, in pipeline order.
+pub fn snapshot() -> Vec<(Task, TaskMetrics)> {
+  let metrics = METRICS.lock().map(|metrics| metrics.clone()).unwrap_or_default();
+  Task::iter()
+    .map(|task| (task, metrics.get(&task).copied().unwrap_or_default()))
+    .collect()
+}
+
+/// Mean lock waits below this are not reported as a bottleneck.
+const SLOW_LOCK_WAIT: Duration = Duration::from_millis(1);
+
+/// Task the pipeline backs up at: the deepest queue, or the longest mean lock wait
+/// when no events are waiting.
+pub fn bottleneck(snapshot: &[(Task, TaskMetrics)]) -> Option<Task> {
+  let queued = snapshot
+    .iter()
+    .filter(|(_, metrics)| metrics.queue_depth > 0)
+    .max_by_key(|(_, metrics)| metrics.queue_depth);
+  let waited = || {
+    snapshot
+      .iter()
+      .filter(|(_, metrics)| metrics.mean_lock_wait() >= SLOW_LOCK_WAIT)
+      .max_by_key(|(_, metrics)| metrics.mean_lock_wait())
+  };
+  queued.or_else(waited).map(|(task, _)| *task)
+}
+
+/// Time from the close of the candle until the stage was reached.
+pub fn record_stage(stage: Stage, since_close: Duration) {
+  if let Ok(mut stages) = STAGES.lock() {
+    let samples = stages.entry(stage).or_default();
+    if samples.len() == STAGE_SAMPLES {
+      samples.pop_front();
+    }
+    samples.push_back(since_close);
+  }
+}
+
+/// Median and 95th percentile of the latest latencies of every stage, in pipeline order.
+pub fn stage_latencies() -> Vec<(Stage, Percentiles)> {
+  let stages = STAGES.lock().map(|stages| stages.clone()).unwrap_or_default();
+  Stage::iter()
+    .map(|stage| {
+      let samples = stages.get(&stage).cloned().unwrap_or_default();
+      (stage, percentiles(samples.into()))
+    })
+    .collect()
+}
+
+/// Nearest-rank percentiles of the samples.
+fn percentiles(mut samples: Vec<Duration>) -> Percentiles {
+  samples.sort();
+  let rank = |p: f64| {
+    let index = (p * samples.len() as f64).ceil() as usize;
+    samples.get(index.saturating_sub(1)).copied().unwrap_or_default()
+  };
+  Percentiles { samples: samples.len(), p50: rank(0.5), p95: rank(0.95) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn takes_nearest_rank_percentiles() {
+    assert_eq!(percentiles(vec![]), Percentiles::default());
+    let samples = (1..=20).rev().map(Duration::from_millis).collect();
+    let percentiles = percentiles(samples);
+    assert_eq!(percentiles.samples, 20);
+    assert_eq!(percentiles.p50, Duration::from_millis(10));
+    assert_eq!(percentiles.p95, Duration::from_millis(19));
+  }
+}
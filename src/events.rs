@@ -1,3 +1,6 @@
+pub mod recording;
+pub mod wal;
+
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
@@ -15,6 +18,7 @@ use crate::{
   },
   strategy::Signal,
   trading::{execution::FillEvent, SignalForceExit},
+  utils::crash,
 };
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +55,7 @@ pub struct EventTx {
 
 impl MessageTransmitter<Event> for EventTx {
   fn send(&mut self, message: Event) {
+    crash::record_event(&message);
     if self.receiver_dropped {
       return;
     }
@@ -71,6 +76,7 @@ impl MessageTransmitter<Event> for EventTx {
     }
 
     messages.into_iter().for_each(|message| {
+      crash::record_event(&message);
       let _ = self.event_tx.send(message);
     })
   }
@@ -0,0 +1,64 @@
+use super::Event;
+use crate::screens::run_config::CoreConfiguration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs::File,
+  io::{self, BufRead, BufReader, BufWriter, Write},
+  path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+/// Line of a recording. The first line describes the session, every following one is an
+/// event the trader handled.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedLine {
+  Session { core_id: Uuid, configuration: CoreConfiguration },
+  Event { time: DateTime<Utc>, event: Event },
+}
+
+/// Path of the recording of a session, under `recordings` in the data dir.
+pub fn recording_path(core_id: Uuid) -> PathBuf {
+  crate::paths::data_dir().join("recordings").join(format!("{}.jsonl", core_id))
+}
+
+/// Writes every event a trader handles to a JSONL file, so a session can be replayed.
+/// Each line is flushed right away to survive a crash.
+pub struct EventRecorder {
+  writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+  pub fn create(
+    path: &Path,
+    core_id: Uuid,
+    configuration: CoreConfiguration,
+  ) -> io::Result<Self> {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    let mut recorder = Self { writer: BufWriter::new(File::create(path)?) };
+    recorder.write(&RecordedLine::Session { core_id, configuration })?;
+    Ok(recorder)
+  }
+
+  pub fn record(&mut self, event: &Event) -> io::Result<()> {
+    self.write(&RecordedLine::Event { time: Utc::now(), event: event.clone() })
+  }
+
+  fn write(&mut self, line: &RecordedLine) -> io::Result<()> {
+    serde_json::to_writer(&mut self.writer, line)?;
+    self.writer.write_all(b"\n")?;
+    self.writer.flush()
+  }
+}
+
+/// Reads a recording back, failing on the first malformed line.
+pub fn read_recording(path: &Path) -> io::Result<Vec<RecordedLine>> {
+  BufReader::new(File::open(path)?)
+    .lines()
+    .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+    .map(|line| Ok(serde_json::from_str(&line?)?))
+    .collect()
+}
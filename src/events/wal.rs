@@ -0,0 +1,327 @@
+use crate::{
+  assets::Pair,
+  database::Database,
+  portfolio::{balance::Balance, position::Position, OrderEvent},
+  trading::execution::FillEvent,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs::{File, OpenOptions},
+  io::{self, BufRead, BufReader, Write},
+  path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+/// Line of a write-ahead log. The first line names the trader's session, the following
+/// ones what it sent to the exchange and what came back.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalEntry {
+  Session { core_id: Uuid, pair: Pair },
+  /// Written before the order is sent.
+  Order(OrderEvent),
+  /// The exchange rejected the last order.
+  Rejected,
+  /// Written before the portfolio applies the fill.
+  Fill(FillEvent),
+  /// The portfolio applied the last fill, with the session's equity after it.
+  Applied { balance: Balance },
+}
+
+/// Directory of the logs of running live sessions, `wal` in the data dir.
+pub fn wal_dir() -> PathBuf {
+  crate::paths::data_dir().join("wal")
+}
+
+/// Orders and fills of a live trader, synced to disk before they are acted on so a crash
+/// at any point can be recovered from at the next start. Removed when the trader shuts
+/// down cleanly.
+pub struct WriteAheadLog {
+  path: PathBuf,
+  file: File,
+}
+
+impl WriteAheadLog {
+  pub fn create(core_id: Uuid, pair: Pair) -> io::Result<Self> {
+    let dir = wal_dir();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}_{}.jsonl", core_id, pair));
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut wal = Self { path, file };
+    wal.append(&WalEntry::Session { core_id, pair })?;
+    Ok(wal)
+  }
+
+  /// Returns once the entry is on disk.
+  pub fn append(&mut self, entry: &WalEntry) -> io::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    self.file.write_all(&line)?;
+    self.file.sync_data()
+  }
+
+  pub fn close(self) -> io::Result<()> {
+    std::fs::remove_file(&self.path)
+  }
+}
+
+/// Entries of a log, up to a line torn by the crash.
+fn read_wal(path: &Path) -> io::Result<Vec<WalEntry>> {
+  let mut entries = Vec::new();
+  for line in BufReader::new(File::open(path)?).lines() {
+    match serde_json::from_str(&line?) {
+      Ok(entry) => entries.push(entry),
+      Err(_) => break,
+    }
+  }
+  Ok(entries)
+}
+
+/// Replays the logs left behind by sessions that did not shut down cleanly and removes
+/// them. Exits filled before the crash are stored, positions still open are saved to
+/// `crash_open_positions`. Returns what the user should know about, including orders
+/// that may have been filled without the app knowing.
+pub async fn recover(database: &mut Database) -> Vec<String> {
+  let Ok(dir) = std::fs::read_dir(wal_dir()) else {
+    return Vec::new();
+  };
+  let mut notes = Vec::new();
+  for path in dir.flatten().map(|entry| entry.path()) {
+    if path.extension().is_some_and(|extension| extension == "jsonl") {
+      match recover_wal(&path, database).await {
+        Ok(recovered) => {
+          notes.extend(recovered);
+          let _ = std::fs::remove_file(&path);
+        },
+        Err(e) => notes.push(format!("Failed to recover {}: {}", path.display(), e)),
+      }
+    }
+  }
+  notes
+}
+
+async fn recover_wal(
+  path: &Path,
+  database: &mut Database,
+) -> Result<Vec<String>, String> {
+  let entries = read_wal(path).map_err(|e| e.to_string())?;
+  let (core_id, recovered) = replay(&entries)?;
+  for exited in recovered.exited {
+    database.set_exited_position(core_id, exited).await.map_err(|e| e.to_string())?;
+  }
+  if let Some(position) = recovered.open {
+    database.save_crash_positions(&[position]).await.map_err(|e| e.to_string())?;
+  }
+  Ok(recovered.notes)
+}
+
+/// What the entries of a log leave to store, with the notes about it.
+#[derive(Debug, Default)]
+struct Recovered {
+  /// Exits filled after the last fill the portfolio applied.
+  exited: Vec<Position>,
+  open: Option<Position>,
+  notes: Vec<String>,
+}
+
+/// Replays the entries of a log of the session it names.
+fn replay(entries: &[WalEntry]) -> Result<(Uuid, Recovered), String> {
+  let Some(WalEntry::Session { core_id, pair }) = entries.first().cloned() else {
+    return Err("log has no session".to_string());
+  };
+  let last_applied = entries
+    .iter()
+    .rposition(|entry| matches!(entry, WalEntry::Applied { .. }))
+    .unwrap_or(0);
+  let mut recovered = Recovered::default();
+  let mut balance = Balance::default();
+  let mut pending_order: Option<&OrderEvent> = None;
+  for (index, entry) in entries.iter().enumerate().skip(1) {
+    match entry {
+      WalEntry::Session { .. } => {},
+      WalEntry::Order(order) => {
+        if let Some(unconfirmed) = pending_order.replace(order) {
+          recovered.notes.push(unconfirmed_order(unconfirmed, pair));
+        }
+      },
+      WalEntry::Rejected => pending_order = None,
+      WalEntry::Fill(fill) => {
+        pending_order = None;
+        let exited = apply_fill(&mut recovered.open, core_id, fill, balance)?;
+        if let Some(exited) = exited.filter(|_| index > last_applied) {
+          recovered.exited.push(exited);
+          let note = format!("Stored the exit of {} filled before the crash.", pair);
+          recovered.notes.push(note);
+        }
+      },
+      WalEntry::Applied { balance: applied } => balance = *applied,
+    }
+  }
+  if recovered.open.is_some() {
+    let note = format!("Saved the open {} position to crash_open_positions.", pair);
+    recovered.notes.push(note);
+  }
+  if let Some(unconfirmed) = pending_order {
+    recovered.notes.push(unconfirmed_order(unconfirmed, pair));
+  }
+  Ok((core_id, recovered))
+}
+
+fn unconfirmed_order(order: &OrderEvent, pair: Pair) -> String {
+  format!(
+    "The {:?} order of {} {} may have been filled without a fill being recorded, check \
+     the account.",
+    order.decision, order.quantity, pair
+  )
+}
+
+/// Applies the fill to the position of the log, returning the position it exited.
+fn apply_fill(
+  position: &mut Option<Position>,
+  core_id: Uuid,
+  fill: &FillEvent,
+  balance: Balance,
+) -> Result<Option<Position>, String> {
+  match position.take() {
+    Some(mut open) if fill.decision.is_entry() => {
+      open.add_entry_fill(fill).map_err(|e| e.to_string())?;
+      *position = Some(open);
+    },
+    Some(mut open) => {
+      if open.add_exit_fill(fill).map_err(|e| e.to_string())? {
+        open.close(balance).map_err(|e| e.to_string())?;
+        return Ok(Some(open));
+      }
+      *position = Some(open);
+    },
+    None => *position = Some(Position::enter(core_id, fill).map_err(|e| e.to_string())?),
+  }
+  Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    assets::MarketMeta, portfolio::fixtures::fill_of, strategy::Decision,
+    trading::execution::Fees,
+  };
+  use chrono::DateTime;
+
+  fn order(decision: Decision, quantity: f64) -> WalEntry {
+    let time = DateTime::UNIX_EPOCH;
+    WalEntry::Order(OrderEvent {
+      time,
+      pair: Pair::BTCUSDT,
+      decision,
+      market_meta: MarketMeta { close: 100.0, time },
+      quantity,
+      options: Default::default(),
+    })
+  }
+
+  fn fill(decision: Decision, quantity: f64, price: f64) -> WalEntry {
+    let time = DateTime::UNIX_EPOCH;
+    let fees = Fees::default();
+    WalEntry::Fill(fill_of(Pair::BTCUSDT, decision, quantity, price, fees, time))
+  }
+
+  fn applied() -> WalEntry {
+    WalEntry::Applied { balance: Balance::default() }
+  }
+
+  /// Writes the log as the trader would up to the crash, with the torn start of one more
+  /// line, and replays what can be read back of it.
+  fn recover_torn(entries: &[WalEntry], torn: &str) -> Recovered {
+    let core_id = Uuid::new_v4();
+    let path = std::env::temp_dir().join(format!("meshetar-wal-{}.jsonl", core_id));
+    let mut file = File::create(&path).unwrap();
+    let session = WalEntry::Session { core_id, pair: Pair::BTCUSDT };
+    for entry in std::iter::once(&session).chain(entries) {
+      writeln!(file, "{}", serde_json::to_string(entry).unwrap()).unwrap();
+    }
+    write!(file, "{}", torn).unwrap();
+    let entries = read_wal(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let (recovered_id, recovered) = replay(&entries).unwrap();
+    assert_eq!(recovered_id, core_id);
+    recovered
+  }
+
+  fn recover(entries: &[WalEntry]) -> Recovered {
+    recover_torn(entries, "")
+  }
+
+  #[test]
+  fn flags_an_order_without_a_fill() {
+    let recovered = recover(&[order(Decision::Long, 1.0)]);
+    assert!(recovered.exited.is_empty() && recovered.open.is_none());
+    assert_eq!(recovered.notes.len(), 1);
+    assert!(recovered.notes[0].contains("Long order of 1 BTCUSDT may have been filled"));
+  }
+
+  #[test]
+  fn ignores_a_rejected_order() {
+    let recovered = recover(&[order(Decision::Long, 1.0), WalEntry::Rejected]);
+    assert!(recovered.exited.is_empty() && recovered.open.is_none());
+    assert!(recovered.notes.is_empty());
+  }
+
+  #[test]
+  fn saves_the_position_of_a_fill_not_applied() {
+    let recovered =
+      recover(&[order(Decision::Long, 1.0), fill(Decision::Long, 1.0, 100.0)]);
+    assert!(recovered.exited.is_empty());
+    let open = recovered.open.unwrap();
+    assert_eq!((open.quantity, open.enter_avg_price_gross), (1.0, 100.0));
+    let note = "Saved the open BTCUSDT position to crash_open_positions.";
+    assert_eq!(recovered.notes, [note]);
+  }
+
+  #[test]
+  fn stores_an_exit_filled_after_the_last_applied_fill() {
+    let recovered = recover(&[
+      order(Decision::Long, 1.0),
+      fill(Decision::Long, 1.0, 100.0),
+      applied(),
+      order(Decision::CloseLong, -1.0),
+      fill(Decision::CloseLong, 1.0, 110.0),
+    ]);
+    assert!(recovered.open.is_none());
+    assert_eq!(recovered.exited.len(), 1);
+    assert_eq!(recovered.exited[0].realised_profit_loss, 10.0);
+    assert_eq!(recovered.notes, ["Stored the exit of BTCUSDT filled before the crash."]);
+  }
+
+  #[test]
+  fn skips_an_exit_applied_before_the_crash() {
+    let recovered = recover(&[
+      order(Decision::Long, 1.0),
+      fill(Decision::Long, 1.0, 100.0),
+      applied(),
+      order(Decision::CloseLong, -1.0),
+      fill(Decision::CloseLong, 1.0, 110.0),
+      applied(),
+    ]);
+    assert!(recovered.exited.is_empty() && recovered.open.is_none());
+    assert!(recovered.notes.is_empty());
+  }
+
+  #[test]
+  fn reads_up_to_a_torn_last_line() {
+    let torn = serde_json::to_string(&fill(Decision::CloseLong, 1.0, 110.0)).unwrap();
+    let recovered = recover_torn(
+      &[
+        order(Decision::Long, 1.0),
+        fill(Decision::Long, 1.0, 100.0),
+        applied(),
+        order(Decision::CloseLong, -1.0),
+      ],
+      &torn[..torn.len() / 2],
+    );
+    assert!(recovered.exited.is_empty());
+    assert_eq!(recovered.open.unwrap().quantity, 1.0);
+    assert_eq!(recovered.notes.len(), 2);
+    assert!(recovered.notes[1].contains("CloseLong order of -1 BTCUSDT may have been"));
+  }
+}
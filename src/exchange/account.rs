@@ -1,7 +1,7 @@
 use crate::{
   assets::Pair,
   events::Event,
-  exchange::{binance_client::BinanceClient, error::ExchangeError},
+  exchange::{binance_client::BinanceClient, error::ExchangeError, payload::parse},
   portfolio::balance::Balance,
   utils::serde_utils::f64_from_string,
 };
@@ -11,8 +11,30 @@ use binance_spot_connector_rust::{
 use chrono::{DateTime, Utc};
 use futures::{StreamExt, TryFutureExt};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use tokio::sync::mpsc::{self, UnboundedReceiver};
 
+/// Name of an account in the exchange config, `main` is the one of the top level keys.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct AccountName(pub String);
+
+impl AccountName {
+  pub const MAIN: &'static str = "main";
+}
+
+impl Default for AccountName {
+  fn default() -> Self {
+    Self(Self::MAIN.to_string())
+  }
+}
+
+impl fmt::Display for AccountName {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 struct ExchangeAccountBalance {
   a: String,
@@ -50,10 +72,6 @@ pub async fn new_account_stream(
   let (mut conn, _) = BinanceWebSocketClient::connect_async(stream_url)
     .map_err(|e| ExchangeError::BinanceStreamError(e.to_string()))
     .await?;
-  let key = binance_client
-    .client
-    .send(binance_spot_connector_rust::stream::new_listen_key())
-    .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))?;
   let key = binance_client.get_stream_key().await?;
   let stream = binance_spot_connector_rust::user_data_stream::user_data(&key);
   conn.subscribe(vec![&stream.into()]).await;
@@ -64,8 +82,8 @@ pub async fn new_account_stream(
         Ok(message) => {
           let data = message.into_data();
           if let Ok(string_data) = String::from_utf8(data) {
-            let raw_event_parse: Result<ExchangeAccountUpdate, serde_json::Error> =
-              serde_json::from_str(&string_data);
+            let raw_event_parse: Result<ExchangeAccountUpdate, ExchangeError> =
+              parse("outboundAccountPosition", &string_data);
             match raw_event_parse {
               Ok(ev) => {
                 let balances: Vec<(String, Balance)> =
@@ -105,6 +123,18 @@ impl ExchangeAccountBalanceFromRest {
   }
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CommissionRates {
+  #[serde(deserialize_with = "f64_from_string")]
+  maker: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  taker: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  buyer: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  seller: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RawExchangeAccount {
@@ -112,6 +142,8 @@ pub struct RawExchangeAccount {
   taker_commission: i64,
   buyer_commission: i64,
   seller_commission: i64,
+  #[serde(default)]
+  commission_rates: CommissionRates,
   balances: Vec<ExchangeAccountBalanceFromRest>,
   can_trade: bool,
   can_withdraw: bool,
@@ -173,18 +205,51 @@ impl ExchangeAccount {
   }
 }
 
+/// What the API key is allowed to do, from its settings on Binance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiRestrictions {
+  pub ip_restrict: bool,
+  create_time: i64,
+  pub enable_reading: bool,
+  pub enable_withdrawals: bool,
+  pub enable_internal_transfer: bool,
+  #[serde(default)]
+  permits_universal_transfer: bool,
+  pub enable_spot_and_margin_trading: bool,
+  #[serde(default)]
+  enable_margin: bool,
+  #[serde(default)]
+  enable_futures: bool,
+  #[serde(default)]
+  enable_vanilla_options: bool,
+  #[serde(default)]
+  enable_portfolio_margin_trading: bool,
+  #[serde(default)]
+  enable_fix_api_trade: bool,
+  #[serde(default)]
+  enable_fix_read_only: bool,
+  #[serde(default)]
+  trading_authority_expiration_time: Option<i64>,
+}
+
+/// Restrictions of the client's API key. Only on the live exchange, the testnet has no
+/// wallet endpoints.
+pub async fn get_api_restrictions(
+  binance_client: &BinanceClient,
+) -> Result<ApiRestrictions, ExchangeError> {
+  let request = binance_spot_connector_rust::wallet::api_key_permission()
+    .recv_window(binance_client.recv_window);
+  let res = binance_client.send(request)?;
+  parse("api_restrictions", &res)
+}
+
 pub async fn get_account_from_exchange(
   binance_client: BinanceClient,
 ) -> Result<ExchangeAccount, ExchangeError> {
-  let request = binance_spot_connector_rust::trade::account().recv_window(5000);
-  let res = binance_client
-    .client
-    .send(request)
-    .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))?;
-  let res = res
-    .into_body_str()
-    .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))?;
-  let account: RawExchangeAccount =
-    serde_json::from_str(&res).map_err(|e| ExchangeError::JsonSerDe(e))?;
+  let request = binance_spot_connector_rust::trade::account()
+    .recv_window(binance_client.recv_window as i64);
+  let res = binance_client.send(request)?;
+  let account: RawExchangeAccount = parse("account", &res)?;
   Ok(account.to_exchange_account())
 }
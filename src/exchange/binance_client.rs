@@ -1,14 +1,33 @@
-use super::error::ExchangeError;
+use super::{
+  account::AccountName,
+  error::{ApiError, ExchangeError},
+};
 use crate::utils::load_config::{read_config, ConfigError, ExchangeConfig};
-use binance_spot_connector_rust::{http::Credentials, ureq::BinanceHttpClient};
+use binance_spot_connector_rust::{
+  http::{
+    request::{Request, RequestBuilder},
+    Credentials, Method,
+  },
+  ureq::BinanceHttpClient,
+};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
+/// Shared by all clones, so a time re-sync applies to every user of the client.
 #[derive(Clone)]
 pub struct BinanceClient {
-  pub client: BinanceHttpClient,
+  client: Arc<RwLock<BinanceHttpClient>>,
+  /// Milliseconds a signed request stays valid for, set on signed requests.
+  pub recv_window: u64,
+  /// Endpoint of the server time, which differs between the spot and futures APIs.
+  time_path: &'static str,
 }
 
+const SERVER_TIME: &str = "/api/v3/time";
+const FUTURES_SERVER_TIME: &str = "/fapi/v1/time";
+
 #[derive(Error, Debug)]
 pub enum BinanceClientError {
   #[error("Init failed {0}")]
@@ -25,38 +44,115 @@ struct BinanceRawKey {
   pub listen_key: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceServerTime {
+  server_time: i64,
+}
+
 impl BinanceClient {
+  /// Client of the `main` account.
   pub async fn new() -> Result<BinanceClient, ExchangeError> {
+    Self::for_account(&AccountName::default()).await
+  }
+
+  pub async fn for_account(
+    account: &AccountName,
+  ) -> Result<BinanceClient, ExchangeError> {
     let config: ExchangeConfig =
       read_config().map_err(|e| ExchangeError::ConfigOnInit(e))?;
+    Self::connect(&config, account, &config.rest_url, SERVER_TIME)
+  }
+
+  /// Client of the account on the USD-M futures REST API.
+  pub async fn futures(account: &AccountName) -> Result<BinanceClient, ExchangeError> {
+    let config: ExchangeConfig = read_config().map_err(ExchangeError::ConfigOnInit)?;
+    Self::connect(&config, account, &config.futures_rest_url, FUTURES_SERVER_TIME)
+  }
+
+  fn connect(
+    config: &ExchangeConfig,
+    account: &AccountName,
+    rest_url: &str,
+    time_path: &'static str,
+  ) -> Result<BinanceClient, ExchangeError> {
+    let account = config.account(account)?;
+
+    let credentials = Credentials::from_hmac(
+      account.binance_api_key.clone(),
+      account.binance_api_secret.clone(),
+    );
+
+    let client = BinanceHttpClient::with_url(rest_url).credentials(credentials);
+    let binance_client = BinanceClient {
+      client: Arc::new(RwLock::new(client)),
+      recv_window: config.recv_window,
+      time_path,
+    };
+    if let Err(e) = binance_client.sync_time() {
+      log::warn!("Failed to sync time with Binance: {}", e);
+    }
+    Ok(binance_client)
+  }
+
+  /// Client of a test, with placeholder credentials and without syncing the time.
+  #[cfg(test)]
+  pub fn with_url(rest_url: &str) -> BinanceClient {
+    let credentials = Credentials::from_hmac("test", "test");
+    let client = BinanceHttpClient::with_url(rest_url).credentials(credentials);
+    BinanceClient {
+      client: Arc::new(RwLock::new(client)),
+      recv_window: 5000,
+      time_path: SERVER_TIME,
+    }
+  }
 
-    let credentials =
-      Credentials::from_hmac(config.binance_api_key, config.binance_api_secret);
+  /// Sends the request and returns the response body. A timestamp rejected for being
+  /// outside of recvWindow re-syncs the time, so the request can be retried.
+  pub fn send<R: Into<Request>>(&self, request: R) -> Result<String, ExchangeError> {
+    let client = self.client.read().expect("Binance client lock poisoned").clone();
+    let result = client
+      .send(request)
+      .and_then(|response| response.into_body_str())
+      .map_err(ExchangeError::from);
+    if let Err(ExchangeError::Api(ApiError::TimestampOutOfRecvWindow(_))) = &result {
+      if let Err(e) = self.sync_time() {
+        log::warn!("Failed to re-sync time with Binance: {}", e);
+      }
+    }
+    result
+  }
 
-    let client =
-      BinanceHttpClient::with_url(&ExchangeConfig::get_exchange_url(config.use_testnet))
-        .credentials(credentials);
-    Ok(BinanceClient { client })
+  /// Calibrates the timestamp of signed requests to the server time and returns the
+  /// offset in milliseconds. Only a local clock ahead of the server is corrected, one
+  /// behind it is covered by recvWindow.
+  pub fn sync_time(&self) -> Result<i64, ExchangeError> {
+    let sent_at = Utc::now().timestamp_millis();
+    let res = self.send(RequestBuilder::new(Method::Get, self.time_path))?;
+    let received_at = Utc::now().timestamp_millis();
+    let server_time: BinanceServerTime = serde_json::from_str(&res)?;
+    let offset = (sent_at + received_at) / 2 - server_time.server_time;
+    log::info!("Local clock is {}ms off Binance server time", offset);
+    let mut client = self.client.write().expect("Binance client lock poisoned");
+    *client = client.clone().timestamp_delta(offset.max(0));
+    Ok(offset)
   }
 
   pub async fn credentials() -> Result<Credentials, ExchangeError> {
     let config: ExchangeConfig =
       read_config().map_err(|e| ExchangeError::ConfigOnInit(e))?;
+    let account = config.account(&AccountName::default())?;
 
-    let credentials =
-      Credentials::from_hmac(config.binance_api_key, config.binance_api_secret);
+    let credentials = Credentials::from_hmac(
+      account.binance_api_key.clone(),
+      account.binance_api_secret.clone(),
+    );
 
     Ok(credentials)
   }
 
   pub async fn get_stream_key(&self) -> Result<String, ExchangeError> {
-    let key = self
-      .client
-      .send(binance_spot_connector_rust::stream::new_listen_key())
-      .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))?;
-    let key = key
-      .into_body_str()
-      .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))?;
+    let key = self.send(binance_spot_connector_rust::stream::new_listen_key())?;
 
     let key: BinanceRawKey = serde_json::from_str(&key)?;
     Ok(key.listen_key)
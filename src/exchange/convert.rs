@@ -0,0 +1,202 @@
+use super::{binance_client::BinanceClient, error::ExchangeError, payload::parse};
+use crate::utils::{
+  formatting::{format_number, timestamp_to_dt},
+  serde_utils::f64_from_string,
+};
+use binance_spot_connector_rust::http::{request::RequestBuilder, Method};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const GET_QUOTE: &str = "/sapi/v1/convert/getQuote";
+const ACCEPT_QUOTE: &str = "/sapi/v1/convert/acceptQuote";
+
+/// Asset small balances are converted to.
+pub const DUST_TARGET: &str = "USDT";
+
+/// Price Binance offers for converting an amount of one asset to another, until it
+/// expires.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConvertQuote {
+  pub quote_id: String,
+  pub from_asset: String,
+  pub from_amount: f64,
+  pub to_asset: String,
+  pub to_amount: f64,
+  pub valid_until: DateTime<Utc>,
+}
+
+impl ConvertQuote {
+  pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+    now >= self.valid_until
+  }
+}
+
+impl fmt::Display for ConvertQuote {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {} to {} {}",
+      format_number(self.from_amount),
+      self.from_asset,
+      format_number(self.to_amount),
+      self.to_asset
+    )
+  }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawQuote {
+  quote_id: String,
+  #[serde(deserialize_with = "f64_from_string")]
+  ratio: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  inverse_ratio: f64,
+  valid_timestamp: i64,
+  #[serde(deserialize_with = "f64_from_string")]
+  to_amount: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  from_amount: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawConvertOrder {
+  order_id: String,
+  create_time: i64,
+  order_status: String,
+}
+
+/// Asks for a quote of converting all of `from_amount`.
+pub fn get_quote(
+  binance_client: &BinanceClient,
+  from_asset: &str,
+  from_amount: f64,
+  to_asset: &str,
+) -> Result<ConvertQuote, ExchangeError> {
+  let from_amount = from_amount.to_string();
+  let recv_window = binance_client.recv_window.to_string();
+  let request = RequestBuilder::new(Method::Post, GET_QUOTE)
+    .params([
+      ("fromAsset", from_asset),
+      ("toAsset", to_asset),
+      ("fromAmount", from_amount.as_str()),
+      ("recvWindow", recv_window.as_str()),
+    ])
+    .sign();
+  let quote: RawQuote = parse("convert_quote", &binance_client.send(request)?)?;
+  Ok(ConvertQuote {
+    quote_id: quote.quote_id,
+    from_asset: from_asset.to_string(),
+    from_amount: quote.from_amount,
+    to_asset: to_asset.to_string(),
+    to_amount: quote.to_amount,
+    valid_until: timestamp_to_dt(quote.valid_timestamp),
+  })
+}
+
+/// Converts at the quoted price, returns the status of the conversion.
+pub fn accept_quote(
+  binance_client: &BinanceClient,
+  quote: &ConvertQuote,
+) -> Result<String, ExchangeError> {
+  let recv_window = binance_client.recv_window.to_string();
+  let request = RequestBuilder::new(Method::Post, ACCEPT_QUOTE)
+    .params([("quoteId", quote.quote_id.as_str()), ("recvWindow", recv_window.as_str())])
+    .sign();
+  let order: RawConvertOrder = parse("convert_accept", &binance_client.send(request)?)?;
+  log::info!("Converted {}, order {} {}.", quote, order.order_id, order.order_status);
+  Ok(order.order_status)
+}
+
+/// Quotes of converting the available balances worth less than `threshold` of
+/// [DUST_TARGET]. Assets Binance cannot convert, or not in amounts this small, are left
+/// out.
+pub fn quote_dust(
+  binance_client: &BinanceClient,
+  balances: &[(String, f64)],
+  threshold: f64,
+) -> Vec<ConvertQuote> {
+  balances
+    .iter()
+    .filter(|(asset, available)| asset != DUST_TARGET && *available > 0.0)
+    .filter_map(|(asset, available)| {
+      match get_quote(binance_client, asset, *available, DUST_TARGET) {
+        Ok(quote) => Some(quote),
+        Err(e) => {
+          log::warn!("No quote for converting {} {}: {}", available, asset, e);
+          None
+        },
+      }
+    })
+    .filter(|quote| quote.to_amount < threshold)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+  };
+
+  fn quote(from_asset: &str, from_amount: &str, to_amount: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+      "quoteId": format!("{from_asset}-quote"),
+      "ratio": "1",
+      "inverseRatio": "1",
+      "validTimestamp": 1_700_000_010_000i64,
+      "toAmount": to_amount,
+      "fromAmount": from_amount
+    }))
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn quotes_and_converts_small_balances() {
+    let server = MockServer::start().await;
+    for (asset, amount, value) in [("BNB", "0.01", "5.8"), ("BTC", "0.5", "21000")] {
+      Mock::given(method("POST"))
+        .and(path(GET_QUOTE))
+        .and(query_param("fromAsset", asset))
+        .respond_with(quote(asset, amount, value))
+        .mount(&server)
+        .await;
+    }
+    Mock::given(method("POST"))
+      .and(path(GET_QUOTE))
+      .and(query_param("fromAsset", "XYZ"))
+      .respond_with(
+        ResponseTemplate::new(400)
+          .set_body_json(json!({ "code": -23000, "msg": "Unsupported pair." })),
+      )
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path(ACCEPT_QUOTE))
+      .and(query_param("quoteId", "BNB-quote"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "orderId": "933256278426274426",
+        "createTime": 1_700_000_001_000i64,
+        "orderStatus": "SUCCESS"
+      })))
+      .mount(&server)
+      .await;
+    let binance_client = BinanceClient::with_url(&server.uri());
+    let balances = [
+      ("BNB".to_string(), 0.01),
+      ("BTC".to_string(), 0.5),
+      ("XYZ".to_string(), 3.0),
+      ("USDT".to_string(), 100.0),
+      ("ETH".to_string(), 0.0),
+    ];
+
+    // BTC is worth more than the threshold, XYZ cannot be converted
+    let quotes = quote_dust(&binance_client, &balances, 10.0);
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0].to_string(), "0.01 BNB to 5.8 USDT");
+    assert_eq!(accept_quote(&binance_client, &quotes[0]).unwrap(), "SUCCESS");
+  }
+}
@@ -0,0 +1,241 @@
+use super::{
+  account::AccountName, binance_client::BinanceClient, error::ExchangeError,
+  payload::parse,
+};
+use crate::{
+  assets::Pair,
+  database::{
+    add_derivative_series, error::DatabaseError, fetch_latest_derivative_time,
+    DerivativeSeries,
+  },
+  utils::{formatting::timestamp_to_dt, serde_utils::f64_from_string},
+};
+use binance_spot_connector_rust::http::{request::RequestBuilder, Method};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use tracing::{error, info};
+
+const FUNDING_RATE: &str = "/fapi/v1/fundingRate";
+const OPEN_INTEREST: &str = "/futures/data/openInterestHist";
+const FUNDING_RATE_LIMIT: usize = 1000;
+const OPEN_INTEREST_LIMIT: usize = 500;
+/// Period of the open interest history, Binance keeps the last 30 days of it.
+const OPEN_INTEREST_PERIOD: &str = "5m";
+
+/// Funding rates and open interest of the USD-M perpetual futures of pairs, stored for
+/// strategy features and the Markets screen, from `derivatives` in the config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DerivativesConfig {
+  /// Pairs whose perpetual futures are ingested, none by default.
+  pub pairs: Vec<Pair>,
+  pub refresh_minutes: u64,
+  /// History fetched for a pair without any stored yet.
+  pub backfill_days: i64,
+}
+
+impl Default for DerivativesConfig {
+  fn default() -> Self {
+    Self { pairs: Vec::new(), refresh_minutes: 60, backfill_days: 30 }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFundingRate {
+  funding_time: i64,
+  #[serde(deserialize_with = "f64_from_string")]
+  funding_rate: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawOpenInterest {
+  timestamp: i64,
+  #[serde(deserialize_with = "f64_from_string")]
+  sum_open_interest: f64,
+}
+
+/// Funding rates of the perpetual future of the pair since `start`, oldest first.
+pub fn fetch_funding_rates(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  start: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, f64)>, ExchangeError> {
+  let (symbol, limit) = (pair.to_string(), FUNDING_RATE_LIMIT.to_string());
+  fetch_pages(start, FUNDING_RATE_LIMIT, |start_time| {
+    let start_time = start_time.to_string();
+    let request = RequestBuilder::new(Method::Get, FUNDING_RATE).params([
+      ("symbol", symbol.as_str()),
+      ("startTime", start_time.as_str()),
+      ("limit", limit.as_str()),
+    ]);
+    let rates: Vec<RawFundingRate> =
+      parse("funding_rate", &binance_client.send(request)?)?;
+    Ok(rates.into_iter().map(|rate| (rate.funding_time, rate.funding_rate)).collect())
+  })
+}
+
+/// Open interest of the perpetual future of the pair since `start` in base asset units,
+/// every 5 minutes, oldest first.
+pub fn fetch_open_interest(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  start: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, f64)>, ExchangeError> {
+  let (symbol, limit) = (pair.to_string(), OPEN_INTEREST_LIMIT.to_string());
+  fetch_pages(start, OPEN_INTEREST_LIMIT, |start_time| {
+    let start_time = start_time.to_string();
+    let request = RequestBuilder::new(Method::Get, OPEN_INTEREST).params([
+      ("symbol", symbol.as_str()),
+      ("period", OPEN_INTEREST_PERIOD),
+      ("startTime", start_time.as_str()),
+      ("limit", limit.as_str()),
+    ]);
+    let open_interest: Vec<RawOpenInterest> =
+      parse("open_interest", &binance_client.send(request)?)?;
+    Ok(open_interest.into_iter().map(|oi| (oi.timestamp, oi.sum_open_interest)).collect())
+  })
+}
+
+/// Requests pages of `(timestamp, value)` from a start time until one is not full.
+fn fetch_pages(
+  start: DateTime<Utc>,
+  limit: usize,
+  mut fetch_page: impl FnMut(i64) -> Result<Vec<(i64, f64)>, ExchangeError>,
+) -> Result<Vec<(DateTime<Utc>, f64)>, ExchangeError> {
+  let mut start_time = start.timestamp_millis();
+  let mut values = Vec::new();
+  loop {
+    let page = fetch_page(start_time)?;
+    let is_last = page.len() < limit;
+    let Some((last_time, _)) = page.last() else {
+      break;
+    };
+    start_time = last_time + 1;
+    values.extend(page.into_iter().map(|(time, value)| (timestamp_to_dt(time), value)));
+    if is_last {
+      break;
+    }
+  }
+  Ok(values)
+}
+
+/// Stores the funding rates and open interest of the pair since the latest stored ones,
+/// or of the last `backfill_days`. Returns the number of new funding rates and open
+/// interest values.
+pub async fn ingest(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  backfill_days: i64,
+) -> Result<(usize, usize), DatabaseError> {
+  let backfill = Utc::now() - Duration::try_days(backfill_days).unwrap_or_default();
+  let mut counts = [0; 2];
+  for (count, series) in
+    counts.iter_mut().zip([DerivativeSeries::FundingRate, DerivativeSeries::OpenInterest])
+  {
+    let start = match fetch_latest_derivative_time(series, pair).await? {
+      Some(latest) => latest + Duration::try_milliseconds(1).unwrap_or_default(),
+      None => backfill,
+    };
+    let values = match series {
+      DerivativeSeries::FundingRate => fetch_funding_rates(binance_client, pair, start)?,
+      DerivativeSeries::OpenInterest => fetch_open_interest(binance_client, pair, start)?,
+    };
+    add_derivative_series(series, pair, &values).await?;
+    *count = values.len();
+  }
+  Ok((counts[0], counts[1]))
+}
+
+/// Ingests the configured pairs at start and then every `refresh_minutes`, for as long
+/// as the app runs.
+pub fn spawn_ingestion(config: DerivativesConfig) {
+  if config.pairs.is_empty() {
+    return;
+  }
+  tokio::spawn(async move {
+    let binance_client = match BinanceClient::futures(&AccountName::default()).await {
+      Ok(binance_client) => binance_client,
+      Err(e) => {
+        error!("Error connecting to Binance futures: {}", e);
+        return;
+      },
+    };
+    let period = std::time::Duration::from_secs(config.refresh_minutes.max(1) * 60);
+    let mut interval = tokio::time::interval(period);
+    loop {
+      interval.tick().await;
+      for pair in &config.pairs {
+        match ingest(&binance_client, *pair, config.backfill_days).await {
+          Ok((funding_rates, open_interest)) => info!(
+            "Ingested {} funding rates and {} open interest values of {}.",
+            funding_rates, open_interest, pair
+          ),
+          Err(e) => error!("Error ingesting derivatives data of {}: {}", pair, e),
+        }
+      }
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+  };
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn fetches_pages_until_one_is_not_full() {
+    let server = MockServer::start().await;
+    let start_time = 1_700_000_000_000i64;
+    let time = |i: usize| start_time + i as i64 * 300_000;
+    let page: Vec<_> = (0..OPEN_INTEREST_LIMIT)
+      .map(|i| {
+        json!({
+          "symbol": "BTCUSDT",
+          "sumOpenInterest": format!("{}", 100 + i),
+          "sumOpenInterestValue": "1",
+          "timestamp": time(i)
+        })
+      })
+      .collect();
+    let next_start = time(OPEN_INTEREST_LIMIT - 1) + 1;
+    Mock::given(method("GET"))
+      .and(path(OPEN_INTEREST))
+      .and(query_param("startTime", start_time.to_string()))
+      .respond_with(ResponseTemplate::new(200).set_body_json(page))
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path(OPEN_INTEREST))
+      .and(query_param("startTime", next_start.to_string()))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path(FUNDING_RATE))
+      .and(query_param("symbol", "BTCUSDT"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+        "symbol": "BTCUSDT",
+        "fundingTime": 1_700_006_400_000i64,
+        "fundingRate": "0.00010000",
+        "markPrice": "37000.1"
+      }])))
+      .mount(&server)
+      .await;
+    let binance_client = BinanceClient::with_url(&server.uri());
+
+    let start = timestamp_to_dt(start_time);
+    let open_interest = fetch_open_interest(&binance_client, Pair::BTCUSDT, start);
+    let open_interest = open_interest.unwrap();
+    assert_eq!(open_interest.len(), OPEN_INTEREST_LIMIT);
+    assert_eq!(open_interest[0], (start, 100.0));
+    let funding_rates = fetch_funding_rates(&binance_client, Pair::BTCUSDT, start);
+    let funding_rates = funding_rates.unwrap();
+    assert_eq!(funding_rates, vec![(timestamp_to_dt(1_700_006_400_000), 0.0001)]);
+  }
+}
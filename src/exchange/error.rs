@@ -1,4 +1,9 @@
 use crate::{database::error::DatabaseError, utils::load_config::ConfigError};
+use binance_spot_connector_rust::{
+  http::error::{ClientError, HttpError},
+  ureq::Error as BinanceHttpError,
+};
+use std::{collections::HashMap, time::Duration};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,10 +12,139 @@ pub enum ExchangeError {
   BinanceStreamError(String),
   #[error("Binance client error: {0}")]
   BinanceClientError(String),
+  #[error("Binance API error: {0}")]
+  Api(#[from] ApiError),
   #[error("Exchange didnt fill the order")]
   UnfilledOrder,
   #[error("Failed to serialize/deserialize JSON due to: {0}")]
   JsonSerDe(#[from] serde_json::Error),
+  #[error("Invalid {kind} payload at {line}:{column}: {message}, near `{excerpt}`")]
+  InvalidPayload {
+    kind: &'static str,
+    line: usize,
+    column: usize,
+    message: String,
+    excerpt: String,
+  },
+  #[error("Invalid {kind} payload: {message}")]
+  InvalidPayloadValue { kind: &'static str, message: String },
   #[error("Init failed {0}")]
   ConfigOnInit(#[from] ConfigError),
 }
+
+/// Error response of the Binance REST API, by what the caller can do about it. See
+/// <https://binance-docs.github.io/apidocs/spot/en/#error-codes>.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ApiError {
+  #[error("insufficient balance: {0}")]
+  InsufficientBalance(String),
+  #[error("invalid symbol: {0}")]
+  InvalidSymbol(String),
+  #[error("rate limited: {message}")]
+  RateLimited { message: String, retry_after: Option<Duration> },
+  #[error("timestamp outside of recvWindow: {0}")]
+  TimestampOutOfRecvWindow(String),
+  #[error("invalid API key, signature or permissions: {0}")]
+  Unauthorized(String),
+  #[error("order rejected: {0}")]
+  OrderRejected(String),
+  #[error("unknown order: {0}")]
+  UnknownOrder(String),
+  #[error("invalid request: {0}")]
+  InvalidRequest(String),
+  #[error("server error {status}: {message}")]
+  Server { status: u16, message: String },
+  #[error("request failed: {0}")]
+  Transport(String),
+  #[error("code {code}: {message}")]
+  Other { code: i16, message: String },
+}
+
+impl ApiError {
+  pub fn from_code(code: i16, message: String, retry_after: Option<Duration>) -> Self {
+    match code {
+      -1003 | -1015 => Self::RateLimited { message, retry_after },
+      -1021 => Self::TimestampOutOfRecvWindow(message),
+      -1121 => Self::InvalidSymbol(message),
+      -1002 | -1022 | -2014 | -2015 => Self::Unauthorized(message),
+      -2010 if message.to_lowercase().contains("insufficient balance") => {
+        Self::InsufficientBalance(message)
+      },
+      -2010 => Self::OrderRejected(message),
+      -2011 | -2013 => Self::UnknownOrder(message),
+      -1100..=-1013 => Self::InvalidRequest(message),
+      -1001 | -1000 | -1006 | -1007 | -1008 => Self::Server { status: 500, message },
+      _ => Self::Other { code, message },
+    }
+  }
+
+  /// Whether sending the same request again later may succeed.
+  pub fn is_retryable(&self) -> bool {
+    matches!(
+      self,
+      Self::RateLimited { .. }
+        | Self::TimestampOutOfRecvWindow(_)
+        | Self::Server { .. }
+        | Self::Transport(_)
+    )
+  }
+
+  /// How long to wait before retrying, when Binance says so.
+  pub fn retry_after(&self) -> Option<Duration> {
+    match self {
+      Self::RateLimited { retry_after, .. } => *retry_after,
+      _ => None,
+    }
+  }
+}
+
+fn retry_after(headers: &HashMap<String, String>) -> Option<Duration> {
+  headers
+    .iter()
+    .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+    .and_then(|(_, value)| value.trim().parse().ok())
+    .map(Duration::from_secs)
+}
+
+impl From<Box<BinanceHttpError>> for ApiError {
+  fn from(error: Box<BinanceHttpError>) -> Self {
+    match *error {
+      BinanceHttpError::Client(ClientError::Structured(HttpError {
+        status_code,
+        data,
+        headers,
+      })) => {
+        let retry_after = retry_after(&headers);
+        match status_code {
+          // 418 is an IP ban for ignoring 429s
+          429 | 418 => Self::RateLimited { message: data.message, retry_after },
+          _ => Self::from_code(data.code, data.message, retry_after),
+        }
+      },
+      BinanceHttpError::Client(ClientError::Raw(HttpError {
+        status_code,
+        data,
+        headers,
+      })) => match status_code {
+        429 | 418 => {
+          Self::RateLimited { message: data, retry_after: retry_after(&headers) }
+        },
+        _ => Self::InvalidRequest(format!("{}: {}", status_code, data)),
+      },
+      BinanceHttpError::Server(HttpError { status_code, data, .. }) => {
+        Self::Server { status: status_code, message: data }
+      },
+      BinanceHttpError::InvalidApiSecret => {
+        Self::Unauthorized("invalid API secret".to_string())
+      },
+      BinanceHttpError::Parse(e) => Self::InvalidRequest(e.to_string()),
+      BinanceHttpError::Send(e) => Self::Transport(e.to_string()),
+    }
+  }
+}
+
+impl From<Box<BinanceHttpError>> for ExchangeError {
+  fn from(error: Box<BinanceHttpError>) -> Self {
+    ExchangeError::Api(ApiError::from(error))
+  }
+}
@@ -1,9 +1,11 @@
 use super::{
   binance_client::{self, BinanceClient},
-  error::ExchangeError,
+  error::{ApiError, ExchangeError},
+  payload::parse,
 };
 use crate::{
-  assets::{Pair, Side},
+  assets::{Asset, Pair, Side},
+  trading::params::{self, OrderOptions, OrderType},
   utils::serde_utils::f64_from_string,
 };
 use binance_spot_connector_rust::trade::order::TimeInForce;
@@ -15,9 +17,12 @@ pub struct ExchangeFill {
   pub qty: f64,
   pub updated_at: DateTime<Utc>,
   pub price: f64,
+  /// Commission taken in the base asset, which is not held after a buy.
+  pub base_commission: f64,
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ExchangeFillResponseFill {
   #[serde(deserialize_with = "f64_from_string")]
   price: f64,
@@ -25,44 +30,85 @@ pub struct ExchangeFillResponseFill {
   qty: f64,
   #[serde(deserialize_with = "f64_from_string")]
   commission: f64,
+  #[serde(default)]
+  commission_asset: String,
+  #[serde(default)]
+  trade_id: i64,
 }
 
+/// Response of a new order with `newOrderRespType` FULL. Fields that are not used are
+/// still listed, so strict parsing only fails on ones Binance adds.
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExchangeFillResponse {
-  //   "symbol": "BTCUSDT",
-  // "orderId": 28,
-  // "orderListId": -1, //Unless OCO, value will be -1
-  // "clientOrderId": "6gCrw2kRUAF9CvJDGP16IP",
+  #[serde(default)]
+  symbol: String,
+  #[serde(default)]
+  order_id: i64,
+  /// -1 unless part of an OCO
+  #[serde(default)]
+  order_list_id: i64,
+  #[serde(default)]
+  client_order_id: String,
   transact_time: u64,
-  // price: f64,
-  // "origQty": "10.00000000",
+  #[serde(default)]
+  price: String,
+  #[serde(default)]
+  orig_qty: String,
   #[serde(deserialize_with = "f64_from_string")]
   executed_qty: f64,
-  // "cummulativeQuoteQty": "10.00000000",
-  status: String,
-  fills: Vec<ExchangeFillResponseFill>,
-  // "timeInForce": "GTC",
-  // "type": "MARKET",
-  // "side": "SELL",
-  // "workingTime": 1507725176595,
-  // "selfTradePreventionMode": "NONE"
+  #[serde(default)]
+  cummulative_quote_qty: String,
+  pub status: String,
+  pub fills: Vec<ExchangeFillResponseFill>,
+  #[serde(default)]
+  time_in_force: String,
+  #[serde(default, rename = "type")]
+  order_type: String,
+  #[serde(default)]
+  side: String,
+  #[serde(default)]
+  working_time: u64,
+  #[serde(default)]
+  self_trade_prevention_mode: String,
 }
 
+/// Sends an order with the options, limit ones at `price`. What a limit order does not
+/// fill right away is cancelled, so the fill is what executed when it was placed.
 pub fn fill_order(
   binance_client: &BinanceClient,
   pair: Pair,
   qty: f64,
   side: Side,
+  price: f64,
+  options: &OrderOptions,
 ) -> Result<ExchangeFill, ExchangeError> {
   let truncated_qty = (qty * 100_000.0).round() / 100_000.0;
   let dec_qty = rust_decimal::Decimal::from_f64(truncated_qty).unwrap();
-  let request = binance_spot_connector_rust::trade::new_order(
+  let order_type = match options.order_type {
+    OrderType::Market => "MARKET",
+    OrderType::Limit if options.post_only => "LIMIT_MAKER",
+    OrderType::Limit => "LIMIT",
+  };
+  let mut request = binance_spot_connector_rust::trade::new_order(
     &pair.to_string(),
     side.to_binance_side(),
-    "MARKET",
+    order_type,
   )
-  .quantity(dec_qty);
+  .quantity(dec_qty)
+  .recv_window(binance_client.recv_window);
+  if options.order_type == OrderType::Limit {
+    let price = rust_decimal::Decimal::from_f64(price)
+      .ok_or(ExchangeError::BinanceClientError(format!("invalid price {}", price)))?;
+    request = request.price(price.round_dp(pair.price_decimals()));
+  }
+  if order_type == "LIMIT" {
+    request = request.time_in_force(match options.time_in_force {
+      params::TimeInForce::Gtc => TimeInForce::Gtc,
+      params::TimeInForce::Ioc => TimeInForce::Ioc,
+      params::TimeInForce::Fok => TimeInForce::Fok,
+    });
+  }
 
   log::info!(
     "------ INTO REQ -------- dec: {}, qty: {:?}, side: {:?}",
@@ -71,27 +117,43 @@ pub fn fill_order(
     side
   );
 
-  let res = binance_client.client.send(request).map_err(|e| {
-    ExchangeError::BinanceClientError(format!("Error on order fill: {:?}", e))
-  })?;
-
-  let res = res.into_body_str().map_err(|e| {
-    ExchangeError::BinanceClientError(format!("Error parsing fill event res: {:?}", e))
-  })?;
+  let res = binance_client.send(request)?;
 
   log::info!("RES string: {:?}", res);
 
-  let res: ExchangeFillResponse =
-    serde_json::from_str(&res).map_err(|e| ExchangeError::JsonSerDe(e))?;
-  let price = weighted_average_price(res.fills);
-  if res.status == "FILLED" && price.is_some() {
-    Ok(ExchangeFill {
-      qty: res.executed_qty,
-      updated_at: DateTime::from_timestamp_millis(res.transact_time as i64).unwrap(),
-      price: price.unwrap(),
-    })
-  } else {
-    Err(ExchangeError::UnfilledOrder)
+  let res: ExchangeFillResponse = parse("order", &res)?;
+  let updated_at = DateTime::from_timestamp_millis(res.transact_time as i64).ok_or(
+    ExchangeError::InvalidPayloadValue {
+      kind: "order",
+      message: format!("transactTime {} is out of range", res.transact_time),
+    },
+  )?;
+  let base_commission = res
+    .fills
+    .iter()
+    .filter(|fill| fill.commission_asset.parse::<Asset>().ok() == Some(pair.base()))
+    .map(|fill| fill.commission)
+    .sum();
+  let (qty, price) = match res.status.as_str() {
+    "NEW" | "PARTIALLY_FILLED" => {
+      // Resting on the book, cancelled with what filled meanwhile
+      let order_id = res.order_id as u64;
+      match cancel_order(binance_client, pair, order_id) {
+        Ok(()) | Err(ExchangeError::Api(ApiError::UnknownOrder(_))) => {},
+        Err(e) => return Err(e),
+      }
+      let order = query_order(binance_client, pair, order_id)?;
+      let price = (order.executed_qty > 0.0)
+        .then(|| order.cummulative_quote_qty / order.executed_qty);
+      (order.executed_qty, price)
+    },
+    _ => (res.executed_qty, weighted_average_price(res.fills)),
+  };
+  match price {
+    Some(price) if qty > 0.0 => {
+      Ok(ExchangeFill { qty, updated_at, price, base_commission })
+    },
+    _ => Err(ExchangeError::UnfilledOrder),
   }
 }
 
@@ -103,3 +165,108 @@ fn weighted_average_price(fills: Vec<ExchangeFillResponseFill>) -> Option<f64> {
   let weighted_sum: f64 = fills.iter().map(|fill| fill.price * fill.qty).sum();
   Some(weighted_sum / total_weight)
 }
+
+/// Acknowledgement of a new order that is not filled right away.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderAck {
+  symbol: String,
+  order_id: u64,
+  order_list_id: i64,
+  client_order_id: String,
+  transact_time: u64,
+}
+
+/// An order as Binance reports it when queried.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderState {
+  #[serde(default)]
+  symbol: String,
+  pub order_id: u64,
+  #[serde(default)]
+  order_list_id: i64,
+  #[serde(default)]
+  client_order_id: String,
+  #[serde(default)]
+  price: String,
+  #[serde(default)]
+  orig_qty: String,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub executed_qty: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub cummulative_quote_qty: f64,
+  pub status: String,
+  #[serde(default)]
+  time_in_force: String,
+  #[serde(default, rename = "type")]
+  order_type: String,
+  #[serde(default)]
+  side: String,
+  #[serde(default)]
+  stop_price: String,
+  #[serde(default)]
+  iceberg_qty: String,
+  #[serde(default)]
+  time: u64,
+  pub update_time: i64,
+  #[serde(default)]
+  is_working: bool,
+  #[serde(default)]
+  working_time: i64,
+  #[serde(default)]
+  orig_quote_order_qty: String,
+  #[serde(default)]
+  self_trade_prevention_mode: String,
+  #[serde(default)]
+  trailing_delta: u64,
+  #[serde(default)]
+  trailing_time: i64,
+}
+
+/// Sends a `STOP_LOSS` order that trails the price by `trailing_delta` basis points.
+/// Returns its order id.
+pub fn place_trailing_stop(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  qty: f64,
+  side: Side,
+  trailing_delta: u64,
+) -> Result<u64, ExchangeError> {
+  let truncated_qty = (qty * 100_000.0).round() / 100_000.0;
+  let dec_qty = rust_decimal::Decimal::from_f64(truncated_qty).unwrap();
+  let request = binance_spot_connector_rust::trade::new_order(
+    &pair.to_string(),
+    side.to_binance_side(),
+    "STOP_LOSS",
+  )
+  .quantity(dec_qty)
+  .trailing_delta(trailing_delta)
+  .recv_window(binance_client.recv_window);
+  let ack: OrderAck = parse("order_ack", &binance_client.send(request)?)?;
+  log::info!("Placed trailing stop {} of {} {} {}.", ack.order_id, side, dec_qty, pair);
+  Ok(ack.order_id)
+}
+
+pub fn query_order(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  order_id: u64,
+) -> Result<OrderState, ExchangeError> {
+  let request = binance_spot_connector_rust::trade::get_order(&pair.to_string())
+    .order_id(order_id)
+    .recv_window(binance_client.recv_window);
+  parse("query_order", &binance_client.send(request)?)
+}
+
+pub fn cancel_order(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  order_id: u64,
+) -> Result<(), ExchangeError> {
+  let request = binance_spot_connector_rust::trade::cancel_order(&pair.to_string())
+    .order_id(order_id)
+    .recv_window(binance_client.recv_window);
+  binance_client.send(request)?;
+  Ok(())
+}
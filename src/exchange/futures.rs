@@ -0,0 +1,379 @@
+use super::{
+  binance_client::BinanceClient,
+  error::{ApiError, ExchangeError},
+  execution::ExchangeFill,
+  payload::parse,
+};
+use crate::{
+  assets::{Pair, Side},
+  trading::params::{OrderOptions, OrderType},
+  utils::serde_utils::f64_from_string,
+};
+use binance_spot_connector_rust::http::{request::RequestBuilder, Method};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+const ORDER: &str = "/fapi/v1/order";
+const LEVERAGE: &str = "/fapi/v1/leverage";
+const MARGIN_TYPE: &str = "/fapi/v1/marginType";
+const POSITION_MODE: &str = "/fapi/v1/positionSide/dual";
+const POSITION_RISK: &str = "/fapi/v2/positionRisk";
+const ACCOUNT: &str = "/fapi/v2/account";
+/// Margin type and position mode that are already set.
+const NO_CHANGE_CODES: [i16; 2] = [-4046, -4059];
+
+/// How the margin of a futures position is held.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum MarginType {
+  /// Each position only risks the margin set aside for it.
+  #[default]
+  Isolated,
+  /// Positions share the margin of the whole futures wallet.
+  Cross,
+}
+
+/// Whether a pair keeps one net position or a long and a short one at once.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PositionMode {
+  #[default]
+  #[strum(serialize = "one-way")]
+  OneWay,
+  Hedge,
+}
+
+/// Leverage and margin of the USD-M futures positions of a session.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FuturesSettings {
+  /// Binance accepts 1 to 125, depending on the pair and the notional.
+  pub leverage: u32,
+  pub margin_type: MarginType,
+  pub position_mode: PositionMode,
+}
+
+impl Default for FuturesSettings {
+  fn default() -> Self {
+    Self {
+      leverage: 1,
+      margin_type: MarginType::Isolated,
+      position_mode: PositionMode::OneWay,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FuturesOrder {
+  order_id: u64,
+  status: String,
+  #[serde(deserialize_with = "f64_from_string")]
+  executed_qty: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  avg_price: f64,
+  update_time: i64,
+}
+
+/// Margin of the futures wallet against what its positions need to stay open.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct MarginHealth {
+  /// Wallet balance with the unrealised PnL.
+  #[serde(rename = "totalMarginBalance", deserialize_with = "f64_from_string")]
+  pub margin_balance: f64,
+  #[serde(rename = "totalMaintMargin", deserialize_with = "f64_from_string")]
+  pub maintenance_margin: f64,
+}
+
+impl MarginHealth {
+  /// Maintenance margin over the margin balance, Binance liquidates at 1.
+  pub fn ratio(&self) -> f64 {
+    if self.margin_balance <= 0.0 {
+      return if self.maintenance_margin > 0.0 { 1.0 } else { 0.0 };
+    }
+    self.maintenance_margin / self.margin_balance
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawPositionRisk {
+  #[serde(deserialize_with = "f64_from_string")]
+  position_amt: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  liquidation_price: f64,
+}
+
+/// Futures position of a pair as the exchange holds it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ExchangePosition {
+  /// Negative for short positions.
+  pub quantity: f64,
+  pub liquidation_price: Option<f64>,
+}
+
+/// Sends a signed request with the recvWindow of the client.
+fn send_signed<'a>(
+  binance_client: &BinanceClient,
+  method: Method,
+  path: &str,
+  params: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<String, ExchangeError> {
+  let recv_window = binance_client.recv_window.to_string();
+  let request = RequestBuilder::new(method, path)
+    .params(params)
+    .params([("recvWindow", recv_window.as_str())]);
+  binance_client.send(request.sign())
+}
+
+/// Sets the position mode of the account and the margin type and leverage of the pair
+/// before a session trades it. Settings that are already in place are left alone.
+pub fn configure(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  settings: &FuturesSettings,
+) -> Result<(), ExchangeError> {
+  let (symbol, leverage) = (pair.to_string(), settings.leverage.to_string());
+  let dual = (settings.position_mode == PositionMode::Hedge).to_string();
+  let margin_type = match settings.margin_type {
+    MarginType::Isolated => "ISOLATED",
+    MarginType::Cross => "CROSSED",
+  };
+  let changes = [
+    (POSITION_MODE, vec![("dualSidePosition", dual.as_str())]),
+    (MARGIN_TYPE, vec![("symbol", symbol.as_str()), ("marginType", margin_type)]),
+    (LEVERAGE, vec![("symbol", symbol.as_str()), ("leverage", leverage.as_str())]),
+  ];
+  for (path, params) in changes {
+    match send_signed(binance_client, Method::Post, path, params) {
+      Err(ExchangeError::Api(ApiError::Other { code, .. }))
+        if NO_CHANGE_CODES.contains(&code) => {},
+      result => {
+        result?;
+      },
+    }
+  }
+  log::info!(
+    "Trading futures of {} at {}x, {} margin, {} positions.",
+    pair,
+    settings.leverage,
+    settings.margin_type,
+    settings.position_mode
+  );
+  Ok(())
+}
+
+/// Side of the position an order of `side` enters or exits in hedge mode.
+fn position_side(side: Side, is_entry: bool) -> &'static str {
+  match (side, is_entry) {
+    (Side::Buy, true) | (Side::Sell, false) => "LONG",
+    (Side::Sell, true) | (Side::Buy, false) => "SHORT",
+  }
+}
+
+/// Sends a futures order like [super::execution::fill_order] sends a spot one. What a
+/// limit order does not fill right away is cancelled.
+#[allow(clippy::too_many_arguments)]
+pub fn fill_futures_order(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  qty: f64,
+  side: Side,
+  is_entry: bool,
+  price: f64,
+  options: &OrderOptions,
+  settings: &FuturesSettings,
+) -> Result<ExchangeFill, ExchangeError> {
+  let symbol = pair.to_string();
+  let quantity = ((qty * 100_000.0).round() / 100_000.0).to_string();
+  let price = format!("{:.*}", pair.price_decimals() as usize, price);
+  let time_in_force = match options.post_only {
+    true => "GTX".to_string(),
+    false => options.time_in_force.to_string(),
+  };
+  let mut params = vec![
+    ("symbol", symbol.as_str()),
+    ("side", if side == Side::Buy { "BUY" } else { "SELL" }),
+    ("quantity", quantity.as_str()),
+    ("newOrderRespType", "RESULT"),
+  ];
+  match options.order_type {
+    OrderType::Market => params.push(("type", "MARKET")),
+    OrderType::Limit => params.extend([
+      ("type", "LIMIT"),
+      ("price", price.as_str()),
+      ("timeInForce", time_in_force.as_str()),
+    ]),
+  }
+  match settings.position_mode {
+    PositionMode::Hedge => params.push(("positionSide", position_side(side, is_entry))),
+    PositionMode::OneWay if !is_entry => params.push(("reduceOnly", "true")),
+    PositionMode::OneWay => {},
+  }
+  let res = send_signed(binance_client, Method::Post, ORDER, params)?;
+  let mut order: FuturesOrder = parse("futures_order", &res)?;
+  if matches!(order.status.as_str(), "NEW" | "PARTIALLY_FILLED") {
+    // Resting on the book, cancelled with what filled meanwhile
+    let order_id = order.order_id.to_string();
+    let params = [("symbol", symbol.as_str()), ("orderId", order_id.as_str())];
+    match send_signed(binance_client, Method::Delete, ORDER, params) {
+      Ok(_) | Err(ExchangeError::Api(ApiError::UnknownOrder(_))) => {},
+      Err(e) => return Err(e),
+    }
+    let res = send_signed(binance_client, Method::Get, ORDER, params)?;
+    order = parse("futures_query_order", &res)?;
+  }
+  let updated_at = DateTime::from_timestamp_millis(order.update_time).ok_or(
+    ExchangeError::InvalidPayloadValue {
+      kind: "futures_order",
+      message: format!("updateTime {} is out of range", order.update_time),
+    },
+  )?;
+  let (qty, price) = (order.executed_qty, order.avg_price);
+  match qty > 0.0 && price > 0.0 {
+    true => Ok(ExchangeFill { qty, updated_at, price, base_commission: 0.0 }),
+    false => Err(ExchangeError::UnfilledOrder),
+  }
+}
+
+/// Open position of the pair on the exchange, none once it was exited or liquidated.
+pub fn fetch_position(
+  binance_client: &BinanceClient,
+  pair: Pair,
+) -> Result<Option<ExchangePosition>, ExchangeError> {
+  let symbol = pair.to_string();
+  let params = [("symbol", symbol.as_str())];
+  let res = send_signed(binance_client, Method::Get, POSITION_RISK, params)?;
+  let positions: Vec<RawPositionRisk> = parse("position_risk", &res)?;
+  Ok(positions.into_iter().find(|position| position.position_amt != 0.0).map(
+    |position| ExchangePosition {
+      quantity: position.position_amt,
+      liquidation_price: Some(position.liquidation_price).filter(|price| *price > 0.0),
+    },
+  ))
+}
+
+/// Margin balance and maintenance margin of the whole futures wallet.
+pub fn fetch_margin_health(
+  binance_client: &BinanceClient,
+) -> Result<MarginHealth, ExchangeError> {
+  let res = send_signed(binance_client, Method::Get, ACCOUNT, [])?;
+  parse("futures_account", &res)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+  };
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn configures_and_trades_futures() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path(POSITION_MODE))
+      .respond_with(ResponseTemplate::new(400).set_body_json(
+        json!({ "code": -4059, "msg": "No need to change position side." }),
+      ))
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path(MARGIN_TYPE))
+      .and(query_param("marginType", "ISOLATED"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": 200 })))
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path(LEVERAGE))
+      .and(query_param("leverage", "5"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "leverage": 5 })))
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path(ORDER))
+      .and(query_param("type", "MARKET"))
+      .and(query_param("reduceOnly", "true"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "orderId": 9,
+        "status": "FILLED",
+        "executedQty": "0.010",
+        "avgPrice": "41000.50",
+        "updateTime": 1_700_000_000_000i64
+      })))
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path(POSITION_RISK))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+        { "positionSide": "BOTH", "positionAmt": "0.010", "liquidationPrice": "33210.4" }
+      ])))
+      .mount(&server)
+      .await;
+    let binance_client = BinanceClient::with_url(&server.uri());
+    let settings = FuturesSettings { leverage: 5, ..Default::default() };
+
+    configure(&binance_client, Pair::BTCUSDT, &settings).unwrap();
+    let options = OrderOptions::default();
+    let fill = fill_futures_order(
+      &binance_client,
+      Pair::BTCUSDT,
+      0.01,
+      Side::Sell,
+      false,
+      41_000.0,
+      &options,
+      &settings,
+    )
+    .unwrap();
+    assert_eq!((fill.qty, fill.price), (0.01, 41_000.5));
+    let position = fetch_position(&binance_client, Pair::BTCUSDT).unwrap().unwrap();
+    assert_eq!(position.liquidation_price, Some(33_210.4));
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn no_position_once_liquidated() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path(POSITION_RISK))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+        { "positionSide": "BOTH", "positionAmt": "0.000", "liquidationPrice": "0" }
+      ])))
+      .mount(&server)
+      .await;
+    let binance_client = BinanceClient::with_url(&server.uri());
+    assert_eq!(fetch_position(&binance_client, Pair::BTCUSDT).unwrap(), None);
+  }
+}
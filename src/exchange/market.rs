@@ -0,0 +1,75 @@
+use super::{binance_client::BinanceClient, error::ExchangeError, BinanceKline};
+use crate::{
+  assets::{Level, OrderBookL1, Pair},
+  utils::serde_utils::f64_from_string,
+};
+use binance_spot_connector_rust::market::klines::KlineInterval;
+use chrono::Utc;
+use serde::Deserialize;
+
+/// Number of hourly closes shown as the sparkline of a ticker.
+const SPARKLINE_HOURS: u32 = 24;
+
+/// 24h statistics of a symbol, as returned by the `ticker/24hr` endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticker24h {
+  pub symbol: String,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub last_price: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub price_change_percent: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub quote_volume: f64,
+}
+
+pub fn fetch_tickers_24h(
+  binance_client: &BinanceClient,
+  symbols: &[String],
+) -> Result<Vec<Ticker24h>, ExchangeError> {
+  let request = binance_spot_connector_rust::market::ticker_twenty_four_hr()
+    .symbols(symbols.iter().map(String::as_str).collect());
+  let res = binance_client.send(request)?;
+  Ok(serde_json::from_str(&res)?)
+}
+
+/// Hourly closes of the last day, oldest first.
+pub fn fetch_sparkline(
+  binance_client: &BinanceClient,
+  symbol: &str,
+) -> Result<Vec<f64>, ExchangeError> {
+  let request =
+    binance_spot_connector_rust::market::klines(symbol, KlineInterval::Hours1)
+      .limit(SPARKLINE_HOURS);
+  let res = binance_client.send(request)?;
+  let klines: Vec<BinanceKline> = serde_json::from_str(&res)?;
+  Ok(klines.iter().filter_map(|kline| kline.4.parse().ok()).collect())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BookTicker {
+  #[serde(deserialize_with = "f64_from_string")]
+  bid_price: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  bid_qty: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  ask_price: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  ask_qty: f64,
+}
+
+/// Best bid and ask of the pair with their quantities.
+pub fn fetch_book_ticker(
+  binance_client: &BinanceClient,
+  pair: Pair,
+) -> Result<OrderBookL1, ExchangeError> {
+  let request =
+    binance_spot_connector_rust::market::book_ticker().symbol(&pair.to_string());
+  let ticker: BookTicker = serde_json::from_str(&binance_client.send(request)?)?;
+  Ok(OrderBookL1 {
+    last_update_time: Utc::now(),
+    best_bid: Level { price: ticker.bid_price, amount: ticker.bid_qty },
+    best_ask: Level { price: ticker.ask_price, amount: ticker.ask_qty },
+  })
+}
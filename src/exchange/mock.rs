@@ -0,0 +1,237 @@
+use super::binance_client::BinanceClient;
+use crate::assets::{Candle, Pair};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::{net::TcpListener, sync::broadcast};
+use tokio_tungstenite::tungstenite::Message;
+use wiremock::{
+  matchers::{method, path},
+  Mock, MockServer, Request, Respond, ResponseTemplate,
+};
+
+/// Binance for tests: the REST API on a wiremock server and the market stream on an
+/// in-process websocket, so sessions run without touching the network. Every kline and
+/// fill is at the same price.
+pub struct MockExchange {
+  server: MockServer,
+  stream_url: String,
+  stream_tx: broadcast::Sender<String>,
+}
+
+impl MockExchange {
+  /// Serves the server time, closed klines up to now and fills market orders in full.
+  pub async fn start(price: f64) -> Self {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/api/v3/time"))
+      .respond_with(
+        ResponseTemplate::new(200)
+          .set_body_json(json!({ "serverTime": Utc::now().timestamp_millis() })),
+      )
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/api/v3/klines"))
+      .respond_with(Klines { price })
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .respond_with(Fills { price })
+      .mount(&server)
+      .await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let stream_url = format!("ws://{}", listener.local_addr().unwrap());
+    let (stream_tx, _) = broadcast::channel(100);
+    tokio::spawn(serve_stream(listener, stream_tx.clone()));
+    Self { server, stream_url, stream_tx }
+  }
+
+  /// Client of the REST API, with credentials the mock does not check.
+  pub fn client(&self) -> BinanceClient {
+    BinanceClient::with_url(&self.server.uri())
+  }
+
+  pub fn stream_url(&self) -> &str {
+    &self.stream_url
+  }
+
+  /// Sends the kline to every connection to the market stream.
+  pub fn send_kline(&self, pair: Pair, candle: &Candle, is_closed: bool) {
+    let kline = json!({
+      "e": "kline",
+      "E": Utc::now().timestamp_millis(),
+      "s": pair.to_string(),
+      "k": {
+        "t": candle.open_time.timestamp_millis(),
+        "T": candle.close_time.timestamp_millis(),
+        "s": pair.to_string(),
+        "i": "1m",
+        "f": 0,
+        "L": candle.trade_count,
+        "o": candle.open.to_string(),
+        "c": candle.close.to_string(),
+        "h": candle.high.to_string(),
+        "l": candle.low.to_string(),
+        "v": candle.volume.to_string(),
+        "n": candle.trade_count,
+        "x": is_closed,
+        "q": (candle.volume * candle.close).to_string(),
+        "V": "0",
+        "Q": "0",
+        "B": "0"
+      }
+    });
+    let _ = self.stream_tx.send(kline.to_string());
+  }
+
+  /// Rejects orders with the Binance error code and message instead of filling them.
+  pub async fn reject_orders(&self, code: i16, message: &str) {
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .respond_with(
+        ResponseTemplate::new(400).set_body_json(json!({ "code": code, "msg": message })),
+      )
+      .with_priority(1)
+      .mount(&self.server)
+      .await;
+  }
+
+  /// Order requests received so far.
+  pub async fn orders(&self) -> Vec<Request> {
+    let requests = self.server.received_requests().await.unwrap_or_default();
+    requests.into_iter().filter(|request| request.url.path() == "/api/v3/order").collect()
+  }
+}
+
+/// Candle of the minute at the price.
+pub fn candle(open_time: DateTime<Utc>, price: f64) -> Candle {
+  Candle {
+    open_time,
+    close_time: open_time + Duration::try_milliseconds(59_999).unwrap(),
+    open: price,
+    high: price,
+    low: price,
+    close: price,
+    volume: 1.0,
+    trade_count: 1,
+  }
+}
+
+/// Parameters of a request, the connector sends them in the query of every method.
+fn params(request: &Request) -> HashMap<String, String> {
+  request.url.query_pairs().into_owned().collect()
+}
+
+/// Closed one minute klines from `startTime`, at most `limit` of them.
+struct Klines {
+  price: f64,
+}
+
+impl Respond for Klines {
+  fn respond(&self, request: &Request) -> ResponseTemplate {
+    let params = params(request);
+    let minute = Duration::try_minutes(1).unwrap();
+    let start = params
+      .get("startTime")
+      .and_then(|start| start.parse().ok())
+      .and_then(DateTime::from_timestamp_millis)
+      .unwrap_or_else(Utc::now);
+    let limit = params.get("limit").and_then(|limit| limit.parse().ok()).unwrap_or(500);
+    let mut open_time = start.duration_trunc(minute).unwrap();
+    if open_time < start {
+      open_time += minute;
+    }
+    let mut klines = Vec::new();
+    while open_time + minute <= Utc::now() && klines.len() < limit {
+      let price = self.price.to_string();
+      klines.push(json!([
+        open_time.timestamp_millis(),
+        price,
+        price,
+        price,
+        price,
+        "1",
+        (open_time + minute).timestamp_millis() - 1,
+        price,
+        1,
+        "0",
+        "0",
+        "0"
+      ]));
+      open_time += minute;
+    }
+    ResponseTemplate::new(200).set_body_json(klines)
+  }
+}
+
+/// Fills the whole quantity of a market order.
+struct Fills {
+  price: f64,
+}
+
+impl Respond for Fills {
+  fn respond(&self, request: &Request) -> ResponseTemplate {
+    let params = params(request);
+    let quantity = params.get("quantity").cloned().unwrap_or_default();
+    let now = Utc::now().timestamp_millis();
+    ResponseTemplate::new(200).set_body_json(json!({
+      "symbol": params.get("symbol"),
+      "orderId": 1,
+      "orderListId": -1,
+      "clientOrderId": "mock",
+      "transactTime": now,
+      "price": "0",
+      "origQty": quantity,
+      "executedQty": quantity,
+      "cummulativeQuoteQty": "0",
+      "status": "FILLED",
+      "timeInForce": "GTC",
+      "type": "MARKET",
+      "side": params.get("side"),
+      "workingTime": now,
+      "selfTradePreventionMode": "NONE",
+      "fills": [{
+        "price": self.price.to_string(),
+        "qty": quantity,
+        "commission": "0",
+        "commissionAsset": "USDT",
+        "tradeId": 1
+      }]
+    }))
+  }
+}
+
+/// Forwards the stream messages to every connection, subscriptions are not answered.
+async fn serve_stream(listener: TcpListener, stream_tx: broadcast::Sender<String>) {
+  while let Ok((socket, _)) = listener.accept().await {
+    let mut messages = stream_tx.subscribe();
+    tokio::spawn(async move {
+      let Ok(websocket) = tokio_tungstenite::accept_async(socket).await else {
+        return;
+      };
+      let (mut sink, mut incoming) = websocket.split();
+      loop {
+        tokio::select! {
+          message = messages.recv() => match message {
+            Ok(text) => {
+              if sink.send(Message::Text(text)).await.is_err() {
+                return;
+              }
+            },
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+          },
+          request = incoming.next() => {
+            if !matches!(request, Some(Ok(_))) {
+              return;
+            }
+          },
+        }
+      }
+    });
+  }
+}
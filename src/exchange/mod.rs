@@ -1,16 +1,26 @@
 pub mod account;
 pub mod binance_client;
+pub mod convert;
+pub mod derivatives;
 pub mod error;
 pub mod execution;
+pub mod futures;
+pub mod market;
+#[cfg(test)]
+pub mod mock;
+pub mod payload;
+pub mod sim;
+pub mod trades;
+pub mod transfer;
 
-use self::account::ExchangeAccount;
+use self::account::{AccountName, ApiRestrictions, ExchangeAccount};
 use self::binance_client::BinanceClient;
 use self::error::ExchangeError;
 use crate::assets::{MarketEvent, MarketEventDetail};
 use crate::portfolio::balance::Balance;
 use crate::utils::serde_utils::f64_default;
 use crate::{
-  assets::{error::AssetError, Candle, Pair},
+  assets::{error::AssetError, Candle, Pair, CANDLE_INTERVAL},
   database::Database,
   utils::formatting::timestamp_to_dt,
 };
@@ -20,7 +30,7 @@ use binance_spot_connector_rust::{
   market::klines::KlineInterval, wallet::user_asset::UserAsset,
 };
 use chrono::{DateTime, Duration, Utc};
-use futures::TryFutureExt;
+use ::futures::TryFutureExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -28,9 +38,13 @@ use tokio::sync::Mutex;
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum ExchangeEvent {
-  ExchangeAccount(ExchangeAccount),
-  ExchangeBalanceUpdate(Vec<(String, Balance)>),
+  ExchangeAccount(AccountName, ExchangeAccount),
+  ExchangeBalanceUpdate(AccountName, Vec<(String, Balance)>),
+  /// Restrictions of the account's API key, checked once at startup.
+  ApiRestrictions(AccountName, ApiRestrictions),
   Market(MarketEvent),
+  /// Payload of the market stream that failed to parse, with where and an excerpt.
+  InvalidPayload(String),
 }
 
 pub async fn fetch_candles(
@@ -38,32 +52,36 @@ pub async fn fetch_candles(
   asset: Pair,
   binance_client: Arc<BinanceClient>,
 ) -> Result<Vec<Candle>, ExchangeError> {
-  let mut start_time: i64 = (Utc::now() - duration).timestamp_millis();
+  fetch_candles_with_progress(duration, asset, binance_client, |_| {}).await
+}
+
+/// [fetch_candles], calling `progress` with the share of the duration fetched after every
+/// page of candles.
+pub async fn fetch_candles_with_progress(
+  duration: Duration,
+  asset: Pair,
+  binance_client: Arc<BinanceClient>,
+  mut progress: impl FnMut(f64),
+) -> Result<Vec<Candle>, ExchangeError> {
+  let first_time = (Utc::now() - duration).timestamp_millis();
+  let mut start_time: i64 = first_time;
   let mut candles = Vec::<Candle>::new();
   loop {
     tokio::select! {
         _ = tokio::time::sleep(tokio::time::Duration::from_millis(10)) => {
             log::info!("Loading candles from: {:?}", timestamp_to_dt(start_time));
-            let request = binance_spot_connector_rust::market::klines(&asset.to_string(), KlineInterval::Minutes1)
+            let request = binance_spot_connector_rust::market::klines(&asset.to_string(), CANDLE_INTERVAL)
                 .start_time(start_time as u64)
                 .limit(1000);
-            let klines;
-            {
-                let data = binance_client.client
-                    .send(request)
-                    .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))
-                    ?;
-                klines = data
-                    .into_body_str()
-                    .map_err(|e| ExchangeError::BinanceClientError(format!("{:?}", e)))
-                    ?;
-            };
+            let klines = binance_client.send(request)?;
 
             let new_candles = parse_binance_klines(&klines).await?;
             let last_candle = &new_candles.last();
             if let Some(last_candle) = last_candle {
                 start_time = last_candle.close_time.timestamp_millis();
                 candles.extend(new_candles);// .concat(new_candles);
+                let fetched = (start_time - first_time) as f64;
+                progress((fetched / duration.num_milliseconds().max(1) as f64).min(1.0));
             } else {
                 break
             }
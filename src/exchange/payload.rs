@@ -0,0 +1,353 @@
+use super::error::ExchangeError;
+use serde::{
+  de::{
+    DeserializeOwned, DeserializeSeed, Error as _, IntoDeserializer, MapAccess, SeqAccess,
+    Visitor,
+  },
+  forward_to_deserialize_any, Deserializer,
+};
+use serde_json::Value;
+use std::{
+  cell::RefCell,
+  iter::Enumerate,
+  slice,
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Characters of the payload shown around the position of a parse error.
+const EXCERPT_RADIUS: usize = 40;
+
+/// Set from `strict_parsing` of the config.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Makes [parse] reject payloads with fields the payload structs do not know, so changes
+/// to the API surface as errors.
+pub fn set_strict(strict: bool) {
+  STRICT.store(strict, Ordering::Relaxed);
+}
+
+/// Parses a Binance payload, reporting which payload failed, where and with an excerpt
+/// around the offending part. Strict parsing also rejects fields the payload structs do
+/// not know.
+pub fn parse<T: DeserializeOwned>(
+  kind: &'static str,
+  payload: &str,
+) -> Result<T, ExchangeError> {
+  parse_checked(kind, payload, STRICT.load(Ordering::Relaxed))
+}
+
+fn parse_checked<T: DeserializeOwned>(
+  kind: &'static str,
+  payload: &str,
+  strict: bool,
+) -> Result<T, ExchangeError> {
+  let parsed = serde_json::from_str(payload).map_err(|e| ExchangeError::InvalidPayload {
+    kind,
+    line: e.line(),
+    column: e.column(),
+    excerpt: excerpt(payload, e.line(), e.column()),
+    message: e.to_string(),
+  })?;
+  if strict {
+    if let Some((path, key)) = unknown_field::<T>(payload) {
+      let (line, column) = position(payload, &key);
+      return Err(ExchangeError::InvalidPayload {
+        kind,
+        line,
+        column,
+        excerpt: excerpt(payload, line, column),
+        message: format!("unknown field `{}`", path),
+      });
+    }
+  }
+  Ok(parsed)
+}
+
+fn excerpt(payload: &str, line: usize, column: usize) -> String {
+  let line = payload.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+  let chars: Vec<char> = line.chars().collect();
+  let at = column.saturating_sub(1).min(chars.len());
+  let start = at.saturating_sub(EXCERPT_RADIUS);
+  let end = (at + EXCERPT_RADIUS).min(chars.len());
+  chars[start..end].iter().collect()
+}
+
+/// Line and column of the first occurrence of the key in the payload.
+fn position(payload: &str, key: &str) -> (usize, usize) {
+  let at = payload.find(&format!("\"{}\"", key)).unwrap_or_default();
+  let before = &payload[..at];
+  let line = before.matches('\n').count() + 1;
+  let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+  (line, column)
+}
+
+/// Path and key of the first field of the payload that the struct it is parsed into, or
+/// one nested in it, does not know.
+fn unknown_field<T: DeserializeOwned>(payload: &str) -> Option<(String, String)> {
+  let value: Value = serde_json::from_str(payload).ok()?;
+  let unknown = RefCell::new(None);
+  let _ = T::deserialize(Known { value: &value, path: String::new(), unknown: &unknown });
+  unknown.into_inner()
+}
+
+fn join(path: &str, key: &str) -> String {
+  if path.is_empty() {
+    key.to_string()
+  } else {
+    format!("{}.{}", path, key)
+  }
+}
+
+/// Deserializes a parsed payload, noting the first field of an object that the struct
+/// it is deserialized as does not list.
+struct Known<'a> {
+  value: &'a Value,
+  path: String,
+  unknown: &'a RefCell<Option<(String, String)>>,
+}
+
+impl<'de> Deserializer<'de> for Known<'_> {
+  type Error = serde_json::Error;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+    let Known { value, path, unknown } = self;
+    match value {
+      Value::Array(values) => {
+        visitor.visit_seq(KnownSeq { values: values.iter().enumerate(), path, unknown })
+      },
+      Value::Object(map) => {
+        visitor.visit_map(KnownMap { entries: map.iter(), next: None, path, unknown })
+      },
+      value => value.clone().deserialize_any(visitor),
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    match self.value {
+      Value::Null => visitor.visit_none(),
+      _ => visitor.visit_some(self),
+    }
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    fields: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    if let Value::Object(map) = self.value {
+      let mut unknown = self.unknown.borrow_mut();
+      if unknown.is_none() {
+        *unknown = map
+          .keys()
+          .find(|key| !fields.contains(&key.as_str()))
+          .map(|key| (join(&self.path, key), key.clone()));
+      }
+    }
+    self.deserialize_any(visitor)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    name: &'static str,
+    variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    self.value.clone().deserialize_enum(name, variants, visitor)
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+    unit unit_struct seq tuple tuple_struct map identifier ignored_any
+  }
+}
+
+struct KnownSeq<'a> {
+  values: Enumerate<slice::Iter<'a, Value>>,
+  path: String,
+  unknown: &'a RefCell<Option<(String, String)>>,
+}
+
+impl<'de> SeqAccess<'de> for KnownSeq<'_> {
+  type Error = serde_json::Error;
+
+  fn next_element_seed<S: DeserializeSeed<'de>>(
+    &mut self,
+    seed: S,
+  ) -> Result<Option<S::Value>, Self::Error> {
+    let Some((i, value)) = self.values.next() else {
+      return Ok(None);
+    };
+    let path = format!("{}[{}]", self.path, i);
+    seed.deserialize(Known { value, path, unknown: self.unknown }).map(Some)
+  }
+}
+
+struct KnownMap<'a> {
+  entries: serde_json::map::Iter<'a>,
+  next: Option<(&'a String, &'a Value)>,
+  path: String,
+  unknown: &'a RefCell<Option<(String, String)>>,
+}
+
+impl<'de> MapAccess<'de> for KnownMap<'_> {
+  type Error = serde_json::Error;
+
+  fn next_key_seed<S: DeserializeSeed<'de>>(
+    &mut self,
+    seed: S,
+  ) -> Result<Option<S::Value>, Self::Error> {
+    let Some((key, value)) = self.entries.next() else {
+      return Ok(None);
+    };
+    self.next = Some((key, value));
+    seed.deserialize(key.as_str().into_deserializer()).map(Some)
+  }
+
+  fn next_value_seed<S: DeserializeSeed<'de>>(
+    &mut self,
+    seed: S,
+  ) -> Result<S::Value, Self::Error> {
+    let (key, value) =
+      self.next.take().ok_or_else(|| serde_json::Error::custom("value without a key"))?;
+    let path = join(&self.path, key);
+    seed.deserialize(Known { value, path, unknown: self.unknown })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use super::*;
+  use crate::{
+    assets::asset_ticker::KlineEvent,
+    exchange::{
+      account::{ApiRestrictions, RawExchangeAccount},
+      execution::ExchangeFillResponse,
+      trades::AccountTrade,
+    },
+  };
+
+  const KLINE: &str = include_str!("../../tests/payloads/kline.json");
+  const ACCOUNT: &str = include_str!("../../tests/payloads/account.json");
+  const ORDER_FULL: &str = include_str!("../../tests/payloads/order_full.json");
+  const MY_TRADES: &str = include_str!("../../tests/payloads/my_trades.json");
+  const API_RESTRICTIONS: &str =
+    include_str!("../../tests/payloads/api_restrictions.json");
+
+  #[test]
+  fn test_parse_kline_sample() {
+    let kline: KlineEvent = parse("kline", KLINE).unwrap();
+    assert_eq!(kline.symbol, "BTCUSDT");
+    assert_eq!(kline.detail.close_price, 42005.37);
+    assert!(kline.detail.is_closed);
+  }
+
+  #[test]
+  fn test_parse_account_sample() {
+    let account: RawExchangeAccount = parse("account", ACCOUNT).unwrap();
+    let account = account.to_exchange_account();
+    assert_eq!(account.get_balances().len(), 2);
+  }
+
+  #[test]
+  fn test_parse_order_sample() {
+    let order: ExchangeFillResponse = parse("order", ORDER_FULL).unwrap();
+    assert_eq!(order.status, "FILLED");
+    assert_eq!(order.fills.len(), 2);
+  }
+
+  #[test]
+  fn test_parse_my_trades_sample() {
+    let trades: Vec<AccountTrade> = parse("my_trades", MY_TRADES).unwrap();
+    assert_eq!(trades.len(), 2);
+    assert!(trades[0].is_buyer);
+    assert_eq!(trades[1].commission_asset, "BTC");
+  }
+
+  #[test]
+  fn test_parse_api_restrictions_sample() {
+    let restrictions: ApiRestrictions =
+      parse("api_restrictions", API_RESTRICTIONS).unwrap();
+    assert!(!restrictions.enable_withdrawals);
+    assert!(restrictions.enable_spot_and_margin_trading);
+  }
+
+  #[test]
+  fn test_parse_error_details() {
+    let payload = KLINE.replacen("\"x\": true", "\"x\": \"yes\"", 1);
+    match parse::<KlineEvent>("kline", &payload) {
+      Err(ExchangeError::InvalidPayload { kind, line, excerpt, .. }) => {
+        assert_eq!(kind, "kline");
+        assert!(line > 1);
+        assert!(excerpt.contains("yes"));
+      },
+      other => panic!("expected an invalid payload error, got {:?}", other.map(|_| ())),
+    }
+  }
+
+  #[test]
+  fn test_strict_parsing_accepts_the_samples() {
+    parse_checked::<KlineEvent>("kline", KLINE, true).unwrap();
+    parse_checked::<RawExchangeAccount>("account", ACCOUNT, true).unwrap();
+    parse_checked::<ExchangeFillResponse>("order", ORDER_FULL, true).unwrap();
+    parse_checked::<Vec<AccountTrade>>("my_trades", MY_TRADES, true).unwrap();
+    parse_checked::<ApiRestrictions>("api_restrictions", API_RESTRICTIONS, true).unwrap();
+  }
+
+  #[test]
+  fn test_strict_parsing_rejects_unknown_fields() {
+    let payload = ACCOUNT.replacen("\"uid\"", "\"newField\": 1, \"uid\"", 1);
+    assert!(parse_checked::<RawExchangeAccount>("account", &payload, false).is_ok());
+    match parse_checked::<RawExchangeAccount>("account", &payload, true) {
+      Err(ExchangeError::InvalidPayload { message, excerpt, .. }) => {
+        assert!(message.contains("`newField`"));
+        assert!(excerpt.contains("newField"));
+      },
+      other => panic!("expected an invalid payload error, got {:?}", other.map(|_| ())),
+    }
+  }
+
+  #[test]
+  fn test_strict_parsing_rejects_unknown_nested_fields() {
+    let payload = ORDER_FULL.replacen("\"tradeId\"", "\"newField\": 1, \"tradeId\"", 1);
+    match parse_checked::<ExchangeFillResponse>("order", &payload, true) {
+      Err(ExchangeError::InvalidPayload { message, line, .. }) => {
+        assert!(message.contains("`fills[0].newField`"));
+        assert!(line > 1);
+      },
+      other => panic!("expected an invalid payload error, got {:?}", other.map(|_| ())),
+    }
+  }
+
+  proptest! {
+    #[test]
+    fn test_parse_never_panics_on_arbitrary_input(payload in "\\PC*") {
+      let _ = parse::<KlineEvent>("kline", &payload);
+      let _ = parse::<RawExchangeAccount>("account", &payload);
+      let _ = parse::<ExchangeFillResponse>("order", &payload);
+    }
+
+    #[test]
+    fn test_parse_never_panics_on_truncated_samples(cut in 0..1usize << 16) {
+      for sample in [KLINE, ACCOUNT, ORDER_FULL] {
+        let cut = sample.char_indices().nth(cut % sample.len()).map_or(0, |(i, _)| i);
+        let _ = parse::<KlineEvent>("kline", &sample[..cut]);
+        let _ = parse::<RawExchangeAccount>("account", &sample[..cut]);
+        let _ = parse::<ExchangeFillResponse>("order", &sample[..cut]);
+      }
+    }
+  }
+}
@@ -0,0 +1,274 @@
+use super::{
+  error::{ApiError, ExchangeError},
+  execution::ExchangeFill,
+};
+use crate::{
+  assets::{Candle, Pair, Side},
+  trading::params::{OrderOptions, OrderType, TimeInForce},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Simulated exchange of sessions that do not run live, from `sim_exchange` in the
+/// config. Without it their orders still go to the exchange, Binance testnet by default.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SimConfig {
+  pub enabled: bool,
+  /// Price market orders lose to the last price, in basis points.
+  pub slippage_bps: f64,
+  /// Share of the volume of a candle that resting limit orders fill in it.
+  pub volume_share: f64,
+}
+
+impl Default for SimConfig {
+  fn default() -> Self {
+    Self { enabled: false, slippage_bps: 2.0, volume_share: 0.1 }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimOrder {
+  pub id: u64,
+  pub pair: Pair,
+  pub side: Side,
+  pub quantity: f64,
+  pub filled: f64,
+  /// None for market orders.
+  pub limit: Option<f64>,
+}
+
+impl SimOrder {
+  pub fn remaining(&self) -> f64 {
+    (self.quantity - self.filled).max(0.0)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimFill {
+  pub order_id: u64,
+  pub pair: Pair,
+  pub side: Side,
+  pub qty: f64,
+  pub price: f64,
+  pub time: DateTime<Utc>,
+}
+
+/// Exchange in memory without any network. Orders fill against the last price of their
+/// pair, moved by replayed candles or the points of a synthetic price path, and limit
+/// orders that do not fill at once rest on its book until a candle reaches them.
+#[derive(Debug, Default)]
+pub struct SimExchange {
+  config: SimConfig,
+  next_id: u64,
+  /// Last price of each pair and when it was set.
+  prices: HashMap<Pair, (f64, DateTime<Utc>)>,
+  book: Vec<SimOrder>,
+  fills: Vec<SimFill>,
+}
+
+impl SimExchange {
+  pub fn new(config: SimConfig) -> Self {
+    Self { config, ..Self::default() }
+  }
+
+  pub fn price(&self, pair: Pair) -> Option<f64> {
+    self.prices.get(&pair).map(|(price, _)| *price)
+  }
+
+  /// Resting limit orders of the pair.
+  pub fn open_orders(&self, pair: Pair) -> impl Iterator<Item = &SimOrder> {
+    self.book.iter().filter(move |order| order.pair == pair)
+  }
+
+  /// Every fill so far, oldest first.
+  pub fn fills(&self) -> &[SimFill] {
+    &self.fills
+  }
+
+  /// Moves the pair to the close of the candle. Resting orders whose limit the candle
+  /// reached fill at their limit, or at the open when the candle gapped past it, up to
+  /// the share of its volume.
+  pub fn on_candle(&mut self, pair: Pair, candle: &Candle) -> Vec<SimFill> {
+    let time = candle.close_time;
+    let mut capacity = if candle.volume > 0.0 {
+      candle.volume * self.config.volume_share
+    } else {
+      f64::INFINITY
+    };
+    let mut fills = Vec::new();
+    for order in self.book.iter_mut().filter(|order| order.pair == pair) {
+      let Some(limit) = order.limit else {
+        continue;
+      };
+      let price = match order.side {
+        Side::Buy if candle.low <= limit => limit.min(candle.open),
+        Side::Sell if candle.high >= limit => limit.max(candle.open),
+        _ => continue,
+      };
+      let qty = order.remaining().min(capacity);
+      if qty <= 0.0 {
+        break;
+      }
+      capacity -= qty;
+      order.filled += qty;
+      let side = order.side.clone();
+      fills.push(SimFill { order_id: order.id, pair, side, qty, price, time });
+    }
+    self.book.retain(|order| order.remaining() > 0.0);
+    self.prices.insert(pair, (candle.close, time));
+    self.fills.extend(fills.iter().cloned());
+    fills
+  }
+
+  /// Moves the pair to a point of a synthetic price path, a candle of the one price.
+  pub fn on_price(
+    &mut self,
+    pair: Pair,
+    price: f64,
+    time: DateTime<Utc>,
+  ) -> Vec<SimFill> {
+    let candle = Candle {
+      open_time: time,
+      close_time: time,
+      open: price,
+      high: price,
+      low: price,
+      close: price,
+      volume: 0.0,
+      trade_count: 0,
+    };
+    self.on_candle(pair, &candle)
+  }
+
+  /// Places the order. Market orders and limit orders that cross the last price fill in
+  /// full at once, the rest of a `GTC` limit order rests on the book and that of `IOC`
+  /// and `FOK` orders expires. Post-only orders that would fill at once are rejected, as
+  /// `LIMIT_MAKER` orders are by Binance.
+  pub fn place(
+    &mut self,
+    pair: Pair,
+    side: Side,
+    quantity: f64,
+    limit: f64,
+    options: &OrderOptions,
+  ) -> Result<SimOrder, ExchangeError> {
+    let (last, time) = *self
+      .prices
+      .get(&pair)
+      .ok_or(ApiError::InvalidSymbol(format!("no price of {} yet", pair)))?;
+    self.next_id += 1;
+    let mut order =
+      SimOrder { id: self.next_id, pair, side, quantity, filled: 0.0, limit: None };
+    let slippage = last * self.config.slippage_bps / 10_000.0;
+    let price = match (options.order_type, &order.side) {
+      (OrderType::Market, Side::Buy) => Some(last + slippage),
+      (OrderType::Market, Side::Sell) => Some(last - slippage),
+      (OrderType::Limit, Side::Buy) if limit >= last => Some(last),
+      (OrderType::Limit, Side::Sell) if limit <= last => Some(last),
+      (OrderType::Limit, _) => None,
+    };
+    if options.order_type == OrderType::Limit {
+      order.limit = Some(limit);
+    }
+    match price {
+      Some(_) if options.post_only => {
+        let message = "order would immediately match and take".to_string();
+        return Err(ApiError::OrderRejected(message).into());
+      },
+      Some(price) => {
+        order.filled = quantity;
+        let side = order.side.clone();
+        let fill = SimFill { order_id: order.id, pair, side, qty: quantity, price, time };
+        self.fills.push(fill);
+      },
+      None if options.time_in_force == TimeInForce::Gtc => self.book.push(order.clone()),
+      None => {},
+    }
+    Ok(order)
+  }
+
+  /// Takes the order off the book, with what it filled until then.
+  pub fn cancel(&mut self, order_id: u64) -> Result<SimOrder, ExchangeError> {
+    let index = self
+      .book
+      .iter()
+      .position(|order| order.id == order_id)
+      .ok_or(ApiError::UnknownOrder(format!("order {} is not open", order_id)))?;
+    Ok(self.book.remove(index))
+  }
+
+  /// What the order fills once placed, the rest cancelled, as `execution::fill_order`
+  /// does on the exchange. The price of the order is the pair's price when none is known
+  /// yet.
+  pub fn fill_order(
+    &mut self,
+    pair: Pair,
+    qty: f64,
+    side: Side,
+    price: f64,
+    options: &OrderOptions,
+    time: DateTime<Utc>,
+  ) -> Result<ExchangeFill, ExchangeError> {
+    self.prices.entry(pair).or_insert((price, time));
+    let order = self.place(pair, side, qty, price, options)?;
+    if order.remaining() > 0.0 && options.time_in_force == TimeInForce::Gtc {
+      self.cancel(order.id)?;
+    }
+    if order.filled <= 0.0 {
+      return Err(ExchangeError::UnfilledOrder);
+    }
+    let fill = self.fills.last().ok_or(ExchangeError::UnfilledOrder)?;
+    let (qty, price) = (order.filled, fill.price);
+    Ok(ExchangeFill { qty, updated_at: fill.time, price, base_commission: 0.0 })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::exchange::mock::candle;
+  use chrono::{Duration, TimeZone};
+
+  fn limit(time_in_force: TimeInForce, post_only: bool) -> OrderOptions {
+    OrderOptions { order_type: OrderType::Limit, time_in_force, post_only }
+  }
+
+  #[test]
+  fn matches_orders_against_candles() {
+    let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+    let mut exchange = SimExchange::new(SimConfig::default());
+    let pair = Pair::BTCUSDT;
+    assert!(exchange.place(pair, Side::Buy, 1.0, 0.0, &OrderOptions::default()).is_err());
+    exchange.on_price(pair, 100.0, start);
+
+    let market = OrderOptions::default();
+    let bought =
+      exchange.fill_order(pair, 1.0, Side::Buy, 100.0, &market, start).unwrap();
+    assert_eq!((bought.qty, bought.price), (1.0, 100.02));
+    let taking = limit(TimeInForce::Gtc, true);
+    assert!(exchange.place(pair, Side::Buy, 1.0, 101.0, &taking).is_err());
+    let ioc = limit(TimeInForce::Ioc, false);
+    let expired = exchange.place(pair, Side::Buy, 1.0, 95.0, &ioc);
+    assert_eq!(expired.unwrap().filled, 0.0);
+    assert_eq!(exchange.open_orders(pair).count(), 0);
+
+    // Rests until a candle reaches it, filling a tenth of the candle volume each time
+    let resting = exchange.place(pair, Side::Buy, 0.15, 95.0, &taking).unwrap();
+    let mut dip = candle(start + Duration::try_minutes(1).unwrap(), 96.0);
+    dip.low = 94.0;
+    let fills = exchange.on_candle(pair, &dip);
+    assert_eq!(fills.len(), 1);
+    assert_eq!((fills[0].qty, fills[0].price), (0.1, 95.0));
+    let mut gap = candle(start + Duration::try_minutes(2).unwrap(), 90.0);
+    gap.volume = 10.0;
+    let fills = exchange.on_candle(pair, &gap);
+    assert!((fills[0].qty - 0.05).abs() < 1e-9);
+    assert_eq!(fills[0].price, 90.0);
+    assert_eq!(exchange.open_orders(pair).count(), 0);
+    assert!(exchange.cancel(resting.id).is_err());
+    assert_eq!(exchange.price(pair), Some(90.0));
+    assert_eq!(exchange.fills().len(), 3);
+  }
+}
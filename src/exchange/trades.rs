@@ -0,0 +1,74 @@
+use super::{binance_client::BinanceClient, error::ExchangeError, payload::parse};
+use crate::{
+  assets::Pair,
+  utils::{formatting::timestamp_to_dt, serde_utils::f64_from_string},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+/// Binance limits a `myTrades` request to trades within 24 hours.
+const MAX_REQUEST_SPAN_HOURS: i64 = 24;
+const MAX_REQUEST_TRADES: u32 = 1000;
+
+/// Trade of the account from `GET /api/v3/myTrades`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountTrade {
+  pub symbol: String,
+  pub id: u64,
+  pub order_id: i64,
+  #[serde(default)]
+  pub order_list_id: i64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub price: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub qty: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub quote_qty: f64,
+  #[serde(deserialize_with = "f64_from_string")]
+  pub commission: f64,
+  pub commission_asset: String,
+  pub time: i64,
+  pub is_buyer: bool,
+  pub is_maker: bool,
+  pub is_best_match: bool,
+}
+
+impl AccountTrade {
+  pub fn time(&self) -> DateTime<Utc> {
+    timestamp_to_dt(self.time)
+  }
+}
+
+/// Trades of the account in the pair between `from` and `to`, oldest first.
+pub fn fetch_account_trades(
+  binance_client: &BinanceClient,
+  pair: Pair,
+  from: DateTime<Utc>,
+  to: DateTime<Utc>,
+) -> Result<Vec<AccountTrade>, ExchangeError> {
+  let mut trades: Vec<AccountTrade> = Vec::new();
+  let mut start = from;
+  while start < to {
+    let span = Duration::try_hours(MAX_REQUEST_SPAN_HOURS).unwrap_or_default();
+    let end = (start + span).min(to);
+    let request = binance_spot_connector_rust::trade::my_trades(&pair.to_string())
+      .start_time(start.timestamp_millis() as u64)
+      .end_time(end.timestamp_millis() as u64)
+      .limit(MAX_REQUEST_TRADES)
+      .recv_window(binance_client.recv_window);
+    let batch: Vec<AccountTrade> = parse("my_trades", &binance_client.send(request)?)?;
+    // A full batch may leave trades of the span out, continue after its last one
+    start = match batch.last() {
+      Some(last) if batch.len() == MAX_REQUEST_TRADES as usize => {
+        last.time() + Duration::try_milliseconds(1).unwrap_or_default()
+      },
+      _ => end,
+    };
+    trades.extend(batch);
+  }
+  trades.sort_by_key(|trade| (trade.time, trade.id));
+  trades.dedup_by_key(|trade| trade.id);
+  log::info!("Account trades of {} fetched: {}", pair, trades.len());
+  Ok(trades)
+}
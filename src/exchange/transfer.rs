@@ -0,0 +1,260 @@
+use super::{
+  account::AccountName, binance_client::BinanceClient, error::ExchangeError,
+  payload::parse,
+};
+use crate::utils::{
+  formatting::{format_number, timestamp_to_dt},
+  load_config::{ConfigError, ExchangeConfig},
+  serde_utils::f64_from_string,
+};
+use binance_spot_connector_rust::http::{request::RequestBuilder, Method};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const UNIVERSAL_TRANSFER: &str = "/sapi/v1/sub-account/universalTransfer";
+
+/// Move of an asset between the spot wallets of two configured accounts.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Transfer {
+  pub from: AccountName,
+  pub to: AccountName,
+  pub asset: String,
+  pub amount: f64,
+}
+
+impl fmt::Display for Transfer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {} from {} to {}",
+      format_number(self.amount),
+      self.asset,
+      self.from,
+      self.to
+    )
+  }
+}
+
+/// Transfer between accounts as Binance lists it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TransferRecord {
+  pub id: u64,
+  pub time: DateTime<Utc>,
+  pub from: AccountName,
+  pub to: AccountName,
+  pub asset: String,
+  pub amount: f64,
+  pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTransferId {
+  tran_id: u64,
+  #[serde(default)]
+  client_tran_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTransfer {
+  tran_id: u64,
+  from_email: String,
+  to_email: String,
+  asset: String,
+  #[serde(deserialize_with = "f64_from_string")]
+  amount: f64,
+  create_time_stamp: i64,
+  from_account_type: String,
+  to_account_type: String,
+  status: String,
+  #[serde(default)]
+  client_tran_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTransferHistory {
+  result: Vec<RawTransfer>,
+  total_count: u64,
+}
+
+/// Sends the transfer with the keys of `main`, which Binance only accepts from the master
+/// account of the sub-accounts. Returns the id of the transfer.
+pub fn transfer(
+  binance_client: &BinanceClient,
+  config: &ExchangeConfig,
+  transfer: &Transfer,
+) -> Result<u64, ExchangeError> {
+  let amount = transfer.amount.to_string();
+  let recv_window = binance_client.recv_window.to_string();
+  let mut params = vec![
+    ("fromAccountType", "SPOT"),
+    ("toAccountType", "SPOT"),
+    ("asset", transfer.asset.as_str()),
+    ("amount", amount.as_str()),
+    ("recvWindow", recv_window.as_str()),
+  ];
+  if let Some(email) = email(config, &transfer.from)? {
+    params.push(("fromEmail", email));
+  }
+  if let Some(email) = email(config, &transfer.to)? {
+    params.push(("toEmail", email));
+  }
+  let request =
+    RequestBuilder::new(Method::Post, UNIVERSAL_TRANSFER).params(params).sign();
+  let response: RawTransferId =
+    parse("universal_transfer", &binance_client.send(request)?)?;
+  log::info!("Transferred {}, id {}.", transfer, response.tran_id);
+  Ok(response.tran_id)
+}
+
+/// Transfers between the configured accounts, latest first. Binance lists the ones from
+/// the master account by default and the ones from a sub-account by its email.
+pub fn fetch_transfers(
+  binance_client: &BinanceClient,
+  config: &ExchangeConfig,
+) -> Result<Vec<TransferRecord>, ExchangeError> {
+  let recv_window = binance_client.recv_window.to_string();
+  let sub_accounts = config
+    .accounts
+    .iter()
+    .filter(|account| account.name.0 != AccountName::MAIN)
+    .filter_map(|account| account.email.as_deref());
+  let mut records = Vec::new();
+  for from_email in [None].into_iter().chain(sub_accounts.map(Some)) {
+    let mut request = RequestBuilder::new(Method::Get, UNIVERSAL_TRANSFER)
+      .params([("recvWindow", recv_window.as_str())]);
+    if let Some(email) = from_email {
+      request = request.params([("fromEmail", email)]);
+    }
+    let history: RawTransferHistory =
+      parse("universal_transfer_history", &binance_client.send(request.sign())?)?;
+    records.extend(history.result.into_iter().map(|raw| TransferRecord {
+      id: raw.tran_id,
+      time: timestamp_to_dt(raw.create_time_stamp),
+      from: account_of(config, &raw.from_email),
+      to: account_of(config, &raw.to_email),
+      asset: raw.asset,
+      amount: raw.amount,
+      status: raw.status,
+    }));
+  }
+  records.sort_by(|a, b| b.time.cmp(&a.time).then(b.id.cmp(&a.id)));
+  records.dedup_by_key(|record| record.id);
+  Ok(records)
+}
+
+/// Email of the account in transfers, none for `main` as the master account.
+fn email<'a>(
+  config: &'a ExchangeConfig,
+  account: &AccountName,
+) -> Result<Option<&'a str>, ExchangeError> {
+  if account.0 == AccountName::MAIN {
+    return Ok(None);
+  }
+  let account = config.account(account)?;
+  match account.email.as_deref() {
+    Some(email) => Ok(Some(email)),
+    None => Err(ConfigError::NoEmail(account.name.clone()).into()),
+  }
+}
+
+/// Configured account of the email, the email itself for accounts that are not.
+fn account_of(config: &ExchangeConfig, email: &str) -> AccountName {
+  config
+    .accounts
+    .iter()
+    .find(|account| account.email.as_deref() == Some(email))
+    .map(|account| account.name.clone())
+    .unwrap_or_else(|| AccountName(email.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::load_config::AccountConfig;
+  use serde_json::json;
+  use std::path::PathBuf;
+  use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+  };
+
+  fn account(name: &str, email: Option<&str>) -> AccountConfig {
+    AccountConfig {
+      name: AccountName(name.to_string()),
+      binance_api_key: "key".to_string(),
+      binance_api_secret: "secret".to_string(),
+      email: email.map(str::to_string),
+    }
+  }
+
+  fn config() -> ExchangeConfig {
+    ExchangeConfig {
+      accounts: vec![
+        account(AccountName::MAIN, Some("master@example.com")),
+        account("grid", Some("grid@example.com")),
+        account("spot", None),
+      ],
+      use_testnet: true,
+      recv_window: 5000,
+      rest_url: String::new(),
+      stream_url: String::new(),
+      futures_rest_url: String::new(),
+      database: PathBuf::new(),
+      statistics: Default::default(),
+      allow_withdrawal_keys: false,
+    }
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn transfers_between_master_and_sub_accounts() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path(UNIVERSAL_TRANSFER))
+      .and(query_param("toEmail", "grid@example.com"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "tranId": 7 })))
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path(UNIVERSAL_TRANSFER))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "result": [{
+          "tranId": 7,
+          "fromEmail": "master@example.com",
+          "toEmail": "grid@example.com",
+          "asset": "USDT",
+          "amount": "100",
+          "createTimeStamp": 1_700_000_000_000i64,
+          "fromAccountType": "SPOT",
+          "toAccountType": "SPOT",
+          "status": "SUCCESS",
+          "clientTranId": ""
+        }],
+        "totalCount": 1
+      })))
+      .mount(&server)
+      .await;
+    let binance_client = BinanceClient::with_url(&server.uri());
+    let config = config();
+    let to_grid = Transfer {
+      from: AccountName::default(),
+      to: AccountName("grid".to_string()),
+      asset: "USDT".to_string(),
+      amount: 100.0,
+    };
+    assert_eq!(transfer(&binance_client, &config, &to_grid).unwrap(), 7);
+    let to_spot = Transfer { to: AccountName("spot".to_string()), ..to_grid.clone() };
+    assert!(matches!(
+      transfer(&binance_client, &config, &to_spot),
+      Err(ExchangeError::ConfigOnInit(ConfigError::NoEmail(_)))
+    ));
+
+    // Listed once from the master and once from grid, the only sub-account with an email
+    let records = fetch_transfers(&binance_client, &config).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!((&records[0].from, &records[0].to), (&to_grid.from, &to_grid.to));
+  }
+}
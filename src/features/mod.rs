@@ -0,0 +1,337 @@
+use crate::assets::Candle;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Spans of the EMA a value is computed over after its SMA seed, which leaves the seed
+/// a weight of about e^-10.
+const EMA_SPANS: usize = 5;
+
+/// A single column of the feature matrix. The value is the lookback window in candles.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum Feature {
+  /// Relative change of close price over the window.
+  Return(usize),
+  /// Relative distance of close price from its simple moving average.
+  Sma(usize),
+  /// Relative distance of close price from its exponential moving average, seeded with
+  /// the simple moving average of the window five windows back.
+  Ema(usize),
+  /// Relative strength index, scaled to 0..1.
+  Rsi(usize),
+  /// Z-score of volume against the window.
+  VolumeZScore(usize),
+  /// Latest funding rate of the perpetual future of the pair, 0 before the first one.
+  FundingRate,
+  /// Relative change of the open interest of the perpetual future over the window, 0
+  /// while it is not known.
+  OpenInterestChange(usize),
+}
+
+impl Feature {
+  pub fn name(&self) -> String {
+    match self {
+      Feature::Return(n) => format!("return_{n}"),
+      Feature::Sma(n) => format!("sma_{n}"),
+      Feature::Ema(n) => format!("ema_{n}"),
+      Feature::Rsi(n) => format!("rsi_{n}"),
+      Feature::VolumeZScore(n) => format!("volume_z_{n}"),
+      Feature::FundingRate => "funding_rate".to_string(),
+      Feature::OpenInterestChange(n) => format!("open_interest_{n}"),
+    }
+  }
+
+  /// Number of preceding candles needed before the feature has a value.
+  pub fn lookback(&self) -> usize {
+    match self {
+      Feature::Return(n) | Feature::Rsi(n) | Feature::OpenInterestChange(n) => *n,
+      Feature::Sma(n) | Feature::VolumeZScore(n) => n.saturating_sub(1),
+      Feature::Ema(n) => ((EMA_SPANS + 1) * n).saturating_sub(1),
+      Feature::FundingRate => 0,
+    }
+  }
+
+  /// Whether the feature is computed from [`Derivatives`] rather than the candles.
+  pub fn is_derivative(&self) -> bool {
+    matches!(self, Feature::FundingRate | Feature::OpenInterestChange(_))
+  }
+
+  fn compute(&self, candles: &[Candle], derivatives: &Derivatives) -> Vec<f64> {
+    let closes: Vec<f64> = candles.iter().map(|candle| candle.close).collect();
+    match *self {
+      Feature::Return(n) => (0..closes.len())
+        .map(|i| if i < n { f64::NAN } else { closes[i] / closes[i - n] - 1.0 })
+        .collect(),
+      Feature::Sma(n) => (0..closes.len())
+        .map(|i| {
+          if n == 0 || i + 1 < n {
+            f64::NAN
+          } else {
+            closes[i] / mean(&closes[i + 1 - n..=i]) - 1.0
+          }
+        })
+        .collect(),
+      Feature::Ema(n) => {
+        // Computed over the lookback alone, so the history before it never matters
+        let alpha = 2.0 / (n as f64 + 1.0);
+        let lookback = self.lookback();
+        (0..closes.len())
+          .map(|i| {
+            if n == 0 || i < lookback {
+              return f64::NAN;
+            }
+            let window = &closes[i - lookback..=i];
+            let ema = window[n..]
+              .iter()
+              .fold(mean(&window[..n]), |ema, close| alpha * close + (1.0 - alpha) * ema);
+            closes[i] / ema - 1.0
+          })
+          .collect()
+      },
+      Feature::Rsi(n) => (0..closes.len())
+        .map(|i| {
+          if n == 0 || i < n {
+            return f64::NAN;
+          }
+          let (gains, losses) =
+            closes[i - n..=i].windows(2).fold((0.0, 0.0), |(gains, losses), w| {
+              let change = w[1] - w[0];
+              if change > 0.0 {
+                (gains + change, losses)
+              } else {
+                (gains, losses - change)
+              }
+            });
+          if gains + losses == 0.0 {
+            0.5
+          } else {
+            gains / (gains + losses)
+          }
+        })
+        .collect(),
+      Feature::VolumeZScore(n) => {
+        let volumes: Vec<f64> = candles.iter().map(|candle| candle.volume).collect();
+        (0..volumes.len())
+          .map(|i| {
+            if n == 0 || i + 1 < n {
+              return f64::NAN;
+            }
+            let window = &volumes[i + 1 - n..=i];
+            let mean = mean(window);
+            let std = (window.iter().map(|v| (v - mean).powi(2)).sum::<f64>()
+              / window.len() as f64)
+              .sqrt();
+            if std == 0.0 {
+              0.0
+            } else {
+              (volumes[i] - mean) / std
+            }
+          })
+          .collect()
+      },
+      Feature::FundingRate => as_of(&derivatives.funding_rates, candles)
+        .into_iter()
+        .map(|rate| rate.unwrap_or_default())
+        .collect(),
+      Feature::OpenInterestChange(n) => {
+        let open_interest = as_of(&derivatives.open_interest, candles);
+        (0..open_interest.len())
+          .map(|i| match i.checked_sub(n).map(|j| (open_interest[j], open_interest[i])) {
+            None => f64::NAN,
+            Some((Some(before), Some(now))) if before > 0.0 => now / before - 1.0,
+            Some(_) => 0.0,
+          })
+          .collect()
+      },
+    }
+  }
+}
+
+/// Latest value of the series at the open of each candle, none before its first one.
+fn as_of(series: &[(DateTime<Utc>, f64)], candles: &[Candle]) -> Vec<Option<f64>> {
+  let mut next = 0;
+  let mut value = None;
+  candles
+    .iter()
+    .map(|candle| {
+      while next < series.len() && series[next].0 <= candle.open_time {
+        value = Some(series[next].1);
+        next += 1;
+      }
+      value
+    })
+    .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+  values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Funding rates and open interest of the perpetual future of a pair by time, oldest
+/// first, as stored by [`crate::exchange::derivatives`].
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Derivatives {
+  pub funding_rates: Vec<(DateTime<Utc>, f64)>,
+  pub open_interest: Vec<(DateTime<Utc>, f64)>,
+}
+
+/// Ordered list of features a model is trained on. It is stored with the model metadata
+/// so prediction computes exactly the same columns as training did.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct FeaturePipeline {
+  pub features: Vec<Feature>,
+}
+
+impl Default for FeaturePipeline {
+  fn default() -> Self {
+    Self {
+      features: vec![
+        Feature::Return(1),
+        Feature::Return(5),
+        Feature::Return(15),
+        Feature::Sma(20),
+        Feature::Ema(50),
+        Feature::Rsi(14),
+        Feature::VolumeZScore(20),
+      ],
+    }
+  }
+}
+
+impl FeaturePipeline {
+  pub fn columns(&self) -> Vec<String> {
+    self.features.iter().map(Feature::name).collect()
+  }
+
+  /// Number of leading candles that are consumed before the first complete row.
+  pub fn warmup(&self) -> usize {
+    self.features.iter().map(Feature::lookback).max().unwrap_or_default()
+  }
+
+  /// Whether any of the features needs the [`Derivatives`] of the pair.
+  pub fn uses_derivatives(&self) -> bool {
+    self.features.iter().any(Feature::is_derivative)
+  }
+
+  /// Computes the feature matrix for candles sorted by open time. Candles within the
+  /// warm-up period are dropped, so every row is complete.
+  pub fn compute(&self, candles: &[Candle], derivatives: &Derivatives) -> FeatureMatrix {
+    let columns: Vec<Vec<f64>> = self
+      .features
+      .iter()
+      .map(|feature| feature.compute(candles, derivatives))
+      .collect();
+    let warmup = self.warmup();
+    let rows = (warmup..candles.len())
+      .map(|i| columns.iter().map(|column| column[i]).collect())
+      .collect();
+    FeatureMatrix {
+      columns: self.columns(),
+      open_times: candles.iter().skip(warmup).map(|candle| candle.open_time).collect(),
+      closes: candles.iter().skip(warmup).map(|candle| candle.close).collect(),
+      rows,
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct FeatureMatrix {
+  pub columns: Vec<String>,
+  pub open_times: Vec<DateTime<Utc>>,
+  pub closes: Vec<f64>,
+  pub rows: Vec<Vec<f64>>,
+}
+
+impl FeatureMatrix {
+  pub fn is_empty(&self) -> bool {
+    self.rows.is_empty()
+  }
+
+  /// Open times formatted the same way candles are stored, for passing to Python.
+  pub fn open_times_rfc3339(&self) -> Vec<String> {
+    self.open_times.iter().map(|time| time.to_rfc3339()).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn candle(minute: i64) -> Candle {
+    let minutes = chrono::Duration::try_minutes(minute).unwrap();
+    let open_time = DateTime::<Utc>::default() + minutes;
+    Candle {
+      open_time,
+      close_time: open_time,
+      open: 100.0,
+      high: 100.0,
+      low: 100.0,
+      close: 100.0,
+      volume: 1.0,
+      trade_count: 1,
+    }
+  }
+
+  fn value(feature: Feature, candles: &[Candle]) -> f64 {
+    let values = feature.compute(candles, &Derivatives::default());
+    values.last().copied().unwrap()
+  }
+
+  #[test]
+  fn pins_the_values_on_a_trend() {
+    // Closes up 1 per candle, from 100 to 199
+    let candles: Vec<Candle> =
+      (0..100).map(|i| Candle { close: 100.0 + i as f64, ..candle(i) }).collect();
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+    assert!(close(value(Feature::Return(5), &candles), 199.0 / 194.0 - 1.0));
+    // The averages lag the close by (n - 1) / 2 candles
+    assert!(close(value(Feature::Sma(20), &candles), 199.0 / 189.5 - 1.0));
+    assert!(close(value(Feature::Ema(10), &candles), 199.0 / 194.5 - 1.0));
+    assert_eq!(value(Feature::Rsi(14), &candles), 1.0);
+    assert_eq!(value(Feature::VolumeZScore(20), &candles), 0.0);
+  }
+
+  #[test]
+  fn values_do_not_depend_on_the_history_before_the_lookback() {
+    let candles: Vec<Candle> = (0..400)
+      .map(|i| Candle {
+        close: 100.0 + 10.0 * (i as f64 / 7.0).sin() + i as f64 * 0.1,
+        volume: 1.0 + (i % 5) as f64,
+        ..candle(i)
+      })
+      .collect();
+    let features = [
+      Feature::Return(5),
+      Feature::Sma(20),
+      Feature::Ema(10),
+      Feature::Ema(50),
+      Feature::Rsi(14),
+      Feature::VolumeZScore(20),
+    ];
+    for feature in features {
+      let all = value(feature, &candles);
+      let just_enough = candles.len() - 1 - feature.lookback();
+      for start in [1, 50, just_enough] {
+        assert_eq!(value(feature, &candles[start..]), all, "{}", feature.name());
+      }
+      assert!(value(feature, &candles[just_enough + 1..]).is_nan());
+    }
+  }
+
+  #[test]
+  fn aligns_derivatives_to_the_open_of_candles() {
+    let candles: Vec<Candle> = (0..4).map(candle).collect();
+    let time = |minute: usize| candles[minute].open_time;
+    let derivatives = Derivatives {
+      funding_rates: vec![(time(1), 0.0001)],
+      open_interest: vec![(time(0), 100.0), (time(2), 110.0)],
+    };
+    let pipeline = FeaturePipeline {
+      features: vec![Feature::FundingRate, Feature::OpenInterestChange(2)],
+    };
+    assert!(pipeline.uses_derivatives());
+    let matrix = pipeline.compute(&candles, &derivatives);
+    assert_eq!(matrix.columns, ["funding_rate", "open_interest_2"]);
+    let change = 110.0 / 100.0 - 1.0;
+    assert_eq!(matrix.rows, vec![vec![0.0001, change], vec![0.0001, change]]);
+  }
+}
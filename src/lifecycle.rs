@@ -0,0 +1,88 @@
+use crate::{assets::Pair, screens::ScreenId};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Where the session of the app is in its life. A session starts from Idle or once the
+/// previous one ended, and only one runs at a time.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub enum RunState {
+  #[default]
+  Idle,
+  /// The core fetches the history before it starts the traders.
+  FetchingHistory { core_id: Uuid, pair: Pair },
+  Running { core_id: Uuid, pair: Pair },
+  /// The core was told to terminate and closes the session.
+  Terminating { core_id: Uuid, pair: Pair },
+  Finished { core_id: Uuid },
+  Failed { error: String },
+}
+
+/// What happened to the session, moves the [RunState] on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunEvent {
+  Started { core_id: Uuid, pair: Pair },
+  TradersStarted(Uuid),
+  Terminate,
+  Finished(Uuid),
+  /// None when the session could not be started.
+  Failed { core_id: Option<Uuid>, error: String },
+}
+
+impl RunState {
+  /// The state after the event, none when the event does not apply to the state, like
+  /// messages of an earlier session.
+  pub fn next(&self, event: RunEvent) -> Option<RunState> {
+    let is_session = |id: Uuid| self.session().is_some_and(|(core_id, _)| core_id == id);
+    match (self, event) {
+      (_, RunEvent::Started { core_id, pair }) if self.can_start() => {
+        Some(RunState::FetchingHistory { core_id, pair })
+      },
+      (RunState::FetchingHistory { core_id, pair }, RunEvent::TradersStarted(id))
+        if *core_id == id =>
+      {
+        Some(RunState::Running { core_id: *core_id, pair: *pair })
+      },
+      (
+        RunState::FetchingHistory { core_id, pair } | RunState::Running { core_id, pair },
+        RunEvent::Terminate,
+      ) => Some(RunState::Terminating { core_id: *core_id, pair: *pair }),
+      (_, RunEvent::Finished(core_id)) if is_session(core_id) => {
+        Some(RunState::Finished { core_id })
+      },
+      (_, RunEvent::Failed { core_id: None, error }) if self.can_start() => {
+        Some(RunState::Failed { error })
+      },
+      (_, RunEvent::Failed { core_id: Some(core_id), error }) if is_session(core_id) => {
+        Some(RunState::Failed { error })
+      },
+      _ => None,
+    }
+  }
+
+  /// Session of the states with a running core.
+  pub fn session(&self) -> Option<(Uuid, Pair)> {
+    match self {
+      RunState::FetchingHistory { core_id, pair }
+      | RunState::Running { core_id, pair }
+      | RunState::Terminating { core_id, pair } => Some((*core_id, *pair)),
+      _ => None,
+    }
+  }
+
+  pub fn can_start(&self) -> bool {
+    matches!(self, RunState::Idle | RunState::Finished { .. } | RunState::Failed { .. })
+  }
+
+  /// Commands other than starting are only sent to a core that is not terminating.
+  pub fn accepts_commands(&self) -> bool {
+    matches!(self, RunState::FetchingHistory { .. } | RunState::Running { .. })
+  }
+
+  /// Screen the state is shown on, none for states without one of their own.
+  pub fn screen(&self) -> Option<ScreenId> {
+    match self {
+      RunState::Finished { core_id } => Some(ScreenId::REPORT(*core_id)),
+      _ => self.session().map(ScreenId::RUNNING),
+    }
+  }
+}
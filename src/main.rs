@@ -6,13 +6,21 @@ pub mod action;
 pub mod app;
 pub mod assets;
 pub mod cli;
+pub mod clock;
+pub mod commands;
 pub mod components;
 pub mod config;
+#[cfg(unix)]
+pub mod control;
 pub mod core;
 pub mod database;
+pub mod diagnostics;
 pub mod events;
 pub mod exchange;
+pub mod features;
+pub mod lifecycle;
 pub mod mode;
+pub mod paths;
 pub mod portfolio;
 pub mod screens;
 pub mod statistic;
@@ -20,19 +28,41 @@ pub mod strategy;
 pub mod trading;
 pub mod tui;
 pub mod utils;
+pub mod watchlist;
 
 use crate::{
   app::App,
-  utils::{initialize_logging, initialize_panic_handler, version},
+  config::Config,
+  utils::{initialize_logging, initialize_panic_handler, load_config, version},
 };
 use clap::Parser;
 use cli::Cli;
 use eyre::Result;
 
 async fn tokio_main() -> Result<()> {
-  initialize_logging()?;
-  initialize_panic_handler()?;
   let args = Cli::parse();
+  if let Some(data_dir) = args.data_dir {
+    paths::set_data_dir(data_dir);
+  }
+  std::env::set_var(paths::MODELS_DIR_ENV, paths::models_dir());
+  if let Some(profile) = args.profile {
+    load_config::set_profile(profile);
+  }
+  if let Some(db) = args.db {
+    database::sqlite::set_path(db);
+  }
+  // Loaded again by the app, once logging is up to report problems with it
+  let log_config = Config::new().map(|config| config.logging).unwrap_or_default();
+  initialize_logging(log_config)?;
+  initialize_panic_handler()?;
+  if let Some(command) = args.command {
+    return commands::run(command).await;
+  }
+  if let Some(path) = args.replay {
+    let report = trading::replay::replay(&path).await?;
+    println!("{}", report);
+    return Ok(());
+  }
   let mut app = App::new(args.tick_rate, args.frame_rate).await?;
   app.run().await?;
   Ok(())
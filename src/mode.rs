@@ -1,7 +1,27 @@
 use serde::{Deserialize, Serialize};
+use strum::EnumString;
 
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Keymap of the open screen, or of every screen for `Global`.
+#[derive(
+  Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumString,
+)]
 pub enum Mode {
+  /// Bindings of every screen, unless the keymap of the screen binds the key too.
+  Global,
   #[default]
   Home,
+  Models,
+  ModelConfig,
+  Report,
+  Sessions,
+  RunConfig,
+  Running,
+  Exchange,
+  Trades,
+  Analytics,
+  Markets,
+  Diagnostics,
+  Settings,
+  Compare,
+  Correlations,
 }
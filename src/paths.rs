@@ -0,0 +1,77 @@
+use crate::utils::{CONFIG_FOLDER, DATA_FOLDER};
+use directories::ProjectDirs;
+use std::{
+  path::{Path, PathBuf},
+  sync::OnceLock,
+};
+
+/// Read by the model scripts, which save and load models under it.
+pub const MODELS_DIR_ENV: &str = "MESHETAR_MODELS_DIR";
+
+/// Set by `--data-dir`, takes precedence over the environment and the XDG directories.
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keeps all files under this directory, the config under its `.config`. Only the first
+/// call has an effect.
+pub fn set_data_dir(path: PathBuf) {
+  let _ = DATA_DIR_OVERRIDE.set(path);
+}
+
+fn project_directory() -> Option<ProjectDirs> {
+  ProjectDirs::from("com", "belakm", env!("CARGO_PKG_NAME"))
+}
+
+/// `--data-dir`, else `MESHETAR_TUI_DATA`, else the XDG data directory, else `.data`.
+pub fn data_dir() -> PathBuf {
+  if let Some(path) = DATA_DIR_OVERRIDE.get() {
+    return path.clone();
+  }
+  match (DATA_FOLDER.clone(), project_directory()) {
+    (Some(path), _) => path,
+    (None, Some(dirs)) => dirs.data_local_dir().to_path_buf(),
+    (None, None) => PathBuf::from(".").join(".data"),
+  }
+}
+
+/// `MESHETAR_TUI_CONFIG`, else `.config` of `--data-dir`, else the XDG config directory,
+/// else `.config`.
+pub fn config_dir() -> PathBuf {
+  match (CONFIG_FOLDER.clone(), DATA_DIR_OVERRIDE.get(), project_directory()) {
+    (Some(path), ..) => path,
+    (None, Some(data_dir), _) => data_dir.join(".config"),
+    (None, None, Some(dirs)) => dirs.config_local_dir().to_path_buf(),
+    (None, None, None) => PathBuf::from(".").join(".config"),
+  }
+}
+
+/// Paths of the config files are relative to the data directory.
+pub fn resolve(path: &Path) -> PathBuf {
+  if path.is_absolute() {
+    path.to_path_buf()
+  } else {
+    data_dir().join(path)
+  }
+}
+
+/// Exchange config of the profile, `env.<profile>.toml` or `env.toml`.
+pub fn exchange_config_file(profile: Option<&str>) -> PathBuf {
+  match profile {
+    Some(profile) => config_dir().join(format!("env.{}.toml", profile)),
+    None => config_dir().join("env.toml"),
+  }
+}
+
+/// Trained models, one directory per model.
+pub fn models_dir() -> PathBuf {
+  data_dir().join("models").join("generated")
+}
+
+/// Log file of the app and its rotated copies.
+pub fn logs_dir() -> PathBuf {
+  data_dir().join("logs")
+}
+
+/// Output of the sessions, one directory per session.
+pub fn sessions_dir() -> PathBuf {
+  data_dir().join("sessions")
+}
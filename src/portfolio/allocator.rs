@@ -1,9 +1,11 @@
 use crate::strategy::{Decision, SignalStrength};
 
-use super::{position::Position, OrderEvent};
+use super::{position::Position, regime::RegimeLimits, OrderEvent};
 
 pub struct Allocator {
     pub default_order_value: f64,
+    /// Share of the available balance an entry at full signal strength commits.
+    pub risk_per_trade: f64,
 }
 
 impl Allocator {
@@ -13,21 +15,30 @@ impl Allocator {
         position: Option<&Position>,
         signal_strength: SignalStrength,
         max_value: f64,
+        limits: RegimeLimits,
     ) {
         // Calculate exact order_size, then round it to a more appropriate decimal place
         // let default_order_size = self.default_order_value / order.market_meta.close;
-        let default_order_size = max_value / order.market_meta.close;
-        let default_order_size = (default_order_size * 10000000.0).floor() / 10000000.0;
+        // Scale the order by the confidence of the signal
+        let strength = signal_strength.0.clamp(0.0, 1.0);
+        // Sized for the volatility regime of the pair, within its cap
+        let order_value = (strength * self.risk_per_trade * limits.size * max_value)
+            .min(limits.max_entry_share.max(0.0) * max_value);
+        let order_size = order_value / order.market_meta.close;
+        let order_size = (order_size * 10000000.0).floor() / 10000000.0;
 
         match order.decision {
             // Entry
-            Decision::Long => order.quantity = default_order_size * signal_strength.0,
+            Decision::Long => order.quantity = order_size,
 
             // Entry
-            Decision::Short => order.quantity = -default_order_size * signal_strength.0,
+            Decision::Short => order.quantity = -order_size,
 
-            // Exit
-            _ => order.quantity = 0.0 - position.as_ref().unwrap().quantity,
+            // Exit, of what earlier exits left open
+            _ => {
+                let position = position.unwrap();
+                order.quantity = -position.quantity.signum() * position.open_quantity();
+            }
         }
     }
 }
@@ -1,7 +1,13 @@
-use crate::utils::serde_utils::f64_default;
+use super::{error::PortfolioError, position::Position};
+use crate::{
+  assets::{Asset, Pair},
+  utils::serde_utils::f64_default,
+};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::{BTreeMap, HashMap};
+use strum::{Display, EnumIter, EnumString};
 use uuid::Uuid;
 
 pub type BalanceId = String;
@@ -23,4 +29,192 @@ impl Balance {
   pub fn balance_id(core_id: Uuid) -> BalanceId {
     format!("{}_balance", core_id)
   }
+
+  /// Entering reserves the position's margin and its fees.
+  pub fn apply_entry(&mut self, position: &Position) {
+    self.reserve(position.margin(), position.enter_fees_total);
+  }
+
+  /// Reserves the margin and fees of an entry fill.
+  pub fn reserve(&mut self, margin: f64, fees: f64) {
+    self.available += -margin - fees;
+  }
+
+  /// Exiting releases the reserved margin and fees together with the realised PnL,
+  /// which already has the fees of both sides deducted.
+  pub fn apply_exit(&mut self, position: &Position) {
+    self.available +=
+      position.margin() + position.realised_profit_loss + position.enter_fees_total;
+    self.total += position.realised_profit_loss;
+  }
+}
+
+/// Where a live session takes its starting equity from.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EquitySource {
+  /// The starting equity of the run configuration.
+  #[default]
+  Entered,
+  /// The free balance of the pair's quote asset on the exchange, followed through the
+  /// account updates of the session.
+  Exchange,
+}
+
+/// Balances of a session per asset, in the asset's own units. Positions reserve the
+/// quote asset of their pair, which goes negative for an asset the session was not funded
+/// with: that part of the reporting currency is treated as converted into it.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct AssetBalances {
+  #[serde(default)]
+  pub reporting_currency: Asset,
+  pub balances: BTreeMap<Asset, Balance>,
+}
+
+impl AssetBalances {
+  /// Starts with the whole balance in the reporting currency.
+  pub fn funded(reporting_currency: Asset, balance: Balance) -> Self {
+    Self { reporting_currency, balances: BTreeMap::from([(reporting_currency, balance)]) }
+  }
+
+  pub fn get(&self, asset: Asset, time: DateTime<Utc>) -> Balance {
+    self.balances.get(&asset).copied().unwrap_or(Balance { time, ..Balance::default() })
+  }
+
+  pub fn set(&mut self, asset: Asset, balance: Balance) {
+    self.balances.insert(asset, balance);
+  }
+
+  /// Sets the available balance of the asset to its free balance on the exchange and
+  /// moves the total by the same amount. Returns the difference.
+  pub fn reconcile(&mut self, asset: Asset, free: f64, time: DateTime<Utc>) -> f64 {
+    let mut balance = self.get(asset, time);
+    let difference = free - balance.available;
+    balance.available = free;
+    balance.total += difference;
+    balance.time = time;
+    self.set(asset, balance);
+    difference
+  }
+
+  /// Total and available of every asset in the reporting currency, none of them
+  /// without a price to convert with.
+  pub fn valuation(
+    &self,
+    rates: &ExchangeRates,
+    time: DateTime<Utc>,
+  ) -> Result<Balance, PortfolioError> {
+    let mut valuation = Balance { time, ..Balance::default() };
+    for (asset, balance) in &self.balances {
+      let rate = rates.rate(*asset, self.reporting_currency).ok_or(
+        PortfolioError::ExchangeRateMissing(*asset, self.reporting_currency),
+      )?;
+      valuation.total += balance.total * rate;
+      valuation.available += balance.available * rate;
+    }
+    Ok(valuation)
+  }
+}
+
+/// Latest prices of the pairs a session has seen, to convert between their assets.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct ExchangeRates {
+  prices: HashMap<Pair, f64>,
+}
+
+impl ExchangeRates {
+  pub fn update(&mut self, pair: Pair, price: f64) {
+    if price > 0.0 {
+      self.prices.insert(pair, price);
+    }
+  }
+
+  /// Units of `to` one unit of `from` is worth, through a pair of the two or through
+  /// one asset in between, e.g. ETH to USDT over ETHBTC and BTCUSDT.
+  pub fn rate(&self, from: Asset, to: Asset) -> Option<f64> {
+    if from == to {
+      return Some(1.0);
+    }
+    self.direct_rate(from, to).or_else(|| {
+      self
+        .prices
+        .keys()
+        .flat_map(|pair| [pair.base(), pair.quote()])
+        .find_map(|via| Some(self.direct_rate(from, via)? * self.direct_rate(via, to)?))
+    })
+  }
+
+  fn direct_rate(&self, from: Asset, to: Asset) -> Option<f64> {
+    self.prices.iter().find_map(|(pair, price)| {
+      if (pair.base(), pair.quote()) == (from, to) {
+        Some(*price)
+      } else if (pair.base(), pair.quote()) == (to, from) {
+        Some(1.0 / price)
+      } else {
+        None
+      }
+    })
+  }
+
+  pub fn convert(&self, amount: f64, from: Asset, to: Asset) -> Option<f64> {
+    self.rate(from, to).map(|rate| amount * rate)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rates(prices: &[(Pair, f64)]) -> ExchangeRates {
+    let mut rates = ExchangeRates::default();
+    for (pair, price) in prices {
+      rates.update(*pair, *price);
+    }
+    rates
+  }
+
+  #[test]
+  fn converts_directly_inversely_and_through_another_asset() {
+    let rates = rates(&[(Pair::BTCUSDT, 50_000.0), (Pair::ETHBTC, 0.05)]);
+    assert_eq!(rates.rate(Asset::USDT, Asset::USDT), Some(1.0));
+    assert_eq!(rates.rate(Asset::BTC, Asset::USDT), Some(50_000.0));
+    assert_eq!(rates.rate(Asset::USDT, Asset::BTC), Some(1.0 / 50_000.0));
+    assert_eq!(rates.rate(Asset::ETH, Asset::USDT), Some(2_500.0));
+    assert_eq!(rates.rate(Asset::USDT, Asset::ETH), Some(1.0 / 2_500.0));
+    assert_eq!(rates.convert(2.0, Asset::ETH, Asset::BTC), Some(0.1));
+
+    let eth_only = self::rates(&[(Pair::ETHBTC, 0.05)]);
+    assert_eq!(eth_only.rate(Asset::BTC, Asset::USDT), None);
+    assert_eq!(eth_only.rate(Asset::ETH, Asset::USDT), None);
+  }
+
+  #[test]
+  fn values_every_asset_in_the_reporting_currency() {
+    let time = DateTime::UNIX_EPOCH;
+    let funded = Balance { time, total: 10_000.0, available: 10_000.0 };
+    let mut balances = AssetBalances::funded(Asset::USDT, funded);
+    balances.set(Asset::BTC, Balance { time, total: 0.0, available: -0.05 });
+
+    let missing = balances.valuation(&rates(&[(Pair::ETHBTC, 0.05)]), time);
+    assert!(matches!(
+      missing,
+      Err(PortfolioError::ExchangeRateMissing(Asset::BTC, Asset::USDT))
+    ));
+    let valuation = balances.valuation(&rates(&[(Pair::BTCUSDT, 50_000.0)]), time);
+    let valuation = valuation.unwrap();
+    assert_eq!((valuation.total, valuation.available), (10_000.0, 7_500.0));
+  }
 }
@@ -1,6 +1,7 @@
 use thiserror::Error;
 
-use crate::database::error::DatabaseError;
+use crate::{assets::Asset, database::error::DatabaseError};
+use uuid::Uuid;
 
 #[derive(Error, Debug)]
 pub enum PortfolioError {
@@ -14,8 +15,16 @@ pub enum PortfolioError {
   CannotExitPositionWithEntryFill,
   #[error("Cannot exit Position with an entry decision FillEvent.")]
   CannotEnterPositionWithExitFill,
+  #[error("Cannot add an entry FillEvent of the other side to a Position.")]
+  EntrySideMismatch,
+  #[error("Cannot exit a Position with the FillEvent of the other side.")]
+  ExitSideMismatch,
   #[error(
     "Failed to parse Position entry Side due to ambiguous fill quantity & Decision."
   )]
   ParseEntrySide,
+  #[error("Balance of core {0} is not initialized yet")]
+  BalanceMissing(Uuid),
+  #[error("No exchange rate of {0} to {1} yet")]
+  ExchangeRateMissing(Asset, Asset),
 }
@@ -0,0 +1,41 @@
+use crate::{
+  assets::{MarketMeta, Pair},
+  strategy::Decision,
+  trading::execution::{Fees, FillEvent},
+};
+use chrono::{DateTime, Utc};
+use proptest::prelude::*;
+
+/// Equal up to the rounding of the arithmetic.
+pub fn close(a: f64, b: f64) -> bool {
+  (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+pub fn fees() -> impl Strategy<Value = Fees> {
+  (0.0..0.01f64, 0.0..1.0f64).prop_map(|(exchange, slippage)| Fees { exchange, slippage })
+}
+
+/// Fill of BTCUSDT.
+pub fn fill(decision: Decision, quantity: f64, price: f64, fees: Fees) -> FillEvent {
+  fill_of(Pair::BTCUSDT, decision, quantity, price, fees, DateTime::UNIX_EPOCH)
+}
+
+pub fn fill_of(
+  pair: Pair,
+  decision: Decision,
+  quantity: f64,
+  price: f64,
+  fees: Fees,
+  time: DateTime<Utc>,
+) -> FillEvent {
+  FillEvent::builder()
+    .time(time)
+    .asset(pair)
+    .market_meta(MarketMeta { close: price, time })
+    .decision(decision)
+    .quantity(quantity)
+    .fill_value_gross(quantity.abs() * price)
+    .fees(fees)
+    .build()
+    .unwrap()
+}
@@ -1,7 +1,10 @@
 pub mod allocator;
 pub mod balance;
 pub mod error;
+#[cfg(test)]
+pub(crate) mod fixtures;
 pub mod position;
+pub mod regime;
 pub mod risk;
 
 use self::{
@@ -9,21 +12,24 @@ use self::{
   balance::Balance,
   error::PortfolioError,
   position::{determine_position_id, Position, PositionUpdate},
-  risk::RiskEvaluator,
+  regime::RegimeLimits,
+  risk::{CorrelationLimit, RiskEvaluator},
 };
 use crate::{
   assets::{MarketEvent, MarketMeta, Pair, Side},
+  clock::{real_clock, SharedClock},
   database::{error::DatabaseError, Database},
+  diagnostics::{self, Task},
   events::Event,
-  statistic::{StatisticConfig, TradingSummary},
+  statistic::{correlation::CorrelationMatrix, StatisticConfig, TradingSummary},
   strategy::{Decision, Signal, SignalStrength},
-  trading::{execution::FillEvent, SignalForceExit},
+  trading::{execution::FillEvent, params::OrderOptions, SignalForceExit},
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
-use tokio::sync::Mutex;
-use tracing::info;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{info, instrument};
 use uuid::Uuid;
 
 #[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
@@ -33,6 +39,8 @@ pub struct OrderEvent {
   pub decision: Decision,
   pub market_meta: MarketMeta,
   pub quantity: f64,
+  #[serde(default)]
+  pub options: OrderOptions,
 }
 
 pub struct Portfolio {
@@ -40,6 +48,16 @@ pub struct Portfolio {
   allocation_manager: Allocator,
   risk_manager: RiskEvaluator,
   statistic_config: StatisticConfig,
+  clock: SharedClock,
+  order_options: OrderOptions,
+  /// Limits of the volatility regime of each pair of a session, none leaves entries as
+  /// allocated.
+  regime_limits: HashMap<(Uuid, Pair), RegimeLimits>,
+  correlation_limit: CorrelationLimit,
+  /// Correlations of the pairs, empty until they are first computed.
+  correlations: CorrelationMatrix,
+  /// Leverage of the positions of each futures session, spot sessions have none.
+  leverage: HashMap<Uuid, f64>,
 }
 
 impl Portfolio {
@@ -47,24 +65,100 @@ impl Portfolio {
     PortfolioBuilder::new()
   }
 
+  /// Clock of the session the portfolio is trading for, orders are stamped with it.
+  pub fn set_clock(&mut self, clock: SharedClock) {
+    self.clock = clock;
+  }
+
+  /// Options of the orders of the session the portfolio is trading for.
+  pub fn set_order_options(&mut self, order_options: OrderOptions) {
+    self.order_options = order_options;
+  }
+
+  /// Share of the available balance entries at full signal strength commit.
+  pub fn set_risk_per_trade(&mut self, risk_per_trade: f64) {
+    self.allocation_manager.risk_per_trade = risk_per_trade;
+  }
+
+  /// Limits of entries of the pair in its current volatility regime.
+  pub fn set_regime_limits(&mut self, core_id: Uuid, pair: Pair, limits: RegimeLimits) {
+    self.regime_limits.insert((core_id, pair), limits);
+  }
+
+  /// Entries are checked against the open positions of correlated pairs with these.
+  pub fn set_correlations(
+    &mut self,
+    correlation_limit: CorrelationLimit,
+    correlations: CorrelationMatrix,
+  ) {
+    self.correlation_limit = correlation_limit;
+    self.correlations = correlations;
+  }
+
+  /// Positions of the session are entered with the leverage, 1 trades spot.
+  pub fn set_leverage(&mut self, core_id: Uuid, leverage: f64) {
+    self.leverage.insert(core_id, leverage);
+  }
+
+  fn leverage(&self, core_id: Uuid) -> f64 {
+    self.leverage.get(&core_id).copied().unwrap_or(1.0)
+  }
+
+  /// Replaces the estimated liquidation price of the open position of the pair with the
+  /// one the exchange reported.
+  pub async fn set_liquidation_price(
+    &mut self,
+    core_id: Uuid,
+    pair: &Pair,
+    price: f64,
+  ) -> Result<(), PortfolioError> {
+    let position_id = determine_position_id(&core_id, pair);
+    let mut database = self.database().await;
+    if let Some(mut position) = database.get_open_position(&position_id)? {
+      position.liquidation_price = Some(price);
+      database.set_open_position(position)?;
+    }
+    Ok(())
+  }
+
+  /// Whether the open position of the pair reached its liquidation price.
+  pub async fn liquidated(
+    &self,
+    core_id: Uuid,
+    pair: &Pair,
+  ) -> Result<bool, PortfolioError> {
+    let position_id = determine_position_id(&core_id, pair);
+    let position = self.database().await.get_open_position(&position_id)?;
+    Ok(position.is_some_and(|position| position.is_liquidated()))
+  }
+
+  async fn database(&self) -> MutexGuard<'_, Database> {
+    diagnostics::lock(Task::Database, &self.database).await
+  }
+
   pub async fn open_positions(
     &self,
     core_id: Uuid,
   ) -> Result<Vec<Position>, PortfolioError> {
-    let mut database = self.database.lock().await;
+    let mut database = self.database().await;
     let positions = database.get_all_open_positions(core_id)?;
     Ok(positions)
   }
 
+  #[instrument(
+    name = "portfolio",
+    skip_all,
+    fields(core_id = %core_id, pair = %signal.pair)
+  )]
   pub async fn generate_order(
     &mut self,
     core_id: Uuid,
     signal: &Signal,
-    time_is_live: bool,
   ) -> Result<Option<OrderEvent>, PortfolioError> {
     let position_id = determine_position_id(&core_id, &signal.pair);
-    let position = { self.database.lock().await.get_open_position(&position_id)? };
-    if position.is_none() && self.no_cash_to_enter_new_position(core_id).await? {
+    let position = { self.database().await.get_open_position(&position_id)? };
+    let available = self.balance(core_id).await?.available;
+    if position.is_none() && available == 0.0 {
       info!("No cash available to open a new position.");
       return Ok(None);
     }
@@ -74,49 +168,71 @@ impl Portfolio {
         None => return Ok(None),
         Some(net_signal) => net_signal,
       };
-    let order_time = if time_is_live { Utc::now() } else { signal.time };
     let mut order = OrderEvent {
-      time: order_time,
+      time: self.clock.now(),
       pair: signal.pair.clone(),
       market_meta: signal.market_meta,
       decision: *signal_decision,
       quantity: 1.0,
+      options: self.order_options,
+    };
+    let max_value = {
+      let database = self.database().await;
+      // Available balance is in the reporting currency, the order in the pair's quote
+      let reporting = self.statistic_config.reporting_currency;
+      let rates = database.get_exchange_rates(core_id);
+      let quote = signal.pair.quote();
+      let max_margin = rates
+        .convert(available, reporting, quote)
+        .ok_or(PortfolioError::ExchangeRateMissing(reporting, quote))?;
+      max_margin * self.leverage(core_id)
     };
-    let max_value =
-      { self.database.lock().await.get_balance(core_id).unwrap().available };
+    let limits =
+      self.regime_limits.get(&(core_id, signal.pair)).copied().unwrap_or_default();
     self.allocation_manager.allocate_order(
       &mut order,
       position,
       *signal_strength,
       max_value,
+      limits,
     );
+    if position.is_none() {
+      let open_positions = self.database().await.get_all_open_positions(core_id)?;
+      if let Some(correlated) = self.correlation_limit.correlated_position(
+        &order,
+        &open_positions,
+        &self.correlations,
+      ) {
+        info!(
+          "Not entering {}, it correlates with the open position of {}.",
+          signal.pair, correlated.asset
+        );
+        return Ok(None);
+      }
+    }
     log::info!("ORDER {:?}", order);
     Ok(self.risk_manager.evaluate_order(order))
   }
-  async fn no_cash_to_enter_new_position(
-    &mut self,
-    core_id: Uuid,
-  ) -> Result<bool, PortfolioError> {
-    let res = self
-      .database
-      .lock()
-      .await
-      .get_balance(core_id)
-      .map(|balance| Ok(balance.available == 0.0))
-      .map_err(PortfolioError::RepositoryInteraction)?;
-    res
+  /// Balance of the core, missing until the core initialized it in the database.
+  async fn balance(&self, core_id: Uuid) -> Result<Balance, PortfolioError> {
+    let balance = self.database().await.get_balance(core_id);
+    balance.map_err(|_| PortfolioError::BalanceMissing(core_id))
   }
+  #[instrument(
+    name = "portfolio",
+    skip_all,
+    fields(core_id = %core_id, pair = %signal.asset)
+  )]
   pub async fn generate_exit_order(
     &mut self,
     core_id: Uuid,
     signal: SignalForceExit,
-    live_trading: bool,
   ) -> Result<Option<OrderEvent>, PortfolioError> {
     // Determine PositionId associated with the SignalForceExit
     let position_id = determine_position_id(&core_id, &signal.asset);
 
     // Retrieve Option<Position> associated with the PositionId
-    let position = match self.database.lock().await.get_open_position(&position_id)? {
+    let position = match self.database().await.get_open_position(&position_id)? {
       None => {
         info!(
           position_id = &*position_id,
@@ -127,16 +243,17 @@ impl Portfolio {
       },
       Some(position) => position,
     };
-    let time = if live_trading { Utc::now() } else { signal.time };
     Ok(Some(OrderEvent {
-      time,
+      time: self.clock.now(),
       pair: signal.asset,
       market_meta: MarketMeta {
         close: position.current_symbol_price,
         time: position.meta.update_time,
       },
       decision: position.determine_exit_decision(),
-      quantity: 0.0 - position.quantity,
+      quantity: 0.0
+        - position.quantity.signum() * position.open_quantity() * signal.share,
+      options: OrderOptions::default(),
     }))
   }
 
@@ -147,7 +264,9 @@ impl Portfolio {
   ) -> Result<Option<PositionUpdate>, PortfolioError> {
     // Determine the position_id associated to the input MarketEvent
     let position_id = determine_position_id(&core_id, &market.pair);
-    let mut database = self.database.lock().await;
+    let mut database = self.database().await;
+    database.record_market_time(core_id, market.time);
+    database.record_price(core_id, market.pair, market.price());
     // Update Position if Portfolio has an open Position for that Symbol-Exchange combination
     if let Some(mut position) = database.get_open_position(&position_id)? {
       // Derive PositionUpdate event that communicates the open Position's change in state
@@ -161,51 +280,90 @@ impl Portfolio {
     Ok(None)
   }
 
+  /// Whether the open position of the pair retraced past the trailing stop.
+  pub async fn trailing_stop_hit(
+    &self,
+    core_id: Uuid,
+    pair: &Pair,
+    trailing_stop: f64,
+  ) -> Result<bool, PortfolioError> {
+    let position_id = determine_position_id(&core_id, pair);
+    let position = self.database().await.get_open_position(&position_id)?;
+    Ok(position.is_some_and(|position| position.trailing_stop_hit(trailing_stop)))
+  }
+
+  #[instrument(
+    name = "portfolio",
+    skip_all,
+    fields(core_id = %core_id, pair = %fill.asset)
+  )]
   pub async fn update_from_fill(
     &mut self,
     core_id: Uuid,
     fill: &FillEvent,
   ) -> Result<Vec<Event>, PortfolioError> {
     let mut generated_events: Vec<Event> = Vec::with_capacity(2);
-    let mut database = self.database.lock().await;
-    let mut balance = database.get_balance(core_id)?;
+    let mut database = self.database().await;
+    // Positions are settled in the quote asset of their pair
+    let quote = fill.asset.quote();
+    let mut asset_balances = database.get_asset_balances(core_id)?;
+    // Checked before anything changes, the fill is booked in the reporting currency
+    let reporting = asset_balances.reporting_currency;
+    let rates = database.get_exchange_rates(core_id);
+    let rate = rates
+      .rate(quote, reporting)
+      .ok_or(PortfolioError::ExchangeRateMissing(quote, reporting))?;
+    let mut balance = asset_balances.get(quote, fill.time);
     let position_id = determine_position_id(&core_id, &fill.asset);
     balance.time = fill.time;
-    match database.remove_position(&position_id)? {
+    let mut exited_position = None;
+    // Written back or removed once the fill applied, a fill that fails leaves it as is
+    match database.get_open_position(&position_id)? {
+      // Partial fills of an order add up in the open position
+      Some(mut position) if fill.decision.is_entry() => {
+        position.add_entry_fill(fill)?;
+        let fees = fill.fees.calculate_total_fees(fill.fill_value_gross);
+        balance.reserve(fill.fill_value_gross / position.leverage, fees);
+        generated_events.push(Event::PositionUpdate(PositionUpdate::from(&mut position)));
+        database.set_open_position(position)?;
+      },
       Some(mut position) => {
-        let position_exit = position.exit(balance, fill)?;
-        generated_events.push(Event::PositionExit(position_exit));
-
-        balance.available += position.enter_value_gross
-          + position.realised_profit_loss
-          + position.enter_fees_total;
-        balance.total += position.realised_profit_loss;
-
-        let asset = position.asset.clone();
-        let mut stats = database.get_statistics(&core_id)?;
-        stats.update(&position);
-
-        // Persist exited Position & Updated Market statistics in Repository
-        database.set_statistics(core_id, stats)?;
-        database.set_exited_position(core_id, position)?;
+        if !position.add_exit_fill(fill)? {
+          generated_events
+            .push(Event::PositionUpdate(PositionUpdate::from(&mut position)));
+          database.set_open_position(position)?;
+        } else {
+          let position_exit = position.close(balance)?;
+          database.remove_position(&position_id)?;
+          generated_events.push(Event::PositionExit(position_exit));
+          balance.apply_exit(&position);
+          exited_position = Some(position);
+        }
       },
       None => {
-        let position = Position::enter(core_id, fill)?;
+        let leverage = self.leverage(core_id);
+        let position = Position::enter(core_id, fill)?.with_leverage(leverage);
         generated_events.push(Event::PositionNew(position.clone()));
-        balance.available += -position.enter_value_gross - position.enter_fees_total;
+        balance.apply_entry(&position);
         database.set_open_position(position)?;
       },
     };
-    generated_events.push(Event::Balance(balance));
-    database.set_balance(core_id, balance)?;
-    Ok(generated_events)
-  }
+    asset_balances.set(quote, balance);
+    database.set_asset_balances(core_id, asset_balances.clone());
 
-  pub async fn get_statistics(
-    &mut self,
-    core_id: &Uuid,
-  ) -> Result<TradingSummary, DatabaseError> {
-    self.database.lock().await.get_statistics(core_id)
+    let valuation = asset_balances.valuation(&rates, fill.time)?;
+    if let Some(position) = exited_position {
+      // Statistics are in the reporting currency, with the session's equity at the exit
+      let mut reported = position.converted(rate);
+      reported.meta.exit_balance = Some(valuation);
+
+      // Persist exited Position & Updated Market statistics in Repository
+      database.update_statistics(core_id, &reported)?;
+      database.set_exited_position(core_id, position).await?;
+    }
+    generated_events.push(Event::Balance(valuation));
+    database.set_balance(core_id, valuation)?;
+    Ok(generated_events)
   }
 }
 
@@ -240,6 +398,7 @@ pub struct PortfolioBuilder {
   allocation_manager: Option<Allocator>,
   risk_manager: Option<RiskEvaluator>,
   statistic_config: Option<StatisticConfig>,
+  clock: Option<SharedClock>,
 }
 
 impl PortfolioBuilder {
@@ -249,6 +408,7 @@ impl PortfolioBuilder {
       allocation_manager: None,
       risk_manager: None,
       statistic_config: None,
+      clock: None,
     }
   }
   pub fn database(self, database: Arc<Mutex<Database>>) -> Self {
@@ -263,6 +423,10 @@ impl PortfolioBuilder {
   pub fn statistic_config(self, value: StatisticConfig) -> Self {
     Self { statistic_config: Some(value), ..self }
   }
+  /// Defaults to the wall clock.
+  pub fn clock(self, value: SharedClock) -> Self {
+    Self { clock: Some(value), ..self }
+  }
   pub async fn build(self) -> Result<Portfolio, PortfolioError> {
     let portfolio = Portfolio {
       allocation_manager: self
@@ -275,8 +439,335 @@ impl PortfolioBuilder {
       statistic_config: self
         .statistic_config
         .ok_or(PortfolioError::BuilderIncomplete("statistic_config"))?,
+      clock: self.clock.unwrap_or_else(real_clock),
+      order_options: OrderOptions::default(),
+      regime_limits: HashMap::new(),
+      correlation_limit: CorrelationLimit::default(),
+      correlations: CorrelationMatrix::default(),
+      leverage: HashMap::new(),
     };
 
     Ok(portfolio)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use super::*;
+  use crate::{
+    assets::Asset,
+    clock::real_clock,
+    portfolio::{
+      fixtures::{close, fees, fill, fill_of},
+      position::PositionExit,
+    },
+    trading::execution::{Execution, Fees},
+  };
+
+  const STARTING_EQUITY: f64 = 10_000.0;
+
+  /// Enters with a fraction of the available balance and exits at another price.
+  #[derive(Clone, Debug)]
+  struct RoundTrip {
+    side: Side,
+    fraction: f64,
+    enter_price: f64,
+    exit_price: f64,
+  }
+
+  /// Session of BTCUSDT that runs round trips through `update_from_fill` and tracks what
+  /// the balance should add up to.
+  struct Ledger {
+    portfolio: Portfolio,
+    core_id: Uuid,
+    fees: Fees,
+    gross_profit_loss: f64,
+    fees_paid: f64,
+  }
+
+  impl Ledger {
+    async fn new(fees: Fees) -> Self {
+      let (portfolio, core_id) = session(&[Pair::BTCUSDT]).await;
+      Self { portfolio, core_id, fees, gross_profit_loss: 0.0, fees_paid: 0.0 }
+    }
+
+    async fn balance(&self) -> Balance {
+      self.portfolio.balance(self.core_id).await.unwrap()
+    }
+
+    async fn update_from_fill(&mut self, fill: &FillEvent) -> Vec<Event> {
+      self.portfolio.update_from_fill(self.core_id, fill).await.unwrap()
+    }
+
+    /// Returns the entered position with the balance after the entry, and the exit,
+    /// none when the balance can not cover the fees anymore.
+    async fn round_trip(
+      &mut self,
+      trip: &RoundTrip,
+    ) -> Option<(Position, Balance, PositionExit)> {
+      let spendable = self.balance().await.available - self.fees.slippage;
+      if spendable <= 0.0 {
+        return None;
+      }
+      // Value and its fees stay within the available balance
+      let value = trip.fraction * spendable / (1.0 + self.fees.exchange);
+      let quantity = value / trip.enter_price;
+      let (decision, quantity) = match trip.side {
+        Side::Buy => (Decision::Long, quantity),
+        Side::Sell => (Decision::Short, -quantity),
+      };
+      let entry = fill(decision, quantity, trip.enter_price, self.fees);
+      let events = self.update_from_fill(&entry).await;
+      let position = events.into_iter().find_map(|event| match event {
+        Event::PositionNew(position) => Some(position),
+        _ => None,
+      })?;
+      let entered = self.balance().await;
+
+      let exit_decision = position.determine_exit_decision();
+      let exit = fill(exit_decision, -position.quantity, trip.exit_price, self.fees);
+      let events = self.update_from_fill(&exit).await;
+      let exited = events.into_iter().find_map(|event| match event {
+        Event::PositionExit(exited) => Some(exited),
+        _ => None,
+      })?;
+
+      self.gross_profit_loss += match trip.side {
+        Side::Buy => position.quantity * (trip.exit_price - trip.enter_price),
+        Side::Sell => -position.quantity * (trip.enter_price - trip.exit_price),
+      };
+      self.fees_paid += position.enter_fees_total + exited.exit_fees_total;
+      Some((position, entered, exited))
+    }
+  }
+
+  /// Runtime of the property tests, shared as the test database is connected once.
+  fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> =
+      std::sync::OnceLock::new();
+    RUNTIME.get_or_init(|| tokio::runtime::Runtime::new().unwrap()).block_on(future)
+  }
+
+  fn round_trip(sides: &'static [Side]) -> impl Strategy<Value = RoundTrip> {
+    (prop::sample::select(sides), 0.01..0.9f64, 1.0..100_000.0f64, 1.0..100_000.0f64)
+      .prop_map(|(side, fraction, enter_price, exit_price)| RoundTrip {
+        side,
+        fraction,
+        enter_price,
+        exit_price,
+      })
+  }
+
+  #[tokio::test]
+  async fn test_long_round_trip() {
+    let mut ledger = Ledger::new(Fees { exchange: 0.001, slippage: 0.0 }).await;
+    let trip =
+      RoundTrip { side: Side::Buy, fraction: 0.5, enter_price: 100.0, exit_price: 110.0 };
+    let (_, _, exited) = ledger.round_trip(&trip).await.unwrap();
+    let value = 0.5 * STARTING_EQUITY / 1.001;
+    let expected = value * 1.1 - value - value * 0.001 - value * 1.1 * 0.001;
+    assert!(close(exited.realised_profit_loss, expected));
+    let balance = ledger.balance().await;
+    assert!(close(balance.total, STARTING_EQUITY + expected));
+    assert!(close(balance.available, balance.total));
+  }
+
+  /// Portfolio of a new session of the pairs, funded with the starting equity in USDT.
+  async fn session(pairs: &[Pair]) -> (Portfolio, Uuid) {
+    let database = Arc::new(Mutex::new(Database::test().await));
+    let core_id = Uuid::new_v4();
+    let statistic_config = crate::app::STATISTIC_CONFIG;
+    {
+      let mut database = database.lock().await;
+      let time = DateTime::UNIX_EPOCH;
+      let balance = Balance { time, total: STARTING_EQUITY, available: STARTING_EQUITY };
+      let reporting = statistic_config.reporting_currency;
+      database.init_balance(core_id, balance, reporting).unwrap();
+      let summary = TradingSummary::init(statistic_config, Some(time));
+      database.init_statistics(core_id, pairs.iter().copied(), summary);
+    }
+    let portfolio = Portfolio::builder()
+      .database(database)
+      .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 1.0 })
+      .risk_manager(RiskEvaluator {})
+      .statistic_config(statistic_config)
+      .build()
+      .await
+      .unwrap();
+    (portfolio, core_id)
+  }
+
+  #[tokio::test]
+  async fn test_eth_btc_round_trip_is_valued_in_usdt() {
+    let (mut portfolio, core_id) = session(&[Pair::ETHBTC]).await;
+    let time = DateTime::UNIX_EPOCH;
+    let entry = fill_of(Pair::ETHBTC, Decision::Long, 1.0, 0.05, Fees::default(), time);
+    let missing = portfolio.update_from_fill(core_id, &entry).await;
+    assert!(matches!(
+      missing,
+      Err(PortfolioError::ExchangeRateMissing(Asset::BTC, Asset::USDT))
+    ));
+    assert!(portfolio.open_positions(core_id).await.unwrap().is_empty());
+
+    portfolio.database().await.record_price(core_id, Pair::BTCUSDT, 50_000.0);
+    portfolio.update_from_fill(core_id, &entry).await.unwrap();
+    let entered = portfolio.balance(core_id).await.unwrap();
+    // 0.05 BTC reserved, worth 2500 USDT
+    assert!(close(entered.available, STARTING_EQUITY - 2_500.0));
+    assert!(close(entered.total, STARTING_EQUITY));
+
+    let exit =
+      fill_of(Pair::ETHBTC, Decision::CloseLong, 1.0, 0.06, Fees::default(), time);
+    portfolio.update_from_fill(core_id, &exit).await.unwrap();
+    let exited = portfolio.balance(core_id).await.unwrap();
+    // 0.01 BTC of profit
+    assert!(close(exited.total, STARTING_EQUITY + 500.0));
+    assert!(close(exited.available, exited.total));
+    let statistics = portfolio.database().await.get_aggregate_statistics(&core_id);
+    assert!(close(statistics.unwrap().pnl.total_pnl, 500.0));
+  }
+
+  #[tokio::test]
+  async fn test_short_round_trip_through_the_execution() {
+    let (mut portfolio, core_id) = session(&[Pair::BTCUSDT]).await;
+    let execution = Execution::stub(0.0, real_clock());
+    let order = |decision: Decision, quantity: f64, price: f64| OrderEvent {
+      time: DateTime::UNIX_EPOCH,
+      pair: Pair::BTCUSDT,
+      decision,
+      market_meta: MarketMeta { close: price, time: DateTime::UNIX_EPOCH },
+      quantity,
+      options: Default::default(),
+    };
+
+    let short = order(Decision::Short, -1.0, 100.0);
+    let entry = execution.generate_fill(&short, false).await.unwrap();
+    assert_eq!(entry.quantity, -1.0);
+    portfolio.update_from_fill(core_id, &entry).await.unwrap();
+    let position = portfolio.open_positions(core_id).await.unwrap().remove(0);
+    assert_eq!((position.side, position.quantity), (Side::Sell, -1.0));
+
+    let close_short = order(Decision::CloseShort, 1.0, 90.0);
+    let exit = execution.generate_fill(&close_short, false).await.unwrap();
+    assert_eq!(exit.quantity, 1.0);
+    let events = portfolio.update_from_fill(core_id, &exit).await.unwrap();
+    assert!(events.iter().any(|event| matches!(event, Event::PositionExit(_))));
+    assert!(portfolio.open_positions(core_id).await.unwrap().is_empty());
+    let balance = portfolio.balance(core_id).await.unwrap();
+    assert!(close(balance.total, STARTING_EQUITY + 10.0));
+    assert!(close(balance.available, balance.total));
+  }
+
+  #[tokio::test]
+  async fn test_fill_of_the_other_side_leaves_the_position_and_balance() {
+    let (mut portfolio, core_id) = session(&[Pair::BTCUSDT]).await;
+    let entry = fill(Decision::Long, 1.0, 100.0, Fees::default());
+    portfolio.update_from_fill(core_id, &entry).await.unwrap();
+    let positions = portfolio.open_positions(core_id).await.unwrap();
+    let balance = portfolio.balance(core_id).await.unwrap();
+
+    let short = fill(Decision::Short, -1.0, 100.0, Fees::default());
+    let entered = portfolio.update_from_fill(core_id, &short).await;
+    assert!(matches!(entered, Err(PortfolioError::EntrySideMismatch)));
+    let close_short = fill(Decision::CloseShort, 1.0, 100.0, Fees::default());
+    let exited = portfolio.update_from_fill(core_id, &close_short).await;
+    assert!(matches!(exited, Err(PortfolioError::ExitSideMismatch)));
+
+    assert_eq!(portfolio.open_positions(core_id).await.unwrap(), positions);
+    assert_eq!(portfolio.balance(core_id).await.unwrap(), balance);
+  }
+
+  #[tokio::test]
+  async fn test_signal_exit_after_a_reduce_exits_what_is_open() {
+    let (mut portfolio, core_id) = session(&[Pair::BTCUSDT]).await;
+    let entry = fill(Decision::Long, 1.0, 100.0, Fees::default());
+    portfolio.update_from_fill(core_id, &entry).await.unwrap();
+
+    let reduce = SignalForceExit {
+      time: DateTime::UNIX_EPOCH,
+      asset: Pair::BTCUSDT,
+      share: 0.5,
+    };
+    let reduce = portfolio.generate_exit_order(core_id, reduce).await.unwrap().unwrap();
+    assert!(close(reduce.quantity, -0.5));
+    let reduced = fill(Decision::CloseLong, 0.5, 100.0, Fees::default());
+    portfolio.update_from_fill(core_id, &reduced).await.unwrap();
+
+    let signal = Signal {
+      time: DateTime::UNIX_EPOCH,
+      pair: Pair::BTCUSDT,
+      market_meta: MarketMeta { close: 110.0, time: DateTime::UNIX_EPOCH },
+      signals: HashMap::from([(Decision::CloseLong, SignalStrength(1.0))]),
+      votes: vec![],
+    };
+    let exit = portfolio.generate_order(core_id, &signal).await.unwrap().unwrap();
+    assert_eq!(exit.decision, Decision::CloseLong);
+    assert!(close(exit.quantity, -0.5));
+    let exited = fill(Decision::CloseLong, 0.5, 110.0, Fees::default());
+    let events = portfolio.update_from_fill(core_id, &exited).await.unwrap();
+    assert!(events.iter().any(|event| matches!(event, Event::PositionExit(_))));
+    let balance = portfolio.balance(core_id).await.unwrap();
+    assert!(close(balance.total, STARTING_EQUITY + 5.0));
+  }
+
+  proptest! {
+    #[test]
+    fn test_balance_total_is_starting_equity_plus_realised_minus_fees(
+      fees in fees(),
+      trips in prop::collection::vec(round_trip(&[Side::Buy, Side::Sell]), 1..30),
+    ) {
+      block_on(async {
+        let mut ledger = Ledger::new(fees).await;
+        for trip in trips.iter() {
+          if ledger.round_trip(trip).await.is_none() {
+            break;
+          }
+        }
+        let balance = ledger.balance().await;
+        let expected = STARTING_EQUITY + ledger.gross_profit_loss - ledger.fees_paid;
+        prop_assert!(close(balance.total, expected));
+        // With every position exited nothing stays reserved
+        prop_assert!(close(balance.available, balance.total));
+        Ok(())
+      })?;
+    }
+
+    #[test]
+    fn test_available_balance_is_never_negative_for_long_positions(
+      fees in fees(),
+      trips in prop::collection::vec(round_trip(&[Side::Buy]), 1..30),
+    ) {
+      block_on(async {
+        let mut ledger = Ledger::new(fees).await;
+        for trip in trips.iter() {
+          if let Some((_, entered, _)) = ledger.round_trip(trip).await {
+            prop_assert!(entered.available >= 0.0);
+            prop_assert!(ledger.balance().await.available >= 0.0);
+          }
+        }
+        Ok(())
+      })?;
+    }
+
+    #[test]
+    fn test_exit_mirrors_entry(
+      fees in fees(),
+      trip in round_trip(&[Side::Buy, Side::Sell]),
+    ) {
+      block_on(async {
+        let mut ledger = Ledger::new(fees).await;
+        let flat = RoundTrip { exit_price: trip.enter_price, ..trip };
+        let (position, _, exited) = ledger.round_trip(&flat).await.unwrap();
+        prop_assert!(close(exited.exit_value_gross, position.enter_value_gross));
+        prop_assert!(close(exited.exit_avg_price_gross, position.enter_avg_price_gross));
+        // At an unchanged price only the fees are lost
+        let fees_total = position.enter_fees_total + exited.exit_fees_total;
+        prop_assert!(close(exited.realised_profit_loss, -fees_total));
+        prop_assert!(close(ledger.balance().await.total, STARTING_EQUITY - fees_total));
+        Ok(())
+      })?;
+    }
+  }
+}
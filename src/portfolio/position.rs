@@ -3,6 +3,7 @@ use crate::{
   portfolio::error::PortfolioError,
   strategy::Decision,
   trading::execution::{FeeAmount, Fees, FillEvent},
+  utils::serde_utils::f64_one,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -35,8 +36,37 @@ pub struct Position {
   pub unrealised_profit_loss: f64,
   pub realised_profit_loss: f64,
   pub n_position_updates: i64,
+  /// Quantity of the exit fills so far, without sign.
+  #[serde(default)]
+  pub exited_quantity: f64,
+  /// Most favourable price since the entry, for trailing stops.
+  #[serde(default)]
+  pub best_price: f64,
+  /// Slippage part of the entry and exit fees.
+  #[serde(default)]
+  pub slippage_total: FeeAmount,
+  /// Value of the entry and exit fills at the price when they were decided, 0 for
+  /// positions stored before it was recorded.
+  #[serde(default)]
+  pub enter_decision_value: f64,
+  #[serde(default)]
+  pub exit_decision_value: f64,
+  /// Of a futures position, 1 on spot.
+  #[serde(default = "f64_one")]
+  pub leverage: f64,
+  /// Price a leveraged position is liquidated at, estimated on entries until the
+  /// exchange reports it.
+  #[serde(default)]
+  pub liquidation_price: Option<f64>,
 }
 
+/// Share of the quantity that may stay open once a position counts as exited, for
+/// rounding in fill quantities.
+const EXIT_TOLERANCE: f64 = 1e-9;
+/// Maintenance margin rate of the lowest notional tier of USD-M futures, for estimated
+/// liquidation prices.
+const MAINTENANCE_MARGIN_RATE: f64 = 0.004;
+
 impl Position {
   pub fn builder() -> PositionBuilder {
     PositionBuilder::new()
@@ -44,6 +74,23 @@ impl Position {
   pub fn calculate_avg_price_gross(fill: &FillEvent) -> f64 {
     (fill.fill_value_gross / fill.quantity).abs()
   }
+  /// Value of the fill at the price of the order, the close when it was decided.
+  pub fn calculate_decision_value(fill: &FillEvent) -> f64 {
+    fill.quantity.abs() * fill.market_meta.close
+  }
+  /// What the fills of an exited position cost against the prices they were decided at,
+  /// positive when they were worse. None when the decision prices were not recorded.
+  pub fn execution_slippage(&self) -> Option<f64> {
+    if self.enter_decision_value == 0.0 || self.exit_decision_value == 0.0 {
+      return None;
+    }
+    let entry = self.enter_value_gross - self.enter_decision_value;
+    let exit = self.exit_decision_value - self.exit_value_gross;
+    match self.side {
+      Side::Buy => Some(entry + exit),
+      Side::Sell => Some(-entry - exit),
+    }
+  }
   pub fn parse_entry_side(fill: &FillEvent) -> Result<Side, PortfolioError> {
     match fill.decision {
       Decision::Long if fill.quantity.is_sign_positive() => Ok(Side::Buy),
@@ -60,23 +107,66 @@ impl Position {
       Side::Sell => Decision::CloseShort,
     }
   }
-  pub fn calculate_unrealised_profit_loss(&self) -> f64 {
-    let approx_total_fees = self.enter_fees_total * 2.0;
-
+  /// PnL before fees of the position valued at `value_gross`. Long positions gain when
+  /// the value rises above the entry value, short positions when it falls below it.
+  pub fn calculate_gross_profit_loss(&self, value_gross: f64) -> f64 {
     match self.side {
-      Side::Buy => self.current_value_gross - self.enter_value_gross - approx_total_fees,
-      Side::Sell => self.enter_value_gross - self.current_value_gross - approx_total_fees,
+      Side::Buy => value_gross - self.enter_value_gross,
+      Side::Sell => self.enter_value_gross - value_gross,
     }
   }
+  /// PnL if the open quantity was exited at the current price, together with the
+  /// partial exits so far. Fees are those of the entry, of the exits so far and the
+  /// estimated fees of exiting the rest.
+  pub fn calculate_unrealised_profit_loss(&self) -> f64 {
+    let approx_exit_fees = self.enter_fees.calculate_total_fees(self.current_value_gross);
+    self.calculate_gross_profit_loss(self.current_value_gross + self.exit_value_gross)
+      - self.enter_fees_total
+      - self.exit_fees_total
+      - approx_exit_fees
+  }
+  /// Quantity not exited yet, without sign.
+  pub fn open_quantity(&self) -> f64 {
+    (self.quantity.abs() - self.exited_quantity).max(0.0)
+  }
   pub fn calculate_realised_profit_loss(&self) -> f64 {
-    let total_fees = self.enter_fees_total + self.exit_fees_total;
+    self.calculate_gross_profit_loss(self.exit_value_gross)
+      - self.enter_fees_total
+      - self.exit_fees_total
+  }
+  /// Return on the margin of the position, which is its whole value without leverage.
+  pub fn calculate_profit_loss_return(&self) -> f64 {
+    self.realised_profit_loss / self.margin()
+  }
+  /// Part of the entry value the balance puts up for the position.
+  pub fn margin(&self) -> f64 {
+    self.enter_value_gross / self.leverage
+  }
+  /// Same position entered with leverage, with its liquidation price estimated.
+  pub fn with_leverage(mut self, leverage: f64) -> Position {
+    self.leverage = leverage.max(1.0);
+    self.liquidation_price = self.estimate_liquidation_price();
+    self
+  }
+  /// Liquidation price of an isolated position, where the loss leaves only the
+  /// maintenance margin. None without leverage.
+  pub fn estimate_liquidation_price(&self) -> Option<f64> {
+    if self.leverage <= 1.0 {
+      return None;
+    }
+    let buffer = 1.0 / self.leverage - MAINTENANCE_MARGIN_RATE;
     match self.side {
-      Side::Buy => self.exit_value_gross - self.enter_value_gross - total_fees,
-      Side::Sell => self.enter_value_gross - self.exit_value_gross - total_fees,
+      Side::Buy => Some(self.enter_avg_price_gross * (1.0 - buffer)),
+      Side::Sell => Some(self.enter_avg_price_gross * (1.0 + buffer)),
     }
   }
-  pub fn calculate_profit_loss_return(&self) -> f64 {
-    self.realised_profit_loss / self.enter_value_gross
+  /// Whether the current price reached the liquidation price.
+  pub fn is_liquidated(&self) -> bool {
+    match (self.liquidation_price, &self.side) {
+      (Some(price), Side::Buy) => self.current_symbol_price <= price,
+      (Some(price), Side::Sell) => self.current_symbol_price >= price,
+      (None, _) => false,
+    }
   }
   pub fn enter(core_id: Uuid, fill: &FillEvent) -> Result<Position, PortfolioError> {
     let metadata = PositionMeta {
@@ -86,8 +176,7 @@ impl Position {
     };
     let enter_fees_total = fill.fees.calculate_total_fees(fill.fill_value_gross);
     let enter_avg_price_gross = Position::calculate_avg_price_gross(fill);
-    let unrealised_profit_loss = -enter_fees_total * 2.0;
-    Ok(Position {
+    let mut position = Position {
       position_id: determine_position_id(&core_id, &fill.asset),
       asset: fill.asset.clone(),
       meta: metadata,
@@ -103,45 +192,124 @@ impl Position {
       exit_value_gross: 0.0,
       current_symbol_price: enter_avg_price_gross,
       current_value_gross: fill.fill_value_gross,
-      unrealised_profit_loss,
+      unrealised_profit_loss: 0.0,
       realised_profit_loss: 0.0,
       n_position_updates: 0,
-    })
+      exited_quantity: 0.0,
+      best_price: enter_avg_price_gross,
+      slippage_total: fill.fees.slippage,
+      enter_decision_value: Position::calculate_decision_value(fill),
+      exit_decision_value: 0.0,
+      leverage: 1.0,
+      liquidation_price: None,
+    };
+    position.unrealised_profit_loss = position.calculate_unrealised_profit_loss();
+    Ok(position)
+  }
+  /// Adds another entry fill of the same side, the entry price becomes the volume
+  /// weighted average of the fills.
+  pub fn add_entry_fill(&mut self, fill: &FillEvent) -> Result<(), PortfolioError> {
+    if Position::parse_entry_side(fill)? != self.side {
+      return Err(PortfolioError::EntrySideMismatch);
+    }
+    self.quantity += fill.quantity;
+    self.enter_value_gross += fill.fill_value_gross;
+    self.enter_fees_total += fill.fees.calculate_total_fees(fill.fill_value_gross);
+    self.enter_fees = fill.fees;
+    self.slippage_total += fill.fees.slippage;
+    self.enter_decision_value += Position::calculate_decision_value(fill);
+    self.enter_avg_price_gross = self.enter_value_gross / self.quantity.abs();
+    self.liquidation_price = self.estimate_liquidation_price();
+    self.meta.update_time = fill.time;
+    self.current_value_gross = self.current_symbol_price * self.open_quantity();
+    self.unrealised_profit_loss = self.calculate_unrealised_profit_loss();
+    Ok(())
+  }
+  /// Adds an exit fill, the exit price becomes the volume weighted average of the exit
+  /// fills. Returns whether the whole quantity is exited.
+  pub fn add_exit_fill(&mut self, fill: &FillEvent) -> Result<bool, PortfolioError> {
+    if fill.decision.is_entry() {
+      return Err(PortfolioError::CannotExitPositionWithEntryFill);
+    }
+    if fill.decision != self.determine_exit_decision() {
+      return Err(PortfolioError::ExitSideMismatch);
+    }
+    self.exited_quantity += fill.quantity.abs();
+    self.exit_value_gross += fill.fill_value_gross;
+    self.exit_fees_total += fill.fees.calculate_total_fees(fill.fill_value_gross);
+    self.exit_fees = fill.fees;
+    self.slippage_total += fill.fees.slippage;
+    self.exit_decision_value += Position::calculate_decision_value(fill);
+    self.exit_avg_price_gross = self.exit_value_gross / self.exited_quantity;
+    self.meta.update_time = fill.time;
+    self.current_value_gross = self.current_symbol_price * self.open_quantity();
+    self.unrealised_profit_loss = self.calculate_unrealised_profit_loss();
+    Ok(self.open_quantity() <= self.quantity.abs() * EXIT_TOLERANCE)
   }
   pub fn update(&mut self, market: &MarketEvent) -> Option<PositionUpdate> {
-    // Determine close from MarketEvent
-    let close = match &market.detail {
-      MarketEventDetail::Trade(trade) => trade.price,
-      MarketEventDetail::Candle(candle) => candle.close,
-      MarketEventDetail::BacktestCandle((candle, _)) => candle.close,
-      MarketEventDetail::OrderBookL1(book_l1) => book_l1.volume_weighted_mid_price(),
-    };
+    let close = market.price();
     self.meta.update_time = market.time;
     self.current_symbol_price = close;
-    self.current_value_gross = close * self.quantity.abs();
+    self.best_price = match self.side {
+      // Positions stored before the field start tracking from here
+      _ if self.best_price == 0.0 => close,
+      Side::Buy => self.best_price.max(close),
+      Side::Sell => self.best_price.min(close),
+    };
+    self.current_value_gross = close * self.open_quantity();
     self.unrealised_profit_loss = self.calculate_unrealised_profit_loss();
     self.n_position_updates = self.n_position_updates + 1;
     Some(PositionUpdate::from(self))
   }
+  /// Whether the price retraced from the best since the entry by the `trailing_stop`
+  /// share. A stop of 0 is never hit.
+  pub fn trailing_stop_hit(&self, trailing_stop: f64) -> bool {
+    if trailing_stop <= 0.0 || self.best_price == 0.0 {
+      return false;
+    }
+    match self.side {
+      Side::Buy => self.current_symbol_price <= self.best_price * (1.0 - trailing_stop),
+      Side::Sell => self.current_symbol_price >= self.best_price * (1.0 + trailing_stop),
+    }
+  }
+  /// Exits the position with its last fill.
   pub fn exit(
     &mut self,
-    mut balance: Balance,
+    balance: Balance,
     fill: &FillEvent,
   ) -> Result<PositionExit, PortfolioError> {
-    if fill.decision.is_entry() {
-      return Err(PortfolioError::CannotExitPositionWithEntryFill);
-    }
-    self.exit_fees = fill.fees;
-    self.exit_fees_total = fill.fees.calculate_total_fees(fill.fill_value_gross);
-    self.exit_value_gross = fill.fill_value_gross;
-    self.exit_avg_price_gross = Position::calculate_avg_price_gross(fill);
+    self.add_exit_fill(fill)?;
+    self.close(balance)
+  }
+  /// Realises the PnL of the exit fills and adds it to the balance.
+  pub fn close(&mut self, mut balance: Balance) -> Result<PositionExit, PortfolioError> {
+    self.current_value_gross = 0.0;
     self.realised_profit_loss = self.calculate_realised_profit_loss();
     self.unrealised_profit_loss = self.realised_profit_loss;
     balance.total += self.realised_profit_loss;
-    self.meta.update_time = fill.time;
     self.meta.exit_balance = Some(balance);
     PositionExit::try_from(self)
   }
+  /// Copy with prices, values, fees and PnL multiplied by `rate`, to report a position
+  /// of a pair quoted in another asset in the reporting currency.
+  pub fn converted(&self, rate: f64) -> Position {
+    Position {
+      enter_fees_total: self.enter_fees_total * rate,
+      enter_avg_price_gross: self.enter_avg_price_gross * rate,
+      enter_value_gross: self.enter_value_gross * rate,
+      exit_fees_total: self.exit_fees_total * rate,
+      exit_avg_price_gross: self.exit_avg_price_gross * rate,
+      exit_value_gross: self.exit_value_gross * rate,
+      current_symbol_price: self.current_symbol_price * rate,
+      current_value_gross: self.current_value_gross * rate,
+      unrealised_profit_loss: self.unrealised_profit_loss * rate,
+      realised_profit_loss: self.realised_profit_loss * rate,
+      slippage_total: self.slippage_total * rate,
+      enter_decision_value: self.enter_decision_value * rate,
+      exit_decision_value: self.exit_decision_value * rate,
+      ..self.clone()
+    }
+  }
 }
 
 /// Builder to construct [`Position`] instances.
@@ -284,6 +452,13 @@ impl PositionBuilder {
         .realised_profit_loss
         .ok_or(PortfolioError::BuilderIncomplete("realised_profit_loss"))?,
       n_position_updates: 0,
+      exited_quantity: 0.0,
+      best_price: 0.0,
+      slippage_total: 0.0,
+      enter_decision_value: 0.0,
+      exit_decision_value: 0.0,
+      leverage: 1.0,
+      liquidation_price: None,
     })
   }
 }
@@ -353,3 +528,339 @@ impl TryFrom<&mut Position> for PositionExit {
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use super::*;
+  use crate::{
+    assets::{Candle, MarketMeta},
+    portfolio::fixtures::{close, fees, fill},
+  };
+
+  const FEES: Fees = Fees { exchange: 0.001, slippage: 0.5 };
+
+  fn market(price: f64) -> MarketEvent {
+    let candle = Candle {
+      open_time: DateTime::UNIX_EPOCH,
+      close_time: DateTime::UNIX_EPOCH,
+      open: price,
+      high: price,
+      low: price,
+      close: price,
+      volume: 1.0,
+      trade_count: 1,
+    };
+    MarketEvent {
+      time: DateTime::UNIX_EPOCH,
+      pair: Pair::BTCUSDT,
+      detail: MarketEventDetail::Candle(candle),
+      degraded: false,
+    }
+  }
+
+  fn enter(side: Side, quantity: f64, price: f64, fees: Fees) -> Position {
+    let (decision, quantity) = match side {
+      Side::Buy => (Decision::Long, quantity),
+      Side::Sell => (Decision::Short, -quantity),
+    };
+    Position::enter(Uuid::new_v4(), &fill(decision, quantity, price, fees)).unwrap()
+  }
+
+  /// Enters `quantity` at `enter_price` and exits it at `exit_price`.
+  fn round_trip(
+    side: Side,
+    quantity: f64,
+    enter_price: f64,
+    exit_price: f64,
+    fees: Fees,
+  ) -> Position {
+    let mut position = enter(side, quantity, enter_price, fees);
+    let exit =
+      fill(position.determine_exit_decision(), -position.quantity, exit_price, fees);
+    position.exit(Balance::default(), &exit).unwrap();
+    position
+  }
+
+  /// Fees of both legs of a round trip of `quantity`.
+  fn fees_of(quantity: f64, enter_price: f64, exit_price: f64) -> f64 {
+    FEES.calculate_total_fees(quantity * enter_price)
+      + FEES.calculate_total_fees(quantity * exit_price)
+  }
+
+  #[test]
+  fn test_long_round_trip_with_profit() {
+    let position = round_trip(Side::Buy, 2.0, 100.0, 110.0, FEES);
+    let expected = 2.0 * (110.0 - 100.0) - fees_of(2.0, 100.0, 110.0);
+    assert!(close(position.realised_profit_loss, expected));
+    assert!(position.realised_profit_loss > 0.0);
+  }
+
+  #[test]
+  fn test_long_round_trip_with_loss() {
+    let position = round_trip(Side::Buy, 2.0, 100.0, 90.0, FEES);
+    let expected = 2.0 * (90.0 - 100.0) - fees_of(2.0, 100.0, 90.0);
+    assert!(close(position.realised_profit_loss, expected));
+  }
+
+  #[test]
+  fn test_short_round_trip_with_profit() {
+    let position = round_trip(Side::Sell, 2.0, 100.0, 90.0, FEES);
+    let expected = 2.0 * (100.0 - 90.0) - fees_of(2.0, 100.0, 90.0);
+    assert!(close(position.realised_profit_loss, expected));
+    assert!(position.realised_profit_loss > 0.0);
+  }
+
+  #[test]
+  fn test_short_round_trip_with_loss() {
+    let position = round_trip(Side::Sell, 2.0, 100.0, 110.0, FEES);
+    let expected = 2.0 * (100.0 - 110.0) - fees_of(2.0, 100.0, 110.0);
+    assert!(close(position.realised_profit_loss, expected));
+  }
+
+  #[test]
+  fn test_fees_are_charged_on_both_legs() {
+    for side in [Side::Buy, Side::Sell] {
+      let position = round_trip(side, 2.0, 100.0, 120.0, FEES);
+      assert!(close(position.enter_fees_total, FEES.calculate_total_fees(200.0)));
+      assert!(close(position.exit_fees_total, FEES.calculate_total_fees(240.0)));
+    }
+  }
+
+  #[test]
+  fn test_short_entry_and_exit_values_are_positive() {
+    let position = round_trip(Side::Sell, 2.0, 100.0, 90.0, FEES);
+    assert_eq!(position.side, Side::Sell);
+    assert!(position.quantity < 0.0);
+    assert!(close(position.enter_value_gross, 200.0));
+    assert!(close(position.exit_value_gross, 180.0));
+    assert!(close(position.enter_avg_price_gross, 100.0));
+    assert!(close(position.exit_avg_price_gross, 90.0));
+  }
+
+  #[test]
+  fn test_leveraged_positions_return_on_margin() {
+    let spot = round_trip(Side::Buy, 2.0, 100.0, 110.0, FEES);
+    let short = enter(Side::Sell, 2.0, 100.0, FEES).with_leverage(10.0);
+    assert!(close(short.margin(), 20.0));
+    assert!(close(short.liquidation_price.unwrap(), 109.6));
+    let mut long = enter(Side::Buy, 2.0, 100.0, FEES).with_leverage(10.0);
+    assert!(close(long.liquidation_price.unwrap(), 90.4));
+    let exit = fill(Decision::CloseLong, -2.0, 110.0, FEES);
+    long.exit(Balance::default(), &exit).unwrap();
+    // Same PnL as on spot, on a tenth of the capital
+    assert!(close(long.realised_profit_loss, spot.realised_profit_loss));
+    let spot_return = spot.calculate_profit_loss_return();
+    assert!(close(long.calculate_profit_loss_return(), 10.0 * spot_return));
+  }
+
+  #[test]
+  fn test_unrealised_profit_loss_follows_the_side() {
+    let mut long = enter(Side::Buy, 2.0, 100.0, FEES);
+    let mut short = enter(Side::Sell, 2.0, 100.0, FEES);
+    let at_entry = -fees_of(2.0, 100.0, 100.0);
+    assert!(close(long.unrealised_profit_loss, at_entry));
+    assert!(close(short.unrealised_profit_loss, at_entry));
+
+    long.update(&market(110.0));
+    short.update(&market(110.0));
+    let fees = fees_of(2.0, 100.0, 110.0);
+    assert!(close(long.unrealised_profit_loss, 20.0 - fees));
+    assert!(close(short.unrealised_profit_loss, -20.0 - fees));
+    assert!(close(short.current_value_gross, 220.0));
+  }
+
+  #[test]
+  fn test_unrealised_matches_realised_at_the_exit_price() {
+    for side in [Side::Buy, Side::Sell] {
+      let mut position = enter(side, 3.0, 100.0, FEES);
+      position.update(&market(95.0));
+      let unrealised = position.unrealised_profit_loss;
+      let exit = fill(position.determine_exit_decision(), -position.quantity, 95.0, FEES);
+      position.exit(Balance::default(), &exit).unwrap();
+      assert!(close(position.realised_profit_loss, unrealised));
+    }
+  }
+
+  #[test]
+  fn test_exit_adds_realised_profit_loss_to_the_balance() {
+    let mut position = enter(Side::Sell, 1.0, 100.0, FEES);
+    let balance = Balance { time: DateTime::UNIX_EPOCH, total: 1000.0, available: 900.0 };
+    let exit =
+      position.exit(balance, &fill(Decision::CloseShort, 1.0, 80.0, FEES)).unwrap();
+    assert!(close(exit.exit_balance.total, 1000.0 + position.realised_profit_loss));
+    assert_eq!(exit.realised_profit_loss, position.realised_profit_loss);
+  }
+
+  #[test]
+  fn test_entry_side_needs_matching_quantity_sign() {
+    let parse = |decision, quantity| {
+      Position::parse_entry_side(&fill(decision, quantity, 100.0, FEES))
+    };
+    assert_eq!(parse(Decision::Long, 1.0).unwrap(), Side::Buy);
+    assert_eq!(parse(Decision::Short, -1.0).unwrap(), Side::Sell);
+    assert!(matches!(parse(Decision::Long, -1.0), Err(PortfolioError::ParseEntrySide)));
+    assert!(matches!(parse(Decision::Short, 1.0), Err(PortfolioError::ParseEntrySide)));
+    assert!(matches!(
+      parse(Decision::CloseLong, -1.0),
+      Err(PortfolioError::CannotEnterPositionWithExitFill)
+    ));
+  }
+
+  #[test]
+  fn test_exit_needs_the_decision_of_the_side() {
+    let mut long = enter(Side::Buy, 1.0, 100.0, FEES);
+    let mut short = enter(Side::Sell, 1.0, 100.0, FEES);
+    let balance = Balance::default();
+    assert!(matches!(
+      long.exit(balance, &fill(Decision::CloseShort, 1.0, 100.0, FEES)),
+      Err(PortfolioError::ExitSideMismatch)
+    ));
+    assert!(matches!(
+      short.exit(balance, &fill(Decision::CloseLong, -1.0, 100.0, FEES)),
+      Err(PortfolioError::ExitSideMismatch)
+    ));
+    assert!(matches!(
+      long.exit(balance, &fill(Decision::Long, 1.0, 100.0, FEES)),
+      Err(PortfolioError::CannotExitPositionWithEntryFill)
+    ));
+  }
+
+  #[test]
+  fn test_entry_fills_average_the_entry_price() {
+    let mut position = enter(Side::Buy, 1.0, 100.0, FEES);
+    position.add_entry_fill(&fill(Decision::Long, 3.0, 120.0, FEES)).unwrap();
+    assert!(close(position.quantity, 4.0));
+    assert!(close(position.enter_value_gross, 460.0));
+    assert!(close(position.enter_avg_price_gross, 115.0));
+    let fees = FEES.calculate_total_fees(100.0) + FEES.calculate_total_fees(360.0);
+    assert!(close(position.enter_fees_total, fees));
+
+    let mut short = enter(Side::Sell, 1.0, 100.0, FEES);
+    short.add_entry_fill(&fill(Decision::Short, -1.0, 110.0, FEES)).unwrap();
+    assert!(close(short.quantity, -2.0));
+    assert!(close(short.enter_avg_price_gross, 105.0));
+  }
+
+  #[test]
+  fn test_entry_fill_needs_the_side_of_the_position() {
+    let mut position = enter(Side::Buy, 1.0, 100.0, FEES);
+    assert!(matches!(
+      position.add_entry_fill(&fill(Decision::Short, -1.0, 100.0, FEES)),
+      Err(PortfolioError::EntrySideMismatch)
+    ));
+    assert!(close(position.quantity, 1.0));
+  }
+
+  #[test]
+  fn test_partial_exits_close_the_position_once_exited() {
+    let mut position = enter(Side::Buy, 3.0, 100.0, FEES);
+    let partial = fill(Decision::CloseLong, -1.0, 110.0, FEES);
+    assert!(!position.add_exit_fill(&partial).unwrap());
+    assert!(close(position.open_quantity(), 2.0));
+    assert!(close(position.current_value_gross, 200.0));
+
+    position.update(&market(110.0));
+    let unrealised = position.unrealised_profit_loss;
+    let rest = fill(Decision::CloseLong, -2.0, 110.0, FEES);
+    assert!(position.add_exit_fill(&rest).unwrap());
+    position.close(Balance::default()).unwrap();
+    assert!(close(position.exit_avg_price_gross, 110.0));
+    assert!(close(position.realised_profit_loss, unrealised));
+    let fees = FEES.calculate_total_fees(300.0)
+      + FEES.calculate_total_fees(110.0)
+      + FEES.calculate_total_fees(220.0);
+    assert!(close(position.realised_profit_loss, 30.0 - fees));
+  }
+
+  #[test]
+  fn test_execution_slippage_against_the_decided_prices() {
+    // Decided at 100 and 110, filled 1 worse on both legs
+    let filled_at = |decision, quantity: f64, decided: f64, price: f64| FillEvent {
+      fill_value_gross: quantity.abs() * price,
+      ..fill(decision, quantity, decided, FEES)
+    };
+    let mut long = Position::enter(
+      Uuid::new_v4(),
+      &filled_at(Decision::Long, 2.0, 100.0, 101.0),
+    )
+    .unwrap();
+    long
+      .exit(Balance::default(), &filled_at(Decision::CloseLong, -2.0, 110.0, 109.0))
+      .unwrap();
+    assert!(close(long.execution_slippage().unwrap(), 4.0));
+
+    let mut short = Position::enter(
+      Uuid::new_v4(),
+      &filled_at(Decision::Short, -2.0, 100.0, 99.0),
+    )
+    .unwrap();
+    short
+      .exit(Balance::default(), &filled_at(Decision::CloseShort, 2.0, 90.0, 91.0))
+      .unwrap();
+    assert!(close(short.execution_slippage().unwrap(), 4.0));
+
+    let stored = Position { enter_decision_value: 0.0, ..long };
+    assert_eq!(stored.execution_slippage(), None);
+  }
+
+  proptest! {
+    /// Before fees a short round trip makes exactly what the long one loses.
+    #[test]
+    fn test_short_mirrors_long(
+      fees in fees(),
+      quantity in 0.001..100.0f64,
+      enter_price in 1.0..100_000.0f64,
+      exit_price in 1.0..100_000.0f64,
+    ) {
+      let long = round_trip(Side::Buy, quantity, enter_price, exit_price, fees);
+      let short = round_trip(Side::Sell, quantity, enter_price, exit_price, fees);
+      let fees_total = long.enter_fees_total + long.exit_fees_total;
+      prop_assert!(close(short.enter_fees_total + short.exit_fees_total, fees_total));
+      prop_assert!(close(
+        long.realised_profit_loss + fees_total,
+        -(short.realised_profit_loss + fees_total)
+      ));
+      let gross = quantity * (exit_price - enter_price);
+      prop_assert!(close(long.realised_profit_loss, gross - fees_total));
+      prop_assert!(close(short.realised_profit_loss, -gross - fees_total));
+    }
+
+    /// Splitting an order into partial fills changes the fees, not the gross PnL.
+    #[test]
+    fn test_split_fills_match_a_single_fill(
+      long in any::<bool>(),
+      fees in fees(),
+      quantities in prop::collection::vec((0.001..10.0f64, 1.0..100_000.0f64), 1..6),
+      exit_price in 1.0..100_000.0f64,
+    ) {
+      let (side, sign) = if long { (Side::Buy, 1.0) } else { (Side::Sell, -1.0) };
+      let entry_decision = if long { Decision::Long } else { Decision::Short };
+      let (first_quantity, first_price) = quantities[0];
+      let mut split = enter(side.clone(), first_quantity, first_price, fees);
+      for &(quantity, price) in &quantities[1..] {
+        let entry = fill(entry_decision, sign * quantity, price, fees);
+        split.add_entry_fill(&entry).unwrap();
+      }
+      let quantity: f64 = quantities.iter().map(|(quantity, _)| quantity).sum();
+      let value: f64 = quantities.iter().map(|(quantity, price)| quantity * price).sum();
+      prop_assert!(close(split.quantity, sign * quantity));
+      prop_assert!(close(split.enter_avg_price_gross, value / quantity));
+
+      let exit_decision = split.determine_exit_decision();
+      for &(quantity, _) in &quantities {
+        let exit = fill(exit_decision, -sign * quantity, exit_price, fees);
+        split.add_exit_fill(&exit).unwrap();
+      }
+      split.close(Balance::default()).unwrap();
+
+      let single = round_trip(side, quantity, value / quantity, exit_price, fees);
+      let gross = |position: &Position| {
+        let fees_total = position.enter_fees_total + position.exit_fees_total;
+        position.realised_profit_loss + fees_total
+      };
+      prop_assert!(close(gross(&split), gross(&single)));
+    }
+  }
+}
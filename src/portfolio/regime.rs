@@ -0,0 +1,173 @@
+use crate::assets::Candle;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use strum::Display;
+
+/// Volatility of the market of a pair, relative to its own recent history.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+pub enum VolatilityRegime {
+  Low,
+  Normal,
+  High,
+}
+
+/// How entries are sized in a regime.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RegimeLimits {
+  /// Multiplies the value of entries.
+  pub size: f64,
+  /// Entries commit at most this share of the available balance.
+  pub max_entry_share: f64,
+}
+
+impl Default for RegimeLimits {
+  fn default() -> Self {
+    Self { size: 1.0, max_entry_share: 1.0 }
+  }
+}
+
+/// Volatility regimes of traded pairs, from `volatility_regime` in the config. The ATR
+/// of the latest `atr_period` candles, relative to the close, is ranked among the ATRs
+/// of the last `lookback` candles.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RegimeConfig {
+  pub enabled: bool,
+  pub atr_period: usize,
+  pub lookback: usize,
+  /// ATRs ranked below this percentile are low volatility.
+  pub low_percentile: f64,
+  /// ATRs ranked above this percentile are high volatility.
+  pub high_percentile: f64,
+  pub low: RegimeLimits,
+  pub normal: RegimeLimits,
+  pub high: RegimeLimits,
+}
+
+impl Default for RegimeConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      atr_period: 14,
+      lookback: 500,
+      low_percentile: 0.25,
+      high_percentile: 0.75,
+      low: RegimeLimits::default(),
+      normal: RegimeLimits::default(),
+      high: RegimeLimits { size: 0.5, max_entry_share: 0.25 },
+    }
+  }
+}
+
+impl RegimeConfig {
+  pub fn limits(&self, regime: VolatilityRegime) -> RegimeLimits {
+    match regime {
+      VolatilityRegime::Low => self.low,
+      VolatilityRegime::Normal => self.normal,
+      VolatilityRegime::High => self.high,
+    }
+  }
+}
+
+/// Classifies the regime of a pair from its closed candles.
+pub struct RegimeClassifier {
+  config: RegimeConfig,
+  previous_close: Option<f64>,
+  true_ranges: VecDeque<f64>,
+  atrs: VecDeque<f64>,
+  regime: Option<VolatilityRegime>,
+}
+
+impl RegimeClassifier {
+  pub fn new(config: RegimeConfig) -> Self {
+    Self {
+      config,
+      previous_close: None,
+      true_ranges: VecDeque::new(),
+      atrs: VecDeque::new(),
+      regime: None,
+    }
+  }
+
+  pub fn config(&self) -> &RegimeConfig {
+    &self.config
+  }
+
+  /// Adds a closed candle, returns the regime when it changed. There is none until
+  /// `atr_period` ATRs are ranked.
+  pub fn update(&mut self, candle: &Candle) -> Option<VolatilityRegime> {
+    let period = self.config.atr_period.max(1);
+    let true_range = match self.previous_close {
+      Some(previous) => (candle.high - candle.low)
+        .max((candle.high - previous).abs())
+        .max((candle.low - previous).abs()),
+      None => candle.high - candle.low,
+    };
+    self.previous_close = Some(candle.close);
+    self.true_ranges.push_back(true_range);
+    if self.true_ranges.len() > period {
+      self.true_ranges.pop_front();
+    }
+    if self.true_ranges.len() < period || candle.close <= 0.0 {
+      return None;
+    }
+    let atr = self.true_ranges.iter().sum::<f64>() / period as f64 / candle.close;
+    self.atrs.push_back(atr);
+    if self.atrs.len() > self.config.lookback.max(period) {
+      self.atrs.pop_front();
+    }
+    if self.atrs.len() < period {
+      return None;
+    }
+    let below = self.atrs.iter().filter(|other| **other < atr).count();
+    let percentile = below as f64 / (self.atrs.len() - 1).max(1) as f64;
+    let regime = if percentile < self.config.low_percentile {
+      VolatilityRegime::Low
+    } else if percentile > self.config.high_percentile {
+      VolatilityRegime::High
+    } else {
+      VolatilityRegime::Normal
+    };
+    if self.regime == Some(regime) {
+      return None;
+    }
+    self.regime = Some(regime);
+    Some(regime)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn candle(close: f64, range: f64) -> Candle {
+    Candle {
+      open_time: Default::default(),
+      close_time: Default::default(),
+      open: close,
+      high: close + range / 2.0,
+      low: close - range / 2.0,
+      close,
+      volume: 1.0,
+      trade_count: 1,
+    }
+  }
+
+  #[test]
+  fn classifies_by_the_rank_of_the_latest_atr() {
+    let config = RegimeConfig { atr_period: 2, lookback: 10, ..Default::default() };
+    let mut classifier = RegimeClassifier::new(config);
+    assert_eq!(classifier.update(&candle(100.0, 2.0)), None);
+    assert_eq!(classifier.update(&candle(100.0, 2.0)), None);
+    // Two ATRs ranked, the second one equal to the first
+    assert_eq!(classifier.update(&candle(100.0, 2.0)), Some(VolatilityRegime::Low));
+    for _ in 0..5 {
+      classifier.update(&candle(100.0, 2.0));
+    }
+    assert_eq!(classifier.update(&candle(100.0, 8.0)), Some(VolatilityRegime::High));
+    assert_eq!(classifier.update(&candle(100.0, 8.0)), None);
+    assert_eq!(classifier.config().limits(VolatilityRegime::High).size, 0.5);
+  }
+}
@@ -1,8 +1,93 @@
-use super::OrderEvent;
+use super::{position::Position, OrderEvent, Portfolio};
+use crate::{
+    assets::Side,
+    statistic::correlation::{rolling_correlations, CorrelationMatrix},
+    strategy::Decision,
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+use tracing::error;
+
+/// Keeps sessions from doubling up on correlated pairs, from `correlation_limit` in the
+/// config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CorrelationLimit {
+    /// Entries are rejected while a position of a pair whose returns correlate with the
+    /// pair's by more than this is open in the same direction. 0 turns it off.
+    pub max_correlation: f64,
+    /// Latest candles the correlations are computed over.
+    pub window: usize,
+    pub refresh_minutes: u64,
+}
+
+impl Default for CorrelationLimit {
+    fn default() -> Self {
+        Self { max_correlation: 0.0, window: 1440, refresh_minutes: 60 }
+    }
+}
+
+impl CorrelationLimit {
+    pub fn is_enabled(&self) -> bool {
+        self.max_correlation > 0.0
+    }
+
+    /// Open position the entry would double up on, if any. Positions in the opposite
+    /// direction count for pairs that move against each other.
+    pub fn correlated_position<'a>(
+        &self,
+        order: &OrderEvent,
+        positions: &'a [Position],
+        correlations: &CorrelationMatrix,
+    ) -> Option<&'a Position> {
+        let direction = match order.decision {
+            Decision::Long => 1.0,
+            Decision::Short => -1.0,
+            _ => return None,
+        };
+        if !self.is_enabled() {
+            return None;
+        }
+        positions.iter().filter(|position| position.asset != order.pair).find(|position| {
+            let side = match position.side {
+                Side::Buy => 1.0,
+                Side::Sell => -1.0,
+            };
+            let correlation = correlations.get(order.pair, position.asset);
+            correlation.is_some_and(|c| c * direction * side > self.max_correlation)
+        })
+    }
+}
+
+/// Recomputes the correlations the portfolio checks entries against every
+/// `refresh_minutes`, for as long as the app runs.
+pub fn spawn_correlations(limit: CorrelationLimit, portfolio: Arc<Mutex<Portfolio>>) {
+    if !limit.is_enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        let period = Duration::from_secs(limit.refresh_minutes.max(1) * 60);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            match rolling_correlations(limit.window).await {
+                Ok(correlations) => {
+                    portfolio.lock().await.set_correlations(limit.clone(), correlations)
+                }
+                Err(e) => error!("Error computing correlations: {}", e),
+            }
+        }
+    });
+}
 
 pub struct RiskEvaluator {}
 impl RiskEvaluator {
     pub fn evaluate_order(&self, order: OrderEvent) -> Option<OrderEvent> {
+        if let Some(reason) = order.options.invalid() {
+            log::warn!("Rejected the order for {}: {}", order.pair, reason);
+            return None;
+        }
         if self.risk_too_high(&order) {
             return None;
         }
@@ -12,3 +97,33 @@ impl RiskEvaluator {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assets::{MarketMeta, Pair},
+        strategy::Decision,
+        trading::params::{OrderOptions, OrderType, TimeInForce},
+    };
+
+    #[test]
+    fn rejects_options_invalid_for_the_order_type() {
+        let order = |order_type, time_in_force, post_only| OrderEvent {
+            time: Default::default(),
+            pair: Pair::BTCUSDT,
+            decision: Decision::Long,
+            market_meta: MarketMeta::default(),
+            quantity: 1.0,
+            options: OrderOptions { order_type, time_in_force, post_only },
+        };
+        let evaluator = RiskEvaluator {};
+        let accepted = |order| evaluator.evaluate_order(order).is_some();
+        assert!(accepted(order(OrderType::Market, TimeInForce::Gtc, false)));
+        assert!(accepted(order(OrderType::Limit, TimeInForce::Fok, false)));
+        assert!(accepted(order(OrderType::Limit, TimeInForce::Gtc, true)));
+        assert!(!accepted(order(OrderType::Market, TimeInForce::Ioc, false)));
+        assert!(!accepted(order(OrderType::Market, TimeInForce::Gtc, true)));
+        assert!(!accepted(order(OrderType::Limit, TimeInForce::Ioc, true)));
+    }
+}
@@ -2,23 +2,34 @@ use crate::{
   action::Action,
   assets::Pair,
   config::Config,
+  database::snapshot::DatabaseSnapshot,
+  mode::Mode,
   tui::{Event, Frame},
 };
 use crossterm::event::{KeyEvent, MouseEvent};
 use eyre::Result;
 use ratatui::layout::Rect;
 use serde::Serialize;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{mpsc::UnboundedSender, watch};
 use uuid::Uuid;
 
+pub mod analytics;
+pub mod compare;
+pub mod correlations;
+pub mod diagnostics;
 pub mod exchange;
+#[cfg(test)]
+mod fixtures;
 pub mod home;
+pub mod market;
 pub mod model_config;
 pub mod models;
 pub mod report;
 pub mod run_config;
 pub mod running;
 pub mod sessions;
+pub mod settings;
+pub mod trades;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ScreenId {
@@ -30,6 +41,84 @@ pub enum ScreenId {
   RUNCONFIG,
   RUNNING((Uuid, Pair)),
   EXCHANGE,
+  TRADES(Uuid),
+  ANALYTICS,
+  MARKETS,
+  DIAGNOSTICS,
+  SETTINGS,
+  COMPARE(Uuid, Uuid),
+  CORRELATIONS,
+}
+
+impl ScreenId {
+  pub fn title(&self) -> &'static str {
+    match self {
+      Self::HOME => "Home",
+      Self::MODELS => "Models",
+      Self::MODELCONFIG => "New model",
+      Self::REPORT(_) => "Report",
+      Self::SESSIONS => "Sessions",
+      Self::RUNCONFIG => "Run",
+      Self::RUNNING(_) => "Running",
+      Self::EXCHANGE => "Exchange",
+      Self::TRADES(_) => "Trades",
+      Self::ANALYTICS => "Analytics",
+      Self::MARKETS => "Markets",
+      Self::DIAGNOSTICS => "Diagnostics",
+      Self::SETTINGS => "Settings",
+      Self::COMPARE(..) => "Compare",
+      Self::CORRELATIONS => "Correlations",
+    }
+  }
+
+  /// Keymap of the screen.
+  pub fn mode(&self) -> Mode {
+    match self {
+      Self::HOME => Mode::Home,
+      Self::MODELS => Mode::Models,
+      Self::MODELCONFIG => Mode::ModelConfig,
+      Self::REPORT(_) => Mode::Report,
+      Self::SESSIONS => Mode::Sessions,
+      Self::RUNCONFIG => Mode::RunConfig,
+      Self::RUNNING(_) => Mode::Running,
+      Self::EXCHANGE => Mode::Exchange,
+      Self::TRADES(_) => Mode::Trades,
+      Self::ANALYTICS => Mode::Analytics,
+      Self::MARKETS => Mode::Markets,
+      Self::DIAGNOSTICS => Mode::Diagnostics,
+      Self::SETTINGS => Mode::Settings,
+      Self::COMPARE(..) => Mode::Compare,
+      Self::CORRELATIONS => Mode::Correlations,
+    }
+  }
+
+  /// Screen of the mode, for `Navigate(<mode>)` in keybindings. Screens of a session
+  /// cannot be opened by name.
+  pub fn from_mode(mode: Mode) -> Option<Self> {
+    match mode {
+      Mode::Home => Some(Self::HOME),
+      Mode::Models => Some(Self::MODELS),
+      Mode::ModelConfig => Some(Self::MODELCONFIG),
+      Mode::Sessions => Some(Self::SESSIONS),
+      Mode::RunConfig => Some(Self::RUNCONFIG),
+      Mode::Exchange => Some(Self::EXCHANGE),
+      Mode::Analytics => Some(Self::ANALYTICS),
+      Mode::Markets => Some(Self::MARKETS),
+      Mode::Diagnostics => Some(Self::DIAGNOSTICS),
+      Mode::Settings => Some(Self::SETTINGS),
+      Mode::Correlations => Some(Self::CORRELATIONS),
+      Mode::Global
+      | Mode::Report
+      | Mode::Running
+      | Mode::Trades
+      | Mode::Compare => None,
+    }
+  }
+
+  /// Transient screens are not returned to with back navigation.
+  pub fn is_transient(&self) -> bool {
+    matches!(self, Self::RUNNING(_))
+  }
 }
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
@@ -62,6 +151,22 @@ pub trait Screen {
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
     Ok(())
   }
+  /// Register a receiver of the database snapshot for screens that display stored state.
+  ///
+  /// # Arguments
+  ///
+  /// * `snapshot` - A watch receiver that always holds the latest snapshot.
+  ///
+  /// # Returns
+  ///
+  /// * `Result<()>` - An Ok result or an error.
+  #[allow(unused_variables)]
+  fn register_snapshot_handler(
+    &mut self,
+    snapshot: watch::Receiver<DatabaseSnapshot>,
+  ) -> Result<()> {
+    Ok(())
+  }
   /// Initialize the component with a specified area if necessary.
   ///
   /// # Arguments
@@ -91,6 +196,15 @@ pub trait Screen {
     };
     Ok(r)
   }
+  /// Whether keys are typed into a text field, so the app does not treat them as
+  /// shortcuts.
+  ///
+  /// # Returns
+  ///
+  /// * `bool` - True while a text field is being edited.
+  fn captures_text(&self) -> bool {
+    false
+  }
   /// Handle key events and produce actions if necessary.
   ///
   /// # Arguments
@@ -141,4 +255,14 @@ pub trait Screen {
   ///
   /// * `Result<()>` - An Ok result or an error.
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()>;
+  /// Whether the screen changed since it was last drawn, so adaptive rendering draws it
+  /// again. Input and actions redraw anyway, screens only report changes of their own,
+  /// like data loaded on a tick. Screens that do not track them are always drawn.
+  ///
+  /// # Returns
+  ///
+  /// * `bool` - True when the screen has to be drawn on the next frame.
+  fn is_dirty(&self) -> bool {
+    true
+  }
 }
@@ -0,0 +1,129 @@
+use super::{Screen, ScreenId};
+use crate::{
+  action::Action,
+  components::{heatmap::PnLHeatmapWidget, style::button},
+  config::Config,
+  database::{error::DatabaseError, fetch_exited_positions},
+  portfolio::position::Position,
+  statistic::heatmap::PnLHeatmap,
+  utils::formatting::format_signed_number,
+};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+
+/// When the strategy makes or loses money: PnL of all persisted exited positions by
+/// weekday and hour of exit.
+#[derive(Default)]
+pub struct Analytics {
+  command_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  positions_rx: Option<oneshot::Receiver<Result<Vec<Position>, DatabaseError>>>,
+  heatmap: Option<PnLHeatmap>,
+  error: Option<String>,
+  /// Positions loaded since the last draw.
+  dirty: bool,
+}
+
+impl Analytics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn receive_positions(&mut self) {
+    let Some(positions_rx) = &mut self.positions_rx else {
+      return;
+    };
+    match positions_rx.try_recv() {
+      Ok(Ok(positions)) => self.heatmap = Some(PnLHeatmap::from_positions(&positions)),
+      Ok(Err(e)) => self.error = Some(e.to_string()),
+      Err(oneshot::error::TryRecvError::Empty) => return,
+      Err(oneshot::error::TryRecvError::Closed) => {
+        self.error = Some("Loading positions was interrupted.".to_string())
+      },
+    }
+    self.positions_rx = None;
+    self.dirty = true;
+  }
+
+  fn summary(heatmap: &PnLHeatmap) -> String {
+    match heatmap.best_and_worst() {
+      Some(((best_day, best_hour), (worst_day, worst_hour))) => format!(
+        "{} trades. Best: {} {:02}h ({}), worst: {} {:02}h ({}). Times are UTC.",
+        heatmap.trades(),
+        best_day,
+        best_hour,
+        format_signed_number(heatmap.bucket(best_day, best_hour).profit_loss),
+        worst_day,
+        worst_hour,
+        format_signed_number(heatmap.bucket(worst_day, worst_hour).profit_loss)
+      ),
+      None => "No exited trades yet.".to_string(),
+    }
+  }
+}
+
+impl Screen for Analytics {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let (positions_tx, positions_rx) = oneshot::channel();
+    tokio::spawn(async move {
+      let _ = positions_tx.send(fetch_exited_positions(None).await);
+    });
+    self.positions_rx = Some(positions_rx);
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Tick => self.receive_positions(),
+      Action::Accept => {
+        if let Some(command_tx) = &self.command_tx {
+          command_tx.send(Action::NavigateBack)?;
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    self.dirty = false;
+    let content_layout = Layout::default()
+      .constraints(vec![Constraint::Length(2), Constraint::Min(0), Constraint::Length(3)])
+      .split(area);
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Percentage(40),
+    ])
+    .split(content_layout[2]);
+    match (&self.heatmap, &self.error) {
+      (_, Some(error)) => {
+        f.render_widget(Paragraph::new(format!("Error: {error}")), content_layout[0])
+      },
+      (Some(heatmap), None) => {
+        f.render_widget(Paragraph::new(Self::summary(heatmap)), content_layout[0]);
+        f.render_widget(PnLHeatmapWidget::new(heatmap), content_layout[1]);
+      },
+      (None, None) => {
+        f.render_widget(Paragraph::new("Loading trades..."), content_layout[0])
+      },
+    }
+    f.render_widget(button("Back", true), button_layout[1]);
+    Ok(())
+  }
+
+  fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+}
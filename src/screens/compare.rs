@@ -0,0 +1,207 @@
+use super::Screen;
+use crate::{
+  action::{Action, MoveDirection},
+  components::{
+    glyphs::glyphs,
+    style::{button, theme},
+    table::{Cell, Column, Table, TableRow},
+  },
+  config::Config,
+  database::{error::DatabaseError, fetch_exited_positions},
+  portfolio::position::Position,
+  statistic::comparison::{MetricDiff, SessionComparison},
+  utils::formatting::{format_number, format_signed_number},
+};
+use eyre::Result;
+use ratatui::{prelude::*, symbols::Marker, widgets::*};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use uuid::Uuid;
+
+type Positions = Result<Vec<Position>, DatabaseError>;
+
+/// Metric of both sessions, as a row of the comparison table.
+pub struct MetricRow(MetricDiff);
+
+impl TableRow for MetricRow {
+  fn columns() -> Vec<Column> {
+    vec![
+      Column::new("Metric", Constraint::Fill(1)),
+      Column::new("[1]", Constraint::Percentage(22)).right(),
+      Column::new("[2]", Constraint::Percentage(22)).right(),
+      Column::new("Difference", Constraint::Percentage(22)).right(),
+    ]
+  }
+
+  fn cells(&self) -> Vec<Cell> {
+    let metric = &self.0;
+    let diff_style = match metric.improved() {
+      Some(true) => Style::default().fg(theme().positive),
+      Some(false) => Style::default().fg(theme().negative),
+      None => Style::default(),
+    };
+    vec![
+      metric.label.into(),
+      format_number(metric.first).into(),
+      format_number(metric.second).into(),
+      Cell::styled(format_signed_number(metric.diff()), diff_style),
+    ]
+  }
+}
+
+/// Two stored sessions side by side, the first one marked on the Sessions screen being
+/// the baseline: their key metrics with the difference and overlaid equity curves.
+#[derive(Default)]
+pub struct Compare {
+  command_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  core_ids: [Uuid; 2],
+  positions_rx: Option<oneshot::Receiver<(Positions, Positions)>>,
+  metrics: Table<MetricRow>,
+  /// Realised PnL after each trade of either session, as points of the chart.
+  equity: [Vec<(f64, f64)>; 2],
+  loaded: bool,
+  error: Option<String>,
+  /// Sessions loaded or the selected metric moved since the last draw.
+  dirty: bool,
+}
+
+impl Compare {
+  pub fn new(first: Uuid, second: Uuid) -> Self {
+    Self { core_ids: [first, second], ..Self::default() }
+  }
+
+  fn receive_positions(&mut self) {
+    let Some(positions_rx) = &mut self.positions_rx else {
+      return;
+    };
+    match positions_rx.try_recv() {
+      Ok((Ok(first), Ok(second))) => self.set_comparison(&first, &second),
+      Ok((Err(e), _) | (_, Err(e))) => self.error = Some(e.to_string()),
+      Err(oneshot::error::TryRecvError::Empty) => return,
+      Err(oneshot::error::TryRecvError::Closed) => {
+        self.error = Some("Loading sessions was interrupted.".to_string())
+      },
+    }
+    self.positions_rx = None;
+    self.dirty = true;
+  }
+
+  fn set_comparison(&mut self, first: &[Position], second: &[Position]) {
+    let comparison = SessionComparison::new(first, second);
+    self.metrics.update_items(comparison.metrics.into_iter().map(MetricRow).collect());
+    self.equity = comparison.equity.map(|curve| {
+      curve.into_iter().enumerate().map(|(i, equity)| ((i + 1) as f64, equity)).collect()
+    });
+    self.loaded = true;
+  }
+
+  fn equity_chart(&self) -> Chart<'_> {
+    let points = self.equity.iter().flatten();
+    let trades = points.clone().map(|(trade, _)| *trade).fold(1.0, f64::max);
+    let (low, high) = points.map(|(_, equity)| *equity).fold(
+      (0.0, 0.0),
+      |(low, high): (f64, f64), equity| (low.min(equity), high.max(equity)),
+    );
+    let marker = if glyphs().ascii { Marker::Block } else { Marker::Braille };
+    let datasets = [("[1]", theme().text_dimmed), ("[2]", theme().brand)]
+      .into_iter()
+      .zip(&self.equity)
+      .map(|((name, color), data)| {
+        Dataset::default()
+          .name(name)
+          .marker(marker)
+          .graph_type(GraphType::Line)
+          .style(Style::default().fg(color))
+          .data(data)
+      })
+      .collect();
+    let label = |value: f64| Span::raw(format_number(value));
+    Chart::new(datasets)
+      .block(Block::default().title("Realised PnL by trade"))
+      .x_axis(
+        Axis::default()
+          .bounds([0.0, trades])
+          .labels(vec![Span::raw("0"), Span::raw(format!("{trades}"))]),
+      )
+      .y_axis(Axis::default().bounds([low, high]).labels(vec![label(low), label(high)]))
+  }
+}
+
+impl Screen for Compare {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let (positions_tx, positions_rx) = oneshot::channel();
+    let [first, second] = self.core_ids;
+    tokio::spawn(async move {
+      let positions = tokio::join!(
+        fetch_exited_positions(Some(first)),
+        fetch_exited_positions(Some(second))
+      );
+      let _ = positions_tx.send(positions);
+    });
+    self.positions_rx = Some(positions_rx);
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Tick => self.receive_positions(),
+      Action::Move(MoveDirection::Up) => {
+        self.metrics.previous();
+        self.dirty = true;
+      },
+      Action::Move(MoveDirection::Down) => {
+        self.metrics.next();
+        self.dirty = true;
+      },
+      Action::Accept => {
+        if let Some(command_tx) = &self.command_tx {
+          command_tx.send(Action::NavigateBack)?;
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    self.dirty = false;
+    let content_layout =
+      Layout::vertical(vec![Constraint::Min(0), Constraint::Length(3)]).split(area);
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Percentage(40),
+    ])
+    .split(content_layout[1]);
+    match (&self.error, self.loaded) {
+      (Some(error), _) => {
+        f.render_widget(Paragraph::new(format!("Error: {error}")), content_layout[0])
+      },
+      (None, false) => {
+        f.render_widget(Paragraph::new("Loading sessions..."), content_layout[0])
+      },
+      (None, true) => {
+        let layout = Layout::vertical(vec![Constraint::Length(22), Constraint::Min(0)])
+          .split(content_layout[0]);
+        self.metrics.draw(f, layout[0])?;
+        f.render_widget(self.equity_chart(), layout[1]);
+      },
+    }
+    f.render_widget(button("Back", true), button_layout[1]);
+    Ok(())
+  }
+
+  fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+}
@@ -0,0 +1,124 @@
+use super::Screen;
+use crate::{
+  action::Action,
+  components::{heatmap::CorrelationHeatmapWidget, style::button},
+  config::Config,
+  database::error::DatabaseError,
+  statistic::correlation::{rolling_correlations, CorrelationMatrix},
+};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+
+/// Rolling correlations between the returns of the tracked pairs, over the latest
+/// `correlation_limit.window` stored candles.
+#[derive(Default)]
+pub struct Correlations {
+  command_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  correlations_rx: Option<oneshot::Receiver<Result<CorrelationMatrix, DatabaseError>>>,
+  correlations: Option<CorrelationMatrix>,
+  error: Option<String>,
+  /// Correlations loaded since the last draw.
+  dirty: bool,
+}
+
+impl Correlations {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn receive_correlations(&mut self) {
+    let Some(correlations_rx) = &mut self.correlations_rx else {
+      return;
+    };
+    match correlations_rx.try_recv() {
+      Ok(Ok(correlations)) => self.correlations = Some(correlations),
+      Ok(Err(e)) => self.error = Some(e.to_string()),
+      Err(oneshot::error::TryRecvError::Empty) => return,
+      Err(oneshot::error::TryRecvError::Closed) => {
+        self.error = Some("Computing correlations was interrupted.".to_string())
+      },
+    }
+    self.correlations_rx = None;
+    self.dirty = true;
+  }
+
+  fn summary(&self) -> String {
+    let limit = &self.config.correlation_limit;
+    let window = format!("Returns of the last {} candles.", limit.window);
+    match limit.is_enabled() {
+      true => format!(
+        "{window} Entries correlated above {:.2} with an open position are rejected.",
+        limit.max_correlation
+      ),
+      false => window,
+    }
+  }
+}
+
+impl Screen for Correlations {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let (correlations_tx, correlations_rx) = oneshot::channel();
+    let window = self.config.correlation_limit.window;
+    tokio::spawn(async move {
+      let _ = correlations_tx.send(rolling_correlations(window).await);
+    });
+    self.correlations_rx = Some(correlations_rx);
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Tick => self.receive_correlations(),
+      Action::Accept => {
+        if let Some(command_tx) = &self.command_tx {
+          command_tx.send(Action::NavigateBack)?;
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    self.dirty = false;
+    let content_layout = Layout::default()
+      .constraints(vec![Constraint::Length(2), Constraint::Min(0), Constraint::Length(3)])
+      .split(area);
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Percentage(40),
+    ])
+    .split(content_layout[2]);
+    match (&self.correlations, &self.error) {
+      (_, Some(error)) => {
+        f.render_widget(Paragraph::new(format!("Error: {error}")), content_layout[0])
+      },
+      (Some(correlations), None) => {
+        f.render_widget(Paragraph::new(self.summary()), content_layout[0]);
+        f.render_widget(CorrelationHeatmapWidget::new(correlations), content_layout[1]);
+      },
+      (None, None) => {
+        f.render_widget(Paragraph::new("Computing correlations..."), content_layout[0])
+      },
+    }
+    f.render_widget(button("Back", true), button_layout[1]);
+    Ok(())
+  }
+
+  fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+}
@@ -0,0 +1,220 @@
+use super::Screen;
+use crate::{
+  action::{Action, MoveDirection},
+  components::{
+    glyphs::glyphs,
+    list::List,
+    style::{button, default_style, theme},
+    ListDisplay,
+  },
+  diagnostics::{self, Percentiles, Stage, Task, TaskMetrics},
+  paths,
+};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct DiagnosticsRow {
+  task: Task,
+  metrics: TaskMetrics,
+  is_bottleneck: bool,
+}
+
+fn row_layout(area: Rect) -> std::rc::Rc<[Rect]> {
+  Layout::horizontal(vec![
+    Constraint::Percentage(16),
+    Constraint::Percentage(12),
+    Constraint::Percentage(12),
+    Constraint::Percentage(12),
+    Constraint::Percentage(12),
+    Constraint::Percentage(12),
+    Constraint::Percentage(12),
+    Constraint::Percentage(12),
+  ])
+  .split(area)
+}
+
+fn format_duration(duration: Duration) -> String {
+  if duration < Duration::from_millis(1) {
+    format!("{}{}s", duration.as_micros(), glyphs().micro)
+  } else {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+  }
+}
+
+impl ListDisplay for DiagnosticsRow {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    let area = row_layout(area);
+    let metrics = &self.metrics;
+    let cells = [
+      self.task.to_string(),
+      metrics.iterations.to_string(),
+      format_duration(metrics.mean_latency()),
+      format_duration(metrics.max_latency),
+      metrics.queue_depth.to_string(),
+      metrics.max_queue_depth.to_string(),
+      format_duration(metrics.mean_lock_wait()),
+      format_duration(metrics.max_lock_wait),
+    ];
+    let style = if self.is_bottleneck {
+      default_style(active).fg(theme().warning)
+    } else {
+      default_style(active)
+    };
+    for (index, cell) in cells.into_iter().enumerate() {
+      f.render_widget(Paragraph::new(cell).style(style), area[index]);
+    }
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let area = row_layout(area);
+    let titles = [
+      "Task",
+      "Iterations",
+      "Latency",
+      "Max latency",
+      "Queue",
+      "Max queue",
+      "Lock wait",
+      "Max wait",
+    ];
+    for (index, title) in titles.into_iter().enumerate() {
+      f.render_widget(Paragraph::new(title).style(default_style(false)), area[index]);
+    }
+    Ok(())
+  }
+}
+
+/// Latency of a stage of acting on a closed candle.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct LatencyRow {
+  stage: Stage,
+  percentiles: Percentiles,
+}
+
+fn latency_layout(area: Rect) -> std::rc::Rc<[Rect]> {
+  Layout::horizontal(vec![
+    Constraint::Percentage(28),
+    Constraint::Percentage(24),
+    Constraint::Percentage(24),
+    Constraint::Percentage(24),
+  ])
+  .split(area)
+}
+
+impl ListDisplay for LatencyRow {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    let area = latency_layout(area);
+    let cells = [
+      self.stage.to_string(),
+      self.percentiles.samples.to_string(),
+      format_duration(self.percentiles.p50),
+      format_duration(self.percentiles.p95),
+    ];
+    for (index, cell) in cells.into_iter().enumerate() {
+      f.render_widget(Paragraph::new(cell).style(default_style(active)), area[index]);
+    }
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let area = latency_layout(area);
+    let titles = ["Since candle close", "Samples", "p50", "p95"];
+    for (index, title) in titles.into_iter().enumerate() {
+      f.render_widget(Paragraph::new(title).style(default_style(false)), area[index]);
+    }
+    Ok(())
+  }
+}
+
+/// Event loop latency, queue depths and lock waits of the pipeline, with the task it
+/// backs up at highlighted, and the latency of live trades from the candle close.
+#[derive(Default)]
+pub struct Diagnostics {
+  command_tx: Option<UnboundedSender<Action>>,
+  rows: List<DiagnosticsRow>,
+  latency: List<LatencyRow>,
+  bottleneck: Option<Task>,
+}
+
+impl Diagnostics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl Screen for Diagnostics {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Tick => {
+        let snapshot = diagnostics::snapshot();
+        self.bottleneck = diagnostics::bottleneck(&snapshot);
+        let rows = snapshot
+          .into_iter()
+          .map(|(task, metrics)| DiagnosticsRow {
+            task,
+            metrics,
+            is_bottleneck: self.bottleneck == Some(task),
+          })
+          .collect();
+        self.rows.update_items(rows);
+        let latency = diagnostics::stage_latencies()
+          .into_iter()
+          .map(|(stage, percentiles)| LatencyRow { stage, percentiles })
+          .collect();
+        self.latency.update_items(latency);
+      },
+      Action::Move(direction) => match direction {
+        MoveDirection::Up => self.rows.previous(),
+        MoveDirection::Down => self.rows.next(),
+        _ => {},
+      },
+      Action::Accept => {
+        if let Some(command_tx) = &self.command_tx {
+          command_tx.send(Action::NavigateBack)?;
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let content_layout = Layout::default()
+      .constraints(vec![
+        Constraint::Length(2),
+        Constraint::Min(0),
+        Constraint::Length(9),
+        Constraint::Length(2),
+        Constraint::Length(3),
+      ])
+      .split(area);
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Percentage(40),
+    ])
+    .split(content_layout[4]);
+    let status = match self.bottleneck {
+      Some(task) => format!("Pipeline backs up at: {}", task),
+      None => "No backlog".to_string(),
+    };
+    f.render_widget(Paragraph::new(status), content_layout[0]);
+    self.rows.draw(f, content_layout[1])?;
+    let latency_block =
+      Block::default().borders(Borders::TOP).title("Live trade latency");
+    let latency_area = latency_block.inner(content_layout[2]);
+    f.render_widget(latency_block, content_layout[2]);
+    self.latency.draw(f, latency_area)?;
+    let logs = format!("Logs are in {}", paths::logs_dir().display());
+    f.render_widget(Paragraph::new(logs).style(Style::new().dim()), content_layout[3]);
+    f.render_widget(button("Back", true), button_layout[1]);
+    Ok(())
+  }
+}
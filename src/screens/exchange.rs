@@ -1,37 +1,282 @@
 use super::{Screen, ScreenId};
 use crate::{
-  action::{Action, MoveDirection, ScreenUpdate},
+  action::{Action, MoveDirection},
+  assets::Asset,
   components::{
-    list::{LabelValueItem, List},
-    style::{button, default_layout, outer_container_block, stylized_block},
+    glyphs::glyphs,
+    style::{
+      button, centered_rect, default_layout, outer_container_block, stylized_block, theme,
+    },
+    table::{Cell, Column, Table, TableRow},
   },
   config::{Config, KeyBindings},
-  database::{error::DatabaseError, Database},
+  database::{error::DatabaseError, snapshot::DatabaseSnapshot, Database},
+  exchange::{
+    account::AccountName,
+    convert::{ConvertQuote, DUST_TARGET},
+    transfer::{Transfer, TransferRecord},
+  },
+  portfolio::balance::Balance,
   statistic::TradingSummary,
+  utils::{
+    formatting::{dt_to_readable, format_amount, format_number},
+    load_config::read_config,
+  },
 };
+use chrono::Utc;
 use crossterm::event::{KeyCode, KeyEvent};
 use eyre::Result;
 use ratatui::{prelude::*, widgets::*};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
 use tokio::sync::{
   mpsc::{UnboundedReceiver, UnboundedSender},
-  Mutex,
+  watch, Mutex,
 };
 use uuid::Uuid;
 
+/// Balance of an asset on the exchange, as a row of the balances table.
+pub struct BalanceRow {
+  asset: String,
+  balance: Balance,
+}
+
+impl BalanceRow {
+  fn format(&self, amount: f64) -> String {
+    match Asset::from_str(&self.asset) {
+      Ok(asset) => format_amount(amount, asset),
+      Err(_) => format_number(amount),
+    }
+  }
+}
+
+impl TableRow for BalanceRow {
+  fn columns() -> Vec<Column> {
+    vec![
+      Column::new("Asset", Constraint::Percentage(30)),
+      Column::new("Total", Constraint::Percentage(35)).right(),
+      Column::new("Available", Constraint::Percentage(35)).right(),
+    ]
+  }
+
+  fn cells(&self) -> Vec<Cell> {
+    vec![
+      self.asset.clone().into(),
+      self.format(self.balance.total).into(),
+      self.format(self.balance.available).into(),
+    ]
+  }
+}
+
+/// Transfer between accounts, as a row of the transfers table.
+pub struct TransferRow(TransferRecord);
+
+impl TableRow for TransferRow {
+  fn columns() -> Vec<Column> {
+    vec![
+      Column::new("Time", Constraint::Length(13)),
+      Column::new("From", Constraint::Percentage(20)),
+      Column::new("To", Constraint::Percentage(20)),
+      Column::new("Amount", Constraint::Percentage(25)).right(),
+      Column::new("Status", Constraint::Fill(1)),
+    ]
+  }
+
+  fn cells(&self) -> Vec<Cell> {
+    let transfer = &self.0;
+    vec![
+      dt_to_readable(transfer.time).into(),
+      transfer.from.to_string().into(),
+      transfer.to.to_string().into(),
+      format!("{} {}", format_number(transfer.amount), transfer.asset).into(),
+      transfer.status.clone().into(),
+    ]
+  }
+}
+
+/// Transfer of the selected asset out of the listed account, being entered.
+struct TransferForm {
+  asset: String,
+  available: f64,
+  to: AccountName,
+  amount: String,
+  /// Waits for `y` to send it.
+  confirming: bool,
+}
+
+impl TransferForm {
+  /// A positive amount within the available balance.
+  fn amount(&self) -> Option<f64> {
+    self.amount.parse().ok().filter(|amount| *amount > 0.0 && *amount <= self.available)
+  }
+}
+
+/// Conversion of the small balances of the listed account to [DUST_TARGET].
+enum DustConversion {
+  Quoting,
+  /// Waits for `y` to accept the quotes.
+  Quoted(Vec<ConvertQuote>),
+}
+
 #[derive(Default)]
 pub struct Exchange {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
-  balances_list: List<LabelValueItem<f64>>,
+  snapshot: Option<watch::Receiver<DatabaseSnapshot>>,
+  balances_list: Table<BalanceRow>,
   selected_action: usize,
+  /// Whose balances are listed.
+  account: AccountName,
+  /// Accounts with balances from the exchange.
+  accounts: Vec<AccountName>,
+  transfer: Option<TransferForm>,
+  transfers_list: Table<TransferRow>,
+  dust: Option<DustConversion>,
 }
 
 impl Exchange {
   pub fn new() -> Self {
     Self { ..Self::default() }
   }
+
+  fn start_transfer(&mut self) {
+    let Some(row) = self.balances_list.selected() else {
+      return;
+    };
+    let Some(to) = self.accounts.iter().find(|account| **account != self.account) else {
+      return;
+    };
+    self.transfer = Some(TransferForm {
+      asset: row.asset.clone(),
+      available: row.balance.available,
+      to: to.clone(),
+      amount: String::new(),
+      confirming: false,
+    });
+  }
+
+  /// Moves the destination of the transfer to the next or previous other account.
+  fn step_destination(&mut self, step: isize) {
+    let Some(transfer) = &mut self.transfer else {
+      return;
+    };
+    let others: Vec<&AccountName> =
+      self.accounts.iter().filter(|account| **account != self.account).collect();
+    if let Some(index) = others.iter().position(|account| **account == transfer.to) {
+      let next = (index as isize + step).rem_euclid(others.len() as isize) as usize;
+      transfer.to = others[next].clone();
+    }
+  }
+
+  /// Asks for confirmation of a valid transfer, sends a confirmed one.
+  fn accept_transfer(&mut self) -> Option<Action> {
+    let transfer = self.transfer.as_mut()?;
+    let amount = transfer.amount()?;
+    if !transfer.confirming {
+      transfer.confirming = true;
+      return None;
+    }
+    let transfer = self.transfer.take()?;
+    Some(Action::Transfer(Transfer {
+      from: self.account.clone(),
+      to: transfer.to,
+      asset: transfer.asset,
+      amount,
+    }))
+  }
+
+  /// Converts at the quotes, or quotes again once they expired.
+  fn accept_dust(&mut self) -> Option<Action> {
+    let Some(DustConversion::Quoted(quotes)) = &self.dust else {
+      return None;
+    };
+    if quotes.is_empty() {
+      return None;
+    }
+    if quotes.iter().any(|quote| quote.is_expired(Utc::now())) {
+      self.dust = Some(DustConversion::Quoting);
+      return Some(Action::QuoteDust(self.account.clone()));
+    }
+    let quotes = quotes.clone();
+    self.dust = None;
+    Some(Action::ConvertDust(self.account.clone(), quotes))
+  }
+
+  fn draw_dust(&self, f: &mut Frame<'_>, area: Rect) {
+    let Some(dust) = &self.dust else {
+      return;
+    };
+    let threshold = format_number(self.config.dust_threshold) + " " + DUST_TARGET;
+    let lines = match dust {
+      DustConversion::Quoting => {
+        let quoting = format!("Quoting balances under {}...", threshold);
+        vec![Line::from(quoting), "n cancel".into()]
+      },
+      DustConversion::Quoted(quotes) if quotes.is_empty() => {
+        vec![Line::from(format!("No balances under {}", threshold)), "n close".into()]
+      },
+      DustConversion::Quoted(quotes) => {
+        let total: f64 = quotes.iter().map(|quote| quote.to_amount).sum();
+        let expired = quotes.iter().any(|quote| quote.is_expired(Utc::now()));
+        let mut lines = vec![Line::from(format!("Convert balances under {}", threshold))];
+        lines.extend(quotes.iter().map(|quote| Line::from(quote.to_string())));
+        lines.push(Line::from(format!("Total {} {}", format_number(total), DUST_TARGET)));
+        lines.push(if expired {
+          Line::from("Quotes expired, y quote again, n cancel")
+            .style(Style::default().fg(theme().text_critical))
+        } else {
+          Line::from("y convert, n cancel")
+        });
+        lines
+      },
+    };
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let modal = centered_rect(area.width.min(56), height, area);
+    f.render_widget(Clear, modal);
+    f.render_widget(
+      Paragraph::new(lines).alignment(Alignment::Center).block(stylized_block(true)),
+      modal,
+    );
+  }
+
+  fn draw_transfer(&self, f: &mut Frame<'_>, area: Rect) {
+    let Some(transfer) = &self.transfer else {
+      return;
+    };
+    let modal = centered_rect(area.width.min(56), area.height.min(7), area);
+    f.render_widget(Clear, modal);
+    let text = match transfer.amount() {
+      Some(amount) if transfer.confirming => {
+        let transfer = Transfer {
+          from: self.account.clone(),
+          to: transfer.to.clone(),
+          asset: transfer.asset.clone(),
+          amount,
+        };
+        Text::from(format!("Transfer {}?\ny confirm, n cancel", transfer))
+      },
+      amount => {
+        let mut lines = vec![
+          Line::from(format!("Transfer {} from {}", transfer.asset, self.account)),
+          Line::from(format!("to {} ({} account)", transfer.to, glyphs().left_right)),
+          Line::from(format!("Amount: {}_", transfer.amount)),
+        ];
+        if amount.is_none() && !transfer.amount.is_empty() {
+          let available = format_number(transfer.available);
+          lines.push(
+            Line::from(format!("Up to the available {}", available))
+              .style(Style::default().fg(theme().text_critical)),
+          );
+        }
+        lines.push(Line::from("Enter review, n cancel"));
+        Text::from(lines)
+      },
+    };
+    f.render_widget(
+      Paragraph::new(text).alignment(Alignment::Center).block(stylized_block(true)),
+      modal,
+    );
+  }
 }
 
 impl Screen for Exchange {
@@ -45,17 +290,105 @@ impl Screen for Exchange {
     Ok(())
   }
 
+  fn register_snapshot_handler(
+    &mut self,
+    snapshot: watch::Receiver<DatabaseSnapshot>,
+  ) -> Result<()> {
+    self.snapshot = Some(snapshot);
+    Ok(())
+  }
+
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let has_accounts = read_config().is_ok_and(|config| config.accounts.len() > 1);
+    if let Some(command_tx) = self.command_tx.as_ref().filter(|_| has_accounts) {
+      command_tx.send(Action::QueryTransfers)?;
+    }
+    Ok(())
+  }
+
+  fn captures_text(&self) -> bool {
+    self.transfer.is_some() || self.dust.is_some()
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if self.dust.is_some() {
+      match key.code {
+        KeyCode::Char('y') => return Ok(self.accept_dust()),
+        KeyCode::Char('n') => self.dust = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
+    if let Some(transfer) = &mut self.transfer {
+      match key.code {
+        KeyCode::Char('y') if transfer.confirming => return Ok(self.accept_transfer()),
+        KeyCode::Char('n') if transfer.confirming => transfer.confirming = false,
+        KeyCode::Char('n') => self.transfer = None,
+        KeyCode::Char(c) if !transfer.confirming && (c.is_ascii_digit() || c == '.') => {
+          transfer.amount.push(c)
+        },
+        KeyCode::Backspace if !transfer.confirming => {
+          transfer.amount.pop();
+        },
+        _ => {},
+      }
+      return Ok(None);
+    }
+    match key.code {
+      KeyCode::Char('a') => {
+        let index = self.accounts.iter().position(|account| *account == self.account);
+        let next = index.map_or(0, |index| (index + 1) % self.accounts.len());
+        if let Some(account) = self.accounts.get(next) {
+          self.account = account.clone();
+        }
+      },
+      KeyCode::Char('t') => self.start_transfer(),
+      KeyCode::Char('c') => {
+        self.dust = Some(DustConversion::Quoting);
+        return Ok(Some(Action::QuoteDust(self.account.clone())));
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
-      Action::Tick => {},
+      Action::Tick => {
+        if let Some(snapshot) = &self.snapshot {
+          let snapshot = snapshot.borrow();
+          self.accounts = snapshot.exchange_accounts();
+          let balances = snapshot.sorted_exchange_balances(&self.account);
+          self.balances_list.update_items(
+            balances
+              .into_iter()
+              .map(|(asset, balance)| BalanceRow { asset, balance })
+              .collect(),
+          );
+        }
+      },
+      Action::Transfers(transfers) => {
+        self.transfers_list.update_items(transfers.into_iter().map(TransferRow).collect())
+      },
+      Action::DustQuotes(account, quotes)
+        if account == self.account && self.dust.is_some() =>
+      {
+        self.dust = Some(DustConversion::Quoted(quotes))
+      },
+      Action::Accept if self.transfer.is_some() => return Ok(self.accept_transfer()),
+      Action::Accept if self.dust.is_some() => return Ok(self.accept_dust()),
       Action::Accept => {
         if let Some(command_tx) = &self.command_tx {
-          command_tx.send(Action::Navigate(ScreenId::HOME))?;
+          command_tx.send(Action::NavigateBack)?;
         }
       },
-      Action::ScreenUpdate(update) => match update {
-        _ => {},
+      Action::Move(MoveDirection::Left) if self.transfer.is_some() => {
+        self.step_destination(-1)
       },
+      Action::Move(MoveDirection::Right) if self.transfer.is_some() => {
+        self.step_destination(1)
+      },
+      Action::Move(_) if self.transfer.is_some() || self.dust.is_some() => {},
       Action::Move(direction) => match direction {
         MoveDirection::Up => self.balances_list.previous(),
         MoveDirection::Down => self.balances_list.next(),
@@ -79,14 +412,112 @@ impl Screen for Exchange {
       Constraint::Percentage(30),
     ])
     .split(content_layout[2]);
-    f.render_widget(
-      Paragraph::new("Report was generated in summary.html"),
-      content_layout[0],
-    );
+    let heading = if self.accounts.len() > 1 {
+      format!("Balances of {}, a next account, t transfer, c convert dust", self.account)
+    } else {
+      format!("Balances of {}, c convert dust", self.account)
+    };
+    f.render_widget(Paragraph::new(heading), content_layout[0]);
 
-    self.balances_list.draw(f, content_layout[0])?;
+    if self.transfers_list.is_empty() {
+      self.balances_list.draw(f, content_layout[1])?;
+    } else {
+      let lists_layout = Layout::vertical(vec![
+        Constraint::Fill(3),
+        Constraint::Length(1),
+        Constraint::Fill(2),
+      ])
+      .split(content_layout[1]);
+      self.balances_list.draw(f, lists_layout[0])?;
+      f.render_widget(
+        Paragraph::new("Transfers").style(Style::default().fg(theme().text_dimmed)),
+        lists_layout[1],
+      );
+      self.transfers_list.draw(f, lists_layout[2])?;
+    }
     f.render_widget(button("Back", true), button_layout[1]);
     f.render_widget(button("1000 USDT", true), button_layout[3]);
+    self.draw_transfer(f, area);
+    self.draw_dust(f, area);
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::{render, snapshot};
+  use chrono::DateTime;
+  use crossterm::event::KeyModifiers;
+
+  fn key(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+  }
+
+  #[test]
+  fn exchange_transfer() {
+    let grid = AccountName("grid".to_string());
+    let usdt = Balance { time: DateTime::UNIX_EPOCH, total: 500.0, available: 400.0 };
+    let mut snapshot = snapshot();
+    let balances = [("USDT".to_string(), usdt)].into();
+    snapshot.exchange_balances.insert(AccountName::default(), balances);
+    snapshot.exchange_balances.insert(grid.clone(), Default::default());
+    let mut exchange = Exchange::new();
+    exchange.register_snapshot_handler(watch::channel(snapshot).1).unwrap();
+    exchange.update(Action::Tick).unwrap();
+    exchange
+      .update(Action::Transfers(vec![TransferRecord {
+        id: 7,
+        time: DateTime::UNIX_EPOCH,
+        from: grid.clone(),
+        to: AccountName::default(),
+        asset: "USDT".to_string(),
+        amount: 50.0,
+        status: "SUCCESS".to_string(),
+      }]))
+      .unwrap();
+
+    exchange.handle_key_events(key('t')).unwrap();
+    for c in "100".chars() {
+      exchange.handle_key_events(key(c)).unwrap();
+    }
+    assert!(exchange.captures_text());
+    assert_eq!(exchange.update(Action::Accept).unwrap(), None);
+    insta::assert_snapshot!(render(&mut exchange, 80, 30));
+
+    let transfer = Transfer {
+      from: AccountName::default(),
+      to: grid,
+      asset: "USDT".to_string(),
+      amount: 100.0,
+    };
+    let confirm = exchange.handle_key_events(key('y')).unwrap();
+    assert_eq!(confirm, Some(Action::Transfer(transfer)));
+    assert!(!exchange.captures_text());
+  }
+
+  #[test]
+  fn exchange_convert_dust() {
+    let mut exchange = Exchange::new();
+    let config = Config { dust_threshold: 10.0, ..Config::default() };
+    exchange.register_config_handler(config).unwrap();
+    let quote = |asset: &str, from_amount, to_amount| ConvertQuote {
+      quote_id: format!("{asset}-quote"),
+      from_asset: asset.to_string(),
+      from_amount,
+      to_asset: DUST_TARGET.to_string(),
+      to_amount,
+      valid_until: DateTime::<Utc>::MAX_UTC,
+    };
+    let quotes = vec![quote("BNB", 0.01, 5.8), quote("DOGE", 12.0, 1.9)];
+
+    let quote_dust = exchange.handle_key_events(key('c')).unwrap();
+    assert_eq!(quote_dust, Some(Action::QuoteDust(AccountName::default())));
+    exchange.update(Action::DustQuotes(AccountName::default(), quotes.clone())).unwrap();
+    insta::assert_snapshot!(render(&mut exchange, 80, 30));
+
+    let convert = exchange.handle_key_events(key('y')).unwrap();
+    assert_eq!(convert, Some(Action::ConvertDust(AccountName::default(), quotes)));
+    assert!(!exchange.captures_text());
+  }
+}
@@ -0,0 +1,92 @@
+use super::Screen;
+use crate::{
+  action::Action,
+  assets::Pair,
+  database::snapshot::DatabaseSnapshot,
+  exchange::account::AccountName,
+  portfolio::{balance::Balance, fixtures::fill_of, position::Position},
+  statistic::{StatisticConfig, TradingSummary},
+  strategy::Decision,
+  trading::execution::Fees,
+};
+use chrono::{DateTime, Duration, Utc};
+use ratatui::{backend::TestBackend, Terminal};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Session of the fixture snapshot, fixed so it reads the same in every snapshot.
+pub const CORE_ID: Uuid = Uuid::from_u128(0x6d657368_6574_6172_0000_000000000001);
+
+const STARTING_EQUITY: f64 = 1000.0;
+const FEES: Fees = Fees { exchange: 0.001, slippage: 0.0 };
+
+/// Renders the screen after a tick into a terminal of the size, for snapshotting its
+/// buffer.
+pub fn render(screen: &mut dyn Screen, width: u16, height: u16) -> TestBackend {
+  screen.update(Action::Tick).unwrap();
+  let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+  terminal.draw(|f| screen.draw(f, f.size()).unwrap()).unwrap();
+  terminal.backend().clone()
+}
+
+/// Receiver of the fixture snapshot, for `register_snapshot_handler`.
+pub fn snapshot_rx() -> watch::Receiver<DatabaseSnapshot> {
+  watch::channel(snapshot()).1
+}
+
+/// A BTCUSDT session that won one trade, lost one and holds a long position.
+pub fn snapshot() -> DatabaseSnapshot {
+  let start = time(0);
+  let exited = vec![
+    round_trip(0.01, 40_000.0, 42_000.0, 1),
+    round_trip(0.01, 42_000.0, 41_000.0, 3),
+  ];
+  let enter = fill_of(Pair::BTCUSDT, Decision::Long, 0.02, 41_500.0, FEES, time(5));
+  let mut open = Position::enter(CORE_ID, &enter).unwrap();
+  open.unrealised_profit_loss = 4.2;
+
+  let config = StatisticConfig {
+    starting_equity: STARTING_EQUITY,
+    trading_days_per_year: 365,
+    risk_free_return: 0.0,
+    created_at: start,
+    reporting_currency: Default::default(),
+  };
+  let mut statistics = TradingSummary::init(config, Some(start));
+  statistics.generate_summary(&exited);
+  let total = STARTING_EQUITY
+    + exited.iter().map(|position| position.realised_profit_loss).sum::<f64>();
+
+  let mut snapshot = DatabaseSnapshot::default();
+  snapshot.balances.insert(CORE_ID, balance(total));
+  snapshot.starting_balances.insert(CORE_ID, balance(STARTING_EQUITY));
+  snapshot.open_positions.insert(open.position_id.clone(), open);
+  snapshot.exited_positions.insert(CORE_ID, exited);
+  snapshot.pair_statistics.insert((CORE_ID, Pair::BTCUSDT), statistics.clone());
+  snapshot.statistics.insert(CORE_ID, statistics);
+  snapshot.summary_paths.insert(CORE_ID, "summary.html".into());
+  snapshot.session_accounts.insert(CORE_ID, AccountName::default());
+  snapshot
+}
+
+/// Hours after the start of the fixture session.
+fn time(hours: i64) -> DateTime<Utc> {
+  DateTime::UNIX_EPOCH
+    + Duration::try_days(19_800).unwrap()
+    + Duration::try_hours(hours).unwrap()
+}
+
+fn balance(total: f64) -> Balance {
+  Balance { time: time(0), total, available: total }
+}
+
+/// Long of `quantity` held for an hour.
+fn round_trip(quantity: f64, enter_price: f64, exit_price: f64, hours: i64) -> Position {
+  let pair = Pair::BTCUSDT;
+  let enter = fill_of(pair, Decision::Long, quantity, enter_price, FEES, time(hours));
+  let mut position = Position::enter(CORE_ID, &enter).unwrap();
+  let exit_time = time(hours + 1);
+  let exit = fill_of(pair, Decision::CloseLong, -quantity, exit_price, FEES, exit_time);
+  position.exit(balance(STARTING_EQUITY), &exit).unwrap();
+  position
+}
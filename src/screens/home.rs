@@ -2,7 +2,8 @@ use super::{Screen, ScreenId};
 use crate::{
   action::{Action, MoveDirection},
   components::style::{
-    default_layout, header_style, outer_container_block, stylized_block, stylized_button,
+    button_style, centered_rect, default_layout, header_style, outer_container_block,
+    stylized_block, stylized_button,
   },
   config::{Config, KeyBindings},
 };
@@ -22,18 +23,29 @@ pub struct Home {
   selected_action: usize,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(EnumIter, EnumString, EnumCount, Display)]
 enum HomeAction {
   RUN,
+  MARKETS,
   MODELS,
   SESSIONS,
+  ANALYTICS,
+  CORRELATIONS,
+  DIAGNOSTICS,
+  SETTINGS,
 }
 impl HomeAction {
   fn to_screen_id(&self) -> ScreenId {
     match self {
       Self::RUN => ScreenId::RUNCONFIG,
+      Self::MARKETS => ScreenId::MARKETS,
       Self::MODELS => ScreenId::MODELS,
       Self::SESSIONS => ScreenId::SESSIONS,
+      Self::ANALYTICS => ScreenId::ANALYTICS,
+      Self::CORRELATIONS => ScreenId::CORRELATIONS,
+      Self::DIAGNOSTICS => ScreenId::DIAGNOSTICS,
+      Self::SETTINGS => ScreenId::SETTINGS,
     }
   }
 }
@@ -77,38 +89,51 @@ impl Screen for Home {
   }
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
-    let layout = Layout::default()
-      .constraints(vec![
-        Constraint::Percentage(10),
-        Constraint::Length(4),
-        Constraint::Length(4),
-        Constraint::Length(4),
-        Constraint::Percentage(10),
-      ])
+    // Bordered buttons with a gap below, without the gap or the borders on short
+    // terminals.
+    let row_height = (area.height / HomeAction::COUNT as u16).min(4);
+    let layout = Layout::vertical(vec![Constraint::Length(row_height); HomeAction::COUNT])
+      .flex(layout::Flex::Center)
       .split(area);
 
     for (index, action) in HomeAction::iter().enumerate() {
-      let inner_area = Layout::default()
-        .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
-        .split(layout[index + 1]);
-      let inner_area = inner_area[0];
-      let button_width = 20;
-      let padding = (inner_area.width - button_width) / 2;
-      let inner_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(vec![
-          Constraint::Length(padding),
-          Constraint::Length(button_width),
-          Constraint::Min(padding),
-        ])
-        .split(inner_area);
       let is_selected = index == self.selected_action;
-      let button = Paragraph::new(action.to_string())
-        .alignment(Alignment::Center)
-        .block(stylized_button(is_selected));
-      f.render_widget(button, inner_layout[1]);
+      let button = Paragraph::new(action.to_string()).alignment(Alignment::Center);
+      let button = if row_height >= 3 {
+        button.block(stylized_button(is_selected))
+      } else {
+        button.style(button_style(is_selected))
+      };
+      f.render_widget(button, centered_rect(20, row_height.min(3), layout[index]));
     }
 
     Ok(())
   }
+
+  fn is_dirty(&self) -> bool {
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::render;
+
+  #[test]
+  fn home() {
+    insta::assert_snapshot!(render(&mut Home::default(), 80, 30));
+  }
+
+  #[test]
+  fn home_short() {
+    insta::assert_snapshot!(render(&mut Home::default(), 60, 14));
+  }
+
+  #[test]
+  fn home_with_selection() {
+    let mut home = Home::default();
+    home.update(Action::Move(MoveDirection::Down)).unwrap();
+    insta::assert_snapshot!(render(&mut home, 80, 30));
+  }
 }
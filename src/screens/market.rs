@@ -0,0 +1,275 @@
+use super::{Screen, ScreenId};
+use crate::{
+  action::{Action, MoveDirection},
+  components::{
+    list::List,
+    sparkline::MiniChart,
+    style::{button, default_style, theme},
+    ListDisplay,
+  },
+  assets::Pair,
+  config::Config,
+  database::{fetch_derivative_series, DerivativeSeries},
+  exchange::{
+    binance_client::BinanceClient,
+    market::{fetch_sparkline, fetch_tickers_24h, Ticker24h},
+  },
+  utils::formatting::{format_compact, format_number},
+};
+use chrono::{Duration, Utc};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use std::str::FromStr;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::warn;
+
+/// How often tickers are refetched while the screen is open.
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+/// Days of stored funding rates and open interest plotted under the tickers.
+const DERIVATIVES_DAYS: i64 = 3;
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct TickerRow {
+  ticker: Ticker24h,
+  closes: Vec<f64>,
+  /// Stored funding rates of the perpetual future of the pair, oldest first.
+  funding_rates: Vec<f64>,
+  /// Stored open interest of the perpetual future of the pair, oldest first.
+  open_interest: Vec<f64>,
+}
+
+impl TickerRow {
+  fn has_derivatives(&self) -> bool {
+    !self.funding_rates.is_empty() || !self.open_interest.is_empty()
+  }
+}
+
+fn row_layout(area: Rect) -> std::rc::Rc<[Rect]> {
+  Layout::horizontal(vec![
+    Constraint::Percentage(15),
+    Constraint::Percentage(17),
+    Constraint::Percentage(12),
+    Constraint::Percentage(17),
+    Constraint::Percentage(39),
+  ])
+  .split(area)
+}
+
+impl ListDisplay for TickerRow {
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, active: bool) -> Result<()> {
+    let area = row_layout(area);
+    let change = self.ticker.price_change_percent;
+    let change_color = if change >= 0.0 { theme().positive } else { theme().negative };
+    let cells = [
+      Paragraph::new(self.ticker.symbol.clone()),
+      Paragraph::new(format_number(self.ticker.last_price)),
+      Paragraph::new(format!("{:+.2}%", change)),
+      Paragraph::new(format_compact(self.ticker.quote_volume)),
+    ];
+    for (index, cell) in cells.into_iter().enumerate() {
+      let style = match index {
+        2 => default_style(active).fg(change_color),
+        _ => default_style(active),
+      };
+      f.render_widget(cell.style(style), area[index]);
+    }
+    f.render_widget(
+      MiniChart::new(&self.closes)
+        .style(Style::default().fg(change_color).bg(theme().bg)),
+      area[4],
+    );
+    Ok(())
+  }
+  fn draw_header(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let area = row_layout(area);
+    for (index, title) in ["Pair", "Last price", "24h", "24h volume", "24h (1h closes)"]
+      .into_iter()
+      .enumerate()
+    {
+      f.render_widget(Paragraph::new(title).style(default_style(false)), area[index]);
+    }
+    Ok(())
+  }
+}
+
+fn fetch_rows(
+  binance_client: &BinanceClient,
+  symbols: &[String],
+) -> Result<Vec<TickerRow>, String> {
+  let tickers = fetch_tickers_24h(binance_client, symbols).map_err(|e| e.to_string())?;
+  Ok(
+    tickers
+      .into_iter()
+      .map(|ticker| {
+        let closes =
+          fetch_sparkline(binance_client, &ticker.symbol).unwrap_or_else(|e| {
+            warn!("Failed to fetch sparkline of {}: {}", ticker.symbol, e);
+            Vec::new()
+          });
+        TickerRow { ticker, closes, ..TickerRow::default() }
+      })
+      .collect(),
+  )
+}
+
+/// Adds the stored funding rates and open interest of the last days, of tickers that
+/// are pairs whose derivatives are ingested.
+async fn load_derivatives(row: &mut TickerRow) {
+  let Ok(pair) = Pair::from_str(&row.ticker.symbol) else {
+    return;
+  };
+  let until = Utc::now();
+  let from = until - Duration::try_days(DERIVATIVES_DAYS).unwrap_or_default();
+  for (series, values) in [
+    (DerivativeSeries::FundingRate, &mut row.funding_rates),
+    (DerivativeSeries::OpenInterest, &mut row.open_interest),
+  ] {
+    match fetch_derivative_series(series, pair, from, until).await {
+      Ok(stored) => *values = stored.into_iter().map(|(_, value)| value).collect(),
+      Err(e) => warn!("Failed to load {:?} of {}: {}", series, pair, e),
+    }
+  }
+}
+
+/// Funding rate and open interest of the selected pair, latest value above the chart.
+fn derivatives_panel(f: &mut Frame<'_>, area: Rect, row: &TickerRow) {
+  let layout =
+    Layout::horizontal(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+      .split(area);
+  let funding_rate =
+    row.funding_rates.last().map(|rate| format!("{:+.4}%", rate * 100.0));
+  let open_interest = row.open_interest.last().copied().map(format_compact);
+  let panels = [
+    ("Funding rate", &row.funding_rates, funding_rate),
+    ("Open interest", &row.open_interest, open_interest),
+  ];
+  for ((title, values, latest), area) in panels.into_iter().zip(layout.iter()) {
+    let area = Layout::vertical(vec![Constraint::Length(1), Constraint::Min(0)])
+      .split(area.inner(&Margin { horizontal: 1, vertical: 0 }));
+    f.render_widget(
+      Paragraph::new(format!("{title}: {}", latest.as_deref().unwrap_or("n/a")))
+        .style(Style::default().fg(theme().text_dimmed)),
+      area[0],
+    );
+    let chart = MiniChart::new(values).style(Style::default().fg(theme().brand));
+    f.render_widget(chart, area[1]);
+  }
+}
+
+/// Last price, 24h change and a day of hourly closes for the pairs listed under
+/// `market_overview` in the config. Refreshed from the REST API while open.
+#[derive(Default)]
+pub struct MarketOverview {
+  command_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  rows_rx: Option<UnboundedReceiver<Result<Vec<TickerRow>, String>>>,
+  tickers_list: List<TickerRow>,
+  is_loading: bool,
+  error: Option<String>,
+}
+
+impl MarketOverview {
+  pub fn new() -> Self {
+    Self { is_loading: true, ..Self::default() }
+  }
+}
+
+impl Screen for MarketOverview {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let (rows_tx, rows_rx) = mpsc::unbounded_channel();
+    let symbols = self.config.market_overview.clone();
+    tokio::spawn(async move {
+      let binance_client = match BinanceClient::new().await {
+        Ok(binance_client) => binance_client,
+        Err(e) => {
+          let _ = rows_tx.send(Err(e.to_string()));
+          return;
+        },
+      };
+      let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+      loop {
+        interval.tick().await;
+        let mut rows = fetch_rows(&binance_client, &symbols);
+        for row in rows.iter_mut().flatten() {
+          load_derivatives(row).await;
+        }
+        // Stops once the screen is navigated away from
+        if rows_tx.send(rows).is_err() {
+          break;
+        }
+      }
+    });
+    self.rows_rx = Some(rows_rx);
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Tick => {
+        if let Some(rows_rx) = &mut self.rows_rx {
+          while let Ok(rows) = rows_rx.try_recv() {
+            self.is_loading = false;
+            match rows {
+              Ok(rows) => {
+                self.tickers_list.update_items(rows);
+                self.error = None;
+              },
+              Err(e) => self.error = Some(e),
+            }
+          }
+        }
+      },
+      Action::Move(direction) => match direction {
+        MoveDirection::Up => self.tickers_list.previous(),
+        MoveDirection::Down => self.tickers_list.next(),
+        _ => {},
+      },
+      Action::Accept => {
+        if let Some(command_tx) = &self.command_tx {
+          command_tx.send(Action::NavigateBack)?;
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let content_layout = Layout::default()
+      .constraints(vec![Constraint::Length(2), Constraint::Min(0), Constraint::Length(3)])
+      .split(area);
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Percentage(40),
+    ])
+    .split(content_layout[2]);
+    let status = match (&self.error, self.is_loading) {
+      (Some(e), _) => format!("Failed to refresh tickers: {e}"),
+      (None, true) => "Loading tickers...".to_string(),
+      (None, false) => format!("Refreshed every {}s", REFRESH_INTERVAL.as_secs()),
+    };
+    f.render_widget(Paragraph::new(status), content_layout[0]);
+    match self.tickers_list.get_selected().filter(TickerRow::has_derivatives) {
+      Some(row) => {
+        let layout = Layout::vertical(vec![Constraint::Min(0), Constraint::Length(5)])
+          .split(content_layout[1]);
+        self.tickers_list.draw(f, layout[0])?;
+        derivatives_panel(f, layout[1], &row);
+      },
+      None => self.tickers_list.draw(f, content_layout[1])?,
+    }
+    f.render_widget(button("Back", true), button_layout[1]);
+    Ok(())
+  }
+}
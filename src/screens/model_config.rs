@@ -150,4 +150,8 @@ impl Screen for ModelConfig {
 
     Ok(())
   }
+
+  fn is_dirty(&self) -> bool {
+    false
+  }
 }
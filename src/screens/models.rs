@@ -3,10 +3,11 @@ use crate::{
   action::{Action, MoveDirection},
   assets::Pair,
   components::{
-    list::List,
     style::{button, default_layout, outer_container_block, stylized_block},
+    table::Table,
   },
   config::{Config, KeyBindings},
+  database::{error::DatabaseError, fetch_backtest_equity_by_model},
   strategy::{get_generated_models, ModelMetadata},
 };
 use chrono::{DateTime, Duration, Utc};
@@ -15,32 +16,37 @@ use eyre::Result;
 use ratatui::{prelude::*, widgets::*};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs, path::Path, str::FromStr};
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
 
-const SYNC_DURATION: Duration = Duration::milliseconds(500);
+type EquityByModel = HashMap<String, Vec<f64>>;
 
 #[derive(Default)]
 pub struct Models {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
   selected_action: usize,
-  last_sync: DateTime<Utc>,
-  model_list: List<ModelMetadata>,
+  model_list: Table<ModelMetadata>,
+  equity_rx: Option<oneshot::Receiver<Result<EquityByModel, DatabaseError>>>,
+  backtest_equity: HashMap<String, Vec<f64>>,
+  /// Backtest equity loaded since the last draw.
+  dirty: bool,
 }
 
 impl Models {
   pub fn new() -> Self {
-    let mut new_model = Self { last_sync: Utc::now(), ..Self::default() };
+    let mut new_model = Self::default();
     let _ = new_model.sync_with_fs();
     new_model
   }
 
   fn sync_with_fs(&mut self) -> Result<()> {
-    if self.last_sync + SYNC_DURATION < Utc::now() {
-      let metadata_list = get_generated_models()?;
-      let sorted_list = self.model_list.update_items(metadata_list);
-      self.last_sync = Utc::now();
+    let mut metadata_list = get_generated_models()?;
+    for metadata in metadata_list.iter_mut() {
+      if let Some(equity) = self.backtest_equity.get(metadata.name()) {
+        metadata.set_backtest_equity(equity.clone());
+      }
     }
+    self.model_list.update_items(metadata_list);
     Ok(())
   }
 }
@@ -56,19 +62,44 @@ impl Screen for Models {
     Ok(())
   }
 
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let (equity_tx, equity_rx) = oneshot::channel();
+    tokio::spawn(async move {
+      let _ = equity_tx.send(fetch_backtest_equity_by_model().await);
+    });
+    self.equity_rx = Some(equity_rx);
+    Ok(())
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
       Action::Tick => {
-        self.sync_with_fs()?;
+        if let Some(equity_rx) = &mut self.equity_rx {
+          match equity_rx.try_recv() {
+            Ok(Ok(backtest_equity)) => {
+              self.backtest_equity = backtest_equity;
+              self.equity_rx = None;
+              self.dirty = true;
+              self.sync_with_fs()?;
+            },
+            Ok(Err(e)) => {
+              log::warn!("Failed to load backtest equity: {}", e);
+              self.equity_rx = None;
+            },
+            Err(oneshot::error::TryRecvError::Closed) => self.equity_rx = None,
+            Err(oneshot::error::TryRecvError::Empty) => {},
+          }
+        }
       },
+      Action::ModelsChanged => self.sync_with_fs()?,
       Action::Accept => {
         if let Some(command_tx) = &self.command_tx {
-          let screen = if self.selected_action == 0 {
-            ScreenId::HOME
+          let action = if self.selected_action == 0 {
+            Action::NavigateBack
           } else {
-            ScreenId::MODELCONFIG
+            Action::Navigate(ScreenId::MODELCONFIG)
           };
-          command_tx.send(Action::Navigate(screen))?;
+          command_tx.send(action)?;
         }
       },
       Action::Move(direction) => match direction {
@@ -91,6 +122,7 @@ impl Screen for Models {
   }
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    self.dirty = false;
     let content_layout = Layout::default()
       .constraints(vec![Constraint::Min(0), Constraint::Length(3)])
       .split(area);
@@ -113,4 +145,40 @@ impl Screen for Models {
 
     Ok(())
   }
+
+  fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::render;
+  use serde_json::json;
+
+  /// Created a bit over two days ago, the list shows how long ago.
+  fn model(name: &str, is_finished: bool, error: &str) -> ModelMetadata {
+    let created_at = Utc::now() - Duration::try_minutes(60 * 51 + 30).unwrap();
+    serde_json::from_value(json!({
+      "created_at": created_at,
+      "pair": "BTCUSDT",
+      "is_finished": is_finished,
+      "error": error,
+      "name": name,
+      "uuid": "00000000-0000-0000-0000-000000000000",
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn models() {
+    let mut models = Models::default();
+    models.model_list.update_items(vec![
+      model("brave-otter", true, ""),
+      model("quiet-heron", true, "Not enough candles"),
+      model("swift-lynx", false, ""),
+    ]);
+    insta::assert_snapshot!(render(&mut models, 100, 20));
+  }
 }
@@ -1,22 +1,24 @@
 use super::{Screen, ScreenId};
 use crate::{
-  action::{Action, ScreenUpdate},
+  action::{Action, MoveDirection},
+  assets::Pair,
   components::{
     list::{LabelValueItem, List},
-    style::{button, default_layout, outer_container_block, stylized_block},
+    sparkline::MiniChart,
+    style::{button, default_layout, outer_container_block, stylized_block, theme},
   },
   config::{Config, KeyBindings},
-  database::{error::DatabaseError, Database},
+  database::{error::DatabaseError, snapshot::DatabaseSnapshot, Database},
   statistic::TradingSummary,
 };
 use crossterm::event::{KeyCode, KeyEvent};
 use eyre::Result;
 use ratatui::{prelude::*, widgets::*};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 use tokio::sync::{
   mpsc::{UnboundedReceiver, UnboundedSender},
-  Mutex,
+  watch, Mutex,
 };
 use uuid::Uuid;
 
@@ -24,8 +26,17 @@ use uuid::Uuid;
 pub struct Report {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
+  snapshot: Option<watch::Receiver<DatabaseSnapshot>>,
   short_report_list: Option<List<LabelValueItem<String>>>,
   core_id: Uuid,
+  summary_path: Option<PathBuf>,
+  show_summary_path: bool,
+  selected_action: usize,
+  /// Pairs of the session, with a tab each after the aggregate one.
+  pairs: Vec<Pair>,
+  /// 0 is the aggregate of every pair.
+  tab: usize,
+  drawdown_curve: Vec<f64>,
 }
 
 impl Report {
@@ -45,21 +56,77 @@ impl Screen for Report {
     Ok(())
   }
 
+  fn register_snapshot_handler(
+    &mut self,
+    snapshot: watch::Receiver<DatabaseSnapshot>,
+  ) -> Result<()> {
+    self.snapshot = Some(snapshot);
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if key.code == KeyCode::Tab {
+      self.tab = (self.tab + 1) % (self.pairs.len() + 1);
+    }
+    Ok(None)
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
-      Action::Tick => {},
-      Action::Accept => {
-        if let Some(command_tx) = &self.command_tx {
-          command_tx.send(Action::Navigate(ScreenId::SESSIONS))?;
+      Action::Tick => {
+        if let Some(snapshot) = &self.snapshot {
+          let snapshot = snapshot.borrow();
+          self.pairs = snapshot.report_pairs(&self.core_id);
+          let pair = self.tab.checked_sub(1).and_then(|i| self.pairs.get(i));
+          let overview = match pair {
+            Some(pair) => snapshot.pair_report_overview(&self.core_id, pair),
+            None => snapshot.report_overview(&self.core_id),
+          };
+          self.drawdown_curve = snapshot.drawdown_curve(&self.core_id, pair);
+          if let Some(overview) = overview {
+            self
+              .short_report_list
+              .get_or_insert_with(List::default)
+              .update_items(overview);
+          }
+          self.summary_path = snapshot.summary_paths.get(&self.core_id).cloned();
         }
       },
-      Action::ScreenUpdate(update) => match update {
-        ScreenUpdate::Report(report) => {
-          // let mut list = List::default();
-          // list.update_items(report.g());
-          // self.short_report_list = Some(list)
+      Action::Move(direction) => match direction {
+        MoveDirection::Up => {
+          if let Some(list) = self.short_report_list.as_mut() {
+            list.previous()
+          }
+        },
+        MoveDirection::Down => {
+          if let Some(list) = self.short_report_list.as_mut() {
+            list.next()
+          }
+        },
+        MoveDirection::Left => {
+          self.selected_action = self.selected_action.saturating_sub(1)
+        },
+        MoveDirection::Right => self.selected_action = (self.selected_action + 1).min(2),
+      },
+      Action::Copy => {
+        let line = self.short_report_list.as_ref().and_then(List::get_selected);
+        if let Some(line) = line {
+          return Ok(Some(Action::Clipboard(line.to_string())));
+        }
+      },
+      Action::CopyId => return Ok(Some(Action::Clipboard(self.core_id.to_string()))),
+      Action::Accept => match self.selected_action {
+        0 => {
+          if let Some(command_tx) = &self.command_tx {
+            command_tx.send(Action::NavigateBack)?;
+          }
+        },
+        1 => self.show_summary_path = true,
+        _ => {
+          if let Some(command_tx) = &self.command_tx {
+            command_tx.send(Action::Navigate(ScreenId::TRADES(self.core_id)))?;
+          }
         },
-        _ => {},
       },
       _ => {},
     }
@@ -68,23 +135,83 @@ impl Screen for Report {
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
     let content_layout = Layout::default()
-      .constraints(vec![Constraint::Length(2), Constraint::Min(0), Constraint::Length(3)])
+      .constraints(vec![
+        Constraint::Length(2),
+        Constraint::Length(2),
+        Constraint::Min(0),
+        Constraint::Length(4),
+        Constraint::Length(3),
+      ])
       .split(area);
     let button_layout = Layout::horizontal(vec![
-      Constraint::Percentage(40),
       Constraint::Percentage(20),
-      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Length(1),
+      Constraint::Percentage(20),
+      Constraint::Length(1),
+      Constraint::Percentage(20),
+      Constraint::Percentage(20),
     ])
-    .split(content_layout[2]);
+    .split(content_layout[4]);
+    let summary_text = match (&self.summary_path, self.show_summary_path) {
+      (None, _) => "Report is being written...".to_string(),
+      (Some(_), false) => "Report was written, open it to see where.".to_string(),
+      (Some(path), true) => {
+        let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+        format!("Report: {}", path.display())
+      },
+    };
+    f.render_widget(Paragraph::new(summary_text), content_layout[0]);
+    let titles = std::iter::once("Total".to_string())
+      .chain(self.pairs.iter().map(Pair::to_string))
+      .collect::<Vec<_>>();
+    let tabs = Tabs::new(titles)
+      .select(self.tab.min(self.pairs.len()))
+      .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_widget(tabs, content_layout[1]);
+
+    if let Some(short_report_list) = &mut self.short_report_list {
+      short_report_list.draw(f, content_layout[2])?;
+    }
+    let drawdown_block = Block::default().borders(Borders::TOP).title("Drawdown");
+    let drawdown_area = drawdown_block.inner(content_layout[3]);
+    f.render_widget(drawdown_block, content_layout[3]);
     f.render_widget(
-      Paragraph::new("Report was generated in summary.html"),
-      content_layout[0],
+      MiniChart::new(&self.drawdown_curve).style(Style::default().fg(theme().negative)),
+      drawdown_area,
     );
-
-    // if let Some(short_report_list) = &mut self.short_report_list {
-    //   short_report_list.draw(f, content_layout[1])?;
-    // }
-    f.render_widget(button("Back", true), button_layout[1]);
+    f.render_widget(button("Back", self.selected_action == 0), button_layout[1]);
+    f.render_widget(button("Open report", self.selected_action == 1), button_layout[3]);
+    f.render_widget(button("Trades", self.selected_action == 2), button_layout[5]);
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::{render, snapshot_rx, CORE_ID};
+
+  #[test]
+  fn report() {
+    let mut report = Report::new(CORE_ID);
+    report.register_snapshot_handler(snapshot_rx()).unwrap();
+    insta::assert_snapshot!(render(&mut report, 100, 40));
+  }
+
+  #[test]
+  fn copies_report_lines_and_the_session_id() {
+    let mut report = Report::new(CORE_ID);
+    report.register_snapshot_handler(snapshot_rx()).unwrap();
+    report.update(Action::Tick).unwrap();
+    report.update(Action::Move(MoveDirection::Down)).unwrap();
+    assert_eq!(
+      report.update(Action::Copy).unwrap(),
+      Some(Action::Clipboard("Wins / losses: 1 / 1".to_string()))
+    );
+    assert_eq!(
+      report.update(Action::CopyId).unwrap(),
+      Some(Action::Clipboard(CORE_ID.to_string()))
+    );
+  }
+}
@@ -1,18 +1,34 @@
 use super::{Screen, ScreenId};
 use crate::{
   action::{Action, MoveDirection},
-  assets::Pair,
+  assets::{gaps::GapPolicy, Pair},
   components::{
-    form::{input::Input, select::Select},
+    form::{
+      input::Input, multi_select::MultiSelect, select::Select, text_input::TextInput,
+    },
     style::{
       button, button_style, centered_text, default_action_block_style, default_header,
-      default_layout, outer_container_block, stylized_block,
+      default_layout, disabled_button, outer_container_block, stylized_block,
+      NARROW_WIDTH,
     },
     ListDisplay,
   },
   config::{Config, KeyBindings},
   core::Command,
-  strategy::{get_generated_models, ModelId},
+  database::SessionLabel,
+  exchange::{
+    account::AccountName,
+    futures::{FuturesSettings, MarginType, PositionMode},
+  },
+  portfolio::balance::EquitySource,
+  strategy::{
+    ensemble::{Ensemble, EnsembleMember, VotingMethod},
+    get_generated_models,
+    plugin::{self, StrategyPlugin},
+    ModelId, SignalEvaluation,
+  },
+  trading::params::{Market, OrderOptions, OrderType, TimeInForce, TrailingStops},
+  utils::load_config::read_config,
 };
 use chrono::{DateTime, Duration, Utc};
 use crossterm::event::{KeyCode, KeyEvent};
@@ -24,9 +40,8 @@ use strum::{EnumCount, EnumIter, IntoEnumIterator};
 use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
-const MODEL_SYNC_DURATION: Duration = Duration::milliseconds(500);
 
-#[derive(Default, Serialize, Clone, PartialEq, Debug)]
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct CoreConfiguration {
   pub run_live: bool,
   pub n_days_to_fetch: u64,
@@ -34,7 +49,51 @@ pub struct CoreConfiguration {
   pub backtest_last_n_candles: usize,
   pub exchange_fee: f64,
   pub pair: Pair,
-  pub model_name: String,
+  pub ensemble: Ensemble,
+  pub signal_evaluation: SignalEvaluation,
+  #[serde(default)]
+  pub label: SessionLabel,
+  /// Model whose training candles a backtest runs on, instead of the latest stored ones.
+  #[serde(default)]
+  pub dataset: Option<String>,
+  /// How a backtest handles missing candles.
+  #[serde(default)]
+  pub gaps: GapPolicy,
+  /// Backtests replay the recorded aggregate trades within each candle.
+  #[serde(default)]
+  pub replay_trades: bool,
+  /// Where live sessions take their starting equity from, backtests always start with
+  /// `starting_equity`.
+  #[serde(default)]
+  pub equity_source: EquitySource,
+  /// Account live sessions trade on.
+  #[serde(default)]
+  pub account: AccountName,
+  /// Where live sessions keep the trailing stop, backtests always check it themselves.
+  #[serde(default)]
+  pub trailing_stops: TrailingStops,
+  #[serde(default)]
+  pub order_options: OrderOptions,
+  #[serde(default)]
+  pub market: Market,
+  /// Leverage and margin of sessions on the futures market.
+  #[serde(default)]
+  pub futures: FuturesSettings,
+}
+
+impl CoreConfiguration {
+  /// Leverage of the positions of the session, 1 on spot.
+  pub fn leverage(&self) -> f64 {
+    match self.market {
+      Market::Spot => 1.0,
+      Market::Futures => self.futures.leverage.max(1) as f64,
+    }
+  }
+
+  /// Ensemble the session trades with, which also goes short on futures.
+  pub fn trading_ensemble(&self) -> Ensemble {
+    self.ensemble.clone().with_shorts(self.market == Market::Futures)
+  }
 }
 
 #[derive(Default, PartialEq, EnumIter, EnumCount, Clone)]
@@ -42,10 +101,27 @@ enum SelectedField {
   #[default]
   Pair,
   Model,
+  Strategy,
+  Voting,
+  MinConfidence,
+  SignalEvaluation,
   StartingEquity,
+  EquitySource,
+  Account,
+  TrailingStops,
+  OrderType,
+  TimeInForce,
+  PostOnly,
+  Market,
+  Leverage,
+  MarginType,
+  PositionMode,
   ExchangeFee,
   BacktestLastNCandles,
+  BacktestGaps,
   FetchLastNDays,
+  Label,
+  Notes,
   Actions,
 }
 
@@ -59,11 +135,31 @@ pub struct RunConfig {
   selected_action: usize,
   fetch_last_n_days: Input,
   backtest_last_n_candles: Input,
+  backtest_gaps: Select<GapPolicy>,
   starting_equity: Input,
+  equity_source: Select<EquitySource>,
+  account: Select<AccountName>,
+  trailing_stops: Select<TrailingStops>,
+  order_type: Select<OrderType>,
+  time_in_force: Select<TimeInForce>,
+  post_only: Select<bool>,
+  market: Select<Market>,
+  leverage: Input,
+  margin_type: Select<MarginType>,
+  position_mode: Select<PositionMode>,
   exchange_fee: Input,
-  model_id: Select<ModelId>,
+  model_ids: MultiSelect<ModelId>,
+  strategy: Select<StrategyPlugin>,
+  voting: Select<VotingMethod>,
+  min_confidence: Input,
+  signal_evaluation: Select<SignalEvaluation>,
   pair: Select<Pair>,
-  last_model_sync: DateTime<Utc>,
+  label: TextInput,
+  notes: TextInput,
+  /// A session is running, starting another one is disabled.
+  session_running: bool,
+  /// First field drawn when the form does not fit.
+  form_offset: usize,
 }
 
 impl RunConfig {
@@ -74,17 +170,87 @@ impl RunConfig {
         Some(1440.0),
         Some("(Backtest) N Candles".to_string()),
       ),
+      backtest_gaps: Select::new(
+        GapPolicy::iter().collect(),
+        Some(GapPolicy::default()),
+        Some("(Backtest) Candle gaps".to_string()),
+      ),
+      min_confidence: Input::new(Some(0.0), Some("Min signal confidence".to_string())),
+      signal_evaluation: Select::new(
+        SignalEvaluation::iter().collect(),
+        Some(SignalEvaluation::default()),
+        Some("Signals on".to_string()),
+      ),
       starting_equity: Input::new(Some(1000.0), Some("Starting equity".to_string())),
+      equity_source: Select::new(
+        EquitySource::iter().collect(),
+        Some(EquitySource::default()),
+        Some("(Live) Equity from".to_string()),
+      ),
+      account: Select::new(
+        read_config()
+          .map(|config| config.account_names())
+          .unwrap_or_else(|_| vec![AccountName::default()]),
+        Some(AccountName::default()),
+        Some("(Live) Account".to_string()),
+      ),
+      trailing_stops: Select::new(
+        TrailingStops::iter().collect(),
+        Some(TrailingStops::default()),
+        Some("(Live) Trailing stop on".to_string()),
+      ),
+      order_type: Select::new(
+        OrderType::iter().collect(),
+        Some(OrderType::default()),
+        Some("Order type".to_string()),
+      ),
+      time_in_force: Select::new(
+        TimeInForce::iter().collect(),
+        Some(TimeInForce::default()),
+        Some("Time in force".to_string()),
+      ),
+      post_only: Select::new(
+        vec![false, true],
+        Some(false),
+        Some("Post-only".to_string()),
+      ),
+      market: Select::new(
+        Market::iter().collect(),
+        Some(Market::default()),
+        Some("Market".to_string()),
+      ),
+      leverage: Input::new(Some(1.0), Some("(Futures) Leverage".to_string())),
+      margin_type: Select::new(
+        MarginType::iter().collect(),
+        Some(MarginType::default()),
+        Some("(Futures) Margin".to_string()),
+      ),
+      position_mode: Select::new(
+        PositionMode::iter().collect(),
+        Some(PositionMode::default()),
+        Some("(Futures) Positions".to_string()),
+      ),
       exchange_fee: Input::new(Some(0.0), Some("Exchange fee".to_string())),
       pair: Select::new(
-        vec![Pair::BTCUSDT, Pair::ETHBTC],
+        Pair::iter().collect(),
         Some(Pair::BTCUSDT),
         Some("Pair".to_string()),
       ),
-      model_id: Select::new(vec![], None, Some("Model".to_string())),
+      model_ids: MultiSelect::new(vec![], Some("Models".to_string())),
+      strategy: Select::new(
+        vec![StrategyPlugin::default()],
+        Some(StrategyPlugin::default()),
+        Some("Strategy".to_string()),
+      ),
+      voting: Select::new(
+        VotingMethod::iter().collect(),
+        Some(VotingMethod::default()),
+        Some("Voting".to_string()),
+      ),
+      label: TextInput::new(Some("Run label".to_string()), 40),
+      notes: TextInput::new(Some("Notes".to_string()), 200),
       selected_field_index: 0,
       selected_field: SelectedField::Pair,
-      last_model_sync: Utc::now(),
       ..Self::default()
     };
     config.set_field_active(SelectedField::Pair);
@@ -93,25 +259,84 @@ impl RunConfig {
 
   fn activate_field(&mut self, selected_field: SelectedField) {}
 
+  fn draw_field(
+    &mut self,
+    field: &SelectedField,
+    f: &mut Frame<'_>,
+    area: Rect,
+  ) -> Result<()> {
+    match field {
+      SelectedField::Pair => self.pair.draw(f, area),
+      SelectedField::Model => self.model_ids.draw(f, area),
+      SelectedField::Strategy => self.strategy.draw(f, area),
+      SelectedField::Voting => self.voting.draw(f, area),
+      SelectedField::MinConfidence => self.min_confidence.draw(f, area),
+      SelectedField::SignalEvaluation => self.signal_evaluation.draw(f, area),
+      SelectedField::StartingEquity => self.starting_equity.draw(f, area),
+      SelectedField::EquitySource => self.equity_source.draw(f, area),
+      SelectedField::Account => self.account.draw(f, area),
+      SelectedField::TrailingStops => self.trailing_stops.draw(f, area),
+      SelectedField::OrderType => self.order_type.draw(f, area),
+      SelectedField::TimeInForce => self.time_in_force.draw(f, area),
+      SelectedField::PostOnly => self.post_only.draw(f, area),
+      SelectedField::Market => self.market.draw(f, area),
+      SelectedField::Leverage => self.leverage.draw(f, area),
+      SelectedField::MarginType => self.margin_type.draw(f, area),
+      SelectedField::PositionMode => self.position_mode.draw(f, area),
+      SelectedField::ExchangeFee => self.exchange_fee.draw(f, area),
+      SelectedField::BacktestLastNCandles => self.backtest_last_n_candles.draw(f, area),
+      SelectedField::BacktestGaps => self.backtest_gaps.draw(f, area),
+      SelectedField::FetchLastNDays => self.fetch_last_n_days.draw(f, area),
+      SelectedField::Label => self.label.draw(f, area),
+      SelectedField::Notes => self.notes.draw(f, area),
+      SelectedField::Actions => Ok(()),
+    }
+  }
+
   fn set_field_active(&mut self, selected_field: SelectedField) {
-    self.model_id.set_active(selected_field == SelectedField::Model);
+    self.model_ids.set_active(selected_field == SelectedField::Model);
+    self.strategy.set_active(selected_field == SelectedField::Strategy);
+    self.voting.set_active(selected_field == SelectedField::Voting);
+    self.min_confidence.set_active(selected_field == SelectedField::MinConfidence);
+    self
+      .signal_evaluation
+      .set_active(selected_field == SelectedField::SignalEvaluation);
     self.pair.set_active(selected_field == SelectedField::Pair);
     self.fetch_last_n_days.set_active(selected_field == SelectedField::FetchLastNDays);
     self
       .backtest_last_n_candles
       .set_active(selected_field == SelectedField::BacktestLastNCandles);
+    self.backtest_gaps.set_active(selected_field == SelectedField::BacktestGaps);
     self.starting_equity.set_active(selected_field == SelectedField::StartingEquity);
+    self.equity_source.set_active(selected_field == SelectedField::EquitySource);
+    self.account.set_active(selected_field == SelectedField::Account);
+    self.trailing_stops.set_active(selected_field == SelectedField::TrailingStops);
+    self.order_type.set_active(selected_field == SelectedField::OrderType);
+    self.time_in_force.set_active(selected_field == SelectedField::TimeInForce);
+    self.post_only.set_active(selected_field == SelectedField::PostOnly);
+    self.market.set_active(selected_field == SelectedField::Market);
+    self.leverage.set_active(selected_field == SelectedField::Leverage);
+    self.margin_type.set_active(selected_field == SelectedField::MarginType);
+    self.position_mode.set_active(selected_field == SelectedField::PositionMode);
     self.exchange_fee.set_active(selected_field == SelectedField::ExchangeFee);
+    self.label.set_active(selected_field == SelectedField::Label);
+    self.notes.set_active(selected_field == SelectedField::Notes);
   }
 
-  fn sync_models(&mut self) -> Result<()> {
-    if self.last_model_sync + MODEL_SYNC_DURATION < Utc::now() {
-      let metadata_list = get_generated_models()?;
-      let model_id_list: Vec<ModelId> =
-        metadata_list.iter().map(|metadata| metadata.to_model_id()).collect();
-      let sorted_list = self.model_id.set_options(model_id_list);
-      self.last_model_sync = Utc::now();
+  fn text_field(&mut self) -> Option<&mut TextInput> {
+    match self.selected_field {
+      SelectedField::Label => Some(&mut self.label),
+      SelectedField::Notes => Some(&mut self.notes),
+      _ => None,
     }
+  }
+
+  fn sync_models(&mut self) -> Result<()> {
+    let metadata_list = get_generated_models()?;
+    let model_id_list: Vec<ModelId> =
+      metadata_list.iter().map(|metadata| metadata.to_model_id()).collect();
+    self.model_ids.set_options(model_id_list);
+    self.strategy.set_options(plugin::discover(&self.config.strategies_dir));
     Ok(())
   }
 }
@@ -127,11 +352,36 @@ impl Screen for RunConfig {
     Ok(())
   }
 
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    self.sync_models()
+  }
+
+  fn captures_text(&self) -> bool {
+    self.label.is_editing() || self.notes.is_editing()
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if let Some(field) = self.text_field().filter(|field| field.is_editing()) {
+      match key.code {
+        KeyCode::Char(c) => field.push(c),
+        KeyCode::Backspace => field.pop(),
+        _ => {},
+      }
+      return Ok(None);
+    }
+    if key.code == KeyCode::Char(' ')
+      && self.selected_field == SelectedField::Model
+      && self.model_ids.is_editing()
+    {
+      self.model_ids.toggle_current();
+    }
+    Ok(None)
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
-      Action::Tick => {
-        self.sync_models()?;
-      },
+      Action::ModelsChanged => self.sync_models()?,
+      Action::RunStateChanged(state) => self.session_running = !state.can_start(),
       Action::Move(direction) => match direction {
         MoveDirection::Left => {
           if self.selected_field == SelectedField::Actions {
@@ -147,7 +397,20 @@ impl Screen for RunConfig {
           if self.is_field_being_edited {
             match self.selected_field {
               SelectedField::Pair => self.pair.edit_next(),
-              SelectedField::Model => self.model_id.edit_next(),
+              SelectedField::Model => self.model_ids.edit_next(),
+              SelectedField::Strategy => self.strategy.edit_next(),
+              SelectedField::Voting => self.voting.edit_next(),
+              SelectedField::SignalEvaluation => self.signal_evaluation.edit_next(),
+              SelectedField::BacktestGaps => self.backtest_gaps.edit_next(),
+              SelectedField::EquitySource => self.equity_source.edit_next(),
+              SelectedField::Account => self.account.edit_next(),
+              SelectedField::TrailingStops => self.trailing_stops.edit_next(),
+              SelectedField::OrderType => self.order_type.edit_next(),
+              SelectedField::TimeInForce => self.time_in_force.edit_next(),
+              SelectedField::PostOnly => self.post_only.edit_next(),
+              SelectedField::Market => self.market.edit_next(),
+              SelectedField::MarginType => self.margin_type.edit_next(),
+              SelectedField::PositionMode => self.position_mode.edit_next(),
               _ => (),
             };
           } else {
@@ -163,7 +426,20 @@ impl Screen for RunConfig {
           if self.is_field_being_edited {
             match self.selected_field {
               SelectedField::Pair => self.pair.edit_previous(),
-              SelectedField::Model => self.model_id.edit_previous(),
+              SelectedField::Model => self.model_ids.edit_previous(),
+              SelectedField::Strategy => self.strategy.edit_previous(),
+              SelectedField::Voting => self.voting.edit_previous(),
+              SelectedField::SignalEvaluation => self.signal_evaluation.edit_previous(),
+              SelectedField::BacktestGaps => self.backtest_gaps.edit_previous(),
+              SelectedField::EquitySource => self.equity_source.edit_previous(),
+              SelectedField::Account => self.account.edit_previous(),
+              SelectedField::TrailingStops => self.trailing_stops.edit_previous(),
+              SelectedField::OrderType => self.order_type.edit_previous(),
+              SelectedField::TimeInForce => self.time_in_force.edit_previous(),
+              SelectedField::PostOnly => self.post_only.edit_previous(),
+              SelectedField::Market => self.market.edit_previous(),
+              SelectedField::MarginType => self.margin_type.edit_previous(),
+              SelectedField::PositionMode => self.position_mode.edit_previous(),
               _ => (),
             };
           } else {
@@ -178,10 +454,26 @@ impl Screen for RunConfig {
       Action::Accept => {
         if let Some(command_tx) = &self.command_tx {
           if self.selected_field == SelectedField::Actions {
-            let options = self.pair.value().zip(self.model_id.value());
+            let model_ids = self.model_ids.values();
+            let strategy = self.strategy.value().unwrap_or_default().path;
+            let options = self.pair.value().zip(self.voting.value());
+            let can_start = !self.session_running && !model_ids.is_empty();
+            let signal_evaluation = self.signal_evaluation.value().unwrap_or_default();
             let screen_id = if self.selected_action == 2 {
-              command_tx.send(Action::Navigate(ScreenId::HOME))?;
-            } else if let Some((pair, model_id)) = options {
+              command_tx.send(Action::NavigateBack)?;
+            } else if let (Some((pair, voting)), true) = (options, can_start) {
+              let members = model_ids
+                .iter()
+                .map(|model_id| {
+                  EnsembleMember::new(
+                    model_id.name.clone(),
+                    model_id.ensemble_weight,
+                    model_id.features.clone(),
+                    model_id.backend,
+                  )
+                  .with_strategy(strategy.clone())
+                })
+                .collect();
               command_tx.send(Action::CoreCommand(Command::Start(
                 CoreConfiguration {
                   run_live: self.selected_action == 1,
@@ -189,8 +481,30 @@ impl Screen for RunConfig {
                   starting_equity: self.starting_equity.value(),
                   backtest_last_n_candles: self.backtest_last_n_candles.value() as usize,
                   exchange_fee: self.exchange_fee.value(),
-                  model_name: model_id.name.clone(),
+                  ensemble: Ensemble::new(members, voting, self.min_confidence.value()),
+                  signal_evaluation,
                   pair,
+                  label: SessionLabel {
+                    label: self.label.value(),
+                    notes: self.notes.value(),
+                  },
+                  dataset: None,
+                  gaps: self.backtest_gaps.value().unwrap_or_default(),
+                  replay_trades: false,
+                  equity_source: self.equity_source.value().unwrap_or_default(),
+                  account: self.account.value().unwrap_or_default(),
+                  trailing_stops: self.trailing_stops.value().unwrap_or_default(),
+                  order_options: OrderOptions {
+                    order_type: self.order_type.value().unwrap_or_default(),
+                    time_in_force: self.time_in_force.value().unwrap_or_default(),
+                    post_only: self.post_only.value().unwrap_or_default(),
+                  },
+                  market: self.market.value().unwrap_or_default(),
+                  futures: FuturesSettings {
+                    leverage: (self.leverage.value() as u32).clamp(1, 125),
+                    margin_type: self.margin_type.value().unwrap_or_default(),
+                    position_mode: self.position_mode.value().unwrap_or_default(),
+                  },
                 },
               )))?;
             };
@@ -198,13 +512,30 @@ impl Screen for RunConfig {
             // ACTIVATE INPUTS
             let is_field_being_edited = match self.selected_field {
               SelectedField::Pair => self.pair.toggle_edit(),
-              SelectedField::Model => self.model_id.toggle_edit(),
+              SelectedField::Model => self.model_ids.toggle_edit(),
+              SelectedField::Strategy => self.strategy.toggle_edit(),
+              SelectedField::Voting => self.voting.toggle_edit(),
+              SelectedField::MinConfidence => self.min_confidence.toggle_edit(),
+              SelectedField::SignalEvaluation => self.signal_evaluation.toggle_edit(),
               SelectedField::ExchangeFee => self.exchange_fee.toggle_edit(),
               SelectedField::StartingEquity => self.starting_equity.toggle_edit(),
+              SelectedField::EquitySource => self.equity_source.toggle_edit(),
+              SelectedField::Account => self.account.toggle_edit(),
+              SelectedField::TrailingStops => self.trailing_stops.toggle_edit(),
+              SelectedField::OrderType => self.order_type.toggle_edit(),
+              SelectedField::TimeInForce => self.time_in_force.toggle_edit(),
+              SelectedField::PostOnly => self.post_only.toggle_edit(),
+              SelectedField::Market => self.market.toggle_edit(),
+              SelectedField::Leverage => self.leverage.toggle_edit(),
+              SelectedField::MarginType => self.margin_type.toggle_edit(),
+              SelectedField::PositionMode => self.position_mode.toggle_edit(),
               SelectedField::FetchLastNDays => self.fetch_last_n_days.toggle_edit(),
               SelectedField::BacktestLastNCandles => {
                 self.backtest_last_n_candles.toggle_edit()
               },
+              SelectedField::BacktestGaps => self.backtest_gaps.toggle_edit(),
+              SelectedField::Label => self.label.toggle_edit(),
+              SelectedField::Notes => self.notes.toggle_edit(),
               SelectedField::Actions => false,
             };
             self.is_field_being_edited = is_field_being_edited
@@ -221,79 +552,94 @@ impl Screen for RunConfig {
       .constraints(vec![Constraint::Min(0), Constraint::Length(3)])
       .split(area);
 
-    let form_layout = Layout::default()
-      .constraints(vec![
-        Constraint::Length(2),
-        Constraint::Length(2),
-        Constraint::Length(2),
-        Constraint::Length(2),
-        Constraint::Length(2),
-        Constraint::Length(2),
-        Constraint::Min(0),
-      ])
-      .split(content_layout[0]);
-
-    // Pair
-    self.pair.draw(f, form_layout[0])?;
-
-    // Model
-    self.model_id.draw(f, form_layout[1])?;
-
-    // Starting Equity
-    self.starting_equity.draw(f, form_layout[2])?;
-
-    // Exchange Fee
-    self.exchange_fee.draw(f, form_layout[3])?;
-
-    // Backtest Last N Candles
-    self.backtest_last_n_candles.draw(f, form_layout[4])?;
-
-    // Last N days fetch
-    self.fetch_last_n_days.draw(f, form_layout[5])?;
+    // Fields take a row and a separator, only the row on short terminals, and scroll to
+    // the selected one when they still do not fit.
+    let fields: Vec<SelectedField> =
+      SelectedField::iter().filter(|field| *field != SelectedField::Actions).collect();
+    let form_area = content_layout[0];
+    let field_height = if form_area.height >= 2 * fields.len() as u16 { 2 } else { 1 };
+    let visible = ((form_area.height / field_height) as usize).max(1);
+    let selected = self.selected_field_index.min(fields.len() - 1);
+    if selected < self.form_offset {
+      self.form_offset = selected;
+    } else if selected >= self.form_offset + visible {
+      self.form_offset = selected + 1 - visible;
+    }
+    self.form_offset = self.form_offset.min(fields.len().saturating_sub(visible));
+    let form_layout =
+      Layout::vertical(vec![Constraint::Length(field_height); visible]).split(form_area);
+    for (area, field) in form_layout.iter().zip(fields.iter().skip(self.form_offset)) {
+      self.draw_field(field, f, *area)?;
+    }
 
+    let (margin, button_width) = if area.width < NARROW_WIDTH {
+      (Constraint::Length(0), Constraint::Fill(1))
+    } else {
+      (Constraint::Percentage(9), Constraint::Percentage(26))
+    };
     let button_layout = Layout::default()
       .direction(Direction::Horizontal)
       .constraints(vec![
-        Constraint::Percentage(9),
-        Constraint::Percentage(26),
+        margin,
+        button_width,
         Constraint::Length(1),
-        Constraint::Percentage(26),
+        button_width,
         Constraint::Length(1),
-        Constraint::Percentage(26),
-        Constraint::Percentage(9),
+        button_width,
+        margin,
       ])
       .split(content_layout[1]);
 
     match self.selected_field {
       SelectedField::Pair => self.pair.draw_edit(f, content_layout[0])?,
-      SelectedField::Model => self.model_id.draw_edit(f, content_layout[0])?,
+      SelectedField::Model => self.model_ids.draw_edit(f, content_layout[0])?,
+      SelectedField::Strategy => self.strategy.draw_edit(f, content_layout[0])?,
+      SelectedField::Voting => self.voting.draw_edit(f, content_layout[0])?,
+      SelectedField::MinConfidence => {
+        self.min_confidence.draw_edit(f, content_layout[0])?
+      },
+      SelectedField::SignalEvaluation => {
+        self.signal_evaluation.draw_edit(f, content_layout[0])?
+      },
       SelectedField::StartingEquity => {
         self.starting_equity.draw_edit(f, content_layout[0])?
       },
+      SelectedField::EquitySource => self.equity_source.draw_edit(f, content_layout[0])?,
+      SelectedField::Account => self.account.draw_edit(f, content_layout[0])?,
+      SelectedField::TrailingStops => {
+        self.trailing_stops.draw_edit(f, content_layout[0])?
+      },
+      SelectedField::OrderType => self.order_type.draw_edit(f, content_layout[0])?,
+      SelectedField::TimeInForce => self.time_in_force.draw_edit(f, content_layout[0])?,
+      SelectedField::PostOnly => self.post_only.draw_edit(f, content_layout[0])?,
+      SelectedField::Market => self.market.draw_edit(f, content_layout[0])?,
+      SelectedField::Leverage => self.leverage.draw_edit(f, content_layout[0])?,
+      SelectedField::MarginType => self.margin_type.draw_edit(f, content_layout[0])?,
+      SelectedField::PositionMode => {
+        self.position_mode.draw_edit(f, content_layout[0])?
+      },
       SelectedField::ExchangeFee => self.exchange_fee.draw_edit(f, content_layout[0])?,
       SelectedField::BacktestLastNCandles => {
         self.backtest_last_n_candles.draw_edit(f, content_layout[0])?
       },
+      SelectedField::BacktestGaps => self.backtest_gaps.draw_edit(f, content_layout[0])?,
       SelectedField::FetchLastNDays => {
         self.fetch_last_n_days.draw_edit(f, content_layout[0])?
       },
-      SelectedField::Actions => (),
+      SelectedField::Label | SelectedField::Notes | SelectedField::Actions => (),
     };
 
-    f.render_widget(
-      button(
-        "BACKTEST",
-        self.selected_field == SelectedField::Actions && self.selected_action == 0,
-      ),
-      button_layout[1],
-    );
-    f.render_widget(
-      button(
-        "RUN",
-        self.selected_field == SelectedField::Actions && self.selected_action == 1,
-      ),
-      button_layout[3],
-    );
+    let start_button = |text, action| {
+      let selected =
+        self.selected_field == SelectedField::Actions && self.selected_action == action;
+      if self.session_running {
+        disabled_button(text)
+      } else {
+        button(text, selected)
+      }
+    };
+    f.render_widget(start_button("BACKTEST", 0), button_layout[1]);
+    f.render_widget(start_button("RUN", 1), button_layout[3]);
     f.render_widget(
       button(
         "BACK",
@@ -305,3 +651,28 @@ impl Screen for RunConfig {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::render;
+
+  #[test]
+  fn run_config() {
+    insta::assert_snapshot!(render(&mut RunConfig::new(), 100, 36));
+  }
+
+  #[test]
+  fn run_config_short() {
+    insta::assert_snapshot!(render(&mut RunConfig::new(), 60, 20));
+  }
+
+  #[test]
+  fn run_config_short_scrolled() {
+    let mut run_config = RunConfig::new();
+    for _ in 0..12 {
+      run_config.update(Action::Move(MoveDirection::Down)).unwrap();
+    }
+    insta::assert_snapshot!(render(&mut run_config, 60, 14));
+  }
+}
@@ -1,22 +1,35 @@
 use super::{Screen, ScreenId};
 use crate::{
-  action::{Action, ScreenUpdate},
-  assets::Pair,
+  action::{Action, MoveDirection},
+  assets::{backtest_ticker::BacktestProgress, Pair, Side},
   components::{
+    glyphs::glyphs,
     list::{LabelValueItem, List},
-    style::{button, default_layout, outer_container_block, stylized_block},
+    style::{
+      button, centered_rect, default_layout, disabled_button, outer_container_block,
+      stylized_block, theme, NARROW_WIDTH,
+    },
   },
   config::{Config, KeyBindings},
-  core::Command,
-  database::{error::DatabaseError, Database},
+  core::{Command, CoreMessage},
+  database::{error::DatabaseError, snapshot::DatabaseSnapshot, Database},
+  lifecycle::RunState,
+  portfolio::{position::Position, regime::VolatilityRegime},
   statistic::TradingSummary,
+  trading::params::RuntimeParams,
+  utils::formatting::{duration_to_readable, format_amount, format_signed_number},
 };
+use chrono::{DateTime, Utc};
 use crossterm::event::{KeyCode, KeyEvent};
 use eyre::Result;
 use ratatui::{prelude::*, widgets::*};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
-use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use std::{
+  collections::HashMap,
+  sync::Arc,
+  time::{Duration, Instant},
+};
+use tokio::sync::{mpsc::UnboundedSender, watch, Mutex};
 use uuid::Uuid;
 
 #[derive(Default)]
@@ -26,15 +39,50 @@ pub enum RunningMode {
   RUNNING,
 }
 
+/// Editable runtime parameters: label, step of an arrow key and the allowed range.
+const PARAM_FIELDS: [(&str, f64, f64, f64); 3] = [
+  ("Risk per trade", 0.05, 0.05, 1.0),
+  ("Min confidence", 0.05, 0.0, 1.0),
+  ("Trailing stop", 0.005, 0.0, 0.5),
+];
+
 #[derive(Default)]
 pub struct Running {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
+  snapshot: Option<watch::Receiver<DatabaseSnapshot>>,
   mode: RunningMode,
   stats: Option<TradingSummary>,
   core_id: Uuid,
   pair: Pair,
   short_report_list: Option<List<LabelValueItem<String>>>,
+  /// Last market event of a pair whose market data went stale.
+  stale_since: Option<DateTime<Utc>>,
+  /// Candles the trader still needs before its first signal.
+  warm_up_remaining: usize,
+  positions_list: List<LabelValueItem<String>>,
+  /// Pairs of the items in the positions list.
+  position_pairs: Vec<Pair>,
+  /// Pair of the position waiting for the exit to be confirmed.
+  confirm_exit: Option<Pair>,
+  /// Parameters the traders apply.
+  params: RuntimeParams,
+  /// Parameters being edited, sent to the traders on accept.
+  edited_params: Option<RuntimeParams>,
+  selected_param: usize,
+  /// Volatility regimes of the pairs, when the session classifies them.
+  regimes: Vec<(Pair, VolatilityRegime)>,
+  backtest_progress: BacktestProgress,
+  /// When the first backtest progress arrived, the ETA is extrapolated from it.
+  backtest_started: Option<Instant>,
+  /// Exited positions of the session.
+  trades: usize,
+  /// The report opens once the session finished.
+  run_state: RunState,
+  /// Days of history fetched before the traders start.
+  history_days: i64,
+  /// Pair whose history is being fetched and the share fetched.
+  history_progress: Option<(Pair, f64)>,
 }
 
 impl Running {
@@ -49,6 +97,112 @@ impl Running {
   pub fn set_core(&mut self, core_id: Uuid) {
     self.core_id = core_id
   }
+
+  fn selected_position(&self) -> Option<Pair> {
+    let selected = self.positions_list.get_selected()?;
+    let index = self.positions_list.items().iter().position(|item| *item == selected)?;
+    self.position_pairs.get(index).copied()
+  }
+
+  fn exit_position(&mut self, pair: Pair) -> Option<Action> {
+    self.confirm_exit = None;
+    Some(Action::CoreCommand(Command::ExitPosition(pair)))
+  }
+
+  fn update_params(&mut self) -> Option<Action> {
+    let params = self.edited_params.take()?;
+    Some(Action::CoreCommand(Command::UpdateParams(params)))
+  }
+
+  /// Steps the selected parameter by `steps` of its step, within its range.
+  fn step_param(&mut self, steps: f64) {
+    let Some(params) = self.edited_params.as_mut() else {
+      return;
+    };
+    let (_, step, min, max) = PARAM_FIELDS[self.selected_param];
+    let value = param_value(params, self.selected_param);
+    *value = (*value + steps * step).clamp(min, max);
+  }
+
+  /// "12000/43200 candles to 2024-03-01 12:00, 17 trades, ETA 1m 20s"
+  fn backtest_gauge(&self) -> Gauge<'_> {
+    let progress = &self.backtest_progress;
+    let time = progress
+      .time
+      .map(|time| format!(" to {}", time.format("%Y-%m-%d %H:%M")))
+      .unwrap_or_default();
+    let elapsed = self.backtest_started.map(|started| started.elapsed());
+    let eta = match elapsed {
+      Some(elapsed) if progress.candles > 0 => {
+        let remaining = progress.total.saturating_sub(progress.candles);
+        let eta = elapsed.mul_f64(remaining as f64 / progress.candles as f64);
+        match chrono::Duration::from_std(eta) {
+          Ok(eta) if eta.num_seconds() >= 3 => duration_to_readable(&eta),
+          _ => "a few seconds".to_string(),
+        }
+      },
+      _ => "unknown".to_string(),
+    };
+    let label = format!(
+      "{}/{} candles{time}, {} trades, ETA {eta}",
+      progress.candles, progress.total, self.trades
+    );
+    Gauge::default()
+      .gauge_style(Style::default().fg(theme().positive))
+      .ratio(progress.ratio())
+      .label(label)
+  }
+
+  /// "BTCUSDT 45% of 30 days"
+  fn history_gauge(&self) -> Gauge<'_> {
+    let (pair, progress) = self.history_progress.unwrap_or((self.pair, 0.0));
+    let label = format!("{pair} {:.0}% of {} days", progress * 100.0, self.history_days);
+    Gauge::default()
+      .gauge_style(Style::default().fg(theme().positive))
+      .ratio(progress.clamp(0.0, 1.0))
+      .label(label)
+  }
+
+  fn params_paragraph(&self) -> Paragraph<'_> {
+    let mut params = self.edited_params.unwrap_or(self.params);
+    let mut lines: Vec<Line> = PARAM_FIELDS
+      .iter()
+      .enumerate()
+      .map(|(index, (label, ..))| {
+        let value = *param_value(&mut params, index);
+        let value = match index {
+          2 if value == 0.0 => "off".to_string(),
+          2 => format!("{:.1}%", value * 100.0),
+          _ => format!("{:.2}", value),
+        };
+        let line = Line::from(format!("{label}: {value}"));
+        if self.edited_params.is_some() && index == self.selected_param {
+          line.style(Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+          line
+        }
+      })
+      .collect();
+    if !self.regimes.is_empty() {
+      let regimes: Vec<String> =
+        self.regimes.iter().map(|(pair, regime)| format!("{pair} {regime}")).collect();
+      lines.push(Line::from(format!("Volatility: {}", regimes.join(", "))));
+    }
+    let title = match self.edited_params {
+      Some(_) => format!("Parameters, {} change, s apply, e cancel", glyphs().left_right),
+      None => "Parameters, e edit".to_string(),
+    };
+    Paragraph::new(lines)
+      .block(stylized_block(self.edited_params.is_some()).title(title))
+  }
+}
+
+fn param_value(params: &mut RuntimeParams, index: usize) -> &mut f64 {
+  match index {
+    0 => &mut params.risk_per_trade,
+    1 => &mut params.min_confidence,
+    _ => &mut params.trailing_stop,
+  }
 }
 
 impl Screen for Running {
@@ -62,33 +216,130 @@ impl Screen for Running {
     Ok(())
   }
 
+  fn register_snapshot_handler(
+    &mut self,
+    snapshot: watch::Receiver<DatabaseSnapshot>,
+  ) -> Result<()> {
+    self.snapshot = Some(snapshot);
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if let Some(pair) = self.confirm_exit {
+      return Ok(match key.code {
+        KeyCode::Char('y') => self.exit_position(pair),
+        KeyCode::Char('n') => {
+          self.confirm_exit = None;
+          None
+        },
+        _ => None,
+      });
+    }
+    if self.edited_params.is_some() {
+      return Ok(match key.code {
+        KeyCode::Char('s') => self.update_params(),
+        KeyCode::Char('e') => {
+          self.edited_params = None;
+          None
+        },
+        _ => None,
+      });
+    }
+    let command = match key.code {
+      KeyCode::Char('e') => {
+        self.edited_params = Some(self.params);
+        return Ok(None);
+      },
+      KeyCode::Char('x') => {
+        self.confirm_exit = self.selected_position();
+        return Ok(None);
+      },
+      KeyCode::Char('p') => Command::Pause,
+      KeyCode::Char('r') => Command::Resume,
+      _ => return Ok(None),
+    };
+    Ok(Some(Action::CoreCommand(command)))
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
       Action::Tick => {
-        if let Some(command_tx) = &self.command_tx {
-          command_tx.send(Action::GenerateRunOverview(self.core_id, self.pair))?;
+        if let Some(snapshot) = &self.snapshot {
+          let snapshot = snapshot.borrow();
+          let overview = snapshot.run_overview(&self.core_id, &self.pair);
+          self.short_report_list.get_or_insert_with(List::default).update_items(overview);
+          let positions = snapshot.open_positions(&self.core_id);
+          self.position_pairs = positions.iter().map(|position| position.asset).collect();
+          self.positions_list.update_items(positions.iter().map(position_item).collect());
+          if let Some(params) = snapshot.runtime_params.get(&self.core_id) {
+            self.params = *params;
+          }
+          if let Some(regimes) = snapshot.volatility_regimes.get(&self.core_id) {
+            self.regimes =
+              regimes.iter().map(|(pair, regime)| (*pair, *regime)).collect();
+            self.regimes.sort_by_key(|(pair, _)| pair.to_string());
+          }
+          self.trades = snapshot.exited_positions.get(&self.core_id).map_or(0, Vec::len);
         }
       },
-      Action::ScreenUpdate(update) => match update {
-        ScreenUpdate::Running(overview) => {
-          if self.short_report_list.is_none() {
-            let list = List::default();
-            self.short_report_list = Some(list)
-          }
-          let _ = self.short_report_list.as_mut().is_some_and(|list| {
-            list.update_items(overview);
-            true
-          });
-        },
-        _ => {},
+      Action::Move(MoveDirection::Up) if self.edited_params.is_some() => {
+        self.selected_param = self.selected_param.saturating_sub(1)
+      },
+      Action::Move(MoveDirection::Down) if self.edited_params.is_some() => {
+        self.selected_param = (self.selected_param + 1).min(PARAM_FIELDS.len() - 1)
+      },
+      Action::Move(MoveDirection::Left) => self.step_param(-1.0),
+      Action::Move(MoveDirection::Right) => self.step_param(1.0),
+      Action::Move(MoveDirection::Up) if self.confirm_exit.is_none() => {
+        self.positions_list.previous()
+      },
+      Action::Move(MoveDirection::Down) if self.confirm_exit.is_none() => {
+        self.positions_list.next()
       },
+      Action::Accept if self.confirm_exit.is_some() => {
+        return Ok(self.confirm_exit.and_then(|pair| self.exit_position(pair)));
+      },
+      Action::Accept if self.edited_params.is_some() => return Ok(self.update_params()),
+      Action::CoreMessage(CoreMessage::MarketDataStale { core_id, pair, since })
+        if core_id == self.core_id && pair == self.pair =>
+      {
+        self.stale_since = Some(since)
+      },
+      Action::CoreMessage(CoreMessage::MarketDataFresh { core_id, pair })
+        if core_id == self.core_id && pair == self.pair =>
+      {
+        self.stale_since = None
+      },
+      Action::CoreMessage(CoreMessage::WarmingUp { core_id, pair, remaining })
+        if core_id == self.core_id && pair == self.pair =>
+      {
+        self.warm_up_remaining = remaining
+      },
+      Action::CoreMessage(CoreMessage::BacktestProgress { core_id, progress })
+        if core_id == self.core_id =>
+      {
+        self.mode = RunningMode::BACKTEST;
+        self.backtest_started.get_or_insert_with(Instant::now);
+        self.backtest_progress = progress;
+      },
+      Action::CoreMessage(CoreMessage::HistoryFetchStarted { core_id, days })
+        if core_id == self.core_id =>
+      {
+        self.history_days = days
+      },
+      Action::CoreMessage(CoreMessage::HistoryFetchProgress { core_id, pair, progress })
+        if core_id == self.core_id =>
+      {
+        self.history_progress = Some((pair, progress))
+      },
+      Action::RunStateChanged(state) => self.run_state = state,
+      Action::Accept if !self.run_state.accepts_commands() => {},
       Action::Accept => {
-        if let Some(command_tx) = &self.command_tx {
-          command_tx.send(Action::CoreCommand(Command::Terminate(
-            "User finished the run".to_string(),
-          )))?;
-          command_tx.send(Action::Navigate(ScreenId::REPORT(self.core_id.clone())))?;
-        }
+        let message = match self.mode {
+          RunningMode::BACKTEST => "User cancelled the backtest",
+          RunningMode::RUNNING => "User finished the run",
+        };
+        return Ok(Some(Action::CoreCommand(Command::Terminate(message.to_string()))));
       },
       _ => {},
     }
@@ -108,17 +359,173 @@ impl Screen for Running {
       ])
       .split(content_layout[2]);
 
-    f.render_widget(
-      Paragraph::new(format!("Running {}", self.core_id)),
-      content_layout[0],
-    );
+    let header = match self.stale_since {
+      Some(since) => Paragraph::new(format!(
+        "No market data since {}, entries paused until it recovers.",
+        since.format("%H:%M:%S")
+      ))
+      .style(Style::default().fg(theme().text_warning).bg(theme().warning)),
+      None if matches!(self.run_state, RunState::Terminating { .. }) => {
+        Paragraph::new("Finishing the session, closing its positions for the report.")
+      },
+      None if matches!(self.run_state, RunState::FetchingHistory { .. }) => {
+        Paragraph::new("Fetching history, the traders start once it is stored.")
+      },
+      None if self.warm_up_remaining > 0 => Paragraph::new(format!(
+        "Warming up, {} more candles before the first signal.",
+        self.warm_up_remaining
+      )),
+      None => Paragraph::new(format!(
+        "Running {}. p pause, r resume, x exit the selected position, e edit parameters",
+        self.core_id
+      )),
+    };
+    let fetching_history = matches!(self.run_state, RunState::FetchingHistory { .. });
+    match self.mode {
+      _ if fetching_history => {
+        let header_layout =
+          Layout::vertical(vec![Constraint::Length(1), Constraint::Length(1)])
+            .split(content_layout[0]);
+        f.render_widget(header, header_layout[0]);
+        f.render_widget(self.history_gauge(), header_layout[1]);
+      },
+      RunningMode::BACKTEST if self.stale_since.is_none() => {
+        let header_layout =
+          Layout::vertical(vec![Constraint::Length(1), Constraint::Length(1)])
+            .split(content_layout[0]);
+        f.render_widget(header, header_layout[0]);
+        f.render_widget(self.backtest_gauge(), header_layout[1]);
+      },
+      _ => f.render_widget(header, content_layout[0]),
+    }
 
+    // Overview and positions side by side, stacked on narrow terminals.
+    let lists_direction = if area.width < NARROW_WIDTH {
+      Direction::Vertical
+    } else {
+      Direction::Horizontal
+    };
+    let lists_layout = Layout::new(
+      lists_direction,
+      vec![Constraint::Percentage(50), Constraint::Percentage(50)],
+    )
+    .split(content_layout[1]);
     if let Some(list) = self.short_report_list.as_mut() {
-      list.draw(f, content_layout[1])?;
+      list.draw(f, lists_layout[0])?;
+    } else {
+      f.render_widget(Paragraph::new("Waiting for DB"), lists_layout[0]);
+    }
+    let params_height = if self.regimes.is_empty() { 5 } else { 6 };
+    let right_layout =
+      Layout::vertical(vec![Constraint::Min(0), Constraint::Length(params_height)])
+        .split(lists_layout[1]);
+    if self.positions_list.is_empty() {
+      f.render_widget(Paragraph::new("No open positions."), right_layout[0]);
+    } else {
+      self.positions_list.draw(f, right_layout[0])?;
+    }
+    f.render_widget(self.params_paragraph(), right_layout[1]);
+    let finish = match self.mode {
+      RunningMode::BACKTEST => "Cancel",
+      RunningMode::RUNNING => "Finish",
+    };
+    if self.run_state.accepts_commands() {
+      f.render_widget(button(finish, true), button_layout[1]);
     } else {
-      f.render_widget(Paragraph::new("Waiting for DB"), content_layout[1]);
+      f.render_widget(disabled_button(finish), button_layout[1]);
+    }
+
+    if let Some(pair) = self.confirm_exit {
+      let modal = centered_rect(area.width.min(48), area.height.min(5), area);
+      f.render_widget(Clear, modal);
+      let text = format!("Exit the {pair} position at market?\ny confirm, n cancel");
+      f.render_widget(
+        Paragraph::new(text).alignment(Alignment::Center).block(stylized_block(true)),
+        modal,
+      );
     }
-    f.render_widget(button("Finish", true), button_layout[1]);
     Ok(())
   }
 }
+
+/// "Long 0.002 @ 42005.37, PnL +1.23", with "5x, liq. 33874.30" of leveraged ones.
+fn position_item(position: &Position) -> LabelValueItem<String> {
+  let side = match position.side {
+    Side::Buy => "Long",
+    Side::Sell => "Short",
+  };
+  let quote = position.asset.quote();
+  let mut value = format!(
+    "{side} {} @ {}, PnL {}",
+    format_amount(position.quantity.abs(), position.asset.base()),
+    format_amount(position.enter_avg_price_gross, quote),
+    format_signed_number(position.unrealised_profit_loss)
+  );
+  if position.leverage > 1.0 {
+    value += &format!(", {}x", position.leverage);
+  }
+  if let Some(price) = position.liquidation_price {
+    value += &format!(", liq. {}", format_amount(price, quote));
+  }
+  LabelValueItem::new(position.asset.to_string(), value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::{render, snapshot, snapshot_rx, CORE_ID};
+  use crossterm::event::KeyModifiers;
+  use std::collections::HashMap;
+
+  fn running() -> Running {
+    let mut running = Running::new(CORE_ID, Pair::BTCUSDT);
+    running.set_mode(RunningMode::RUNNING);
+    running.register_snapshot_handler(snapshot_rx()).unwrap();
+    running
+  }
+
+  #[test]
+  fn running_live() {
+    insta::assert_snapshot!(render(&mut running(), 100, 30));
+  }
+
+  #[test]
+  fn running_confirm_exit() {
+    let mut running = running();
+    running.update(Action::Tick).unwrap();
+    let exit = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+    running.handle_key_events(exit).unwrap();
+    insta::assert_snapshot!(render(&mut running, 100, 30));
+  }
+
+  #[test]
+  fn running_in_high_volatility() {
+    let mut snapshot = snapshot();
+    let regimes = HashMap::from([(Pair::BTCUSDT, VolatilityRegime::High)]);
+    snapshot.volatility_regimes.insert(CORE_ID, regimes);
+    let mut running = Running::new(CORE_ID, Pair::BTCUSDT);
+    running.set_mode(RunningMode::RUNNING);
+    running.register_snapshot_handler(watch::channel(snapshot).1).unwrap();
+    running.update(Action::Tick).unwrap();
+    insta::assert_snapshot!(render(&mut running, 100, 30));
+  }
+
+  #[test]
+  fn leveraged_position_item() {
+    let snapshot = snapshot();
+    let position = snapshot.open_positions.values().next().unwrap();
+    let item = position_item(&position.clone().with_leverage(5.0));
+    let item = item.to_string();
+    assert!(item.ends_with(", 5x, liq. 33,366.00"), "{}", item);
+  }
+
+  #[test]
+  fn running_narrow() {
+    insta::assert_snapshot!(render(&mut running(), 60, 30));
+  }
+
+  #[test]
+  fn running_waiting_for_db() {
+    insta::assert_snapshot!(render(&mut Running::new(CORE_ID, Pair::BTCUSDT), 100, 30));
+  }
+}
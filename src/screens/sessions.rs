@@ -1,27 +1,99 @@
 use super::{Screen, ScreenId};
 use crate::{
-  action::Action,
-  components::style::{button, default_layout, outer_container_block, stylized_block},
-  config::{Config, KeyBindings},
+  action::{Action, MoveDirection},
+  components::{
+    list::{LabelValueItem, List},
+    style::{button, disabled_button},
+  },
+  config::Config,
+  database::{error::DatabaseError, fetch_sessions, SessionRecord},
 };
 use crossterm::event::{KeyCode, KeyEvent};
 use eyre::Result;
 use ratatui::{prelude::*, widgets::*};
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration};
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
 use uuid::Uuid;
 
+/// Stored sessions, latest first, with the label and notes they were started with. Two
+/// of them marked with space are compared side by side.
 #[derive(Default)]
 pub struct Sessions {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
+  sessions_rx: Option<oneshot::Receiver<Result<Vec<SessionRecord>, DatabaseError>>>,
+  sessions: Vec<SessionRecord>,
+  sessions_list: List<LabelValueItem<String>>,
+  loaded: bool,
+  error: Option<String>,
+  /// Sessions loaded since the last draw.
+  dirty: bool,
+  /// Indices of the sessions to compare, the first one is the baseline.
+  marked: Vec<usize>,
+  selected_action: usize,
 }
 
 impl Sessions {
   pub fn new() -> Self {
     Self::default()
   }
+
+  fn receive_sessions(&mut self) {
+    let Some(sessions_rx) = &mut self.sessions_rx else {
+      return;
+    };
+    match sessions_rx.try_recv() {
+      Ok(Ok(sessions)) => {
+        self.sessions = sessions;
+        self.update_items();
+        self.loaded = true;
+      },
+      Ok(Err(e)) => self.error = Some(e.to_string()),
+      Err(oneshot::error::TryRecvError::Empty) => return,
+      Err(oneshot::error::TryRecvError::Closed) => {
+        self.error = Some("Loading sessions was interrupted.".to_string())
+      },
+    }
+    self.sessions_rx = None;
+    self.dirty = true;
+  }
+
+  fn update_items(&mut self) {
+    let items = self
+      .sessions
+      .iter()
+      .enumerate()
+      .map(|(index, session)| {
+        let mark = self.marked.iter().position(|marked| *marked == index);
+        session_item(session, mark)
+      })
+      .collect();
+    self.sessions_list.update_items(items);
+  }
+
+  /// Marks the selected session for comparison, or unmarks it. Marking a third one
+  /// replaces the second.
+  fn toggle_mark(&mut self) {
+    let Some(index) = self.sessions_list.selected_index() else {
+      return;
+    };
+    if let Some(position) = self.marked.iter().position(|marked| *marked == index) {
+      self.marked.remove(position);
+    } else {
+      self.marked.truncate(1);
+      self.marked.push(index);
+    }
+    self.update_items();
+    self.dirty = true;
+  }
+
+  /// Screen comparing the marked sessions, once there are two.
+  fn comparison(&self) -> Option<ScreenId> {
+    let [first, second] = self.marked.as_slice() else {
+      return None;
+    };
+    let core_id = |index: &usize| Uuid::parse_str(&self.sessions[*index].core_id).ok();
+    Some(ScreenId::COMPARE(core_id(first)?, core_id(second)?))
+  }
 }
 
 impl Screen for Sessions {
@@ -35,14 +107,48 @@ impl Screen for Sessions {
     Ok(())
   }
 
+  fn init(&mut self, _area: Rect) -> Result<()> {
+    let (sessions_tx, sessions_rx) = oneshot::channel();
+    tokio::spawn(async move {
+      let _ = sessions_tx.send(fetch_sessions().await);
+    });
+    self.sessions_rx = Some(sessions_rx);
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if key.code == KeyCode::Char(' ') {
+      self.toggle_mark();
+    }
+    Ok(None)
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
-      Action::Tick => {
-        // Get stats
+      Action::Tick => self.receive_sessions(),
+      Action::Move(MoveDirection::Up) => self.sessions_list.previous(),
+      Action::Move(MoveDirection::Down) => self.sessions_list.next(),
+      Action::Copy => {
+        if let Some(item) = self.sessions_list.get_selected() {
+          return Ok(Some(Action::Clipboard(item.to_string())));
+        }
       },
+      Action::CopyId => {
+        let session =
+          self.sessions_list.selected_index().and_then(|i| self.sessions.get(i));
+        if let Some(session) = session {
+          return Ok(Some(Action::Clipboard(session.core_id.clone())));
+        }
+      },
+      Action::Move(MoveDirection::Left) => self.selected_action = 0,
+      Action::Move(MoveDirection::Right) => self.selected_action = 1,
       Action::Accept => {
-        if let Some(command_tx) = &self.command_tx {
-          command_tx.send(Action::Navigate(ScreenId::HOME))?;
+        let action = match (self.selected_action, self.comparison()) {
+          (0, _) => Some(Action::NavigateBack),
+          (_, comparison) => comparison.map(Action::Navigate),
+        };
+        if let Some((command_tx, action)) = self.command_tx.as_ref().zip(action) {
+          command_tx.send(action)?;
         }
       },
       _ => {},
@@ -51,19 +157,58 @@ impl Screen for Sessions {
   }
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    self.dirty = false;
     let content_layout = Layout::default()
       .constraints(vec![Constraint::Min(0), Constraint::Length(3)])
       .split(area);
     let button_layout = Layout::default()
       .direction(Direction::Horizontal)
       .constraints(vec![
-        Constraint::Percentage(40),
         Constraint::Percentage(20),
-        Constraint::Percentage(40),
+        Constraint::Percentage(30),
+        Constraint::Length(1),
+        Constraint::Percentage(30),
+        Constraint::Percentage(20),
       ])
       .split(content_layout[1]);
-    f.render_widget(Paragraph::new("TODO: List of sessions"), content_layout[0]);
-    f.render_widget(button("Back", true), button_layout[1]);
+    match (&self.error, self.loaded) {
+      (Some(error), _) => {
+        f.render_widget(Paragraph::new(format!("Error: {error}")), content_layout[0])
+      },
+      (None, false) => {
+        f.render_widget(Paragraph::new("Loading sessions..."), content_layout[0])
+      },
+      (None, true) if self.sessions_list.is_empty() => {
+        f.render_widget(Paragraph::new("No sessions yet."), content_layout[0])
+      },
+      (None, true) => self.sessions_list.draw(f, content_layout[0])?,
+    }
+    f.render_widget(button("Back", self.selected_action == 0), button_layout[1]);
+    let compare = if self.comparison().is_some() {
+      button("Compare", self.selected_action == 1)
+    } else {
+      disabled_button("Compare (mark two)")
+    };
+    f.render_widget(compare, button_layout[3]);
     Ok(())
   }
+
+  fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+}
+
+/// "2024-03-01 12:00 backtest" and "rsi-threshold-0.7 test: lower threshold", with the
+/// place of the session in the comparison when it is marked.
+fn session_item(session: &SessionRecord, mark: Option<usize>) -> LabelValueItem<String> {
+  let kind = if session.is_backtest { "backtest" } else { "live" };
+  let mark = mark.map(|mark| format!("[{}] ", mark + 1)).unwrap_or_default();
+  let label = format!("{mark}{} {kind}", session.started_at.format("%Y-%m-%d %H:%M"));
+  let value = match (session.label.as_str(), session.notes.as_str()) {
+    ("", "") => session.core_id.clone(),
+    (label, "") => label.to_string(),
+    ("", notes) => notes.to_string(),
+    (label, notes) => format!("{label}: {notes}"),
+  };
+  LabelValueItem::new(label, value)
 }
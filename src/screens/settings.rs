@@ -0,0 +1,223 @@
+use super::Screen;
+use crate::{
+  action::{Action, MoveDirection},
+  components::{form::select::Select, style::button},
+  config::Config,
+  tui::RenderSettings,
+};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+use tokio::sync::mpsc::UnboundedSender;
+
+const TICK_RATES: [f64; 6] = [0.5, 1.0, 2.0, 4.0, 10.0, 20.0];
+const FRAME_RATES: [f64; 6] = [5.0, 10.0, 15.0, 30.0, 60.0, 120.0];
+
+#[derive(Default, PartialEq, EnumIter, EnumCount, Clone, Copy)]
+enum SelectedField {
+  #[default]
+  TickRate,
+  FrameRate,
+  Adaptive,
+  Actions,
+}
+
+/// Tick and frame rates and adaptive rendering, applied without a restart.
+#[derive(Default)]
+pub struct Settings {
+  command_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  selected_field: SelectedField,
+  selected_field_index: usize,
+  is_field_being_edited: bool,
+  selected_action: usize,
+  tick_rate: Select<f64>,
+  frame_rate: Select<f64>,
+  adaptive: Select<bool>,
+}
+
+/// Presets with the current rate among them, which may be any rate from the command line.
+fn rates(presets: &[f64], current: f64) -> Vec<f64> {
+  let mut rates = presets.to_vec();
+  if !rates.contains(&current) {
+    rates.push(current);
+    rates.sort_by(f64::total_cmp);
+  }
+  rates
+}
+
+impl Settings {
+  pub fn new(current: RenderSettings) -> Self {
+    let mut settings = Self {
+      tick_rate: Select::new(
+        rates(&TICK_RATES, current.tick_rate),
+        Some(current.tick_rate),
+        Some("Ticks per second".to_string()),
+      ),
+      frame_rate: Select::new(
+        rates(&FRAME_RATES, current.frame_rate),
+        Some(current.frame_rate),
+        Some("Frames per second".to_string()),
+      ),
+      adaptive: Select::new(
+        vec![false, true],
+        Some(current.adaptive),
+        Some("Draw only on changes".to_string()),
+      ),
+      ..Self::default()
+    };
+    settings.set_field_active(SelectedField::TickRate);
+    settings
+  }
+
+  fn set_field_active(&mut self, selected_field: SelectedField) {
+    self.tick_rate.set_active(selected_field == SelectedField::TickRate);
+    self.frame_rate.set_active(selected_field == SelectedField::FrameRate);
+    self.adaptive.set_active(selected_field == SelectedField::Adaptive);
+  }
+
+  fn select_field(&mut self, index: usize) {
+    self.selected_field_index = index;
+    self.selected_field =
+      SelectedField::iter().nth(index).unwrap_or(SelectedField::Actions);
+    self.set_field_active(self.selected_field);
+  }
+
+  fn field(&mut self, field: SelectedField) -> Option<&mut Select<f64>> {
+    match field {
+      SelectedField::TickRate => Some(&mut self.tick_rate),
+      SelectedField::FrameRate => Some(&mut self.frame_rate),
+      SelectedField::Adaptive | SelectedField::Actions => None,
+    }
+  }
+
+  fn render_settings(&self) -> Option<RenderSettings> {
+    Some(RenderSettings {
+      tick_rate: self.tick_rate.value()?,
+      frame_rate: self.frame_rate.value()?,
+      adaptive: self.adaptive.value()?,
+    })
+  }
+}
+
+impl Screen for Settings {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Move(direction) => match direction {
+        MoveDirection::Left => self.selected_action = 0,
+        MoveDirection::Right => self.selected_action = 1,
+        MoveDirection::Down if self.is_field_being_edited => {
+          match self.field(self.selected_field) {
+            Some(rate) => rate.edit_next(),
+            None => self.adaptive.edit_next(),
+          }
+        },
+        MoveDirection::Up if self.is_field_being_edited => {
+          match self.field(self.selected_field) {
+            Some(rate) => rate.edit_previous(),
+            None => self.adaptive.edit_previous(),
+          }
+        },
+        MoveDirection::Down => {
+          self.select_field((self.selected_field_index + 1) % SelectedField::COUNT)
+        },
+        MoveDirection::Up => {
+          self.select_field(self.selected_field_index.saturating_sub(1))
+        },
+      },
+      Action::Accept => match self.selected_field {
+        SelectedField::Actions => {
+          if let Some(command_tx) = &self.command_tx {
+            if self.selected_action == 0 {
+              if let Some(settings) = self.render_settings() {
+                command_tx.send(Action::SetRenderSettings(settings))?;
+              }
+            }
+            command_tx.send(Action::NavigateBack)?;
+          }
+        },
+        SelectedField::Adaptive => {
+          self.is_field_being_edited = self.adaptive.toggle_edit();
+        },
+        field => {
+          self.is_field_being_edited =
+            self.field(field).is_some_and(|rate| rate.toggle_edit());
+        },
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let content_layout =
+      Layout::vertical(vec![Constraint::Min(0), Constraint::Length(3)]).split(area);
+    let form_layout = Layout::vertical(vec![
+      Constraint::Length(2),
+      Constraint::Length(2),
+      Constraint::Length(2),
+      Constraint::Min(0),
+    ])
+    .split(content_layout[0]);
+    self.tick_rate.draw(f, form_layout[0])?;
+    self.frame_rate.draw(f, form_layout[1])?;
+    self.adaptive.draw(f, form_layout[2])?;
+    f.render_widget(
+      Paragraph::new(
+        "Ticks refresh the data of the screens, frames draw them. Drawing only on \
+         changes skips the frames of idle screens.",
+      )
+      .wrap(Wrap { trim: true })
+      .style(Style::new().dim()),
+      form_layout[3].inner(&Margin { horizontal: 0, vertical: 1 }),
+    );
+    match self.selected_field {
+      SelectedField::Adaptive => self.adaptive.draw_edit(f, content_layout[0])?,
+      field => {
+        if let Some(rate) = self.field(field) {
+          rate.draw_edit(f, content_layout[0])?
+        }
+      },
+    }
+
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(20),
+      Constraint::Percentage(30),
+      Constraint::Length(1),
+      Constraint::Percentage(30),
+      Constraint::Percentage(20),
+    ])
+    .split(content_layout[1]);
+    let actions = self.selected_field == SelectedField::Actions;
+    let selected = |action| actions && self.selected_action == action;
+    f.render_widget(button("APPLY", selected(0)), button_layout[1]);
+    f.render_widget(button("BACK", selected(1)), button_layout[3]);
+    Ok(())
+  }
+
+  fn is_dirty(&self) -> bool {
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::screens::fixtures::render;
+
+  #[test]
+  fn settings() {
+    let current = RenderSettings { tick_rate: 1.0, frame_rate: 60.0, adaptive: true };
+    insta::assert_snapshot!(render(&mut Settings::new(current), 80, 20));
+  }
+}
@@ -0,0 +1,266 @@
+use super::{Screen, ScreenId};
+use crate::{
+  action::{Action, MoveDirection},
+  assets::Pair,
+  components::{
+    glyphs::glyphs,
+    style::button,
+    table::{Cell, Column, Table, TableRow},
+  },
+  config::Config,
+  database::snapshot::DatabaseSnapshot,
+  portfolio::position::Position,
+  utils::formatting::{
+    dt_to_readable, format_amount, format_number, format_signed_number, readable_duration,
+  },
+};
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use std::cmp::Ordering;
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::sync::{mpsc::UnboundedSender, watch};
+use uuid::Uuid;
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Display, EnumIter)]
+enum TradeColumn {
+  Entry,
+  #[default]
+  Exit,
+  Side,
+  Quantity,
+  #[strum(serialize = "PnL")]
+  ProfitLoss,
+  Fees,
+  Duration,
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Display)]
+enum TradeFilter {
+  #[default]
+  All,
+  Wins,
+  Losses,
+}
+
+/// Exited position as a row of the trades table.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct TradeRow {
+  entry: DateTime<Utc>,
+  exit: DateTime<Utc>,
+  side: String,
+  pair: Pair,
+  quantity: f64,
+  profit_loss: f64,
+  fees: f64,
+  duration: String,
+}
+
+impl From<&Position> for TradeRow {
+  fn from(position: &Position) -> Self {
+    let exit = position
+      .meta
+      .exit_balance
+      .map(|balance| balance.time)
+      .unwrap_or(position.meta.update_time);
+    Self {
+      entry: position.meta.enter_time,
+      exit,
+      side: format!("{:?}", position.side),
+      pair: position.asset,
+      quantity: position.quantity,
+      profit_loss: position.realised_profit_loss,
+      fees: position.enter_fees_total + position.exit_fees_total,
+      duration: readable_duration(position.meta.enter_time, exit),
+    }
+  }
+}
+
+impl TradeRow {
+  fn compare(&self, other: &Self, column: TradeColumn) -> Ordering {
+    match column {
+      TradeColumn::Entry => self.entry.cmp(&other.entry),
+      TradeColumn::Exit => self.exit.cmp(&other.exit),
+      TradeColumn::Side => self.side.cmp(&other.side),
+      TradeColumn::Quantity => self.quantity.total_cmp(&other.quantity),
+      TradeColumn::ProfitLoss => self.profit_loss.total_cmp(&other.profit_loss),
+      TradeColumn::Fees => self.fees.total_cmp(&other.fees),
+      TradeColumn::Duration => (self.exit - self.entry).cmp(&(other.exit - other.entry)),
+    }
+  }
+}
+
+impl TableRow for TradeRow {
+  fn columns() -> Vec<Column> {
+    TradeColumn::iter()
+      .map(|column| {
+        let title = column.to_string();
+        match column {
+          TradeColumn::Entry | TradeColumn::Exit => {
+            Column::new(title, Constraint::Ratio(3, 16))
+          },
+          TradeColumn::Side => Column::new(title, Constraint::Ratio(1, 16)),
+          _ => Column::new(title, Constraint::Ratio(9, 64)).right(),
+        }
+      })
+      .collect()
+  }
+
+  fn cells(&self) -> Vec<Cell> {
+    vec![
+      dt_to_readable(self.entry).into(),
+      dt_to_readable(self.exit).into(),
+      self.side.clone().into(),
+      format_amount(self.quantity, self.pair.base()).into(),
+      format_signed_number(self.profit_loss).into(),
+      format_number(self.fees).into(),
+      self.duration.clone().into(),
+    ]
+  }
+}
+
+/// Exited positions of a session. Left/right picks the sort column, `s` flips the
+/// order and `f` cycles between all, winning and losing trades.
+#[derive(Default)]
+pub struct Trades {
+  command_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  snapshot: Option<watch::Receiver<DatabaseSnapshot>>,
+  core_id: Uuid,
+  trades_list: Table<TradeRow>,
+  sort_column: TradeColumn,
+  descending: bool,
+  filter: TradeFilter,
+}
+
+impl Trades {
+  pub fn new(core_id: Uuid) -> Self {
+    Self { core_id, descending: true, ..Self::default() }
+  }
+
+  fn rows(&self, positions: &[Position]) -> Vec<TradeRow> {
+    let mut rows: Vec<TradeRow> = positions
+      .iter()
+      .map(TradeRow::from)
+      .filter(|row| match self.filter {
+        TradeFilter::All => true,
+        TradeFilter::Wins => row.profit_loss > 0.0,
+        TradeFilter::Losses => row.profit_loss < 0.0,
+      })
+      .collect();
+    rows.sort_by(|a, b| {
+      let ordering = a.compare(b, self.sort_column);
+      if self.descending {
+        ordering.reverse()
+      } else {
+        ordering
+      }
+    });
+    rows
+  }
+
+  fn shift_sort_column(&mut self, forward: bool) {
+    let columns: Vec<TradeColumn> = TradeColumn::iter().collect();
+    let index =
+      columns.iter().position(|column| *column == self.sort_column).unwrap_or(0);
+    let index = if forward {
+      (index + 1) % columns.len()
+    } else {
+      (index + columns.len() - 1) % columns.len()
+    };
+    self.sort_column = columns[index];
+  }
+}
+
+impl Screen for Trades {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn register_snapshot_handler(
+    &mut self,
+    snapshot: watch::Receiver<DatabaseSnapshot>,
+  ) -> Result<()> {
+    self.snapshot = Some(snapshot);
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    match key.code {
+      KeyCode::Char('s') => self.descending = !self.descending,
+      KeyCode::Char('f') => {
+        self.filter = match self.filter {
+          TradeFilter::All => TradeFilter::Wins,
+          TradeFilter::Wins => TradeFilter::Losses,
+          TradeFilter::Losses => TradeFilter::All,
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Tick => {
+        if let Some(snapshot) = &self.snapshot {
+          let rows = match snapshot.borrow().exited_positions.get(&self.core_id) {
+            Some(positions) => self.rows(positions),
+            None => Vec::new(),
+          };
+          self.trades_list.update_items(rows);
+        }
+      },
+      Action::Move(direction) => match direction {
+        MoveDirection::Up => self.trades_list.previous(),
+        MoveDirection::Down => self.trades_list.next(),
+        MoveDirection::Left => self.shift_sort_column(false),
+        MoveDirection::Right => self.shift_sort_column(true),
+      },
+      Action::Accept => {
+        if let Some(command_tx) = &self.command_tx {
+          command_tx.send(Action::NavigateBack)?;
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let content_layout = Layout::default()
+      .constraints(vec![Constraint::Length(2), Constraint::Min(0), Constraint::Length(3)])
+      .split(area);
+    let button_layout = Layout::horizontal(vec![
+      Constraint::Percentage(40),
+      Constraint::Percentage(20),
+      Constraint::Percentage(40),
+    ])
+    .split(content_layout[2]);
+    let order = if self.descending { "descending" } else { "ascending" };
+    f.render_widget(
+      Paragraph::new(format!(
+        "{} trades, sorted by {} ({}). {} column, s order, f filter",
+        self.filter,
+        self.sort_column,
+        order,
+        glyphs().left_right
+      )),
+      content_layout[0],
+    );
+    if self.trades_list.is_empty() {
+      f.render_widget(Paragraph::new("No exited trades."), content_layout[1]);
+    } else {
+      self.trades_list.draw(f, content_layout[1])?;
+    }
+    f.render_widget(button("Back", true), button_layout[1]);
+    Ok(())
+  }
+}
@@ -0,0 +1,71 @@
+use crate::portfolio::position::Position;
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+/// File of the trades in the session directory, next to the summary.
+pub const TRADES_FILE: &str = "trades.csv";
+const HEADER: &str = "pair,side,entry_time,exit_time,holding_seconds,quantity,\
+                      entry_price,exit_price,gross_pnl,fees,slippage,net_pnl,\
+                      equity_before,equity_after,execution_slippage";
+
+/// What an exited position did to the equity. Amounts are in the quote asset of its pair,
+/// the equity is the session's balance of that asset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TradeAttribution {
+  pub position: Position,
+  pub exit_time: DateTime<Utc>,
+  /// PnL before fees and slippage.
+  pub gross_pnl: f64,
+  /// Exchange fees of the entry and exit, without the slippage.
+  pub fees: f64,
+  pub equity_before: f64,
+  pub equity_after: f64,
+}
+
+impl TradeAttribution {
+  /// None for a position that has not been exited.
+  pub fn from_position(position: &Position) -> Option<Self> {
+    let exit_balance = position.meta.exit_balance?;
+    let costs = position.enter_fees_total + position.exit_fees_total;
+    Some(Self {
+      position: position.clone(),
+      exit_time: exit_balance.time,
+      gross_pnl: position.realised_profit_loss + costs,
+      fees: costs - position.slippage_total,
+      equity_before: exit_balance.total - position.realised_profit_loss,
+      equity_after: exit_balance.total,
+    })
+  }
+}
+
+/// Exited positions as CSV, one trade per line in the order of the positions.
+pub fn trades_csv(positions: &[Position]) -> String {
+  let mut csv = format!("{HEADER}\n");
+  for trade in positions.iter().filter_map(TradeAttribution::from_position) {
+    let position = &trade.position;
+    let holding = trade.exit_time - position.meta.enter_time;
+    let _ = writeln!(
+      csv,
+      "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+      position.asset,
+      position.side,
+      position.meta.enter_time.to_rfc3339(),
+      trade.exit_time.to_rfc3339(),
+      holding.num_seconds(),
+      position.quantity,
+      position.enter_avg_price_gross,
+      position.exit_avg_price_gross,
+      trade.gross_pnl,
+      trade.fees,
+      position.slippage_total,
+      position.realised_profit_loss,
+      trade.equity_before,
+      trade.equity_after,
+      position
+        .execution_slippage()
+        .map(|slippage| slippage.to_string())
+        .unwrap_or_default()
+    );
+  }
+  csv
+}
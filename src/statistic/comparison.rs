@@ -0,0 +1,157 @@
+use super::{StatisticConfig, TradingSummary};
+use crate::portfolio::position::Position;
+use chrono::Utc;
+
+/// Starting equity of sessions without exited positions to derive it from.
+const DEFAULT_STARTING_EQUITY: f64 = 1000.0;
+
+/// Which way a metric improves, for coloring the difference.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Better {
+  Higher,
+  Lower,
+  Neither,
+}
+
+/// Metric of two sessions and how the second one differs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricDiff {
+  pub label: &'static str,
+  pub first: f64,
+  pub second: f64,
+  pub better: Better,
+}
+
+impl MetricDiff {
+  pub fn diff(&self) -> f64 {
+    self.second - self.first
+  }
+
+  /// Whether the second session did better, none when they are even or it does not
+  /// matter.
+  pub fn improved(&self) -> Option<bool> {
+    let diff = self.diff();
+    if diff == 0.0 || !diff.is_finite() {
+      return None;
+    }
+    match self.better {
+      Better::Higher => Some(diff > 0.0),
+      Better::Lower => Some(diff < 0.0),
+      Better::Neither => None,
+    }
+  }
+}
+
+/// Key metrics and equity curves of two stored sessions, from their exited positions, to
+/// see what a change of parameters or models did.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionComparison {
+  pub metrics: Vec<MetricDiff>,
+  /// Cumulative realised PnL after each exited position of either session.
+  pub equity: [Vec<f64>; 2],
+}
+
+impl SessionComparison {
+  pub fn new(first: &[Position], second: &[Position]) -> Self {
+    let (a, b) = (summary(first), summary(second));
+    let metric = |label, better, value: fn(&TradingSummary) -> f64| MetricDiff {
+      label,
+      first: value(&a),
+      second: value(&b),
+      better,
+    };
+    let metrics = vec![
+      metric("Trades", Better::Neither, |s| s.trades.trades as f64),
+      metric("Win rate %", Better::Higher, |s| s.trades.win_rate() * 100.0),
+      metric("Profit factor", Better::Higher, |s| s.trades.profit_factor()),
+      metric("Expectancy", Better::Higher, |s| s.trades.expectancy()),
+      metric("Total PnL", Better::Higher, |s| s.pnl.total_pnl),
+      metric("Fees", Better::Lower, |s| s.costs.fees),
+      metric("Max drawdown", Better::Lower, |s| {
+        s.drawdown.max_drawdown.drawdown.drawdown
+      }),
+      metric("Longest losing streak", Better::Lower, |s| {
+        s.trades.longest_losing_streak as f64
+      }),
+      metric("Sharpe ratio", Better::Higher, |s| s.tear_sheet.sharpe_ratio.daily()),
+      metric("Sortino ratio", Better::Higher, |s| s.tear_sheet.sortino_ratio.daily()),
+    ];
+    Self { metrics, equity: [equity_curve(first), equity_curve(second)] }
+  }
+}
+
+/// Statistics of the positions, from the equity before the first of them.
+fn summary(positions: &[Position]) -> TradingSummary {
+  let first = positions.first();
+  let starting_equity = first
+    .and_then(|position| {
+      let exit_balance = position.meta.exit_balance?;
+      Some(exit_balance.total - position.realised_profit_loss)
+    })
+    .unwrap_or(DEFAULT_STARTING_EQUITY);
+  let created_at = first.map_or_else(Utc::now, |position| position.meta.enter_time);
+  let config = StatisticConfig {
+    starting_equity,
+    trading_days_per_year: 365,
+    risk_free_return: 0.0,
+    created_at,
+    reporting_currency: Default::default(),
+  };
+  let mut summary = TradingSummary::init(config, None);
+  summary.generate_summary(positions);
+  summary
+}
+
+fn equity_curve(positions: &[Position]) -> Vec<f64> {
+  positions
+    .iter()
+    .scan(0.0, |equity, position| {
+      *equity += position.realised_profit_loss;
+      Some(*equity)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    portfolio::{balance::Balance, fixtures::fill},
+    strategy::Decision,
+    trading::execution::Fees,
+  };
+  use chrono::DateTime;
+  use uuid::Uuid;
+
+  /// Long of one unit from 100 to `exit_price`, in a session with 1000 of equity.
+  fn round_trips(exit_prices: &[f64]) -> Vec<Position> {
+    let time = DateTime::UNIX_EPOCH;
+    let mut balance = Balance { time, total: 1000.0, available: 1000.0 };
+    exit_prices
+      .iter()
+      .map(|exit_price| {
+        let enter = fill(Decision::Long, 1.0, 100.0, Fees::default());
+        let exit = fill(Decision::CloseLong, -1.0, *exit_price, Fees::default());
+        let mut position = Position::enter(Uuid::new_v4(), &enter).unwrap();
+        position.exit(balance, &exit).unwrap();
+        balance = position.meta.exit_balance.unwrap();
+        position
+      })
+      .collect()
+  }
+
+  #[test]
+  fn compares_metrics_and_equity_curves() {
+    let first = round_trips(&[110.0, 80.0]);
+    let second = round_trips(&[115.0, 105.0, 95.0]);
+    let comparison = SessionComparison::new(&first, &second);
+
+    assert_eq!(comparison.equity, [vec![10.0, -10.0], vec![15.0, 20.0, 15.0]]);
+    let metric = |label| comparison.metrics.iter().find(|m| m.label == label).unwrap();
+    assert_eq!(metric("Trades").diff(), 1.0);
+    assert_eq!(metric("Trades").improved(), None);
+    assert_eq!(metric("Total PnL").diff(), 25.0);
+    assert_eq!(metric("Total PnL").improved(), Some(true));
+    assert_eq!(metric("Win rate %").first, 50.0);
+  }
+}
@@ -0,0 +1,131 @@
+use crate::{
+  assets::Pair,
+  database::{error::DatabaseError, fetch_latest_closes},
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+/// Closes of a pair by candle open time, oldest first.
+pub type Closes = Vec<(DateTime<Utc>, f64)>;
+
+/// Returns of fewer candles both pairs closed are not correlated.
+const MIN_COMMON_RETURNS: usize = 3;
+
+/// Pearson correlations between the candle returns of pairs, over the candles both of
+/// them closed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CorrelationMatrix {
+  pub pairs: Vec<Pair>,
+  /// Row by row, none where the pairs have too few common returns or one did not move.
+  values: Vec<Option<f64>>,
+}
+
+impl CorrelationMatrix {
+  pub fn new(closes: &[(Pair, Closes)]) -> Self {
+    let returns: Vec<HashMap<DateTime<Utc>, f64>> =
+      closes.iter().map(|(_, closes)| returns(closes)).collect();
+    let values = returns
+      .iter()
+      .enumerate()
+      .flat_map(|(row, a)| {
+        returns.iter().enumerate().map(move |(column, b)| match row == column {
+          true => Some(1.0),
+          false => correlation(a, b),
+        })
+      })
+      .collect();
+    Self { pairs: closes.iter().map(|(pair, _)| *pair).collect(), values }
+  }
+
+  pub fn get(&self, a: Pair, b: Pair) -> Option<f64> {
+    let row = self.pairs.iter().position(|pair| *pair == a)?;
+    let column = self.pairs.iter().position(|pair| *pair == b)?;
+    self.values[row * self.pairs.len() + column]
+  }
+}
+
+/// Correlations of the returns of every pair over its last `window` stored candles.
+pub async fn rolling_correlations(
+  window: usize,
+) -> Result<CorrelationMatrix, DatabaseError> {
+  let mut closes = Vec::new();
+  for pair in Pair::iter().filter(|pair| !pair.is_synthetic()) {
+    closes.push((pair, fetch_latest_closes(pair, window + 1).await?));
+  }
+  Ok(CorrelationMatrix::new(&closes))
+}
+
+/// Return of each candle from the close of the one before, by its open time.
+fn returns(closes: &[(DateTime<Utc>, f64)]) -> HashMap<DateTime<Utc>, f64> {
+  closes
+    .windows(2)
+    .filter(|candles| candles[0].1 > 0.0)
+    .map(|candles| (candles[1].0, candles[1].1 / candles[0].1 - 1.0))
+    .collect()
+}
+
+fn correlation(
+  a: &HashMap<DateTime<Utc>, f64>,
+  b: &HashMap<DateTime<Utc>, f64>,
+) -> Option<f64> {
+  let common: Vec<(f64, f64)> = a
+    .iter()
+    .filter_map(|(time, a_return)| Some((*a_return, *b.get(time)?)))
+    .collect();
+  if common.len() < MIN_COMMON_RETURNS {
+    return None;
+  }
+  let n = common.len() as f64;
+  let mean_a = common.iter().map(|(a, _)| a).sum::<f64>() / n;
+  let mean_b = common.iter().map(|(_, b)| b).sum::<f64>() / n;
+  let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+  for (a, b) in &common {
+    covariance += (a - mean_a) * (b - mean_b);
+    variance_a += (a - mean_a).powi(2);
+    variance_b += (b - mean_b).powi(2);
+  }
+  if variance_a == 0.0 || variance_b == 0.0 {
+    return None;
+  }
+  Some(covariance / (variance_a * variance_b).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Duration;
+
+  fn closes(prices: &[f64]) -> Closes {
+    let start = DateTime::<Utc>::default();
+    let minute = |i: usize| start + Duration::try_minutes(i as i64).unwrap();
+    prices.iter().enumerate().map(|(i, price)| (minute(i), *price)).collect()
+  }
+
+  fn correlation_of(btc: &[f64], eth: &[f64]) -> Option<f64> {
+    let (btc, eth) = ((Pair::BTCUSDT, closes(btc)), (Pair::ETHBTC, closes(eth)));
+    let matrix = CorrelationMatrix::new(&[btc, eth]);
+    assert_eq!(matrix.get(Pair::BTCUSDT, Pair::BTCUSDT), Some(1.0));
+    let correlation = matrix.get(Pair::BTCUSDT, Pair::ETHBTC);
+    assert_eq!(correlation, matrix.get(Pair::ETHBTC, Pair::BTCUSDT));
+    correlation
+  }
+
+  #[test]
+  fn correlates_returns_of_common_candles() {
+    let prices = [100.0, 101.0, 99.0, 102.0, 104.0];
+    let doubled = prices.map(|price| price * 2.0);
+    // Every return of the mirror is the opposite of the pair's
+    let mut mirror = vec![100.0];
+    for candles in prices.windows(2) {
+      let last = mirror[mirror.len() - 1];
+      mirror.push(last * (2.0 - candles[1] / candles[0]));
+    }
+    let correlation = correlation_of(&prices, &doubled).unwrap();
+    assert!((correlation - 1.0).abs() < 1e-9, "{correlation}");
+    let correlation = correlation_of(&prices, &mirror).unwrap();
+    assert!((correlation + 1.0).abs() < 1e-9, "{correlation}");
+    // Two common returns are too few
+    assert_eq!(correlation_of(&prices, &prices[..3]), None);
+  }
+}
@@ -0,0 +1,80 @@
+use crate::portfolio::position::Position;
+use chrono::{Datelike, Timelike, Weekday};
+
+pub const HOURS: usize = 24;
+pub const WEEKDAYS: [Weekday; 7] = [
+  Weekday::Mon,
+  Weekday::Tue,
+  Weekday::Wed,
+  Weekday::Thu,
+  Weekday::Fri,
+  Weekday::Sat,
+  Weekday::Sun,
+];
+
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct PnLBucket {
+  pub profit_loss: f64,
+  pub trades: usize,
+}
+
+/// Realised profit and loss of exited positions, bucketed by the UTC weekday and hour
+/// their exit happened in.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PnLHeatmap {
+  buckets: [[PnLBucket; HOURS]; WEEKDAYS.len()],
+}
+
+impl PnLHeatmap {
+  pub fn from_positions(positions: &[Position]) -> Self {
+    let mut heatmap = Self::default();
+    for position in positions {
+      heatmap.update(position);
+    }
+    heatmap
+  }
+
+  /// Adds an exited position to its bucket, open positions are skipped.
+  pub fn update(&mut self, position: &Position) {
+    let Some(exit_balance) = position.meta.exit_balance else {
+      return;
+    };
+    let weekday = exit_balance.time.weekday().num_days_from_monday() as usize;
+    let hour = exit_balance.time.hour() as usize;
+    let bucket = &mut self.buckets[weekday][hour];
+    bucket.profit_loss += position.realised_profit_loss;
+    bucket.trades += 1;
+  }
+
+  pub fn bucket(&self, weekday: Weekday, hour: usize) -> PnLBucket {
+    self.buckets[weekday.num_days_from_monday() as usize][hour % HOURS]
+  }
+
+  /// Largest absolute bucket PnL, used to scale colour intensity.
+  pub fn max_abs(&self) -> f64 {
+    self
+      .buckets
+      .iter()
+      .flatten()
+      .map(|bucket| bucket.profit_loss.abs())
+      .fold(0.0, f64::max)
+  }
+
+  pub fn trades(&self) -> usize {
+    self.buckets.iter().flatten().map(|bucket| bucket.trades).sum()
+  }
+
+  /// Buckets with the highest and the lowest PnL, among buckets that had trades.
+  pub fn best_and_worst(&self) -> Option<((Weekday, usize), (Weekday, usize))> {
+    let traded: Vec<(Weekday, usize, f64)> = WEEKDAYS
+      .iter()
+      .flat_map(|weekday| (0..HOURS).map(move |hour| (*weekday, hour)))
+      .map(|(weekday, hour)| (weekday, hour, self.bucket(weekday, hour)))
+      .filter(|(_, _, bucket)| bucket.trades > 0)
+      .map(|(weekday, hour, bucket)| (weekday, hour, bucket.profit_loss))
+      .collect();
+    let best = traded.iter().max_by(|a, b| a.2.total_cmp(&b.2))?;
+    let worst = traded.iter().min_by(|a, b| a.2.total_cmp(&b.2))?;
+    Some(((best.0, best.1), (worst.0, worst.1)))
+  }
+}
@@ -36,7 +36,7 @@ impl Default for Drawdown {
 
 impl Drawdown {
     /// Initialises a new [`Drawdown`] using the starting equity as the first peak.
-    pub fn init(starting_equity: f64) -> Self {
+    pub fn init(starting_equity: f64, start_time: DateTime<Utc>) -> Self {
         Self {
             equity_range: Range {
                 activated: true,
@@ -44,7 +44,7 @@ impl Drawdown {
                 low: starting_equity,
             },
             drawdown: 0.0,
-            start_time: Utc::now(),
+            start_time,
             duration: Duration::zero(),
         }
     }
@@ -193,3 +193,65 @@ impl AvgDrawdown {
         self.mean_duration = Duration::milliseconds(self.mean_duration_milliseconds);
     }
 }
+
+/// Drawdown of the Portfolio at one [`EquityPoint`], 0 at an equity peak.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+pub struct DrawdownPoint {
+    pub time: DateTime<Utc>,
+    pub drawdown: f64,
+}
+
+/// [`DrawdownCurve`] is the drawdown after every equity update, with the trough of the
+/// current drawdown kept to measure how long the recovery from it takes.
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Deserialize, Serialize)]
+pub struct DrawdownCurve {
+    pub points: Vec<DrawdownPoint>,
+    trough: Option<EquityPoint>,
+}
+
+impl DrawdownCurve {
+    /// Adds the drawdown at the input [`EquityPoint`]. Returns the time from the trough to
+    /// the new peak if the point ended a drawdown.
+    pub fn update(&mut self, current: EquityPoint, drawdown: &Drawdown) -> Option<Duration> {
+        self.points.push(DrawdownPoint {
+            time: current.time,
+            drawdown: drawdown.drawdown,
+        });
+        if !drawdown.is_waiting_for_peak() {
+            if self.trough.is_none_or(|trough| current.total <= trough.total) {
+                self.trough = Some(current);
+            }
+            return None;
+        }
+        self.trough
+            .take()
+            .map(|trough| current.time.signed_duration_since(trough.time))
+    }
+
+    pub fn values(&self) -> Vec<f64> {
+        self.points.iter().map(|point| point.drawdown).collect()
+    }
+}
+
+/// [`AvgRecovery`] is the mean time the Portfolio took from the trough of a drawdown back
+/// to a new equity peak.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default, Deserialize, Serialize)]
+pub struct AvgRecovery {
+    pub count: u64,
+    pub mean_recovery_milliseconds: i64,
+}
+
+impl AvgRecovery {
+    pub fn update(&mut self, recovery: Duration) {
+        self.count += 1;
+        self.mean_recovery_milliseconds = welford_online::calculate_mean(
+            self.mean_recovery_milliseconds,
+            recovery.num_milliseconds(),
+            self.count as i64,
+        );
+    }
+
+    pub fn mean_recovery(&self) -> Duration {
+        Duration::try_milliseconds(self.mean_recovery_milliseconds).unwrap_or_default()
+    }
+}
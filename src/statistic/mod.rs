@@ -1,30 +1,54 @@
+pub mod attribution;
+pub mod comparison;
+pub mod correlation;
 pub mod dispersion;
 pub mod error;
+pub mod heatmap;
 pub mod metric;
+pub mod session_time;
 pub mod summary_drawdown;
 pub mod summary_pnl;
+pub mod summary_rolling;
 pub mod welford_online;
 
 use self::{
-  metric::ratio::{CalmarRatio, SharpeRatio, SortinoRatio},
+  metric::{
+    drawdown::DrawdownCurve,
+    ratio::{CalmarRatio, SharpeRatio, SortinoRatio},
+  },
   summary_drawdown::DrawdownSummary,
-  summary_pnl::{PnLReturnSummary, ProfitLossSummary},
+  summary_pnl::{
+    CostSummary, ExecutionSummary, PnLReturnSummary, ProfitLossSummary,
+    TradeOutcomeSummary,
+  },
+  summary_rolling::RollingSummary,
 };
 use crate::{
+  assets::Asset,
   components::{list::LabelValueItem, ListDisplay},
   portfolio::position::Position,
-  utils::formatting::{dt_to_readable, duration_to_readable, readable_duration},
+  utils::formatting::{
+    dt_to_readable, duration_to_readable, format_number, readable_duration,
+  },
 };
 use chrono::{DateTime, Utc};
 use prettytable::{row, Cell, Row, Table};
 use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
 pub struct TradingSummary {
   pub pnl_returns: PnLReturnSummary,
   pub pnl: ProfitLossSummary,
   pub drawdown: DrawdownSummary,
   pub tear_sheet: TearSheet,
+  #[serde(default)]
+  pub trades: TradeOutcomeSummary,
+  #[serde(default)]
+  pub rolling: RollingSummary,
+  #[serde(default)]
+  pub costs: CostSummary,
+  #[serde(default)]
+  pub execution: ExecutionSummary,
   pub starting_time: DateTime<Utc>,
 }
 
@@ -34,8 +58,12 @@ impl TradingSummary {
     Self {
       pnl_returns: PnLReturnSummary::new(starting_time),
       pnl: ProfitLossSummary::new(),
-      drawdown: DrawdownSummary::new(config.starting_equity),
+      drawdown: DrawdownSummary::new(config.starting_equity, starting_time),
       tear_sheet: TearSheet::new(config.risk_free_return),
+      trades: TradeOutcomeSummary::default(),
+      rolling: RollingSummary::default(),
+      costs: CostSummary::default(),
+      execution: ExecutionSummary::default(),
       starting_time,
     }
   }
@@ -44,12 +72,55 @@ impl TradingSummary {
     self.drawdown.update(position);
     self.tear_sheet.update(&self.pnl_returns, &self.drawdown);
     self.pnl.update(position);
+    self.trades.update(position);
+    self.rolling.update(position);
+    self.costs.update(position);
+    self.execution.update(position);
   }
   pub fn generate_summary(&mut self, positions: &[Position]) {
     for position in positions.iter() {
       self.update(position)
     }
   }
+
+  /// All statistics as label/value rows, for showing the report inside the TUI.
+  pub fn generate_short_report(&self) -> Vec<LabelValueItem<String>> {
+    let item = |label: &str, value: String| LabelValueItem::new(label.to_string(), value);
+    let max_drawdown = &self.drawdown.max_drawdown.drawdown;
+    vec![
+      item("Trades", self.trades.trades.to_string()),
+      item("Wins / losses", format!("{} / {}", self.trades.wins, self.trades.losses)),
+      item("Win rate", format!("{:.2}%", self.trades.win_rate() * 100.0)),
+      item("Profit factor", format!("{:.3}", self.trades.profit_factor())),
+      item("Avg. win", format_number(self.trades.avg_win())),
+      item("Avg. loss", format_number(self.trades.avg_loss())),
+      item("Expectancy", format_number(self.trades.expectancy())),
+      item("Total PnL", format_number(self.pnl.total_pnl)),
+      item("Fees", format_number(self.costs.fees)),
+      item("Slippage", format_number(self.costs.slippage)),
+      item(
+        "Costs of gross PnL",
+        format!("{:.2}%", self.costs.share_of_gross_pnl() * 100.0),
+      ),
+      item("Execution slippage", format_number(self.execution.slippage)),
+      item("Slippage vs decision", format!("{:.2} bps", self.execution.mean_bps())),
+      item("Worst trade slippage", format!("{:.2} bps", self.execution.worst_bps)),
+      item("Mean return", format_number(self.pnl_returns.total.mean)),
+      item("Trades per day", format!("{:.3}", self.pnl_returns.trades_per_day)),
+      item("Max drawdown", format!("{:.3}", max_drawdown.drawdown)),
+      item("Max drawdown duration", duration_to_readable(&max_drawdown.duration)),
+      item("Avg. drawdown", format!("{:.3}", self.drawdown.avg_drawdown.mean_drawdown)),
+      item("Current drawdown", format!("{:.3}", self.drawdown.current_drawdown.drawdown)),
+      item(
+        "Avg. recovery time",
+        duration_to_readable(&self.drawdown.avg_recovery.mean_recovery()),
+      ),
+      item("Longest losing streak", self.trades.longest_losing_streak.to_string()),
+      item("Sharpe ratio", format!("{:.3}", self.tear_sheet.sharpe_ratio.daily())),
+      item("Sortino ratio", format!("{:.3}", self.tear_sheet.sortino_ratio.daily())),
+      item("Calmar ratio", format!("{:.3}", self.tear_sheet.calmar_ratio.daily())),
+    ]
+  }
 }
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
@@ -58,6 +129,10 @@ pub struct StatisticConfig {
   pub trading_days_per_year: usize,
   pub risk_free_return: f64,
   pub created_at: DateTime<Utc>,
+  /// Currency of the starting equity, statistics and PnL of pairs quoted in other assets
+  /// are converted to it.
+  #[serde(default)]
+  pub reporting_currency: Asset,
 }
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
@@ -132,22 +207,36 @@ pub trait TableBuilder {
 }
 
 pub fn combine(builders: Vec<(String, TradingSummary)>) -> Vec<Table> {
-  let mut tables = vec![Table::new(), Table::new(), Table::new(), Table::new()];
+  let mut tables = vec![
+    Table::new(),
+    Table::new(),
+    Table::new(),
+    Table::new(),
+    Table::new(),
+    Table::new(),
+    Table::new(),
+  ];
   builders.into_iter().enumerate().for_each(|(row_index, (id, trading_summary))| {
     // Insert rows for each table
     tables[0].add_row(trading_summary.pnl_returns.row());
     tables[1].add_row(trading_summary.tear_sheet.row());
     tables[2].add_row(trading_summary.drawdown.row());
     tables[3].add_row(trading_summary.pnl.row());
+    tables[4].add_row(trading_summary.trades.row());
+    tables[5].add_row(trading_summary.costs.row());
+    tables[6].add_row(trading_summary.execution.row());
     for table in tables.iter_mut() {
       table.get_mut_row(row_index).unwrap().insert_cell(0, Cell::new(&id));
     }
     if row_index == 0 {
-      let mut rows = Vec::with_capacity(4);
+      let mut rows = Vec::with_capacity(7);
       rows.push(trading_summary.pnl_returns.titles());
       rows.push(trading_summary.tear_sheet.titles());
       rows.push(trading_summary.drawdown.titles());
       rows.push(trading_summary.pnl.titles());
+      rows.push(trading_summary.trades.titles());
+      rows.push(trading_summary.costs.titles());
+      rows.push(trading_summary.execution.titles());
       for (index, row) in rows.iter_mut().enumerate() {
         //row.insert_cell(0, Cell::new("Asset"));
         tables[index].set_titles(row.to_owned())
@@ -158,6 +247,33 @@ pub fn combine(builders: Vec<(String, TradingSummary)>) -> Vec<Table> {
   tables
 }
 
+/// Drawdown curve as an inline SVG line, 0 at the top and the deepest drawdown at the
+/// bottom.
+pub fn drawdown_svg(curve: &DrawdownCurve) -> String {
+  const WIDTH: f64 = 800.0;
+  const HEIGHT: f64 = 200.0;
+  let deepest = curve.points.iter().map(|point| point.drawdown).fold(0.0, f64::min);
+  let last = curve.points.len().saturating_sub(1).max(1) as f64;
+  let points: Vec<String> = curve
+    .points
+    .iter()
+    .enumerate()
+    .map(|(index, point)| {
+      let y = if deepest < 0.0 { point.drawdown / deepest * HEIGHT } else { 0.0 };
+      format!("{:.1},{:.1}", index as f64 / last * WIDTH, y)
+    })
+    .collect();
+  format!(
+    "<h3>Drawdown (deepest {:.3})</h3>\n<svg class=\"drawdown\" viewBox=\"0 0 {} {}\" \
+     preserveAspectRatio=\"none\"><polyline fill=\"none\" stroke=\"currentColor\" \
+     vector-effect=\"non-scaling-stroke\" points=\"{}\"/></svg>",
+    deepest,
+    WIDTH,
+    HEIGHT,
+    points.join(" ")
+  )
+}
+
 pub fn exited_positions_table(positions: Vec<Position>) -> Table {
   let mut table = Table::new();
   let title_row = row![
@@ -0,0 +1,64 @@
+use crate::utils::formatting::duration_to_readable;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Wall clock time of a session next to the market time it covered. A backtest goes
+/// through days of candles in seconds, so the two are only the same for live sessions.
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SessionTime {
+  pub is_backtest: bool,
+  pub started_at: DateTime<Utc>,
+  pub finished_at: Option<DateTime<Utc>>,
+  /// Time of the first and the latest market event of the session.
+  pub first_event: Option<DateTime<Utc>>,
+  pub last_event: Option<DateTime<Utc>>,
+}
+
+impl SessionTime {
+  pub fn new(started_at: DateTime<Utc>, is_backtest: bool) -> Self {
+    Self {
+      is_backtest,
+      started_at,
+      finished_at: None,
+      first_event: None,
+      last_event: None,
+    }
+  }
+
+  pub fn market_event(&mut self, time: DateTime<Utc>) {
+    self.first_event = Some(self.first_event.map_or(time, |first| first.min(time)));
+    self.last_event = Some(self.last_event.map_or(time, |last| last.max(time)));
+  }
+
+  pub fn finish(&mut self, finished_at: DateTime<Utc>) {
+    self.finished_at.get_or_insert(finished_at);
+  }
+
+  /// Wall clock time the session ran for, until now while it is running.
+  pub fn run_time(&self, now: DateTime<Utc>) -> Duration {
+    self.finished_at.unwrap_or(now) - self.started_at
+  }
+
+  /// Market time between the first and the latest market event.
+  pub fn simulated(&self) -> Duration {
+    match (self.first_event, self.last_event) {
+      (Some(first), Some(last)) => last - first,
+      _ => Duration::zero(),
+    }
+  }
+
+  /// "simulated 30d in 42s" for backtests, the run time for live sessions.
+  pub fn describe(&self, now: DateTime<Utc>) -> String {
+    let run_time = self.run_time(now);
+    let run_time = if run_time.num_seconds() < 60 {
+      format!("{}s", run_time.num_seconds())
+    } else {
+      duration_to_readable(&run_time)
+    };
+    if self.is_backtest {
+      format!("simulated {} in {}", duration_to_readable(&self.simulated()), run_time)
+    } else {
+      run_time
+    }
+  }
+}
@@ -2,19 +2,24 @@ use crate::portfolio::position::Position;
 
 use super::{
     metric::{
-        drawdown::{AvgDrawdown, Drawdown, MaxDrawdown},
+        drawdown::{AvgDrawdown, AvgRecovery, Drawdown, DrawdownCurve, MaxDrawdown},
         EquityPoint,
     },
     TableBuilder,
 };
+use chrono::{DateTime, Utc};
 use prettytable::{row, Row};
 use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
 pub struct DrawdownSummary {
     pub current_drawdown: Drawdown,
     pub avg_drawdown: AvgDrawdown,
     pub max_drawdown: MaxDrawdown,
+    #[serde(default)]
+    pub curve: DrawdownCurve,
+    #[serde(default)]
+    pub avg_recovery: AvgRecovery,
 }
 
 impl DrawdownSummary {
@@ -30,12 +35,17 @@ impl DrawdownSummary {
             self.avg_drawdown.update(&ended_drawdown);
             self.max_drawdown.update(&ended_drawdown);
         }
+        if let Some(recovery) = self.curve.update(equity_point, &self.current_drawdown) {
+            self.avg_recovery.update(recovery);
+        }
     }
-    pub fn new(starting_equity: f64) -> Self {
+    pub fn new(starting_equity: f64, starting_time: DateTime<Utc>) -> Self {
         Self {
-            current_drawdown: Drawdown::init(starting_equity),
+            current_drawdown: Drawdown::init(starting_equity, starting_time),
             avg_drawdown: AvgDrawdown::init(),
             max_drawdown: MaxDrawdown::init(),
+            curve: DrawdownCurve::default(),
+            avg_recovery: AvgRecovery::default(),
         }
     }
 }
@@ -47,6 +57,8 @@ impl TableBuilder for DrawdownSummary {
             "Max Drawdown Days",
             "Avg. Drawdown",
             "Avg. Drawdown Days",
+            "Current Drawdown",
+            "Avg. Recovery Days",
         ]
     }
 
@@ -56,6 +68,8 @@ impl TableBuilder for DrawdownSummary {
             self.max_drawdown.drawdown.duration.num_days().to_string(),
             format!("{:.3}", self.avg_drawdown.mean_drawdown),
             self.avg_drawdown.mean_duration.num_days().to_string(),
+            format!("{:.3}", self.current_drawdown.drawdown),
+            self.avg_recovery.mean_recovery().num_days().to_string(),
         ]
     }
 }
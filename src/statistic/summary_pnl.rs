@@ -240,3 +240,191 @@ impl TableBuilder for ProfitLossSummary {
         ]
     }
 }
+
+/// Win/loss statistics of exited trades, in quote currency.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default, Deserialize, Serialize)]
+pub struct TradeOutcomeSummary {
+    pub trades: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    pub current_losing_streak: u64,
+    pub longest_losing_streak: u64,
+}
+
+impl TradeOutcomeSummary {
+    pub fn update(&mut self, position: &Position) {
+        // Only exited Positions have a final outcome
+        if position.meta.exit_balance.is_none() {
+            return;
+        }
+        let pnl = position.realised_profit_loss;
+        self.trades += 1;
+        if pnl > 0.0 {
+            self.wins += 1;
+            self.gross_profit += pnl;
+            self.current_losing_streak = 0;
+        } else if pnl < 0.0 {
+            self.losses += 1;
+            self.gross_loss -= pnl;
+            self.current_losing_streak += 1;
+            self.longest_losing_streak =
+                self.longest_losing_streak.max(self.current_losing_streak);
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        ratio(self.wins as f64, self.trades as f64)
+    }
+
+    /// Gross profit over gross loss, infinite when there were only winning trades.
+    pub fn profit_factor(&self) -> f64 {
+        if self.gross_loss == 0.0 && self.gross_profit > 0.0 {
+            f64::INFINITY
+        } else {
+            ratio(self.gross_profit, self.gross_loss)
+        }
+    }
+
+    pub fn avg_win(&self) -> f64 {
+        ratio(self.gross_profit, self.wins as f64)
+    }
+
+    pub fn avg_loss(&self) -> f64 {
+        ratio(self.gross_loss, self.losses as f64)
+    }
+
+    /// Average profit or loss per trade.
+    pub fn expectancy(&self) -> f64 {
+        ratio(self.gross_profit - self.gross_loss, self.trades as f64)
+    }
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+impl TableBuilder for TradeOutcomeSummary {
+    fn titles(&self) -> Row {
+        row![
+            "Win Rate",
+            "Profit Factor",
+            "Avg. Win",
+            "Avg. Loss",
+            "Expectancy",
+            "Longest Losing Streak",
+        ]
+    }
+
+    fn row(&self) -> Row {
+        row![
+            format!("{:.3}", self.win_rate()),
+            format!("{:.3}", self.profit_factor()),
+            format!("{:.8}", self.avg_win()),
+            format!("{:.8}", self.avg_loss()),
+            format!("{:.8}", self.expectancy()),
+            self.longest_losing_streak,
+        ]
+    }
+}
+
+/// Fees and slippage paid on exited trades, against the PnL before them.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default, Deserialize, Serialize)]
+pub struct CostSummary {
+    /// Exchange fees, without the slippage.
+    pub fees: f64,
+    pub slippage: f64,
+    /// PnL before fees and slippage.
+    pub gross_pnl: f64,
+}
+
+impl CostSummary {
+    pub fn update(&mut self, position: &Position) {
+        if position.meta.exit_balance.is_none() {
+            return;
+        }
+        let costs = position.enter_fees_total + position.exit_fees_total;
+        self.fees += costs - position.slippage_total;
+        self.slippage += position.slippage_total;
+        self.gross_pnl += position.realised_profit_loss + costs;
+    }
+
+    pub fn total(&self) -> f64 {
+        self.fees + self.slippage
+    }
+
+    /// Costs as a share of the gross PnL, of its size when it is a loss.
+    pub fn share_of_gross_pnl(&self) -> f64 {
+        ratio(self.total(), self.gross_pnl.abs())
+    }
+}
+
+impl TableBuilder for CostSummary {
+    fn titles(&self) -> Row {
+        row!["Fees", "Slippage", "Gross PnL", "Costs % Of Gross PnL"]
+    }
+
+    fn row(&self) -> Row {
+        row![
+            format!("{:.8}", self.fees),
+            format!("{:.8}", self.slippage),
+            format!("{:.8}", self.gross_pnl),
+            format!("{:.2}%", self.share_of_gross_pnl() * 100.0),
+        ]
+    }
+}
+
+/// Slippage of exited trades against the prices their orders were decided at, what the
+/// `slippage` fee of backtests stands in for.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default, Deserialize, Serialize)]
+pub struct ExecutionSummary {
+    /// Trades with recorded decision prices.
+    pub trades: u64,
+    pub slippage: f64,
+    /// Value of the fills at the decision prices.
+    pub decision_value: f64,
+    /// Slippage of the worst trade in basis points of its decision value.
+    pub worst_bps: f64,
+}
+
+impl ExecutionSummary {
+    pub fn update(&mut self, position: &Position) {
+        if position.meta.exit_balance.is_none() {
+            return;
+        }
+        let Some(slippage) = position.execution_slippage() else {
+            return;
+        };
+        let decision_value = position.enter_decision_value + position.exit_decision_value;
+        let bps = ratio(slippage, decision_value) * 10_000.0;
+        self.worst_bps = if self.trades == 0 { bps } else { self.worst_bps.max(bps) };
+        self.trades += 1;
+        self.slippage += slippage;
+        self.decision_value += decision_value;
+    }
+
+    /// Slippage in basis points of the value decided.
+    pub fn mean_bps(&self) -> f64 {
+        ratio(self.slippage, self.decision_value) * 10_000.0
+    }
+}
+
+impl TableBuilder for ExecutionSummary {
+    fn titles(&self) -> Row {
+        row!["Execution Slippage", "Slippage bps", "Worst Trade bps", "Measured Trades"]
+    }
+
+    fn row(&self) -> Row {
+        row![
+            format!("{:.8}", self.slippage),
+            format!("{:.2}", self.mean_bps()),
+            format!("{:.2}", self.worst_bps),
+            self.trades.to_string(),
+        ]
+    }
+}
@@ -0,0 +1,92 @@
+use crate::portfolio::position::Position;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Exited trades within a trailing time window. Trades are added as they exit and
+/// evicted once they fall out of the window, so totals are kept without rescanning.
+#[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+pub struct RollingWindow {
+  pub window_hours: i64,
+  exits: VecDeque<(DateTime<Utc>, f64)>,
+  pub trades: usize,
+  pub wins: usize,
+  pub pnl: f64,
+}
+
+impl RollingWindow {
+  pub fn new(window_hours: i64) -> Self {
+    Self { window_hours, exits: VecDeque::new(), trades: 0, wins: 0, pnl: 0.0 }
+  }
+
+  pub fn update(&mut self, exit_time: DateTime<Utc>, profit_loss: f64) {
+    self.exits.push_back((exit_time, profit_loss));
+    self.trades += 1;
+    if profit_loss > 0.0 {
+      self.wins += 1;
+    }
+    self.pnl += profit_loss;
+    self.expire(exit_time);
+  }
+
+  /// Evicts trades that exited more than the window before `now`.
+  pub fn expire(&mut self, now: DateTime<Utc>) {
+    let Some(cutoff) = TimeDelta::try_hours(self.window_hours)
+      .and_then(|window| now.checked_sub_signed(window))
+    else {
+      return;
+    };
+    while let Some((exit_time, profit_loss)) = self.exits.front().copied() {
+      if exit_time > cutoff {
+        break;
+      }
+      self.exits.pop_front();
+      self.trades -= 1;
+      if profit_loss > 0.0 {
+        self.wins -= 1;
+      }
+      self.pnl -= profit_loss;
+    }
+    if self.exits.is_empty() {
+      // Drop accumulated float error once the window is empty.
+      self.pnl = 0.0;
+    }
+  }
+
+  pub fn win_rate(&self) -> f64 {
+    if self.trades == 0 {
+      0.0
+    } else {
+      self.wins as f64 / self.trades as f64
+    }
+  }
+}
+
+/// PnL, win rate and trade count over the last 24 hours and the last 7 days.
+#[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+pub struct RollingSummary {
+  pub last_day: RollingWindow,
+  pub last_week: RollingWindow,
+}
+
+impl Default for RollingSummary {
+  fn default() -> Self {
+    Self { last_day: RollingWindow::new(24), last_week: RollingWindow::new(24 * 7) }
+  }
+}
+
+impl RollingSummary {
+  pub fn update(&mut self, position: &Position) {
+    // Only exited Positions count towards rolling metrics
+    let Some(exit_balance) = position.meta.exit_balance else {
+      return;
+    };
+    self.last_day.update(exit_balance.time, position.realised_profit_loss);
+    self.last_week.update(exit_balance.time, position.realised_profit_loss);
+  }
+
+  pub fn expire(&mut self, now: DateTime<Utc>) {
+    self.last_day.expire(now);
+    self.last_week.expire(now);
+  }
+}
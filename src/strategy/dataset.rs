@@ -0,0 +1,92 @@
+use super::error::StrategyError;
+use crate::assets::Candle;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt::Write, path::Path};
+
+/// Copy of the candles a model was trained on, in its model directory.
+pub const DATASET_FILE: &str = "candles.csv";
+const HEADER: &str = "open_time,close_time,open,high,low,close,volume,trade_count";
+
+/// Candle range a model was trained on, with the hash of its copy so changes to the data
+/// can be detected and backtests can rerun on the same candles.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DatasetSnapshot {
+  pub first_open_time: Option<DateTime<Utc>>,
+  pub last_open_time: Option<DateTime<Utc>>,
+  pub candles: usize,
+  pub sha256: String,
+}
+
+impl DatasetSnapshot {
+  /// Writes the candles to the dataset file of the model directory.
+  pub fn write(dir: &Path, candles: &[Candle]) -> Result<Self, StrategyError> {
+    let mut csv = format!("{HEADER}\n");
+    for candle in candles {
+      let _ = writeln!(
+        csv,
+        "{},{},{},{},{},{},{},{}",
+        candle.open_time.to_rfc3339(),
+        candle.close_time.to_rfc3339(),
+        candle.open,
+        candle.high,
+        candle.low,
+        candle.close,
+        candle.volume,
+        candle.trade_count
+      );
+    }
+    std::fs::write(dir.join(DATASET_FILE), &csv)
+      .map_err(|e| StrategyError::FileError(e.to_string()))?;
+    Ok(Self {
+      first_open_time: candles.first().map(|candle| candle.open_time),
+      last_open_time: candles.last().map(|candle| candle.open_time),
+      candles: candles.len(),
+      sha256: sha256(csv.as_bytes()),
+    })
+  }
+
+  /// Whether the dataset file of the model directory still has the recorded hash.
+  pub fn is_intact(&self, dir: &Path) -> bool {
+    std::fs::read(dir.join(DATASET_FILE))
+      .is_ok_and(|contents| sha256(&contents) == self.sha256)
+  }
+
+  /// Candles of the dataset file, failing when it changed since it was written.
+  pub fn read(&self, dir: &Path) -> Result<Vec<Candle>, StrategyError> {
+    let file_error = |e: String| StrategyError::FileError(format!("{DATASET_FILE}: {e}"));
+    let contents = std::fs::read_to_string(dir.join(DATASET_FILE))
+      .map_err(|e| file_error(e.to_string()))?;
+    if sha256(contents.as_bytes()) != self.sha256 {
+      return Err(file_error("changed since training".to_string()));
+    }
+    contents.lines().skip(1).map(|line| parse_candle(line).map_err(file_error)).collect()
+  }
+}
+
+fn sha256(contents: &[u8]) -> String {
+  format!("{:x}", Sha256::digest(contents))
+}
+
+fn parse_candle(line: &str) -> Result<Candle, String> {
+  let fields: Vec<&str> = line.split(',').collect();
+  let [open_time, close_time, open, high, low, close, volume, trade_count] = fields[..]
+  else {
+    return Err(format!("malformed line {line}"));
+  };
+  let time = |field: &str| field.parse::<DateTime<Utc>>().map_err(|e| e.to_string());
+  let number = |field: &str| field.parse::<f64>().map_err(|e| e.to_string());
+  Ok(Candle {
+    open_time: time(open_time)?,
+    close_time: time(close_time)?,
+    open: number(open)?,
+    high: number(high)?,
+    low: number(low)?,
+    close: number(close)?,
+    volume: number(volume)?,
+    trade_count: trade_count
+      .parse()
+      .map_err(|e: std::num::ParseIntError| e.to_string())?,
+  })
+}
@@ -0,0 +1,209 @@
+use super::{generate_signals_map, onnx::ModelBackend, Decision, ModelId, SignalStrength};
+use crate::features::FeaturePipeline;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+use strum::{Display, EnumIter};
+
+/// How individual model outputs are combined into a single decision.
+#[derive(
+  Copy, Clone, Default, PartialEq, Eq, Debug, Display, EnumIter, Deserialize, Serialize,
+)]
+pub enum VotingMethod {
+  /// Every model has one vote, the most common output wins.
+  #[default]
+  Majority,
+  /// Votes count with the weight of the model that cast them.
+  Weighted,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct EnsembleMember {
+  pub model_name: String,
+  pub weight: f64,
+  pub features: FeaturePipeline,
+  pub backend: ModelBackend,
+  /// Strategy module the Python model runs with, the built-in scripts when none.
+  #[serde(default)]
+  pub strategy: Option<PathBuf>,
+}
+
+impl EnsembleMember {
+  pub fn new(
+    model_name: String,
+    weight: f64,
+    features: FeaturePipeline,
+    backend: ModelBackend,
+  ) -> Self {
+    Self { model_name, weight, features, backend, strategy: None }
+  }
+
+  pub fn with_strategy(self, strategy: Option<PathBuf>) -> Self {
+    Self { strategy, ..self }
+  }
+}
+
+/// Individual output of a single model, kept alongside the combined signal.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ModelVote {
+  pub model_name: String,
+  pub weight: f64,
+  pub output: String,
+  /// Probability the model assigned to its output, between 0 and 1.
+  pub confidence: f64,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct Ensemble {
+  pub members: Vec<EnsembleMember>,
+  pub voting: VotingMethod,
+  /// Combined signals with strength below this value are discarded.
+  pub min_confidence: f64,
+  /// Sell outputs also enter shorts and buy outputs exit them.
+  #[serde(default)]
+  pub shorts: bool,
+}
+
+impl Ensemble {
+  pub fn new(
+    members: Vec<EnsembleMember>,
+    voting: VotingMethod,
+    min_confidence: f64,
+  ) -> Self {
+    Self { members, voting, min_confidence, shorts: false }
+  }
+
+  pub fn with_shorts(self, shorts: bool) -> Self {
+    Self { shorts, ..self }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.members.is_empty()
+  }
+
+  /// Replaces the members with a single model, keeping the strategy module of the
+  /// first. Returns the names of the models that were replaced.
+  pub fn swap_model(&mut self, model_id: &ModelId) -> Vec<String> {
+    let strategy = self.members.first().and_then(|member| member.strategy.clone());
+    let previous = self.members.drain(..).map(|member| member.model_name).collect();
+    self.members.push(
+      EnsembleMember::new(
+        model_id.name.clone(),
+        model_id.ensemble_weight,
+        model_id.features.clone(),
+        model_id.backend,
+      )
+      .with_strategy(strategy),
+    );
+    previous
+  }
+
+  /// Tallies the votes and returns the winning output as a signal map. Signal strength is
+  /// the share of the (weighted) vote the winner received, scaled by the confidence of
+  /// the models that voted for it. A tie for first place is treated as no consensus and
+  /// yields no signal, as does a strength below `min_confidence`.
+  pub fn combine(&self, votes: &[ModelVote]) -> HashMap<Decision, SignalStrength> {
+    let mut tally: HashMap<&str, f64> = HashMap::new();
+    let mut confidence: HashMap<&str, f64> = HashMap::new();
+    let mut total = 0.0;
+    for vote in votes {
+      let weight = match self.voting {
+        VotingMethod::Majority => 1.0,
+        VotingMethod::Weighted => vote.weight.max(0.0),
+      };
+      *tally.entry(vote.output.as_str()).or_default() += weight;
+      *confidence.entry(vote.output.as_str()).or_default() +=
+        weight * vote.confidence.clamp(0.0, 1.0);
+      total += weight;
+    }
+    if total == 0.0 {
+      return HashMap::new();
+    }
+
+    let mut ranked: Vec<(&str, f64)> = tally.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    match ranked.as_slice() {
+      [(_, first), (_, second), ..] if first == second => HashMap::new(),
+      [(output, _), ..] => {
+        let strength = confidence.get(output).copied().unwrap_or_default() / total;
+        if strength < self.min_confidence {
+          HashMap::new()
+        } else {
+          generate_signals_map(output, strength, self.shorts)
+        }
+      },
+      [] => HashMap::new(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn vote(output: &str, weight: f64, confidence: f64) -> ModelVote {
+    ModelVote {
+      model_name: format!("{} model", output),
+      weight,
+      output: output.to_string(),
+      confidence,
+    }
+  }
+
+  fn ensemble(voting: VotingMethod, min_confidence: f64) -> Ensemble {
+    Ensemble::new(Vec::new(), voting, min_confidence)
+  }
+
+  fn strength(signals: &HashMap<Decision, SignalStrength>, decision: Decision) -> f64 {
+    signals.get(&decision).map(|strength| strength.0).unwrap()
+  }
+
+  #[test]
+  fn majority_counts_votes_and_weighted_counts_weights() {
+    let votes = [vote("buy", 1.0, 1.0), vote("buy", 1.0, 1.0), vote("sell", 3.0, 1.0)];
+
+    let majority = ensemble(VotingMethod::Majority, 0.0).combine(&votes);
+    assert_eq!(majority.len(), 1);
+    assert!((strength(&majority, Decision::Long) - 2.0 / 3.0).abs() < 1e-9);
+
+    let weighted = ensemble(VotingMethod::Weighted, 0.0).combine(&votes);
+    assert_eq!(weighted.len(), 1);
+    assert!((strength(&weighted, Decision::CloseLong) - 3.0 / 5.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn ties_give_no_signal() {
+    let votes = [vote("buy", 1.0, 1.0), vote("sell", 1.0, 1.0)];
+    assert!(ensemble(VotingMethod::Majority, 0.0).combine(&votes).is_empty());
+    let votes = [vote("buy", 2.0, 1.0), vote("sell", 1.0, 1.0), vote("sell", 1.0, 1.0)];
+    assert!(ensemble(VotingMethod::Weighted, 0.0).combine(&votes).is_empty());
+    assert!(ensemble(VotingMethod::Majority, 0.0).combine(&[]).is_empty());
+  }
+
+  #[test]
+  fn strength_scales_with_the_confidence_of_the_winners() {
+    let votes = [vote("buy", 1.0, 0.5), vote("buy", 1.0, 1.0), vote("sell", 1.0, 1.0)];
+    let signals = ensemble(VotingMethod::Majority, 0.0).combine(&votes);
+    assert!((strength(&signals, Decision::Long) - 0.5).abs() < 1e-9);
+  }
+
+  #[test]
+  fn signals_below_min_confidence_are_dropped() {
+    let votes = [vote("buy", 1.0, 0.5), vote("buy", 1.0, 1.0), vote("sell", 1.0, 1.0)];
+    assert!(ensemble(VotingMethod::Majority, 0.6).combine(&votes).is_empty());
+    let signals = ensemble(VotingMethod::Majority, 0.5).combine(&votes);
+    assert!(signals.contains_key(&Decision::Long));
+  }
+
+  #[test]
+  fn shorts_are_entered_and_exited_only_when_enabled() {
+    let sell = [vote("sell", 1.0, 1.0)];
+    let buy = [vote("buy", 1.0, 1.0)];
+    let spot = ensemble(VotingMethod::Majority, 0.0);
+    assert!(!spot.combine(&sell).contains_key(&Decision::Short));
+    assert!(!spot.combine(&buy).contains_key(&Decision::CloseShort));
+    let futures = spot.with_shorts(true);
+    assert!(futures.combine(&sell).contains_key(&Decision::Short));
+    assert!(futures.combine(&sell).contains_key(&Decision::CloseLong));
+    assert!(futures.combine(&buy).contains_key(&Decision::CloseShort));
+  }
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::database::error::DatabaseError;
 use pyo3::PyErr;
 use thiserror::Error;
 
@@ -9,6 +10,14 @@ pub enum StrategyError {
   PythonError(PythonErrWrapper),
   #[error("Error with file management: {0}")]
   FileError(String),
+  #[error("ONNX inference error: {0}")]
+  Onnx(String),
+  #[error("WASM strategy error: {0}")]
+  Wasm(String),
+  #[error("Strategy task failed: {0}")]
+  Task(String),
+  #[error("Database error: {0}")]
+  Database(#[from] DatabaseError),
 }
 
 #[derive(Debug)]
@@ -1,12 +1,31 @@
+pub mod dataset;
+pub mod ensemble;
 pub mod error;
+pub mod onnx;
+pub mod plugin;
+pub mod wasm;
+pub mod watcher;
 
-use self::error::StrategyError;
+use self::{
+  dataset::DatasetSnapshot,
+  ensemble::{Ensemble, EnsembleMember, ModelVote},
+  error::StrategyError,
+  onnx::{ModelBackend, OnnxModel},
+  plugin::{EntryPoint, Script},
+  wasm::WasmModel,
+};
 use crate::{
-  assets::{Candle, MarketEvent, MarketEventDetail, MarketMeta, Pair},
+  assets::{
+    candle_window::CandleWindow, Candle, MarketEvent, MarketEventDetail, MarketMeta,
+    Pair,
+  },
   components::{
-    style::{default_style, DEFAULT_THEME},
-    ListDisplay,
+    style::theme,
+    table::{Cell, Column, TableRow},
   },
+  database::{fetch_derivatives, Database},
+  features::{Derivatives, FeaturePipeline},
+  paths,
   utils::{
     formatting::{generate_petname, time_ago, timestamp_to_dt},
     remove_vec_items_from_start,
@@ -17,20 +36,33 @@ use futures::TryFutureExt;
 use petname::Petnames;
 use pyo3::{prelude::*, types::PyModule};
 use ratatui::{
-  prelude::{Constraint, Direction, Layout},
-  style::Style,
-  widgets::{Block, Paragraph},
+  prelude::Constraint,
+  style::{Modifier, Style},
 };
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, collections::HashMap, path::Path};
-use tokio::fs;
+use std::{
+  cmp::Ordering,
+  collections::{hash_map::Entry, HashMap},
+  fmt,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+use strum::{Display, EnumIter};
+use tokio::{fs, sync::Mutex};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-#[derive(Default, Clone)]
+/// Number of feature rows passed to a model on every live prediction.
+const LIVE_FEATURE_ROWS: usize = 50;
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize)]
 pub struct ModelId {
   pub name: String,
   pub uuid: Uuid,
   pub pair: Pair,
+  pub ensemble_weight: f64,
+  pub features: FeaturePipeline,
+  pub backend: ModelBackend,
 }
 impl std::fmt::Display for ModelId {
   fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -44,6 +76,7 @@ pub struct Signal {
   pub pair: Pair,
   pub market_meta: MarketMeta,
   pub signals: HashMap<Decision, SignalStrength>,
+  pub votes: Vec<ModelVote>,
 }
 
 impl PartialOrd for Signal {
@@ -94,46 +127,164 @@ impl Decision {
   pub fn is_exit(&self) -> bool {
     matches!(self, Decision::CloseLong | Decision::CloseShort)
   }
+  /// Sign of the quantity of its fills, negative for the ones that sell.
+  pub fn quantity_sign(&self) -> f64 {
+    match self {
+      Decision::Long | Decision::CloseShort => 1.0,
+      Decision::Short | Decision::CloseLong => -1.0,
+    }
+  }
 }
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
 pub struct SignalStrength(pub f64);
 
+/// Which live kline events the strategy generates signals on.
+#[derive(
+  Copy, Clone, Default, PartialEq, Eq, Debug, Display, EnumIter, Deserialize, Serialize,
+)]
+pub enum SignalEvaluation {
+  /// Only closed candles, at most one signal per candle.
+  #[default]
+  CandleClose,
+  /// Every kline update, debounced by `UPDATE_DEBOUNCE_SECONDS`. Closed candles are
+  /// always evaluated.
+  EveryUpdate,
+}
+
+/// Minimum time between two evaluations of unclosed candles.
+const UPDATE_DEBOUNCE_SECONDS: i64 = 5;
+
 pub struct Strategy {
   pair: Pair,
-  model_name: String,
+  ensemble: Ensemble,
+  onnx_models: HashMap<String, OnnxModel>,
+  wasm_models: HashMap<String, WasmModel>,
+  evaluation: SignalEvaluation,
+  last_evaluation: Option<DateTime<Utc>>,
 }
 impl Strategy {
-  pub fn new(pair: Pair, model_name: String) -> Self {
-    Strategy { pair, model_name }
+  pub fn new(
+    pair: Pair,
+    ensemble: Ensemble,
+    evaluation: SignalEvaluation,
+  ) -> Self {
+    Strategy {
+      pair,
+      ensemble,
+      onnx_models: HashMap::new(),
+      wasm_models: HashMap::new(),
+      evaluation,
+      last_evaluation: None,
+    }
+  }
+  /// Replaces the models of the ensemble with a single model, keeping voting settings
+  /// and the strategy module. Returns the names of the models that were replaced.
+  pub fn swap_model(&mut self, model_id: &ModelId) -> Vec<String> {
+    let previous = self.ensemble.swap_model(model_id);
+    self.onnx_models.clear();
+    self.wasm_models.clear();
+    previous
+  }
+
+  pub fn min_confidence(&self) -> f64 {
+    self.ensemble.min_confidence
+  }
+
+  pub fn set_min_confidence(&mut self, min_confidence: f64) {
+    self.ensemble.min_confidence = min_confidence;
   }
+
+  /// Closed candles every model of the ensemble needs before the one it predicts.
+  pub fn required_history(&self) -> usize {
+    self
+      .ensemble
+      .members
+      .iter()
+      .map(|member| member.features.warmup() + LIVE_FEATURE_ROWS - 1)
+      .max()
+      .unwrap_or_default()
+  }
+
+  /// Evaluates the ensemble on the candle of the event and the closed candles before
+  /// it in the window.
   pub async fn generate_signal(
     &mut self,
     market_event: &MarketEvent,
+    window: &CandleWindow,
   ) -> Result<Option<Signal>, StrategyError> {
-    if let MarketEventDetail::BacktestCandle((_, signal)) = &market_event.detail {
-      Ok(signal.to_owned())
-    } else if let MarketEventDetail::Candle(candle) = &market_event.detail {
-      // Run model
-      let pyscript = include_str!("../../models/run_model.py");
-      let args =
-        (candle.open_time.to_rfc3339(), self.pair.to_string(), self.model_name.clone());
-      let model_output = run_candle(pyscript, args)?;
-      let signals = generate_signals_map(&model_output);
-      if signals.len() == 0 {
-        return Ok(None);
-      }
-      let time = Utc::now();
-      let signal = Signal {
-        time,
-        pair: self.pair.clone(),
-        market_meta: MarketMeta { close: candle.close, time },
-        signals,
+    let candle = match &market_event.detail {
+      MarketEventDetail::BacktestCandle((_, signal)) => return Ok(signal.to_owned()),
+      MarketEventDetail::Candle(candle) => candle,
+      MarketEventDetail::CandleUpdate(candle) => match self.evaluation {
+        SignalEvaluation::CandleClose => return Ok(None),
+        SignalEvaluation::EveryUpdate => {
+          let debounced = self.last_evaluation.is_some_and(|last| {
+            (Utc::now() - last).num_seconds() < UPDATE_DEBOUNCE_SECONDS
+          });
+          if debounced {
+            return Ok(None);
+          }
+          candle
+        },
+      },
+      _ => return Ok(None),
+    };
+    self.last_evaluation = Some(Utc::now());
+
+    // Run every model of the ensemble
+    let mut votes = Vec::with_capacity(self.ensemble.members.len());
+    for member in self.ensemble.members.iter() {
+      let candles =
+        window.history(candle, member.features.warmup() + LIVE_FEATURE_ROWS - 1);
+      let derivatives = derivatives_of(&member.features, self.pair, &candles).await?;
+      let features = member.features.compute(&candles, &derivatives);
+      let (output, confidence) = if features.is_empty() {
+        ("hold".to_string(), 1.0)
+      } else if member.backend == ModelBackend::Onnx {
+        let model = match self.onnx_models.entry(member.model_name.clone()) {
+          Entry::Occupied(entry) => entry.into_mut(),
+          Entry::Vacant(entry) => entry
+            .insert(OnnxModel::load(&member.model_name, features.columns.len())?),
+        };
+        model.predict(&features)?.pop().unwrap_or(("hold".to_string(), 1.0))
+      } else if member.backend == ModelBackend::Wasm {
+        let model = match self.wasm_models.entry(member.model_name.clone()) {
+          Entry::Occupied(entry) => entry.into_mut(),
+          Entry::Vacant(entry) => entry.insert(WasmModel::load(&member.model_name)?),
+        };
+        model.decide(self.pair, &candles)?.pop().unwrap_or(("hold".to_string(), 1.0))
+      } else {
+        let args = (
+          candle.open_time.to_rfc3339(),
+          self.pair.to_string(),
+          member.model_name.clone(),
+          features.columns,
+          features.rows,
+        );
+        let script = plugin::script(member.strategy.as_deref(), EntryPoint::Run)?;
+        run_candle(&script, args)?
       };
-      Ok(Some(signal))
-    } else {
-      Ok(None)
+      votes.push(ModelVote {
+        model_name: member.model_name.clone(),
+        weight: member.weight,
+        output,
+        confidence,
+      });
+    }
+    let signals = self.ensemble.combine(&votes);
+    if signals.len() == 0 {
+      return Ok(None);
     }
+    let time = Utc::now();
+    let signal = Signal {
+      time,
+      pair: self.pair.clone(),
+      market_meta: MarketMeta { close: candle.close, time },
+      signals,
+      votes,
+    };
+    Ok(Some(signal))
   }
 
   /// buffer_n_of_candles - number of candles that are required for analysis of the "first" candle
@@ -142,26 +293,72 @@ impl Strategy {
     candles: Vec<Candle>,
     buffer_n_of_candles: usize,
     pair: Pair,
-    model_name: String,
+    ensemble: &Ensemble,
+    cancel: &CancellationToken,
   ) -> Result<Option<Vec<Option<Signal>>>, StrategyError> {
-    let pyscript = include_str!("../../models/backtest.py");
-    let args = (open_time.to_rfc3339(), pair.to_string(), model_name);
-    let model_output = run_backtest(pyscript, args)?;
-    let candles_that_were_analyzed = remove_vec_items_from_start(candles, 0);
-    let mut candles_with_signals: Vec<(Candle, HashMap<Decision, SignalStrength>)> =
-      Vec::new();
-    for candle in candles_that_were_analyzed {
-      let raw_signal =
-        model_output.iter().find(|(_, datetime)| datetime == &candle.open_time);
-      let signal_map = match raw_signal {
-        Some(raw_signal) => generate_signals_map(&raw_signal.0),
-        None => generate_signals_map("hold"),
+    let mut outputs_per_model = Vec::with_capacity(ensemble.members.len());
+    for member in ensemble.members.iter() {
+      if cancel.is_cancelled() {
+        return Ok(None);
+      }
+      let derivatives = derivatives_of(&member.features, pair, &candles).await?;
+      let features = member.features.compute(&candles, &derivatives);
+      let model_output: HashMap<DateTime<Utc>, (String, f64)> = match member.backend {
+        ModelBackend::Onnx => {
+          let model = OnnxModel::load(&member.model_name, features.columns.len())?;
+          features.open_times.iter().copied().zip(model.predict(&features)?).collect()
+        },
+        ModelBackend::Wasm => {
+          let model = WasmModel::load(&member.model_name)?;
+          let decisions = model.decide(pair, &candles)?;
+          candles.iter().map(|candle| candle.open_time).zip(decisions).collect()
+        },
+        ModelBackend::Python => {
+          let args = (
+            open_time.to_rfc3339(),
+            pair.to_string(),
+            member.model_name.clone(),
+            features.open_times_rfc3339(),
+            features.columns,
+            features.rows,
+          );
+          let script =
+            plugin::script(member.strategy.as_deref(), EntryPoint::Backtest)?;
+          // The interpreter can not be interrupted, a cancelled call finishes its page
+          // in the background and its output is dropped
+          let outputs = tokio::task::spawn_blocking(move || run_backtest(&script, args));
+          let outputs = tokio::select! {
+            outputs = outputs => outputs.map_err(|e| StrategyError::Task(e.to_string()))?,
+            _ = cancel.cancelled() => return Ok(None),
+          };
+          outputs?
+            .into_iter()
+            .map(|(output, confidence, datetime)| (datetime, (output, confidence)))
+            .collect()
+        },
       };
-      candles_with_signals.push((candle, signal_map));
+      outputs_per_model.push((member, model_output));
     }
-    let signals: Vec<Option<Signal>> = candles_with_signals
+    let candles_that_were_analyzed = remove_vec_items_from_start(candles, 0);
+    let signals: Vec<Option<Signal>> = candles_that_were_analyzed
       .iter()
-      .map(|(candle, signal_map)| {
+      .map(|candle| {
+        let votes: Vec<ModelVote> = outputs_per_model
+          .iter()
+          .map(|(member, model_output)| {
+            let (output, confidence) = model_output
+              .get(&candle.open_time)
+              .cloned()
+              .unwrap_or_else(|| ("hold".to_string(), 1.0));
+            ModelVote {
+              model_name: member.model_name.clone(),
+              weight: member.weight,
+              output,
+              confidence,
+            }
+          })
+          .collect();
+        let signal_map = ensemble.combine(&votes);
         if signal_map.len() == 0 {
           None
         } else {
@@ -169,7 +366,8 @@ impl Strategy {
             time: candle.close_time,
             pair: pair.clone(),
             market_meta: MarketMeta { close: candle.close, time: candle.close_time },
-            signals: signal_map.to_owned(),
+            signals: signal_map,
+            votes,
           })
         }
       })
@@ -179,43 +377,61 @@ impl Strategy {
   }
 }
 
-fn generate_signals_map(model_output: &str) -> HashMap<Decision, SignalStrength> {
+/// Signals of the model output, entering and exiting shorts only with `shorts`.
+fn generate_signals_map(
+  model_output: &str,
+  strength: f64,
+  shorts: bool,
+) -> HashMap<Decision, SignalStrength> {
   let mut signals = HashMap::with_capacity(4);
   match model_output {
     "sell" => {
-      // signals.insert(Decision::Short, SignalStrength(1.0));
-      signals.insert(Decision::CloseLong, SignalStrength(1.0));
+      if shorts {
+        signals.insert(Decision::Short, SignalStrength(strength));
+      }
+      signals.insert(Decision::CloseLong, SignalStrength(strength));
     },
     "buy" => {
-      signals.insert(Decision::Long, SignalStrength(1.0));
-      // signals.insert(Decision::CloseShort, SignalStrength(1.0));
+      signals.insert(Decision::Long, SignalStrength(strength));
+      if shorts {
+        signals.insert(Decision::CloseShort, SignalStrength(strength));
+      }
     },
     _ => (),
   };
   signals
 }
 
-fn run_candle(script: &str, args: (String, String, String)) -> PyResult<String> {
-  let result: PyResult<String> = Python::with_gil(|py| {
-    let activators = PyModule::from_code(py, script, "activators.py", "activators")?;
-    let prediction: String = activators.getattr("run")?.call1(args)?.extract()?;
+/// (candle_time, pair, model_name, feature columns, feature rows)
+type RunArgs = (String, String, String, Vec<String>, Vec<Vec<f64>>);
+/// (candle_time, pair, model_name, open times, feature columns, feature rows)
+type BacktestArgs = (String, String, String, Vec<String>, Vec<String>, Vec<Vec<f64>>);
+
+/// Returns the model output together with the probability the model assigned to it.
+fn run_candle(script: &Script, args: RunArgs) -> PyResult<(String, f64)> {
+  let result: PyResult<(String, f64)> = Python::with_gil(|py| {
+    let activators =
+      PyModule::from_code(py, &script.code, &script.file_name, &script.module_name)?;
+    let prediction: (String, f64) =
+      activators.getattr(script.function)?.call1(args)?.extract()?;
     Ok(prediction)
   });
   Ok(result?)
 }
 
 fn run_backtest(
-  script: &str,
-  args: (String, String, String),
-) -> PyResult<Vec<(String, DateTime<Utc>)>> {
+  script: &Script,
+  args: BacktestArgs,
+) -> PyResult<Vec<(String, f64, DateTime<Utc>)>> {
   let result: PyResult<Vec<_>> = Python::with_gil(|py| {
-    let activators = PyModule::from_code(py, script, "activators.py", "activators")?;
-    let signals: Vec<(String, String)> =
-      activators.getattr("backtest")?.call1(args)?.extract()?;
-    let mut parsed_signals: Vec<(String, DateTime<Utc>)> = Vec::new();
-    for (time, signal) in signals {
+    let activators =
+      PyModule::from_code(py, &script.code, &script.file_name, &script.module_name)?;
+    let signals: Vec<(String, String, f64)> =
+      activators.getattr(script.function)?.call1(args)?.extract()?;
+    let mut parsed_signals: Vec<(String, f64, DateTime<Utc>)> = Vec::new();
+    for (time, signal, confidence) in signals {
       let datetime = DateTime::parse_from_rfc3339(&time).unwrap().with_timezone(&Utc);
-      parsed_signals.push((signal, datetime));
+      parsed_signals.push((signal, confidence, datetime));
     }
     Ok(parsed_signals)
   });
@@ -230,6 +446,25 @@ pub struct ModelMetadata {
   error: String,
   name: String,
   uuid: Uuid,
+  #[serde(default = "default_ensemble_weight")]
+  ensemble_weight: f64,
+  #[serde(default)]
+  features: FeaturePipeline,
+  #[serde(default)]
+  backend: ModelBackend,
+  /// Equity of the model's latest backtest, for charting in the models list.
+  #[serde(skip)]
+  backtest_equity: Vec<f64>,
+  /// Candles the model was trained on, none for models trained before they were kept.
+  #[serde(default)]
+  dataset: Option<DatasetSnapshot>,
+  /// Set when the copy of the training candles no longer matches its hash.
+  #[serde(skip)]
+  dataset_changed: bool,
+}
+
+fn default_ensemble_weight() -> f64 {
+  1.0
 }
 
 impl ModelMetadata {
@@ -246,42 +481,98 @@ impl ModelMetadata {
       error,
       name: generate_petname(),
       uuid: Uuid::new_v4(),
+      ensemble_weight: default_ensemble_weight(),
+      features: FeaturePipeline::default(),
+      backend: ModelBackend::default(),
+      backtest_equity: Vec::new(),
+      dataset: None,
+      dataset_changed: false,
     }
   }
 
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn dir(&self) -> PathBuf {
+    paths::models_dir().join(&self.name)
+  }
+
+  /// Candles the model was trained on, for backtesting on the identical dataset.
+  pub fn dataset_candles(&self) -> Result<Vec<Candle>, StrategyError> {
+    match &self.dataset {
+      Some(dataset) => dataset.read(&self.dir()),
+      None => Err(StrategyError::FileError(format!(
+        "Model {} has no training dataset",
+        self.name
+      ))),
+    }
+  }
+
+  pub fn set_backtest_equity(&mut self, equity: Vec<f64>) {
+    self.backtest_equity = equity;
+  }
+
   pub fn to_model_id(&self) -> ModelId {
-    ModelId { name: self.name.clone(), uuid: self.uuid, pair: self.pair.clone() }
+    ModelId {
+      name: self.name.clone(),
+      uuid: self.uuid,
+      pair: self.pair.clone(),
+      ensemble_weight: self.ensemble_weight,
+      features: self.features.clone(),
+      backend: self.backend,
+    }
   }
 }
 
-impl ListDisplay for ModelMetadata {
-  fn draw(
-    &mut self,
-    f: &mut ratatui::Frame<'_>,
-    area: ratatui::prelude::Rect,
-    active: bool,
-  ) -> eyre::Result<()> {
-    f.render_widget(Block::default().style(default_style(active)), area.clone());
-    let row_layout = Layout::default()
-      .direction(Direction::Horizontal)
-      .constraints(vec![
-        Constraint::Max(8),
-        Constraint::Length(8),
-        Constraint::Min(0),
-        Constraint::Length(20),
-        Constraint::Length(8),
-      ])
-      .split(area);
-
-    let status = match self.is_finished {
+impl fmt::Display for ModelMetadata {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let status = if !self.is_finished {
+      "generating"
+    } else if !self.error.is_empty() {
+      "error"
+    } else if self.dataset_changed {
+      "changed"
+    } else {
+      "ready"
+    };
+    write!(
+      f,
+      "{:<24} {:<8} {:<10} {}",
+      self.name,
+      self.pair.to_string(),
+      status,
+      self.created_at.format("%Y-%m-%d %H:%M")
+    )?;
+    if !self.error.is_empty() {
+      write!(f, " {}", self.error)?;
+    }
+    Ok(())
+  }
+}
+
+impl TableRow for ModelMetadata {
+  fn columns() -> Vec<Column> {
+    vec![
+      Column::new("", Constraint::Length(4)),
+      Column::new("Pair", Constraint::Length(8)),
+      Column::new("Status", Constraint::Min(0)),
+      Column::new("Pet name", Constraint::Length(20)),
+      Column::new("Last backtest", Constraint::Length(16)),
+      Column::new("Created", Constraint::Length(12)).right(),
+    ]
+  }
+
+  fn cells(&self) -> Vec<Cell> {
+    let (status, status_color) = match self.is_finished {
       true => {
         if self.error.len() == 0 {
-          "🟢 OK"
+          ("OK", theme().positive)
         } else {
-          "🟪 ERR"
+          ("ERR", theme().negative)
         }
       },
-      false => "🔵 WORK",
+      false => ("WORK", theme().warning),
     };
 
     let has_error = self.error != "";
@@ -289,89 +580,115 @@ impl ListDisplay for ModelMetadata {
       "Generating".to_string()
     } else if has_error {
       self.error.clone()
+    } else if self.dataset_changed {
+      "Ready, training data changed".to_string()
     } else {
       "Ready".to_string()
     };
-    let error_style = if has_error {
-      default_style(active).fg(DEFAULT_THEME.text_critical)
-    } else {
-      default_style(active).fg(DEFAULT_THEME.text_dimmed)
+    let msg_color =
+      if has_error { theme().text_critical } else { theme().text_dimmed };
+    let equity_color = match self.backtest_equity.last() {
+      Some(equity) if *equity < 0.0 => theme().text_critical,
+      _ => theme().text_dimmed,
     };
 
-    f.render_widget(Paragraph::new(status), row_layout[0]);
-    f.render_widget(Paragraph::new(self.pair.to_string()), row_layout[1]);
-    f.render_widget(Paragraph::new(msg).style(error_style), row_layout[2]);
-    f.render_widget(Paragraph::new(self.name.clone()), row_layout[3]);
-    f.render_widget(Paragraph::new(time_ago(self.created_at)), row_layout[4]);
-
-    Ok(())
+    vec![
+      Cell::styled(
+        status,
+        Style::default().fg(status_color).add_modifier(Modifier::BOLD),
+      ),
+      self.pair.to_string().into(),
+      Cell::styled(msg, Style::default().fg(msg_color)),
+      self.name.clone().into(),
+      Cell::Chart(self.backtest_equity.clone(), Style::default().fg(equity_color)),
+      time_ago(self.created_at).into(),
+    ]
   }
-  fn draw_header(
-    &mut self,
-    f: &mut ratatui::Frame<'_>,
-    area: ratatui::prelude::Rect,
-  ) -> eyre::Result<()> {
-    f.render_widget(Block::default().style(default_style(false)), area.clone());
-    let header_style = Style::default().fg(DEFAULT_THEME.text_dimmed);
-    let row_layout = Layout::default()
-      .direction(Direction::Horizontal)
-      .constraints(vec![
-        Constraint::Max(8),
-        Constraint::Length(8),
-        Constraint::Min(0),
-        Constraint::Length(20),
-        Constraint::Length(8),
-      ])
-      .split(area);
-    f.render_widget(Paragraph::new(""), row_layout[0]);
-    f.render_widget(Paragraph::new("Pair").style(header_style), row_layout[1]);
-    f.render_widget(Paragraph::new("Status").style(header_style), row_layout[2]);
-    f.render_widget(Paragraph::new("Pet name").style(header_style), row_layout[3]);
-    f.render_widget(Paragraph::new("Created").style(header_style), row_layout[4]);
-    Ok(())
+}
+
+/// Stored funding rates and open interest over the candles, when the features use them.
+async fn derivatives_of(
+  features: &FeaturePipeline,
+  pair: Pair,
+  candles: &[Candle],
+) -> Result<Derivatives, StrategyError> {
+  if !features.uses_derivatives() {
+    return Ok(Derivatives::default());
   }
+  Ok(fetch_derivatives(pair, candles).await?)
+}
+
+/// Trains a model with the `train` function of the strategy module, or the built-in
+/// script without one.
+pub async fn generate_new_model(
+  pair: Pair,
+  strategy: Option<PathBuf>,
+  database: Arc<Mutex<Database>>,
+) -> Result<(), StrategyError> {
+  train_model(pair, strategy, database, 0).await.map(|_| ())
 }
 
-pub async fn generate_new_model(pair: Pair) -> Result<(), StrategyError> {
+/// Trains a model on the stored candles of the pair except the latest
+/// `holdout_candles`, which are left to validate it on.
+pub async fn train_model(
+  pair: Pair,
+  strategy: Option<PathBuf>,
+  database: Arc<Mutex<Database>>,
+  holdout_candles: usize,
+) -> Result<ModelMetadata, StrategyError> {
+  let script = plugin::script(strategy.as_deref(), EntryPoint::Train)?;
   let created_at = Utc::now();
-  let model_metadata =
+  let mut model_metadata =
     ModelMetadata::new(created_at.clone(), pair.clone(), false, "".to_string());
+  let mut candles = database.lock().await.fetch_all_candles(pair).await?;
+  candles.truncate(candles.len().saturating_sub(holdout_candles));
+  let derivatives = derivatives_of(&model_metadata.features, pair, &candles).await?;
+  let features = model_metadata.features.compute(&candles, &derivatives);
   let file_name = model_metadata.name.clone();
-  let file_path = format!("models/generated/{}", file_name.clone());
-  match fs::create_dir(file_path.clone()).await {
+  let file_path = model_metadata.dir();
+  let _ = fs::create_dir_all(paths::models_dir()).await;
+  match fs::create_dir(&file_path).await {
     Ok(_) => {
-      fs::File::create(format!("{file_path}/meta.toml"))
+      let dataset = DatasetSnapshot::write(&model_metadata.dir(), &candles)?;
+      model_metadata.dataset = Some(dataset);
+      fs::File::create(file_path.join("meta.toml"))
         .await
         .map_err(|e| StrategyError::FileError(e.to_string()))?;
       fs::write(
-        format!("{file_path}/meta.toml"),
+        file_path.join("meta.toml"),
         toml::to_string_pretty::<ModelMetadata>(&model_metadata)
           .map_err(|e| StrategyError::FileError(e.to_string()))?,
       )
       .map_err(|e| StrategyError::FileError(e.to_string()))
       .await?;
       let result: PyResult<()> = Python::with_gil(|py| {
-        let pyscript = include_str!("../../models/create_model.py");
-        let args = (pair.to_string(), file_name);
+        let args = (
+          pair.to_string(),
+          file_name,
+          features.open_times_rfc3339(),
+          features.closes,
+          features.columns,
+          features.rows,
+        );
         let activators =
-          PyModule::from_code(py, pyscript, "activators.py", "activators")?;
-        activators.getattr("new_model")?.call1(args)?;
+          PyModule::from_code(py, &script.code, &script.file_name, &script.module_name)?;
+        activators.getattr(script.function)?.call1(args)?;
         Ok(())
       });
       match result {
         Ok(_) => {
           fs::write(
-            format!("{file_path}/meta.toml"),
+            file_path.join("meta.toml"),
             toml::to_string_pretty::<ModelMetadata>(&model_metadata)
               .map_err(|e| StrategyError::FileError(e.to_string()))?,
           )
           .map_err(|e| StrategyError::FileError(e.to_string()))
           .await?;
-          Ok(())
+          Ok(model_metadata)
         },
         Err(e) => {
           fs::write(
-            format!("{file_path}/meta.toml"),
+            file_path.join("meta.toml"),
             toml::to_string_pretty::<ModelMetadata>(&model_metadata)
               .map_err(|e| StrategyError::FileError(e.to_string()))?,
           )
@@ -390,8 +707,11 @@ pub async fn generate_new_model(pair: Pair) -> Result<(), StrategyError> {
 }
 
 pub fn get_generated_models() -> eyre::Result<Vec<ModelMetadata>> {
-  let path = Path::new("models/generated");
+  let path = paths::models_dir();
   let mut metadata_list: Vec<ModelMetadata> = Vec::new();
+  if !path.exists() {
+    return Ok(metadata_list);
+  }
   for entry in std::fs::read_dir(path)? {
     let entry = entry?;
     if entry.path().is_dir() {
@@ -399,7 +719,13 @@ pub fn get_generated_models() -> eyre::Result<Vec<ModelMetadata>> {
       if config_path.exists() && config_path.is_file() {
         let file = std::fs::read_to_string(&config_path)?;
         match parse_model_metadata(&file) {
-          Ok(metadata) => {
+          Ok(mut metadata) => {
+            if let Some(dataset) = &metadata.dataset {
+              metadata.dataset_changed = !dataset.is_intact(&entry.path());
+              if metadata.dataset_changed {
+                log::warn!("Training data of model {} changed.", metadata.name);
+              }
+            }
             metadata_list.push(metadata);
           },
           Err(e) => log::warn!("Error on reading modal metafile: {:?}", e),
@@ -430,5 +756,34 @@ pub fn parse_model_metadata(contents: &str) -> eyre::Result<ModelMetadata> {
     value.get("name").and_then(toml::Value::as_str).unwrap_or_default().parse()?;
   let is_finished: bool =
     value.get("is_finished").and_then(toml::Value::as_bool).unwrap_or_default();
-  Ok(ModelMetadata { created_at, pair, is_finished, error, name, uuid })
+  let ensemble_weight: f64 = value
+    .get("ensemble_weight")
+    .and_then(toml::Value::as_float)
+    .unwrap_or_else(default_ensemble_weight);
+  let features: FeaturePipeline = match value.get("features") {
+    Some(features) => features.clone().try_into()?,
+    None => FeaturePipeline::default(),
+  };
+  let backend: ModelBackend = match value.get("backend").and_then(toml::Value::as_str) {
+    Some(backend) => backend.parse()?,
+    None => ModelBackend::default(),
+  };
+  let dataset: Option<DatasetSnapshot> = match value.get("dataset") {
+    Some(dataset) => Some(dataset.clone().try_into()?),
+    None => None,
+  };
+  Ok(ModelMetadata {
+    created_at,
+    pair,
+    is_finished,
+    error,
+    name,
+    uuid,
+    ensemble_weight,
+    features,
+    backend,
+    backtest_equity: Vec::new(),
+    dataset,
+    dataset_changed: false,
+  })
 }
@@ -0,0 +1,143 @@
+use super::error::StrategyError;
+use crate::features::FeatureMatrix;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumString};
+
+/// Runtime used to run a generated model.
+#[derive(
+  Copy, Clone, Default, PartialEq, Eq, Debug, Display, EnumString, Deserialize, Serialize,
+)]
+pub enum ModelBackend {
+  /// Keras model run through the embedded Python interpreter.
+  #[default]
+  Python,
+  /// `model.onnx` exported next to the Keras model, run natively.
+  Onnx,
+  /// `model.wasm` strategy, run in a sandboxed WebAssembly runtime.
+  Wasm,
+}
+
+/// Model exported to ONNX, together with the class cutoffs chosen during training.
+/// Outputs are expected in label order: buy, hold, sell.
+pub struct OnnxModel {
+  #[cfg(feature = "onnx")]
+  plan: tract_onnx::prelude::TypedRunnableModel<tract_onnx::prelude::TypedModel>,
+  cutoffs: Vec<f64>,
+}
+
+pub(super) fn model_path(model_name: &str) -> PathBuf {
+  crate::paths::models_dir().join(model_name)
+}
+
+impl OnnxModel {
+  #[cfg(feature = "onnx")]
+  pub fn load(model_name: &str, n_features: usize) -> Result<Self, StrategyError> {
+    use tract_onnx::prelude::*;
+    let path = model_path(model_name);
+    let plan = tract_onnx::onnx()
+      .model_for_path(path.join("model.onnx"))
+      .and_then(|model| {
+        model.with_input_fact(0, f32::fact([1, n_features]).into())?.into_optimized()
+      })
+      .and_then(|model| model.into_runnable())
+      .map_err(|e| StrategyError::Onnx(e.to_string()))?;
+    Ok(Self { plan, cutoffs: read_cutoffs(&path)? })
+  }
+
+  #[cfg(not(feature = "onnx"))]
+  pub fn load(model_name: &str, n_features: usize) -> Result<Self, StrategyError> {
+    Err(StrategyError::Onnx("built without the `onnx` feature".to_string()))
+  }
+
+  /// Runs every row of the matrix and returns the decision and its probability per row.
+  pub fn predict(
+    &self,
+    features: &FeatureMatrix,
+  ) -> Result<Vec<(String, f64)>, StrategyError> {
+    robust_scale(&features.rows)
+      .iter()
+      .map(|row| {
+        let probabilities = self.run_row(row)?;
+        Ok(decide(&probabilities, &self.cutoffs))
+      })
+      .collect()
+  }
+
+  #[cfg(feature = "onnx")]
+  fn run_row(&self, row: &[f32]) -> Result<Vec<f64>, StrategyError> {
+    use tract_onnx::prelude::*;
+    let input = tract_ndarray::Array2::from_shape_vec((1, row.len()), row.to_vec())
+      .map_err(|e| StrategyError::Onnx(e.to_string()))?;
+    let outputs = self
+      .plan
+      .run(tvec!(Tensor::from(input).into()))
+      .map_err(|e| StrategyError::Onnx(e.to_string()))?;
+    let probabilities = outputs[0]
+      .to_array_view::<f32>()
+      .map_err(|e| StrategyError::Onnx(e.to_string()))?
+      .iter()
+      .map(|p| *p as f64)
+      .collect();
+    Ok(probabilities)
+  }
+
+  #[cfg(not(feature = "onnx"))]
+  fn run_row(&self, row: &[f32]) -> Result<Vec<f64>, StrategyError> {
+    Err(StrategyError::Onnx("built without the `onnx` feature".to_string()))
+  }
+}
+
+fn read_cutoffs(path: &Path) -> Result<Vec<f64>, StrategyError> {
+  let file = std::fs::read_to_string(path.join("cutoffs.json"))
+    .map_err(|e| StrategyError::FileError(e.to_string()))?;
+  serde_json::from_str(&file).map_err(|e| StrategyError::FileError(e.to_string()))
+}
+
+/// Same decision rule as the Python scripts: buy wins over sell, otherwise hold.
+fn decide(probabilities: &[f64], cutoffs: &[f64]) -> (String, f64) {
+  let p = |i: usize| probabilities.get(i).copied().unwrap_or_default();
+  let cutoff = |i: usize| cutoffs.get(i).copied().unwrap_or(1.0);
+  if p(0) > cutoff(0) {
+    ("buy".to_string(), p(0))
+  } else if p(2) > cutoff(2) {
+    ("sell".to_string(), p(2))
+  } else {
+    ("hold".to_string(), 1.0 - p(0).max(p(2)))
+  }
+}
+
+/// Equivalent of sklearn's `RobustScaler().fit_transform`, fitted on the given rows.
+fn robust_scale(rows: &[Vec<f64>]) -> Vec<Vec<f32>> {
+  let n_columns = rows.first().map(Vec::len).unwrap_or_default();
+  let stats: Vec<(f64, f64)> = (0..n_columns)
+    .map(|column| {
+      let mut values: Vec<f64> = rows.iter().map(|row| row[column]).collect();
+      values.sort_by(f64::total_cmp);
+      let median = quantile(&values, 0.5);
+      let iqr = quantile(&values, 0.75) - quantile(&values, 0.25);
+      (median, if iqr == 0.0 { 1.0 } else { iqr })
+    })
+    .collect();
+  rows
+    .iter()
+    .map(|row| {
+      row
+        .iter()
+        .zip(stats.iter())
+        .map(|(value, (median, iqr))| ((value - median) / iqr) as f32)
+        .collect()
+    })
+    .collect()
+}
+
+/// Linear interpolation between closest ranks, like numpy's default percentile.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+  if sorted.is_empty() {
+    return 0.0;
+  }
+  let position = q * (sorted.len() - 1) as f64;
+  let lower = position.floor() as usize;
+  let upper = position.ceil() as usize;
+  sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
@@ -0,0 +1,147 @@
+use super::error::StrategyError;
+use std::{
+  borrow::Cow,
+  ffi::OsStr,
+  fmt,
+  path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// Functions a strategy module defines, with the same arguments as the built-in
+/// scripts in `models/`:
+///
+/// - `run(candle_time, pair, model_name, columns, rows) -> (output, confidence)`
+/// - `backtest(candle_time, pair, model_name, open_times, columns, rows)
+///   -> [(open_time, output, confidence)]`
+/// - `train(pair, model_name, open_times, closes, columns, rows)`
+pub const INTERFACE: [&str; 3] = ["run", "backtest", "train"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntryPoint {
+  Run,
+  Backtest,
+  Train,
+}
+
+impl EntryPoint {
+  fn function(&self) -> &'static str {
+    match self {
+      EntryPoint::Run => "run",
+      EntryPoint::Backtest => "backtest",
+      EntryPoint::Train => "train",
+    }
+  }
+
+  /// Embedded script and function used when no strategy is selected.
+  fn builtin(&self) -> (&'static str, &'static str) {
+    match self {
+      EntryPoint::Run => (include_str!("../../models/run_model.py"), "run"),
+      EntryPoint::Backtest => (include_str!("../../models/backtest.py"), "backtest"),
+      EntryPoint::Train => (include_str!("../../models/create_model.py"), "new_model"),
+    }
+  }
+}
+
+/// Python module of an entry point, ready for `PyModule::from_code`.
+pub struct Script {
+  pub code: Cow<'static, str>,
+  pub file_name: String,
+  pub module_name: String,
+  pub function: &'static str,
+}
+
+/// Loads the entry point from the strategy module, or the built-in one without it.
+pub fn script(
+  strategy: Option<&Path>,
+  entry: EntryPoint,
+) -> Result<Script, StrategyError> {
+  match strategy {
+    Some(path) => Ok(Script {
+      code: Cow::Owned(
+        std::fs::read_to_string(path)
+          .map_err(|e| StrategyError::FileError(format!("{}: {}", path.display(), e)))?,
+      ),
+      file_name: path.display().to_string(),
+      module_name: module_name(path),
+      function: entry.function(),
+    }),
+    None => {
+      let (code, function) = entry.builtin();
+      Ok(Script {
+        code: Cow::Borrowed(code),
+        file_name: "activators.py".to_string(),
+        module_name: "activators".to_string(),
+        function,
+      })
+    },
+  }
+}
+
+/// A strategy module to run the ensemble's Python models with, none being the built-in
+/// scripts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StrategyPlugin {
+  pub path: Option<PathBuf>,
+}
+
+impl StrategyPlugin {
+  pub fn name(&self) -> String {
+    self.path.as_deref().map(module_name).unwrap_or_else(|| "built-in".to_string())
+  }
+}
+
+impl fmt::Display for StrategyPlugin {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+fn module_name(path: &Path) -> String {
+  path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Python modules in the directory that define the whole [INTERFACE], sorted by name
+/// after the built-in strategy. Modules missing a function are skipped with a warning.
+pub fn discover(dir: &Path) -> Vec<StrategyPlugin> {
+  let mut plugins = Vec::new();
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    return vec![StrategyPlugin::default()];
+  };
+  for path in entries.flatten().map(|entry| entry.path()) {
+    if path.extension() != Some(OsStr::new("py")) {
+      continue;
+    }
+    let Ok(code) = std::fs::read_to_string(&path) else {
+      continue;
+    };
+    let missing: Vec<&str> = INTERFACE
+      .into_iter()
+      .filter(|function| !defines(&code, function))
+      .collect();
+    if missing.is_empty() {
+      plugins.push(StrategyPlugin { path: Some(path) });
+    } else {
+      debug!("Skipping strategy {}, missing {}", path.display(), missing.join(", "));
+    }
+  }
+  plugins.sort_by_key(|plugin| plugin.name());
+  plugins.insert(0, StrategyPlugin::default());
+  plugins
+}
+
+/// Finds the strategy module of the name in the directory.
+pub fn find(dir: &Path, name: &str) -> Result<StrategyPlugin, StrategyError> {
+  discover(dir).into_iter().find(|plugin| plugin.name() == name).ok_or_else(|| {
+    StrategyError::FileError(format!("No strategy named {} in {}", name, dir.display()))
+  })
+}
+
+/// Whether a top level `def` of the function is in the module.
+fn defines(code: &str, function: &str) -> bool {
+  code.lines().any(|line| {
+    line
+      .strip_prefix("def ")
+      .and_then(|rest| rest.trim_start().strip_prefix(function))
+      .is_some_and(|rest| rest.trim_start().starts_with('('))
+  })
+}
@@ -0,0 +1,113 @@
+use super::{error::StrategyError, onnx::model_path};
+use crate::assets::{Candle, Pair};
+use serde::Serialize;
+
+/// Fuel a single call may burn before the guest is stopped.
+#[cfg(feature = "wasm")]
+const FUEL_PER_CALL: u64 = 10_000_000_000;
+/// Largest linear memory a guest may grow to.
+#[cfg(feature = "wasm")]
+const MAX_MEMORY_BYTES: usize = 256 << 20;
+
+/// Strategy compiled to WebAssembly, `model.wasm` in the model's directory. The module
+/// is linked without imports, so it cannot reach the filesystem, the network or the
+/// API keys, and runs on a fresh instance with a fuel budget on every call.
+///
+/// Guest ABI:
+/// - exports its `memory`
+/// - `alloc(len: i32) -> i32` returns a buffer the host writes the input JSON to
+/// - `decide(ptr: i32, len: i32) -> i64` reads `{"pair": ..., "candles": [...]}` and
+///   returns `ptr << 32 | len` of the output JSON, one `["buy" | "sell" | "hold",
+///   confidence]` per candle
+pub struct WasmModel {
+  #[cfg(feature = "wasm")]
+  engine: wasmtime::Engine,
+  #[cfg(feature = "wasm")]
+  module: wasmtime::Module,
+}
+
+#[derive(Serialize)]
+struct GuestInput<'a> {
+  pair: String,
+  candles: &'a [Candle],
+}
+
+fn wasm_error(e: impl ToString) -> StrategyError {
+  StrategyError::Wasm(e.to_string())
+}
+
+impl WasmModel {
+  #[cfg(feature = "wasm")]
+  pub fn load(model_name: &str) -> Result<Self, StrategyError> {
+    let mut config = wasmtime::Config::new();
+    config.consume_fuel(true);
+    let engine = wasmtime::Engine::new(&config).map_err(wasm_error)?;
+    let module =
+      wasmtime::Module::from_file(&engine, model_path(model_name).join("model.wasm"))
+        .map_err(wasm_error)?;
+    if let Some(import) = module.imports().next() {
+      return Err(StrategyError::Wasm(format!(
+        "module imports {}::{}, strategies get no host functions",
+        import.module(),
+        import.name()
+      )));
+    }
+    Ok(Self { engine, module })
+  }
+
+  #[cfg(not(feature = "wasm"))]
+  pub fn load(model_name: &str) -> Result<Self, StrategyError> {
+    Err(StrategyError::Wasm("built without the `wasm` feature".to_string()))
+  }
+
+  /// Returns the decision and its confidence for every candle.
+  pub fn decide(
+    &self,
+    pair: Pair,
+    candles: &[Candle],
+  ) -> Result<Vec<(String, f64)>, StrategyError> {
+    let input = serde_json::to_vec(&GuestInput { pair: pair.to_string(), candles })
+      .map_err(wasm_error)?;
+    let output = self.call(&input)?;
+    let decisions: Vec<(String, f64)> =
+      serde_json::from_slice(&output).map_err(wasm_error)?;
+    if decisions.len() != candles.len() {
+      return Err(StrategyError::Wasm(format!(
+        "expected {} decisions, got {}",
+        candles.len(),
+        decisions.len()
+      )));
+    }
+    Ok(decisions)
+  }
+
+  #[cfg(feature = "wasm")]
+  fn call(&self, input: &[u8]) -> Result<Vec<u8>, StrategyError> {
+    use wasmtime::{Instance, Store, StoreLimits, StoreLimitsBuilder};
+    let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build();
+    let mut store = Store::new(&self.engine, limits);
+    store.limiter(|limits: &mut StoreLimits| limits);
+    store.set_fuel(FUEL_PER_CALL).map_err(wasm_error)?;
+    let instance = Instance::new(&mut store, &self.module, &[]).map_err(wasm_error)?;
+    let memory = instance
+      .get_memory(&mut store, "memory")
+      .ok_or_else(|| StrategyError::Wasm("module exports no memory".to_string()))?;
+    let alloc =
+      instance.get_typed_func::<i32, i32>(&mut store, "alloc").map_err(wasm_error)?;
+    let decide = instance
+      .get_typed_func::<(i32, i32), i64>(&mut store, "decide")
+      .map_err(wasm_error)?;
+    let len = i32::try_from(input.len()).map_err(wasm_error)?;
+    let ptr = alloc.call(&mut store, len).map_err(wasm_error)?;
+    memory.write(&mut store, ptr as u32 as usize, input).map_err(wasm_error)?;
+    let packed = decide.call(&mut store, (ptr, len)).map_err(wasm_error)? as u64;
+    let mut output = vec![0; (packed & 0xffff_ffff) as usize];
+    memory.read(&store, (packed >> 32) as usize, &mut output).map_err(wasm_error)?;
+    Ok(output)
+  }
+
+  #[cfg(not(feature = "wasm"))]
+  fn call(&self, input: &[u8]) -> Result<Vec<u8>, StrategyError> {
+    Err(StrategyError::Wasm("built without the `wasm` feature".to_string()))
+  }
+}
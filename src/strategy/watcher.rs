@@ -0,0 +1,35 @@
+use crate::action::Action;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{path::Path, time::Duration};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+/// Changes closer together than this, like the files of a model being written, are
+/// reported once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Sends `Action::ModelsChanged` when anything in the directories changes. Directories
+/// that do not exist are skipped. Watching stops when the watcher is dropped.
+pub fn watch_models(
+  dirs: &[&Path],
+  action_tx: UnboundedSender<Action>,
+) -> notify::Result<RecommendedWatcher> {
+  let (change_tx, mut change_rx) = mpsc::unbounded_channel();
+  let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+    if event.is_ok_and(|event| !matches!(event.kind, EventKind::Access(_))) {
+      let _ = change_tx.send(());
+    }
+  })?;
+  for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+    watcher.watch(dir, RecursiveMode::Recursive)?;
+  }
+  tokio::spawn(async move {
+    while change_rx.recv().await.is_some() {
+      tokio::time::sleep(DEBOUNCE).await;
+      while change_rx.try_recv().is_ok() {}
+      if action_tx.send(Action::ModelsChanged).is_err() {
+        break;
+      }
+    }
+  });
+  Ok(watcher)
+}
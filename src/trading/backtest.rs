@@ -0,0 +1,184 @@
+use super::{error::TraderError, replay::run_offline};
+use crate::{
+  assets::{
+    backtest_ticker::{self, BacktestProgress, TickerOptions},
+    gaps::GapStats,
+    Candle, MarketEvent,
+  },
+  database::Database,
+  events::Event,
+  screens::run_config::CoreConfiguration,
+  statistic::{self, TradingSummary},
+  strategy::{error::StrategyError, get_generated_models},
+};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::sync::{broadcast, mpsc, watch, Mutex};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Candles at the start of the backtest that only serve as model input.
+const BUFFER_N_OF_CANDLES: usize = 50;
+/// Capacity of the event channel of a backtest run from the TUI.
+pub const BACKTEST_EVENTS: usize = 64;
+
+#[derive(Debug)]
+pub struct BacktestReport {
+  pub core_id: Uuid,
+  pub configuration: CoreConfiguration,
+  pub candles: usize,
+  pub gaps: GapStats,
+  pub fills: usize,
+  pub statistics: TradingSummary,
+}
+
+impl fmt::Display for BacktestReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Backtest {} of {}", self.core_id, self.configuration.pair)?;
+    writeln!(f, "Candles: {}", self.candles)?;
+    writeln!(f, "Gaps ({}): {}", self.configuration.gaps, self.gaps)?;
+    writeln!(f, "Fills: {}", self.fills)?;
+    let tables = statistic::combine(vec![(
+      self.configuration.pair.to_string(),
+      self.statistics.clone(),
+    )]);
+    for table in tables {
+      write!(f, "{}", table)?;
+    }
+    Ok(())
+  }
+}
+
+/// Runs the ensemble over the last candles of the pair in the database, through a trader
+/// with a stub execution.
+pub async fn backtest(
+  configuration: CoreConfiguration,
+) -> Result<BacktestReport, TraderError> {
+  let database = Arc::new(Mutex::new(Database::new().await?));
+  backtest_on(configuration, database).await
+}
+
+/// Like [backtest], with a database that is already open.
+pub async fn backtest_on(
+  configuration: CoreConfiguration,
+  database: Arc<Mutex<Database>>,
+) -> Result<BacktestReport, TraderError> {
+  let (progress_tx, progress_rx) = watch::channel(BacktestProgress::default());
+  let cancel = CancellationToken::new();
+  let ticker =
+    ticker(&configuration, database.clone(), Some(progress_tx), cancel).await?;
+  run_ticker(configuration, database, ticker, progress_rx).await
+}
+
+/// Like [backtest_on], over the candles instead of those of the configuration.
+pub async fn backtest_candles(
+  configuration: CoreConfiguration,
+  database: Arc<Mutex<Database>>,
+  candles: Vec<Candle>,
+) -> Result<BacktestReport, TraderError> {
+  let (progress_tx, progress_rx) = watch::channel(BacktestProgress::default());
+  let options = TickerOptions {
+    buffer_n_of_candles: BUFFER_N_OF_CANDLES,
+    gaps: configuration.gaps,
+    trades: false,
+    progress_tx: Some(progress_tx),
+    cancel: CancellationToken::new(),
+  };
+  let ticker = backtest_ticker::new_dataset_ticker(
+    candles,
+    configuration.pair,
+    configuration.trading_ensemble(),
+    options,
+  );
+  run_ticker(configuration, database, ticker, progress_rx).await
+}
+
+async fn run_ticker(
+  configuration: CoreConfiguration,
+  database: Arc<Mutex<Database>>,
+  mut ticker: mpsc::Receiver<MarketEvent>,
+  progress_rx: watch::Receiver<BacktestProgress>,
+) -> Result<BacktestReport, TraderError> {
+  let core_id = Uuid::new_v4();
+  let mut inputs = Vec::new();
+  while let Some(market_event) = ticker.recv().await {
+    inputs.push(Event::Market(market_event));
+  }
+  let candles = progress_rx.borrow().candles;
+  let gaps = progress_rx.borrow().gaps;
+  let outputs = run_offline(core_id, &configuration, database.clone(), inputs).await?;
+  let fills = outputs.iter().filter(|event| matches!(event, Event::Fill(_))).count();
+  let statistics = database.lock().await.get_aggregate_statistics(&core_id)?;
+  Ok(BacktestReport { core_id, configuration, candles, gaps, fills, statistics })
+}
+
+/// Backtest candles of the configuration, from the model's training dataset when one is
+/// set and from the database otherwise. Cancelling the token stops the models and ends
+/// the candles early.
+pub async fn ticker(
+  configuration: &CoreConfiguration,
+  database: Arc<Mutex<Database>>,
+  progress_tx: Option<watch::Sender<BacktestProgress>>,
+  cancel: CancellationToken,
+) -> Result<mpsc::Receiver<MarketEvent>, TraderError> {
+  let options = TickerOptions {
+    buffer_n_of_candles: BUFFER_N_OF_CANDLES,
+    gaps: configuration.gaps,
+    trades: configuration.replay_trades,
+    progress_tx,
+    cancel,
+  };
+  let ticker = match &configuration.dataset {
+    Some(model) => backtest_ticker::new_dataset_ticker(
+      dataset_candles(model)?,
+      configuration.pair,
+      configuration.trading_ensemble(),
+      options,
+    ),
+    None => {
+      backtest_ticker::new_ticker(
+        database,
+        configuration.backtest_last_n_candles,
+        configuration.pair,
+        configuration.trading_ensemble(),
+        options,
+      )
+      .await?
+    },
+  };
+  Ok(ticker)
+}
+
+/// Passes the ticker's candles on to the traders of a session, waiting while the channel
+/// is half full so none are skipped. The channel closes when the candles run out or the
+/// backtest is cancelled, candles the ticker sent ahead are dropped then.
+pub fn forward(
+  mut ticker: mpsc::Receiver<MarketEvent>,
+  events_tx: broadcast::Sender<Event>,
+  cancel: CancellationToken,
+) {
+  tokio::spawn(async move {
+    while let Some(market_event) = ticker.recv().await {
+      while events_tx.len() >= BACKTEST_EVENTS / 2 {
+        if cancel.is_cancelled() {
+          return;
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+      }
+      if cancel.is_cancelled() || events_tx.send(Event::Market(market_event)).is_err() {
+        break;
+      }
+    }
+  });
+}
+
+/// Training candles of the generated model, failing when they changed since.
+fn dataset_candles(model: &str) -> Result<Vec<Candle>, TraderError> {
+  let metadata = get_generated_models()
+    .map_err(|e| StrategyError::FileError(e.to_string()))?
+    .into_iter()
+    .find(|metadata| metadata.name() == model)
+    .ok_or_else(|| {
+      StrategyError::FileError(format!("No generated model named {model}"))
+    })?;
+  Ok(metadata.dataset_candles()?)
+}
@@ -1,6 +1,8 @@
 use crate::{
-  assets::error::AssetError, exchange::error::ExchangeError,
-  portfolio::error::PortfolioError, strategy::error::StrategyError,
+  assets::{error::AssetError, Pair},
+  database::error::DatabaseError,
+  exchange::error::ExchangeError, portfolio::error::PortfolioError,
+  strategy::error::StrategyError,
 };
 use thiserror::Error;
 
@@ -18,4 +20,12 @@ pub enum TraderError {
   AssetError(#[from] AssetError),
   #[error("Exchange error: {0}")]
   ExchangeError(#[from] ExchangeError),
+  #[error("Database error: {0}")]
+  Database(#[from] DatabaseError),
+  #[error("Failed to replay recording: {0}")]
+  Replay(String),
+  #[error("Failed to load models of scheduled backtests: {0}")]
+  Schedule(String),
+  #[error("No stored candles of {0} to stress test on")]
+  NoStressCandles(Pair),
 }
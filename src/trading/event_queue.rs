@@ -0,0 +1,385 @@
+use crate::events::Event;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use strum::EnumCount;
+
+/// What the queue drops when a market event arrives while it is full.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum OverflowPolicy {
+  /// Drops the oldest queued market event to make room.
+  #[default]
+  DropOldest,
+  /// Drops the incoming market event.
+  DropNewest,
+}
+
+/// Size of the trader's event queue, from `trader_queue` in the config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EventQueueConfig {
+  /// Market events the queue holds before the overflow policy applies.
+  pub capacity: usize,
+  pub overflow: OverflowPolicy,
+}
+
+impl Default for EventQueueConfig {
+  fn default() -> Self {
+    Self { capacity: 256, overflow: OverflowPolicy::DropOldest }
+  }
+}
+
+/// Events lost on the way to the trader.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueueLag {
+  /// Market events dropped by the overflow policy.
+  pub dropped: u64,
+  /// Events the broadcast channel skipped before they were queued.
+  pub lagged: u64,
+}
+
+/// Order in which queued events are handled, so position state is current before the
+/// next signal is evaluated. Events of the same priority keep their order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumCount)]
+pub enum Priority {
+  Fill,
+  Order,
+  ForceExit,
+  Signal,
+  Other,
+  Market,
+}
+
+impl Priority {
+  pub fn of(event: &Event) -> Self {
+    match event {
+      Event::Fill(_) => Priority::Fill,
+      Event::Order(_) => Priority::Order,
+      Event::SignalForceExit(_) => Priority::ForceExit,
+      Event::Signal(_) => Priority::Signal,
+      Event::Market(_) => Priority::Market,
+      _ => Priority::Other,
+    }
+  }
+}
+
+/// Events waiting for the trader, popped by [Priority]. Only market events count
+/// towards the capacity and are dropped on overflow, orders, fills and every other
+/// event are always kept.
+#[derive(Debug)]
+pub struct EventQueue {
+  config: EventQueueConfig,
+  lanes: [VecDeque<Event>; Priority::COUNT],
+  lag: QueueLag,
+}
+
+impl EventQueue {
+  pub fn new(config: EventQueueConfig) -> Self {
+    Self { config, lanes: Default::default(), lag: QueueLag::default() }
+  }
+
+  /// Never drops anything, for replays where every event is queued up front.
+  pub fn unbounded() -> Self {
+    Self::new(EventQueueConfig { capacity: usize::MAX, ..EventQueueConfig::default() })
+  }
+
+  /// Returns the market event dropped to stay within the capacity.
+  pub fn push(&mut self, event: Event) -> Option<Event> {
+    let priority = Priority::of(&event);
+    if priority != Priority::Market {
+      self.lanes[priority as usize].push_back(event);
+      return None;
+    }
+    let market = &mut self.lanes[Priority::Market as usize];
+    let dropped = if market.len() < self.config.capacity {
+      None
+    } else {
+      match self.config.overflow {
+        OverflowPolicy::DropOldest => market.pop_front(),
+        OverflowPolicy::DropNewest => return self.count_dropped(event),
+      }
+    };
+    market.push_back(event);
+    dropped.and_then(|dropped| self.count_dropped(dropped))
+  }
+
+  fn count_dropped(&mut self, event: Event) -> Option<Event> {
+    self.lag.dropped += 1;
+    Some(event)
+  }
+
+  pub fn pop(&mut self) -> Option<Event> {
+    self.lanes.iter_mut().find_map(VecDeque::pop_front)
+  }
+
+  /// Counts events the broadcast channel skipped.
+  pub fn record_lag(&mut self, skipped: u64) {
+    self.lag.lagged += skipped;
+  }
+
+  pub fn lag(&self) -> QueueLag {
+    self.lag
+  }
+
+  pub fn len(&self) -> usize {
+    self.lanes.iter().map(VecDeque::len).sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+impl Default for EventQueue {
+  fn default() -> Self {
+    Self::new(EventQueueConfig::default())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use super::*;
+  use crate::{
+    assets::{Candle, MarketEvent, MarketEventDetail, MarketMeta, Pair},
+    exchange::ExchangeEvent,
+    portfolio::{fixtures::fill_of, OrderEvent},
+    strategy::{Decision, Signal},
+    trading::{execution::Fees, SignalForceExit},
+  };
+  use chrono::{DateTime, Duration, Utc};
+
+  fn time(i: i64) -> DateTime<Utc> {
+    DateTime::UNIX_EPOCH + Duration::try_minutes(i).unwrap()
+  }
+
+  fn market(i: i64) -> Event {
+    let candle = Candle {
+      open_time: time(i),
+      close_time: time(i + 1),
+      open: 1.0,
+      high: 1.0,
+      low: 1.0,
+      close: 1.0,
+      volume: 1.0,
+      trade_count: 1,
+    };
+    Event::Market(MarketEvent {
+      time: time(i),
+      pair: Pair::BTCUSDT,
+      detail: MarketEventDetail::Candle(candle),
+      degraded: false,
+    })
+  }
+
+  fn order(i: i64) -> Event {
+    Event::Order(OrderEvent {
+      time: time(i),
+      pair: Pair::BTCUSDT,
+      decision: Decision::Long,
+      market_meta: MarketMeta { close: 1.0, time: time(i) },
+      quantity: 1.0,
+      options: Default::default(),
+    })
+  }
+
+  fn fill(i: i64) -> Event {
+    Event::Fill(fill_of(Pair::BTCUSDT, Decision::Long, 1.0, 1.0, Fees::default(), time(i)))
+  }
+
+  fn signal(i: i64) -> Event {
+    Event::Signal(Signal {
+      time: time(i),
+      pair: Pair::BTCUSDT,
+      market_meta: MarketMeta { close: 1.0, time: time(i) },
+      signals: Default::default(),
+      votes: Vec::new(),
+    })
+  }
+
+  fn force_exit(i: i64) -> Event {
+    Event::SignalForceExit(SignalForceExit {
+      time: time(i),
+      asset: Pair::BTCUSDT,
+      share: 1.0,
+    })
+  }
+
+  fn event(priority: Priority, i: i64) -> Event {
+    match priority {
+      Priority::Fill => fill(i),
+      Priority::Order => order(i),
+      Priority::ForceExit => force_exit(i),
+      Priority::Signal => signal(i),
+      Priority::Other => {
+        Event::Exchange(ExchangeEvent::ExchangeBalanceUpdate(Default::default(), vec![]))
+      },
+      Priority::Market => market(i),
+    }
+  }
+
+  fn event_time(event: &Event) -> DateTime<Utc> {
+    match event {
+      Event::Fill(fill) => fill.time,
+      Event::Order(order) => order.time,
+      Event::SignalForceExit(exit) => exit.time,
+      Event::Signal(signal) => signal.time,
+      Event::Market(market_event) => market_event.time,
+      _ => DateTime::UNIX_EPOCH,
+    }
+  }
+
+  fn market_time(event: &Event) -> Option<DateTime<Utc>> {
+    match event {
+      Event::Market(market_event) => Some(market_event.time),
+      _ => None,
+    }
+  }
+
+  fn queue(capacity: usize, overflow: OverflowPolicy) -> EventQueue {
+    EventQueue::new(EventQueueConfig { capacity, overflow })
+  }
+
+  /// Ten market events and an order and a fill arrive for every event the strategy
+  /// gets through.
+  fn run_slow_strategy(queue: &mut EventQueue, rounds: i64) -> Vec<Event> {
+    let mut handled = Vec::new();
+    for round in 0..rounds {
+      for i in 0..10 {
+        queue.push(market(round * 10 + i));
+      }
+      queue.push(order(round));
+      queue.push(fill(round));
+      handled.extend(queue.pop());
+    }
+    while let Some(event) = queue.pop() {
+      handled.push(event);
+    }
+    handled
+  }
+
+  #[test]
+  fn test_slow_strategy_never_loses_orders_or_fills() {
+    for overflow in [OverflowPolicy::DropOldest, OverflowPolicy::DropNewest] {
+      let mut queue = queue(8, overflow);
+      let handled = run_slow_strategy(&mut queue, 20);
+      let orders = handled.iter().filter(|e| matches!(e, Event::Order(_))).count();
+      let fills = handled.iter().filter(|e| matches!(e, Event::Fill(_))).count();
+      let markets = handled.iter().filter(|e| matches!(e, Event::Market(_))).count();
+      assert_eq!(orders, 20);
+      assert_eq!(fills, 20);
+      assert_eq!(markets as u64 + queue.lag().dropped, 200);
+      assert!(queue.lag().dropped > 0);
+    }
+  }
+
+  #[test]
+  fn test_drop_oldest_keeps_latest_market_events() {
+    let mut queue = queue(3, OverflowPolicy::DropOldest);
+    for i in 0..5 {
+      queue.push(market(i));
+    }
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.lag().dropped, 2);
+    let times: Vec<_> =
+      std::iter::from_fn(|| queue.pop()).filter_map(|e| market_time(&e)).collect();
+    assert_eq!(times, vec![time(2), time(3), time(4)]);
+  }
+
+  #[test]
+  fn test_drop_newest_keeps_earliest_market_events() {
+    let mut queue = queue(3, OverflowPolicy::DropNewest);
+    let dropped: Vec<_> = (0..5).filter_map(|i| queue.push(market(i))).collect();
+    assert_eq!(
+      dropped.iter().filter_map(market_time).collect::<Vec<_>>(),
+      [time(3), time(4)]
+    );
+    let times: Vec<_> =
+      std::iter::from_fn(|| queue.pop()).filter_map(|e| market_time(&e)).collect();
+    assert_eq!(times, vec![time(0), time(1), time(2)]);
+  }
+
+  #[test]
+  fn test_full_queue_still_takes_orders_and_fills() {
+    let mut queue = queue(1, OverflowPolicy::DropOldest);
+    queue.push(market(0));
+    assert_eq!(queue.push(order(0)), None);
+    assert_eq!(queue.push(fill(0)), None);
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.lag().dropped, 0);
+  }
+
+  #[test]
+  fn test_lag_accounting() {
+    let mut queue = queue(1, OverflowPolicy::DropOldest);
+    queue.record_lag(4);
+    queue.push(market(0));
+    queue.push(market(1));
+    assert_eq!(queue.lag(), QueueLag { dropped: 1, lagged: 4 });
+  }
+
+  #[test]
+  fn test_fills_and_orders_before_queued_market_events() {
+    let mut queue = EventQueue::default();
+    queue.push(market(0));
+    queue.push(market(1));
+    queue.push(signal(2));
+    queue.push(order(3));
+    queue.push(fill(4));
+    let priorities: Vec<_> =
+      std::iter::from_fn(|| queue.pop()).map(|e| Priority::of(&e)).collect();
+    assert_eq!(
+      priorities,
+      [
+        Priority::Fill,
+        Priority::Order,
+        Priority::Signal,
+        Priority::Market,
+        Priority::Market
+      ]
+    );
+  }
+
+  fn priority() -> impl Strategy<Value = Priority> {
+    prop::sample::select(vec![
+      Priority::Fill,
+      Priority::Order,
+      Priority::ForceExit,
+      Priority::Signal,
+      Priority::Market,
+    ])
+  }
+
+  proptest! {
+    /// Bursts of events arrive between pops, every pop takes the earliest queued event
+    /// of the highest priority.
+    #[test]
+    fn test_pops_by_priority_then_arrival_under_bursts(
+      bursts in prop::collection::vec(
+        (prop::collection::vec(priority(), 0..20), 0..5usize),
+        1..20,
+      )
+    ) {
+      let mut queue = EventQueue::unbounded();
+      let mut expected: Vec<(Priority, i64)> = Vec::new();
+      let mut i = 0;
+      for (burst, pops) in bursts {
+        for priority in burst {
+          queue.push(event(priority, i));
+          expected.push((priority, i));
+          i += 1;
+        }
+        for _ in 0..pops {
+          let next = expected.iter().copied().min();
+          let popped = queue.pop();
+          prop_assert_eq!(popped.as_ref().map(Priority::of), next.map(|(p, _)| p));
+          if let (Some(popped), Some(next)) = (popped, next) {
+            prop_assert_eq!(event_time(&popped), time(next.1));
+            expected.retain(|queued| *queued != next);
+          }
+        }
+      }
+      prop_assert_eq!(queue.len(), expected.len());
+    }
+  }
+}
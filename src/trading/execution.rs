@@ -1,20 +1,70 @@
-use super::error::TraderError;
+use super::{
+  error::TraderError,
+  slicing::{SlicingConfig, MAX_CHILD_ORDERS, QUANTITY_STEP},
+};
 use crate::{
-  assets::{MarketMeta, Pair, Side},
+  assets::{Candle, MarketMeta, Pair, Side},
+  clock::SharedClock,
   exchange::{
     binance_client::{self, BinanceClient},
-    execution::fill_order,
+    error::{ApiError, ExchangeError},
+    execution::{
+      cancel_order, fill_order, place_trailing_stop, query_order, ExchangeFill,
+    },
+    futures::{fetch_position, fill_futures_order, ExchangePosition, FuturesSettings},
+    market::fetch_book_ticker,
+    sim::SimExchange,
   },
-  portfolio::OrderEvent,
+  portfolio::{position::Position, OrderEvent},
   strategy::Decision,
 };
 use chrono::{DateTime, Utc};
 use rust_decimal::prelude::Signed;
 use serde::{Deserialize, Serialize};
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::instrument;
+
+/// Attempts at sending an order that Binance turned away without executing it.
+const ORDER_ATTEMPTS: u32 = 3;
+const ORDER_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Bounds of `trailingDelta` in basis points Binance accepts.
+const TRAILING_DELTA_BIPS: (u64, u64) = (10, 2000);
 
+#[derive(Clone)]
 pub struct Execution {
   exchange_fee: f64,
-  binance_client: BinanceClient,
+  clock: SharedClock,
+  /// None fills every order at its market price without an exchange, for replays.
+  binance_client: Option<BinanceClient>,
+  /// Splitting of large orders of live runs.
+  slicing: SlicingConfig,
+  /// Orders go to USD-M futures with these settings instead of spot.
+  futures: Option<FuturesSettings>,
+  /// Orders fill on the simulated exchange instead of the client.
+  sim: Option<Arc<Mutex<SimExchange>>>,
+}
+
+/// Trailing stop order on the exchange that closes the open position of the pair.
+#[derive(Clone, PartialEq, Debug)]
+pub struct NativeStop {
+  pub order_id: u64,
+  pub pair: Pair,
+  /// Of the exit, opposite to the entry.
+  pub side: Side,
+  pub quantity: f64,
+  pub trailing_stop: f64,
+}
+
+/// The trailing stop as a share of the price in basis points, within what Binance
+/// accepts.
+fn trailing_delta(trailing_stop: f64) -> u64 {
+  let (min, max) = TRAILING_DELTA_BIPS;
+  ((trailing_stop * 10_000.0).round() as u64).clamp(min, max)
 }
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default, Deserialize, Serialize)]
@@ -32,9 +82,280 @@ impl Fees {
 pub type FeeAmount = f64;
 
 impl Execution {
-  pub fn new(exchange_fee: f64, binance_client: BinanceClient) -> Self {
-    Execution { exchange_fee, binance_client }
+  pub fn new(
+    exchange_fee: f64,
+    binance_client: BinanceClient,
+    clock: SharedClock,
+  ) -> Self {
+    Execution {
+      exchange_fee,
+      clock,
+      binance_client: Some(binance_client),
+      slicing: SlicingConfig::default(),
+      futures: None,
+      sim: None,
+    }
+  }
+
+  pub fn stub(exchange_fee: f64, clock: SharedClock) -> Self {
+    Execution {
+      exchange_fee,
+      clock,
+      binance_client: None,
+      slicing: Default::default(),
+      futures: None,
+      sim: None,
+    }
+  }
+
+  pub fn with_slicing(self, slicing: SlicingConfig) -> Self {
+    Self { slicing, ..self }
+  }
+
+  /// Sends orders to USD-M futures, with a client of the futures API.
+  pub fn with_futures(self, settings: FuturesSettings) -> Self {
+    Self { futures: Some(settings), ..self }
+  }
+
+  /// Fills orders on the simulated exchange, which the trader moves with its candles.
+  pub fn with_sim(self, sim: Arc<Mutex<SimExchange>>) -> Self {
+    Self { sim: Some(sim), ..self }
+  }
+
+  /// Moves the simulated exchange, if any, to the candle of the pair.
+  pub fn on_candle(&self, pair: Pair, candle: &Candle) {
+    if let Some(sim) = &self.sim {
+      sim.lock().expect("Sim exchange lock poisoned").on_candle(pair, candle);
+    }
+  }
+
+  /// Sends the order, retrying when Binance rejected it before execution because of
+  /// rate limits or the request timestamp. Other errors, including 5xx responses and
+  /// network failures where the order may have gone through, abort the fill.
+  async fn send_order(
+    &self,
+    binance_client: &BinanceClient,
+    order: &OrderEvent,
+    side: Side,
+    quantity: f64,
+  ) -> Result<ExchangeFill, ExchangeError> {
+    let mut attempt = 1;
+    loop {
+      let result = match &self.futures {
+        Some(settings) => fill_futures_order(
+          binance_client,
+          order.pair,
+          quantity,
+          side.clone(),
+          order.decision.is_entry(),
+          order.market_meta.close,
+          &order.options,
+          settings,
+        ),
+        None => fill_order(
+          binance_client,
+          order.pair.clone(),
+          quantity,
+          side.clone(),
+          order.market_meta.close,
+          &order.options,
+        ),
+      };
+      match result {
+        Err(ExchangeError::Api(
+          e @ (ApiError::RateLimited { .. } | ApiError::TimestampOutOfRecvWindow(_)),
+        )) if attempt < ORDER_ATTEMPTS => {
+          let delay = e.retry_after().unwrap_or(ORDER_RETRY_DELAY);
+          log::warn!("Order for {} rejected ({}), retry in {:?}", order.pair, e, delay);
+          tokio::time::sleep(delay).await;
+          attempt += 1;
+        },
+        result => return result,
+      }
+    }
+  }
+
+  /// Sends the order as child orders of the slicing algorithm, sized by the best level of
+  /// the book on the side they take from. A child order that fails, or cancelling
+  /// between two of them, ends the order with what filled before.
+  async fn send_sliced(
+    &self,
+    binance_client: &BinanceClient,
+    order: &OrderEvent,
+    side: Side,
+    cancel: &CancellationToken,
+  ) -> Result<ExchangeFill, ExchangeError> {
+    let total = order.quantity.abs();
+    let (mut filled, mut value, mut remaining) = (0.0, 0.0, total);
+    let mut base_commission = 0.0;
+    let mut updated_at = self.clock.now();
+    let mut sent = 0;
+    while remaining >= QUANTITY_STEP && sent < MAX_CHILD_ORDERS {
+      if sent > 0 {
+        tokio::select! {
+          _ = tokio::time::sleep(self.slicing.delay()) => {},
+          _ = cancel.cancelled() => {
+            log::info!("Order for {} cancelled at {} of {}", order.pair, filled, total);
+            break;
+          },
+        }
+      }
+      let book = fetch_book_ticker(binance_client, order.pair)
+        .inspect_err(|e| log::warn!("No order book of {} to slice on: {}", order.pair, e))
+        .ok()
+        .map(|book| match side {
+          Side::Buy => book.best_ask.amount,
+          Side::Sell => book.best_bid.amount,
+        });
+      let quantity = self.slicing.child_quantity(total, remaining, sent, book);
+      sent += 1;
+      match self.send_order(binance_client, order, side.clone(), quantity).await {
+        Ok(fill) => {
+          filled += fill.qty;
+          value += fill.qty * fill.price;
+          remaining -= fill.qty;
+          base_commission += fill.base_commission;
+          updated_at = fill.updated_at;
+        },
+        Err(e) if filled > 0.0 => {
+          let pair = order.pair;
+          log::warn!("Order for {} stopped at {} of {}: {}", pair, filled, total, e);
+          break;
+        },
+        Err(e) => return Err(e),
+      }
+    }
+    let algorithm = self.slicing.algorithm;
+    log::info!("Sent the order for {} as {} {} orders.", order.pair, sent, algorithm);
+    if filled > 0.0 {
+      let price = value / filled;
+      Ok(ExchangeFill { qty: filled, updated_at, price, base_commission })
+    } else {
+      Err(ExchangeError::UnfilledOrder)
+    }
+  }
+
+  fn client(&self) -> Result<&BinanceClient, ExchangeError> {
+    self
+      .binance_client
+      .as_ref()
+      .ok_or(ExchangeError::BinanceClientError("no exchange to keep stops on".into()))
+  }
+
+  /// Whether orders go to USD-M futures on the exchange, rather than spot or a
+  /// simulation.
+  pub fn trades_futures(&self) -> bool {
+    self.futures.is_some() && self.binance_client.is_some() && self.sim.is_none()
+  }
+
+  /// Open futures position of the pair on the exchange, none without one.
+  pub fn futures_position(
+    &self,
+    pair: Pair,
+  ) -> Result<Option<ExchangePosition>, ExchangeError> {
+    match (&self.futures, &self.binance_client) {
+      (Some(_), Some(binance_client)) => fetch_position(binance_client, pair),
+      _ => Ok(None),
+    }
+  }
+
+  /// Exit fill of a position the exchange liquidated, at its liquidation price.
+  pub fn liquidation_fill(&self, position: &Position) -> Result<FillEvent, TraderError> {
+    let time = self.clock.now();
+    let price = position.liquidation_price.unwrap_or(position.current_symbol_price);
+    let quantity = position.open_quantity();
+    let decision = position.determine_exit_decision();
+    let fill = FillEvent::builder()
+      .time(time)
+      .asset(position.asset)
+      .market_meta(MarketMeta { close: price, time })
+      .decision(decision)
+      .quantity(decision.quantity_sign() * quantity)
+      .fill_value_gross(quantity * price)
+      .fees(Fees { exchange: self.exchange_fee, slippage: 0.0 })
+      .build()?;
+    Ok(fill)
+  }
+
+  /// Places a trailing stop on the exchange that exits `quantity` of the position.
+  pub fn place_trailing_stop(
+    &self,
+    position: &Position,
+    quantity: f64,
+    trailing_stop: f64,
+  ) -> Result<NativeStop, ExchangeError> {
+    if self.futures.is_some() {
+      let error = "exchange trailing stops are only kept on spot";
+      return Err(ExchangeError::BinanceClientError(error.into()));
+    }
+    let side = match position.determine_exit_decision() {
+      Decision::CloseShort => Side::Buy,
+      _ => Side::Sell,
+    };
+    let stop =
+      NativeStop { order_id: 0, pair: position.asset, side, quantity, trailing_stop };
+    self.renew_trailing_stop(&stop, trailing_stop)
+  }
+
+  /// Places the stop again at another trailing stop, once the previous one is cancelled.
+  pub fn renew_trailing_stop(
+    &self,
+    stop: &NativeStop,
+    trailing_stop: f64,
+  ) -> Result<NativeStop, ExchangeError> {
+    let order_id = place_trailing_stop(
+      self.client()?,
+      stop.pair,
+      stop.quantity,
+      stop.side.clone(),
+      trailing_delta(trailing_stop),
+    )?;
+    Ok(NativeStop { order_id, trailing_stop, ..stop.clone() })
   }
+
+  /// Fill of the stop once the exchange triggered and filled it.
+  pub fn stop_fill(&self, stop: &NativeStop) -> Result<Option<FillEvent>, TraderError> {
+    let order = query_order(self.client()?, stop.pair, stop.order_id)?;
+    if order.status != "FILLED" || order.executed_qty == 0.0 {
+      return Ok(None);
+    }
+    let time =
+      DateTime::from_timestamp_millis(order.update_time).unwrap_or(self.clock.now());
+    let price = order.cummulative_quote_qty / order.executed_qty;
+    let decision = match stop.side {
+      Side::Sell => Decision::CloseLong,
+      Side::Buy => Decision::CloseShort,
+    };
+    let fill = FillEvent::builder()
+      .time(time)
+      .asset(stop.pair)
+      .market_meta(MarketMeta { close: price, time })
+      .decision(decision)
+      .quantity(decision.quantity_sign() * order.executed_qty)
+      .fill_value_gross(order.cummulative_quote_qty)
+      .fees(Fees { exchange: self.exchange_fee, slippage: 0.0 })
+      .build()?;
+    Ok(Some(fill))
+  }
+
+  /// Cancels the stop before the position is exited otherwise. Returns its fill instead
+  /// when the exchange filled it first.
+  pub fn cancel_trailing_stop(
+    &self,
+    stop: &NativeStop,
+  ) -> Result<Option<FillEvent>, TraderError> {
+    match cancel_order(self.client()?, stop.pair, stop.order_id) {
+      Ok(()) => Ok(None),
+      Err(ExchangeError::Api(ApiError::UnknownOrder(_))) => self.stop_fill(stop),
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  #[instrument(
+    name = "execution",
+    skip_all,
+    fields(pair = %order.pair, live = is_live_run)
+  )]
   pub async fn generate_fill(
     &self,
     order: &OrderEvent,
@@ -42,22 +363,86 @@ impl Execution {
   ) -> Result<FillEvent, TraderError> {
     log::info!("Received a new order to fill: {:?}", order);
 
-    let fill_time = if is_live_run { Utc::now() } else { order.time };
+    let side = order_side(order);
+    let exchange_execution = match (&self.sim, &self.binance_client) {
+      (Some(sim), _) => sim.lock().expect("Sim exchange lock poisoned").fill_order(
+        order.pair,
+        order.quantity.abs(),
+        side,
+        order.market_meta.close,
+        &order.options,
+        self.clock.now(),
+      )?,
+      (None, Some(binance_client)) => {
+        self.send_order(binance_client, order, side, order.quantity.abs()).await?
+      },
+      (None, None) => ExchangeFill {
+        qty: order.quantity.abs(),
+        updated_at: self.clock.now(),
+        price: order.market_meta.close,
+        base_commission: 0.0,
+      },
+    };
+
+    self.fill_event(order, exchange_execution, is_live_run)
+  }
+
+  /// Whether the order of a live run goes out as child orders of the slicing algorithm,
+  /// to be sent with `spawn_sliced` rather than `generate_fill`.
+  pub fn slices(&self, order: &OrderEvent, is_live_run: bool) -> bool {
+    // Slices are sized by the spot order book
+    is_live_run
+      && self.sim.is_none()
+      && self.futures.is_none()
+      && self.binance_client.is_some()
+      && self.slicing.applies(order.quantity, order.market_meta.close)
+  }
 
-    let side = if order.decision.is_entry() { Side::Buy } else { Side::Sell };
-    let exchange_execution =
-      fill_order(&self.binance_client, order.pair.clone(), order.quantity.abs(), side)?;
+  /// Sends the child orders of the order in a task of its own, so the trader stays
+  /// responsive while they are spaced out. The task ends with the fill of what the child
+  /// orders filled, and early with it once cancelled.
+  pub fn spawn_sliced(
+    &self,
+    order: OrderEvent,
+    cancel: CancellationToken,
+  ) -> JoinHandle<Result<FillEvent, TraderError>> {
+    let execution = self.clone();
+    tokio::spawn(async move {
+      log::info!("Received a new order to slice: {:?}", order);
+      let binance_client = execution.client()?;
+      let side = order_side(&order);
+      let exchange_execution =
+        execution.send_sliced(binance_client, &order, side, &cancel).await?;
+      execution.fill_event(&order, exchange_execution, true)
+    })
+  }
 
-    let fill_event = FillEvent::builder()
-      .time(exchange_execution.updated_at)
+  fn fill_event(
+    &self,
+    order: &OrderEvent,
+    exchange_execution: ExchangeFill,
+    is_live_run: bool,
+  ) -> Result<FillEvent, TraderError> {
+    // Backtest orders are still sent to the exchange, but happen at the session's time
+    let fill_time =
+      if is_live_run { exchange_execution.updated_at } else { self.clock.now() };
+    FillEvent::builder()
+      .time(fill_time)
       .asset(order.pair.clone())
       .market_meta(order.market_meta)
       .decision(order.decision)
-      .quantity(exchange_execution.qty)
+      .quantity(order.decision.quantity_sign() * exchange_execution.qty)
       .fill_value_gross(exchange_execution.qty.abs() * exchange_execution.price)
       .fees(Fees { exchange: self.exchange_fee, slippage: 0.0 })
-      .build()?;
-    Ok(fill_event)
+      .base_commission(exchange_execution.base_commission)
+      .build()
+  }
+}
+
+fn order_side(order: &OrderEvent) -> Side {
+  match order.decision {
+    Decision::Long | Decision::CloseShort => Side::Buy,
+    Decision::Short | Decision::CloseLong => Side::Sell,
   }
 }
 
@@ -70,6 +455,9 @@ pub struct FillEvent {
   pub quantity: f64,
   pub fill_value_gross: f64,
   pub fees: Fees,
+  /// Part of the quantity the exchange took as commission, in the base asset.
+  #[serde(default)]
+  pub base_commission: f64,
 }
 
 impl FillEvent {
@@ -87,6 +475,7 @@ pub struct FillEventBuilder {
   pub fill_value_gross: Option<f64>,
   pub fees: Option<Fees>,
   pub market_meta: Option<MarketMeta>,
+  pub base_commission: Option<f64>,
 }
 
 impl FillEventBuilder {
@@ -122,6 +511,10 @@ impl FillEventBuilder {
     Self { market_meta: Some(value), ..self }
   }
 
+  pub fn base_commission(self, value: f64) -> Self {
+    Self { base_commission: Some(value), ..self }
+  }
+
   pub fn build(self) -> Result<FillEvent, TraderError> {
     Ok(FillEvent {
       time: self.time.ok_or(TraderError::FillBuilderIncomplete("time"))?,
@@ -135,6 +528,291 @@ impl FillEventBuilder {
       market_meta: self
         .market_meta
         .ok_or(TraderError::FillBuilderIncomplete("market_meta"))?,
+      base_commission: self.base_commission.unwrap_or_default(),
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clock::real_clock,
+    trading::{
+      params::{OrderOptions, OrderType},
+      slicing::SlicingAlgorithm,
+    },
+  };
+  use serde_json::json;
+  use uuid::Uuid;
+  use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+  };
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn keeps_the_trailing_stop_on_the_exchange() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .and(query_param("type", "STOP_LOSS"))
+      .and(query_param("side", "SELL"))
+      .and(query_param("trailingDelta", "50"))
+      // Less the commission taken in BTC
+      .and(query_param("quantity", "0.00999"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "symbol": "BTCUSDT",
+        "orderId": 42,
+        "orderListId": -1,
+        "clientOrderId": "stop",
+        "transactTime": 1_700_000_000_000i64
+      })))
+      .mount(&server)
+      .await;
+    Mock::given(method("DELETE"))
+      .and(path("/api/v3/order"))
+      .respond_with(
+        ResponseTemplate::new(400)
+          .set_body_json(json!({ "code": -2011, "msg": "Unknown order sent." })),
+      )
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/api/v3/order"))
+      .and(query_param("orderId", "42"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "symbol": "BTCUSDT",
+        "orderId": 42,
+        "executedQty": "0.01",
+        "cummulativeQuoteQty": "410",
+        "status": "FILLED",
+        "type": "STOP_LOSS",
+        "side": "SELL",
+        "updateTime": 1_700_000_060_000i64,
+        "trailingDelta": 50
+      })))
+      .mount(&server)
+      .await;
+    let execution =
+      Execution::new(0.001, BinanceClient::with_url(&server.uri()), real_clock());
+    let time = DateTime::UNIX_EPOCH;
+    let entry = FillEvent::builder()
+      .time(time)
+      .asset(Pair::BTCUSDT)
+      .market_meta(MarketMeta { close: 42_000.0, time })
+      .decision(Decision::Long)
+      .quantity(0.01)
+      .fill_value_gross(420.0)
+      .fees(Fees::default())
+      .base_commission(0.00001)
+      .build()
+      .unwrap();
+    let position = Position::enter(Uuid::new_v4(), &entry).unwrap();
+    let quantity = position.open_quantity() - entry.base_commission;
+
+    let stop = execution.place_trailing_stop(&position, quantity, 0.005).unwrap();
+    assert_eq!((stop.order_id, stop.side.clone()), (42, Side::Sell));
+
+    // Cancelling a stop the exchange already filled returns its fill
+    let fill = execution.cancel_trailing_stop(&stop).unwrap().unwrap();
+    assert_eq!(fill.decision, Decision::CloseLong);
+    assert_eq!(fill.market_meta.close, 41_000.0);
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn reports_the_commission_taken_in_the_base_asset() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "transactTime": 1_700_000_000_000i64,
+        "executedQty": "0.02",
+        "status": "FILLED",
+        "fills": [
+          { "price": "40000", "qty": "0.01", "commission": "0.00001",
+            "commissionAsset": "BTC" },
+          { "price": "40000", "qty": "0.01", "commission": "0.4",
+            "commissionAsset": "USDT" }
+        ]
+      })))
+      .mount(&server)
+      .await;
+    let execution =
+      Execution::new(0.001, BinanceClient::with_url(&server.uri()), real_clock());
+    let time = DateTime::UNIX_EPOCH;
+    let order = OrderEvent {
+      time,
+      pair: Pair::BTCUSDT,
+      decision: Decision::Long,
+      market_meta: MarketMeta { close: 40_000.0, time },
+      quantity: 0.02,
+      options: Default::default(),
+    };
+
+    let fill = execution.generate_fill(&order, true).await.unwrap();
+    assert_eq!((fill.quantity, fill.base_commission), (0.02, 0.00001));
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn cancels_what_a_limit_order_does_not_fill() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .and(query_param("type", "LIMIT"))
+      .and(query_param("timeInForce", "GTC"))
+      .and(query_param("price", "41000.12"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "orderId": 7,
+        "transactTime": 1_700_000_000_000i64,
+        "executedQty": "0.004",
+        "status": "PARTIALLY_FILLED",
+        "fills": [{ "price": "41000.12", "qty": "0.004", "commission": "0" }]
+      })))
+      .mount(&server)
+      .await;
+    Mock::given(method("DELETE"))
+      .and(path("/api/v3/order"))
+      .and(query_param("orderId", "7"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/api/v3/order"))
+      .and(query_param("orderId", "7"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "orderId": 7,
+        "executedQty": "0.005",
+        "cummulativeQuoteQty": "205",
+        "status": "CANCELED",
+        "updateTime": 1_700_000_001_000i64
+      })))
+      .mount(&server)
+      .await;
+    let execution =
+      Execution::new(0.001, BinanceClient::with_url(&server.uri()), real_clock());
+    let time = DateTime::UNIX_EPOCH;
+    let order = OrderEvent {
+      time,
+      pair: Pair::BTCUSDT,
+      decision: Decision::Long,
+      market_meta: MarketMeta { close: 41_000.123, time },
+      quantity: 0.01,
+      options: OrderOptions { order_type: OrderType::Limit, ..Default::default() },
+    };
+
+    // Filled up to the cancel, at the average price of the order
+    let fill = execution.generate_fill(&order, true).await.unwrap();
+    assert_eq!(fill.quantity, 0.005);
+    assert_eq!(fill.fill_value_gross, 205.0);
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn slices_large_orders_into_icebergs() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/api/v3/ticker/bookTicker"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "symbol": "BTCUSDT",
+        "bidPrice": "39999.99",
+        "bidQty": "0.5",
+        "askPrice": "40000",
+        "askQty": "0.03"
+      })))
+      .mount(&server)
+      .await;
+    // Half of the 0.03 at the best ask, less than the 0.04 iceberg share
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .and(query_param("quantity", "0.015"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "transactTime": 1_700_000_000_000i64,
+        "executedQty": "0.015",
+        "status": "FILLED",
+        "fills": [{ "price": "40000", "qty": "0.015", "commission": "0" }]
+      })))
+      .expect(13)
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .and(query_param("quantity", "0.005"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "transactTime": 1_700_000_000_000i64,
+        "executedQty": "0.005",
+        "status": "FILLED",
+        "fills": [{ "price": "40010", "qty": "0.005", "commission": "0" }]
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+    let slicing = SlicingConfig {
+      algorithm: SlicingAlgorithm::Iceberg,
+      ..Default::default()
+    };
+    let execution =
+      Execution::new(0.001, BinanceClient::with_url(&server.uri()), real_clock())
+        .with_slicing(slicing);
+    let time = DateTime::UNIX_EPOCH;
+    let order = OrderEvent {
+      time,
+      pair: Pair::BTCUSDT,
+      decision: Decision::Long,
+      market_meta: MarketMeta { close: 40_000.0, time },
+      quantity: 0.2,
+      options: Default::default(),
+    };
+
+    assert!(execution.slices(&order, true) && !execution.slices(&order, false));
+    let task = execution.spawn_sliced(order, CancellationToken::new());
+    let fill = task.await.unwrap().unwrap();
+    assert!((fill.quantity - 0.2).abs() < 1e-9);
+    assert!((fill.fill_value_gross - (0.195 * 40_000.0 + 0.005 * 40_010.0)).abs() < 1e-6);
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn cancelling_a_twap_ends_it_with_what_filled() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/api/v3/order"))
+      .and(query_param("quantity", "0.04"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "transactTime": 1_700_000_000_000i64,
+        "executedQty": "0.04",
+        "status": "FILLED",
+        "fills": [{ "price": "40000", "qty": "0.04", "commission": "0" }]
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+    let slicing = SlicingConfig {
+      algorithm: SlicingAlgorithm::Twap,
+      slices: 5,
+      interval_secs: 3600,
+      max_book_share: 0.0,
+      ..Default::default()
+    };
+    let execution =
+      Execution::new(0.001, BinanceClient::with_url(&server.uri()), real_clock())
+        .with_slicing(slicing);
+    let time = DateTime::UNIX_EPOCH;
+    let order = OrderEvent {
+      time,
+      pair: Pair::BTCUSDT,
+      decision: Decision::Long,
+      market_meta: MarketMeta { close: 40_000.0, time },
+      quantity: 0.2,
+      options: Default::default(),
+    };
+
+    // The first child order goes out at once, the next one would wait an hour
+    let cancel = CancellationToken::new();
+    let task = execution.spawn_sliced(order, cancel.clone());
+    cancel.cancel();
+    let fill = tokio::time::timeout(Duration::from_secs(10), task)
+      .await
+      .expect("cancelled TWAP still waiting")
+      .unwrap()
+      .unwrap();
+    assert!((fill.quantity - 0.04).abs() < 1e-9);
+  }
+}
@@ -0,0 +1,199 @@
+use super::{error::TraderError, execution::Fees, execution::FillEvent};
+use crate::{
+  assets::{Asset, MarketMeta, Pair},
+  database::{Database, SessionLabel},
+  exchange::{
+    binance_client::BinanceClient,
+    trades::{fetch_account_trades, AccountTrade},
+  },
+  portfolio::{balance::Balance, error::PortfolioError, position::Position},
+  statistic::{self, StatisticConfig, TradingSummary},
+  strategy::Decision,
+};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ImportReport {
+  pub core_id: Uuid,
+  pub pair: Pair,
+  pub trades: usize,
+  /// Sells without a position bought within the range, e.g. of earlier holdings.
+  pub skipped: usize,
+  /// Trades with the commission paid in a third asset, like BNB, left out of the fees.
+  pub unpriced_commissions: usize,
+  pub open_position: Option<Position>,
+  pub statistics: TradingSummary,
+}
+
+impl fmt::Display for ImportReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Imported trades {} of {}", self.core_id, self.pair)?;
+    writeln!(f, "Trades: {}", self.trades)?;
+    writeln!(f, "Skipped sells: {}", self.skipped)?;
+    if self.unpriced_commissions > 0 {
+      writeln!(f, "Commissions in other assets: {}", self.unpriced_commissions)?;
+    }
+    if let Some(position) = &self.open_position {
+      writeln!(
+        f,
+        "Still open: {} at {}",
+        position.quantity, position.enter_avg_price_gross
+      )?;
+    }
+    let tables =
+      statistic::combine(vec![(self.pair.to_string(), self.statistics.clone())]);
+    for table in tables {
+      write!(f, "{}", table)?;
+    }
+    Ok(())
+  }
+}
+
+/// Pulls the account's trades of the pair from Binance and stores the positions they
+/// make up as a session, so its statistics show what was actually traded. Spot trades
+/// can only make long positions: buys enter or add to one and sells exit it.
+pub async fn import_trades(
+  pair: Pair,
+  from: DateTime<Utc>,
+  to: DateTime<Utc>,
+  statistic_config: StatisticConfig,
+) -> Result<ImportReport, TraderError> {
+  let binance_client = BinanceClient::new().await?;
+  let trades = fetch_account_trades(&binance_client, pair, from, to)?;
+  let core_id = Uuid::new_v4();
+  let starting_equity = statistic_config.starting_equity;
+  let mut ledger = TradeLedger::new(
+    core_id,
+    pair,
+    Balance { time: from, total: starting_equity, available: starting_equity },
+  );
+
+  let mut database = Database::new().await?;
+  let label = SessionLabel {
+    label: format!("Imported {pair} trades"),
+    notes: format!("Account trades from {} to {}", from.date_naive(), to.date_naive()),
+  };
+  database.add_session(core_id, from, false, &label).await?;
+  // PnL of the trades is in the pair's quote asset, without prices of other pairs
+  database.init_balance(core_id, ledger.balance, pair.quote())?;
+  database.init_statistics(
+    core_id,
+    [pair],
+    TradingSummary::init(statistic_config, Some(from)),
+  );
+  for trade in &trades {
+    if let Some(position) = ledger.apply(trade)? {
+      database.update_statistics(core_id, &position)?;
+      database.set_exited_position(core_id, position).await?;
+    }
+  }
+  database.set_balance(core_id, ledger.balance)?;
+
+  Ok(ImportReport {
+    core_id,
+    pair,
+    trades: trades.len(),
+    skipped: ledger.skipped,
+    unpriced_commissions: ledger.unpriced_commissions,
+    open_position: ledger.open_position,
+    statistics: database.get_aggregate_statistics(&core_id)?,
+  })
+}
+
+/// Replays account trades into positions and the balance the way the portfolio does with
+/// fills.
+struct TradeLedger {
+  core_id: Uuid,
+  pair: Pair,
+  balance: Balance,
+  open_position: Option<Position>,
+  skipped: usize,
+  unpriced_commissions: usize,
+}
+
+impl TradeLedger {
+  fn new(core_id: Uuid, pair: Pair, balance: Balance) -> Self {
+    Self {
+      core_id,
+      pair,
+      balance,
+      open_position: None,
+      skipped: 0,
+      unpriced_commissions: 0,
+    }
+  }
+
+  /// Returns the position the trade exited.
+  fn apply(&mut self, trade: &AccountTrade) -> Result<Option<Position>, PortfolioError> {
+    self.balance.time = trade.time();
+    if trade.is_buyer {
+      let fill = self.fill(trade, Decision::Long, trade.qty);
+      let fees = fill.fees.calculate_total_fees(fill.fill_value_gross);
+      match &mut self.open_position {
+        Some(position) => position.add_entry_fill(&fill)?,
+        None => self.open_position = Some(Position::enter(self.core_id, &fill)?),
+      }
+      self.balance.reserve(fill.fill_value_gross, fees);
+      return Ok(None);
+    }
+
+    let Some(open_quantity) = self.open_position.as_ref().map(Position::open_quantity)
+    else {
+      self.skipped += 1;
+      return Ok(None);
+    };
+    // Sells past the bought quantity are of holdings from before the range
+    let quantity = trade.qty.min(open_quantity);
+    if quantity < trade.qty {
+      self.skipped += 1;
+    }
+    let fill = self.fill(trade, Decision::CloseLong, quantity);
+    let Some(mut position) = self.open_position.take() else {
+      return Ok(None);
+    };
+    if !position.add_exit_fill(&fill)? {
+      self.open_position = Some(position);
+      return Ok(None);
+    }
+    position.close(self.balance)?;
+    self.balance.apply_exit(&position);
+    Ok(Some(position))
+  }
+
+  /// Fill of `quantity` of the trade, with the commission in the quote asset.
+  fn fill(
+    &mut self,
+    trade: &AccountTrade,
+    decision: Decision,
+    quantity: f64,
+  ) -> FillEvent {
+    let share = if trade.qty > 0.0 { quantity / trade.qty } else { 0.0 };
+    let value = trade.quote_qty * share;
+    let commission_asset = trade.commission_asset.parse::<Asset>().ok();
+    let commission = match commission_asset {
+      Some(asset) if asset == self.pair.quote() => trade.commission * share,
+      Some(asset) if asset == self.pair.base() => trade.commission * trade.price * share,
+      _ => {
+        if trade.commission > 0.0 {
+          self.unpriced_commissions += 1;
+        }
+        0.0
+      },
+    };
+    let exchange = if value > 0.0 { commission / value } else { 0.0 };
+    let quantity = decision.quantity_sign() * quantity;
+    FillEvent {
+      time: trade.time(),
+      asset: self.pair,
+      market_meta: MarketMeta { close: trade.price, time: trade.time() },
+      decision,
+      quantity,
+      fill_value_gross: value,
+      fees: Fees { exchange, slippage: 0.0 },
+      // Counted in the fee instead
+      base_commission: 0.0,
+    }
+  }
+}
@@ -1,34 +1,68 @@
+pub mod backtest;
 pub mod error;
+pub mod event_queue;
 pub mod execution;
+pub mod import;
+pub mod params;
+pub mod replay;
+pub mod schedule;
+pub mod slicing;
+pub mod stress;
 
-use self::{error::TraderError, execution::Execution};
+use self::{
+  error::TraderError,
+  event_queue::{EventQueue, EventQueueConfig},
+  execution::{Execution, FillEvent, NativeStop},
+  params::{RuntimeParams, TrailingStops},
+};
 use crate::{
-  assets::{Feed, MarketEventDetail, MarketFeed, Pair},
-  core::Command,
-  events::{Event, EventTx, MessageTransmitter},
-  portfolio::Portfolio,
-  strategy::Strategy,
+  assets::{
+    candle_window::CandleWindow, Candle, Feed, MarketEventDetail, MarketFeed, Pair,
+  },
+  clock::{real_clock, SharedClock},
+  core::{Command, CoreMessage},
+  database::Database,
+  diagnostics::{self, Stage, Task},
+  exchange::error::{ApiError, ExchangeError},
+  events::{
+    recording::EventRecorder,
+    wal::{WalEntry, WriteAheadLog},
+    Event, EventTx, MessageTransmitter,
+  },
+  portfolio::{
+    error::PortfolioError,
+    position::Position,
+    regime::{RegimeClassifier, RegimeConfig},
+    Portfolio,
+  },
+  strategy::{ModelId, Signal, Strategy},
+  utils::serde_utils::f64_one,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::VecDeque, sync::Arc, time::Duration};
+use std::{sync::Arc, time::Duration};
 use strum::{Display, EnumString};
 use tokio::{
   sync::{broadcast, mpsc, Mutex},
+  task::JoinHandle,
   time::sleep,
 };
-use tracing::{debug, error, info, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, warn};
 use uuid::Uuid;
 
-#[derive(Clone, Eq, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Deserialize, Serialize)]
 pub struct SignalForceExit {
   pub time: DateTime<Utc>,
   pub asset: Pair,
+  /// Share of the open quantity to exit.
+  #[serde(default = "f64_one")]
+  pub share: f64,
 }
 impl SignalForceExit {
   fn from(asset: Pair, time: Option<DateTime<Utc>>) -> Self {
     let time = if time.is_some() { time.unwrap() } else { Utc::now() };
-    SignalForceExit { time, asset }
+    SignalForceExit { time, asset, share: 1.0 }
   }
 }
 
@@ -36,147 +70,351 @@ pub struct Trader {
   core_id: Uuid,
   pub pair: Pair,
   command_reciever: mpsc::Receiver<Command>,
+  message_tx: Option<mpsc::Sender<CoreMessage>>,
   event_transmitter: EventTx,
   event_rx: broadcast::Receiver<Event>,
-  event_queue: VecDeque<Event>,
+  event_queue: EventQueue,
   portfolio: Arc<Mutex<Portfolio>>,
+  database: Arc<Mutex<Database>>,
   strategy: Strategy,
+  /// Closed candles of the pair, handed to the strategy.
+  candle_window: CandleWindow,
+  /// Set once stored candles were loaded into the window.
+  window_loaded: bool,
+  /// Candles still missing from the window when last reported, 0 once warmed up.
+  warm_up_remaining: Option<usize>,
   execution: Execution,
   trading_is_live: bool,
+  paused: bool,
+  /// Set while market data of the pair is stale.
+  entries_paused: bool,
+  recorder: Option<EventRecorder>,
+  /// Orders and fills of live runs, synced before they are acted on.
+  wal: Option<WriteAheadLog>,
+  clock: SharedClock,
+  params: RuntimeParams,
+  /// Update of the parameters waiting for the next closed candle.
+  pending_params: Option<RuntimeParams>,
+  /// Signal that came before the core initialized its balance, retried with the next
+  /// market event of the pair.
+  deferred_signal: Option<Signal>,
+  trailing_stops: TrailingStops,
+  /// Trailing stop on the exchange of the open position, with exchange trailing stops.
+  native_stop: Option<NativeStop>,
+  /// Base asset the exchange took as commission on the entries of the open position,
+  /// which is not held and left out of the stop.
+  entry_commission: f64,
+  /// Order whose child orders are being sent in a task of their own, cancelled to end
+  /// it with what filled so far.
+  sliced_order: Option<(CancellationToken, JoinHandle<Result<FillEvent, TraderError>>)>,
+  /// Forced exit waiting for the sliced order to end.
+  deferred_exit: Option<SignalForceExit>,
+  /// Close of the candle the trader is acting on in live runs, for the latency of the
+  /// signal, order and fill that follow it.
+  candle_closed_at: Option<DateTime<Utc>>,
+  /// Volatility regime of the pair, which sizes entries when enabled.
+  regime: Option<RegimeClassifier>,
 }
 
 impl Trader {
   pub fn builder() -> TraderBuilder {
     TraderBuilder::new()
   }
+  #[instrument(
+    name = "trader",
+    skip_all,
+    fields(core_id = %self.core_id, pair = %self.pair)
+  )]
   pub async fn run(&mut self) -> Result<(), TraderError> {
     let _ = tokio::time::sleep(Duration::from_micros(200)).await;
+    diagnostics::lock(Task::Database, &self.database)
+      .await
+      .set_runtime_params(self.core_id, self.params);
 
     'trader_loop: loop {
+      if self.sliced_order.as_ref().is_some_and(|(_, task)| task.is_finished()) {
+        if let Some(fill) = self.collect_sliced_order().await {
+          self.event_queue.push(Event::Fill(fill));
+        }
+        if let Some(exit) = self.deferred_exit.take() {
+          self.event_queue.push(Event::SignalForceExit(exit));
+        }
+      }
       while let Some(command) = self.receive_remote_command() {
         match command {
-          Command::Terminate(_) => break 'trader_loop,
+          Command::Terminate(_) => {
+            if let Some((cancel, _)) = &self.sliced_order {
+              // What the child orders filled is applied before shutting down
+              cancel.cancel();
+              if let Some(fill) = self.collect_sliced_order().await {
+                self.apply_fill(fill).await?;
+              }
+            }
+            break 'trader_loop;
+          },
           Command::ExitPosition(asset) => {
-            self
-              .event_queue
-              .push_back(Event::SignalForceExit(SignalForceExit::from(asset, None)));
+            let exit = SignalForceExit::from(asset, None);
+            self.event_queue.push(Event::SignalForceExit(exit));
           },
+          Command::ReducePosition(asset, share) => {
+            let exit = SignalForceExit { share, ..SignalForceExit::from(asset, None) };
+            self.event_queue.push(Event::SignalForceExit(exit));
+          },
+          Command::SwapModel(model_id) => self.swap_model(model_id).await,
+          Command::Pause => {
+            info!("Trader {} paused.", self.pair);
+            self.paused = true;
+          },
+          Command::Resume => {
+            info!("Trader {} resumed.", self.pair);
+            self.paused = false;
+          },
+          Command::PauseEntries => self.entries_paused = true,
+          Command::ResumeEntries => self.entries_paused = false,
+          Command::UpdateParams(params) => self.pending_params = Some(params),
           _ => continue,
         }
       }
-      match self.event_rx.try_recv() {
-        Ok(event) => {
-          self.event_queue.push_back(event);
-        },
-        Err(e) => {
-          let err_msg = format!("Error on trader event feed: {:?}", e);
-          match e {
-            broadcast::error::TryRecvError::Empty => {
-              continue;
-            },
-            broadcast::error::TryRecvError::Lagged(num_skipped) => {
-              log::warn!("Trader skipped {} messages (lag).", num_skipped);
-              continue;
-            },
-            broadcast::error::TryRecvError::Closed => {
-              log::warn!("{}", err_msg);
-              let positions =
-                self.portfolio.lock().await.open_positions(self.core_id).await;
-              match positions {
-                Ok(positions) => {
-                  if positions.len() > 0 {
-                    let last_update = positions.last().unwrap().meta.update_time;
-                    self.event_queue.push_back(Event::SignalForceExit(
-                      SignalForceExit::from(self.pair.clone(), Some(last_update)),
-                    ));
-                  } else {
-                    break;
-                  }
-                },
-                Err(e) => {
-                  error!("{:?}", e)
-                },
-              }
-            },
-          }
-        },
+      // Drain the feed so the broadcast channel does not lag, the queue decides what to
+      // drop instead
+      let dropped = self.event_queue.lag().dropped;
+      let mut feed_closed = false;
+      loop {
+        match self.event_rx.try_recv() {
+          Ok(event) => {
+            self.event_queue.push(event);
+          },
+          Err(broadcast::error::TryRecvError::Empty) => break,
+          Err(broadcast::error::TryRecvError::Lagged(num_skipped)) => {
+            log::warn!("Trader skipped {} messages (lag).", num_skipped);
+            self.event_queue.record_lag(num_skipped);
+          },
+          Err(broadcast::error::TryRecvError::Closed) => {
+            feed_closed = true;
+            break;
+          },
+        }
+      }
+      let dropped = self.event_queue.lag().dropped - dropped;
+      if dropped > 0 {
+        log::warn!("Trader {} queue full, dropped {} market events.", self.pair, dropped);
+      }
+      if feed_closed && self.event_queue.is_empty() {
+        log::warn!("Trader event feed closed.");
+        let positions = diagnostics::lock(Task::Portfolio, &self.portfolio)
+          .await
+          .open_positions(self.core_id)
+          .await;
+        match positions {
+          Ok(positions) => {
+            if positions.len() > 0 {
+              let last_update = positions.last().unwrap().meta.update_time;
+              self.event_queue.push(Event::SignalForceExit(SignalForceExit::from(
+                self.pair.clone(),
+                Some(last_update),
+              )));
+            } else {
+              break;
+            }
+          },
+          Err(e) => {
+            error!("{:?}", e)
+          },
+        }
+      } else if self.event_queue.is_empty() {
+        continue;
       }
-      while let Some(event) = self.event_queue.pop_front() {
+      while let Some(event) = self.event_queue.pop() {
+        diagnostics::record_queue_depth(
+          Task::Trader,
+          self.event_queue.len() + self.event_rx.len(),
+        );
+        let _timer = diagnostics::time_iteration(Task::Trader);
+        self.record(&event);
         match event {
           Event::Market(market_event) => {
-            if market_event.pair == self.pair {
-              match self.strategy.generate_signal(&market_event).await {
+            let is_own_pair = market_event.pair == self.pair;
+            let closed_candle =
+              matches!(market_event.detail, MarketEventDetail::Candle(_));
+            if is_own_pair {
+              self.clock.advance(market_event.time);
+              self.candle_closed_at = match &market_event.detail {
+                MarketEventDetail::Candle(candle) if self.trading_is_live => {
+                  Some(candle.close_time)
+                },
+                _ => None,
+              };
+              if let MarketEventDetail::Candle(candle)
+              | MarketEventDetail::BacktestCandle((candle, _)) = &market_event.detail
+              {
+                self.execution.on_candle(self.pair, candle);
+              }
+              if let MarketEventDetail::Candle(candle) = &market_event.detail {
+                if let Some(params) = self.pending_params.take() {
+                  self.apply_params(params).await;
+                }
+                self.update_regime(candle).await;
+              }
+              if let MarketEventDetail::Candle(candle)
+              | MarketEventDetail::CandleUpdate(candle) = &market_event.detail
+              {
+                if !self.window_loaded {
+                  self.load_window(candle.open_time).await;
+                }
+              }
+              self.candle_window.update(&market_event.detail);
+              let signal = if self.warming_up(&market_event.detail) {
+                Ok(None)
+              } else {
+                self.strategy.generate_signal(&market_event, &self.candle_window).await
+              };
+              match signal {
                 Ok(Some(signal)) => {
+                  self.record_stage(Stage::Signal);
+                  if let Err(e) = diagnostics::lock(Task::Database, &self.database)
+                    .await
+                    .add_model_votes(self.core_id, &signal)
+                    .await
+                  {
+                    warn!("Failed to record model votes: {}", e);
+                  }
                   self.event_transmitter.send(Event::Signal(signal.clone()));
-                  self.event_queue.push_back(Event::Signal(signal));
+                  self.event_queue.push(Event::Signal(signal));
+                },
+                Ok(None) => {
+                  if let Some(signal) = &self.deferred_signal {
+                    self.event_queue.push(Event::Signal(signal.clone()));
+                  }
                 },
-                Ok(None) => { /* No signal = do nothing*/ },
                 Err(e) => {
                   error!("Exiting on strategy error. {}", e);
                   return Err(TraderError::from(e));
                 },
               }
             }
-            if let Some(position_update) = self
-              .portfolio
-              .lock()
-              .await
-              .update_from_market(self.core_id, market_event)
-              .await?
+            if let Some(position_update) =
+              diagnostics::lock(Task::Portfolio, &self.portfolio)
+                .await
+                .update_from_market(self.core_id, market_event)
+                .await?
             {
               self.event_transmitter.send(Event::PositionUpdate(position_update));
             }
+            if !is_own_pair {
+              continue;
+            }
+            if let Some(stop) = &self.native_stop {
+              // Polled on closed candles, the stop protects the position in between
+              if closed_candle {
+                match self.execution.stop_fill(stop) {
+                  Ok(Some(fill)) => {
+                    info!("Trailing stop of {} filled on the exchange.", self.pair);
+                    self.event_queue.push(Event::Fill(fill));
+                  },
+                  Ok(None) => {},
+                  Err(e) => warn!("Failed to check the stop of {}: {}", self.pair, e),
+                }
+              }
+            } else if self.trailing_stop_hit().await {
+              info!("Trailing stop of {} hit, exiting.", self.pair);
+              let exit = SignalForceExit::from(self.pair, Some(self.clock.now()));
+              self.event_queue.push(Event::SignalForceExit(exit));
+            } else if !self.trading_is_live && self.liquidated().await {
+              // Live positions are liquidated by the exchange
+              info!("Position of {} reached its liquidation price, exiting.", self.pair);
+              let exit = SignalForceExit::from(self.pair, Some(self.clock.now()));
+              self.event_queue.push(Event::SignalForceExit(exit));
+            } else if self.trading_is_live && closed_candle {
+              self.check_exchange_liquidation().await;
+            }
           },
           Event::Signal(signal) => {
-            match self
-              .portfolio
-              .lock()
+            if self.paused {
+              continue;
+            }
+            if self.sliced_order.is_some() {
+              debug!("Order of {} still being sent, skipping signal.", self.pair);
+              continue;
+            }
+            match diagnostics::lock(Task::Portfolio, &self.portfolio)
               .await
-              .generate_order(self.core_id, &signal, self.trading_is_live)
+              .generate_order(self.core_id, &signal)
               .await
             {
+              Ok(Some(order)) if self.entries_paused && order.decision.is_entry() => {
+                self.deferred_signal = None;
+                info!("Entries of {} paused, skipping order.", self.pair);
+              },
               Ok(order) => {
+                self.deferred_signal = None;
                 if let Some(order) = order {
+                  self.record_stage(Stage::Order);
                   self.event_transmitter.send(Event::Order(order.clone()));
-                  self.event_queue.push_back(Event::Order(order));
+                  self.event_queue.push(Event::Order(order));
+                }
+              },
+              Err(e @ PortfolioError::BalanceMissing(_)) => {
+                let error = format!("Deferring signal of {}: {}", self.pair, e);
+                warn!("{}", error);
+                if self.deferred_signal.replace(signal).is_none() {
+                  self.report_error(error);
                 }
               },
               Err(e) => warn!("{}", e),
             }
           },
           Event::SignalForceExit(signal_force_exit) => {
-            match self
-              .portfolio
-              .lock()
+            if let Some((cancel, _)) = &self.sliced_order {
+              // Exits once the sliced order ends with what it filled
+              cancel.cancel();
+              self.deferred_exit = Some(signal_force_exit);
+              continue;
+            }
+            match diagnostics::lock(Task::Portfolio, &self.portfolio)
               .await
-              .generate_exit_order(self.core_id, signal_force_exit, self.trading_is_live)
+              .generate_exit_order(self.core_id, signal_force_exit)
               .await
             {
               Ok(order) => {
                 if let Some(order) = order {
                   self.event_transmitter.send(Event::Order(order.clone()));
-                  self.event_queue.push_back(Event::Order(order));
+                  self.event_queue.push(Event::Order(order));
                 }
               },
               Err(e) => warn!("{}", e),
             }
           },
           Event::Order(order) => {
-            match self.execution.generate_fill(&order, self.trading_is_live).await {
-              Ok(fill) => {
-                self.event_transmitter.send(Event::Fill(fill.clone()));
-                self.event_queue.push_back(Event::Fill(fill));
-              },
-              Err(e) => {
-                log::error!("{:?}", e);
-              },
+            if order.decision.is_exit() {
+              match self.cancel_native_stop() {
+                Ok(Some(fill)) => {
+                  // The exchange exited the position first
+                  self.event_queue.push(Event::Fill(fill));
+                  continue;
+                },
+                Ok(None) => {},
+                Err(e) => warn!("Failed to cancel the stop of {}: {}", self.pair, e),
+              }
+            }
+            if let Err(e) = self.write_ahead(WalEntry::Order(order.clone())) {
+              // Without the entry a crash could lose the order
+              log::error!("Order for {} not sent, pausing trader: {}", self.pair, e);
+              self.paused = true;
+              continue;
+            }
+            if self.execution.slices(&order, self.trading_is_live) {
+              // Spaced out child orders would hold up commands and market events
+              let cancel = CancellationToken::new();
+              let task = self.execution.spawn_sliced(order, cancel.clone());
+              self.sliced_order = Some((cancel, task));
+              continue;
+            }
+            let result = self.execution.generate_fill(&order, self.trading_is_live).await;
+            if let Some(fill) = self.acknowledge_fill(result) {
+              self.event_queue.push(Event::Fill(fill));
             }
           },
-          Event::Fill(fill) => {
-            let fill_side_effect_events =
-              self.portfolio.lock().await.update_from_fill(self.core_id, &fill).await?;
-            self.event_transmitter.send_many(fill_side_effect_events);
-          },
+          Event::Fill(fill) => self.apply_fill(fill).await?,
           _ => {},
         }
       }
@@ -189,8 +427,389 @@ impl Trader {
     }
 
     info!("Trader {} shutting down.", self.pair);
+    if let Some(Err(e)) = self.wal.take().map(WriteAheadLog::close) {
+      warn!("Failed to remove the write-ahead log of {}: {}", self.pair, e);
+    }
     Ok(())
   }
+  /// Fills the window with the stored candles before the first live candle, so the
+  /// first signals do not wait for the stream. Without them the window fills from the
+  /// stream alone.
+  async fn load_window(&mut self, before: DateTime<Utc>) {
+    self.window_loaded = true;
+    let candles = diagnostics::lock(Task::Database, &self.database)
+      .await
+      .fetch_candles_before(self.pair, before, self.candle_window.capacity())
+      .await;
+    match candles {
+      Ok(candles) => {
+        info!("Loaded {} stored candles of {}.", candles.len(), self.pair);
+        for candle in candles {
+          self.candle_window.push(candle);
+        }
+      },
+      Err(e) => warn!("Failed to load stored candles of {}: {}", self.pair, e),
+    }
+  }
+
+  /// Signals of live candles are not evaluated until the window holds the history the
+  /// strategy needs. The countdown is reported whenever it changes.
+  fn warming_up(&mut self, detail: &MarketEventDetail) -> bool {
+    if !matches!(detail, MarketEventDetail::Candle(_) | MarketEventDetail::CandleUpdate(_))
+    {
+      return false;
+    }
+    let remaining =
+      self.strategy.required_history().saturating_sub(self.candle_window.len());
+    if self.warm_up_remaining != Some(remaining) {
+      if remaining > 0 {
+        info!("Trader {} warming up, {} candles to go.", self.pair, remaining);
+      }
+      self.warm_up_remaining = Some(remaining);
+      if let Some(message_tx) = &self.message_tx {
+        let message =
+          CoreMessage::WarmingUp { core_id: self.core_id, pair: self.pair, remaining };
+        let _ = message_tx.try_send(message);
+      }
+    }
+    remaining > 0
+  }
+
+  /// Logs and reports the fill of an order, or pauses the trader when the exchange
+  /// would reject the following orders the same way.
+  fn acknowledge_fill(
+    &mut self,
+    result: Result<FillEvent, TraderError>,
+  ) -> Option<FillEvent> {
+    match result {
+      Ok(fill) => {
+        self.record_stage(Stage::Ack);
+        self.candle_closed_at = None;
+        if let Err(e) = self.write_ahead(WalEntry::Fill(fill.clone())) {
+          log::error!("Failed to log the fill of {}: {}", self.pair, e);
+        }
+        self.event_transmitter.send(Event::Fill(fill.clone()));
+        Some(fill)
+      },
+      Err(TraderError::ExchangeError(ExchangeError::Api(
+        e @ (ApiError::InsufficientBalance(_)
+        | ApiError::InvalidSymbol(_)
+        | ApiError::Unauthorized(_)),
+      ))) => {
+        log::error!("Order for {} failed, pausing trader: {}", self.pair, e);
+        let _ = self.write_ahead(WalEntry::Rejected);
+        self.paused = true;
+        None
+      },
+      Err(e) => {
+        log::error!("{:?}", e);
+        None
+      },
+    }
+  }
+
+  /// Waits for the task of the sliced order and acknowledges its fill.
+  async fn collect_sliced_order(&mut self) -> Option<FillEvent> {
+    let (_, task) = self.sliced_order.take()?;
+    match task.await {
+      Ok(result) => self.acknowledge_fill(result),
+      Err(e) => {
+        log::error!("Sliced order for {} failed: {}", self.pair, e);
+        None
+      },
+    }
+  }
+
+  async fn apply_fill(&mut self, fill: FillEvent) -> Result<(), TraderError> {
+    let fill_side_effect_events = diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .update_from_fill(self.core_id, &fill)
+      .await?;
+    let equity = fill_side_effect_events.iter().find_map(|event| match event {
+      Event::Balance(balance) => Some(*balance),
+      _ => None,
+    });
+    if let Some(balance) = equity {
+      if let Err(e) = self.write_ahead(WalEntry::Applied { balance }) {
+        log::error!("Failed to log the applied fill of {}: {}", self.pair, e);
+      }
+    }
+    self.event_transmitter.send_many(fill_side_effect_events);
+    if fill.decision.is_entry() {
+      self.entry_commission += fill.base_commission;
+      self.refresh_liquidation_price().await;
+    } else {
+      // Cancelled before the exit order, or the exit itself
+      self.native_stop = None;
+    }
+    self.place_native_stop().await;
+    Ok(())
+  }
+
+  /// Records the time since the close of the candle acted on, in live runs.
+  fn record_stage(&self, stage: Stage) {
+    if let Some(closed_at) = self.candle_closed_at {
+      let since_close = (Utc::now() - closed_at).to_std().unwrap_or_default();
+      diagnostics::record_stage(stage, since_close);
+      debug!("{} of {} {:?} after the candle close", stage, self.pair, since_close);
+    }
+  }
+
+  fn report_error(&self, error: String) {
+    if let Some(message_tx) = &self.message_tx {
+      let message = CoreMessage::SystemError { core_id: self.core_id, error };
+      let _ = message_tx.try_send(message);
+    }
+  }
+
+  /// Syncs the entry to the write-ahead log of live runs.
+  fn write_ahead(&mut self, entry: WalEntry) -> std::io::Result<()> {
+    match &mut self.wal {
+      Some(wal) => wal.append(&entry),
+      None => Ok(()),
+    }
+  }
+
+  /// Other pairs' market events are not recorded, they are never acted on.
+  fn record(&mut self, event: &Event) {
+    let Some(recorder) = &mut self.recorder else {
+      return;
+    };
+    if matches!(event, Event::Market(market_event) if market_event.pair != self.pair) {
+      return;
+    }
+    if let Err(e) = recorder.record(event) {
+      warn!("Failed to record event, recording stopped: {}", e);
+      self.recorder = None;
+    }
+  }
+
+  /// Commands are handled before queued events, so the swap always lands between candles
+  /// and open positions are kept as they are.
+  async fn swap_model(&mut self, model_id: ModelId) {
+    if model_id.pair != self.pair {
+      warn!("Model {} is not for {}, not swapping.", model_id, self.pair);
+      return;
+    }
+    let previous = self.strategy.swap_model(&model_id);
+    let entry = format!("Swapped model {} for {}", previous.join(", "), model_id.name);
+    info!("{}", entry);
+    if let Err(e) = diagnostics::lock(Task::Database, &self.database)
+      .await
+      .add_journal_entry(self.core_id, Utc::now(), &entry)
+      .await
+    {
+      warn!("Failed to record model swap: {}", e);
+    }
+  }
+  /// Applied between candles like a model swap, open positions keep their size and
+  /// the trailing stop applies to them from now on.
+  async fn apply_params(&mut self, params: RuntimeParams) {
+    self.strategy.set_min_confidence(params.min_confidence);
+    diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .set_risk_per_trade(params.risk_per_trade);
+    self.params = params;
+    self.replace_native_stop();
+    let entry = format!("Updated parameters of {}: {}", self.pair, params);
+    info!("{}", entry);
+    let mut database = diagnostics::lock(Task::Database, &self.database).await;
+    database.set_runtime_params(self.core_id, params);
+    if let Err(e) = database.add_journal_entry(self.core_id, Utc::now(), &entry).await {
+      warn!("Failed to record parameter update: {}", e);
+    }
+  }
+
+  /// Sizes the following entries for the volatility regime of the candle, when it
+  /// changed.
+  async fn update_regime(&mut self, candle: &Candle) {
+    let Some(classifier) = self.regime.as_mut() else {
+      return;
+    };
+    let Some(regime) = classifier.update(candle) else {
+      return;
+    };
+    let limits = classifier.config().limits(regime);
+    diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .set_regime_limits(self.core_id, self.pair, limits);
+    let entry = format!(
+      "Volatility of {} is {}, entries sized x{:.2} up to {:.0}% of the balance",
+      self.pair,
+      regime,
+      limits.size,
+      limits.max_entry_share * 100.0
+    );
+    info!("{}", entry);
+    let mut database = diagnostics::lock(Task::Database, &self.database).await;
+    database.set_volatility_regime(self.core_id, self.pair, regime);
+    if let Err(e) = database.add_journal_entry(self.core_id, Utc::now(), &entry).await {
+      warn!("Failed to record volatility regime: {}", e);
+    }
+  }
+
+  /// Keeps a trailing stop on the exchange for what is open of the position after a
+  /// fill, when live sessions do. Without it the trader checks the stop itself.
+  async fn place_native_stop(&mut self) {
+    let trailing_stop = self.params.trailing_stop;
+    if !self.trading_is_live
+      || self.trailing_stops != TrailingStops::Exchange
+      || trailing_stop <= 0.0
+    {
+      return;
+    }
+    let Some(position) = self.open_position().await else {
+      self.entry_commission = 0.0;
+      return;
+    };
+    // The stop of the earlier entries covers less than is open now
+    match self.cancel_native_stop() {
+      Ok(None) => {},
+      Ok(Some(fill)) => {
+        self.event_queue.push(Event::Fill(fill));
+        return;
+      },
+      Err(e) => {
+        warn!("Failed to cancel the stop of {}: {}", self.pair, e);
+        return;
+      },
+    }
+    let quantity = position.open_quantity() - self.entry_commission;
+    match self.execution.place_trailing_stop(&position, quantity, trailing_stop) {
+      Ok(stop) => self.native_stop = Some(stop),
+      Err(e) => {
+        let error = format!("Trailing stop of {} kept by the trader: {}", self.pair, e);
+        warn!("{}", error);
+        self.report_error(error);
+      },
+    }
+  }
+
+  /// Cancels the stop on the exchange, returns its fill when it was filled already. The
+  /// stop is kept when cancelling failed.
+  fn cancel_native_stop(&mut self) -> Result<Option<FillEvent>, TraderError> {
+    let Some(stop) = &self.native_stop else {
+      return Ok(None);
+    };
+    let fill = self.execution.cancel_trailing_stop(stop)?;
+    self.native_stop = None;
+    Ok(fill)
+  }
+
+  /// Moves the stop on the exchange to the updated trailing stop, 0 cancels it.
+  fn replace_native_stop(&mut self) {
+    let trailing_stop = self.params.trailing_stop;
+    let Some(stop) = self.native_stop.clone() else {
+      return;
+    };
+    if stop.trailing_stop == trailing_stop {
+      return;
+    }
+    match self.cancel_native_stop() {
+      Ok(Some(fill)) => {
+        self.event_queue.push(Event::Fill(fill));
+      },
+      Ok(None) if trailing_stop > 0.0 => {
+        match self.execution.renew_trailing_stop(&stop, trailing_stop) {
+          Ok(stop) => self.native_stop = Some(stop),
+          Err(e) => {
+            let error =
+              format!("Trailing stop of {} kept by the trader: {}", self.pair, e);
+            warn!("{}", error);
+            self.report_error(error);
+          },
+        }
+      },
+      Ok(None) => {},
+      Err(e) => warn!("Failed to replace the stop of {}: {}", self.pair, e),
+    }
+  }
+
+  async fn trailing_stop_hit(&self) -> bool {
+    if self.params.trailing_stop <= 0.0 {
+      return false;
+    }
+    let hit = diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .trailing_stop_hit(self.core_id, &self.pair, self.params.trailing_stop)
+      .await;
+    hit.unwrap_or_else(|e| {
+      warn!("Failed to check the trailing stop of {}: {}", self.pair, e);
+      false
+    })
+  }
+
+  async fn liquidated(&self) -> bool {
+    let liquidated = diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .liquidated(self.core_id, &self.pair)
+      .await;
+    liquidated.unwrap_or_else(|e| {
+      warn!("Failed to check the liquidation price of {}: {}", self.pair, e);
+      false
+    })
+  }
+
+  /// Exits the open futures position of the pair once the exchange no longer holds it,
+  /// after liquidating it. Polled on closed candles, fills are handled before them.
+  async fn check_exchange_liquidation(&mut self) {
+    if !self.execution.trades_futures() {
+      return;
+    }
+    let Some(position) = self.open_position().await else {
+      return;
+    };
+    match self.execution.futures_position(self.pair) {
+      Ok(None) => {
+        let error = format!("Position of {} was liquidated by the exchange.", self.pair);
+        warn!("{}", error);
+        self.report_error(error);
+        match self.execution.liquidation_fill(&position) {
+          Ok(fill) => {
+            self.event_queue.push(Event::Fill(fill));
+          },
+          Err(e) => warn!("Failed to exit the liquidated {}: {}", self.pair, e),
+        }
+      },
+      Ok(Some(_)) => {},
+      Err(e) => warn!("Failed to check the position of {}: {}", self.pair, e),
+    }
+  }
+
+  /// Replaces the estimated liquidation price of a live futures position with the one
+  /// of the exchange, which accounts for its margin tiers and the cross margin.
+  async fn open_position(&self) -> Option<Position> {
+    let positions = diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .open_positions(self.core_id)
+      .await;
+    positions.unwrap_or_default().into_iter().find(|position| position.asset == self.pair)
+  }
+
+  async fn refresh_liquidation_price(&self) {
+    if !self.trading_is_live {
+      return;
+    }
+    let price = match self.execution.futures_position(self.pair) {
+      Ok(Some(position)) => match position.liquidation_price {
+        Some(price) => price,
+        None => return,
+      },
+      Ok(None) => return,
+      Err(e) => {
+        warn!("Failed to fetch the liquidation price of {}: {}", self.pair, e);
+        return;
+      },
+    };
+    let result = diagnostics::lock(Task::Portfolio, &self.portfolio)
+      .await
+      .set_liquidation_price(self.core_id, &self.pair, price)
+      .await;
+    if let Err(e) = result {
+      warn!("Failed to set the liquidation price of {}: {}", self.pair, e);
+    }
+  }
+
   fn receive_remote_command(&mut self) -> Option<Command> {
     match self.command_reciever.try_recv() {
       Ok(command) => {
@@ -221,28 +840,44 @@ pub struct TraderBuilder {
   pair: Option<Pair>,
   market_feed: Option<MarketFeed>,
   command_reciever: Option<mpsc::Receiver<Command>>,
+  message_tx: Option<mpsc::Sender<CoreMessage>>,
   event_transmitter: Option<EventTx>,
   event_rx: Option<broadcast::Receiver<Event>>,
-  event_queue: Option<VecDeque<Event>>,
+  event_queue: Option<EventQueue>,
   portfolio: Option<Arc<Mutex<Portfolio>>>,
+  database: Option<Arc<Mutex<Database>>>,
   strategy: Option<Strategy>,
+  candle_window: Option<CandleWindow>,
   execution: Option<Execution>,
   trading_is_live: Option<bool>,
+  recorder: Option<EventRecorder>,
+  wal: Option<WriteAheadLog>,
+  clock: Option<SharedClock>,
+  trailing_stops: TrailingStops,
+  regime: RegimeConfig,
 }
 impl TraderBuilder {
   pub fn new() -> TraderBuilder {
     TraderBuilder {
       core_id: None,
       command_reciever: None,
+      message_tx: None,
       pair: None,
       trading_is_live: None,
       event_transmitter: None,
       event_rx: None,
       portfolio: None,
+      database: None,
       market_feed: None,
       event_queue: None,
       execution: None,
       strategy: None,
+      candle_window: None,
+      recorder: None,
+      wal: None,
+      clock: None,
+      trailing_stops: TrailingStops::default(),
+      regime: RegimeConfig::default(),
     }
   }
   pub fn core_id(self, value: Uuid) -> Self {
@@ -257,6 +892,11 @@ impl TraderBuilder {
     Self { command_reciever: Some(value), ..self }
   }
 
+  /// Reports the warm-up to the app, optional.
+  pub fn message_tx(self, value: mpsc::Sender<CoreMessage>) -> Self {
+    Self { message_tx: Some(value), ..self }
+  }
+
   pub fn event_transmitter(self, value: EventTx) -> Self {
     Self { event_transmitter: Some(value), ..self }
   }
@@ -265,6 +905,10 @@ impl TraderBuilder {
     Self { portfolio: Some(value), ..self }
   }
 
+  pub fn database(self, value: Arc<Mutex<Database>>) -> Self {
+    Self { database: Some(value), ..self }
+  }
+
   pub fn market_feed(self, value: MarketFeed) -> Self {
     Self { market_feed: Some(value), ..self }
   }
@@ -273,6 +917,12 @@ impl TraderBuilder {
     Self { strategy: Some(value), ..self }
   }
 
+  /// Candles kept for the strategy, defaults to
+  /// [crate::assets::candle_window::DEFAULT_CAPACITY].
+  pub fn candle_window(self, value: CandleWindow) -> Self {
+    Self { candle_window: Some(value), ..self }
+  }
+
   pub fn execution(self, value: Execution) -> Self {
     Self { execution: Some(value), ..self }
   }
@@ -285,24 +935,288 @@ impl TraderBuilder {
     Self { event_rx: Some(value), ..self }
   }
 
+  /// Records every handled event, optional.
+  pub fn recorder(self, value: Option<EventRecorder>) -> Self {
+    Self { recorder: value, ..self }
+  }
+
+  pub fn wal(self, value: Option<WriteAheadLog>) -> Self {
+    Self { wal: value, ..self }
+  }
+
+  /// Bounds the events waiting for the trader, defaults to [EventQueueConfig::default].
+  pub fn event_queue(self, value: EventQueue) -> Self {
+    Self { event_queue: Some(value), ..self }
+  }
+
+  /// Where the trailing stop is kept in live runs, defaults to the trader.
+  pub fn trailing_stops(self, value: TrailingStops) -> Self {
+    Self { trailing_stops: value, ..self }
+  }
+
+  /// Classifies the volatility of the pair to size entries, off by default.
+  pub fn volatility_regime(self, value: RegimeConfig) -> Self {
+    Self { regime: value, ..self }
+  }
+
+  /// Advanced with the time of the pair's market events, defaults to the wall clock.
+  pub fn clock(self, value: SharedClock) -> Self {
+    Self { clock: Some(value), ..self }
+  }
+
   pub fn build(self) -> Result<Trader, TraderError> {
+    let strategy = self.strategy.ok_or(TraderError::BuilderIncomplete("strategy"))?;
+    let params =
+      RuntimeParams { min_confidence: strategy.min_confidence(), ..Default::default() };
     Ok(Trader {
       core_id: self.core_id.ok_or(TraderError::BuilderIncomplete("engine_id"))?,
       pair: self.pair.ok_or(TraderError::BuilderIncomplete("pair"))?,
       command_reciever: self
         .command_reciever
         .ok_or(TraderError::BuilderIncomplete("command_rx"))?,
+      message_tx: self.message_tx,
       event_transmitter: self
         .event_transmitter
         .ok_or(TraderError::BuilderIncomplete("event_tx"))?,
       event_rx: self.event_rx.ok_or(TraderError::BuilderIncomplete("event_rx"))?,
-      event_queue: VecDeque::with_capacity(20),
+      event_queue: self.event_queue.unwrap_or_default(),
       portfolio: self.portfolio.ok_or(TraderError::BuilderIncomplete("portfolio"))?,
-      strategy: self.strategy.ok_or(TraderError::BuilderIncomplete("strategy"))?,
+      database: self.database.ok_or(TraderError::BuilderIncomplete("database"))?,
+      strategy,
+      candle_window: self.candle_window.unwrap_or_default(),
+      window_loaded: false,
+      warm_up_remaining: None,
       execution: self.execution.ok_or(TraderError::BuilderIncomplete("execution"))?,
       trading_is_live: self
         .trading_is_live
         .ok_or(TraderError::BuilderIncomplete("trading_is_live"))?,
+      paused: false,
+      entries_paused: false,
+      recorder: self.recorder,
+      wal: self.wal,
+      clock: self.clock.unwrap_or_else(real_clock),
+      params,
+      pending_params: None,
+      deferred_signal: None,
+      trailing_stops: self.trailing_stops,
+      native_stop: None,
+      entry_commission: 0.0,
+      sliced_order: None,
+      deferred_exit: None,
+      candle_closed_at: None,
+      regime: self.regime.enabled.then(|| RegimeClassifier::new(self.regime)),
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    app::STATISTIC_CONFIG,
+    assets::{Candle, MarketEvent, MarketMeta, Side},
+    clock::SimulatedClock,
+    portfolio::{allocator::Allocator, balance::Balance, risk::RiskEvaluator},
+    statistic::TradingSummary,
+    strategy::{
+      ensemble::{Ensemble, ModelVote},
+      Decision, SignalEvaluation, SignalStrength,
+    },
+  };
+  use std::collections::HashMap;
+  use tokio::time::timeout;
+
+  const TIMEOUT: Duration = Duration::from_secs(5);
+
+  fn market(minute: i64, signal: Option<Signal>) -> Event {
+    let time = DateTime::UNIX_EPOCH + chrono::Duration::try_minutes(minute).unwrap();
+    let candle = Candle {
+      open_time: time,
+      close_time: time,
+      open: 100.0,
+      high: 100.0,
+      low: 100.0,
+      close: 100.0,
+      volume: 1.0,
+      trade_count: 1,
+    };
+    Event::Market(MarketEvent {
+      time,
+      pair: Pair::BTCUSDT,
+      detail: MarketEventDetail::BacktestCandle((candle, signal)),
+      degraded: false,
+    })
+  }
+
+  fn long_signal() -> Signal {
+    Signal {
+      time: DateTime::UNIX_EPOCH,
+      pair: Pair::BTCUSDT,
+      market_meta: MarketMeta { close: 100.0, time: DateTime::UNIX_EPOCH },
+      signals: HashMap::from([(Decision::Long, SignalStrength(1.0))]),
+      votes: vec![],
+    }
+  }
+
+  /// The core initializes the balance after the traders may have started, a signal in
+  /// between is deferred until the balance is there instead of panicking the trader.
+  // The trader loop does not yield while its queue is empty
+  #[tokio::test(flavor = "multi_thread")]
+  async fn signal_before_balance_init_is_deferred() {
+    let core_id = Uuid::new_v4();
+    let clock: SharedClock = Arc::new(SimulatedClock::default());
+    let database = Arc::new(Mutex::new(Database::test().await));
+    let portfolio = Portfolio::builder()
+      .database(database.clone())
+      .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 0.1 })
+      .risk_manager(RiskEvaluator {})
+      .statistic_config(STATISTIC_CONFIG)
+      .clock(clock.clone())
+      .build()
+      .await
+      .unwrap();
+    let (command_tx, command_rx) = mpsc::channel(1);
+    let (message_tx, mut message_rx) = mpsc::channel(10);
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    let (market_tx, market_rx) = broadcast::channel(10);
+    let mut trader = Trader::builder()
+      .core_id(core_id)
+      .pair(Pair::BTCUSDT)
+      .command_reciever(command_rx)
+      .message_tx(message_tx)
+      .event_transmitter(EventTx::new(event_tx))
+      .event_rx(market_rx)
+      .portfolio(Arc::new(Mutex::new(portfolio)))
+      .database(database.clone())
+      .strategy(Strategy::new(
+        Pair::BTCUSDT,
+        Ensemble::default(),
+        SignalEvaluation::default(),
+      ))
+      .execution(Execution::stub(0.0, clock.clone()))
+      .trading_is_live(false)
+      .clock(clock)
+      .build()
+      .unwrap();
+    let trader = tokio::spawn(async move { trader.run().await });
+
+    market_tx.send(market(0, Some(long_signal()))).unwrap();
+    let message = timeout(TIMEOUT, message_rx.recv()).await.unwrap();
+    let Some(CoreMessage::SystemError { core_id: id, .. }) = message else {
+      panic!("Expected a system error, got {:?}", message);
+    };
+    assert_eq!(id, core_id);
+
+    let balance =
+      Balance { time: DateTime::UNIX_EPOCH, total: 1000.0, available: 1000.0 };
+    let currency = STATISTIC_CONFIG.reporting_currency;
+    database.lock().await.init_balance(core_id, balance, currency).unwrap();
+    market_tx.send(market(1, None)).unwrap();
+    let order = timeout(TIMEOUT, async {
+      while let Some(event) = event_rx.recv().await {
+        if let Event::Order(order) = event {
+          return order;
+        }
+      }
+      panic!("Trader stopped without an order.");
+    })
+    .await
+    .unwrap();
+    assert_eq!(order.decision, Decision::Long);
+
+    command_tx.send(Command::Terminate("Test finished".to_string())).await.unwrap();
+    assert!(timeout(TIMEOUT, trader).await.unwrap().unwrap().is_ok());
+  }
+
+  /// Signal of a single model's output, combined by an ensemble of a futures session.
+  fn futures_signal(output: &str) -> Signal {
+    let vote = ModelVote {
+      model_name: "model".to_string(),
+      weight: 1.0,
+      output: output.to_string(),
+      confidence: 1.0,
+    };
+    Signal {
+      signals: Ensemble::default().with_shorts(true).combine(&[vote]),
+      ..long_signal()
+    }
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn futures_sessions_enter_and_exit_shorts() {
+    let core_id = Uuid::new_v4();
+    let clock: SharedClock = Arc::new(SimulatedClock::default());
+    let database = Arc::new(Mutex::new(Database::test().await));
+    {
+      let mut database = database.lock().await;
+      let time = DateTime::UNIX_EPOCH;
+      let balance = Balance { time, total: 1000.0, available: 1000.0 };
+      let currency = STATISTIC_CONFIG.reporting_currency;
+      database.init_balance(core_id, balance, currency).unwrap();
+      let summary = TradingSummary::init(STATISTIC_CONFIG, Some(time));
+      database.init_statistics(core_id, [Pair::BTCUSDT].into_iter(), summary);
+    }
+    let portfolio = Portfolio::builder()
+      .database(database.clone())
+      .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 0.1 })
+      .risk_manager(RiskEvaluator {})
+      .statistic_config(STATISTIC_CONFIG)
+      .clock(clock.clone())
+      .build()
+      .await
+      .unwrap();
+    let (command_tx, command_rx) = mpsc::channel(1);
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    let (market_tx, market_rx) = broadcast::channel(10);
+    let mut trader = Trader::builder()
+      .core_id(core_id)
+      .pair(Pair::BTCUSDT)
+      .command_reciever(command_rx)
+      .event_transmitter(EventTx::new(event_tx))
+      .event_rx(market_rx)
+      .portfolio(Arc::new(Mutex::new(portfolio)))
+      .database(database.clone())
+      .strategy(Strategy::new(
+        Pair::BTCUSDT,
+        Ensemble::default(),
+        SignalEvaluation::default(),
+      ))
+      .execution(Execution::stub(0.0, clock.clone()))
+      .trading_is_live(false)
+      .clock(clock)
+      .build()
+      .unwrap();
+    let trader = tokio::spawn(async move { trader.run().await });
+
+    market_tx.send(market(0, Some(futures_signal("sell")))).unwrap();
+    let entered = timeout(TIMEOUT, async {
+      while let Some(event) = event_rx.recv().await {
+        if let Event::PositionNew(position) = event {
+          return position;
+        }
+      }
+      panic!("Trader stopped without a position.");
+    })
+    .await
+    .unwrap();
+    assert_eq!(entered.side, Side::Sell);
+    assert!(entered.quantity < 0.0);
+
+    market_tx.send(market(1, Some(futures_signal("buy")))).unwrap();
+    timeout(TIMEOUT, async {
+      while let Some(event) = event_rx.recv().await {
+        if matches!(event, Event::PositionExit(_)) {
+          return;
+        }
+      }
+      panic!("Trader stopped without an exit.");
+    })
+    .await
+    .unwrap();
+    let open = database.lock().await.get_all_open_positions(core_id).unwrap();
+    assert!(open.is_empty());
+
+    command_tx.send(Command::Terminate("Test finished".to_string())).await.unwrap();
+    assert!(timeout(TIMEOUT, trader).await.unwrap().unwrap().is_ok());
+  }
+}
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use strum::{Display, EnumIter, EnumString};
+
+/// Where the trailing stop of live sessions is kept, backtests always check it in the
+/// trader.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingStops {
+  /// Checked by the trader on every market event of the pair.
+  #[default]
+  Client,
+  /// A `trailingDelta` stop order on Binance, which keeps protecting the position while
+  /// meshetar is disconnected.
+  Exchange,
+}
+
+/// Market a session trades the pair on.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Market {
+  #[default]
+  Spot,
+  /// USD-M perpetual futures, with the leverage and margin of the session.
+  Futures,
+}
+
+/// Type of the orders a session sends on entries and signalled exits, forced exits are
+/// always market orders.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  PartialOrd,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum OrderType {
+  #[default]
+  Market,
+  /// At the close of the candle that signalled it.
+  Limit,
+}
+
+/// How long a limit order stays on the book. Market orders take none, which is `Gtc`.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Eq,
+  PartialOrd,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TimeInForce {
+  /// Good till cancelled.
+  #[default]
+  Gtc,
+  /// Immediate or cancel, what does not fill at once expires.
+  Ioc,
+  /// Fill or kill, expires unless it fills in full at once.
+  Fok,
+}
+
+/// Options of the orders of a session, picked in the Run config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct OrderOptions {
+  pub order_type: OrderType,
+  pub time_in_force: TimeInForce,
+  /// Limit orders are sent as `LIMIT_MAKER`, which Binance rejects when they would take
+  /// liquidity.
+  pub post_only: bool,
+}
+
+impl OrderOptions {
+  /// Why Binance would reject orders with the options, none when it would not.
+  pub fn invalid(&self) -> Option<&'static str> {
+    match (self.order_type, self.time_in_force, self.post_only) {
+      (OrderType::Market, _, true) => Some("post-only needs a limit order"),
+      (OrderType::Market, TimeInForce::Ioc | TimeInForce::Fok, _) => {
+        Some("market orders take no time in force")
+      },
+      (OrderType::Limit, TimeInForce::Ioc | TimeInForce::Fok, true) => {
+        Some("post-only orders stay on the book until cancelled")
+      },
+      _ => None,
+    }
+  }
+}
+
+/// Parameters of a running session that can be changed without restarting it. Traders
+/// apply an update at the next closed candle of their pair.
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct RuntimeParams {
+  /// Share of the available balance an entry at full signal strength commits.
+  pub risk_per_trade: f64,
+  /// Signals of lower strength are not acted on.
+  pub min_confidence: f64,
+  /// Retrace from the best price since entry that exits a position, 0 is off.
+  pub trailing_stop: f64,
+}
+
+impl Default for RuntimeParams {
+  fn default() -> Self {
+    Self { risk_per_trade: 1.0, min_confidence: 0.0, trailing_stop: 0.0 }
+  }
+}
+
+impl fmt::Display for RuntimeParams {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "risk per trade {:.2}, min confidence {:.2}, trailing stop ",
+      self.risk_per_trade, self.min_confidence
+    )?;
+    if self.trailing_stop > 0.0 {
+      write!(f, "{:.1}%", self.trailing_stop * 100.0)
+    } else {
+      write!(f, "off")
+    }
+  }
+}
@@ -0,0 +1,204 @@
+use super::{
+  error::TraderError, event_queue::EventQueue, execution::Execution, Trader,
+};
+use crate::{
+  app::statistic_config,
+  clock::{Clock, SharedClock, SimulatedClock},
+  core::record_conversion_rates,
+  database::Database,
+  events::{
+    recording::{read_recording, RecordedLine},
+    Event, EventTx,
+  },
+  portfolio::{allocator::Allocator, balance::Balance, risk::RiskEvaluator, Portfolio},
+  screens::run_config::CoreConfiguration,
+  statistic::{StatisticConfig, TradingSummary},
+  strategy::Strategy,
+};
+use std::{fmt, path::Path, sync::Arc};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use uuid::Uuid;
+
+/// Outcome of feeding a recording back through a trader.
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+  pub recorded_core_id: Uuid,
+  pub core_id: Uuid,
+  pub inputs: usize,
+  pub recorded: Vec<String>,
+  pub replayed: Vec<String>,
+}
+
+impl ReplayReport {
+  /// Index and both sides of the first decision the replay made differently.
+  pub fn first_divergence(&self) -> Option<(usize, Option<&str>, Option<&str>)> {
+    let len = self.recorded.len().max(self.replayed.len());
+    (0..len)
+      .map(|i| {
+        (
+          i,
+          self.recorded.get(i).map(String::as_str),
+          self.replayed.get(i).map(String::as_str),
+        )
+      })
+      .find(|(_, recorded, replayed)| recorded != replayed)
+  }
+}
+
+impl fmt::Display for ReplayReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Replayed session {} as {}", self.recorded_core_id, self.core_id)?;
+    writeln!(f, "Input events: {}", self.inputs)?;
+    writeln!(f, "Recorded decisions: {}", self.recorded.len())?;
+    writeln!(f, "Replayed decisions: {}", self.replayed.len())?;
+    match self.first_divergence() {
+      None => write!(f, "Replay matches the recording."),
+      Some((index, recorded, replayed)) => write!(
+        f,
+        "First divergence at decision {}:\n  recorded: {}\n  replayed: {}",
+        index,
+        recorded.unwrap_or("-"),
+        replayed.unwrap_or("-")
+      ),
+    }
+  }
+}
+
+/// Signals, orders and fills are compared by what was decided, not when the exchange
+/// filled them, as the replay fills at the market price of the order.
+fn describe(event: &Event) -> Option<String> {
+  match event {
+    Event::Signal(signal) => {
+      let mut decisions: Vec<String> = signal
+        .signals
+        .iter()
+        .map(|(decision, strength)| format!("{:?} {:.4}", decision, strength.0))
+        .collect();
+      decisions.sort();
+      Some(format!("Signal {} at {}: {}", signal.pair, signal.time, decisions.join(", ")))
+    },
+    Event::Order(order) => Some(format!(
+      "Order {:?} {} at {} qty {:.5}",
+      order.decision, order.pair, order.time, order.quantity
+    )),
+    Event::Fill(fill) => Some(format!("Fill {:?} {}", fill.decision, fill.asset)),
+    _ => None,
+  }
+}
+
+/// Feeds the market events and forced exits of a recording through a new trader with a
+/// stub execution, and compares its decisions with the recorded ones.
+pub async fn replay(path: &Path) -> Result<ReplayReport, TraderError> {
+  let mut lines = read_recording(path)
+    .map_err(|e| TraderError::Replay(format!("{}: {}", path.display(), e)))?
+    .into_iter();
+  let Some(RecordedLine::Session { core_id: recorded_core_id, configuration }) =
+    lines.next()
+  else {
+    return Err(TraderError::Replay("recording does not start with a session".into()));
+  };
+  let events: Vec<Event> = lines
+    .filter_map(|line| match line {
+      RecordedLine::Event { event, .. } => Some(event),
+      RecordedLine::Session { .. } => None,
+    })
+    .collect();
+  let (inputs, recorded): (Vec<Event>, Vec<Event>) = events
+    .into_iter()
+    .partition(|event| matches!(event, Event::Market(_) | Event::SignalForceExit(_)));
+
+  let core_id = Uuid::new_v4();
+  let database = Database::new().await.map_err(|e| TraderError::Replay(e.to_string()))?;
+  let database = Arc::new(Mutex::new(database));
+  let input_count = inputs.len();
+  let outputs = run_offline(core_id, &configuration, database, inputs).await?;
+  let replayed = outputs.iter().filter_map(describe).collect();
+  Ok(ReplayReport {
+    recorded_core_id,
+    core_id,
+    inputs: input_count,
+    recorded: recorded.iter().filter_map(describe).collect(),
+    replayed,
+  })
+}
+
+/// Runs the input events through a new trader with a stub execution, at the time of the
+/// first market event. Returns the events the trader sent.
+pub(super) async fn run_offline(
+  core_id: Uuid,
+  configuration: &CoreConfiguration,
+  database: Arc<Mutex<Database>>,
+  inputs: Vec<Event>,
+) -> Result<Vec<Event>, TraderError> {
+  // The time of the input events, not the time of the run
+  let clock: SharedClock = match inputs.iter().find_map(|event| match event {
+    Event::Market(market_event) => Some(market_event.time),
+    _ => None,
+  }) {
+    Some(started_at) => Arc::new(SimulatedClock::starting_at(started_at)),
+    None => Arc::new(SimulatedClock::default()),
+  };
+  {
+    let mut database = database.lock().await;
+    let starting_equity = configuration.starting_equity;
+    let started_at = clock.now();
+    let statistic_config =
+      StatisticConfig { starting_equity, created_at: started_at, ..statistic_config() };
+    let balance =
+      Balance { time: started_at, total: starting_equity, available: starting_equity };
+    database.init_balance(core_id, balance, statistic_config.reporting_currency)?;
+    database.init_statistics(
+      core_id,
+      [configuration.pair],
+      TradingSummary::init(statistic_config, Some(started_at)),
+    );
+  }
+  let reporting = statistic_config().reporting_currency;
+  let pairs = [configuration.pair];
+  record_conversion_rates(&database, core_id, &pairs, reporting, clock.now(), None).await;
+  let mut portfolio = Portfolio::builder()
+    .database(database.clone())
+    .allocation_manager(Allocator { default_order_value: 100.0, risk_per_trade: 1.0 })
+    .risk_manager(RiskEvaluator {})
+    .statistic_config(statistic_config())
+    .clock(clock.clone())
+    .build()
+    .await?;
+  portfolio.set_order_options(configuration.order_options);
+  portfolio.set_leverage(core_id, configuration.leverage());
+
+  // Everything is queued up front, the closed feed then ends the trader
+  let (event_broadcast, event_rx) = broadcast::channel(inputs.len().max(1));
+  let (command_tx, command_rx) = mpsc::channel(1);
+  let (event_tx, mut output_rx) = mpsc::unbounded_channel();
+  let mut trader = Trader::builder()
+    .core_id(core_id)
+    .pair(configuration.pair)
+    .trading_is_live(configuration.run_live)
+    .command_reciever(command_rx)
+    .event_transmitter(EventTx::new(event_tx))
+    .portfolio(Arc::new(Mutex::new(portfolio)))
+    .database(database)
+    .strategy(Strategy::new(
+      configuration.pair,
+      configuration.trading_ensemble(),
+      configuration.signal_evaluation,
+    ))
+    .execution(Execution::stub(configuration.exchange_fee, clock.clone()))
+    .event_rx(event_rx)
+    .event_queue(EventQueue::unbounded())
+    .clock(clock)
+    .build()?;
+  for event in inputs {
+    let _ = event_broadcast.send(event);
+  }
+  drop(event_broadcast);
+  trader.run().await?;
+  drop(command_tx);
+
+  let mut outputs = Vec::new();
+  while let Ok(event) = output_rx.try_recv() {
+    outputs.push(event);
+  }
+  Ok(outputs)
+}
@@ -0,0 +1,228 @@
+use super::{backtest::backtest_on, error::TraderError};
+use crate::{
+  action::Action,
+  assets::Pair,
+  core::alerts::{send_webhook, Alert},
+  database::{
+    add_scheduled_backtest, fetch_last_scheduled_sharpe, Database, SessionLabel,
+  },
+  screens::run_config::CoreConfiguration,
+  strategy::{
+    ensemble::{Ensemble, EnsembleMember, VotingMethod},
+    get_generated_models,
+  },
+};
+use chrono::{DateTime, Days, NaiveTime, Utc};
+use serde::Deserialize;
+use std::{fmt, sync::Arc};
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Backtests of every model on every pair, run once a day, from `scheduled_backtests` in
+/// the config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ScheduleConfig {
+  /// UTC time of day the suite runs at, e.g. `"02:00:00"`. None turns it off.
+  pub at: Option<NaiveTime>,
+  /// Names of generated models.
+  pub models: Vec<String>,
+  pub pairs: Vec<Pair>,
+  pub last_n_candles: usize,
+  pub starting_equity: f64,
+  pub exchange_fee: f64,
+  /// Alerts when the daily Sharpe ratio of a model on a pair is lower than in its
+  /// previous scheduled run by more than this.
+  pub max_sharpe_drop: f64,
+  /// Drops are also POSTed here, like the `webhook` action of alerts.
+  pub webhook: Option<String>,
+}
+
+impl Default for ScheduleConfig {
+  fn default() -> Self {
+    Self {
+      at: None,
+      models: Vec::new(),
+      pairs: vec![Pair::BTCUSDT],
+      last_n_candles: 1440,
+      starting_equity: 1000.0,
+      exchange_fee: 0.001,
+      max_sharpe_drop: 0.5,
+      webhook: None,
+    }
+  }
+}
+
+impl ScheduleConfig {
+  pub fn is_enabled(&self) -> bool {
+    self.at.is_some() && !self.models.is_empty() && !self.pairs.is_empty()
+  }
+}
+
+/// First time of day `at` after `now`.
+pub fn next_run(at: NaiveTime, now: DateTime<Utc>) -> DateTime<Utc> {
+  let today = now.date_naive().and_time(at).and_utc();
+  if today > now {
+    today
+  } else {
+    today.checked_add_days(Days::new(1)).unwrap_or(today)
+  }
+}
+
+/// Outcome of the backtest of one model on one pair, stored as a session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledResult {
+  pub core_id: Uuid,
+  pub model: String,
+  pub pair: Pair,
+  pub sharpe_ratio: f64,
+  /// Sharpe ratio of the previous scheduled run, none on the first one.
+  pub previous_sharpe_ratio: Option<f64>,
+}
+
+impl ScheduledResult {
+  /// Whether the Sharpe ratio fell by more than `max_drop` since the previous run.
+  pub fn has_dropped(&self, max_drop: f64) -> bool {
+    self
+      .previous_sharpe_ratio
+      .is_some_and(|previous| previous - self.sharpe_ratio > max_drop)
+  }
+}
+
+impl fmt::Display for ScheduledResult {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Scheduled backtest of {} on {}: Sharpe ratio {:.3}",
+      self.model, self.pair, self.sharpe_ratio
+    )?;
+    match self.previous_sharpe_ratio {
+      Some(previous) => write!(f, ", {:.3} in the previous run", previous),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Backtests every configured model on every pair and stores each run as a backtest
+/// session labelled with the model and pair. Models that are not generated are skipped.
+pub async fn run_suite(
+  config: &ScheduleConfig,
+  database: Arc<Mutex<Database>>,
+) -> Result<Vec<ScheduledResult>, TraderError> {
+  let generated =
+    get_generated_models().map_err(|e| TraderError::Schedule(e.to_string()))?;
+  let mut results = Vec::new();
+  for name in &config.models {
+    let Some(model) = generated.iter().find(|model| model.name() == name) else {
+      warn!("Skipping scheduled backtests of {}, no such generated model.", name);
+      continue;
+    };
+    let model = model.to_model_id();
+    for pair in &config.pairs {
+      let member = EnsembleMember::new(
+        model.name.clone(),
+        model.ensemble_weight,
+        model.features.clone(),
+        model.backend,
+      );
+      let label = SessionLabel {
+        label: format!("Scheduled {} {}", name, pair),
+        notes: format!("Backtest of the last {} candles", config.last_n_candles),
+      };
+      let configuration = CoreConfiguration {
+        starting_equity: config.starting_equity,
+        backtest_last_n_candles: config.last_n_candles,
+        exchange_fee: config.exchange_fee,
+        pair: *pair,
+        ensemble: Ensemble::new(vec![member], VotingMethod::default(), 0.0),
+        label: label.clone(),
+        ..CoreConfiguration::default()
+      };
+      let started_at = Utc::now();
+      let report = backtest_on(configuration, database.clone()).await?;
+      database.lock().await.add_session(report.core_id, started_at, true, &label).await?;
+      let sharpe_ratio = report.statistics.tear_sheet.sharpe_ratio.daily();
+      let previous_sharpe_ratio = fetch_last_scheduled_sharpe(name, *pair).await?;
+      add_scheduled_backtest(report.core_id, name, *pair, started_at, sharpe_ratio)
+        .await?;
+      results.push(ScheduledResult {
+        core_id: report.core_id,
+        model: name.clone(),
+        pair: *pair,
+        sharpe_ratio,
+        previous_sharpe_ratio,
+      });
+    }
+  }
+  Ok(results)
+}
+
+/// Runs the suite every day at the configured time, for as long as the app runs. Drops
+/// of the Sharpe ratio are shown as alerts.
+pub fn spawn_schedule(
+  config: ScheduleConfig,
+  database: Arc<Mutex<Database>>,
+  action_tx: UnboundedSender<Action>,
+) {
+  let Some(at) = config.at.filter(|_| config.is_enabled()) else {
+    return;
+  };
+  tokio::spawn(async move {
+    loop {
+      let now = Utc::now();
+      let wait = (next_run(at, now) - now).to_std().unwrap_or_default();
+      tokio::time::sleep(wait).await;
+      let results = match run_suite(&config, database.clone()).await {
+        Ok(results) => results,
+        Err(e) => {
+          error!("Error running scheduled backtests: {}", e);
+          continue;
+        },
+      };
+      for result in results {
+        info!("{}", result);
+        if !result.has_dropped(config.max_sharpe_drop) {
+          continue;
+        }
+        let alert = Alert {
+          core_id: Some(result.core_id),
+          time: Utc::now(),
+          message: format!("Sharpe ratio dropped. {}", result),
+        };
+        if let Some(url) = &config.webhook {
+          send_webhook(url.clone(), alert.clone());
+        }
+        let _ = action_tx.send(Action::Alert(alert));
+      }
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  #[test]
+  fn runs_daily_and_alerts_on_sharpe_drops() {
+    let at = NaiveTime::from_hms_opt(2, 0, 0).unwrap();
+    let night = Utc.with_ymd_and_hms(2024, 3, 1, 1, 30, 0).unwrap();
+    let morning = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+    assert_eq!(next_run(at, night), morning);
+    assert_eq!(next_run(at, morning), Utc.with_ymd_and_hms(2024, 3, 2, 2, 0, 0).unwrap());
+
+    let mut result = ScheduledResult {
+      core_id: Uuid::new_v4(),
+      model: "model".to_string(),
+      pair: Pair::BTCUSDT,
+      sharpe_ratio: 0.8,
+      previous_sharpe_ratio: None,
+    };
+    assert!(!result.has_dropped(0.5));
+    result.previous_sharpe_ratio = Some(1.2);
+    assert!(!result.has_dropped(0.5));
+    result.previous_sharpe_ratio = Some(1.5);
+    assert!(result.has_dropped(0.5));
+  }
+}
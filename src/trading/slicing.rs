@@ -0,0 +1,119 @@
+use serde::Deserialize;
+use std::time::Duration;
+use strum::Display;
+
+/// Child orders of one order at most, the last one takes what is left.
+pub const MAX_CHILD_ORDERS: usize = 50;
+/// Smallest quantity sent, orders are rounded to it.
+pub const QUANTITY_STEP: f64 = 0.00001;
+
+/// How live sessions split large orders, from `order_slicing` in the config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Display, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SlicingAlgorithm {
+  /// Every order is sent whole.
+  #[default]
+  Off,
+  /// Evenly over `slices` child orders, `interval_secs` apart.
+  Twap,
+  /// Child orders of `iceberg_share` of the order, each sent once the previous filled.
+  Iceberg,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SlicingConfig {
+  pub algorithm: SlicingAlgorithm,
+  /// Orders worth less than this in the quote asset of the pair are sent whole.
+  pub min_notional: f64,
+  pub slices: usize,
+  pub interval_secs: u64,
+  pub iceberg_share: f64,
+  /// Child orders take at most this share of the quantity at the best price of the book,
+  /// 0 ignores the book.
+  pub max_book_share: f64,
+}
+
+impl Default for SlicingConfig {
+  fn default() -> Self {
+    Self {
+      algorithm: SlicingAlgorithm::Off,
+      min_notional: 1000.0,
+      slices: 5,
+      interval_secs: 10,
+      iceberg_share: 0.2,
+      max_book_share: 0.5,
+    }
+  }
+}
+
+impl SlicingConfig {
+  /// Whether an order of the quantity at the price is split.
+  pub fn applies(&self, quantity: f64, price: f64) -> bool {
+    self.algorithm != SlicingAlgorithm::Off && quantity.abs() * price >= self.min_notional
+  }
+
+  /// Pause before the next child order.
+  pub fn delay(&self) -> Duration {
+    match self.algorithm {
+      SlicingAlgorithm::Twap => Duration::from_secs(self.interval_secs),
+      SlicingAlgorithm::Off | SlicingAlgorithm::Iceberg => Duration::ZERO,
+    }
+  }
+
+  /// Quantity of the next child order of an order of `total` with `remaining` left after
+  /// `sent` child orders. `book` is the quantity at the best price on the side the order
+  /// takes from, when known.
+  pub fn child_quantity(
+    &self,
+    total: f64,
+    remaining: f64,
+    sent: usize,
+    book: Option<f64>,
+  ) -> f64 {
+    let slices = self.slices.clamp(1, MAX_CHILD_ORDERS);
+    let last = match self.algorithm {
+      SlicingAlgorithm::Off => true,
+      SlicingAlgorithm::Twap => sent + 1 >= slices,
+      SlicingAlgorithm::Iceberg => sent + 1 >= MAX_CHILD_ORDERS,
+    };
+    if last {
+      return remaining;
+    }
+    let planned = match self.algorithm {
+      SlicingAlgorithm::Twap => remaining / (slices - sent) as f64,
+      _ => total * self.iceberg_share.clamp(0.0, 1.0),
+    };
+    let cap = book
+      .filter(|_| self.max_book_share > 0.0)
+      .map_or(f64::INFINITY, |book| book * self.max_book_share);
+    planned.min(cap).max(QUANTITY_STEP).min(remaining)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn plans_child_orders_within_the_book() {
+    let twap = SlicingConfig {
+      algorithm: SlicingAlgorithm::Twap,
+      slices: 4,
+      ..Default::default()
+    };
+    assert!(!twap.applies(0.01, 40_000.0));
+    assert!(twap.applies(0.1, 40_000.0));
+    assert_eq!(twap.child_quantity(1.0, 1.0, 0, None), 0.25);
+    // Half of the 0.3 at the best price, the rest spreads over the later slices
+    assert_eq!(twap.child_quantity(1.0, 1.0, 0, Some(0.3)), 0.15);
+    assert_eq!(twap.child_quantity(1.0, 0.85, 1, None), 0.85 / 3.0);
+    assert_eq!(twap.child_quantity(1.0, 0.4, 3, Some(0.1)), 0.4);
+
+    let iceberg = SlicingConfig { algorithm: SlicingAlgorithm::Iceberg, ..twap };
+    assert_eq!(iceberg.child_quantity(1.0, 1.0, 0, None), 0.2);
+    assert_eq!(iceberg.child_quantity(1.0, 0.1, 8, None), 0.1);
+    assert_eq!(iceberg.child_quantity(1.0, 0.5, MAX_CHILD_ORDERS - 1, None), 0.5);
+  }
+}
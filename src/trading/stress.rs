@@ -0,0 +1,260 @@
+use super::{backtest::backtest_candles, error::TraderError};
+use crate::{
+  assets::{Candle, Side},
+  database::{Database, SessionLabel},
+  portfolio::position::Position,
+  screens::run_config::CoreConfiguration,
+};
+use chrono::Utc;
+use prettytable::{row, Table};
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::PI, fmt, sync::Arc};
+use strum::{Display, EnumIter, EnumString};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Share of the price a flash crash takes, over its first candles.
+const FLASH_CRASH_DEPTH: f64 = 0.3;
+const FLASH_CRASH_CANDLES: usize = 5;
+/// Candles the price takes to recover from the flash crash.
+const FLASH_CRASH_RECOVERY: usize = 60;
+const GAP_DOWN: f64 = 0.2;
+/// Chop swings this share around the first price, once per period of candles.
+const CHOP_AMPLITUDE: f64 = 0.02;
+const CHOP_PERIOD: f64 = 120.0;
+
+/// Shock a stress test applies to the stored candles of the pair, from the middle of
+/// them on.
+#[derive(
+  Copy,
+  Clone,
+  Debug,
+  PartialEq,
+  Eq,
+  Display,
+  EnumIter,
+  EnumString,
+  Deserialize,
+  Serialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum Scenario {
+  /// The price falls 30% over 5 candles and recovers over the next hour.
+  FlashCrash,
+  /// The price opens 20% lower and stays there.
+  GapDown,
+  /// The trend is replaced by swings of 2% around the first price, for all candles.
+  Chop,
+}
+
+impl Scenario {
+  /// Factor the prices of the candle at the index are multiplied by.
+  fn factor(&self, index: usize, candles: &[Candle]) -> f64 {
+    let middle = candles.len() / 2;
+    match self {
+      Scenario::FlashCrash if index < middle => 1.0,
+      Scenario::FlashCrash => {
+        let since = index - middle;
+        if since < FLASH_CRASH_CANDLES {
+          1.0 - FLASH_CRASH_DEPTH * (since + 1) as f64 / FLASH_CRASH_CANDLES as f64
+        } else {
+          let recovered = (since + 1 - FLASH_CRASH_CANDLES) as f64;
+          let recovered = (recovered / FLASH_CRASH_RECOVERY as f64).min(1.0);
+          1.0 - FLASH_CRASH_DEPTH * (1.0 - recovered)
+        }
+      },
+      Scenario::GapDown if index < middle => 1.0,
+      Scenario::GapDown => 1.0 - GAP_DOWN,
+      Scenario::Chop => {
+        let swing = (2.0 * PI * index as f64 / CHOP_PERIOD).sin();
+        candles[0].open * (1.0 + CHOP_AMPLITUDE * swing) / candles[index].close
+      },
+    }
+  }
+
+  /// The candles with the shock applied.
+  pub fn apply(&self, candles: &[Candle]) -> Vec<Candle> {
+    candles
+      .iter()
+      .enumerate()
+      .map(|(index, candle)| {
+        let factor = self.factor(index, candles);
+        Candle {
+          open: candle.open * factor,
+          high: candle.high * factor,
+          low: candle.low * factor,
+          close: candle.close * factor,
+          ..*candle
+        }
+      })
+      .collect()
+  }
+}
+
+/// How the strategy came through a scenario.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioResult {
+  pub scenario: Scenario,
+  /// Backtest session of the scenario.
+  pub core_id: Uuid,
+  pub trades: usize,
+  pub pnl: f64,
+  /// Deepest drawdown of the equity, as a positive share.
+  pub max_drawdown: f64,
+  /// Positions exited at their liquidation price.
+  pub liquidations: usize,
+  /// Largest loss of an open position at the worst price while it was open, as a share
+  /// of its margin. At 1 the margin is gone.
+  pub peak_margin_used: f64,
+}
+
+/// Stress test of a strategy on one pair across scenarios.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StressReport {
+  pub configuration: CoreConfiguration,
+  pub candles: usize,
+  pub results: Vec<ScenarioResult>,
+}
+
+impl StressReport {
+  /// Scenario with the deepest drawdown.
+  pub fn worst_case(&self) -> Option<&ScenarioResult> {
+    self.results.iter().max_by(|a, b| a.max_drawdown.total_cmp(&b.max_drawdown))
+  }
+
+  pub fn table(&self) -> Table {
+    let mut table = Table::new();
+    table.set_titles(row![
+      "Scenario",
+      "Trades",
+      "PnL",
+      "Max Drawdown",
+      "Liquidations",
+      "Peak Margin Used"
+    ]);
+    for result in &self.results {
+      table.add_row(row![
+        result.scenario,
+        result.trades,
+        format!("{:.2}", result.pnl),
+        format!("{:.2}%", result.max_drawdown * 100.0),
+        result.liquidations,
+        format!("{:.0}%", result.peak_margin_used * 100.0)
+      ]);
+    }
+    table
+  }
+}
+
+impl fmt::Display for StressReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let pair = self.configuration.pair;
+    let leverage = self.configuration.leverage();
+    writeln!(f, "Stress test of {} on {} candles at {}x", pair, self.candles, leverage)?;
+    write!(f, "{}", self.table())?;
+    if let Some(worst) = self.worst_case() {
+      let drawdown = worst.max_drawdown * 100.0;
+      writeln!(f, "Worst case: {}, max drawdown {:.2}%", worst.scenario, drawdown)?;
+    }
+    Ok(())
+  }
+}
+
+/// Backtests the configuration on each scenario applied to the last stored candles of
+/// its pair, each stored as a backtest session labelled with the scenario.
+pub async fn stress_test(
+  configuration: CoreConfiguration,
+  scenarios: &[Scenario],
+  database: Arc<Mutex<Database>>,
+) -> Result<StressReport, TraderError> {
+  let pair = configuration.pair;
+  let n_candles = configuration.backtest_last_n_candles;
+  let candles =
+    database.lock().await.fetch_candles_before(pair, Utc::now(), n_candles).await?;
+  if candles.is_empty() {
+    return Err(TraderError::NoStressCandles(pair));
+  }
+  let mut results = Vec::new();
+  for scenario in scenarios {
+    let label = SessionLabel {
+      label: format!("Stress {} {}", scenario, pair),
+      notes: format!("Stress test on the last {} candles", candles.len()),
+    };
+    let labelled = CoreConfiguration { label: label.clone(), ..configuration.clone() };
+    let shocked = scenario.apply(&candles);
+    let started_at = Utc::now();
+    let report = backtest_candles(labelled, database.clone(), shocked.clone()).await?;
+    let mut database = database.lock().await;
+    database.add_session(report.core_id, started_at, true, &label).await?;
+    let positions = database.get_exited_positions(report.core_id)?;
+    let drawdowns = report.statistics.drawdown.curve.values();
+    results.push(ScenarioResult {
+      scenario: *scenario,
+      core_id: report.core_id,
+      trades: positions.len(),
+      pnl: positions.iter().map(|position| position.realised_profit_loss).sum(),
+      max_drawdown: -drawdowns.into_iter().fold(0.0, f64::min),
+      liquidations: positions.iter().filter(|position| position.is_liquidated()).count(),
+      peak_margin_used: positions
+        .iter()
+        .map(|position| margin_used(position, &shocked))
+        .fold(0.0, f64::max),
+    });
+  }
+  Ok(StressReport { configuration, candles: candles.len(), results })
+}
+
+/// Loss of the position at the worst price of the candles while it was open, as a
+/// share of its margin.
+fn margin_used(position: &Position, candles: &[Candle]) -> f64 {
+  let (enter, exit) = (position.meta.enter_time, position.meta.update_time);
+  let held = candles
+    .iter()
+    .filter(|candle| candle.close_time >= enter && candle.open_time <= exit);
+  let loss_per_unit = held
+    .map(|candle| match position.side {
+      Side::Buy => position.enter_avg_price_gross - candle.low,
+      Side::Sell => candle.high - position.enter_avg_price_gross,
+    })
+    .fold(0.0, f64::max);
+  let margin = position.margin();
+  if margin > 0.0 {
+    loss_per_unit * position.quantity.abs() / margin
+  } else {
+    0.0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::exchange::mock::candle;
+  use chrono::{Duration, TimeZone};
+
+  #[test]
+  fn applies_the_shock_of_each_scenario() {
+    let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+    // Trending up 1 per candle
+    let candles: Vec<Candle> = (0..200)
+      .map(|i| candle(start + Duration::try_minutes(i).unwrap(), 100.0 + i as f64))
+      .collect();
+
+    let gap = Scenario::GapDown.apply(&candles);
+    assert_eq!(gap[99].close, candles[99].close);
+    assert!((gap[100].open - candles[100].open * 0.8).abs() < 1e-9);
+    assert_eq!(gap[199].open_time, candles[199].open_time);
+
+    let crash = Scenario::FlashCrash.apply(&candles);
+    assert!((crash[104].close - candles[104].close * 0.7).abs() < 1e-9);
+    assert!(crash[102].close < crash[101].close);
+    assert_eq!(crash[164].close, candles[164].close);
+
+    let chop = Scenario::Chop.apply(&candles);
+    let (low, high) = chop
+      .iter()
+      .fold((f64::MAX, f64::MIN), |(low, high), c| (low.min(c.close), high.max(c.close)));
+    assert!(low >= 100.0 * 0.98 - 1e-9 && high <= 100.0 * 1.02 + 1e-9);
+    assert!((chop[120].close - 100.0).abs() < 1e-6);
+  }
+}
@@ -42,6 +42,14 @@ pub enum Event {
   Resize(u16, u16),
 }
 
+/// Rates of the event loop and whether frames are drawn only when something changed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct RenderSettings {
+  pub tick_rate: f64,
+  pub frame_rate: f64,
+  pub adaptive: bool,
+}
+
 pub struct Tui {
   pub terminal: ratatui::Terminal<Backend<IO>>,
   pub task: JoinHandle<()>,
@@ -87,6 +95,13 @@ impl Tui {
     self
   }
 
+  /// Restarts the event loop at the rates.
+  pub fn set_rates(&mut self, tick_rate: f64, frame_rate: f64) {
+    self.tick_rate = tick_rate;
+    self.frame_rate = frame_rate;
+    self.start();
+  }
+
   pub fn mouse(mut self, mouse: bool) -> Self {
     self.mouse = mouse;
     self
@@ -1,14 +1,22 @@
-use directories::ProjectDirs;
+use crate::paths;
 use eyre::Result;
 use lazy_static::lazy_static;
-use std::path::PathBuf;
+use log_file::{LogConfig, RollingFile};
+use std::{
+  path::PathBuf,
+  sync::{Mutex, OnceLock},
+};
 use tracing::error;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{
-  self, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer,
+  self, filter::EnvFilter, prelude::__tracing_subscriber_SubscriberExt, reload,
+  util::SubscriberInitExt, Layer, Registry,
 };
+pub mod clipboard;
+pub mod crash;
 pub mod formatting;
 pub mod load_config;
+pub mod log_file;
 pub mod serde_utils;
 
 pub static GIT_COMMIT_HASH: &'static str = env!("_GIT_INFO");
@@ -24,9 +32,8 @@ lazy_static! {
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
-fn project_directory() -> Option<ProjectDirs> {
-  ProjectDirs::from("com", "belakm", env!("CARGO_PKG_NAME"))
-}
+/// Filter of the log file, replaced by [set_log_filter].
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
 pub fn initialize_panic_handler() -> Result<()> {
   // let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default()
@@ -45,6 +52,9 @@ pub fn initialize_panic_handler() -> Result<()> {
         error!("Unable to exit Terminal: {:?}", r);
       }
     }
+    if let Some(path) = crash::recover(panic_info) {
+      eprintln!("Crash report written to {}", path.display());
+    }
 
     #[cfg(not(debug_assertions))]
     {
@@ -81,47 +91,35 @@ pub fn initialize_panic_handler() -> Result<()> {
   Ok(())
 }
 
-pub fn get_data_dir() -> PathBuf {
-  let directory = if let Some(s) = DATA_FOLDER.clone() {
-    s
-  } else if let Some(proj_dirs) = project_directory() {
-    proj_dirs.data_local_dir().to_path_buf()
-  } else {
-    PathBuf::from(".").join(".data")
-  };
-  directory
-}
-
-pub fn get_config_dir() -> PathBuf {
-  let directory = if let Some(s) = CONFIG_FOLDER.clone() {
-    s
-  } else if let Some(proj_dirs) = project_directory() {
-    proj_dirs.config_local_dir().to_path_buf()
-  } else {
-    PathBuf::from(".").join(".config")
-  };
-  directory
-}
-
-pub fn initialize_logging() -> Result<()> {
-  let directory = get_data_dir();
+pub fn initialize_logging(config: LogConfig) -> Result<()> {
+  let directory = paths::logs_dir();
   std::fs::create_dir_all(directory.clone())?;
-  let log_path = directory.join(LOG_FILE.clone());
-  let log_file = std::fs::File::create(log_path)?;
+  let log_file = RollingFile::open(directory.join(LOG_FILE.clone()), config)?;
   std::env::set_var(
     "RUST_LOG",
     std::env::var("RUST_LOG")
       .or_else(|_| std::env::var(LOG_ENV.clone()))
       .unwrap_or_else(|_| format!("{}=info", env!("CARGO_CRATE_NAME"))),
   );
+  let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
   let file_subscriber = tracing_subscriber::fmt::layer()
     .with_file(true)
     .with_line_number(true)
-    .with_writer(log_file)
+    .with_writer(Mutex::new(log_file))
     .with_target(false)
     .with_ansi(false)
-    .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
+    .with_filter(filter);
   tracing_subscriber::registry().with(file_subscriber).with(ErrorLayer::default()).init();
+  let _ = LOG_FILTER.set(handle);
+  Ok(())
+}
+
+/// Replaces the filter of the log file with the directives, e.g.
+/// `meshetar_tui[trader{pair=BTCUSDT}]=debug`.
+pub fn set_log_filter(directives: &str) -> Result<()> {
+  let filter = EnvFilter::try_new(directives)?;
+  let handle = LOG_FILTER.get().ok_or_else(|| eyre::eyre!("Logging is not initialized"))?;
+  handle.reload(filter)?;
   Ok(())
 }
 
@@ -157,8 +155,9 @@ pub fn version() -> String {
   let commit_hash = GIT_COMMIT_HASH;
   //
   // let current_exe_path = PathBuf::from(clap::crate_name!()).display().to_string();
-  let config_dir_path = get_config_dir().display().to_string();
-  let data_dir_path = get_data_dir().display().to_string();
+  let config_dir_path = paths::config_dir().display().to_string();
+  let data_dir_path = paths::data_dir().display().to_string();
+  let logs_dir_path = paths::logs_dir().display().to_string();
 
   format!(
     "\
@@ -167,7 +166,8 @@ pub fn version() -> String {
 Authors: {author}
 
 Config directory: {config_dir_path}
-Data directory: {data_dir_path}"
+Data directory: {data_dir_path}
+Log directory: {logs_dir_path}"
   )
 }
 
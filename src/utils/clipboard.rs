@@ -0,0 +1,16 @@
+/// System clipboard, opened on the first copy. X11 and Wayland only keep copied text
+/// while the clipboard that set it is open, so it lives as long as the app.
+#[derive(Default)]
+pub struct Clipboard {
+  inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+  pub fn copy(&mut self, text: &str) -> Result<(), arboard::Error> {
+    let clipboard = match &mut self.inner {
+      Some(clipboard) => clipboard,
+      None => self.inner.insert(arboard::Clipboard::new()?),
+    };
+    clipboard.set_text(text)
+  }
+}
@@ -0,0 +1,147 @@
+use crate::{database::Database, events::Event};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::{
+  backtrace::Backtrace,
+  collections::VecDeque,
+  fmt::Write as _,
+  panic::PanicHookInfo,
+  path::PathBuf,
+  sync::{Arc, Mutex as StdMutex},
+  time::Duration,
+};
+use tokio::sync::Mutex;
+
+/// How long the panic hook waits for open positions to be written.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the panic handler does before the process exits, from `panic_recovery` in the
+/// config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PanicRecovery {
+  /// Write open positions to the `crash_open_positions` table.
+  pub save_state: bool,
+  /// Write a crash report with the backtrace, statistics and recent events.
+  pub crash_report: bool,
+  /// How many of the most recent events the crash report includes.
+  pub last_events: usize,
+}
+
+impl Default for PanicRecovery {
+  fn default() -> Self {
+    Self { save_state: true, crash_report: true, last_events: 200 }
+  }
+}
+
+#[derive(Default)]
+struct CrashContext {
+  recovery: PanicRecovery,
+  database: Option<Arc<Mutex<Database>>>,
+  events: VecDeque<String>,
+}
+
+lazy_static! {
+  static ref CRASH_CONTEXT: StdMutex<CrashContext> = StdMutex::new(CrashContext {
+    recovery: PanicRecovery::default(),
+    ..CrashContext::default()
+  });
+}
+
+/// Sets what the panic handler recovers and the database it saves state from.
+pub fn configure(recovery: PanicRecovery, database: Arc<Mutex<Database>>) {
+  if let Ok(mut context) = CRASH_CONTEXT.lock() {
+    context.recovery = recovery;
+    context.database = Some(database);
+  }
+}
+
+/// Remembers the event for the crash report, keeping only the configured number of the
+/// most recent ones.
+pub fn record_event(event: &Event) {
+  if let Ok(mut context) = CRASH_CONTEXT.lock() {
+    let capacity = context.recovery.last_events;
+    if capacity == 0 {
+      return;
+    }
+    while context.events.len() >= capacity {
+      context.events.pop_front();
+    }
+    context.events.push_back(format!("{} {:?}", Utc::now().to_rfc3339(), event));
+  }
+}
+
+/// Saves what can be saved after a panic and returns the path of the crash report, if
+/// one was written. Must not panic itself, so every failure is noted in the report.
+pub fn recover(panic_info: &PanicHookInfo<'_>) -> Option<PathBuf> {
+  // The panicking thread may hold the lock, in which case nothing more is known
+  let context = CRASH_CONTEXT.try_lock().ok()?;
+  let mut report = String::new();
+  let _ = writeln!(
+    report,
+    "{} {} ({}) crashed at {}",
+    env!("CARGO_PKG_NAME"),
+    env!("CARGO_PKG_VERSION"),
+    super::GIT_COMMIT_HASH,
+    Utc::now()
+  );
+  let _ = writeln!(report, "\n{}", panic_info);
+  let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+  match context.database.as_ref().map(|database| database.try_lock()) {
+    None => {
+      let _ = writeln!(report, "\nNo database was open.");
+    },
+    Some(Err(_)) => {
+      let _ = writeln!(report, "\nDatabase was locked, state could not be saved.");
+    },
+    Some(Ok(database)) => {
+      if context.recovery.save_state {
+        let _ = writeln!(report, "\n{}", flush_open_positions(&database));
+      }
+      let statistics = serde_json::to_string_pretty(database.get_all_statistics())
+        .unwrap_or_else(|e| format!("Failed to serialize statistics: {}", e));
+      let _ = writeln!(report, "\nStatistics:\n{}", statistics);
+    },
+  }
+
+  let _ = writeln!(report, "\nLast {} events:", context.events.len());
+  for event in context.events.iter() {
+    let _ = writeln!(report, "{}", event);
+  }
+
+  if !context.recovery.crash_report {
+    return None;
+  }
+  let path = crate::paths::data_dir()
+    .join(format!("crash-{}.log", Utc::now().format("%Y%m%d-%H%M%S")));
+  std::fs::write(&path, report).ok()?;
+  Some(path)
+}
+
+/// Writes open positions from a fresh runtime on its own thread, as the panic may have
+/// happened on a runtime thread that can no longer be blocked on.
+fn flush_open_positions(database: &Database) -> String {
+  std::thread::scope(|scope| {
+    scope
+      .spawn(|| {
+        let runtime =
+          match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => return format!("Failed to start runtime to save positions: {}", e),
+          };
+        match runtime
+          .block_on(tokio::time::timeout(FLUSH_TIMEOUT, database.flush_open_positions()))
+        {
+          Ok(Ok(count)) => {
+            format!("Saved {} open positions to crash_open_positions.", count)
+          },
+          Ok(Err(e)) => format!("Failed to save open positions: {}", e),
+          Err(_) => "Timed out saving open positions.".to_string(),
+        }
+      })
+      .join()
+      .unwrap_or_else(|_| "Saving open positions panicked.".to_string())
+  })
+}
@@ -1,5 +1,8 @@
+use crate::assets::Asset;
 use chrono::{DateTime, Duration, Local, LocalResult, NaiveDateTime, TimeZone, Utc};
 use petname::Petnames;
+use serde::Deserialize;
+use std::sync::OnceLock;
 
 const DATETIME_FORMAT_SHAPE: &str = "%e. %b %H:%M";
 const DATETIME_FORMAT_SHAPE_SHORT: &str = "%H:%M:%S";
@@ -62,3 +65,121 @@ pub fn time_ago(input_time: DateTime<Utc>) -> String {
     if duration.num_seconds() < 3 { "".to_string() } else { " ago".to_string() };
   duration_to_readable(&duration) + &appendix
 }
+
+/// Separators numbers are shown with, from `number_locale` in the config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberLocale {
+  /// 12,345.67
+  #[default]
+  En,
+  /// 12.345,67
+  De,
+  /// 12 345,67
+  Fr,
+  /// 12'345.67
+  Ch,
+}
+
+impl NumberLocale {
+  fn separators(&self) -> (&'static str, char) {
+    match self {
+      NumberLocale::En => (",", '.'),
+      NumberLocale::De => (".", ','),
+      NumberLocale::Fr => ("\u{202f}", ','),
+      NumberLocale::Ch => ("'", '.'),
+    }
+  }
+}
+
+static NUMBER_LOCALE: OnceLock<NumberLocale> = OnceLock::new();
+
+/// Sets the separators of all formatted numbers, has no effect once set.
+pub fn set_number_locale(locale: NumberLocale) {
+  let _ = NUMBER_LOCALE.set(locale);
+}
+
+/// Significant digits, least and most decimals amounts of the asset are shown with.
+fn digit_rules(asset: Asset) -> (i32, i32, i32) {
+  match asset {
+    Asset::USDT => (4, 2, 6),
+    Asset::BTC | Asset::ETH | Asset::SYNTH => (6, 0, 8),
+  }
+}
+
+/// Amount of the asset, e.g. "10,543.24" USDT and "0.00012346" BTC.
+pub fn format_amount(value: f64, asset: Asset) -> String {
+  let (significant, min_decimals, max_decimals) = digit_rules(asset);
+  format_significant(value, significant, min_decimals, max_decimals)
+}
+
+/// Number without a known asset, with 6 significant digits.
+pub fn format_number(value: f64) -> String {
+  format_significant(value, 6, 0, 8)
+}
+
+/// `format_number` with the sign of positive numbers too, e.g. "+12.5".
+pub fn format_signed_number(value: f64) -> String {
+  let number = format_number(value);
+  if value > 0.0 && number.bytes().any(|digit| (b'1'..=b'9').contains(&digit)) {
+    format!("+{number}")
+  } else {
+    number
+  }
+}
+
+/// Large numbers with a unit, e.g. "1.23M" and "845K".
+pub fn format_compact(value: f64) -> String {
+  const UNITS: [(f64, &str); 4] = [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")];
+  let rounded = round_significant(value, 3);
+  match UNITS.iter().find(|(size, _)| rounded.abs() >= *size) {
+    Some((size, unit)) => {
+      format!("{}{unit}", format_significant(rounded / size, 3, 0, 2))
+    },
+    None => format_number(value),
+  }
+}
+
+fn round_significant(value: f64, significant: i32) -> f64 {
+  if value == 0.0 || !value.is_finite() {
+    return value;
+  }
+  let scale = 10f64.powi(significant - 1 - value.abs().log10().floor() as i32);
+  (value * scale).round() / scale
+}
+
+/// Rounds to the significant digits within the decimals, then drops trailing zeros past
+/// the least decimals and groups the thousands.
+fn format_significant(
+  value: f64,
+  significant: i32,
+  min_decimals: i32,
+  max_decimals: i32,
+) -> String {
+  if !value.is_finite() {
+    return value.to_string();
+  }
+  let magnitude = if value == 0.0 { 0 } else { value.abs().log10().floor() as i32 };
+  let decimals = (significant - 1 - magnitude).clamp(min_decimals, max_decimals) as usize;
+  let fixed = format!("{:.*}", decimals, value.abs());
+  let (integer, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+  let fraction = fraction.trim_end_matches('0');
+  let fraction = format!("{fraction:0<width$}", width = min_decimals as usize);
+  let (thousands, decimal) =
+    NUMBER_LOCALE.get().copied().unwrap_or_default().separators();
+  let mut text = String::new();
+  if value < 0.0 && fixed.bytes().any(|digit| (b'1'..=b'9').contains(&digit)) {
+    text.push('-');
+  }
+  for (index, digit) in integer.chars().enumerate() {
+    if index > 0 && (integer.len() - index) % 3 == 0 {
+      text.push_str(thousands);
+    }
+    text.push(digit);
+  }
+  if !fraction.is_empty() {
+    text.push(decimal);
+    text.push_str(&fraction);
+  }
+  text
+}
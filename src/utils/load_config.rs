@@ -1,30 +1,137 @@
+use crate::{assets::Asset, exchange::account::AccountName, paths};
+use serde::Deserialize;
+use std::{path::PathBuf, sync::OnceLock};
 use thiserror::Error;
 
+static PROFILE: OnceLock<String> = OnceLock::new();
+
 #[derive(serde::Deserialize, Debug)]
 pub struct UserConfig {
   binance_api_key: String,
   binance_api_secret: String,
+  /// Of the `main` account, names it in the history of transfers.
+  email: Option<String>,
   use_testnet: bool,
+  #[serde(default = "default_recv_window")]
+  recv_window: u64,
+  /// Overrides the REST url implied by `use_testnet`.
+  rest_url: Option<String>,
+  /// Overrides the websocket url implied by `use_testnet`.
+  stream_url: Option<String>,
+  /// Overrides the USD-M futures REST url implied by `use_testnet`.
+  futures_rest_url: Option<String>,
+  #[serde(default = "default_database")]
+  database: PathBuf,
+  #[serde(default)]
+  statistics: StatisticDefaults,
+  /// Further accounts or sub-accounts a session can trade on, besides `main`.
+  #[serde(default)]
+  accounts: Vec<AccountConfig>,
+  /// Runs live sessions on API keys that can withdraw, which are refused otherwise.
+  #[serde(default)]
+  allow_withdrawal_keys: bool,
+}
+
+/// Keys of a Binance account, `[[accounts]]` in the exchange config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountConfig {
+  pub name: AccountName,
+  pub binance_api_key: String,
+  pub binance_api_secret: String,
+  /// Of a sub-account, needed to transfer to and from it.
+  pub email: Option<String>,
+}
+
+/// Statistic config of new sessions, apart from their starting equity.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StatisticDefaults {
+  pub trading_days_per_year: usize,
+  pub risk_free_return: f64,
+  pub reporting_currency: Asset,
+}
+
+impl Default for StatisticDefaults {
+  fn default() -> Self {
+    Self {
+      trading_days_per_year: 365,
+      risk_free_return: 0.0,
+      reporting_currency: Asset::USDT,
+    }
+  }
+}
+
+/// Binance's own default.
+fn default_recv_window() -> u64 {
+  5000
+}
+
+fn default_database() -> PathBuf {
+  PathBuf::from("database.sqlite")
 }
 
 impl UserConfig {
   pub fn to_config(&self) -> ExchangeConfig {
-    ExchangeConfig {
+    let main = AccountConfig {
+      name: AccountName::default(),
       binance_api_key: self.binance_api_key.clone(),
       binance_api_secret: self.binance_api_secret.clone(),
+      email: self.email.clone(),
+    };
+    ExchangeConfig {
+      accounts: [main].into_iter().chain(self.accounts.iter().cloned()).collect(),
       use_testnet: self.use_testnet,
+      recv_window: self.recv_window,
+      rest_url: self
+        .rest_url
+        .clone()
+        .unwrap_or_else(|| ExchangeConfig::get_exchange_url(self.use_testnet)),
+      stream_url: self
+        .stream_url
+        .clone()
+        .unwrap_or_else(|| ExchangeConfig::get_exchange_stream_url(self.use_testnet)),
+      futures_rest_url: self
+        .futures_rest_url
+        .clone()
+        .unwrap_or_else(|| ExchangeConfig::get_futures_url(self.use_testnet)),
+      database: self.database.clone(),
+      statistics: self.statistics,
+      allow_withdrawal_keys: self.allow_withdrawal_keys,
     }
   }
 }
 
 #[derive(serde::Deserialize, Debug)]
 pub struct ExchangeConfig {
-  pub binance_api_key: String,
-  pub binance_api_secret: String,
+  /// `main` first, then the `[[accounts]]` in the order they are configured.
+  pub accounts: Vec<AccountConfig>,
   pub use_testnet: bool,
+  /// Milliseconds a signed request stays valid for after its timestamp.
+  pub recv_window: u64,
+  pub rest_url: String,
+  pub stream_url: String,
+  /// Of USD-M futures, for their funding rates and open interest.
+  pub futures_rest_url: String,
+  /// SQLite file of the profile.
+  pub database: PathBuf,
+  pub statistics: StatisticDefaults,
+  /// Live sessions may trade on API keys with withdrawals enabled.
+  pub allow_withdrawal_keys: bool,
 }
 
 impl ExchangeConfig {
+  pub fn account(&self, name: &AccountName) -> Result<&AccountConfig, ConfigError> {
+    self
+      .accounts
+      .iter()
+      .find(|account| account.name == *name)
+      .ok_or_else(|| ConfigError::UnknownAccount(name.clone()))
+  }
+
+  pub fn account_names(&self) -> Vec<AccountName> {
+    self.accounts.iter().map(|account| account.name.clone()).collect()
+  }
+
   pub fn get_exchange_stream_url(use_testnet: bool) -> String {
     let binance_stream_url = if use_testnet {
       "wss://testnet.binance.vision/ws".to_string()
@@ -42,22 +149,75 @@ impl ExchangeConfig {
     };
     binance_url
   }
+
+  pub fn get_futures_url(use_testnet: bool) -> String {
+    if use_testnet {
+      "https://testnet.binancefuture.com".to_string()
+    } else {
+      "https://fapi.binance.com".to_string()
+    }
+  }
 }
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
-  #[error(
-    "Problem opening config file, make sure configuration exists at `.config/env.toml`."
-  )]
-  ReadError,
+  #[error("Problem opening config file, make sure configuration exists at `{0}`.")]
+  ReadError(PathBuf),
   #[error("Problem setting configuration")]
   SetError,
+  #[error("No account `{0}` in the exchange config.")]
+  UnknownAccount(AccountName),
+  #[error("Account `{0}` has no email, transfers need it for sub-accounts.")]
+  NoEmail(AccountName),
 }
+/// Selects the profile that [read_config] reads, `env.<profile>.toml` of the config
+/// directory instead of `env.toml`. Only the first call has an effect.
+pub fn set_profile(profile: String) {
+  let _ = PROFILE.set(profile);
+}
+
+pub fn profile() -> Option<&'static str> {
+  PROFILE.get().map(String::as_str)
+}
+
+pub fn config_path() -> PathBuf {
+  paths::exchange_config_file(profile())
+}
+
 pub fn read_config() -> Result<ExchangeConfig, ConfigError> {
+  let path = config_path();
   let config_file =
-    std::fs::read_to_string(".config/env.toml").map_err(|_| ConfigError::ReadError)?;
+    std::fs::read_to_string(&path).map_err(|_| ConfigError::ReadError(path.clone()))?;
   let user_config: UserConfig =
     toml::from_str(&config_file).map_err(|_| ConfigError::SetError)?;
   let config = user_config.to_config();
   Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn main_account_comes_first() {
+    let user_config: UserConfig = toml::from_str(
+      r#"
+      binance_api_key = "key"
+      binance_api_secret = "secret"
+      use_testnet = true
+
+      [[accounts]]
+      name = "grid"
+      binance_api_key = "grid key"
+      binance_api_secret = "grid secret"
+      "#,
+    )
+    .unwrap();
+    let config = user_config.to_config();
+    let grid = AccountName("grid".to_string());
+    assert_eq!(config.account_names(), vec![AccountName::default(), grid.clone()]);
+    assert_eq!(config.account(&grid).unwrap().binance_api_key, "grid key");
+    let unknown = AccountName("spot".to_string());
+    assert!(matches!(config.account(&unknown), Err(ConfigError::UnknownAccount(_))));
+  }
+}
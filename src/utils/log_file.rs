@@ -0,0 +1,123 @@
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize;
+use std::{
+  fs::{self, File, OpenOptions},
+  io::{self, Write},
+  path::PathBuf,
+};
+
+/// When the log file is rotated.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+  /// On the first write of a new day.
+  #[default]
+  Daily,
+  /// Once the file reaches `max_size_mb`.
+  Size,
+}
+
+/// Rotation of the log file, `logging` in the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LogConfig {
+  #[serde(default)]
+  pub rotation: LogRotation,
+  #[serde(default = "default_max_size_mb")]
+  pub max_size_mb: u64,
+  /// Rotated files kept next to the current one, the oldest are removed.
+  #[serde(default = "default_max_files")]
+  pub max_files: usize,
+}
+
+fn default_max_size_mb() -> u64 {
+  10
+}
+
+fn default_max_files() -> usize {
+  7
+}
+
+impl Default for LogConfig {
+  fn default() -> Self {
+    Self {
+      rotation: LogRotation::default(),
+      max_size_mb: default_max_size_mb(),
+      max_files: default_max_files(),
+    }
+  }
+}
+
+/// Log file that is appended to across runs and rotated to `<file>.1`, `<file>.2`, ...
+/// with `.1` the most recent.
+pub struct RollingFile {
+  path: PathBuf,
+  config: LogConfig,
+  file: File,
+  size: u64,
+  opened_on: NaiveDate,
+}
+
+impl RollingFile {
+  pub fn open(path: PathBuf, config: LogConfig) -> io::Result<Self> {
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let metadata = file.metadata()?;
+    let opened_on = metadata
+      .modified()
+      .map(|time| DateTime::<Local>::from(time).date_naive())
+      .unwrap_or_else(|_| Local::now().date_naive());
+    Ok(Self { path, config, file, size: metadata.len(), opened_on })
+  }
+
+  fn should_rotate(&self, next: usize) -> bool {
+    if self.size == 0 {
+      return false;
+    }
+    match self.config.rotation {
+      LogRotation::Daily => Local::now().date_naive() != self.opened_on,
+      LogRotation::Size => {
+        self.size + next as u64 > self.config.max_size_mb.saturating_mul(1024 * 1024)
+      },
+    }
+  }
+
+  fn rotated(&self, n: usize) -> PathBuf {
+    PathBuf::from(format!("{}.{}", self.path.display(), n))
+  }
+
+  fn rotate(&mut self) -> io::Result<()> {
+    let max_files = self.config.max_files;
+    let mut n = max_files.max(1);
+    while self.rotated(n).exists() {
+      fs::remove_file(self.rotated(n))?;
+      n += 1;
+    }
+    for n in (1..max_files).rev() {
+      if self.rotated(n).exists() {
+        fs::rename(self.rotated(n), self.rotated(n + 1))?;
+      }
+    }
+    if max_files > 0 {
+      fs::rename(&self.path, self.rotated(1))?;
+    }
+    self.file =
+      OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+    self.size = 0;
+    self.opened_on = Local::now().date_naive();
+    Ok(())
+  }
+}
+
+impl Write for RollingFile {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    if self.should_rotate(buf.len()) {
+      self.rotate()?;
+    }
+    let written = self.file.write(buf)?;
+    self.size += written as u64;
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.file.flush()
+  }
+}
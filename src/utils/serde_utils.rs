@@ -13,6 +13,10 @@ pub fn f64_default() -> f64 {
     0.0
 }
 
+pub fn f64_one() -> f64 {
+    1.0
+}
+
 /// Serialize a [`Duration`] into a `u64` representing the associated seconds.
 pub fn se_duration_as_secs<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
 where
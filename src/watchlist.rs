@@ -0,0 +1,70 @@
+use crate::assets::{MarketEvent, MarketEventDetail, Pair};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Cross {
+  Above,
+  Below,
+}
+
+/// Price level of a pair to be alerted about, from the `watchlist` list of the config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PriceAlert {
+  pub pair: Pair,
+  pub cross: Cross,
+  pub price: f64,
+  /// Also POST the alert to this url.
+  #[serde(default)]
+  pub webhook: Option<String>,
+}
+
+impl fmt::Display for PriceAlert {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let direction = match self.cross {
+      Cross::Above => "above",
+      Cross::Below => "below",
+    };
+    write!(f, "{} crossed {} {}", self.pair, direction, self.price)
+  }
+}
+
+/// Checks price alerts against the live ticker, independent of any running Core. An alert
+/// fires when the price moves through its level, so it fires again only after the price
+/// has gone back to the other side.
+#[derive(Default)]
+pub struct Watchlist {
+  alerts: Vec<PriceAlert>,
+  last_prices: HashMap<Pair, f64>,
+}
+
+impl Watchlist {
+  pub fn new(alerts: Vec<PriceAlert>) -> Self {
+    Self { alerts, last_prices: HashMap::new() }
+  }
+
+  /// Returns the alerts the market event's price crossed.
+  pub fn update(&mut self, market_event: &MarketEvent) -> Vec<&PriceAlert> {
+    let price = match &market_event.detail {
+      MarketEventDetail::Candle(candle) | MarketEventDetail::CandleUpdate(candle) => {
+        candle.close
+      },
+      MarketEventDetail::Trade(trade) => trade.price,
+      MarketEventDetail::OrderBookL1(book) => book.mid_price(),
+      MarketEventDetail::BacktestCandle(_) => return Vec::new(),
+    };
+    let Some(previous) = self.last_prices.insert(market_event.pair, price) else {
+      return Vec::new();
+    };
+    self
+      .alerts
+      .iter()
+      .filter(|alert| alert.pair == market_event.pair)
+      .filter(|alert| match alert.cross {
+        Cross::Above => previous < alert.price && price >= alert.price,
+        Cross::Below => previous > alert.price && price <= alert.price,
+      })
+      .collect()
+  }
+}